@@ -0,0 +1,235 @@
+//! 対局の裁定（評価値による勝ち・引き分けの判定）
+//!
+//! 自己対局や GUI の対局モードで、決着のついた局面や動かない局面を最後まで
+//! 指させずに打ち切るための判定。両者が直近の手で報告した評価値を勝率に換算し、
+//! 次のどちらかを満たしたら裁定する。
+//!
+//! - 勝ち: 両者の評価値が同じ側の勝ちを示し（勝率 `win_rate` 以上）、
+//!   それが各自 `win_moves` 手続いた
+//! - 引き分け: `draw_min_ply` 手以降、両者の勝率が 50% ± `draw_band` に収まる手が
+//!   各自 `draw_moves` 手続いた
+//!
+//! 勝率は `1 / (1 + exp(-cp / eval_scale))` で換算し、詰みのスコアは勝率 0 / 1 とする。
+//! 評価値を報告しなかった手（定跡手など）があると、その時点までの連続数は数え直しになる。
+//!
+//! ```
+//! use rshogi_core::adjudication::{Adjudication, AdjudicationConfig, Adjudicator, ReportedScore};
+//! use rshogi_core::types::Color;
+//!
+//! let mut adjudicator = Adjudicator::new(AdjudicationConfig::default());
+//! for ply in 0..8 {
+//!     let side = if ply % 2 == 0 { Color::Black } else { Color::White };
+//!     // 先手は大きく勝ち、後手は大きく負けと見ている
+//!     let cp = if side == Color::Black { 3000 } else { -3000 };
+//!     adjudicator.record(side, Some(ReportedScore::Cp(cp)));
+//! }
+//! assert_eq!(adjudicator.decide(8), Some(Adjudication::Win(Color::Black)));
+//! ```
+
+use crate::types::Color;
+
+/// エンジンが報告した評価値（指した側から見た値）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportedScore {
+    /// センチポーン
+    Cp(i32),
+    /// 詰みまでの手数（正なら勝ち、負なら負け。USI の `score mate` と同じ）
+    Mate(i32),
+}
+
+impl ReportedScore {
+    /// 報告した側から見た勝率（0.0〜1.0）
+    pub fn win_rate(self, eval_scale: f64) -> f64 {
+        match self {
+            ReportedScore::Cp(cp) => 1.0 / (1.0 + (-(cp as f64) / eval_scale).exp()),
+            ReportedScore::Mate(n) if n >= 0 => 1.0,
+            ReportedScore::Mate(_) => 0.0,
+        }
+    }
+}
+
+/// 裁定の条件
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AdjudicationConfig {
+    /// 評価値を勝率に換算するときのスケール（cp）
+    pub eval_scale: f64,
+    /// 勝ちと裁定する勝率（0.5〜1.0）。負け側はこの値を 1 から引いた勝率以下
+    pub win_rate: f64,
+    /// 勝ちの裁定に必要な連続手数（各自の手数）。0 で勝ちを裁定しない
+    pub win_moves: u32,
+    /// 引き分けとみなす勝率の 50% からの幅
+    pub draw_band: f64,
+    /// 引き分けの裁定に必要な連続手数（各自の手数）。0 で引き分けを裁定しない
+    pub draw_moves: u32,
+    /// 引き分けを裁定し始める手数
+    pub draw_min_ply: u32,
+}
+
+impl Default for AdjudicationConfig {
+    fn default() -> Self {
+        // 勝率 99% は eval_scale 600 で約 2750cp、±2% は約 ±50cp
+        Self {
+            eval_scale: 600.0,
+            win_rate: 0.99,
+            win_moves: 4,
+            draw_band: 0.02,
+            draw_moves: 8,
+            draw_min_ply: 160,
+        }
+    }
+}
+
+/// 裁定結果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Adjudication {
+    /// 指定した側の勝ち
+    Win(Color),
+    /// 引き分け
+    Draw,
+}
+
+/// 対局中の評価値を記録して裁定する
+///
+/// 1 手指すごとに [`Adjudicator::record`] で指した側の評価値を渡し、
+/// [`Adjudicator::decide`] で裁定を問い合わせる。
+#[derive(Debug, Clone)]
+pub struct Adjudicator {
+    config: AdjudicationConfig,
+    /// 手番ごとの直近の評価値（連続して条件を満たしているかを見るのに必要な分だけ）
+    recent: [Vec<Option<ReportedScore>>; Color::NUM],
+}
+
+impl Adjudicator {
+    pub fn new(config: AdjudicationConfig) -> Self {
+        Self {
+            config,
+            recent: [Vec::new(), Vec::new()],
+        }
+    }
+
+    /// 裁定の条件
+    pub fn config(&self) -> &AdjudicationConfig {
+        &self.config
+    }
+
+    /// `side` が指した手の評価値を記録する（評価値がなければ `None`）
+    pub fn record(&mut self, side: Color, score: Option<ReportedScore>) {
+        let window = self.config.win_moves.max(self.config.draw_moves).max(1) as usize;
+        let recent = &mut self.recent[side.index()];
+        recent.push(score);
+        if recent.len() > window {
+            recent.remove(0);
+        }
+    }
+
+    /// 記録した評価値を破棄する（新しい対局の前に呼ぶ）
+    pub fn clear(&mut self) {
+        for recent in &mut self.recent {
+            recent.clear();
+        }
+    }
+
+    /// これまでの評価値から裁定する。`ply` は現在までに指された手数
+    pub fn decide(&self, ply: u32) -> Option<Adjudication> {
+        let config = &self.config;
+        if config.win_moves > 0 {
+            for winner in [Color::Black, Color::White] {
+                let winning = self.streak(winner, config.win_moves, |rate| rate >= config.win_rate);
+                let losing =
+                    self.streak(!winner, config.win_moves, |rate| rate <= 1.0 - config.win_rate);
+                if winning && losing {
+                    return Some(Adjudication::Win(winner));
+                }
+            }
+        }
+        if config.draw_moves > 0 && ply >= config.draw_min_ply {
+            let balanced = |rate: f64| (rate - 0.5).abs() <= config.draw_band;
+            if self.streak(Color::Black, config.draw_moves, balanced)
+                && self.streak(Color::White, config.draw_moves, balanced)
+            {
+                return Some(Adjudication::Draw);
+            }
+        }
+        None
+    }
+
+    /// `side` の直近 `moves` 手の勝率がすべて `pred` を満たすか
+    fn streak(&self, side: Color, moves: u32, pred: impl Fn(f64) -> bool) -> bool {
+        let recent = &self.recent[side.index()];
+        let moves = moves as usize;
+        recent.len() >= moves
+            && recent[recent.len() - moves..]
+                .iter()
+                .all(|score| score.is_some_and(|s| pred(s.win_rate(self.config.eval_scale))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(adjudicator: &mut Adjudicator, scores: &[(i32, i32)]) {
+        for &(black, white) in scores {
+            adjudicator.record(Color::Black, Some(ReportedScore::Cp(black)));
+            adjudicator.record(Color::White, Some(ReportedScore::Cp(white)));
+        }
+    }
+
+    #[test]
+    fn win_requires_both_sides_to_agree() {
+        let mut adjudicator = Adjudicator::new(AdjudicationConfig::default());
+        // 後手は負けを認めていない
+        play(&mut adjudicator, &[(3000, -100); 4]);
+        assert_eq!(adjudicator.decide(8), None);
+
+        play(&mut adjudicator, &[(3000, -3000); 4]);
+        assert_eq!(adjudicator.decide(16), Some(Adjudication::Win(Color::Black)));
+    }
+
+    #[test]
+    fn win_requires_consecutive_moves() {
+        let mut adjudicator = Adjudicator::new(AdjudicationConfig::default());
+        play(&mut adjudicator, &[(-3000, 3000); 3]);
+        assert_eq!(adjudicator.decide(6), None);
+        play(&mut adjudicator, &[(-3000, 3000)]);
+        assert_eq!(adjudicator.decide(8), Some(Adjudication::Win(Color::White)));
+
+        // 評価値のない手を挟むと数え直し
+        adjudicator.record(Color::Black, None);
+        adjudicator.record(Color::White, Some(ReportedScore::Cp(3000)));
+        assert_eq!(adjudicator.decide(10), None);
+    }
+
+    #[test]
+    fn mate_scores_count_as_decided() {
+        let mut adjudicator = Adjudicator::new(AdjudicationConfig::default());
+        for _ in 0..4 {
+            adjudicator.record(Color::Black, Some(ReportedScore::Mate(-7)));
+            adjudicator.record(Color::White, Some(ReportedScore::Mate(5)));
+        }
+        assert_eq!(adjudicator.decide(8), Some(Adjudication::Win(Color::White)));
+    }
+
+    #[test]
+    fn draw_waits_for_min_ply_and_balanced_scores() {
+        let config = AdjudicationConfig::default();
+        let mut adjudicator = Adjudicator::new(config);
+        play(&mut adjudicator, &[(10, -20); 8]);
+        assert_eq!(adjudicator.decide(config.draw_min_ply - 1), None);
+        assert_eq!(adjudicator.decide(config.draw_min_ply), Some(Adjudication::Draw));
+
+        play(&mut adjudicator, &[(300, -300)]);
+        assert_eq!(adjudicator.decide(config.draw_min_ply + 2), None);
+    }
+
+    #[test]
+    fn zero_moves_disable_each_rule() {
+        let mut adjudicator = Adjudicator::new(AdjudicationConfig {
+            win_moves: 0,
+            draw_moves: 0,
+            ..AdjudicationConfig::default()
+        });
+        play(&mut adjudicator, &[(3000, -3000); 8]);
+        assert_eq!(adjudicator.decide(400), None);
+    }
+}
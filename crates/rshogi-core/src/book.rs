@@ -0,0 +1,378 @@
+//! 定跡（バイナリ形式）
+//!
+//! 局面の Zobrist キー（[`Position::key`]）ごとに候補手を持ち、重み付きで 1 手を選ぶ。
+//! 候補手には探索の評価値・深さをメタデータとして持たせられる。
+//! [`Search::set_book`](crate::search::Search::set_book) で探索に渡すと、定跡にある
+//! 局面では探索せずに定跡手を返す。
+//!
+//! ## ファイル形式（リトルエンディアン）
+//!
+//! | オフセット | サイズ | 内容 |
+//! |---|---|---|
+//! | 0 | 8 | マジック `RSHOGIBK` |
+//! | 8 | 4 | バージョン（`u32`、現在は 1） |
+//! | 12 | 4 | エントリ数（`u32`） |
+//! | 16 | 16 × n | エントリ（キー昇順） |
+//!
+//! エントリは `key: u64`, `move: u16`（[`Move::to_u16`]）, `weight: u16`,
+//! `score: i16`（cp、手番側から見た値）, `depth: u16` の 16 バイト。
+//! 同じキーのエントリは連続して並ぶ。キーは局面の盤面・手番・持ち駒から決まり、
+//! 手数は含まない。
+
+use std::fmt;
+use std::path::Path;
+
+use rand::Rng;
+
+use crate::position::Position;
+use crate::types::Move;
+
+/// ファイル先頭のマジック
+const MAGIC: &[u8; 8] = b"RSHOGIBK";
+
+/// 現在のファイル形式のバージョン
+const VERSION: u32 = 1;
+
+/// ヘッダのサイズ（バイト）
+const HEADER_SIZE: usize = 16;
+
+/// エントリのサイズ（バイト）
+const ENTRY_SIZE: usize = 16;
+
+/// 定跡の読み込みエラー
+#[derive(Debug)]
+pub enum BookError {
+    /// ファイルの読み書きに失敗
+    Io(std::io::Error),
+    /// マジックが一致しない
+    BadMagic,
+    /// 未対応のバージョン
+    UnsupportedVersion(u32),
+    /// ヘッダのエントリ数とデータ長が一致しない
+    Truncated { expected: usize, actual: usize },
+    /// エントリがキー昇順に並んでいない
+    Unsorted { index: usize },
+}
+
+impl fmt::Display for BookError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BookError::Io(e) => write!(f, "I/O error: {e}"),
+            BookError::BadMagic => write!(f, "not an rshogi book (bad magic)"),
+            BookError::UnsupportedVersion(v) => write!(f, "unsupported book version: {v}"),
+            BookError::Truncated { expected, actual } => {
+                write!(f, "truncated book: expected {expected} bytes, got {actual}")
+            }
+            BookError::Unsorted { index } => write!(f, "book entries not sorted at {index}"),
+        }
+    }
+}
+
+impl std::error::Error for BookError {}
+
+impl From<std::io::Error> for BookError {
+    fn from(e: std::io::Error) -> Self {
+        BookError::Io(e)
+    }
+}
+
+/// 定跡の候補手
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BookMove {
+    /// 指し手（[`Book::probe`] の結果では駒情報付きの合法手）
+    pub mv: Move,
+    /// 選択の重み（0 なら重み付き選択では選ばれない）
+    pub weight: u16,
+    /// 評価値（cp、手番側から見た値）
+    pub score: i16,
+    /// 評価値を得た探索深さ
+    pub depth: u16,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct BookEntry {
+    key: u64,
+    book_move: BookMove,
+}
+
+/// 定跡
+///
+/// エントリはキー昇順に保持し、probe は二分探索で行う。
+#[derive(Debug, Clone, Default)]
+pub struct Book {
+    entries: Vec<BookEntry>,
+}
+
+impl Book {
+    /// 空の定跡
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// ファイルから読み込む
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, BookError> {
+        Self::from_bytes(&std::fs::read(path)?)
+    }
+
+    /// ファイルに書き出す
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), BookError> {
+        std::fs::write(path, self.to_bytes())?;
+        Ok(())
+    }
+
+    /// バイト列から読み込む
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, BookError> {
+        if bytes.len() < HEADER_SIZE || &bytes[..8] != MAGIC {
+            return Err(BookError::BadMagic);
+        }
+        let version = u32::from_le_bytes(bytes[8..12].try_into().unwrap());
+        if version != VERSION {
+            return Err(BookError::UnsupportedVersion(version));
+        }
+        let count = u32::from_le_bytes(bytes[12..16].try_into().unwrap()) as usize;
+        let expected = HEADER_SIZE + count * ENTRY_SIZE;
+        if bytes.len() != expected {
+            return Err(BookError::Truncated {
+                expected,
+                actual: bytes.len(),
+            });
+        }
+
+        let mut entries = Vec::with_capacity(count);
+        for (index, chunk) in bytes[HEADER_SIZE..].chunks_exact(ENTRY_SIZE).enumerate() {
+            let entry = BookEntry {
+                key: u64::from_le_bytes(chunk[0..8].try_into().unwrap()),
+                book_move: BookMove {
+                    mv: Move::from_u16(u16::from_le_bytes([chunk[8], chunk[9]])),
+                    weight: u16::from_le_bytes([chunk[10], chunk[11]]),
+                    score: i16::from_le_bytes([chunk[12], chunk[13]]),
+                    depth: u16::from_le_bytes([chunk[14], chunk[15]]),
+                },
+            };
+            if entries.last().is_some_and(|prev: &BookEntry| prev.key > entry.key) {
+                return Err(BookError::Unsorted { index });
+            }
+            entries.push(entry);
+        }
+        Ok(Self { entries })
+    }
+
+    /// バイト列に書き出す
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_SIZE + self.entries.len() * ENTRY_SIZE);
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&VERSION.to_le_bytes());
+        bytes.extend_from_slice(&(self.entries.len() as u32).to_le_bytes());
+        for entry in &self.entries {
+            let m = &entry.book_move;
+            bytes.extend_from_slice(&entry.key.to_le_bytes());
+            bytes.extend_from_slice(&m.mv.to_u16().to_le_bytes());
+            bytes.extend_from_slice(&m.weight.to_le_bytes());
+            bytes.extend_from_slice(&m.score.to_le_bytes());
+            bytes.extend_from_slice(&m.depth.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// `pos` の候補手を追加する（同じ指し手があれば置き換える）
+    pub fn insert(&mut self, pos: &Position, book_move: BookMove) {
+        self.insert_key(pos.key(), book_move);
+    }
+
+    /// キーを指定して候補手を追加する（同じ指し手があれば置き換える）
+    pub fn insert_key(&mut self, key: u64, book_move: BookMove) {
+        let book_move = BookMove {
+            mv: Move::from_u16(book_move.mv.to_u16()),
+            ..book_move
+        };
+        let range = self.range(key);
+        if let Some(entry) =
+            self.entries[range.clone()].iter_mut().find(|e| e.book_move.mv == book_move.mv)
+        {
+            entry.book_move = book_move;
+        } else {
+            self.entries.insert(range.end, BookEntry { key, book_move });
+        }
+    }
+
+    /// 定跡手のある局面数
+    pub fn num_positions(&self) -> usize {
+        let mut count = 0;
+        let mut prev = None;
+        for entry in &self.entries {
+            if prev != Some(entry.key) {
+                count += 1;
+                prev = Some(entry.key);
+            }
+        }
+        count
+    }
+
+    /// 候補手の総数
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// 候補手が 1 つもないか
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// キーに対応する候補手（合法性は検査しない、駒情報なし）
+    pub fn moves_by_key(&self, key: u64) -> impl Iterator<Item = BookMove> + '_ {
+        self.entries[self.range(key)].iter().map(|e| e.book_move)
+    }
+
+    /// `pos` の合法な候補手（記録順）
+    ///
+    /// キーの衝突や壊れたデータで非合法になった手は除く。
+    pub fn probe(&self, pos: &Position) -> Vec<BookMove> {
+        self.moves_by_key(pos.key())
+            .filter_map(|m| legal_move(pos, m.mv).map(|mv| BookMove { mv, ..m }))
+            .collect()
+    }
+
+    /// `pos` の候補手から重みに比例した確率で 1 手選ぶ
+    ///
+    /// 重みの合計が 0 なら評価値が最も高い手を選ぶ。候補手がなければ `None`。
+    pub fn pick<R: Rng + ?Sized>(&self, pos: &Position, rng: &mut R) -> Option<BookMove> {
+        let moves = self.probe(pos);
+        let total: u32 = moves.iter().map(|m| m.weight as u32).sum();
+        if total == 0 {
+            return moves.into_iter().max_by_key(|m| m.score);
+        }
+        let mut r = rng.random_range(0..total);
+        for m in &moves {
+            let w = m.weight as u32;
+            if r < w {
+                return Some(*m);
+            }
+            r -= w;
+        }
+        unreachable!("weighted pick must select a move")
+    }
+
+    /// `pos` の候補手のうち重みが最も大きい手（同じ重みなら評価値が高い手）
+    pub fn best(&self, pos: &Position) -> Option<BookMove> {
+        self.probe(pos).into_iter().max_by_key(|m| (m.weight, m.score))
+    }
+
+    /// キーに対応するエントリの範囲
+    fn range(&self, key: u64) -> std::ops::Range<usize> {
+        let start = self.entries.partition_point(|e| e.key < key);
+        let end = start + self.entries[start..].partition_point(|e| e.key == key);
+        start..end
+    }
+}
+
+/// 駒情報を補った合法手（非合法・パスなら `None`）
+fn legal_move(pos: &Position, mv: Move) -> Option<Move> {
+    if mv.is_none() || mv.is_pass() {
+        return None;
+    }
+    let mv = pos.to_move(mv)?;
+    (mv.is_some() && pos.pseudo_legal(mv) && pos.is_legal(mv)).then_some(mv)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_xoshiro::Xoshiro256PlusPlus;
+
+    fn hirate() -> Position {
+        let mut pos = Position::new();
+        pos.set_hirate();
+        pos
+    }
+
+    fn book_move(usi: &str, weight: u16, score: i16) -> BookMove {
+        BookMove {
+            mv: Move::from_usi(usi).unwrap(),
+            weight,
+            score,
+            depth: 20,
+        }
+    }
+
+    fn sample_book() -> Book {
+        let pos = hirate();
+        let mut book = Book::new();
+        book.insert(&pos, book_move("7g7f", 30, 40));
+        book.insert(&pos, book_move("2g2f", 10, 50));
+        // 非合法な手は probe で除かれる
+        book.insert(&pos, book_move("5e5d", 100, 90));
+        book.insert_key(1, book_move("3c3d", 1, 0));
+        book
+    }
+
+    #[test]
+    fn roundtrip_bytes() {
+        let book = sample_book();
+        let bytes = book.to_bytes();
+        assert_eq!(bytes.len(), HEADER_SIZE + 4 * ENTRY_SIZE);
+        let loaded = Book::from_bytes(&bytes).unwrap();
+        assert_eq!(loaded.entries, book.entries);
+        assert_eq!(loaded.num_positions(), 2);
+    }
+
+    #[test]
+    fn probe_returns_legal_moves_with_metadata() {
+        let book = sample_book();
+        let pos = hirate();
+        let moves = book.probe(&pos);
+        let usi: Vec<_> = moves.iter().map(|m| m.mv.to_usi()).collect();
+        assert_eq!(usi, ["7g7f", "2g2f"]);
+        assert!(moves.iter().all(|m| m.mv.has_piece_info() && m.depth == 20));
+        assert_eq!(book.best(&pos).unwrap().mv.to_usi(), "7g7f");
+    }
+
+    #[test]
+    fn insert_replaces_same_move() {
+        let mut book = sample_book();
+        let pos = hirate();
+        book.insert(&pos, book_move("2g2f", 50, 60));
+        assert_eq!(book.len(), 4);
+        assert_eq!(book.best(&pos).unwrap().mv.to_usi(), "2g2f");
+    }
+
+    #[test]
+    fn pick_follows_weights() {
+        let book = sample_book();
+        let pos = hirate();
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(1);
+        let picks_7g7f = (0..400)
+            .filter(|_| book.pick(&pos, &mut rng).unwrap().mv.to_usi() == "7g7f")
+            .count();
+        // 期待値 300（重み 30 : 10）
+        assert!((250..350).contains(&picks_7g7f), "{picks_7g7f}");
+    }
+
+    #[test]
+    fn pick_falls_back_to_score_when_weights_are_zero() {
+        let pos = hirate();
+        let mut book = Book::new();
+        book.insert(&pos, book_move("7g7f", 0, 40));
+        book.insert(&pos, book_move("2g2f", 0, 50));
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(1);
+        assert_eq!(book.pick(&pos, &mut rng).unwrap().mv.to_usi(), "2g2f");
+        assert!(Book::new().pick(&pos, &mut rng).is_none());
+    }
+
+    #[test]
+    fn from_bytes_rejects_broken_data() {
+        let bytes = sample_book().to_bytes();
+        assert!(matches!(Book::from_bytes(b"RSHOGI"), Err(BookError::BadMagic)));
+        assert!(matches!(
+            Book::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(BookError::Truncated { .. })
+        ));
+        let mut bad_version = bytes.clone();
+        bad_version[8] = 2;
+        assert!(matches!(Book::from_bytes(&bad_version), Err(BookError::UnsupportedVersion(2))));
+        // 先頭エントリのキーを最大にすると昇順が崩れる
+        let mut unsorted = bytes;
+        unsorted[HEADER_SIZE..HEADER_SIZE + 8].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert!(matches!(Book::from_bytes(&unsorted), Err(BookError::Unsorted { index: 1 })));
+    }
+}
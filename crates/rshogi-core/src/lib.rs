@@ -10,8 +10,12 @@
 //! - `tt`: 置換表（Transposition Table）
 //! - `search`: 探索アルゴリズム
 //! - `movepick`: 手の順序付け
-//! - `time`: 時間管理
-//! - `mate`: 1手詰め探索
+//! - `time`: 時刻の抽象化（`Clock`、テスト用の `ManualClock`）
+//! - `mate`: 1手詰め判定と df-pn 詰将棋ソルバー
+//! - `testpos`: タグと期待結果付きの標準テスト局面集
+//! - `book`: 定跡（バイナリ形式、重み付き選択）
+//! - `adjudication`: 評価値による対局の裁定（自己対局・対局モードの勝ち / 引き分け判定）
+//! - `record`: 棋譜ファイル（KIF / KI2 / CSA、`json` と併用で JKF）の読み書き
 //!
 
 pub mod types;
@@ -27,18 +31,37 @@ pub mod movegen;
 // NNUE評価
 pub mod nnue;
 
+// 定跡
+pub mod book;
+
+// 対局の裁定
+pub mod adjudication;
+
 // 置換表
+#[cfg(feature = "search")]
 pub mod tt;
 
 //  探索
+#[cfg(feature = "search")]
 pub mod search;
 
 pub(crate) mod prefetch;
 
-// 時刻（Instant）抽象化
-pub(crate) mod time;
+// 時刻（Instant / Clock）抽象化
+#[cfg(feature = "search")]
+pub mod time;
 
 // 1手詰め探索
+#[cfg(feature = "mate")]
 pub mod mate;
 
+// 標準テスト局面集
+#[cfg(feature = "testpos")]
+pub mod testpos;
+
+// 棋譜ファイル
+#[cfg(feature = "record")]
+pub mod record;
+
+#[cfg(feature = "json")]
 pub use position::json_conversion;
@@ -0,0 +1,440 @@
+//! df-pn（depth-first proof-number search）による詰将棋ソルバー
+//!
+//! 攻め方（探索開始局面の手番）の王手と受け方の応手を AND/OR 木として展開し、
+//! 証明数・反証数を置換表に保持しながら閾値付きの深さ優先探索を行う。
+//! 詰みを証明したら置換表に残した「詰みまでの手数」を辿って手順を取り出す。
+//!
+//! 制約:
+//! - 優等局面・劣等局面（持ち駒の包含関係）による再利用は行わない
+//! - 経路上の同一局面（連続王手の千日手）は攻め方の失敗として扱う。
+//!   経路依存の結果も置換表に残すため、稀に詰みを取り逃がす（GHI 問題）
+//! - 見つかる手順は最短とは限らない（証明木の中で短い手順を選ぶ）
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::movegen::{ExtMoveBuffer, GenType, generate_with_type};
+use crate::position::Position;
+use crate::types::Move;
+
+/// 証明数・反証数の無限大
+const INF: u32 = u32::MAX;
+
+/// 和の上限（INF は終端の証明・反証専用に取っておく）
+const SUM_CAP: u32 = INF - 1;
+
+/// 時間・停止要求を確認する間隔（ノード数）
+const CHECK_INTERVAL: u64 = 1024;
+
+/// 手順の取り出しで辿る最大手数
+const MAX_PV_PLIES: usize = 1024;
+
+/// df-pn ソルバーの探索制限
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DfPnLimits {
+    /// 展開ノード数の上限（0 なら無制限）
+    pub max_nodes: u64,
+    /// 思考時間の上限（`None` なら無制限）
+    pub time_limit: Option<Duration>,
+    /// 探索する最大手数。これより深い局面は不詰として扱う
+    pub max_ply: u16,
+}
+
+impl Default for DfPnLimits {
+    fn default() -> Self {
+        Self {
+            max_nodes: 0,
+            time_limit: None,
+            max_ply: 255,
+        }
+    }
+}
+
+/// 詰み探索の結果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MateOutcome {
+    /// 詰み。攻め方の初手から玉が詰むまでの手順
+    Mate(Vec<Move>),
+    /// 不詰を証明した
+    NoMate,
+    /// 制限（ノード数・時間・停止要求）に達して結論が出なかった
+    Unknown,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    pn: u32,
+    dn: u32,
+    /// 証明済み（pn == 0）の局面で、玉が詰むまでの手数
+    mate_len: u16,
+}
+
+impl Entry {
+    const UNKNOWN: Self = Self {
+        pn: 1,
+        dn: 1,
+        mate_len: 0,
+    };
+}
+
+/// df-pn による詰将棋ソルバー
+///
+/// 置換表は [`DfPnSolver::solve`] ごとに作り直す。
+pub struct DfPnSolver<'a> {
+    limits: DfPnLimits,
+    stop: Option<&'a AtomicBool>,
+    table: HashMap<u64, Entry>,
+    /// 現在の探索経路上の局面（千日手の検出用）
+    path: Vec<u64>,
+    nodes: u64,
+    start: Instant,
+    aborted: bool,
+}
+
+impl<'a> DfPnSolver<'a> {
+    pub fn new(limits: DfPnLimits) -> Self {
+        Self {
+            limits,
+            stop: None,
+            table: HashMap::new(),
+            path: Vec::new(),
+            nodes: 0,
+            start: Instant::now(),
+            aborted: false,
+        }
+    }
+
+    /// 外部からの停止要求（USI `stop` など）を監視する
+    pub fn with_stop_flag(mut self, stop: &'a AtomicBool) -> Self {
+        self.stop = Some(stop);
+        self
+    }
+
+    /// 直前の探索で展開したノード数
+    pub fn nodes(&self) -> u64 {
+        self.nodes
+    }
+
+    /// 手番側が攻め方となる詰みを探す
+    ///
+    /// 探索後の `pos` は呼び出し前の局面に戻っている。
+    pub fn solve(&mut self, pos: &mut Position) -> MateOutcome {
+        self.table.clear();
+        self.path.clear();
+        self.nodes = 0;
+        self.start = Instant::now();
+        self.aborted = false;
+
+        let root = pos.key();
+        loop {
+            self.mid(pos, INF, INF, true, 0);
+            let entry = self.lookup(root);
+            if entry.pn == 0 {
+                return self.extract_pv(pos).map_or(MateOutcome::Unknown, MateOutcome::Mate);
+            }
+            if entry.dn == 0 {
+                return MateOutcome::NoMate;
+            }
+            if self.aborted {
+                return MateOutcome::Unknown;
+            }
+        }
+    }
+
+    fn lookup(&self, key: u64) -> Entry {
+        self.table.get(&key).copied().unwrap_or(Entry::UNKNOWN)
+    }
+
+    /// 子局面の証明数・反証数（経路上の局面は攻め方の失敗）
+    fn child_entry(&self, key: u64) -> Entry {
+        if self.path.contains(&key) {
+            Entry {
+                pn: INF,
+                dn: 0,
+                mate_len: 0,
+            }
+        } else {
+            self.lookup(key)
+        }
+    }
+
+    fn should_abort(&mut self) -> bool {
+        if self.aborted {
+            return true;
+        }
+        if self.limits.max_nodes != 0 && self.nodes >= self.limits.max_nodes {
+            self.aborted = true;
+        } else if self.nodes.is_multiple_of(CHECK_INTERVAL) {
+            let stopped = self.stop.is_some_and(|stop| stop.load(Ordering::Relaxed));
+            let timed_out =
+                self.limits.time_limit.is_some_and(|limit| self.start.elapsed() >= limit);
+            self.aborted = stopped || timed_out;
+        }
+        self.aborted
+    }
+
+    /// 閾値 (`th_pn`, `th_dn`) を超えるまで `pos` 以下を展開する
+    fn mid(&mut self, pos: &mut Position, th_pn: u32, th_dn: u32, or_node: bool, ply: u16) {
+        if self.should_abort() {
+            return;
+        }
+        self.nodes += 1;
+        let key = pos.key();
+
+        let moves = if ply >= self.limits.max_ply {
+            Vec::new()
+        } else {
+            children(pos, or_node)
+        };
+        if moves.is_empty() {
+            // 王手が無ければ不詰、応手が無ければ詰み
+            let entry = if or_node {
+                Entry {
+                    pn: INF,
+                    dn: 0,
+                    mate_len: 0,
+                }
+            } else {
+                Entry {
+                    pn: 0,
+                    dn: INF,
+                    mate_len: 0,
+                }
+            };
+            self.table.insert(key, entry);
+            return;
+        }
+
+        let child_keys: Vec<u64> = moves.iter().map(|&m| key_after(pos, m)).collect();
+        self.path.push(key);
+        loop {
+            let entry = self.aggregate(&child_keys, or_node);
+            self.table.insert(key, entry);
+            if entry.pn == 0 || entry.dn == 0 || entry.pn >= th_pn || entry.dn >= th_dn {
+                break;
+            }
+
+            let (best, second) = self.select_child(&child_keys, or_node);
+            let child = self.child_entry(child_keys[best]);
+            let (child_th_pn, child_th_dn) = if or_node {
+                (th_pn.min(second.saturating_add(1)), th_dn - (entry.dn - child.dn))
+            } else {
+                (th_pn - (entry.pn - child.pn), th_dn.min(second.saturating_add(1)))
+            };
+
+            let mv = moves[best];
+            let gives_check = pos.gives_check(mv);
+            pos.do_move(mv, gives_check);
+            self.mid(pos, child_th_pn, child_th_dn, !or_node, ply + 1);
+            pos.undo_move(mv);
+
+            if self.aborted {
+                break;
+            }
+        }
+        self.path.pop();
+    }
+
+    /// 子局面の値から自局面の証明数・反証数を求める
+    fn aggregate(&self, child_keys: &[u64], or_node: bool) -> Entry {
+        let mut min = INF;
+        let mut sum = 0u32;
+        let mut proven_len: Option<u16> = None;
+        for &key in child_keys {
+            let child = self.child_entry(key);
+            let (minimized, summed) = if or_node {
+                (child.pn, child.dn)
+            } else {
+                (child.dn, child.pn)
+            };
+            min = min.min(minimized);
+            sum = sum.saturating_add(summed);
+            if child.pn == 0 {
+                let len = child.mate_len + 1;
+                proven_len = Some(match proven_len {
+                    // 攻め方は短い詰み、受け方は長い詰みを選ぶ
+                    Some(cur) if or_node => cur.min(len),
+                    Some(cur) => cur.max(len),
+                    None => len,
+                });
+            }
+        }
+        if sum != INF {
+            sum = sum.min(SUM_CAP);
+        }
+
+        let (pn, dn) = if or_node { (min, sum) } else { (sum, min) };
+        Entry {
+            pn,
+            dn,
+            mate_len: if pn == 0 { proven_len.unwrap_or(0) } else { 0 },
+        }
+    }
+
+    /// 次に展開する子（OR 節点は証明数、AND 節点は反証数が最小）と 2 番目の値
+    fn select_child(&self, child_keys: &[u64], or_node: bool) -> (usize, u32) {
+        let mut best = 0;
+        let mut best_value = INF;
+        let mut second = INF;
+        for (i, &key) in child_keys.iter().enumerate() {
+            let child = self.child_entry(key);
+            let value = if or_node { child.pn } else { child.dn };
+            if value < best_value {
+                second = best_value;
+                best_value = value;
+                best = i;
+            } else if value < second {
+                second = value;
+            }
+        }
+        (best, second)
+    }
+
+    /// 証明木から詰み手順を取り出す
+    ///
+    /// 攻め方は詰みまでの手数が最短の王手、受け方は最長の応手を選ぶ。
+    /// 置換表の情報が欠けていて玉が詰む局面まで辿れなければ `None` を返す。
+    fn extract_pv(&self, pos: &mut Position) -> Option<Vec<Move>> {
+        let mut pv = Vec::new();
+        let mut seen = vec![pos.key()];
+        let mut or_node = true;
+        let mut mated = false;
+        while pv.len() < MAX_PV_PLIES {
+            let moves = children(pos, or_node);
+            if moves.is_empty() {
+                mated = !or_node;
+                break;
+            }
+            let proven = moves.iter().filter_map(|&m| {
+                let key = key_after(pos, m);
+                let entry = self.lookup(key);
+                (entry.pn == 0 && !seen.contains(&key)).then_some((m, entry.mate_len))
+            });
+            let next = if or_node {
+                proven.min_by_key(|&(_, len)| len)
+            } else {
+                proven.max_by_key(|&(_, len)| len)
+            };
+            let Some((mv, _)) = next else {
+                break;
+            };
+            let gives_check = pos.gives_check(mv);
+            pos.do_move(mv, gives_check);
+            seen.push(pos.key());
+            pv.push(mv);
+            or_node = !or_node;
+        }
+        for &mv in pv.iter().rev() {
+            pos.undo_move(mv);
+        }
+        mated.then_some(pv)
+    }
+}
+
+/// `mv` を指した後の局面のハッシュキー
+fn key_after(pos: &mut Position, mv: Move) -> u64 {
+    let gives_check = pos.gives_check(mv);
+    pos.do_move(mv, gives_check);
+    let key = pos.key();
+    pos.undo_move(mv);
+    key
+}
+
+/// 攻め方（OR 節点）は王手になる合法手、受け方（AND 節点）は全ての合法手
+///
+/// 不成も含める（歩不成で打ち歩詰めを避ける手順などがあるため）。
+fn children(pos: &Position, or_node: bool) -> Vec<Move> {
+    let mut buffer = ExtMoveBuffer::new();
+    let gen_type = if or_node && !pos.in_check() {
+        GenType::ChecksAll
+    } else {
+        GenType::LegalAll
+    };
+    generate_with_type(pos, gen_type, &mut buffer, None);
+    buffer
+        .iter()
+        .map(|ext| ext.mv)
+        .filter(|&m| pos.is_legal(m) && (!or_node || pos.gives_check(m)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solve(sfen: &str, limits: DfPnLimits) -> MateOutcome {
+        let mut pos = Position::new();
+        pos.set_sfen(sfen).unwrap();
+        let before = pos.to_sfen();
+        let outcome = DfPnSolver::new(limits).solve(&mut pos);
+        assert_eq!(pos.to_sfen(), before, "探索後に局面が戻っていること");
+        outcome
+    }
+
+    fn usi(moves: &[Move]) -> Vec<String> {
+        moves.iter().map(|m| m.to_usi()).collect()
+    }
+
+    #[test]
+    fn finds_mate_in_one() {
+        // 頭金
+        let outcome = solve("4k4/9/4P4/9/9/9/9/9/4K4 b G 1", DfPnLimits::default());
+        let MateOutcome::Mate(pv) = outcome else {
+            panic!("詰みのはず: {outcome:?}");
+        };
+        assert_eq!(usi(&pv), ["G*5b"]);
+    }
+
+    #[test]
+    fn finds_mate_in_three_and_ends_in_checkmate() {
+        let sfen = "7k1/4pl3/6+R2/9/9/9/9/9/4K4 b 2S 1";
+        let outcome = solve(sfen, DfPnLimits::default());
+        let MateOutcome::Mate(pv) = outcome else {
+            panic!("詰みのはず: {outcome:?}");
+        };
+        assert_eq!(pv.len() % 2, 1, "攻め方の手で終わること");
+
+        let mut pos = Position::new();
+        pos.set_sfen(sfen).unwrap();
+        for &mv in &pv {
+            assert!(pos.is_legal(mv));
+            let gives_check = pos.gives_check(mv);
+            pos.do_move(mv, gives_check);
+        }
+        assert!(pos.in_check());
+        assert!(children(&pos, false).is_empty(), "手順の最後で玉が詰んでいること");
+    }
+
+    #[test]
+    fn proves_no_mate_without_checks() {
+        let outcome = solve("4k4/9/9/9/9/9/9/9/4K4 b - 1", DfPnLimits::default());
+        assert_eq!(outcome, MateOutcome::NoMate);
+    }
+
+    #[test]
+    fn pawn_drop_mate_is_not_a_mate() {
+        // 打ち歩詰めしか無い
+        let outcome = solve("8k/6S2/7G1/9/9/9/9/9/4K4 b P 1", DfPnLimits::default());
+        assert_eq!(outcome, MateOutcome::NoMate);
+    }
+
+    #[test]
+    fn stops_at_node_limit() {
+        let limits = DfPnLimits {
+            max_nodes: 1,
+            ..DfPnLimits::default()
+        };
+        let outcome = solve("7k1/4pl3/6+R2/9/9/9/9/9/4K4 b 2S 1", limits);
+        assert_eq!(outcome, MateOutcome::Unknown);
+    }
+
+    #[test]
+    fn stop_flag_aborts_search() {
+        let stop = AtomicBool::new(true);
+        let mut pos = Position::new();
+        pos.set_hirate();
+        let mut solver = DfPnSolver::new(DfPnLimits::default()).with_stop_flag(&stop);
+        assert_eq!(solver.solve(&mut pos), MateOutcome::Unknown);
+    }
+}
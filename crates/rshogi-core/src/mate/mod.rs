@@ -1,6 +1,7 @@
-// 1手詰め探索モジュール
+// 詰み探索モジュール（1手詰め判定と df-pn ソルバー）
 // YaneuraOuのmate1ply_without_effect.cppの移植
 
+pub mod dfpn;
 pub mod drop_mate;
 pub mod helpers;
 pub mod move_mate;
@@ -1403,6 +1403,65 @@ pub fn is_legal_with_pass(pos: &Position, m: Move) -> bool {
     pos.is_legal(m)
 }
 
+// ============================================================================
+// 種類別の合法手生成（UI 向け）
+// ============================================================================
+
+/// 王手になる合法手を生成（不成含む）
+///
+/// 指導モードの「ここで指せる王手」や詰将棋の作成など、探索を使わずに種類別の
+/// 指し手を示す用途向け。`generate_legal_all()` と同様に不成も生成する。
+/// 王手がかかっている局面では、王手を回避しつつ王手をかける手（逆王手）になる。
+pub fn generate_legal_checks(pos: &Position, list: &mut MoveList) {
+    if pos.in_check() {
+        push_legal_evasions(pos, list, |mv| pos.gives_check(mv));
+    } else {
+        push_legal(pos, crate::movegen::GenType::ChecksAll, list);
+    }
+}
+
+/// 駒を取る合法手を生成（不成含む）
+///
+/// 王手がかかっている局面では、王手を回避する手のうち駒を取るものになる。
+pub fn generate_legal_captures(pos: &Position, list: &mut MoveList) {
+    if pos.in_check() {
+        push_legal_evasions(pos, list, |mv| pos.is_capture(mv));
+    } else {
+        push_legal(pos, crate::movegen::GenType::CapturesAll, list);
+    }
+}
+
+/// 王手を回避する合法手を生成（不成含む）
+///
+/// 王手がかかっていない局面では何も生成しない。
+pub fn generate_legal_evasions(pos: &Position, list: &mut MoveList) {
+    if pos.in_check() {
+        push_legal_evasions(pos, list, |_| true);
+    }
+}
+
+/// `gen_type` で生成した pseudo-legal 手のうち合法なものを追加する
+fn push_legal(pos: &Position, gen_type: crate::movegen::GenType, list: &mut MoveList) {
+    let mut buffer = ExtMoveBuffer::new();
+    generate_with_type(pos, gen_type, &mut buffer, None);
+    for ext in buffer.iter() {
+        if pos.is_legal(ext.mv) {
+            list.push(ext.mv);
+        }
+    }
+}
+
+/// 王手回避の合法手のうち `filter` を満たすものを追加する
+fn push_legal_evasions(pos: &Position, list: &mut MoveList, filter: impl Fn(Move) -> bool) {
+    let mut buffer = ExtMoveBuffer::new();
+    generate_evasions_with_promos(pos, &mut buffer, true, PromotionMode::Both);
+    for ext in buffer.iter() {
+        if pos.is_legal(ext.mv) && filter(ext.mv) {
+            list.push(ext.mv);
+        }
+    }
+}
+
 // ============================================================================
 // Position に合法性チェックを追加
 // ============================================================================
@@ -1519,6 +1578,44 @@ impl Position {
         true
     }
 
+    /// 指し手が合法かを全合法手を生成せずに判定する（UI の着手入力の検証向け）
+    ///
+    /// `generate_legal_all()`（不成含む）の結果に含まれるかと同じ判定になる。
+    /// 駒情報の無い指し手（`Move::from_usi()` の結果など）も受け付ける。
+    /// パスは扱わないため `false` を返す（`is_legal_with_pass()` を使う）。
+    pub fn is_legal_move(&self, mv: Move) -> bool {
+        // 成りフラグ付きの駒打ちは生成されない符号化
+        if !mv.is_normal() || (mv.is_drop() && mv.is_promote()) {
+            return false;
+        }
+        let Some(mv) = self.to_move(mv) else {
+            return false;
+        };
+        if !self.pseudo_legal(mv) {
+            return false;
+        }
+
+        // 行き所の無い駒（1 段目の歩・香、1〜2 段目の桂）になる打ち・不成は反則
+        if mv.is_drop() || !mv.is_promote() {
+            let pt = if mv.is_drop() {
+                mv.drop_piece_type()
+            } else {
+                self.piece_on(mv.from()).piece_type()
+            };
+            let rank = mv.to().rank().relative(self.side_to_move()).index();
+            let dead = match pt {
+                PieceType::Pawn | PieceType::Lance => rank < 1,
+                PieceType::Knight => rank < 2,
+                _ => false,
+            };
+            if dead {
+                return false;
+            }
+        }
+
+        self.is_legal(mv)
+    }
+
     /// 打ち歩詰めかどうかをチェック
     fn is_legal_pawn_drop(&self, to: Square) -> bool {
         let us = self.side_to_move();
@@ -2155,4 +2252,68 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_generate_legal_by_kind_matches_filtered_legal_all() {
+        type Generate = fn(&Position, &mut MoveList);
+        let sfens = [
+            "lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL b - 1",
+            "ln1gk2nl/1rs1g2b1/pppppp1pp/6p2/9/2P1P4/PP1P1PPPP/1B2G2R1/LNS1KGSNL b - 1",
+            "4k4/9/9/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL b GS 1",
+            // 開き王手可能局面
+            "4k4/4r4/4S4/9/9/9/9/9/4K4 b - 1",
+            // 王手がかかっている局面（金を取る・逃げる・合駒で逆王手）
+            "9/9/9/4g4/4K4/9/9/9/9 b - 1",
+            "4k4/9/9/9/4r4/9/9/2B6/4K4 b R 1",
+        ];
+
+        for sfen in &sfens {
+            let mut pos = Position::new();
+            pos.set_sfen(sfen).unwrap();
+            let mut all = MoveList::new();
+            generate_legal_all(&pos, &mut all);
+
+            let cases: [(Generate, &dyn Fn(Move) -> bool); 3] = [
+                (generate_legal_checks, &|mv| pos.gives_check(mv)),
+                (generate_legal_captures, &|mv| pos.is_capture(mv)),
+                (generate_legal_evasions, &|_| pos.in_check()),
+            ];
+            for (generate, filter) in cases {
+                let mut list = MoveList::new();
+                generate(&pos, &mut list);
+                let mut got: Vec<u16> = list.iter().map(|mv| mv.raw()).collect();
+                let mut expected: Vec<u16> =
+                    all.iter().filter(|mv| filter(**mv)).map(|mv| mv.raw()).collect();
+                got.sort_unstable();
+                expected.sort_unstable();
+                assert_eq!(got, expected, "{sfen}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_is_legal_move_matches_generate_legal_all() {
+        let sfens = [
+            "lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL b - 1",
+            // 行き所の無い駒・打ち歩詰め・二歩が絡む局面
+            "8k/2P1P1P2/1N1L1N3/9/9/9/9/9/4K4 b PLN 1",
+            "4k4/9/4G4/9/9/9/9/9/4K4 b P 1",
+            "9/9/9/4g4/4K4/9/9/9/9 b - 1",
+            "4k4/9/9/9/4r4/9/9/2B6/4K4 b R 1",
+            "ln1gk2nl/1rs1g2b1/pppppp1pp/6p2/9/2P1P4/PP1P1PPPP/1B2G2R1/LNS1KGSNL w - 1",
+        ];
+        for sfen in &sfens {
+            let mut pos = Position::new();
+            pos.set_sfen(sfen).unwrap();
+            let mut all = MoveList::new();
+            generate_legal_all(&pos, &mut all);
+            let legal: std::collections::HashSet<u16> = all.iter().map(|mv| mv.raw()).collect();
+            for raw in 0..=u16::MAX {
+                let Some(mv) = Move::from_u16_checked(raw) else {
+                    continue;
+                };
+                assert_eq!(pos.is_legal_move(mv), legal.contains(&raw), "{sfen} {}", mv.to_usi());
+            }
+        }
+    }
 }
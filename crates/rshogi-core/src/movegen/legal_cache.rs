@@ -0,0 +1,69 @@
+//! 合法手リストのキャッシュ（UI 向け）
+//!
+//! 盤面のクリックごとに合法手を問い合わせる UI では、同じ局面で `generate_legal_all()` を
+//! 何度も呼ぶことになる。[`LegalMoveCache`] は局面のハッシュキーごとに 1 つのリストを持ち、
+//! `do_move` / `undo_move` でキーが変わった時点で作り直す。
+//!
+//! `Position` 自体には持たせない（探索で局面をコピーするたびにリストも複製されるため）。
+
+use crate::position::Position;
+use crate::types::Move;
+
+use super::generator::generate_legal_all;
+use super::movelist::MoveList;
+
+/// 直近に問い合わせた局面の合法手（不成含む）
+#[derive(Default)]
+pub struct LegalMoveCache {
+    /// リストを作った局面のハッシュキー（未作成なら `None`）
+    key: Option<u64>,
+    moves: MoveList,
+}
+
+impl LegalMoveCache {
+    /// 空のキャッシュを作る
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// `pos` の合法手（`generate_legal_all()` と同じ内容）
+    ///
+    /// 前回と同じ局面ならリストを作り直さない。
+    pub fn moves(&mut self, pos: &Position) -> &[Move] {
+        let key = pos.key();
+        if self.key != Some(key) {
+            self.moves = MoveList::new();
+            generate_legal_all(pos, &mut self.moves);
+            self.key = Some(key);
+        }
+        self.moves.as_slice()
+    }
+
+    /// 次の問い合わせでリストを作り直させる
+    pub fn clear(&mut self) {
+        self.key = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_follows_do_move() {
+        let mut pos = Position::new();
+        pos.set_hirate();
+        let mut cache = LegalMoveCache::new();
+        assert_eq!(cache.moves(&pos).len(), 30);
+
+        let mv = pos.to_move(Move::from_usi("7g7f").unwrap()).unwrap();
+        let gives_check = pos.gives_check(mv);
+        pos.do_move(mv, gives_check);
+        let replies = cache.moves(&pos).to_vec();
+        assert!(replies.iter().all(|m| pos.is_legal_move(*m)));
+        assert!(!replies.contains(&mv));
+
+        pos.undo_move(mv);
+        assert!(cache.moves(&pos).contains(&mv));
+    }
+}
@@ -6,18 +6,26 @@
 //! - `MoveList`: 固定長バッファを使った指し手リスト
 //! - `generate_non_evasions` / `generate_evasions` / `generate_all`: 王手の有無に応じた pseudo-legal 手生成
 //! - `generate_legal`: `Position::is_legal` でフィルタした完全合法手生成
+//! - `Position::is_legal_move` / `LegalMoveCache`: UI の着手入力向けの合法性判定と合法手リストのキャッシュ
+//! - `generate_legal_checks` / `generate_legal_captures` / `generate_legal_evasions`: 王手・駒取り・王手回避に絞った合法手生成（UI 向け、不成含む）
+//! - `perft` / `perft_divide`: 指し手生成の検証用ノード数計測
 //!
 //! `generate_non_evasions` は「王手がかかっていない局面」でのみ、
 //! `generate_evasions` は「王手がかかっている局面」でのみ呼び出すことを前提とする。
 
 mod generator;
+mod legal_cache;
 mod movelist;
+mod perft;
 mod types;
 
 pub use generator::{
     generate_all, generate_evasions, generate_legal, generate_legal_all,
-    generate_legal_all_with_pass, generate_legal_with_pass, generate_non_evasions,
-    generate_with_type, is_legal_with_pass,
+    generate_legal_all_with_pass, generate_legal_captures, generate_legal_checks,
+    generate_legal_evasions, generate_legal_with_pass, generate_non_evasions, generate_with_type,
+    is_legal_with_pass,
 };
+pub use legal_cache::LegalMoveCache;
 pub use movelist::MoveList;
+pub use perft::{perft, perft_divide};
 pub use types::{ExtMove, ExtMoveBuffer, GenType, MAX_MOVES};
@@ -0,0 +1,111 @@
+//! perft（指し手生成の検証用ノード数計測）
+//!
+//! 指定深さまでの合法手の組み合わせ数を数え、既知の値と比べて指し手生成の退行を検出する。
+//! 数えるのは不成を含むすべての合法手（`generate_legal_all` と同じ集合）。
+//!
+//! 合法手リストを作ってから進めるのではなく、pseudo-legal 手を生成して 1 手ずつ
+//! `Position::is_legal` で確かめながら数える。深さ 1 の節点は指し手を進めずに数える。
+
+use super::{ExtMoveBuffer, GenType, generate_with_type};
+use crate::position::Position;
+use crate::types::Move;
+
+/// `depth` 手先までの末端節点数（`depth` が 0 なら 1）
+pub fn perft(pos: &mut Position, depth: u32) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    let buffer = pseudo_legal_moves(pos);
+    let legal = buffer.iter().map(|ext| ext.mv).filter(|&mv| pos.is_legal(mv));
+    if depth == 1 {
+        return legal.count() as u64;
+    }
+    let moves: Vec<Move> = legal.collect();
+    moves.into_iter().map(|mv| perft_child(pos, mv, depth - 1)).sum()
+}
+
+/// ルートの合法手ごとの perft 値（生成順）
+///
+/// 合計は `perft(pos, depth)` に一致する。`depth` が 0 なら空。
+pub fn perft_divide(pos: &mut Position, depth: u32) -> Vec<(Move, u64)> {
+    if depth == 0 {
+        return Vec::new();
+    }
+    let buffer = pseudo_legal_moves(pos);
+    let mut result = Vec::with_capacity(buffer.len());
+    for ext in buffer.iter() {
+        if pos.is_legal(ext.mv) {
+            result.push((ext.mv, perft_child(pos, ext.mv, depth - 1)));
+        }
+    }
+    result
+}
+
+/// 王手の有無に応じた不成を含む pseudo-legal 手
+fn pseudo_legal_moves(pos: &Position) -> ExtMoveBuffer {
+    let gen_type = if pos.in_check() {
+        GenType::EvasionsAll
+    } else {
+        GenType::NonEvasionsAll
+    };
+    let mut buffer = ExtMoveBuffer::new();
+    generate_with_type(pos, gen_type, &mut buffer, None);
+    buffer
+}
+
+/// `mv` を指した局面の perft 値
+fn perft_child(pos: &mut Position, mv: Move, depth: u32) -> u64 {
+    let gives_check = pos.gives_check(mv);
+    pos.do_move(mv, gives_check);
+    let nodes = perft(pos, depth);
+    pos.undo_move(mv);
+    nodes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::movegen::{MoveList, generate_legal_all};
+
+    #[test]
+    fn perft_startpos() {
+        let mut pos = Position::new();
+        pos.set_hirate();
+        assert_eq!(perft(&mut pos, 0), 1);
+        assert_eq!(perft(&mut pos, 1), 30);
+        assert_eq!(perft(&mut pos, 2), 900);
+        assert_eq!(perft(&mut pos, 3), 25_470);
+    }
+
+    #[test]
+    fn perft_complex_position() {
+        // 指し手生成の検証でよく使われる局面（不成・打ち・成りが多い）
+        let mut pos = Position::new();
+        pos.set_sfen("l6nl/5+P1gk/2np1S3/p1p4Pp/3P2Sp1/1PPb2P1P/P5GS1/R8/LN4bKL w RGgsn5p 1")
+            .unwrap();
+        assert_eq!(perft(&mut pos, 1), 207);
+        assert_eq!(perft(&mut pos, 2), 28_684);
+    }
+
+    #[test]
+    fn perft_in_check_counts_evasions() {
+        let mut pos = Position::new();
+        pos.set_sfen("4k4/9/4G4/9/9/9/9/9/4K4 w - 1").unwrap();
+        let mut list = MoveList::new();
+        generate_legal_all(&pos, &mut list);
+        assert_eq!(perft(&mut pos, 1), list.len() as u64);
+    }
+
+    #[test]
+    fn divide_sums_to_perft() {
+        let mut pos = Position::new();
+        pos.set_sfen("l6nl/5+P1gk/2np1S3/p1p4Pp/3P2Sp1/1PPb2P1P/P5GS1/R8/LN4bKL w RGgsn5p 1")
+            .unwrap();
+        let sfen = pos.to_sfen();
+        let divide = perft_divide(&mut pos, 2);
+        assert_eq!(divide.len(), 207);
+        assert_eq!(divide.iter().map(|(_, n)| n).sum::<u64>(), 28_684);
+        assert_eq!(pos.to_sfen(), sfen);
+        assert!(perft_divide(&mut pos, 0).is_empty());
+    }
+}
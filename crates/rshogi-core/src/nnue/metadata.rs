@@ -0,0 +1,272 @@
+//! 評価関数ファイルに埋め込む学習メタデータ
+//!
+//! ヘッダーのアーキテクチャ文字列（arch_str）の末尾に `,key=value` の形で
+//! 学習の来歴を足す。bullet-shogi が `fv_scale=N` などを足しているのと同じ置き場所で、
+//! 重みのレイアウトは変わらない（arch_len が伸びるだけ）ので既存のローダーでそのまま読める。
+//!
+//! | キー | 内容 |
+//! |------|------|
+//! | `run` | 学習ランの ID |
+//! | `dataset` | 学習データのマニフェストのハッシュ |
+//! | `epochs` | 学習したエポック数 |
+//! | `val_loss` | 検証損失 |
+//! | `commit` | 学習に使ったコードの git コミット |
+//!
+//! 特徴量セットは arch_str 先頭の `Features=...` から読む（埋め込みはしない）。
+//! アーキテクチャの判定は arch_str の部分文字列で行うため、値に使える文字を絞り、
+//! 埋め込みで判定結果が変わる値は [`embed_net_metadata`] がエラーにする。
+
+use std::io::{self, Read};
+
+use super::activation::detect_activation_from_arch;
+use super::constants::{
+    MAX_ARCH_LEN, NNUE_VERSION, NNUE_VERSION_HALFKA, NNUE_VERSION_LAYERSTACK_NUM_BUCKETS,
+};
+use super::spec::{parse_arch_dimensions, parse_feature_set_from_arch};
+
+/// メタデータのキー（arch_str に書く順）
+const KEYS: [&str; 5] = ["run", "dataset", "epochs", "val_loss", "commit"];
+
+/// 評価関数ファイルの学習メタデータ
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NetMetadata {
+    /// 特徴量セット（arch_str の `Features=` の値。読み込み時のみ）
+    pub features: Option<String>,
+    /// 学習ランの ID
+    pub run: Option<String>,
+    /// 学習データのマニフェストのハッシュ
+    pub dataset: Option<String>,
+    /// 学習したエポック数
+    pub epochs: Option<u32>,
+    /// 検証損失
+    pub val_loss: Option<f64>,
+    /// 学習に使ったコードの git コミット
+    pub commit: Option<String>,
+}
+
+impl NetMetadata {
+    /// arch_str からメタデータを取り出す（無いキーは `None`）
+    pub fn parse(arch_str: &str) -> Self {
+        let mut meta = Self::default();
+        for part in arch_str.split(',') {
+            let Some((key, value)) = part.split_once('=') else {
+                continue;
+            };
+            let value = value.trim();
+            match key.trim() {
+                "Features" => {
+                    // "HalfKaHmMerged^[73305->256x2]-SCReLU" のうち特徴量名の部分
+                    let name = value.split(['[', '-', '(']).next().unwrap_or(value);
+                    meta.features = Some(name.trim_end_matches('^').to_string());
+                }
+                "run" => meta.run = Some(value.to_string()),
+                "dataset" => meta.dataset = Some(value.to_string()),
+                "epochs" => meta.epochs = value.parse().ok(),
+                "val_loss" => meta.val_loss = value.parse().ok(),
+                "commit" => meta.commit = Some(value.to_string()),
+                _ => {}
+            }
+        }
+        meta
+    }
+
+    /// 学習の来歴（`features` 以外）が 1 つも無いか
+    pub fn is_empty(&self) -> bool {
+        self.run.is_none()
+            && self.dataset.is_none()
+            && self.epochs.is_none()
+            && self.val_loss.is_none()
+            && self.commit.is_none()
+    }
+
+    /// `info string eval ...` に続ける `key=value` の並び（空白区切り）
+    pub fn summary(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(features) = &self.features {
+            parts.push(format!("features={features}"));
+        }
+        parts.extend(self.fields().into_iter().map(|(key, value)| format!("{key}={value}")));
+        parts.join(" ")
+    }
+
+    /// arch_str に書くキーと値（[`KEYS`] の順）
+    fn fields(&self) -> Vec<(&'static str, String)> {
+        let values = [
+            self.run.clone(),
+            self.dataset.clone(),
+            self.epochs.map(|v| v.to_string()),
+            self.val_loss.map(|v| v.to_string()),
+            self.commit.clone(),
+        ];
+        KEYS.iter()
+            .zip(values)
+            .filter_map(|(key, value)| Some((*key, value?)))
+            .collect()
+    }
+}
+
+/// 評価関数ファイルのヘッダーから arch_str を読む
+pub fn read_arch_string<R: Read>(reader: &mut R) -> io::Result<String> {
+    let mut buf4 = [0u8; 4];
+    reader.read_exact(&mut buf4)?;
+    let version = u32::from_le_bytes(buf4);
+    if !matches!(
+        version,
+        NNUE_VERSION | NNUE_VERSION_HALFKA | NNUE_VERSION_LAYERSTACK_NUM_BUCKETS
+    ) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Unknown NNUE version: {version:#x}"),
+        ));
+    }
+    reader.read_exact(&mut buf4)?; // ネットワークハッシュ
+    reader.read_exact(&mut buf4)?;
+    let arch_len = u32::from_le_bytes(buf4) as usize;
+    if arch_len == 0 || arch_len > MAX_ARCH_LEN {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Invalid arch string length: {arch_len}"),
+        ));
+    }
+    let mut arch = vec![0u8; arch_len];
+    reader.read_exact(&mut arch)?;
+    Ok(String::from_utf8_lossy(&arch).into_owned())
+}
+
+/// 評価関数ファイルのヘッダーからメタデータを読む
+pub fn read_net_metadata<R: Read>(reader: &mut R) -> io::Result<NetMetadata> {
+    read_arch_string(reader).map(|arch| NetMetadata::parse(&arch))
+}
+
+/// 評価関数ファイル（全体のバイト列）の arch_str にメタデータを埋め込んだものを返す
+///
+/// 既に埋め込まれているキーは置き換える。値に使えるのは英数字と `._:/+-` のみで、
+/// 埋め込みでアーキテクチャの判定（特徴量セット・層の次元・活性化関数）が
+/// 変わる場合もエラーにする。
+pub fn embed_net_metadata(bytes: &[u8], meta: &NetMetadata) -> io::Result<Vec<u8>> {
+    let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidInput, msg);
+    let arch = read_arch_string(&mut &bytes[..])?;
+    // read_arch_string が読めた時点でヘッダーと arch_str はそろっている
+    let old_len = u32::from_le_bytes([bytes[8], bytes[9], bytes[10], bytes[11]]) as usize;
+
+    let mut parts: Vec<&str> = arch
+        .split(',')
+        .filter(|part| part.split_once('=').is_none_or(|(key, _)| !KEYS.contains(&key.trim())))
+        .collect();
+    let fields = meta.fields();
+    let mut extra = Vec::with_capacity(fields.len());
+    for (key, value) in &fields {
+        let allowed = |c: char| c.is_ascii_alphanumeric() || "._:/+-".contains(c);
+        if value.is_empty() || !value.chars().all(allowed) {
+            return Err(invalid(format!("metadata {key} has unsupported characters: {value:?}")));
+        }
+        extra.push(format!("{key}={value}"));
+    }
+    parts.extend(extra.iter().map(String::as_str));
+    let new_arch = parts.join(",");
+    if new_arch.len() > MAX_ARCH_LEN {
+        return Err(invalid(format!("arch string too long: {} bytes", new_arch.len())));
+    }
+
+    let detect = |s: &str| {
+        (
+            parse_feature_set_from_arch(s).ok(),
+            parse_arch_dimensions(s),
+            detect_activation_from_arch(s),
+        )
+    };
+    if detect(&arch) != detect(&new_arch) {
+        return Err(invalid(format!(
+            "metadata changes how the architecture is detected: {new_arch}"
+        )));
+    }
+
+    let mut out = Vec::with_capacity(bytes.len() - old_len + new_arch.len());
+    out.extend_from_slice(&bytes[..8]);
+    out.extend_from_slice(&(new_arch.len() as u32).to_le_bytes());
+    out.extend_from_slice(new_arch.as_bytes());
+    out.extend_from_slice(&bytes[12 + old_len..]);
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ARCH: &str =
+        "Features=HalfKaHmMerged^[73305->256x2]-SCReLU,fv_scale=13,qa=127,qb=64,scale=600";
+
+    fn net_bytes(arch: &str) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&NNUE_VERSION_HALFKA.to_le_bytes());
+        bytes.extend_from_slice(&0x1234_5678u32.to_le_bytes());
+        bytes.extend_from_slice(&(arch.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(arch.as_bytes());
+        bytes.extend_from_slice(&[1, 2, 3, 4, 5]);
+        bytes
+    }
+
+    fn sample() -> NetMetadata {
+        NetMetadata {
+            run: Some("bullet-42".to_string()),
+            dataset: Some("sha256:0f3a9c".to_string()),
+            epochs: Some(400),
+            val_loss: Some(0.0123),
+            commit: Some("a1b2c3d".to_string()),
+            ..NetMetadata::default()
+        }
+    }
+
+    #[test]
+    fn embed_round_trips_and_keeps_weights() {
+        let bytes = net_bytes(ARCH);
+        let embedded = embed_net_metadata(&bytes, &sample()).unwrap();
+        assert_eq!(&embedded[4..8], &bytes[4..8]);
+        assert_eq!(&embedded[embedded.len() - 5..], &[1, 2, 3, 4, 5]);
+
+        let arch = read_arch_string(&mut &embedded[..]).unwrap();
+        assert!(arch.starts_with(ARCH), "{arch}");
+        let meta = read_net_metadata(&mut &embedded[..]).unwrap();
+        assert_eq!(meta.features.as_deref(), Some("HalfKaHmMerged"));
+        assert_eq!(
+            NetMetadata {
+                features: None,
+                ..meta.clone()
+            },
+            sample()
+        );
+        assert_eq!(
+            meta.summary(),
+            "features=HalfKaHmMerged run=bullet-42 dataset=sha256:0f3a9c epochs=400 \
+             val_loss=0.0123 commit=a1b2c3d"
+        );
+
+        // 埋め込み直すとキーは置き換わる
+        let again = NetMetadata {
+            epochs: Some(500),
+            ..NetMetadata::default()
+        };
+        let arch =
+            read_arch_string(&mut &embed_net_metadata(&embedded, &again).unwrap()[..]).unwrap();
+        assert_eq!(arch, format!("{ARCH},epochs=500"));
+    }
+
+    #[test]
+    fn plain_arch_has_no_metadata() {
+        let meta = read_net_metadata(&mut &net_bytes(ARCH)[..]).unwrap();
+        assert!(meta.is_empty());
+        assert_eq!(meta.summary(), "features=HalfKaHmMerged");
+    }
+
+    #[test]
+    fn embed_rejects_values_that_confuse_detection() {
+        let bytes = net_bytes(ARCH);
+        let with_run = |run: &str| NetMetadata {
+            run: Some(run.to_string()),
+            ..NetMetadata::default()
+        };
+        assert!(embed_net_metadata(&bytes, &with_run("a,b")).is_err());
+        assert!(embed_net_metadata(&bytes, &with_run("")).is_err());
+        assert!(embed_net_metadata(&bytes, &with_run("LayerStacks-test")).is_err());
+    }
+}
@@ -16,11 +16,12 @@
 //! - L3: 隠れ層2出力次元（32, 96）
 //! - A: 活性化関数（CReLU, SCReLU, PairwiseCReLU）
 //!
-//! - ネットワーク構造の読み込み（`Network::load` / `init_nnue`）
+//! - ネットワーク構造の読み込み（`Network::load` / `init_nnue`）と名前付きネットワークのレジストリ（`load_net` / `acquire_net`）
 //! - 入力特徴量（HalfKP: 自玉×駒配置）の計算と変換（`BonaPiece` / `FeatureTransformer`）
 //! - Accumulator による差分更新可能な中間表現の保持（`diff::get_changed_features` を用いた増分更新 + フォールバック全計算）
 //! - AffineTransform + ClippedReLU による 512→32→32→1 の多層パーセプトロン
 //! - NNUE 未初期化時のフォールバック駒得評価
+//! - 評価関数ファイルの arch_str に埋め込む学習メタデータ（`NetMetadata` / `embed_net_metadata`）
 
 mod accumulator;
 mod accumulator_layer_stacks;
@@ -47,6 +48,7 @@ mod layer_stacks;
 mod layers;
 mod leb128;
 mod ls_feature_spec;
+mod metadata;
 #[macro_use]
 pub mod macros;
 mod network;
@@ -58,6 +60,7 @@ pub(crate) mod network_halfkp;
 mod network_layer_stacks;
 pub mod piece_list;
 pub mod prelude;
+mod registry;
 mod shared_weights;
 pub mod spec;
 pub mod stats;
@@ -94,9 +97,10 @@ pub use ls_feature_spec::{
     HalfKaHmMergedSpec, HalfKaHmSplitSpec, HalfKaMergedSpec, HalfKaSplitSpec, HalfKpSpec,
     LsFeatureSpec,
 };
+pub use metadata::{NetMetadata, embed_net_metadata, read_arch_string, read_net_metadata};
 #[cfg(feature = "layerstack-arch")]
 pub use network::evaluate_layer_stacks;
-#[cfg(feature = "layerstack-arch")]
+#[cfg(all(feature = "layerstack-arch", feature = "search"))]
 pub(crate) use network::update_and_evaluate_layer_stacks_cached;
 pub use network::{
     LayerStackBucketMode, NNUENetwork, NnueFormatInfo, SHOGI_PROGRESS_KP_ABS_NUM_WEIGHTS,
@@ -140,6 +144,10 @@ pub use spec::{Activation, ArchitectureSpec, FeatureSet as SpecFeatureSet};
 // Phase 2: 外部 API 統一
 pub use evaluator::NNUEEvaluator;
 pub use network::clear_nnue;
+pub use registry::{
+    DEFAULT_NET_ID, acquire_net, load_net, load_net_from_bytes, net_ids, net_ref_count,
+    register_net, set_default_net, unload_net,
+};
 
 // 統計カウンタ（デバッグ・チューニング用）
 pub use stats::{NnueStatsSnapshot, get_nnue_stats, print_nnue_stats, reset_nnue_stats};
@@ -36,6 +36,7 @@ use super::halfka_split::{HalfKaSplitNetwork, HalfKaSplitStack};
 use super::halfkp::{HalfKPNetwork, HalfKPStack};
 #[cfg(feature = "layerstack-arch")]
 use super::network_layer_stacks::LayerStacksNetwork;
+use super::registry::{self, DEFAULT_NET_ID};
 use super::spec::{Activation, FeatureSet};
 #[cfg(feature = "halfkx-arch")]
 use super::stats::{count_already_computed, count_refresh, count_update};
@@ -46,17 +47,8 @@ use std::cell::Cell;
 use std::fs::File;
 use std::io::{self, BufReader, Cursor, Read, Seek, SeekFrom};
 use std::path::Path;
-use std::sync::atomic::{AtomicBool, AtomicI32, AtomicPtr, Ordering};
-use std::sync::{Arc, LazyLock, OnceLock, RwLock};
-
-/// グローバルなNNUEネットワーク（HalfKP/HalfKaSplit/HalfKaHmMerged^）
-static NETWORK: LazyLock<RwLock<Option<Arc<NNUENetwork>>>> = LazyLock::new(|| RwLock::new(None));
-
-/// `is_nnue_initialized()` の高速パス用 AtomicBool キャッシュ
-///
-/// NNUE ロード時に true、クリア時に false に設定する。
-/// `should_update_board_effects()` 等のホットパスから RwLock::read を回避するため。
-static NNUE_INITIALIZED: AtomicBool = AtomicBool::new(false);
+use std::sync::atomic::{AtomicI32, AtomicPtr, Ordering};
+use std::sync::{Arc, OnceLock};
 
 /// FV_SCALE のグローバルオーバーライド設定
 ///
@@ -1036,37 +1028,33 @@ pub fn progress_sum_to_bucket(sum: f32, num_buckets: usize) -> usize {
 }
 
 /// NNUEを初期化（バージョン自動判別）
+///
+/// レジストリの [`DEFAULT_NET_ID`] に登録する（探索はこのネットワークを使う）。
 pub fn init_nnue<P: AsRef<Path>>(path: P) -> io::Result<()> {
-    let network = Arc::new(NNUENetwork::load(path)?);
-    *NETWORK.write().expect("NNUE lock poisoned") = Some(network);
-    NNUE_INITIALIZED.store(true, Ordering::Release);
-    Ok(())
+    registry::load_net(DEFAULT_NET_ID, path).map(|_| ())
 }
 
 /// バイト列からNNUEを初期化（バージョン自動判別）
+///
+/// レジストリの [`DEFAULT_NET_ID`] に登録する（探索はこのネットワークを使う）。
 pub fn init_nnue_from_bytes(bytes: &[u8]) -> io::Result<()> {
-    let network = Arc::new(NNUENetwork::from_bytes(bytes)?);
-    *NETWORK.write().expect("NNUE lock poisoned") = Some(network);
-    NNUE_INITIALIZED.store(true, Ordering::Release);
-    Ok(())
+    registry::load_net_from_bytes(DEFAULT_NET_ID, bytes).map(|_| ())
 }
 
-/// グローバル NNUE をクリアする
+/// 探索用の NNUE（[`DEFAULT_NET_ID`]）の登録を外す
+///
+/// 他の ID で登録したネットワークはそのまま残る。
 pub fn clear_nnue() {
-    // Safety: false を先に書いてから NETWORK をクリアすること。
-    // 逆順にすると is_nnue_initialized() == true の直後に get_network() が None を返す
-    // 短い窓が生じる。false-negative（ロード済みなのに false に見える瞬間）は安全。
-    NNUE_INITIALIZED.store(false, Ordering::Release);
-    *NETWORK.write().expect("NNUE lock poisoned") = None;
+    registry::unload_net(DEFAULT_NET_ID);
 }
 
 /// NNUEが初期化済みかどうか
 ///
 /// AtomicBool キャッシュにより RwLock::read を回避する。
-/// `init_nnue()` / `clear_nnue()` で更新される。
+/// [`DEFAULT_NET_ID`] の登録・解除で更新される。
 #[inline]
 pub fn is_nnue_initialized() -> bool {
-    NNUE_INITIALIZED.load(Ordering::Acquire)
+    registry::NNUE_INITIALIZED.load(Ordering::Acquire)
 }
 
 // =============================================================================
@@ -1241,11 +1229,11 @@ pub fn detect_format(bytes: &[u8], file_size: u64) -> io::Result<NnueFormatInfo>
     }
 }
 
-/// NNUEネットワークへの参照を取得（初期化されていない場合はNone）
+/// 探索用の NNUE（[`DEFAULT_NET_ID`]）への参照を取得（初期化されていない場合はNone）
 ///
 /// AccumulatorStackVariant の初期化・更新に使用。
 pub fn get_network() -> Option<Arc<NNUENetwork>> {
-    NETWORK.read().expect("NNUE lock poisoned").clone()
+    registry::acquire_net(DEFAULT_NET_ID)
 }
 
 // =============================================================================
@@ -0,0 +1,147 @@
+//! 名前付き NNUE ネットワークのレジストリ
+//!
+//! ネットワークを任意の ID で登録し、複数のネットワークを同時に保持する
+//! （評価値の比較・アンサンブル用）。探索が使うのは [`DEFAULT_NET_ID`] のネットワークで、
+//! [`init_nnue`](super::init_nnue) / [`get_network`](super::get_network) /
+//! [`clear_nnue`](super::clear_nnue) はこの ID に対する操作の略記である。
+//!
+//! - 読み込み（ファイルの読み出しとパース）はロックの外で行うため、別々の ID への
+//!   読み込みは並行して進む。登録時だけ書き込みロックを取る。
+//! - 取得したネットワークは `Arc` で参照カウントされる。[`unload_net`] は登録を外すだけで、
+//!   取得済みの参照（探索中のスレッドなど）が残っていればそれが全て drop されるまで
+//!   メモリは解放されない。
+
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, LazyLock, RwLock};
+
+use super::network::NNUENetwork;
+
+/// 探索が使うネットワークの ID
+pub const DEFAULT_NET_ID: &str = "default";
+
+/// ID → ネットワーク
+static REGISTRY: LazyLock<RwLock<HashMap<String, Arc<NNUENetwork>>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+/// [`DEFAULT_NET_ID`] が登録済みか（`is_nnue_initialized()` の高速パス用キャッシュ）
+///
+/// `should_update_board_effects()` 等のホットパスから RwLock::read を回避するため。
+pub(super) static NNUE_INITIALIZED: AtomicBool = AtomicBool::new(false);
+
+/// ファイルからネットワークを読み込んで `id` で登録する（同じ ID の登録は置き換える）
+pub fn load_net<P: AsRef<Path>>(id: &str, path: P) -> io::Result<Arc<NNUENetwork>> {
+    let network = Arc::new(NNUENetwork::load(path)?);
+    register_net(id, Arc::clone(&network));
+    Ok(network)
+}
+
+/// バイト列からネットワークを読み込んで `id` で登録する（同じ ID の登録は置き換える）
+pub fn load_net_from_bytes(id: &str, bytes: &[u8]) -> io::Result<Arc<NNUENetwork>> {
+    let network = Arc::new(NNUENetwork::from_bytes(bytes)?);
+    register_net(id, Arc::clone(&network));
+    Ok(network)
+}
+
+/// 読み込み済みのネットワークを `id` で登録する
+///
+/// 同じ ID の登録があれば置き換え、元のネットワークを返す。
+pub fn register_net(id: &str, network: Arc<NNUENetwork>) -> Option<Arc<NNUENetwork>> {
+    let previous = REGISTRY
+        .write()
+        .expect("NNUE registry lock poisoned")
+        .insert(id.to_string(), network);
+    if id == DEFAULT_NET_ID {
+        NNUE_INITIALIZED.store(true, Ordering::Release);
+    }
+    previous
+}
+
+/// `id` のネットワークを取得する（未登録なら `None`）
+pub fn acquire_net(id: &str) -> Option<Arc<NNUENetwork>> {
+    REGISTRY.read().expect("NNUE registry lock poisoned").get(id).cloned()
+}
+
+/// `id` の登録を外す（登録がなければ `false`）
+///
+/// 取得済みの参照は有効なまま残る。
+pub fn unload_net(id: &str) -> bool {
+    if id == DEFAULT_NET_ID {
+        // Safety: false を先に書いてから登録を外すこと。
+        // 逆順にすると is_nnue_initialized() == true の直後に get_network() が None を返す
+        // 短い窓が生じる。false-negative（ロード済みなのに false に見える瞬間）は安全。
+        NNUE_INITIALIZED.store(false, Ordering::Release);
+    }
+    REGISTRY.write().expect("NNUE registry lock poisoned").remove(id).is_some()
+}
+
+/// 登録済みの `id` のネットワークを探索用（[`DEFAULT_NET_ID`]）にする
+///
+/// `id` が未登録なら何もせず `false` を返す。
+pub fn set_default_net(id: &str) -> bool {
+    let Some(network) = acquire_net(id) else {
+        return false;
+    };
+    register_net(DEFAULT_NET_ID, network);
+    true
+}
+
+/// 登録済みの ID（昇順）
+pub fn net_ids() -> Vec<String> {
+    let mut ids: Vec<_> =
+        REGISTRY.read().expect("NNUE registry lock poisoned").keys().cloned().collect();
+    ids.sort();
+    ids
+}
+
+/// `id` のネットワークを参照している数（未登録なら `None`）
+///
+/// レジストリ自身の `id` の分は数えない。[`set_default_net`] などで同じネットワークを
+/// 別の ID にも登録していれば、その分は数える。
+pub fn net_ref_count(id: &str) -> Option<usize> {
+    let registry = REGISTRY.read().expect("NNUE registry lock poisoned");
+    registry.get(id).map(|network| Arc::strong_count(network) - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failed_load_does_not_register() {
+        let id = "registry-test-broken";
+        assert!(load_net_from_bytes(id, b"not a network").is_err());
+        assert!(acquire_net(id).is_none());
+        assert!(!net_ids().iter().any(|x| x == id));
+        assert!(!unload_net(id));
+        assert!(!set_default_net(id));
+    }
+
+    #[test]
+    fn acquired_net_outlives_unload() {
+        let path =
+            std::env::var("NNUE_TEST_FILE").unwrap_or_else(|_| "/path/to/your/nn.bin".to_string());
+        let a = match load_net("registry-test-a", &path) {
+            Ok(net) => net,
+            Err(e) => {
+                eprintln!("Skipping test: {e}");
+                return;
+            }
+        };
+        let b = load_net("registry-test-b", &path).unwrap();
+        assert!(!Arc::ptr_eq(&a, &b));
+        assert_eq!(net_ref_count("registry-test-a"), Some(1));
+
+        let held = acquire_net("registry-test-a").unwrap();
+        assert_eq!(net_ref_count("registry-test-a"), Some(2));
+        drop(a);
+        assert!(unload_net("registry-test-a"));
+        assert_eq!(net_ref_count("registry-test-a"), None);
+        // 登録を外しても取得済みの参照は有効
+        assert_eq!(Arc::strong_count(&held), 1);
+        assert!(acquire_net("registry-test-b").is_some());
+        assert!(unload_net("registry-test-b"));
+    }
+}
@@ -0,0 +1,170 @@
+//! 千日手判定用の圧縮履歴（RepetitionHistory）
+//!
+//! 長い対局（500 手超）では `StateInfo`（1 つ 336B）の履歴が大きくなり、探索スレッドへ
+//! 局面を渡すたびに全履歴を複製するコストが無視できない。千日手判定が遡るのは直近
+//! [`REPETITION_WINDOW`] 手だけなので、`Position::clone_with_history` は現在の
+//! `StateInfo` だけを複製し、それより前の局面は判定に必要な情報だけをこの履歴に圧縮する。
+//!
+//! 履歴は作成後に変更せず、`Arc` で複製間（`Position::clone` など）で共有する。
+
+use super::state::StateInfo;
+use crate::types::{Color, Hand, RepetitionState};
+
+/// 千日手判定で遡る最大手数
+pub(crate) const REPETITION_WINDOW: usize = 16;
+
+/// 千日手判定に使う過去の局面の情報
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct HistoryEntry {
+    /// 盤面ハッシュ（手番込み）
+    pub(crate) board_key: u64,
+    /// 手駒
+    pub(crate) hand_snapshot: [Hand; Color::NUM],
+    /// 千日手繰り返し回数
+    pub(crate) repetition_times: i32,
+    /// 千日手種別
+    pub(crate) repetition_type: RepetitionState,
+}
+
+impl HistoryEntry {
+    pub(crate) const EMPTY: HistoryEntry = HistoryEntry {
+        board_key: 0,
+        hand_snapshot: [Hand::EMPTY; Color::NUM],
+        repetition_times: 0,
+        repetition_type: RepetitionState::None,
+    };
+
+    #[inline]
+    pub(crate) fn from_state(st: &StateInfo) -> Self {
+        HistoryEntry {
+            board_key: st.board_key,
+            hand_snapshot: st.hand_snapshot,
+            repetition_times: st.repetition_times,
+            repetition_type: st.repetition_type,
+        }
+    }
+
+    /// 拡張 SFEN（`Position::to_sfen_ext`）の履歴の 1 局面分
+    ///
+    /// `<盤面ハッシュ>.<先手の手駒>.<後手の手駒>.<繰り返し回数><千日手種別>`。ハッシュと手駒は
+    /// 16 進、千日手種別は 1 文字（[`repetition_to_char`]）。
+    pub(crate) fn to_digest(self) -> String {
+        format!(
+            "{:x}.{:x}.{:x}.{}{}",
+            self.board_key,
+            self.hand_snapshot[Color::Black.index()].raw(),
+            self.hand_snapshot[Color::White.index()].raw(),
+            self.repetition_times,
+            repetition_to_char(self.repetition_type)
+        )
+    }
+
+    /// [`to_digest`](Self::to_digest) の逆変換（形式が不正なら `None`）
+    pub(crate) fn from_digest(s: &str) -> Option<Self> {
+        let mut fields = s.split('.');
+        let board_key = u64::from_str_radix(fields.next()?, 16).ok()?;
+        let black = u32::from_str_radix(fields.next()?, 16).ok()?;
+        let white = u32::from_str_radix(fields.next()?, 16).ok()?;
+        let repetition = fields.next()?;
+        if fields.next().is_some() || repetition.len() < 2 {
+            return None;
+        }
+        let (times, kind) = repetition.split_at(repetition.len() - 1);
+        Some(HistoryEntry {
+            board_key,
+            hand_snapshot: [Hand::from_raw(black), Hand::from_raw(white)],
+            repetition_times: times.parse().ok()?,
+            repetition_type: repetition_from_char(kind.chars().next()?)?,
+        })
+    }
+}
+
+/// 千日手種別の 1 文字表現
+fn repetition_to_char(state: RepetitionState) -> char {
+    match state {
+        RepetitionState::None => 'n',
+        RepetitionState::Draw => 'd',
+        RepetitionState::Win => 'w',
+        RepetitionState::Lose => 'l',
+        RepetitionState::Superior => 's',
+        RepetitionState::Inferior => 'i',
+    }
+}
+
+fn repetition_from_char(c: char) -> Option<RepetitionState> {
+    Some(match c {
+        'n' => RepetitionState::None,
+        'd' => RepetitionState::Draw,
+        'w' => RepetitionState::Win,
+        'l' => RepetitionState::Lose,
+        's' => RepetitionState::Superior,
+        'i' => RepetitionState::Inferior,
+        _ => return None,
+    })
+}
+
+/// 状態スタックの根より前の局面（根に近い順、最大 [`REPETITION_WINDOW`] 局面）
+#[derive(Debug, Clone)]
+pub(crate) struct RepetitionHistory {
+    entries: [HistoryEntry; REPETITION_WINDOW],
+    len: usize,
+}
+
+impl RepetitionHistory {
+    /// 根に近い順に並んだ局面から作る（[`REPETITION_WINDOW`] を超えた分は捨てる）
+    pub(crate) fn new(entries: impl IntoIterator<Item = HistoryEntry>) -> Self {
+        let mut history = RepetitionHistory {
+            entries: [HistoryEntry::EMPTY; REPETITION_WINDOW],
+            len: 0,
+        };
+        for (slot, entry) in history.entries.iter_mut().zip(entries) {
+            *slot = entry;
+            history.len += 1;
+        }
+        history
+    }
+
+    #[inline]
+    pub(crate) fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// 根から `back` 手前（1 始まり）の局面
+    #[inline]
+    pub(crate) fn get(&self, back: usize) -> Option<&HistoryEntry> {
+        back.checked_sub(1).and_then(|i| self.entries[..self.len].get(i))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_history_keeps_latest_window() {
+        let entry = |key| HistoryEntry {
+            board_key: key,
+            ..HistoryEntry::EMPTY
+        };
+        let history = RepetitionHistory::new((1..=20).map(entry));
+        assert_eq!(history.get(0), None);
+        assert_eq!(history.get(1).map(|e| e.board_key), Some(1));
+        assert_eq!(history.get(REPETITION_WINDOW).map(|e| e.board_key), Some(16));
+        assert_eq!(history.get(REPETITION_WINDOW + 1), None);
+    }
+
+    #[test]
+    fn test_entry_digest_round_trip() {
+        let entry = HistoryEntry {
+            board_key: 0x0123_4567_89ab_cdef,
+            hand_snapshot: [Hand::from_raw(0x11), Hand::EMPTY],
+            repetition_times: 2,
+            repetition_type: RepetitionState::Lose,
+        };
+        let digest = entry.to_digest();
+        assert_eq!(digest, "123456789abcdef.11.0.2l");
+        assert_eq!(HistoryEntry::from_digest(&digest), Some(entry));
+        assert_eq!(HistoryEntry::from_digest("123.0.0.2x"), None);
+        assert_eq!(HistoryEntry::from_digest("123.0.0"), None);
+    }
+}
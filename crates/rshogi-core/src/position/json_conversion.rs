@@ -1,8 +1,12 @@
 use crate::eval::material::compute_material_value;
-use crate::movegen::{MoveList, generate_legal_with_pass};
+use crate::movegen::{MoveList, generate_legal_all_with_pass, generate_legal_with_pass};
+#[cfg(feature = "record")]
+use crate::record::kif;
 use crate::types::json::{
-    BoardStateJson, CellJson, HandJson, HandsJson, PieceJson, ReplayResultJson,
+    AnnotatedMoveJson, BoardStateJson, CellJson, HandJson, HandsJson, PieceJson, ReplayResultJson,
 };
+#[cfg(feature = "record")]
+use crate::types::json::{PositionDescriptionJson, SideDescriptionJson};
 use crate::types::{Color, File, Hand, Move, Piece, PieceType, Rank, Square};
 
 use super::{Position, SFEN_HIRATE};
@@ -111,6 +115,85 @@ impl Position {
         Ok(pos.to_board_state_json())
     }
 
+    /// 合法手の一覧を、取る・王手・成りの選択・駒打ちの情報付きで返す。
+    ///
+    /// 不成も含めた全合法手（パス権が有効ならパスも）を対象にする。`promotion` は
+    /// 同じ移動元・移動先の成り／不成がどちらも合法なら "optional"、成りのみなら
+    /// "forced"、成れなければ "none"。
+    pub fn annotated_legal_moves(&self) -> Vec<AnnotatedMoveJson> {
+        let mut list = MoveList::new();
+        generate_legal_all_with_pass(self, &mut list);
+        let moves: Vec<Move> = list.iter().copied().collect();
+
+        moves
+            .iter()
+            .map(|&mv| {
+                if mv.is_pass() {
+                    return AnnotatedMoveJson {
+                        usi: mv.to_usi(),
+                        from: None,
+                        to: None,
+                        drop: false,
+                        capture: false,
+                        check: self.gives_check(mv),
+                        promote: false,
+                        promotion: "none".to_string(),
+                    };
+                }
+
+                let to = mv.to();
+                let (from, capture, promotion) = if mv.is_drop() {
+                    (None, false, "none")
+                } else {
+                    let from = mv.from();
+                    let same_path = |m: &&Move| {
+                        !m.is_pass() && !m.is_drop() && m.from() == from && m.to() == to
+                    };
+                    let has_promote = moves.iter().filter(same_path).any(|m| m.is_promote());
+                    let has_non_promote = moves.iter().filter(same_path).any(|m| !m.is_promote());
+                    let promotion = match (has_promote, has_non_promote) {
+                        (true, true) => "optional",
+                        (true, false) => "forced",
+                        _ => "none",
+                    };
+                    (Some(from.to_usi()), !self.piece_on(to).is_none(), promotion)
+                };
+
+                AnnotatedMoveJson {
+                    usi: mv.to_usi(),
+                    from,
+                    to: Some(to.to_usi()),
+                    drop: mv.is_drop(),
+                    capture,
+                    check: self.gives_check(mv),
+                    promote: mv.is_promote(),
+                    promotion: promotion.to_string(),
+                }
+            })
+            .collect()
+    }
+
+    /// SFENをパースし、情報付きの合法手一覧を返す（`annotated_legal_moves` 参照）。
+    ///
+    /// # Arguments
+    /// * `sfen` - 局面のSFEN（"startpos" 可）
+    /// * `pass_rights` - パス権（先手, 後手）。指定時はパスも合法手に含める
+    pub fn annotated_legal_moves_from_sfen(
+        sfen: &str,
+        pass_rights: Option<(u8, u8)>,
+    ) -> Result<Vec<AnnotatedMoveJson>, String> {
+        let mut pos = Position::new();
+        if sfen.trim() == "startpos" {
+            pos.set_sfen(SFEN_HIRATE).map_err(|e| e.to_string())?;
+        } else {
+            pos.set_sfen(sfen).map_err(|e| e.to_string())?;
+        }
+        if let Some((black, white)) = pass_rights {
+            pos.enable_pass_rights(black, white);
+        }
+        Ok(pos.annotated_legal_moves())
+    }
+
     /// 棋譜を厳密に適用し、不正手で停止する。
     ///
     /// # Arguments
@@ -168,6 +251,134 @@ impl Position {
             error,
         })
     }
+
+    /// 局面を読み上げ向けの日本語で表す（盤上の駒・持ち駒・直前の手・王手の有無）。
+    ///
+    /// 指し手の表記は KIF 棋譜の出力と同じ。`moves` は [`Self::replay_moves_strict`] と
+    /// 異なり、不正手があればエラーにする。
+    ///
+    /// # Arguments
+    /// * `sfen` - 開始局面のSFEN（"startpos" 可）
+    /// * `moves` - 開始局面から適用する棋譜（USI形式。直前の手の表記に使う）
+    /// * `pass_rights` - パス権の初期値（先手, 後手）
+    #[cfg(feature = "record")]
+    pub fn describe_position(
+        sfen: &str,
+        moves: &[String],
+        pass_rights: Option<(u8, u8)>,
+    ) -> Result<PositionDescriptionJson, String> {
+        let mut pos = Position::new();
+        if sfen.trim() == "startpos" {
+            pos.set_sfen(SFEN_HIRATE).map_err(|e| e.to_string())?;
+        } else {
+            pos.set_sfen(sfen).map_err(|e| e.to_string())?;
+        }
+        if let Some((black, white)) = pass_rights {
+            pos.enable_pass_rights(black, white);
+        }
+
+        let mut last_move = None;
+        let mut last_to = None;
+        for usi in moves {
+            let parsed =
+                Move::from_usi(usi).ok_or_else(|| format!("failed to parse move: {usi}"))?;
+            let mut list = MoveList::new();
+            generate_legal_with_pass(&pos, &mut list);
+            let mv = list
+                .iter()
+                .copied()
+                .find(|candidate| candidate.raw() == parsed.raw())
+                .ok_or_else(|| format!("illegal move: {usi}"))?;
+
+            let text = if mv.is_pass() {
+                "パス".to_string()
+            } else {
+                kif::kif_move_text(&pos, mv, last_to)
+            };
+            last_move = Some(format!("{}{text}", color_mark(pos.side_to_move())));
+            last_to = (!mv.is_pass()).then(|| mv.to());
+
+            let gives_check = pos.gives_check(mv);
+            pos.do_move(mv, gives_check);
+        }
+
+        let mut list = MoveList::new();
+        generate_legal_with_pass(&pos, &mut list);
+        let in_check = pos.in_check();
+        let no_legal_moves = list.is_empty();
+
+        let turn = pos.side_to_move();
+        let mut summary = format!("{}番、{}手目。", color_name(turn), pos.game_ply());
+        match (in_check, no_legal_moves) {
+            (true, true) => summary.push_str("詰みです。"),
+            (true, false) => summary.push_str("王手されています。"),
+            (false, true) => summary.push_str("指せる手がありません。"),
+            (false, false) => {}
+        }
+        if let Some(last) = &last_move {
+            summary.push_str(&format!("直前の手は{last}。"));
+        }
+
+        Ok(PositionDescriptionJson {
+            turn: color_to_owner(turn).to_string(),
+            ply: pos.game_ply(),
+            in_check,
+            no_legal_moves,
+            last_move,
+            sente: pos.side_description(Color::Black),
+            gote: pos.side_description(Color::White),
+            summary,
+        })
+    }
+
+    /// 片方の対局者の盤上の駒と持ち駒の表記
+    #[cfg(feature = "record")]
+    fn side_description(&self, color: Color) -> SideDescriptionJson {
+        const ORDER: [PieceType; 14] = [
+            PieceType::King,
+            PieceType::Dragon,
+            PieceType::Rook,
+            PieceType::Horse,
+            PieceType::Bishop,
+            PieceType::Gold,
+            PieceType::ProSilver,
+            PieceType::Silver,
+            PieceType::ProKnight,
+            PieceType::Knight,
+            PieceType::ProLance,
+            PieceType::Lance,
+            PieceType::ProPawn,
+            PieceType::Pawn,
+        ];
+        let pieces = ORDER
+            .iter()
+            .flat_map(|&pt| {
+                self.pieces(color, pt)
+                    .iter()
+                    .map(move |sq| format!("{}{}", kif::square_text(sq), kif::move_piece_name(pt)))
+            })
+            .collect();
+        SideDescriptionJson {
+            pieces,
+            hand: kif::hand_items(self, color),
+        }
+    }
+}
+
+#[cfg(feature = "record")]
+fn color_mark(color: Color) -> &'static str {
+    match color {
+        Color::Black => "▲",
+        Color::White => "△",
+    }
+}
+
+#[cfg(feature = "record")]
+fn color_name(color: Color) -> &'static str {
+    match color {
+        Color::Black => "先手",
+        Color::White => "後手",
+    }
 }
 
 fn color_to_owner(color: Color) -> &'static str {
@@ -396,4 +607,82 @@ mod tests {
         assert!(result.error.is_some());
         assert!(result.error.unwrap().contains("illegal move"));
     }
+
+    #[test]
+    fn test_annotated_legal_moves_startpos() {
+        let moves = Position::annotated_legal_moves_from_sfen("startpos", None).unwrap();
+        assert_eq!(moves.len(), 30);
+        let pawn = moves.iter().find(|m| m.usi == "7g7f").unwrap();
+        assert_eq!(pawn.from.as_deref(), Some("7g"));
+        assert_eq!(pawn.to.as_deref(), Some("7f"));
+        assert!(!pawn.capture && !pawn.check && !pawn.drop && !pawn.promote);
+        assert_eq!(pawn.promotion, "none");
+    }
+
+    #[test]
+    fn test_annotated_legal_moves_flags() {
+        // 先手: 歩 2d（2c へ進むと成り選択）、桂 1d（1b へは成り必須、2b の金を取る）、
+        // 持ち駒の金打ちで王手できる
+        let sfen = "4k4/7g1/9/7PN/9/9/9/9/4K4 b G 1";
+        let moves = Position::annotated_legal_moves_from_sfen(sfen, None).unwrap();
+        let find = |usi: &str| moves.iter().find(|m| m.usi == usi).unwrap();
+
+        let pawn_promote = find("2d2c+");
+        assert!(pawn_promote.promote);
+        assert_eq!(pawn_promote.promotion, "optional");
+        assert_eq!(find("2d2c").promotion, "optional");
+
+        let knight_capture = find("1d2b+");
+        assert!(knight_capture.capture);
+        assert_eq!(knight_capture.promotion, "forced");
+        assert!(moves.iter().all(|m| m.usi != "1d2b"));
+
+        let drop_check = find("G*5b");
+        assert!(drop_check.drop && drop_check.check);
+        assert_eq!(drop_check.from, None);
+        assert_eq!(drop_check.to.as_deref(), Some("5b"));
+    }
+
+    #[cfg(feature = "record")]
+    #[test]
+    fn test_describe_position_after_exchange() {
+        let moves: Vec<String> =
+            ["7g7f", "3c3d", "8h2b+", "3a2b"].iter().map(|m| m.to_string()).collect();
+        let desc = Position::describe_position("startpos", &moves, None).unwrap();
+        assert_eq!(desc.turn, "sente");
+        assert_eq!(desc.ply, 5);
+        assert!(!desc.in_check && !desc.no_legal_moves);
+        assert_eq!(desc.last_move.as_deref(), Some("△同　銀(31)"));
+        assert_eq!(desc.sente.hand, vec!["角"]);
+        assert_eq!(desc.gote.hand, vec!["角"]);
+        assert_eq!(desc.sente.pieces.len(), 19);
+        assert_eq!(desc.sente.pieces[..2], ["５九玉", "２八飛"]);
+        assert!(desc.gote.pieces.contains(&"２二銀".to_string()));
+        assert_eq!(desc.summary, "先手番、5手目。直前の手は△同　銀(31)。");
+    }
+
+    #[cfg(feature = "record")]
+    #[test]
+    fn test_describe_position_mate() {
+        let moves = vec!["G*5b".to_string()];
+        let desc =
+            Position::describe_position("4k4/9/4P4/9/9/9/9/9/4K4 b G 1", &moves, None).unwrap();
+        assert_eq!(desc.turn, "gote");
+        assert!(desc.in_check && desc.no_legal_moves);
+        assert_eq!(desc.last_move.as_deref(), Some("▲５二金打"));
+        assert_eq!(desc.sente.pieces, vec!["５九玉", "５二金", "５三歩"]);
+        assert!(desc.sente.hand.is_empty());
+        assert_eq!(desc.summary, "後手番、2手目。詰みです。直前の手は▲５二金打。");
+
+        let illegal = vec!["5c5b".to_string(), "5a5b".to_string()];
+        assert!(Position::describe_position("startpos", &illegal, None).is_err());
+    }
+
+    #[test]
+    fn test_annotated_legal_moves_includes_pass() {
+        let moves = Position::annotated_legal_moves_from_sfen("startpos", Some((1, 1))).unwrap();
+        let pass = moves.iter().find(|m| m.usi == "pass").unwrap();
+        assert_eq!((pass.from.as_ref(), pass.to.as_ref()), (None, None));
+        assert_eq!(pass.promotion, "none");
+    }
 }
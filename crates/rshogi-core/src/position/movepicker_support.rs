@@ -281,6 +281,7 @@ impl Position {
     /// PawnHistory 用のインデックスを計算
     ///
     /// 歩の配置に基づくハッシュ値からインデックスを計算する。
+    #[cfg(feature = "search")]
     pub fn pawn_history_index(&self) -> usize {
         (self.pawn_key() as usize) & (crate::search::PAWN_HISTORY_SIZE - 1)
     }
@@ -469,6 +470,56 @@ impl Position {
         res != 0
     }
 
+    /// 静的駒交換評価値（SEE）を返す
+    ///
+    /// 探索の枝刈りで使う [`Position::see_ge`] と同じ交換手順の結果を、閾値の二分探索で
+    /// 求める。駒価値は SEE 用の値（歩=90 等）で、成りボーナスは考慮しない。
+    /// `m` は現局面で pseudo-legal であること。
+    pub fn see(&self, m: Move) -> Value {
+        if m.is_pass() {
+            return Value::ZERO;
+        }
+
+        // 結果は [取る駒 - 動かす駒, 取る駒] に収まる
+        // （相手は取り返さない選択ができ、自分は取り返された後に止める選択ができる）
+        let captured_value = if m.is_drop() {
+            0
+        } else {
+            let captured = self.piece_on(m.to());
+            if captured.is_some() {
+                see_piece_value(captured.piece_type())
+            } else {
+                0
+            }
+        };
+        let from_value = if m.is_drop() {
+            see_piece_value(m.drop_piece_type())
+        } else {
+            see_piece_value(self.piece_on(m.from()).piece_type())
+        };
+
+        // see_ge(m, lo) が真となる最大の lo を求める
+        let mut lo = captured_value - from_value;
+        let mut hi = captured_value;
+        while lo < hi {
+            let mid = lo + (hi - lo + 1) / 2;
+            if self.see_ge(m, Value::new(mid)) {
+                lo = mid;
+            } else {
+                hi = mid - 1;
+            }
+        }
+        Value::new(lo)
+    }
+
+    /// 指し手が合法で、駒交換の結果が `threshold` 以上になるかを判定
+    ///
+    /// [`Position::see_ge`] は pseudo-legal な手を前提とするが、こちらは任意の手を受け付け、
+    /// 非合法手には `false` を返す。ツールや GUI からの問い合わせ向け。
+    pub fn is_safe(&self, m: Move, threshold: Value) -> bool {
+        self.pseudo_legal(m) && self.is_legal(m) && self.see_ge(m, threshold)
+    }
+
     /// 最も価値の低い攻撃駒を探す（成りは考慮しない）
     fn least_valuable_attacker(
         &self,
@@ -694,6 +745,7 @@ mod tests {
         assert!(pos.see_ge(m, Value::new(400))); // 金(540) - 歩(90) = 450 > 400
     }
 
+    #[cfg(feature = "search")]
     #[test]
     fn test_pawn_history_index() {
         let mut pos = Position::new();
@@ -762,6 +814,52 @@ mod tests {
         );
     }
 
+    /// see() は see_ge() と整合する交換値を返す
+    #[test]
+    fn test_see_value_matches_see_ge() {
+        let mut pos = Position::new();
+        let sq55 = Square::new(File::File5, Rank::Rank5);
+        let sq54 = Square::new(File::File5, Rank::Rank4);
+        let sq58 = Square::new(File::File5, Rank::Rank8);
+        let b_king = Square::new(File::File1, Rank::Rank9);
+        let w_king = Square::new(File::File9, Rank::Rank1);
+
+        // 5五に先手歩、5四に後手金（紐なし）
+        pos.put_piece(Piece::B_PAWN, sq55);
+        pos.put_piece(Piece::W_GOLD, sq54);
+        pos.put_piece(Piece::B_KING, b_king);
+        pos.put_piece(Piece::W_KING, w_king);
+        pos.king_square[Color::Black.index()] = b_king;
+        pos.king_square[Color::White.index()] = w_king;
+        pos.side_to_move = Color::Black;
+
+        let m = Move::new_move(sq55, sq54, false);
+        assert_eq!(pos.see(m), Value::new(540));
+
+        // 5八に後手飛を置くと、歩が 5五 を空けた後に飛車で取り返される: 金 - 歩 = 450
+        pos.put_piece(Piece::W_ROOK, sq58);
+        let see = pos.see(m);
+        assert_eq!(see, Value::new(450));
+        assert!(pos.see_ge(m, see));
+        assert!(!pos.see_ge(m, Value::new(see.raw() + 1)));
+    }
+
+    /// is_safe() は非合法手を弾き、合法手は see_ge() と同じ判定になる
+    #[test]
+    fn test_is_safe_rejects_illegal_moves() {
+        let mut pos = Position::new();
+        pos.set_hirate();
+
+        let quiet = Move::from_usi("7g7f").unwrap();
+        assert_eq!(pos.see(quiet), Value::ZERO);
+        assert!(pos.is_safe(quiet, Value::ZERO));
+        assert!(!pos.is_safe(quiet, Value::new(1)));
+
+        // 歩が 2 マス進む手は非合法
+        let illegal = Move::from_usi("7g7e").unwrap();
+        assert!(!pos.is_safe(illegal, Value::new(-10000)));
+    }
+
     /// 成りフラグ検証テスト: 成れない駒（金）に成りフラグが立っている手は pseudo_legal で弾く
     #[test]
     fn test_pseudo_legal_rejects_invalid_promote_on_gold() {
@@ -0,0 +1,171 @@
+//! 局面の進行度（序盤・中盤・終盤）
+//!
+//! 将棋では取った駒が盤上から消えず手駒になるため、チェスのように盤上の駒の総量では
+//! 進行度を測れない。ここでは両者の持ち駒と盤上の成駒の量から 0（序盤）〜
+//! [`GamePhase::MAX`]（終盤）の連続値を求める。駒の交換が進み、成駒ができるほど
+//! 終盤に近いとみなす。
+//!
+//! 時間管理（中盤に時間を厚く使う）・教師データの重み付け・解析レポートで
+//! 同じ基準を使うためのもので、評価関数の入力には使わない。
+
+use super::Position;
+use crate::types::{Color, PieceType};
+
+/// 持ち駒 1 枚あたりの重み
+const HAND_WEIGHTS: [(PieceType, u32); 7] = [
+    (PieceType::Pawn, 1),
+    (PieceType::Lance, 3),
+    (PieceType::Knight, 3),
+    (PieceType::Silver, 4),
+    (PieceType::Gold, 5),
+    (PieceType::Bishop, 8),
+    (PieceType::Rook, 8),
+];
+
+/// 盤上の成駒 1 枚あたりの重み
+const PROMOTED_WEIGHTS: [(PieceType, u32); 6] = [
+    (PieceType::ProPawn, 2),
+    (PieceType::ProLance, 4),
+    (PieceType::ProKnight, 4),
+    (PieceType::ProSilver, 4),
+    (PieceType::Horse, 8),
+    (PieceType::Dragon, 8),
+];
+
+/// 重みの合計がこの値以上なら進行度を `GamePhase::MAX` とする
+const FULL_WEIGHT: u32 = 32;
+
+/// 進行度がこの値未満なら序盤
+const MIDDLEGAME_START: u16 = 32;
+
+/// 進行度がこの値以上なら終盤
+const ENDGAME_START: u16 = 192;
+
+/// 局面の段階
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PhaseStage {
+    Opening,
+    Middlegame,
+    Endgame,
+}
+
+impl PhaseStage {
+    /// 表示用の名前
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Opening => "opening",
+            Self::Middlegame => "middlegame",
+            Self::Endgame => "endgame",
+        }
+    }
+}
+
+/// 局面の進行度（0 = 序盤 〜 `GamePhase::MAX` = 終盤）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct GamePhase(u16);
+
+impl GamePhase {
+    /// 進行度の最大値
+    pub const MAX: u16 = 256;
+
+    /// 値から作る（`MAX` を超える値は `MAX` に丸める）
+    pub const fn new(value: u16) -> Self {
+        Self(if value > Self::MAX { Self::MAX } else { value })
+    }
+
+    /// 進行度（0〜`MAX`）
+    pub const fn value(self) -> u16 {
+        self.0
+    }
+
+    /// 序盤・中盤・終盤の区分
+    pub const fn stage(self) -> PhaseStage {
+        if self.0 < MIDDLEGAME_START {
+            PhaseStage::Opening
+        } else if self.0 < ENDGAME_START {
+            PhaseStage::Middlegame
+        } else {
+            PhaseStage::Endgame
+        }
+    }
+
+    /// 中盤らしさ（0.0〜1.0）
+    ///
+    /// 序盤と中盤の境界から中盤の中央で 1.0 に達し、終盤の始まりで 0.0 に戻る三角形。
+    /// 時間配分など、中盤で最大にしたい量の重みに使う。
+    pub fn middlegame_weight(self) -> f64 {
+        let start = f64::from(MIDDLEGAME_START);
+        let end = f64::from(ENDGAME_START);
+        let peak = (start + end) / 2.0;
+        let v = f64::from(self.0);
+        if v <= start || v >= end {
+            0.0
+        } else if v <= peak {
+            (v - start) / (peak - start)
+        } else {
+            (end - v) / (end - peak)
+        }
+    }
+}
+
+impl Position {
+    /// 局面の進行度（両者の持ち駒と盤上の成駒から求める）
+    pub fn phase(&self) -> GamePhase {
+        let mut weight = 0;
+        for color in [Color::Black, Color::White] {
+            let hand = self.hand(color);
+            for (pt, w) in HAND_WEIGHTS {
+                weight += hand.count(pt) * w;
+            }
+        }
+        for (pt, w) in PROMOTED_WEIGHTS {
+            weight += self.pieces_pt(pt).count() * w;
+        }
+        let value = weight.min(FULL_WEIGHT) * u32::from(GamePhase::MAX) / FULL_WEIGHT;
+        GamePhase::new(value as u16)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::position::SFEN_HIRATE;
+
+    fn phase_of(sfen: &str) -> GamePhase {
+        let mut pos = Position::new();
+        pos.set_sfen(sfen).unwrap();
+        pos.phase()
+    }
+
+    #[test]
+    fn startpos_is_opening() {
+        let phase = phase_of(SFEN_HIRATE);
+        assert_eq!(phase.value(), 0);
+        assert_eq!(phase.stage(), PhaseStage::Opening);
+        assert_eq!(phase.middlegame_weight(), 0.0);
+    }
+
+    #[test]
+    fn hand_pieces_and_promotions_advance_phase() {
+        // 角交換（両者が角を 1 枚ずつ持つ）
+        let bishop_exchange =
+            phase_of("lnsgkgsnl/1r7/pppppppp1/8p/9/2P6/PP1PPPPPP/7R1/LNSGKGSNL b Bb 1");
+        assert_eq!(bishop_exchange.value(), 128);
+        assert_eq!(bishop_exchange.stage(), PhaseStage::Middlegame);
+        assert!(bishop_exchange.middlegame_weight() > 0.5);
+
+        // 龍ができ、飛車・金銀を持ち合う局面は終盤
+        let endgame =
+            phase_of("ln3k1nl/4g4/p1ppp1s1p/5pp2/9/2P6/PP1PPPPPP/2+r4R1/LN2K2NL b G2Sbp 1");
+        assert_eq!(endgame.stage(), PhaseStage::Endgame);
+        assert!(endgame > bishop_exchange);
+    }
+
+    #[test]
+    fn phase_value_is_clamped() {
+        assert_eq!(GamePhase::new(1000).value(), GamePhase::MAX);
+        assert_eq!(GamePhase::new(GamePhase::MAX).stage(), PhaseStage::Endgame);
+        let all_in_hand = phase_of("4k4/9/9/9/9/9/9/9/4K4 b 2R2B4G4S4N4L18P 1");
+        assert_eq!(all_in_hand.value(), GamePhase::MAX);
+    }
+}
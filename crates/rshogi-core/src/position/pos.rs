@@ -1,10 +1,14 @@
 //! 局面（Position）
 
+use std::sync::Arc;
+
 use super::board_effect::{
     BoardEffects, LongEffects, compute_board_effects_and_long_effects, rewind_by_capturing_piece,
     rewind_by_dropping_piece, rewind_by_no_capturing_piece, update_by_capturing_piece,
     update_by_dropping_piece, update_by_no_capturing_piece,
 };
+use super::history::{HistoryEntry, REPETITION_WINDOW, RepetitionHistory};
+use super::sfen::SfenError;
 use super::state::{
     CS_IDX_BISHOP, CS_IDX_DRAGON, CS_IDX_GOLD, CS_IDX_HORSE, CS_IDX_KNIGHT, CS_IDX_LANCE,
     CS_IDX_PAWN, CS_IDX_ROOK, CS_IDX_SILVER, StateInfo, check_sq_index,
@@ -12,7 +16,8 @@ use super::state::{
 use super::zobrist::{zobrist_hand, zobrist_pass_rights, zobrist_psq, zobrist_side};
 use crate::bitboard::{
     Bitboard, RANK_BB, bishop_effect, dragon_effect, gold_effect, horse_effect, king_effect,
-    knight_effect, lance_effect, lance_step_effect, pawn_effect, rook_effect, silver_effect,
+    knight_effect, lance_effect, lance_step_effect, line_bb, pawn_effect, rook_effect,
+    silver_effect,
 };
 #[cfg(feature = "halfkx-arch")]
 use crate::eval::material::material_needs_board_effects;
@@ -87,6 +92,8 @@ pub struct Position {
     pub(super) king_square: [Square; Color::NUM],
     /// パス権ルールが有効かどうか
     pass_rights_enabled: bool,
+    /// 状態スタックの根より前の局面（`clone_with_history` で圧縮したもの。複製間で共有）
+    root_history: Option<Arc<RepetitionHistory>>,
 
     // === PieceList (NNUE 高速化) ===
     /// 全40駒の BonaPiece 管理テーブル
@@ -158,10 +165,106 @@ impl Position {
             side_to_move: Color::Black,
             king_square: [Square::SQ_11; Color::NUM],
             pass_rights_enabled: false,
+            root_history: None,
             piece_list: PieceList::new(),
         }
     }
 
+    /// 探索スレッドへ渡すための局面のスナップショットを作る
+    ///
+    /// 千日手判定に必要な履歴は保持したまま複製する。複製するのは現在の `StateInfo`
+    /// だけで、それより前の直近 [`REPETITION_WINDOW`] 手は千日手判定に使う情報だけを
+    /// 圧縮履歴（`RepetitionHistory`）に移すため、長い対局でも複製のコストは一定。
+    /// 代わりにスナップショットでは現在の局面より前へ `undo_move` できない。
+    /// SFEN を経由した複製（`set_sfen(&pos.to_sfen())`）は履歴を失うため使わないこと。
+    pub fn clone_with_history(&self) -> Self {
+        let mut root = self.cur_state().clone();
+        root.previous = StateInfo::NO_PREVIOUS;
+        let history = (1..=REPETITION_WINDOW).map_while(|back| self.history_entry(back));
+        let history = RepetitionHistory::new(history);
+        Position {
+            board: self.board,
+            by_type: self.by_type,
+            by_color: self.by_color,
+            board_effects: self.board_effects.clone(),
+            long_effects: self.long_effects.clone(),
+            board_effects_dirty: self.board_effects_dirty,
+            golds_bb: self.golds_bb,
+            bishop_horse_bb: self.bishop_horse_bb,
+            rook_dragon_bb: self.rook_dragon_bb,
+            hdk_bb: self.hdk_bb,
+            hand: self.hand,
+            state_stack: vec![root],
+            state_idx: 0,
+            game_ply: self.game_ply,
+            side_to_move: self.side_to_move,
+            king_square: self.king_square,
+            pass_rights_enabled: self.pass_rights_enabled,
+            root_history: (!history.is_empty()).then(|| Arc::new(history)),
+            piece_list: self.piece_list.clone(),
+        }
+    }
+
+    /// `back` 手前（1 始まり）の局面の千日手判定用の情報（状態スタックの根より前は圧縮履歴から引く）
+    fn history_entry(&self, back: usize) -> Option<HistoryEntry> {
+        if back <= self.state_idx {
+            Some(HistoryEntry::from_state(&self.state_stack[self.state_idx - back]))
+        } else {
+            self.root_history.as_deref()?.get(back - self.state_idx).copied()
+        }
+    }
+
+    /// 千日手判定に必要な履歴を付けた SFEN（拡張 SFEN）を返す
+    ///
+    /// `<SFEN> history <先手の連続王手>.<後手の連続王手>[,<局面>...]` の形式で、局面は直前の局面から
+    /// 順に最大 [`REPETITION_WINDOW`] 手分。[`set_sfen_ext`](Self::set_sfen_ext) で読み戻すと、
+    /// 元の局面と同じように千日手（連続王手の千日手と優等・劣等局面を含む）を判定できる。
+    /// 局面は盤面ハッシュで持つが、Zobrist テーブルは固定の seed から作るのでビルドによらない。
+    pub fn to_sfen_ext(&self) -> String {
+        let st = self.cur_state();
+        let mut out = format!(
+            "{} history {}.{}",
+            self.to_sfen(),
+            st.continuous_check[Color::Black.index()],
+            st.continuous_check[Color::White.index()]
+        );
+        let depth = (st.plies_from_null.max(0) as usize).min(REPETITION_WINDOW);
+        for entry in (1..=depth).map_while(|back| self.history_entry(back)) {
+            out.push(',');
+            out.push_str(&entry.to_digest());
+        }
+        out
+    }
+
+    /// 拡張 SFEN（[`to_sfen_ext`](Self::to_sfen_ext)）から局面を設定する
+    ///
+    /// `history` 以降がなければ [`set_sfen`](Self::set_sfen) と同じ。
+    pub fn set_sfen_ext(&mut self, sfen: &str) -> Result<(), SfenError> {
+        let Some((base, digest)) = sfen.split_once(" history ") else {
+            return self.set_sfen(sfen);
+        };
+        self.set_sfen(base)?;
+
+        let digest = digest.trim();
+        let invalid = || SfenError::History(digest.to_string());
+        let mut parts = digest.split(',');
+        let (black, white) = parts.next().and_then(|cc| cc.split_once('.')).ok_or_else(invalid)?;
+        let continuous_check = [
+            black.parse().map_err(|_| invalid())?,
+            white.parse().map_err(|_| invalid())?,
+        ];
+        let entries = parts.map(HistoryEntry::from_digest).collect::<Option<Vec<_>>>();
+        let entries = entries.filter(|e| e.len() <= REPETITION_WINDOW).ok_or_else(invalid)?;
+
+        let st = self.cur_state_mut();
+        st.continuous_check = continuous_check;
+        st.plies_from_null = entries.len() as i32;
+        let history = RepetitionHistory::new(entries);
+        self.root_history = (!history.is_empty()).then(|| Arc::new(history));
+        self.update_repetition_info();
+        Ok(())
+    }
+
     // ========== 盤面アクセス ==========
 
     /// 指定マスの駒を取得
@@ -638,7 +741,7 @@ impl Position {
 
     /// fromの駒を動かしたときに開き王手になるか（簡易判定）
     pub fn discovered(&self, from: Square, to: Square, ksq: Square, pinned: Bitboard) -> bool {
-        pinned.contains(from) && !crate::mate::aligned(from, to, ksq)
+        pinned.contains(from) && !line_bb(from, to).contains(ksq)
     }
 
     // ========== 内部操作 ==========
@@ -853,7 +956,7 @@ impl Position {
         sfen: &str,
         black_rights: u8,
         white_rights: u8,
-    ) -> Result<(), super::sfen::SfenError> {
+    ) -> Result<(), SfenError> {
         self.set_sfen(sfen)?;
         self.set_pass_rights_enabled(true);
         self.set_pass_rights_pair(black_rights, white_rights);
@@ -1149,7 +1252,7 @@ impl Position {
             if let Some(from_sq) = moved_from {
                 let prev_blockers = self.cur_state().blockers_for_king[them.index()];
                 if prev_blockers.contains(from_sq)
-                    && !crate::mate::aligned(from_sq, moved_to, ksq)
+                    && !line_bb(from_sq, moved_to).contains(ksq)
                     && let Some(dir) = crate::bitboard::direct_of(ksq, from_sq)
                 {
                     let ray = crate::bitboard::direct_effect(from_sq, dir, self.occupied());
@@ -1524,94 +1627,81 @@ impl Position {
         self.state_idx = prev_idx;
     }
 
-    /// 繰り返し情報を更新（最大16手遡り）
+    /// 繰り返し情報を更新（最大 REPETITION_WINDOW 手遡り）
     fn update_repetition_info(&mut self) {
         // 初期化
         let side = self.side_to_move;
-        let (plies_from_null, board_key, hand_snapshot, prev_idx, cc_side, cc_opp) = {
+        let (plies_from_null, board_key, hand_snapshot, cc_side, cc_opp) = {
             let st = self.cur_state();
             (
                 st.plies_from_null,
                 st.board_key,
                 st.hand_snapshot,
-                st.previous,
                 st.continuous_check[side.index()],
                 st.continuous_check[(!side).index()],
             )
         };
 
-        let max_back = plies_from_null.min(16);
+        let max_back = plies_from_null.min(REPETITION_WINDOW as i32);
         let mut repetition = 0;
         let mut repetition_times = 0;
         let mut repetition_type = RepetitionState::None;
 
-        if max_back >= 4 && prev_idx != StateInfo::NO_PREVIOUS {
-            // 千日手は最短4手で成立するため4手前から比較開始
-            let mut dist = 4;
-            let mut st_idx = prev_idx;
-            for _ in 0..3 {
-                debug_assert!(st_idx < self.state_stack.len());
-                // SAFETY: ループ不変条件: st_idx はループ先頭時点で常に有効なインデックス。
-                //   - 1回目: prev_idx は関数先頭で NO_PREVIOUS チェック済み。
-                //   - 2・3回目: 前の反復で NO_PREVIOUS なら break するため無効値では到達しない。
-                //   push_state で設定された .previous は常に有効なインデックスか NO_PREVIOUS。
-                st_idx = unsafe { self.state_stack.get_unchecked(st_idx) }.previous;
-                if st_idx == StateInfo::NO_PREVIOUS {
-                    break;
+        // 千日手は最短4手で成立するため4手前から比較開始
+        let mut dist = 4;
+        while dist <= max_back {
+            // 状態スタックは根から一直線に積まれている（push_state で previous = 1 つ前）ため、
+            // dist 手前の局面はインデックスで直接引ける。根より前は圧縮履歴から引く。
+            let back = dist as usize;
+            let stp = if back <= self.state_idx {
+                // SAFETY: back <= state_idx < state_stack.len()
+                HistoryEntry::from_state(unsafe {
+                    self.state_stack.get_unchecked(self.state_idx - back)
+                })
+            } else {
+                match self.root_history.as_deref().and_then(|h| h.get(back - self.state_idx)) {
+                    Some(entry) => *entry,
+                    None => break,
                 }
-            }
-
-            while dist <= max_back && st_idx != StateInfo::NO_PREVIOUS {
-                debug_assert!(st_idx < self.state_stack.len());
-                // SAFETY: 同上。
-                let stp = unsafe { self.state_stack.get_unchecked(st_idx) };
-                if stp.board_key == board_key {
-                    let prev_hand = stp.hand_snapshot[side.index()];
-                    let cur_hand = hand_snapshot[side.index()];
-
-                    if cur_hand == prev_hand {
-                        let times = stp.repetition_times + 1;
-                        repetition_times = times;
-                        repetition = if times >= 3 { -dist } else { dist };
-
-                        let mut rep_type = if dist <= cc_side {
-                            RepetitionState::Lose
-                        } else if dist <= cc_opp {
-                            RepetitionState::Win
-                        } else {
-                            RepetitionState::Draw
-                        };
-
-                        if stp.repetition_times > 0 && stp.repetition_type != rep_type {
-                            rep_type = RepetitionState::Draw;
-                        }
-
-                        repetition_type = rep_type;
-                        break;
+            };
+            if stp.board_key == board_key {
+                let prev_hand = stp.hand_snapshot[side.index()];
+                let cur_hand = hand_snapshot[side.index()];
+
+                if cur_hand == prev_hand {
+                    let times = stp.repetition_times + 1;
+                    repetition_times = times;
+                    repetition = if times >= 3 { -dist } else { dist };
+
+                    let mut rep_type = if dist <= cc_side {
+                        RepetitionState::Lose
+                    } else if dist <= cc_opp {
+                        RepetitionState::Win
+                    } else {
+                        RepetitionState::Draw
+                    };
+
+                    if stp.repetition_times > 0 && stp.repetition_type != rep_type {
+                        rep_type = RepetitionState::Draw;
                     }
 
-                    if cur_hand.is_superior_or_equal(prev_hand) {
-                        repetition_type = RepetitionState::Superior;
-                        repetition = dist;
-                        break;
-                    }
+                    repetition_type = rep_type;
+                    break;
+                }
 
-                    if prev_hand.is_superior_or_equal(cur_hand) {
-                        repetition_type = RepetitionState::Inferior;
-                        repetition = dist;
-                        break;
-                    }
+                if cur_hand.is_superior_or_equal(prev_hand) {
+                    repetition_type = RepetitionState::Superior;
+                    repetition = dist;
+                    break;
                 }
 
-                let prev_same_side = stp.previous;
-                if prev_same_side == StateInfo::NO_PREVIOUS {
+                if prev_hand.is_superior_or_equal(cur_hand) {
+                    repetition_type = RepetitionState::Inferior;
+                    repetition = dist;
                     break;
                 }
-                debug_assert!(prev_same_side < self.state_stack.len());
-                // SAFETY: prev_same_side は .previous チェーンの有効なインデックス。
-                st_idx = unsafe { self.state_stack.get_unchecked(prev_same_side) }.previous;
-                dist += 2;
             }
+            dist += 2;
         }
 
         let st = self.cur_state_mut();
@@ -1680,6 +1770,7 @@ impl Position {
     }
 
     /// 1手詰めを検出（該当手があれば返す。なければ Move::NONE）
+    #[cfg(feature = "mate")]
     pub fn mate_1ply(&mut self) -> Move {
         crate::mate::mate_1ply(self).unwrap_or(Move::NONE)
     }
@@ -1735,29 +1826,53 @@ impl Position {
         }
 
         // (c) 駒点計算
-        // 大駒（角・馬・飛・龍）= 5点、小駒 = 1点
+        let score = self.declaration_points();
+        let required =
+            self.declaration_required_points(rule).expect("None / TryRule は上部で除外済み");
+
+        if score >= required {
+            Move::WIN
+        } else {
+            Move::NONE
+        }
+    }
+
+    /// 入玉宣言の駒点（手番側、YaneuraOu `Position::DeclarationWin()` 準拠）
+    ///
+    /// 敵陣三段目以内の自駒（玉を除く）と持ち駒を、大駒（角・馬・飛・龍）5点、
+    /// 小駒1点で数える。宣言の他の条件（入玉・枚数・王手）は判定しない。
+    pub fn declaration_points(&self) -> u32 {
+        let us = self.side_to_move();
+        let ef = Self::enemy_field(us);
+        let our_in_enemy = (self.pieces_c(us) & ef).count();
         let big_set = PieceTypeSet::bishop_horse() | PieceTypeSet::rook_dragon();
         let big_in_enemy = (self.pieces_c_by_types(us, big_set) & ef).count();
+        let king_in_enemy = u32::from(ef.contains(self.king_square(us)));
 
         // 小駒1点、大駒5点、玉除く
-        // = 敵陣の自駒数 + 敵陣の自駒の大駒×4 - 1(玉)
+        // = 敵陣の自駒数 + 敵陣の自駒の大駒×4 - 玉
         let h = self.hand(us);
-        let score = our_in_enemy.count() + big_in_enemy * 4 - 1
+        our_in_enemy + big_in_enemy * 4 - king_in_enemy
             + h.count(PieceType::Pawn)
             + h.count(PieceType::Lance)
             + h.count(PieceType::Knight)
             + h.count(PieceType::Silver)
             + h.count(PieceType::Gold)
-            + (h.count(PieceType::Bishop) + h.count(PieceType::Rook)) * 5;
+            + (h.count(PieceType::Bishop) + h.count(PieceType::Rook)) * 5
+    }
 
-        // 必要点を計算（None / TryRule は上部で除外済み）
+    /// 入玉宣言に必要な駒点（手番側）
+    ///
+    /// 点数法でないルール（`None` / `TryRule`）では `None` を返す。
+    pub fn declaration_required_points(&self, rule: EnteringKingRule) -> Option<u32> {
+        let us = self.side_to_move();
         let mut required = match rule {
             EnteringKingRule::Point24 | EnteringKingRule::Point24H => 31u32,
             EnteringKingRule::Point27 | EnteringKingRule::Point27H => match us {
                 Color::Black => 28,
                 Color::White => 27,
             },
-            EnteringKingRule::None | EnteringKingRule::TryRule => unreachable!(),
+            EnteringKingRule::None | EnteringKingRule::TryRule => return None,
         };
 
         // 駒落ち補正（_H バリアント）
@@ -1771,12 +1886,24 @@ impl Position {
                 }
             }
         }
+        Some(required)
+    }
 
-        if score >= required {
-            Move::WIN
-        } else {
-            Move::NONE
+    /// 終局局面の評価値（手番側から見た値）。終局でなければ `None`
+    ///
+    /// - 入玉宣言できる（`rule` による）: `Value::MATE`（探索のルートで宣言勝ちに付ける値と同じ）
+    /// - 合法手が無い（詰み。将棋では王手が掛かっていなくても指せなければ負け）:
+    ///   `Value::mated_in(0)`
+    ///
+    /// 探索・静止探索・静的評価のどの入口でも終局局面の値をこれにそろえる。
+    /// 合法手を生成するので、探索中のノードでは使わないこと。
+    pub fn terminal_value(&self, rule: EnteringKingRule) -> Option<Value> {
+        if self.declaration_win(rule) != Move::NONE {
+            return Some(Value::MATE);
         }
+        let mut moves = crate::movegen::MoveList::new();
+        crate::movegen::generate_legal(self, &mut moves);
+        moves.is_empty().then_some(Value::mated_in(0))
     }
 
     /// トライルール: 玉が敵の初期玉位置に移動できるか判定
@@ -2916,6 +3043,103 @@ mod tests {
         assert_eq!(key1, key4, "Restoring original pass rights should restore key");
     }
 
+    #[test]
+    fn test_clone_with_history_keeps_repetition_history() {
+        let mut pos = Position::new();
+        pos.set_hirate();
+        for usi in ["5i5h", "5a5b", "5h5i", "5b5a", "5i5h", "5a5b", "5h5i"] {
+            let mv = pos.to_move(Move::from_usi(usi).unwrap()).unwrap();
+            pos.do_move(mv, pos.gives_check(mv));
+        }
+        // undo 済みの StateInfo を残しておく
+        let extra = pos.to_move(Move::from_usi("1c1d").unwrap()).unwrap();
+        pos.do_move(extra, pos.gives_check(extra));
+        pos.undo_move(extra);
+
+        let mut snapshot = pos.clone_with_history();
+        assert_eq!(snapshot.state_stack.len(), snapshot.state_idx + 1);
+        assert_eq!(snapshot.to_sfen(), pos.to_sfen());
+        assert_eq!(snapshot.key(), pos.key());
+
+        // SFEN から作り直した局面は履歴が無いため千日手を検出できない
+        let mut reparsed = Position::new();
+        reparsed.set_sfen(&pos.to_sfen()).unwrap();
+
+        let mv = snapshot.to_move(Move::from_usi("5b5a").unwrap()).unwrap();
+        snapshot.do_move(mv, snapshot.gives_check(mv));
+        reparsed.do_move(mv, reparsed.gives_check(mv));
+        assert_ne!(snapshot.repetition_state(16), RepetitionState::None);
+        assert_eq!(reparsed.repetition_state(16), RepetitionState::None);
+    }
+
+    #[test]
+    fn test_clone_with_history_compacts_long_game() {
+        let mut pos = Position::new();
+        pos.set_hirate();
+        let cycle = ["5i5h", "5a5b", "5h5i", "5b5a"];
+        // 4 手で元に戻る往復を 500 手ぶん（千日手の成立は無視して）積む
+        for usi in cycle.iter().cycle().take(500) {
+            let mv = pos.to_move(Move::from_usi(usi).unwrap()).unwrap();
+            pos.do_move(mv, pos.gives_check(mv));
+        }
+        let snapshot = pos.clone_with_history();
+        assert_eq!(snapshot.state_stack.len(), 1);
+        assert_eq!(snapshot.game_ply(), pos.game_ply());
+        assert_eq!(snapshot.repetition_state(16), pos.repetition_state(16));
+
+        // スナップショットのスナップショットでも根より前の履歴で千日手を検出する
+        let mut nested = snapshot.clone_with_history();
+        let mut full = pos.clone();
+        for usi in &cycle[..2] {
+            let mv = nested.to_move(Move::from_usi(usi).unwrap()).unwrap();
+            nested.do_move(mv, nested.gives_check(mv));
+            full.do_move(mv, full.gives_check(mv));
+            assert_eq!(nested.state().repetition, full.state().repetition);
+            assert_eq!(nested.state().repetition_times, full.state().repetition_times);
+            assert_eq!(nested.repetition_state(16), full.repetition_state(16));
+        }
+        assert_ne!(nested.repetition_state(16), RepetitionState::None);
+    }
+
+    #[test]
+    fn test_sfen_ext_round_trips_repetition_history() {
+        use crate::position::SFEN_HIRATE;
+
+        let mut pos = Position::new();
+        pos.set_hirate();
+        let cycle = ["5i5h", "5a5b", "5h5i", "5b5a"];
+        for usi in cycle.iter().cycle().take(6) {
+            let mv = pos.to_move(Move::from_usi(usi).unwrap()).unwrap();
+            pos.do_move(mv, pos.gives_check(mv));
+        }
+
+        let ext = pos.to_sfen_ext();
+        assert!(ext.starts_with(&format!("{} history 0.0,", pos.to_sfen())));
+        let mut restored = Position::new();
+        restored.set_sfen_ext(&ext).unwrap();
+        assert_eq!(restored.key(), pos.key());
+        assert_eq!(restored.to_sfen_ext(), ext);
+
+        // 読み戻した局面でも、元の局面と同じ手順で同じ千日手の判定になる
+        for usi in cycle.iter().cycle().skip(2).take(6) {
+            let mv = pos.to_move(Move::from_usi(usi).unwrap()).unwrap();
+            pos.do_move(mv, pos.gives_check(mv));
+            restored.do_move(mv, restored.gives_check(mv));
+            assert_eq!(restored.state().repetition, pos.state().repetition);
+            assert_eq!(restored.state().repetition_times, pos.state().repetition_times);
+            assert_eq!(restored.repetition_state(16), pos.repetition_state(16));
+        }
+        assert_ne!(restored.repetition_state(16), RepetitionState::None);
+
+        // 履歴のない SFEN も読め、不正な履歴はエラーになる
+        restored.set_sfen_ext(SFEN_HIRATE).unwrap();
+        assert_eq!(restored.to_sfen_ext(), format!("{SFEN_HIRATE} history 0.0"));
+        assert!(matches!(
+            restored.set_sfen_ext(&format!("{SFEN_HIRATE} history 0.0,zz")),
+            Err(SfenError::History(_))
+        ));
+    }
+
     // =========================================
     // 入玉宣言勝ちのテスト
     // =========================================
@@ -2949,6 +3173,32 @@ mod tests {
         assert_eq!(result, Move::WIN, "先手28点以上で宣言勝ち");
     }
 
+    #[test]
+    fn test_declaration_points_and_required() {
+        let sfen = "KGG6/SS7/PPPPPP3/9/9/9/2pppppp1/1ss1gg1nl/4k2nl b 2R2B3p 1";
+        let pos = make_pos(sfen);
+        assert_eq!(pos.declaration_points(), 30);
+        assert_eq!(pos.declaration_required_points(EnteringKingRule::Point27), Some(28));
+        assert_eq!(pos.declaration_required_points(EnteringKingRule::Point24), Some(31));
+        assert_eq!(pos.declaration_required_points(EnteringKingRule::None), None);
+        assert_eq!(pos.declaration_required_points(EnteringKingRule::TryRule), None);
+    }
+
+    #[test]
+    fn test_terminal_value() {
+        let startpos = make_pos("lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL b - 1");
+        assert_eq!(startpos.terminal_value(EnteringKingRule::Point27), None);
+
+        // 宣言勝ちは規則によって終局になる
+        let decl = make_pos("KGG6/SS7/PPPPPP3/9/9/9/2pppppp1/1ss1gg1nl/4k2nl b 2R2B3p 1");
+        assert_eq!(decl.terminal_value(EnteringKingRule::Point27), Some(Value::MATE));
+        assert_eq!(decl.terminal_value(EnteringKingRule::None), None);
+
+        // 頭金で詰み（後手番）
+        let mated = make_pos("4k4/4G4/4P4/9/9/9/9/9/4K4 w - 1");
+        assert_eq!(mated.terminal_value(EnteringKingRule::None), Some(Value::mated_in(0)));
+    }
+
     #[test]
     fn test_declaration_win_king_not_in_enemy() {
         // 先手玉が自陣(9九)にいる → 宣言勝ち不可
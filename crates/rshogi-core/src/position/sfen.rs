@@ -22,6 +22,8 @@ pub enum SfenError {
     Hand(String),
     /// 手数の形式が不正
     Ply(String),
+    /// 拡張 SFEN の履歴の形式が不正
+    History(String),
 }
 
 impl std::fmt::Display for SfenError {
@@ -31,6 +33,7 @@ impl std::fmt::Display for SfenError {
             SfenError::SideToMove(s) => write!(f, "Invalid side to move: {s}"),
             SfenError::Hand(s) => write!(f, "Invalid hand: {s}"),
             SfenError::Ply(s) => write!(f, "Invalid ply: {s}"),
+            SfenError::History(s) => write!(f, "Invalid history: {s}"),
         }
     }
 }
@@ -137,6 +140,9 @@ impl Position {
         // 盤面の利き数を再計算
         self.recompute_board_effects();
 
+        // 千日手判定で後の局面と比べる手駒
+        self.state_mut().hand_snapshot = self.hand;
+
         // 王手駒の計算
         let them = !self.side_to_move;
         self.state_mut().checkers =
@@ -520,28 +526,7 @@ impl Position {
 
 /// 駒をSFEN文字列に変換
 fn piece_to_sfen(pc: Piece) -> String {
-    let base = match pc.piece_type() {
-        PieceType::Pawn => "P",
-        PieceType::Lance => "L",
-        PieceType::Knight => "N",
-        PieceType::Silver => "S",
-        PieceType::Bishop => "B",
-        PieceType::Rook => "R",
-        PieceType::Gold => "G",
-        PieceType::King => "K",
-        PieceType::ProPawn => "+P",
-        PieceType::ProLance => "+L",
-        PieceType::ProKnight => "+N",
-        PieceType::ProSilver => "+S",
-        PieceType::Horse => "+B",
-        PieceType::Dragon => "+R",
-    };
-
-    if pc.color() == Color::White {
-        base.to_lowercase()
-    } else {
-        base.to_string()
-    }
+    pc.to_usi()
 }
 
 /// SFEN文字を駒に変換
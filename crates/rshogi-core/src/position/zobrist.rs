@@ -129,6 +129,24 @@ pub fn zobrist_side() -> u64 {
     ZOBRIST.side
 }
 
+/// Zobrist テーブル全体の指紋
+///
+/// 置換表をファイルに保存したときに記録し、読み込み時に同じキー体系のエンジンかを確かめる。
+pub fn zobrist_fingerprint() -> u64 {
+    // FNV-1a 風に全キーを畳み込む（パス権のキーも含める）
+    let pass_rights = PASS_RIGHTS_KEYS.iter().flatten();
+    let hand = ZOBRIST.hand.iter().flatten();
+    let psq = ZOBRIST.psq.iter().flatten();
+    [ZOBRIST.side, ZOBRIST.no_pawns]
+        .iter()
+        .chain(psq)
+        .chain(hand)
+        .chain(pass_rights)
+        .fold(0xcbf2_9ce4_8422_2325u64, |acc, &key| {
+            (acc ^ key).wrapping_mul(0x0000_0100_0000_01b3)
+        })
+}
+
 // =============================================================================
 // パス権用Zobristキー
 // =============================================================================
@@ -0,0 +1,790 @@
+//! CSA 形式（V2.2）
+//!
+//! 対局サーバーや floodgate が出力する CSA 棋譜を読み書きする。
+//!
+//! - 開始局面: `PI`（平手、駒落ちは `PI82HI` のように取り除く駒を列挙）、
+//!   `P1`〜`P9` の一括表現、`P+` / `P-` の駒別表現（`00AL` で残り全てを持駒）と手番行
+//! - 指し手: `+7776FU`。消費時間は次の `T` 行か、`,T12` のように同じ行に書く
+//! - 終局: `%TORYO` / `%SENNICHITE` / `%TIME_UP` など
+//! - 複数棋譜: `/` だけの行で区切る（[`parse_all`]）
+//!
+//! 対局者名（`N+` / `N-`）と `$EVENT` などの棋譜情報は KIF と同じキー（`先手` / `棋戦`
+//! など）の [`GameRecord::headers`] にする。対応の無い `$` 行はキーをそのまま残す。
+//! `'` で始まるコメント行は直前の指し手（初手より前なら棋譜全体）のコメントになる。
+
+use super::{GameEnd, GameEndKind, GameRecord, MoveTime, RecordError, RecordMove, legal_move};
+use crate::position::{Position, SFEN_HIRATE};
+use crate::types::{Color, File, Move, PieceType, Rank, Square};
+
+/// 棋譜情報の CSA のキーと [`GameRecord::headers`] のキー
+const HEADER_KEYS: [(&str, &str); 8] = [
+    ("N+", "先手"),
+    ("N-", "後手"),
+    ("$EVENT:", "棋戦"),
+    ("$SITE:", "場所"),
+    ("$START_TIME:", "開始日時"),
+    ("$END_TIME:", "終了日時"),
+    ("$TIME_LIMIT:", "持ち時間"),
+    ("$OPENING:", "戦型"),
+];
+
+/// 駒の CSA 表記（成駒を含む）
+const PIECE_CODES: [(&str, PieceType); 14] = [
+    ("FU", PieceType::Pawn),
+    ("KY", PieceType::Lance),
+    ("KE", PieceType::Knight),
+    ("GI", PieceType::Silver),
+    ("KI", PieceType::Gold),
+    ("KA", PieceType::Bishop),
+    ("HI", PieceType::Rook),
+    ("OU", PieceType::King),
+    ("TO", PieceType::ProPawn),
+    ("NY", PieceType::ProLance),
+    ("NK", PieceType::ProKnight),
+    ("NG", PieceType::ProSilver),
+    ("UM", PieceType::Horse),
+    ("RY", PieceType::Dragon),
+];
+
+/// 1 組の駒の枚数（玉を除く。`00AL` の残り駒の計算用）
+const PIECE_COUNTS: [(PieceType, u32); 7] = [
+    (PieceType::Rook, 2),
+    (PieceType::Bishop, 2),
+    (PieceType::Gold, 4),
+    (PieceType::Silver, 4),
+    (PieceType::Knight, 4),
+    (PieceType::Lance, 4),
+    (PieceType::Pawn, 18),
+];
+
+/// CSA 棋譜を読む（`/` 区切りの複数棋譜なら最初の 1 局）
+pub fn parse(text: &str) -> Result<GameRecord, RecordError> {
+    let mut parser = Parser::default();
+    for (idx, line) in text.lines().enumerate() {
+        if line.trim() == "/" {
+            break;
+        }
+        parser.line(idx + 1, line)?;
+    }
+    parser.finish()
+}
+
+/// `/` 区切りの複数棋譜を全て読む
+pub fn parse_all(text: &str) -> Result<Vec<GameRecord>, RecordError> {
+    let mut records = Vec::new();
+    let mut parser = Parser::default();
+    let mut empty = true;
+    for (idx, line) in text.lines().enumerate() {
+        if line.trim() == "/" {
+            records.push(std::mem::take(&mut parser).finish()?);
+            empty = true;
+            continue;
+        }
+        empty &= line.trim().is_empty();
+        parser.line(idx + 1, line)?;
+    }
+    if !empty || records.is_empty() {
+        records.push(parser.finish()?);
+    }
+    Ok(records)
+}
+
+/// CSA 形式（V2.2）で書き出す
+pub fn to_csa(record: &GameRecord) -> Result<String, RecordError> {
+    let mut pos = record.start_position()?;
+    let mut out = String::from("V2.2\n");
+    for (key, value) in &record.headers {
+        if let Some((csa, _)) = HEADER_KEYS.iter().find(|(_, k)| k == key) {
+            out.push_str(&format!("{csa}{value}\n"));
+        } else if key.starts_with('$') {
+            out.push_str(&format!("{key}:{value}\n"));
+        }
+    }
+    out.push_str(&board_lines(&pos));
+    for comment in &record.comments {
+        out.push_str(&format!("'{comment}\n"));
+    }
+    for m in &record.moves {
+        out.push_str(&move_text(&pos, m.mv));
+        out.push('\n');
+        if let Some(time) = m.time {
+            out.push_str(&time_line(time));
+        }
+        for comment in &m.comments {
+            out.push_str(&format!("'{comment}\n"));
+        }
+        let gives_check = pos.gives_check(m.mv);
+        pos.do_move(m.mv, gives_check);
+    }
+    if let Some(end) = &record.end {
+        out.push_str(&special_text(end.kind, pos.side_to_move()));
+        out.push('\n');
+        if let Some(time) = end.time {
+            out.push_str(&time_line(time));
+        }
+        for comment in &end.comments {
+            out.push_str(&format!("'{comment}\n"));
+        }
+    }
+    Ok(out)
+}
+
+// ---------------------------------------------------------------------------
+// 読み込み
+// ---------------------------------------------------------------------------
+
+/// 開始局面の組み立て中の盤面
+#[derive(Clone)]
+struct Setup {
+    /// `[段][筋]`（0 始まり）
+    board: [[Option<(Color, PieceType)>; File::NUM]; Rank::NUM],
+    /// `[手番][PIECE_COUNTS の添字]`
+    hands: [[u32; PIECE_COUNTS.len()]; Color::NUM],
+    side_to_move: Color,
+}
+
+impl Setup {
+    fn empty() -> Self {
+        Self {
+            board: [[None; File::NUM]; Rank::NUM],
+            hands: [[0; PIECE_COUNTS.len()]; Color::NUM],
+            side_to_move: Color::Black,
+        }
+    }
+
+    fn from_sfen_board(sfen: &str) -> Self {
+        let mut setup = Self::empty();
+        let board = sfen.split_whitespace().next().unwrap_or_default();
+        for (r, row) in board.split('/').enumerate() {
+            let mut f = File::NUM;
+            for c in row.chars() {
+                if let Some(n) = c.to_digit(10) {
+                    f -= n as usize;
+                    continue;
+                }
+                f -= 1;
+                let color = if c.is_ascii_uppercase() {
+                    Color::Black
+                } else {
+                    Color::White
+                };
+                let pt = match c.to_ascii_uppercase() {
+                    'P' => PieceType::Pawn,
+                    'L' => PieceType::Lance,
+                    'N' => PieceType::Knight,
+                    'S' => PieceType::Silver,
+                    'G' => PieceType::Gold,
+                    'B' => PieceType::Bishop,
+                    'R' => PieceType::Rook,
+                    _ => PieceType::King,
+                };
+                setup.board[r][f] = Some((color, pt));
+            }
+        }
+        setup
+    }
+
+    /// 盤上・持駒にある駒の枚数（成駒は生駒として数える）
+    fn count(&self, pt: PieceType) -> u32 {
+        let on_board = self
+            .board
+            .iter()
+            .flatten()
+            .filter(|cell| cell.is_some_and(|(_, p)| p.unpromote() == pt))
+            .count() as u32;
+        let i = PIECE_COUNTS.iter().position(|(p, _)| *p == pt).unwrap();
+        on_board + self.hands[0][i] + self.hands[1][i]
+    }
+
+    fn to_sfen(&self) -> String {
+        let mut rows = Vec::with_capacity(Rank::NUM);
+        for row in &self.board {
+            let mut text = String::new();
+            let mut empty = 0;
+            for cell in row.iter().rev() {
+                let Some((color, pt)) = cell else {
+                    empty += 1;
+                    continue;
+                };
+                if empty > 0 {
+                    text.push_str(&empty.to_string());
+                    empty = 0;
+                }
+                text.push_str(&sfen_piece(*color, *pt));
+            }
+            if empty > 0 {
+                text.push_str(&empty.to_string());
+            }
+            rows.push(text);
+        }
+        let mut hand = String::new();
+        for color in [Color::Black, Color::White] {
+            for (i, (pt, _)) in PIECE_COUNTS.iter().enumerate() {
+                let n = self.hands[color.index()][i];
+                if n > 1 {
+                    hand.push_str(&n.to_string());
+                }
+                if n > 0 {
+                    hand.push_str(&sfen_piece(color, *pt));
+                }
+            }
+        }
+        if hand.is_empty() {
+            hand.push('-');
+        }
+        let side = if self.side_to_move == Color::Black {
+            'b'
+        } else {
+            'w'
+        };
+        format!("{} {side} {hand} 1", rows.join("/"))
+    }
+}
+
+#[derive(Default)]
+struct Parser {
+    record: GameRecord,
+    setup: Option<Setup>,
+    pos: Option<Position>,
+}
+
+impl Parser {
+    fn line(&mut self, line_no: usize, line: &str) -> Result<(), RecordError> {
+        let line = line.trim_end_matches('\r');
+        if let Some(comment) = line.strip_prefix('\'') {
+            self.comment(comment);
+            return Ok(());
+        }
+        if line.starts_with('P') && line.as_bytes().get(1).is_some_and(u8::is_ascii_digit) {
+            return self.board_row(line_no, line);
+        }
+        for statement in line.split(',') {
+            let statement = statement.trim();
+            if !statement.is_empty() {
+                self.statement(line_no, statement)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn comment(&mut self, comment: &str) {
+        let comments = match (&mut self.record.end, self.record.moves.last_mut()) {
+            (Some(end), _) => &mut end.comments,
+            (None, Some(m)) => &mut m.comments,
+            (None, None) => &mut self.record.comments,
+        };
+        comments.push(comment.to_string());
+    }
+
+    fn statement(&mut self, line_no: usize, s: &str) -> Result<(), RecordError> {
+        if s.starts_with('V') {
+            return Ok(());
+        }
+        if let Some((csa, key)) = HEADER_KEYS.iter().find(|(csa, _)| s.starts_with(csa)) {
+            self.record.headers.push((key.to_string(), s[csa.len()..].to_string()));
+            return Ok(());
+        }
+        if let Some(rest) = s.strip_prefix('$') {
+            let (key, value) = rest.split_once(':').unwrap_or((rest, ""));
+            self.record.headers.push((format!("${key}"), value.to_string()));
+            return Ok(());
+        }
+        if let Some(removal) = s.strip_prefix("PI") {
+            return self.handicap(line_no, removal);
+        }
+        if let Some(rest) = s.strip_prefix("P+").or_else(|| s.strip_prefix("P-")) {
+            let color = if s.as_bytes()[1] == b'+' {
+                Color::Black
+            } else {
+                Color::White
+            };
+            return self.pieces(line_no, color, rest);
+        }
+        if s == "+" || s == "-" {
+            let setup = self.setup.get_or_insert_with(|| Setup::from_sfen_board(SFEN_HIRATE));
+            setup.side_to_move = if s == "+" { Color::Black } else { Color::White };
+            return Ok(());
+        }
+        if let Some(time) = s.strip_prefix('T') {
+            let time =
+                parse_seconds(time).ok_or_else(|| syntax(line_no, format!("invalid time: {s}")))?;
+            let target = match (&mut self.record.end, self.record.moves.last_mut()) {
+                (Some(end), _) => &mut end.time,
+                (None, Some(m)) => &mut m.time,
+                (None, None) => return Err(syntax(line_no, format!("time before any move: {s}"))),
+            };
+            *target = Some(MoveTime {
+                elapsed_ms: time,
+                total_ms: None,
+            });
+            return Ok(());
+        }
+        if let Some(special) = s.strip_prefix('%') {
+            self.start()?;
+            let kind = special_kind(special, self.side())
+                .ok_or_else(|| syntax(line_no, format!("unknown special move: {s}")))?;
+            self.record.end.get_or_insert(GameEnd::new(kind));
+            return Ok(());
+        }
+        if s.starts_with('+') || s.starts_with('-') {
+            self.start()?;
+            return self.play(line_no, s);
+        }
+        Err(syntax(line_no, format!("unexpected statement: {s}")))
+    }
+
+    /// `PI` 行（平手から取り除く駒を `82HI` のように列挙）
+    fn handicap(&mut self, line_no: usize, removal: &str) -> Result<(), RecordError> {
+        let mut setup = Setup::from_sfen_board(SFEN_HIRATE);
+        if !removal.len().is_multiple_of(4) {
+            return Err(syntax(line_no, format!("invalid PI line: PI{removal}")));
+        }
+        for item in removal.as_bytes().chunks(4) {
+            let item = std::str::from_utf8(item).unwrap_or_default();
+            let (f, r) = csa_square(&item[..2])
+                .ok_or_else(|| syntax(line_no, format!("invalid square in PI line: {item}")))?;
+            if setup.board[r][f].take().is_none() {
+                return Err(syntax(line_no, format!("no piece to remove: {item}")));
+            }
+        }
+        self.setup = Some(setup);
+        Ok(())
+    }
+
+    /// `P1`〜`P9` 行（9 筋から 1 筋へ 3 文字ずつ、空きは ` * `）
+    fn board_row(&mut self, line_no: usize, line: &str) -> Result<(), RecordError> {
+        let r = line.as_bytes()[1].wrapping_sub(b'1') as usize;
+        if r >= Rank::NUM {
+            return Err(syntax(line_no, format!("invalid rank line: {line}")));
+        }
+        let setup = self.setup.get_or_insert_with(Setup::empty);
+        // 行末の空白が削られていても読めるよう、末尾の空き升を補う
+        let mut cells: Vec<char> = line[2..].chars().collect();
+        if cells.len() >= 3 * File::NUM - 2 {
+            cells.resize(3 * File::NUM, ' ');
+        }
+        if cells.len() < 3 * File::NUM {
+            return Err(syntax(line_no, format!("rank line is too short: {line}")));
+        }
+        for (i, cell) in cells.chunks(3).take(File::NUM).enumerate() {
+            let f = File::NUM - 1 - i;
+            let text: String = cell.iter().collect();
+            setup.board[r][f] = if text.trim() == "*" {
+                None
+            } else {
+                Some(
+                    parse_piece(&text)
+                        .ok_or_else(|| syntax(line_no, format!("invalid piece: {text}")))?,
+                )
+            };
+        }
+        Ok(())
+    }
+
+    /// `P+` / `P-` 行（`63FU` で盤上、`00KA` で持駒、`00AL` で残り全てを持駒）
+    fn pieces(&mut self, line_no: usize, color: Color, items: &str) -> Result<(), RecordError> {
+        let setup = self.setup.get_or_insert_with(Setup::empty);
+        if !items.len().is_multiple_of(4) {
+            return Err(syntax(line_no, format!("invalid piece line: {items}")));
+        }
+        for item in items.as_bytes().chunks(4) {
+            let item = std::str::from_utf8(item).unwrap_or_default();
+            let (square, code) = item.split_at(2);
+            if square == "00" && code == "AL" {
+                for (i, (pt, total)) in PIECE_COUNTS.iter().enumerate() {
+                    let rest = total.saturating_sub(setup.count(*pt));
+                    setup.hands[color.index()][i] += rest;
+                }
+                continue;
+            }
+            let pt = piece_code(code)
+                .ok_or_else(|| syntax(line_no, format!("invalid piece: {item}")))?;
+            if square == "00" {
+                let i = PIECE_COUNTS
+                    .iter()
+                    .position(|(p, _)| *p == pt)
+                    .ok_or_else(|| syntax(line_no, format!("invalid hand piece: {item}")))?;
+                setup.hands[color.index()][i] += 1;
+            } else {
+                let (f, r) = csa_square(square)
+                    .ok_or_else(|| syntax(line_no, format!("invalid square: {item}")))?;
+                setup.board[r][f] = Some((color, pt));
+            }
+        }
+        Ok(())
+    }
+
+    /// 開始局面を確定する（2 回目以降は何もしない）
+    fn start(&mut self) -> Result<(), RecordError> {
+        if self.pos.is_some() {
+            return Ok(());
+        }
+        let sfen = match &self.setup {
+            Some(setup) => setup.to_sfen(),
+            None => SFEN_HIRATE.to_string(),
+        };
+        let mut pos = Position::new();
+        pos.set_sfen(&sfen).map_err(RecordError::Position)?;
+        self.record.start_sfen = pos.to_sfen();
+        self.pos = Some(pos);
+        Ok(())
+    }
+
+    fn side(&self) -> Color {
+        self.pos.as_ref().map_or(Color::Black, Position::side_to_move)
+    }
+
+    /// 指し手（`+7776FU`。駒は移動後の駒で、成りは駒の変化で表す）
+    fn play(&mut self, line_no: usize, s: &str) -> Result<(), RecordError> {
+        let illegal = || RecordError::IllegalMove {
+            line: line_no,
+            ply: self.record.moves.len() + 1,
+            text: s.to_string(),
+        };
+        let pos = self.pos.as_ref().expect("start() must be called before play()");
+        if self.record.end.is_some() || s.len() != 7 || !s.is_ascii() {
+            return Err(illegal());
+        }
+        let color = if s.starts_with('+') {
+            Color::Black
+        } else {
+            Color::White
+        };
+        let (f, r) = csa_square(&s[3..5]).ok_or_else(illegal)?;
+        let to = square(f, r);
+        let pt = piece_code(&s[5..7]).ok_or_else(illegal)?;
+        let mv = if &s[1..3] == "00" {
+            Move::new_drop(pt, to)
+        } else {
+            let (f, r) = csa_square(&s[1..3]).ok_or_else(illegal)?;
+            let from = square(f, r);
+            let before = pos.piece_on(from);
+            let promote =
+                before.is_some() && !before.piece_type().is_promoted() && pt.is_promoted();
+            Move::new_move(from, to, promote)
+        };
+        if color != pos.side_to_move() {
+            return Err(illegal());
+        }
+        let mv = legal_move(pos, mv).ok_or_else(illegal)?;
+        if mv.moved_piece_after().piece_type() != pt {
+            return Err(illegal());
+        }
+        let pos = self.pos.as_mut().expect("start() must be called before play()");
+        let gives_check = pos.gives_check(mv);
+        pos.do_move(mv, gives_check);
+        self.record.moves.push(RecordMove::new(mv));
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<GameRecord, RecordError> {
+        self.start()?;
+        Ok(self.record)
+    }
+}
+
+/// `%TORYO` などの終局（`%+ILLEGAL_ACTION` のように反則した側が付くこともある）
+pub(super) fn special_kind(special: &str, side_to_move: Color) -> Option<GameEndKind> {
+    let offender = match special.as_bytes().first() {
+        Some(b'+') => Some(Color::Black),
+        Some(b'-') => Some(Color::White),
+        _ => None,
+    };
+    let special = special.trim_start_matches(['+', '-']);
+    Some(match special {
+        "TORYO" => GameEndKind::Resign,
+        "CHUDAN" => GameEndKind::Interrupt,
+        "SENNICHITE" => GameEndKind::Repetition,
+        "JISHOGI" => GameEndKind::Impasse,
+        "MAX_MOVES" => GameEndKind::MaxMoves,
+        "HIKIWAKE" => GameEndKind::Draw,
+        "TSUMI" => GameEndKind::Mate,
+        "TIME_UP" => GameEndKind::Timeout,
+        "KACHI" => GameEndKind::EnteringKingWin,
+        "ILLEGAL_MOVE" | "ILLEGAL_ACTION" => {
+            if offender.unwrap_or(side_to_move) == side_to_move {
+                GameEndKind::IllegalLoss
+            } else {
+                GameEndKind::IllegalWin
+            }
+        }
+        _ => return None,
+    })
+}
+
+/// `T12` の秒数（小数可）をミリ秒にする
+fn parse_seconds(text: &str) -> Option<u64> {
+    let seconds: f64 = text.parse().ok()?;
+    (seconds >= 0.0).then(|| (seconds * 1000.0).round() as u64)
+}
+
+/// `76` 形式の升目（筋, 段の 0 始まり添字）
+fn csa_square(text: &str) -> Option<(usize, usize)> {
+    let bytes = text.as_bytes();
+    let f = bytes.first()?.checked_sub(b'1')? as usize;
+    let r = bytes.get(1)?.checked_sub(b'1')? as usize;
+    (f < File::NUM && r < Rank::NUM).then_some((f, r))
+}
+
+fn square(f: usize, r: usize) -> Square {
+    Square::new(File::ALL[f], Rank::ALL[r])
+}
+
+pub(super) fn piece_code(code: &str) -> Option<PieceType> {
+    PIECE_CODES.iter().find(|(c, _)| *c == code).map(|(_, pt)| *pt)
+}
+
+pub(super) fn code_of(pt: PieceType) -> &'static str {
+    PIECE_CODES.iter().find(|(_, p)| *p == pt).map_or("FU", |(c, _)| c)
+}
+
+/// `-KY` / `+FU` 形式の駒
+fn parse_piece(text: &str) -> Option<(Color, PieceType)> {
+    let color = match text.as_bytes().first()? {
+        b'+' => Color::Black,
+        b'-' => Color::White,
+        _ => return None,
+    };
+    Some((color, piece_code(text.get(1..3)?)?))
+}
+
+pub(super) fn sfen_piece(color: Color, pt: PieceType) -> String {
+    let letter = match pt.unpromote() {
+        PieceType::Pawn => 'P',
+        PieceType::Lance => 'L',
+        PieceType::Knight => 'N',
+        PieceType::Silver => 'S',
+        PieceType::Gold => 'G',
+        PieceType::Bishop => 'B',
+        PieceType::Rook => 'R',
+        _ => 'K',
+    };
+    let letter = if color == Color::White {
+        letter.to_ascii_lowercase()
+    } else {
+        letter
+    };
+    if pt.is_promoted() {
+        format!("+{letter}")
+    } else {
+        letter.to_string()
+    }
+}
+
+fn syntax(line: usize, message: String) -> RecordError {
+    RecordError::Syntax { line, message }
+}
+
+// ---------------------------------------------------------------------------
+// 書き出し
+// ---------------------------------------------------------------------------
+
+/// 開始局面（平手なら `PI`、それ以外は `P1`〜`P9` と持駒の `P+` / `P-`）と手番
+fn board_lines(pos: &Position) -> String {
+    let mut out = String::new();
+    let mut hirate = Position::new();
+    hirate.set_hirate();
+    let same_board = Square::all().all(|sq| pos.piece_on(sq) == hirate.piece_on(sq));
+    if same_board && pos.hand(Color::Black).is_empty() && pos.hand(Color::White).is_empty() {
+        out.push_str("PI\n");
+    } else {
+        for (r, rank) in Rank::ALL.iter().enumerate() {
+            out.push_str(&format!("P{}", r + 1));
+            for file in File::ALL.iter().rev() {
+                let pc = pos.piece_on(Square::new(*file, *rank));
+                if pc.is_none() {
+                    out.push_str(" * ");
+                } else {
+                    let sign = if pc.color() == Color::Black { '+' } else { '-' };
+                    out.push(sign);
+                    out.push_str(code_of(pc.piece_type()));
+                }
+            }
+            out.push('\n');
+        }
+        for color in [Color::Black, Color::White] {
+            let hand = pos.hand(color);
+            let mut items = String::new();
+            for (pt, _) in PIECE_COUNTS {
+                for _ in 0..hand.count(pt) {
+                    items.push_str("00");
+                    items.push_str(code_of(pt));
+                }
+            }
+            if !items.is_empty() {
+                let sign = if color == Color::Black { '+' } else { '-' };
+                out.push_str(&format!("P{sign}{items}\n"));
+            }
+        }
+    }
+    out.push_str(if pos.side_to_move() == Color::Black {
+        "+\n"
+    } else {
+        "-\n"
+    });
+    out
+}
+
+/// `+7776FU` 形式の指し手
+fn move_text(pos: &Position, mv: Move) -> String {
+    let sign = if pos.side_to_move() == Color::Black {
+        '+'
+    } else {
+        '-'
+    };
+    let from = if mv.is_drop() {
+        "00".to_string()
+    } else {
+        csa_square_text(mv.from())
+    };
+    let pt = pos.to_move(mv).map_or(PieceType::Pawn, |m| m.moved_piece_after().piece_type());
+    format!("{sign}{from}{}{}", csa_square_text(mv.to()), code_of(pt))
+}
+
+fn csa_square_text(sq: Square) -> String {
+    format!("{}{}", sq.file() as u8 + 1, sq.rank() as u8 + 1)
+}
+
+/// `T12` 行（1 秒未満は切り捨て）
+fn time_line(time: MoveTime) -> String {
+    format!("T{}\n", time.elapsed_ms / 1000)
+}
+
+/// 終局の `%` 行
+pub(super) fn special_text(kind: GameEndKind, side_to_move: Color) -> String {
+    match kind {
+        GameEndKind::Resign => "%TORYO".to_string(),
+        GameEndKind::Interrupt => "%CHUDAN".to_string(),
+        GameEndKind::Repetition => "%SENNICHITE".to_string(),
+        GameEndKind::Impasse => "%JISHOGI".to_string(),
+        GameEndKind::MaxMoves => "%MAX_MOVES".to_string(),
+        GameEndKind::Draw => "%HIKIWAKE".to_string(),
+        GameEndKind::Mate => "%TSUMI".to_string(),
+        GameEndKind::Timeout => "%TIME_UP".to_string(),
+        GameEndKind::EnteringKingWin => "%KACHI".to_string(),
+        GameEndKind::IllegalLoss => "%ILLEGAL_MOVE".to_string(),
+        GameEndKind::IllegalWin => {
+            // 反則したのは手番でない側
+            let sign = if side_to_move == Color::Black {
+                '-'
+            } else {
+                '+'
+            };
+            format!("%{sign}ILLEGAL_ACTION")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::record::kif;
+
+    /// floodgate の棋譜と同じ体裁のテスト用棋譜（KIF の testdata と同じ対局）
+    const FLOODGATE_CSA: &str = include_str!("testdata/floodgate.csa");
+
+    #[test]
+    fn parses_floodgate_csa() {
+        let record = parse(FLOODGATE_CSA).unwrap();
+        assert_eq!(record.header("先手"), Some("rshogi_test_black"));
+        assert_eq!(record.header("棋戦"), Some("floodgate"));
+        assert_eq!(record.header("$MAX_MOVES"), Some("256"));
+        assert_eq!(record.start_sfen, SFEN_HIRATE);
+        assert_eq!(record.moves.len(), 32);
+        assert_eq!(
+            record.moves[0].time,
+            Some(MoveTime {
+                elapsed_ms: 1000,
+                total_ms: None,
+            })
+        );
+        assert_eq!(record.moves[0].comments, vec!["** 52 3c3d".to_string()]);
+        assert_eq!(record.end.as_ref().map(|e| e.kind), Some(GameEndKind::Resign));
+
+        // 同じ対局の KIF と指し手・消費時間が一致する
+        let kif = kif::parse_str(include_str!("testdata/floodgate.kif")).unwrap();
+        assert_eq!(record.usi_moves(), kif.usi_moves());
+        let elapsed = |r: &GameRecord| {
+            r.moves.iter().map(|m| m.time.map(|t| t.elapsed_ms)).collect::<Vec<_>>()
+        };
+        assert_eq!(elapsed(&record), elapsed(&kif));
+    }
+
+    #[test]
+    fn csa_round_trip() {
+        let record = parse(FLOODGATE_CSA).unwrap();
+        let text = to_csa(&record).unwrap();
+        assert_eq!(parse(&text).unwrap(), record);
+        assert_eq!(to_csa(&parse(&text).unwrap()).unwrap(), text);
+    }
+
+    #[test]
+    fn inline_time_and_multi_game() {
+        let text = "V2.2\nPI\n+\n+7776FU,T3\n-3334FU,T5\n%CHUDAN\n/\nV2.2\nPI82HI22KA\n-\n-5142OU\nT1\n%TORYO\n";
+        let records = parse_all(text).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].usi_moves(), vec!["7g7f", "3c3d"]);
+        assert_eq!(records[0].moves[1].time.map(|t| t.elapsed_ms), Some(5000));
+        assert_eq!(records[0].end.as_ref().map(|e| e.kind), Some(GameEndKind::Interrupt));
+        assert_eq!(
+            records[1].start_sfen,
+            "lnsgkgsnl/9/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL w - 1"
+        );
+        assert_eq!(records[1].usi_moves(), vec!["5a4b"]);
+        assert_eq!(records[1].end.as_ref().and_then(|e| e.time).map(|t| t.elapsed_ms), None);
+        assert_eq!(records[1].moves[0].time.map(|t| t.elapsed_ms), Some(1000));
+        // 先頭の 1 局だけ読む
+        assert_eq!(parse(text).unwrap(), records[0]);
+    }
+
+    #[test]
+    fn board_and_hand_setup_round_trip() {
+        let text = "\
+P1-KY-KE * -KI * +RY *  * -KY
+P2 *  * -GI-OU *  *  *  *  *
+P3-FU * -FU-FU-FU * -FU * -FU
+P4 *  *  *  *  *  * -FU *  *
+P5 * -FU *  *  *  *  *  *  *
+P6 *  * +FU *  *  * +FU *  *
+P7+FU+FU * +FU+FU+FU *  * +FU
+P8 * +KA+OU *  *  *  *  *  *
+P9+KY+KE+GI+KI * +KI * +KE+KY
+P+00KA00KI00GI00GI
+P-00AL
+-
+-6271OU
+%TORYO
+";
+        let record = parse(text).unwrap();
+        assert_eq!(
+            record.start_sfen,
+            "ln1g1+R2l/2sk5/p1ppp1p1p/6p2/1p7/2P3P2/PP1PPP2P/1BK6/LNSG1G1NL w BG2Srn2p 1"
+        );
+        assert_eq!(record.usi_moves(), vec!["6b7a"]);
+        let again = parse(&to_csa(&record).unwrap()).unwrap();
+        assert_eq!(again, record);
+    }
+
+    #[test]
+    fn illegal_move_is_reported() {
+        let text = "PI\n+\n+7776FU\n+2726FU\n";
+        assert!(matches!(
+            parse(text),
+            Err(RecordError::IllegalMove {
+                line: 4,
+                ply: 2,
+                ..
+            })
+        ));
+        // 成りの表記が盤上の駒と合わない
+        let text = "PI\n+\n+7776TO\n";
+        assert!(matches!(parse(text), Err(RecordError::IllegalMove { ply: 1, .. })));
+    }
+
+    #[test]
+    fn illegal_action_side() {
+        assert_eq!(special_kind("+ILLEGAL_ACTION", Color::White), Some(GameEndKind::IllegalWin));
+        assert_eq!(special_kind("-ILLEGAL_ACTION", Color::White), Some(GameEndKind::IllegalLoss));
+        assert_eq!(special_text(GameEndKind::IllegalWin, Color::White), "%+ILLEGAL_ACTION");
+    }
+}
@@ -0,0 +1,661 @@
+//! JKF 形式（JSON Kifu Format）
+//!
+//! デスクトップ / Web のフロントエンドとの棋譜のやり取り用。JKF の構造をそのまま
+//! serde の型（[`JsonKifu`] など）で表し、[`GameRecord`] と相互に変換する。
+//! JSON 文字列との変換は呼び出し側の `serde_json` などで行う。
+//!
+//! - 開始局面: `initial.preset`（`HIRATE` / `KY` などの駒落ち）と `OTHER` の `data`
+//! - 指し手: `move`（駒・成・取った駒・同）、`time`、`comments`、終局の `special`
+//! - 変化: `forks`（[`RecordMove::forks`] / [`Variation`]）
+//!
+//! 指し手は読み込み時に合法性を検査する。JKF には行番号が無いため、エラーの `line` は 0。
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::csa::{code_of, piece_code, sfen_piece, special_kind, special_text};
+use super::kif::HANDICAPS;
+use super::{
+    GameEnd, GameRecord, MoveTime, RecordError, RecordMove, Variation, legal_move, moved_piece_type,
+};
+use crate::position::{Position, SFEN_HIRATE};
+use crate::types::{Color, File, Move, PieceType, Rank, Square};
+
+/// JKF の駒落ちの名前と KIF の手合割
+const PRESETS: [(&str, &str); 11] = [
+    ("HIRATE", "平手"),
+    ("KY", "香落ち"),
+    ("KY_R", "右香落ち"),
+    ("KA", "角落ち"),
+    ("HI", "飛車落ち"),
+    ("HIKY", "飛香落ち"),
+    ("2", "二枚落ち"),
+    ("4", "四枚落ち"),
+    ("6", "六枚落ち"),
+    ("8", "八枚落ち"),
+    ("10", "十枚落ち"),
+];
+
+/// 持駒の駒種（JKF の `hands` のキー）
+const HAND_PIECES: [PieceType; 7] = [
+    PieceType::Rook,
+    PieceType::Bishop,
+    PieceType::Gold,
+    PieceType::Silver,
+    PieceType::Knight,
+    PieceType::Lance,
+    PieceType::Pawn,
+];
+
+/// JKF の棋譜
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JsonKifu {
+    /// ヘッダ（出現順を保つ）
+    #[serde(default, with = "header_map")]
+    pub header: Vec<(String, String)>,
+    /// 開始局面（省略時は平手）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub initial: Option<JkfInitial>,
+    /// 指し手。先頭は初手より前のコメントだけを持つ要素
+    pub moves: Vec<JkfMoveFormat>,
+}
+
+/// 開始局面
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JkfInitial {
+    /// `HIRATE` / `KY` などの駒落ち、任意局面なら `OTHER`
+    pub preset: String,
+    /// `OTHER` の局面
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<JkfState>,
+}
+
+/// 任意局面
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JkfState {
+    /// 手番（0: 先手、1: 後手）
+    pub color: u8,
+    /// 盤面 `board[筋 - 1][段 - 1]`
+    pub board: Vec<Vec<JkfPiece>>,
+    /// 持駒 `hands[手番]`（`FU` などの CSA 表記と枚数）
+    pub hands: Vec<BTreeMap<String, u32>>,
+}
+
+/// 盤上の駒（空き升は `{}`）
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JkfPiece {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<u8>,
+    /// CSA 表記の駒種（成駒を含む）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>,
+}
+
+/// 指し手欄の 1 要素（指し手、または終局）
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JkfMoveFormat {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub comments: Vec<String>,
+    #[serde(rename = "move", default, skip_serializing_if = "Option::is_none")]
+    pub mv: Option<JkfMove>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time: Option<JkfTime>,
+    /// 終局（`TORYO` などの CSA の特殊な指し手）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub special: Option<String>,
+    /// この手の代わりの変化
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub forks: Vec<Vec<JkfMoveFormat>>,
+}
+
+/// 指し手
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JkfMove {
+    /// 移動元（駒打ちなら `None`）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from: Option<JkfPlace>,
+    pub to: JkfPlace,
+    /// 動かす前の駒（CSA 表記）
+    pub piece: String,
+    /// 手番（0: 先手、1: 後手）
+    pub color: u8,
+    /// 直前の手と同じ升に動いたか（「同」）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub same: Option<bool>,
+    /// 成ったか（成れない手では `None`）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub promote: Option<bool>,
+    /// 取った駒（CSA 表記）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub capture: Option<String>,
+    /// KI2 の相対位置（`L` / `C` / `R` / `U` / `M` / `D` / `H`）。読み込みでは使わない
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub relative: Option<String>,
+}
+
+/// 升目（`x` が筋、`y` が段。いずれも 1 始まり）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JkfPlace {
+    pub x: u8,
+    pub y: u8,
+}
+
+/// 消費時間
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JkfTime {
+    /// この手の消費時間
+    pub now: JkfTimeValue,
+    /// 手番側の累計消費時間
+    pub total: JkfTimeValue,
+}
+
+/// 時間（`h` は省略可）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JkfTimeValue {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub h: Option<u64>,
+    pub m: u64,
+    pub s: u64,
+}
+
+impl JkfTimeValue {
+    fn from_ms(ms: u64, with_hours: bool) -> Self {
+        let sec = ms / 1000;
+        if with_hours {
+            Self {
+                h: Some(sec / 3600),
+                m: sec / 60 % 60,
+                s: sec % 60,
+            }
+        } else {
+            Self {
+                h: None,
+                m: sec / 60,
+                s: sec % 60,
+            }
+        }
+    }
+
+    fn to_ms(self) -> u64 {
+        ((self.h.unwrap_or(0) * 60 + self.m) * 60 + self.s) * 1000
+    }
+}
+
+/// JKF を読む
+pub fn from_jkf(jkf: &JsonKifu) -> Result<GameRecord, RecordError> {
+    let start_sfen = match &jkf.initial {
+        None => SFEN_HIRATE.to_string(),
+        Some(initial) if initial.preset == "OTHER" => {
+            let data = initial
+                .data
+                .as_ref()
+                .ok_or_else(|| syntax("initial.data is required for OTHER".to_string()))?;
+            state_to_sfen(data)?
+        }
+        Some(initial) => preset_sfen(&initial.preset)
+            .ok_or_else(|| syntax(format!("unsupported preset: {}", initial.preset)))?
+            .to_string(),
+    };
+    let mut pos = Position::new();
+    pos.set_sfen(&start_sfen).map_err(RecordError::Position)?;
+
+    let (head, moves) = match jkf.moves.split_first() {
+        Some((head, moves)) if head.mv.is_none() && head.special.is_none() => (Some(head), moves),
+        _ => (None, jkf.moves.as_slice()),
+    };
+    let line = read_line(&mut pos.clone(), moves, 1)?;
+    Ok(GameRecord {
+        headers: jkf.header.clone(),
+        start_sfen: pos.to_sfen(),
+        comments: head.map(|h| h.comments.clone()).unwrap_or_default(),
+        moves: line.moves,
+        end: line.end,
+    })
+}
+
+/// JKF に変換する
+pub fn to_jkf(record: &GameRecord) -> Result<JsonKifu, RecordError> {
+    let pos = record.start_position()?;
+    let mut moves = vec![JkfMoveFormat {
+        comments: record.comments.clone(),
+        ..JkfMoveFormat::default()
+    }];
+    moves.extend(write_line(
+        &mut pos.clone(),
+        &record.moves,
+        record.end.as_ref(),
+        None,
+        [0; Color::NUM],
+    ));
+    Ok(JsonKifu {
+        header: record.headers.clone(),
+        initial: Some(initial_of(&pos)),
+        moves,
+    })
+}
+
+// ---------------------------------------------------------------------------
+// 読み込み
+// ---------------------------------------------------------------------------
+
+/// 指し手列（本譜または変化）を `pos` から読む。`ply` は初手の手数
+fn read_line(
+    pos: &mut Position,
+    items: &[JkfMoveFormat],
+    ply: usize,
+) -> Result<Variation, RecordError> {
+    let mut line = Variation::default();
+    for (i, item) in items.iter().enumerate() {
+        let ply = ply + i;
+        let time = item.time.map(|t| MoveTime {
+            elapsed_ms: t.now.to_ms(),
+            total_ms: Some(t.total.to_ms()),
+        });
+        if let Some(special) = &item.special {
+            let kind = special_kind(special, pos.side_to_move())
+                .ok_or_else(|| syntax(format!("unknown special move at ply {ply}: {special}")))?;
+            line.end = Some(GameEnd {
+                kind,
+                time,
+                comments: item.comments.clone(),
+            });
+            break;
+        }
+        let jkf_move = item
+            .mv
+            .as_ref()
+            .ok_or_else(|| syntax(format!("move or special is required at ply {ply}")))?;
+        let mv = read_move(pos, jkf_move).ok_or_else(|| RecordError::IllegalMove {
+            line: 0,
+            ply,
+            text: format!("{jkf_move:?}"),
+        })?;
+        let mut forks = Vec::with_capacity(item.forks.len());
+        for fork in &item.forks {
+            forks.push(read_line(&mut pos.clone(), fork, ply)?);
+        }
+        let gives_check = pos.gives_check(mv);
+        pos.do_move(mv, gives_check);
+        line.moves.push(RecordMove {
+            time,
+            comments: item.comments.clone(),
+            forks,
+            ..RecordMove::new(mv)
+        });
+    }
+    Ok(line)
+}
+
+/// JKF の指し手を合法手にする（非合法・駒が合わなければ `None`）
+fn read_move(pos: &Position, m: &JkfMove) -> Option<Move> {
+    let color = if m.color == 0 {
+        Color::Black
+    } else {
+        Color::White
+    };
+    if color != pos.side_to_move() {
+        return None;
+    }
+    let piece = piece_code(&m.piece)?;
+    let to = place_square(m.to)?;
+    let mv = match m.from {
+        Some(from) => Move::new_move(place_square(from)?, to, m.promote == Some(true)),
+        None => Move::new_drop(piece, to),
+    };
+    let mv = legal_move(pos, mv)?;
+    (moved_piece_type(pos, mv) == piece).then_some(mv)
+}
+
+fn place_square(place: JkfPlace) -> Option<Square> {
+    let file = File::ALL.get(usize::from(place.x).checked_sub(1)?)?;
+    let rank = Rank::ALL.get(usize::from(place.y).checked_sub(1)?)?;
+    Some(Square::new(*file, *rank))
+}
+
+fn preset_sfen(preset: &str) -> Option<&'static str> {
+    let (_, name) = PRESETS.iter().find(|(p, _)| *p == preset)?;
+    HANDICAPS.iter().find(|(n, _)| n == name).map(|(_, sfen)| *sfen)
+}
+
+/// `OTHER` の局面を SFEN にする
+fn state_to_sfen(state: &JkfState) -> Result<String, RecordError> {
+    if state.board.len() != File::NUM || state.board.iter().any(|col| col.len() != Rank::NUM) {
+        return Err(syntax("initial.data.board must be 9x9".to_string()));
+    }
+    let piece = |p: &JkfPiece| -> Result<Option<(Color, PieceType)>, RecordError> {
+        let Some(kind) = &p.kind else {
+            return Ok(None);
+        };
+        let pt = piece_code(kind).ok_or_else(|| syntax(format!("invalid piece kind: {kind}")))?;
+        let color = if p.color == Some(1) {
+            Color::White
+        } else {
+            Color::Black
+        };
+        Ok(Some((color, pt)))
+    };
+    let mut rows = Vec::with_capacity(Rank::NUM);
+    for r in 0..Rank::NUM {
+        let mut row = String::new();
+        let mut empty = 0;
+        for f in (0..File::NUM).rev() {
+            match piece(&state.board[f][r])? {
+                None => empty += 1,
+                Some((color, pt)) => {
+                    if empty > 0 {
+                        row.push_str(&empty.to_string());
+                        empty = 0;
+                    }
+                    row.push_str(&sfen_piece(color, pt));
+                }
+            }
+        }
+        if empty > 0 {
+            row.push_str(&empty.to_string());
+        }
+        rows.push(row);
+    }
+    let mut hand = String::new();
+    for (i, color) in [Color::Black, Color::White].into_iter().enumerate() {
+        let Some(counts) = state.hands.get(i) else {
+            continue;
+        };
+        for pt in HAND_PIECES {
+            let n = counts.get(code_of(pt)).copied().unwrap_or(0);
+            if n > 1 {
+                hand.push_str(&n.to_string());
+            }
+            if n > 0 {
+                hand.push_str(&sfen_piece(color, pt));
+            }
+        }
+    }
+    if hand.is_empty() {
+        hand.push('-');
+    }
+    let side = if state.color == 0 { 'b' } else { 'w' };
+    Ok(format!("{} {side} {hand} 1", rows.join("/")))
+}
+
+fn syntax(message: String) -> RecordError {
+    RecordError::Syntax { line: 0, message }
+}
+
+// ---------------------------------------------------------------------------
+// 書き出し
+// ---------------------------------------------------------------------------
+
+/// 指し手列（本譜または変化）を書く。`prev_to` は直前の手の移動先、
+/// `totals` は累計消費時間が棋譜に無いときに積算する手番ごとの消費時間
+fn write_line(
+    pos: &mut Position,
+    moves: &[RecordMove],
+    end: Option<&GameEnd>,
+    mut prev_to: Option<Square>,
+    mut totals: [u64; Color::NUM],
+) -> Vec<JkfMoveFormat> {
+    let mut items = Vec::with_capacity(moves.len() + 1);
+    for m in moves {
+        let side = pos.side_to_move();
+        let forks = m
+            .forks
+            .iter()
+            .map(|fork| {
+                write_line(&mut pos.clone(), &fork.moves, fork.end.as_ref(), prev_to, totals)
+            })
+            .collect();
+        items.push(JkfMoveFormat {
+            comments: m.comments.clone(),
+            mv: Some(write_move(pos, m.mv, prev_to)),
+            time: m.time.map(|t| jkf_time(t, side, &mut totals)),
+            special: None,
+            forks,
+        });
+        prev_to = Some(m.mv.to());
+        let gives_check = pos.gives_check(m.mv);
+        pos.do_move(m.mv, gives_check);
+    }
+    if let Some(end) = end {
+        let side = pos.side_to_move();
+        let special = special_text(end.kind, side);
+        items.push(JkfMoveFormat {
+            comments: end.comments.clone(),
+            time: end.time.map(|t| jkf_time(t, side, &mut totals)),
+            special: Some(special.trim_start_matches('%').to_string()),
+            ..JkfMoveFormat::default()
+        });
+    }
+    items
+}
+
+fn write_move(pos: &Position, mv: Move, prev_to: Option<Square>) -> JkfMove {
+    let color = pos.side_to_move();
+    let pt = moved_piece_type(pos, mv);
+    let to = mv.to();
+    let from = (!mv.is_drop()).then(|| mv.from());
+    let can_promote = from.is_some_and(|from| {
+        pt.promote().is_some() && (from.rank().can_promote(color) || to.rank().can_promote(color))
+    });
+    let captured = pos.piece_on(to);
+    JkfMove {
+        from: from.map(square_place),
+        to: square_place(to),
+        piece: code_of(pt).to_string(),
+        color: color.index() as u8,
+        same: (prev_to == Some(to)).then_some(true),
+        promote: can_promote.then(|| mv.is_promote()),
+        capture: captured.is_some().then(|| code_of(captured.piece_type()).to_string()),
+        relative: None,
+    }
+}
+
+fn square_place(sq: Square) -> JkfPlace {
+    JkfPlace {
+        x: sq.file() as u8 + 1,
+        y: sq.rank() as u8 + 1,
+    }
+}
+
+fn jkf_time(time: MoveTime, side: Color, totals: &mut [u64; Color::NUM]) -> JkfTime {
+    totals[side.index()] += time.elapsed_ms;
+    let total = time.total_ms.unwrap_or(totals[side.index()]);
+    JkfTime {
+        now: JkfTimeValue::from_ms(time.elapsed_ms, false),
+        total: JkfTimeValue::from_ms(total, true),
+    }
+}
+
+/// 開始局面（駒落ちの表に一致すればその名前、それ以外は `OTHER`）
+fn initial_of(pos: &Position) -> JkfInitial {
+    let sfen = pos.to_sfen();
+    if let Some((preset, _)) = PRESETS.iter().find(|(preset, _)| preset_sfen(preset) == Some(&sfen))
+    {
+        return JkfInitial {
+            preset: preset.to_string(),
+            data: None,
+        };
+    }
+    let board = File::ALL
+        .iter()
+        .map(|file| {
+            Rank::ALL
+                .iter()
+                .map(|rank| {
+                    let pc = pos.piece_on(Square::new(*file, *rank));
+                    if pc.is_none() {
+                        JkfPiece::default()
+                    } else {
+                        JkfPiece {
+                            color: Some(pc.color().index() as u8),
+                            kind: Some(code_of(pc.piece_type()).to_string()),
+                        }
+                    }
+                })
+                .collect()
+        })
+        .collect();
+    let hands = [Color::Black, Color::White]
+        .iter()
+        .map(|color| {
+            let hand = pos.hand(*color);
+            HAND_PIECES
+                .iter()
+                .map(|pt| (code_of(*pt).to_string(), hand.count(*pt)))
+                .collect()
+        })
+        .collect();
+    JkfInitial {
+        preset: "OTHER".to_string(),
+        data: Some(JkfState {
+            color: pos.side_to_move().index() as u8,
+            board,
+            hands,
+        }),
+    }
+}
+
+/// ヘッダを出現順のまま JSON のオブジェクトとして読み書きする
+mod header_map {
+    use std::fmt;
+
+    use serde::de::{MapAccess, Visitor};
+    use serde::ser::SerializeMap;
+    use serde::{Deserializer, Serializer};
+
+    pub(super) fn serialize<S: Serializer>(
+        headers: &[(String, String)],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(headers.len()))?;
+        for (key, value) in headers {
+            map.serialize_entry(key, value)?;
+        }
+        map.end()
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<(String, String)>, D::Error> {
+        struct HeaderVisitor;
+
+        impl<'de> Visitor<'de> for HeaderVisitor {
+            type Value = Vec<(String, String)>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a map of header strings")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let mut headers = Vec::new();
+                while let Some(entry) = map.next_entry()? {
+                    headers.push(entry);
+                }
+                Ok(headers)
+            }
+        }
+
+        deserializer.deserialize_map(HeaderVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::record::{GameEndKind, kif};
+
+    #[test]
+    fn jkf_round_trip_keeps_record() {
+        let record = kif::parse_str(include_str!("testdata/floodgate.kif")).unwrap();
+        let jkf = to_jkf(&record).unwrap();
+        assert_eq!(jkf.initial.as_ref().map(|i| i.preset.as_str()), Some("HIRATE"));
+        assert_eq!(jkf.moves.len(), 1 + 32 + 1);
+        assert_eq!(jkf.moves[33].special.as_deref(), Some("TORYO"));
+        // 22 手目「同　桂」
+        let m22 = jkf.moves[22].mv.as_ref().unwrap();
+        assert_eq!(m22.same, Some(true));
+        assert_eq!(m22.capture.as_deref(), Some("UM"));
+        // 31 手目「４三角不成」
+        assert_eq!(jkf.moves[31].mv.as_ref().unwrap().promote, Some(false));
+
+        let json = serde_json::to_string(&jkf).unwrap();
+        let parsed: JsonKifu = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, jkf);
+        assert_eq!(parsed.header.first().map(|(k, _)| k.as_str()), Some("開始日時"));
+        assert_eq!(from_jkf(&parsed).unwrap(), record);
+    }
+
+    #[test]
+    fn reads_jkf_with_forks() {
+        let json = r#"{
+            "header": {"先手": "A", "後手": "B"},
+            "initial": {"preset": "KA"},
+            "moves": [
+                {"comments": ["角落ち"]},
+                {"move": {"from": {"x": 5, "y": 1}, "to": {"x": 4, "y": 2}, "piece": "OU", "color": 1},
+                 "forks": [[
+                    {"move": {"from": {"x": 3, "y": 3}, "to": {"x": 3, "y": 4}, "piece": "FU", "color": 1}},
+                    {"move": {"from": {"x": 7, "y": 7}, "to": {"x": 7, "y": 6}, "piece": "FU", "color": 0}},
+                    {"special": "TORYO"}
+                 ]]},
+                {"move": {"from": {"x": 7, "y": 7}, "to": {"x": 7, "y": 6}, "piece": "FU", "color": 0},
+                 "time": {"now": {"m": 0, "s": 3}, "total": {"h": 0, "m": 1, "s": 3}}}
+            ]
+        }"#;
+        let jkf: JsonKifu = serde_json::from_str(json).unwrap();
+        let record = from_jkf(&jkf).unwrap();
+        assert_eq!(
+            record.start_sfen,
+            "lnsgkgsnl/1r7/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL w - 1"
+        );
+        assert_eq!(record.header("後手"), Some("B"));
+        assert_eq!(record.comments, vec!["角落ち".to_string()]);
+        assert_eq!(record.usi_moves(), vec!["5a4b", "7g7f"]);
+        assert_eq!(
+            record.moves[1].time,
+            Some(MoveTime {
+                elapsed_ms: 3000,
+                total_ms: Some(63_000),
+            })
+        );
+        let fork = &record.moves[0].forks[0];
+        assert_eq!(
+            fork.moves.iter().map(|m| m.mv.to_usi()).collect::<Vec<_>>(),
+            vec!["3c3d", "7g7f"]
+        );
+        assert_eq!(fork.end.as_ref().map(|e| e.kind), Some(GameEndKind::Resign));
+
+        // 書き出して読み直しても変化を含めて一致する
+        assert_eq!(from_jkf(&to_jkf(&record).unwrap()).unwrap(), record);
+    }
+
+    #[test]
+    fn other_initial_round_trip() {
+        let record = GameRecord {
+            start_sfen: "4k4/9/4P4/9/9/9/9/9/4K4 b G2r2b3g4s4n4l17p 1".to_string(),
+            ..GameRecord::default()
+        };
+        let jkf = to_jkf(&record).unwrap();
+        let initial = jkf.initial.as_ref().unwrap();
+        assert_eq!(initial.preset, "OTHER");
+        let data = initial.data.as_ref().unwrap();
+        assert_eq!(data.board[4][2].kind.as_deref(), Some("FU"));
+        assert_eq!(data.hands[0].get("KI"), Some(&1));
+        assert_eq!(from_jkf(&jkf).unwrap(), record);
+    }
+
+    #[test]
+    fn rejects_illegal_move() {
+        let json = r#"{"header": {}, "moves": [{},
+            {"move": {"from": {"x": 7, "y": 7}, "to": {"x": 7, "y": 5}, "piece": "FU", "color": 0}}]}"#;
+        let jkf: JsonKifu = serde_json::from_str(json).unwrap();
+        assert!(matches!(
+            from_jkf(&jkf),
+            Err(RecordError::IllegalMove {
+                line: 0,
+                ply: 1,
+                ..
+            })
+        ));
+    }
+}
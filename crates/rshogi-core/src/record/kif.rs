@@ -0,0 +1,1097 @@
+//! KIF / KI2 形式
+//!
+//! 柿木将棋形式の棋譜を読み書きする。
+//!
+//! - KIF: `   1 ７六歩(77)   ( 0:01/00:00:01)` のように 1 行 1 手で移動元を持つ形式
+//! - KI2: `▲７六歩 △３四歩` のように移動元を持たず、`右` / `引` などの動作で
+//!   駒を区別する形式（読み込みのみ）
+//!
+//! 開始局面は `手合割` の駒落ち名か、盤面図（BOD）で表す。`手合割` と盤面図は
+//! [`GameRecord::start_sfen`] に反映し、[`GameRecord::headers`] には含めない。
+//! `*` で始まるコメント行は直前の指し手（初手より前なら棋譜全体）のコメントになる。
+//! 変化（`変化：` 以降）は読まない。
+//!
+//! ファイルの文字コードは [`decode`] で判定する（BOM 付き・妥当な UTF-8 ならそのまま、
+//! それ以外は Shift_JIS）。
+
+use std::borrow::Cow;
+
+use super::{
+    GameEnd, GameEndKind, GameRecord, MoveTime, RecordError, RecordMove, legal_move, legal_moves,
+    moved_piece_type,
+};
+use crate::position::{Position, SFEN_HIRATE};
+use crate::types::{Color, File, Move, PieceType, Rank, Square};
+
+/// 駒落ちの名前と開始局面
+pub(super) const HANDICAPS: [(&str, &str); 11] = [
+    ("平手", SFEN_HIRATE),
+    ("香落ち", "lnsgkgsn1/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL w - 1"),
+    ("右香落ち", "1nsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL w - 1"),
+    ("角落ち", "lnsgkgsnl/1r7/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL w - 1"),
+    ("飛車落ち", "lnsgkgsnl/7b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL w - 1"),
+    ("飛香落ち", "lnsgkgsn1/7b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL w - 1"),
+    ("二枚落ち", "lnsgkgsnl/9/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL w - 1"),
+    ("四枚落ち", "1nsgkgsn1/9/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL w - 1"),
+    ("六枚落ち", "2sgkgs2/9/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL w - 1"),
+    ("八枚落ち", "3gkg3/9/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL w - 1"),
+    ("十枚落ち", "4k4/9/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL w - 1"),
+];
+
+/// 終局を表す指し手欄の語
+const END_WORDS: [(&str, GameEndKind); 11] = [
+    ("投了", GameEndKind::Resign),
+    ("中断", GameEndKind::Interrupt),
+    ("千日手", GameEndKind::Repetition),
+    ("持将棋", GameEndKind::Impasse),
+    ("詰み", GameEndKind::Mate),
+    ("切れ負け", GameEndKind::Timeout),
+    ("時間切れ", GameEndKind::Timeout),
+    ("反則勝ち", GameEndKind::IllegalWin),
+    ("反則負け", GameEndKind::IllegalLoss),
+    ("入玉勝ち", GameEndKind::EnteringKingWin),
+    ("引き分け", GameEndKind::Draw),
+];
+
+/// 筋の全角数字（1 筋から）
+const FILE_CHARS: [char; 9] = ['１', '２', '３', '４', '５', '６', '７', '８', '９'];
+
+/// 段の漢数字（1 段目から）
+const RANK_CHARS: [char; 9] = ['一', '二', '三', '四', '五', '六', '七', '八', '九'];
+
+/// 指し手欄の見出し行
+const MOVES_HEADER: &str = "手数----指手---------消費時間--";
+
+/// バイト列を文字列にする（UTF-8 として妥当ならそのまま、それ以外は Shift_JIS）
+pub fn decode(bytes: &[u8]) -> Cow<'_, str> {
+    let bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes);
+    match std::str::from_utf8(bytes) {
+        Ok(text) => Cow::Borrowed(text),
+        Err(_) => encoding_rs::SHIFT_JIS.decode_without_bom_handling(bytes).0,
+    }
+}
+
+/// 文字列を Shift_JIS にする（`.kif` を Shift_JIS で読む GUI 向け）
+pub fn encode_shift_jis(text: &str) -> Vec<u8> {
+    encoding_rs::SHIFT_JIS.encode(text).0.into_owned()
+}
+
+/// KIF / KI2 のバイト列を読む（文字コードと形式は自動判定）
+pub fn parse(bytes: &[u8]) -> Result<GameRecord, RecordError> {
+    parse_str(&decode(bytes))
+}
+
+/// KIF / KI2 の文字列を読む（行頭が手数の指し手行があれば KIF、なければ KI2）
+pub fn parse_str(text: &str) -> Result<GameRecord, RecordError> {
+    if text.lines().any(|line| kif_move_line(line).is_some()) {
+        parse_kif(text)
+    } else {
+        parse_ki2(text)
+    }
+}
+
+/// KIF 形式を読む
+pub fn parse_kif(text: &str) -> Result<GameRecord, RecordError> {
+    let mut builder = Builder::default();
+    for (idx, line) in text.lines().enumerate() {
+        let line_no = idx + 1;
+        let line = line.trim_end();
+        if line.trim().is_empty() || builder.common_line(line_no, line)? {
+            continue;
+        }
+        if line.starts_with("変化：") {
+            break;
+        }
+        let Some((number, body)) = kif_move_line(line) else {
+            return Err(syntax(line_no, format!("unexpected line: {line}")));
+        };
+        builder.start(line_no)?;
+        let ply = builder.record.moves.len() + 1;
+        if number != ply {
+            return Err(syntax(line_no, format!("expected move number {ply}, found {number}")));
+        }
+        let (text, rest) = split_move_text(body);
+        let time = parse_time(rest).map_err(|m| syntax(line_no, m))?;
+        if let Some(kind) = end_kind(&text) {
+            builder.end(GameEnd {
+                kind,
+                time,
+                comments: Vec::new(),
+            });
+            continue;
+        }
+        let mv = builder.kif_move(line_no, &text)?;
+        builder.push_move(RecordMove {
+            time,
+            ..RecordMove::new(mv)
+        });
+    }
+    builder.finish()
+}
+
+/// KI2 形式を読む
+pub fn parse_ki2(text: &str) -> Result<GameRecord, RecordError> {
+    let mut builder = Builder::default();
+    for (idx, line) in text.lines().enumerate() {
+        let line_no = idx + 1;
+        let line = line.trim_end();
+        if line.trim().is_empty() || builder.common_line(line_no, line)? {
+            continue;
+        }
+        if line.starts_with("変化：") {
+            break;
+        }
+        if !line.contains(['▲', '△', '☗', '☖']) {
+            return Err(syntax(line_no, format!("unexpected line: {line}")));
+        }
+        builder.start(line_no)?;
+        for token in line.split(['▲', '△', '☗', '☖']).skip(1) {
+            let token: String = token.chars().filter(|c| !c.is_whitespace()).collect();
+            if token.is_empty() {
+                continue;
+            }
+            if let Some(kind) = end_kind(&token) {
+                builder.end(GameEnd::new(kind));
+                continue;
+            }
+            let mv = builder.ki2_move(line_no, &token)?;
+            builder.push_move(RecordMove::new(mv));
+        }
+    }
+    builder.finish()
+}
+
+/// KIF 形式で書き出す
+///
+/// 開始局面が駒落ちの定型なら `手合割`、それ以外は盤面図で表す。消費時間の累計が
+/// 無い手は、手番側の消費時間を合算して補う。
+pub fn to_kif(record: &GameRecord) -> Result<String, RecordError> {
+    let mut pos = record.start_position()?;
+    let mut out = String::new();
+    for (key, value) in &record.headers {
+        out.push_str(&format!("{key}：{value}\n"));
+    }
+    match handicap_name(&pos) {
+        Some(name) => out.push_str(&format!("手合割：{name}\n")),
+        None => out.push_str(&board_diagram(&pos)),
+    }
+    for comment in &record.comments {
+        out.push_str(&format!("*{comment}\n"));
+    }
+    out.push_str(MOVES_HEADER);
+    out.push('\n');
+
+    let mut totals = [0u64; Color::NUM];
+    let mut last_to = None;
+    let mut time_text = |side: Color, time: Option<MoveTime>| {
+        time.map(|t| {
+            let total = t.total_ms.unwrap_or(totals[side.index()] + t.elapsed_ms);
+            totals[side.index()] = total;
+            format_time(t.elapsed_ms, total)
+        })
+    };
+    for (i, m) in record.moves.iter().enumerate() {
+        let text = kif_move_text(&pos, m.mv, last_to);
+        let time = time_text(pos.side_to_move(), m.time);
+        out.push_str(&move_line(i + 1, &text, time.as_deref()));
+        for comment in &m.comments {
+            out.push_str(&format!("*{comment}\n"));
+        }
+        last_to = Some(m.mv.to());
+        let gives_check = pos.gives_check(m.mv);
+        pos.do_move(m.mv, gives_check);
+    }
+    let num_moves = record.moves.len();
+    if let Some(end) = &record.end {
+        let time = time_text(pos.side_to_move(), end.time);
+        out.push_str(&move_line(num_moves + 1, end_word(end.kind), time.as_deref()));
+        for comment in &end.comments {
+            out.push_str(&format!("*{comment}\n"));
+        }
+        out.push_str(&summary(end.kind, pos.side_to_move(), num_moves));
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+// ---------------------------------------------------------------------------
+// 読み込み
+// ---------------------------------------------------------------------------
+
+/// KIF / KI2 共通の読み込み状態
+#[derive(Default)]
+struct Builder {
+    record: GameRecord,
+    /// `手合割` の値
+    handicap: Option<String>,
+    /// 盤面図の各段（`|` の間の文字列）
+    board: Vec<String>,
+    /// 盤面図の持駒（先手, 後手）
+    hands: [Option<String>; Color::NUM],
+    /// 盤面図の手番
+    side_to_move: Option<Color>,
+    /// 盤面図の `手数＝`
+    board_ply: Option<i32>,
+    /// 初手を読み始めた後の局面
+    pos: Option<Position>,
+    /// 直前の手の移動先（`同` 用）
+    last_to: Option<Square>,
+}
+
+impl Builder {
+    /// ヘッダ・盤面図・コメントなど指し手以外の行を処理する（処理したら `true`）
+    fn common_line(&mut self, line_no: usize, line: &str) -> Result<bool, RecordError> {
+        if line.starts_with('#') || line.starts_with('&') || line.starts_with(MOVES_HEADER) {
+            return Ok(true);
+        }
+        if let Some(comment) = line.strip_prefix('*') {
+            let comments = match (&mut self.record.end, self.record.moves.last_mut()) {
+                (Some(end), _) => &mut end.comments,
+                (None, Some(m)) => &mut m.comments,
+                (None, None) => &mut self.record.comments,
+            };
+            comments.push(comment.to_string());
+            return Ok(true);
+        }
+        if let Some(summary) = line.trim_start().strip_prefix("まで") {
+            if self.record.end.is_none() && self.pos.is_some() {
+                self.record.end = summary_kind(summary, self.side()).map(GameEnd::new);
+            }
+            return Ok(true);
+        }
+        if self.pos.is_some() {
+            return Ok(false);
+        }
+        if let Some(row) = line.strip_prefix('|') {
+            let row = row.split('|').next().unwrap_or_default();
+            self.board.push(row.to_string());
+            return Ok(true);
+        }
+        let trimmed = line.trim();
+        if trimmed.starts_with('+') || trimmed.starts_with('９') {
+            return Ok(true);
+        }
+        match trimmed {
+            "先手番" | "下手番" => {
+                self.side_to_move = Some(Color::Black);
+                return Ok(true);
+            }
+            "後手番" | "上手番" => {
+                self.side_to_move = Some(Color::White);
+                return Ok(true);
+            }
+            _ => {}
+        }
+        if let Some(rest) = trimmed.strip_prefix("手数＝") {
+            let digits: String = rest.chars().take_while(char::is_ascii_digit).collect();
+            let ply =
+                digits.parse().map_err(|_| syntax(line_no, format!("invalid ply: {rest}")))?;
+            self.board_ply = Some(ply);
+            return Ok(true);
+        }
+        let Some((key, value)) = line.split_once('：') else {
+            return Ok(false);
+        };
+        let (key, value) = (key.trim(), value.trim());
+        if key.ends_with("持駒") {
+            let color = if key.starts_with("先手") || key.starts_with("下手") {
+                Color::Black
+            } else {
+                Color::White
+            };
+            self.hands[color.index()] = Some(value.to_string());
+        } else if key == "手合割" {
+            self.handicap = Some(value.to_string());
+        } else {
+            self.record.headers.push((key.to_string(), value.to_string()));
+        }
+        Ok(true)
+    }
+
+    /// 開始局面を確定する（2 回目以降は何もしない）
+    fn start(&mut self, line_no: usize) -> Result<(), RecordError> {
+        if self.pos.is_some() {
+            return Ok(());
+        }
+        let sfen = if !self.board.is_empty() {
+            board_sfen(self).map_err(|m| syntax(line_no, m))?
+        } else if let Some(name) = &self.handicap {
+            HANDICAPS
+                .iter()
+                .find(|(n, _)| n == name)
+                .map(|(_, sfen)| sfen.to_string())
+                .ok_or_else(|| syntax(line_no, format!("unsupported handicap: {name}")))?
+        } else {
+            SFEN_HIRATE.to_string()
+        };
+        let mut pos = Position::new();
+        pos.set_sfen(&sfen).map_err(RecordError::Position)?;
+        self.record.start_sfen = pos.to_sfen();
+        self.pos = Some(pos);
+        Ok(())
+    }
+
+    fn side(&self) -> Color {
+        self.pos.as_ref().map_or(Color::Black, Position::side_to_move)
+    }
+
+    fn push_move(&mut self, m: RecordMove) {
+        let pos = self.pos.as_mut().expect("start() must be called before push_move()");
+        let gives_check = pos.gives_check(m.mv);
+        pos.do_move(m.mv, gives_check);
+        self.last_to = Some(m.mv.to());
+        self.record.moves.push(m);
+    }
+
+    fn end(&mut self, end: GameEnd) {
+        self.record.end.get_or_insert(end);
+    }
+
+    fn illegal(&self, line_no: usize, text: &str) -> RecordError {
+        RecordError::IllegalMove {
+            line: line_no,
+            ply: self.record.moves.len() + 1,
+            text: text.to_string(),
+        }
+    }
+
+    /// 移動先（`同` なら直前の手の移動先）と残りの文字列
+    fn destination<'a>(&self, text: &'a str) -> Option<(Square, &'a str)> {
+        if let Some(rest) = text.strip_prefix('同') {
+            return Some((self.last_to?, rest.trim_start_matches(['　', ' '])));
+        }
+        parse_square(text)
+    }
+
+    /// KIF の指し手欄（`７六歩(77)` / `同　歩(76)` / `５五角打`）
+    fn kif_move(&self, line_no: usize, text: &str) -> Result<Move, RecordError> {
+        let illegal = || self.illegal(line_no, text);
+        let pos = self.pos.as_ref().expect("start() must be called before kif_move()");
+        let (to, rest) = self.destination(text).ok_or_else(illegal)?;
+        let (pt, rest) = parse_piece(rest).ok_or_else(illegal)?;
+        let (promote, drop, rest) = parse_suffix(rest);
+        let mv = match rest.strip_prefix('(').and_then(|r| r.strip_suffix(')')) {
+            Some(from) if !drop => {
+                let mut digits =
+                    from.chars().filter_map(|c| c.to_digit(10)).map(|d| (d as u8).checked_sub(1));
+                let file = digits.next().flatten().and_then(File::from_u8);
+                let rank = digits.next().flatten().and_then(Rank::from_u8);
+                let (Some(file), Some(rank)) = (file, rank) else {
+                    return Err(illegal());
+                };
+                Move::new_move(Square::new(file, rank), to, promote)
+            }
+            _ => Move::new_drop(pt, to),
+        };
+        let mv = legal_move(pos, mv).ok_or_else(illegal)?;
+        if moved_piece_type(pos, mv) != pt {
+            return Err(illegal());
+        }
+        Ok(mv)
+    }
+
+    /// KI2 の指し手（`７六歩` / `同歩` / `５八金右` / `２二角成`）
+    ///
+    /// 合法手のうち移動先・駒種・成/不成/打が一致するものを集め、`上` / `引` / `寄` /
+    /// `直` / `右` / `左` で 1 手に絞る。`打` が無くても盤上の駒が動けなければ打つ手とみなす。
+    fn ki2_move(&self, line_no: usize, text: &str) -> Result<Move, RecordError> {
+        let illegal = || self.illegal(line_no, text);
+        let pos = self.pos.as_ref().expect("start() must be called before ki2_move()");
+        let (to, rest) = self.destination(text).ok_or_else(illegal)?;
+        let (pt, rest) = parse_piece(rest).ok_or_else(illegal)?;
+        let modifiers: String =
+            rest.chars().take_while(|c| "右左直上引寄行入".contains(*c)).collect();
+        let (promote, drop, rest) = parse_suffix(&rest[modifiers.len()..]);
+        if !rest.is_empty() {
+            return Err(illegal());
+        }
+        let mut candidates: Vec<Move> = legal_moves(pos)
+            .iter()
+            .copied()
+            .filter(|m| {
+                m.to() == to
+                    && moved_piece_type(pos, *m) == pt
+                    && m.is_promote() == promote
+                    && (!drop || m.is_drop())
+            })
+            .collect();
+        if candidates.iter().any(|m| !m.is_drop()) {
+            candidates.retain(|m| !m.is_drop());
+        }
+        let us = pos.side_to_move();
+        // 手番側から見て前に進んだ段数
+        let forward = |m: &Move| {
+            let (from, to) = (m.from().rank() as i32, m.to().rank() as i32);
+            if us == Color::Black {
+                from - to
+            } else {
+                to - from
+            }
+        };
+        for c in modifiers.chars() {
+            match c {
+                '上' | '行' | '入' => candidates.retain(|m| forward(m) > 0),
+                '引' => candidates.retain(|m| forward(m) < 0),
+                '寄' => candidates.retain(|m| forward(m) == 0),
+                '直' => candidates.retain(|m| forward(m) > 0 && m.from().file() == m.to().file()),
+                _ => {}
+            }
+        }
+        for c in modifiers.chars().filter(|c| matches!(c, '右' | '左')) {
+            // 先手から見て右は 1 筋側。後手は逆
+            let rightward = (c == '右') == (us == Color::Black);
+            let files = candidates.iter().map(|m| m.from().file());
+            let Some(target) = (if rightward { files.min() } else { files.max() }) else {
+                break;
+            };
+            candidates.retain(|m| m.from().file() == target);
+        }
+        match candidates.as_slice() {
+            [mv] => Ok(*mv),
+            _ => Err(illegal()),
+        }
+    }
+
+    fn finish(mut self) -> Result<GameRecord, RecordError> {
+        self.start(0)?;
+        Ok(self.record)
+    }
+}
+
+/// 盤面図から SFEN を作る
+fn board_sfen(builder: &Builder) -> Result<String, String> {
+    if builder.board.len() != Rank::NUM {
+        return Err(format!("board diagram has {} rows", builder.board.len()));
+    }
+    let mut rows = Vec::with_capacity(Rank::NUM);
+    for row in &builder.board {
+        let mut sfen = String::new();
+        let mut empty = 0;
+        let mut cells = 0;
+        let mut white = false;
+        for c in row.chars() {
+            match c {
+                'v' => white = true,
+                ' ' | '　' => {}
+                '・' => {
+                    empty += 1;
+                    cells += 1;
+                }
+                _ => {
+                    let (pt, promoted) = board_piece(c).ok_or(format!("unknown piece: {c}"))?;
+                    if empty > 0 {
+                        sfen.push_str(&empty.to_string());
+                        empty = 0;
+                    }
+                    if promoted {
+                        sfen.push('+');
+                    }
+                    let letter = usi_letter(pt);
+                    sfen.push(if white {
+                        letter.to_ascii_lowercase()
+                    } else {
+                        letter
+                    });
+                    white = false;
+                    cells += 1;
+                }
+            }
+        }
+        if cells != File::NUM {
+            return Err(format!("board row has {cells} cells: {row}"));
+        }
+        if empty > 0 {
+            sfen.push_str(&empty.to_string());
+        }
+        rows.push(sfen);
+    }
+    let mut hand = String::new();
+    for (color, text) in [Color::Black, Color::White].into_iter().zip(&builder.hands) {
+        let Some(text) = text else { continue };
+        for token in text.split(|c: char| c.is_whitespace()).filter(|t| !t.is_empty()) {
+            if token == "なし" {
+                continue;
+            }
+            let mut chars = token.chars();
+            let c = chars.next().unwrap_or_default();
+            let (pt, _) = board_piece(c).ok_or(format!("unknown hand piece: {token}"))?;
+            let count = kanji_number(chars.as_str()).ok_or(format!("invalid count: {token}"))?;
+            if count > 1 {
+                hand.push_str(&count.to_string());
+            }
+            let letter = usi_letter(pt);
+            hand.push(if color == Color::White {
+                letter.to_ascii_lowercase()
+            } else {
+                letter
+            });
+        }
+    }
+    if hand.is_empty() {
+        hand.push('-');
+    }
+    let side = match builder.side_to_move {
+        Some(Color::White) => 'w',
+        _ => 'b',
+    };
+    let ply = builder.board_ply.map_or(1, |n| n + 1);
+    Ok(format!("{} {side} {hand} {ply}", rows.join("/")))
+}
+
+/// 行頭が手数の指し手行なら (手数, 残り)
+fn kif_move_line(line: &str) -> Option<(usize, &str)> {
+    let line = line.trim_start();
+    let end = line.find(|c: char| !c.is_ascii_digit())?;
+    if end == 0 {
+        return None;
+    }
+    let rest = &line[end..];
+    if !rest.starts_with([' ', '　']) {
+        return None;
+    }
+    Some((line[..end].parse().ok()?, rest.trim_start()))
+}
+
+/// 指し手欄と、その後ろ（消費時間など）に分ける
+///
+/// `同　歩(76)` の全角空白は指し手欄の一部として扱い、取り除いた形で返す。
+fn split_move_text(body: &str) -> (String, &str) {
+    let (prefix, body) = match body.strip_prefix('同') {
+        Some(rest) => ("同", rest.trim_start_matches(['　', ' '])),
+        None => ("", body),
+    };
+    let (text, rest) = body.split_once(char::is_whitespace).unwrap_or((body, ""));
+    (format!("{prefix}{text}"), rest)
+}
+
+/// `( 0:01/00:00:01)` 形式の消費時間（無ければ `None`）
+fn parse_time(rest: &str) -> Result<Option<MoveTime>, String> {
+    let rest = rest.trim().trim_end_matches('+').trim();
+    let Some(inner) = rest.strip_prefix('(').and_then(|r| r.strip_suffix(')')) else {
+        return Ok(None);
+    };
+    let seconds = |text: &str| {
+        text.trim()
+            .split(':')
+            .try_fold(0u64, |acc, part| part.trim().parse::<u64>().map(|v| acc * 60 + v))
+            .map_err(|e| format!("invalid time {inner}: {e}"))
+    };
+    let (elapsed, total) = match inner.split_once('/') {
+        Some((elapsed, total)) => (seconds(elapsed)?, Some(seconds(total)? * 1000)),
+        None => (seconds(inner)?, None),
+    };
+    Ok(Some(MoveTime {
+        elapsed_ms: elapsed * 1000,
+        total_ms: total,
+    }))
+}
+
+fn end_kind(text: &str) -> Option<GameEndKind> {
+    END_WORDS.iter().find(|(word, _)| *word == text).map(|(_, kind)| *kind)
+}
+
+fn end_word(kind: GameEndKind) -> &'static str {
+    // 手数制限には KIF の語が無いため持将棋として書く
+    let kind = if kind == GameEndKind::MaxMoves {
+        GameEndKind::Impasse
+    } else {
+        kind
+    };
+    END_WORDS.iter().find(|(_, k)| *k == kind).map_or("投了", |(word, _)| word)
+}
+
+/// `まで77手で先手の勝ち` の `77手で` 以降から終局の種類を推定する
+fn summary_kind(summary: &str, side_to_move: Color) -> Option<GameEndKind> {
+    let summary = summary.split_once('で').map_or(summary, |(_, rest)| rest);
+    let winner = if summary.contains("先手の") || summary.contains("下手の") {
+        Some(Color::Black)
+    } else if summary.contains("後手の") || summary.contains("上手の") {
+        Some(Color::White)
+    } else {
+        None
+    };
+    let kind = if summary.contains("中断") {
+        GameEndKind::Interrupt
+    } else if summary.contains("千日手") {
+        GameEndKind::Repetition
+    } else if summary.contains("持将棋") {
+        GameEndKind::Impasse
+    } else if summary.contains("引き分け") {
+        GameEndKind::Draw
+    } else if summary.contains("詰み") {
+        GameEndKind::Mate
+    } else if summary.contains("時間切れ") || summary.contains("切れ負け") {
+        GameEndKind::Timeout
+    } else if summary.contains("入玉") {
+        GameEndKind::EnteringKingWin
+    } else if summary.contains("反則") {
+        if winner == Some(side_to_move) {
+            GameEndKind::IllegalWin
+        } else {
+            GameEndKind::IllegalLoss
+        }
+    } else if winner.is_some() {
+        GameEndKind::Resign
+    } else {
+        return None;
+    };
+    Some(kind)
+}
+
+/// `７六` / `76` 形式の升目と残りの文字列
+fn parse_square(text: &str) -> Option<(Square, &str)> {
+    let mut chars = text.chars();
+    let f = chars.next()?;
+    let r = chars.next()?;
+    let file = FILE_CHARS
+        .iter()
+        .position(|&c| c == f)
+        .or_else(|| f.to_digit(10).filter(|d| (1..=9).contains(d)).map(|d| d as usize - 1))?;
+    let rank = RANK_CHARS
+        .iter()
+        .position(|&c| c == r)
+        .or_else(|| r.to_digit(10).filter(|d| (1..=9).contains(d)).map(|d| d as usize - 1))?;
+    let square = Square::new(File::from_u8(file as u8)?, Rank::from_u8(rank as u8)?);
+    Some((square, chars.as_str()))
+}
+
+/// 駒名と残りの文字列
+fn parse_piece(text: &str) -> Option<(PieceType, &str)> {
+    for (name, pt) in [
+        ("成香", PieceType::ProLance),
+        ("成桂", PieceType::ProKnight),
+        ("成銀", PieceType::ProSilver),
+    ] {
+        if let Some(rest) = text.strip_prefix(name) {
+            return Some((pt, rest));
+        }
+    }
+    let mut chars = text.chars();
+    let (pt, promoted) = board_piece(chars.next()?)?;
+    let pt = if promoted { pt.promote()? } else { pt };
+    Some((pt, chars.as_str()))
+}
+
+/// 成 / 不成 / 打 の後置詞（成り, 打ち, 残り）
+fn parse_suffix(text: &str) -> (bool, bool, &str) {
+    if let Some(rest) = text.strip_prefix("不成") {
+        (false, false, rest)
+    } else if let Some(rest) = text.strip_prefix('成') {
+        (true, false, rest)
+    } else if let Some(rest) = text.strip_prefix('打') {
+        (false, true, rest)
+    } else {
+        (false, false, text)
+    }
+}
+
+/// 盤面図・指し手の 1 文字の駒名（生駒の駒種, 成駒か）
+fn board_piece(c: char) -> Option<(PieceType, bool)> {
+    Some(match c {
+        '歩' => (PieceType::Pawn, false),
+        '香' => (PieceType::Lance, false),
+        '桂' => (PieceType::Knight, false),
+        '銀' => (PieceType::Silver, false),
+        '金' => (PieceType::Gold, false),
+        '角' => (PieceType::Bishop, false),
+        '飛' => (PieceType::Rook, false),
+        '玉' | '王' => (PieceType::King, false),
+        'と' => (PieceType::Pawn, true),
+        '杏' => (PieceType::Lance, true),
+        '圭' => (PieceType::Knight, true),
+        '全' => (PieceType::Silver, true),
+        '馬' => (PieceType::Bishop, true),
+        '龍' | '竜' => (PieceType::Rook, true),
+        _ => return None,
+    })
+}
+
+/// SFEN の駒文字（先手側の大文字）
+fn usi_letter(pt: PieceType) -> char {
+    match pt.unpromote() {
+        PieceType::Pawn => 'P',
+        PieceType::Lance => 'L',
+        PieceType::Knight => 'N',
+        PieceType::Silver => 'S',
+        PieceType::Gold => 'G',
+        PieceType::Bishop => 'B',
+        PieceType::Rook => 'R',
+        _ => 'K',
+    }
+}
+
+/// 持駒の枚数（空なら 1、`十八` まで）
+fn kanji_number(text: &str) -> Option<u32> {
+    let digit = |c: char| RANK_CHARS.iter().position(|&k| k == c).map(|i| i as u32 + 1);
+    let mut chars = text.chars();
+    match (chars.next(), chars.next(), chars.next()) {
+        (None, _, _) => Some(1),
+        (Some('十'), None, _) => Some(10),
+        (Some('十'), Some(c), None) => Some(10 + digit(c)?),
+        (Some(c), None, _) => digit(c),
+        _ => None,
+    }
+}
+
+fn syntax(line: usize, message: String) -> RecordError {
+    RecordError::Syntax { line, message }
+}
+
+// ---------------------------------------------------------------------------
+// 書き出し
+// ---------------------------------------------------------------------------
+
+/// 開始局面が駒落ちの定型ならその名前
+fn handicap_name(pos: &Position) -> Option<&'static str> {
+    let sfen = pos.to_sfen();
+    HANDICAPS.iter().find(|(_, handicap)| *handicap == sfen).map(|(name, _)| *name)
+}
+
+/// 盤面図（持駒・盤面・手番・手数）
+fn board_diagram(pos: &Position) -> String {
+    let mut out = String::new();
+    out.push_str(&format!("後手の持駒：{}\n", hand_text(pos, Color::White)));
+    out.push_str("  ９ ８ ７ ６ ５ ４ ３ ２ １\n");
+    out.push_str("+---------------------------+\n");
+    for (r, rank) in Rank::ALL.iter().enumerate() {
+        out.push('|');
+        for file in File::ALL.iter().rev() {
+            let pc = pos.piece_on(Square::new(*file, *rank));
+            if pc.is_none() {
+                out.push_str(" ・");
+                continue;
+            }
+            out.push(if pc.color() == Color::White { 'v' } else { ' ' });
+            out.push(board_char(pc.piece_type()));
+        }
+        out.push('|');
+        out.push(RANK_CHARS[r]);
+        out.push('\n');
+    }
+    out.push_str("+---------------------------+\n");
+    out.push_str(&format!("先手の持駒：{}\n", hand_text(pos, Color::Black)));
+    if pos.game_ply() > 1 {
+        out.push_str(&format!("手数＝{}\n", pos.game_ply() - 1));
+    }
+    if pos.side_to_move() == Color::White {
+        out.push_str("後手番\n");
+    }
+    out
+}
+
+/// 持駒の表記（`角　歩二　`、無ければ `なし`）
+fn hand_text(pos: &Position, color: Color) -> String {
+    let items = hand_items(pos, color);
+    if items.is_empty() {
+        return "なし".to_string();
+    }
+    items.iter().map(|item| format!("{item}　")).collect()
+}
+
+/// 持駒の駒種ごとの表記（`角` / `歩二`、飛車から歩の順）
+pub(crate) fn hand_items(pos: &Position, color: Color) -> Vec<String> {
+    let hand = pos.hand(color);
+    let mut items = Vec::new();
+    for pt in [
+        PieceType::Rook,
+        PieceType::Bishop,
+        PieceType::Gold,
+        PieceType::Silver,
+        PieceType::Knight,
+        PieceType::Lance,
+        PieceType::Pawn,
+    ] {
+        let count = hand.count(pt);
+        if count == 0 {
+            continue;
+        }
+        let mut item = board_char(pt).to_string();
+        if count >= 10 {
+            item.push('十');
+        }
+        if !count.is_multiple_of(10) && count > 1 {
+            item.push(RANK_CHARS[(count % 10) as usize - 1]);
+        }
+        items.push(item);
+    }
+    items
+}
+
+/// 盤面図の 1 文字の駒名
+fn board_char(pt: PieceType) -> char {
+    match pt {
+        PieceType::Pawn => '歩',
+        PieceType::Lance => '香',
+        PieceType::Knight => '桂',
+        PieceType::Silver => '銀',
+        PieceType::Gold => '金',
+        PieceType::Bishop => '角',
+        PieceType::Rook => '飛',
+        PieceType::King => '玉',
+        PieceType::ProPawn => 'と',
+        PieceType::ProLance => '杏',
+        PieceType::ProKnight => '圭',
+        PieceType::ProSilver => '全',
+        PieceType::Horse => '馬',
+        PieceType::Dragon => '龍',
+    }
+}
+
+/// 指し手欄の駒名
+pub(crate) fn move_piece_name(pt: PieceType) -> &'static str {
+    match pt {
+        PieceType::Pawn => "歩",
+        PieceType::Lance => "香",
+        PieceType::Knight => "桂",
+        PieceType::Silver => "銀",
+        PieceType::Gold => "金",
+        PieceType::Bishop => "角",
+        PieceType::Rook => "飛",
+        PieceType::King => "玉",
+        PieceType::ProPawn => "と",
+        PieceType::ProLance => "成香",
+        PieceType::ProKnight => "成桂",
+        PieceType::ProSilver => "成銀",
+        PieceType::Horse => "馬",
+        PieceType::Dragon => "龍",
+    }
+}
+
+/// KIF の指し手欄（`７六歩(77)` / `同　歩(76)` / `５五角打` / `２二角不成(88)`）
+pub(crate) fn kif_move_text(pos: &Position, mv: Move, last_to: Option<Square>) -> String {
+    let to = mv.to();
+    let mut text = if last_to == Some(to) {
+        "同　".to_string()
+    } else {
+        square_text(to)
+    };
+    let pt = moved_piece_type(pos, mv);
+    text.push_str(move_piece_name(pt));
+    if mv.is_drop() {
+        text.push('打');
+        return text;
+    }
+    let from = mv.from();
+    if mv.is_promote() {
+        text.push('成');
+    } else if pt.can_promote() {
+        let us = pos.side_to_move();
+        if from.rank().can_promote(us) || to.rank().can_promote(us) {
+            text.push_str("不成");
+        }
+    }
+    text.push_str(&format!("({}{})", from.file() as u8 + 1, from.rank() as u8 + 1));
+    text
+}
+
+/// マスの表記（`７六`）
+pub(crate) fn square_text(sq: Square) -> String {
+    format!("{}{}", FILE_CHARS[sq.file() as usize], RANK_CHARS[sq.rank() as usize])
+}
+
+/// 指し手行（指し手欄を揃えて消費時間を付ける）
+fn move_line(ply: usize, text: &str, time: Option<&str>) -> String {
+    match time {
+        Some(time) => {
+            // 全角文字を幅 2 として指し手欄を 14 桁に揃える
+            let width: usize = text.chars().map(|c| if c.is_ascii() { 1 } else { 2 }).sum();
+            let pad = 14usize.saturating_sub(width).max(1);
+            format!("{ply:>4} {text}{}{time}\n", " ".repeat(pad))
+        }
+        None => format!("{ply:>4} {text}\n"),
+    }
+}
+
+/// `( 0:01/00:00:01)` 形式の消費時間
+fn format_time(elapsed_ms: u64, total_ms: u64) -> String {
+    let elapsed = elapsed_ms / 1000;
+    let total = total_ms / 1000;
+    format!(
+        "({:>2}:{:02}/{:02}:{:02}:{:02})",
+        elapsed / 60,
+        elapsed % 60,
+        total / 3600,
+        total / 60 % 60,
+        total % 60
+    )
+}
+
+/// `まで77手で先手の勝ち` 形式の終局行
+fn summary(kind: GameEndKind, side_to_move: Color, num_moves: usize) -> String {
+    let side_name = |c: Color| {
+        if c == Color::Black {
+            "先手"
+        } else {
+            "後手"
+        }
+    };
+    let result = match (kind, kind.winner(side_to_move)) {
+        (GameEndKind::Interrupt, _) => "中断".to_string(),
+        (GameEndKind::Repetition, _) => "千日手".to_string(),
+        (GameEndKind::Impasse | GameEndKind::MaxMoves, _) => "持将棋".to_string(),
+        (GameEndKind::Draw, _) => "引き分け".to_string(),
+        (GameEndKind::Mate, Some(w)) => format!("詰みで{}の勝ち", side_name(w)),
+        (GameEndKind::Timeout, Some(w)) => format!("時間切れにより{}の勝ち", side_name(w)),
+        (GameEndKind::IllegalWin | GameEndKind::IllegalLoss, Some(w)) => {
+            format!("{}の反則勝ち", side_name(w))
+        }
+        (GameEndKind::EnteringKingWin, Some(w)) => format!("入玉で{}の勝ち", side_name(w)),
+        (_, Some(w)) => format!("{}の勝ち", side_name(w)),
+        (_, None) => "中断".to_string(),
+    };
+    format!("まで{num_moves}手で{result}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Floodgate の棋譜を KIF に変換したものと同じ体裁のテスト用棋譜
+    const FLOODGATE_KIF: &str = include_str!("testdata/floodgate.kif");
+    /// 同じ対局の KI2
+    const FLOODGATE_KI2: &str = include_str!("testdata/floodgate.ki2");
+
+    const FLOODGATE_USI: &str = "7g7f 3c3d 2g2f 4c4d 2f2e 2b3c 3i4h 8b4b 5i6h 5a6b 6h7h 7a7b \
+         5g5f 6b7a 4i5h 7a8b 3g3f 9c9d 9g9f 4d4e 8h3c+ 2a3c 2e2d 2c2d 2h2d P*2c 2d2h 4a3b B*6e \
+         7c7d 6e4c 3b4c";
+
+    fn parse_ok(text: &str) -> GameRecord {
+        match parse_str(text) {
+            Ok(record) => record,
+            Err(e) => panic!("failed to parse: {e}"),
+        }
+    }
+
+    #[test]
+    fn parses_floodgate_kif() {
+        let record = parse_ok(FLOODGATE_KIF);
+        assert_eq!(record.header("先手"), Some("rshogi_test_black"));
+        assert_eq!(record.header("棋戦"), Some("floodgate"));
+        assert_eq!(record.header("手合割"), None);
+        assert_eq!(record.start_sfen, SFEN_HIRATE);
+        assert_eq!(record.usi_moves(), FLOODGATE_USI.split_whitespace().collect::<Vec<_>>());
+        assert_eq!(
+            record.moves[0].time,
+            Some(MoveTime {
+                elapsed_ms: 1000,
+                total_ms: Some(1000),
+            })
+        );
+        assert_eq!(record.moves[0].comments, vec!["*評価値=+52".to_string()]);
+        let end = record.end.as_ref().unwrap();
+        assert_eq!(end.kind, GameEndKind::Resign);
+        assert_eq!(
+            end.kind.winner(record.final_position().unwrap().side_to_move()),
+            Some(Color::White)
+        );
+        assert!(record.to_usi_position().starts_with("position startpos moves 7g7f 3c3d"));
+    }
+
+    #[test]
+    fn kif_round_trip() {
+        let record = parse_ok(FLOODGATE_KIF);
+        let text = to_kif(&record).unwrap();
+        let again = parse_kif(&text).unwrap();
+        assert_eq!(again, record);
+        // 書き出しは安定している
+        assert_eq!(to_kif(&again).unwrap(), text);
+    }
+
+    #[test]
+    fn shift_jis_kif_is_decoded() {
+        let bytes = encode_shift_jis(FLOODGATE_KIF);
+        assert!(std::str::from_utf8(&bytes).is_err());
+        assert_eq!(parse(&bytes).unwrap(), parse_ok(FLOODGATE_KIF));
+    }
+
+    #[test]
+    fn ki2_matches_kif() {
+        let kif = parse_ok(FLOODGATE_KIF);
+        let ki2 = parse_ok(FLOODGATE_KI2);
+        assert_eq!(ki2.usi_moves(), kif.usi_moves());
+        assert_eq!(ki2.end.map(|e| e.kind), Some(GameEndKind::Resign));
+    }
+
+    #[test]
+    fn ki2_disambiguates_by_direction() {
+        // 先手の金が 4九と 6九にあり、どちらも 5八に動ける
+        let text = "\
+後手の持駒：なし
+  ９ ８ ７ ６ ５ ４ ３ ２ １
++---------------------------+
+| ・ ・ ・ ・v玉 ・ ・ ・ ・|一
+| ・ ・ ・ ・ ・ ・ ・ ・ ・|二
+| ・ ・ ・ ・ ・ ・ ・ ・ ・|三
+| ・ ・ ・ ・ ・ ・ ・ ・ ・|四
+| ・ ・ ・ ・ ・ ・ ・ ・ ・|五
+| ・ ・ ・ ・ ・ ・ ・ ・ ・|六
+| ・ ・ ・ ・ ・ ・ ・ ・ ・|七
+| ・ ・ ・ ・ ・ ・ ・ ・ ・|八
+| ・ ・ ・ 金 玉 金 ・ ・ ・|九
++---------------------------+
+先手の持駒：歩
+";
+        let right = parse_ki2(&format!("{text}▲５八金右\n")).unwrap();
+        assert_eq!(right.usi_moves(), vec!["4i5h"]);
+        let left = parse_ki2(&format!("{text}▲５八金左\n")).unwrap();
+        assert_eq!(left.usi_moves(), vec!["6i5h"]);
+        // 動作が無いと一意に決まらない
+        assert!(matches!(
+            parse_ki2(&format!("{text}▲５八金\n")),
+            Err(RecordError::IllegalMove { ply: 1, .. })
+        ));
+        // 盤上の歩が動けないので `打` が無くても打つ手
+        let drop = parse_ki2(&format!("{text}▲５五歩\n")).unwrap();
+        assert_eq!(drop.usi_moves(), vec!["P*5e"]);
+    }
+
+    #[test]
+    fn board_diagram_round_trip() {
+        let sfen = "ln1g3+Rl/2sk5/p1ppp1p1p/6p2/1p7/2P3P2/PP1PPP2P/1BK6/LNSG1G1NL w BG2Sr2p 30";
+        let mut pos = Position::new();
+        pos.set_sfen(sfen).unwrap();
+        let record = GameRecord {
+            start_sfen: pos.to_sfen(),
+            moves: vec![RecordMove::new(
+                legal_move(&pos, Move::from_usi("6b5b").unwrap()).unwrap(),
+            )],
+            end: Some(GameEnd::new(GameEndKind::Interrupt)),
+            ..GameRecord::default()
+        };
+        let text = to_kif(&record).unwrap();
+        assert!(text.contains("先手の持駒：角　金　銀二　\n"), "{text}");
+        assert!(text.contains("手数＝29\n後手番\n"), "{text}");
+        assert_eq!(parse_kif(&text).unwrap(), record);
+    }
+
+    #[test]
+    fn handicap_start_position() {
+        let text = "手合割：香落ち\n手数----指手---------消費時間--\n   1 ３四歩(33)\n";
+        let record = parse_kif(text).unwrap();
+        assert_eq!(record.start_sfen, HANDICAPS[1].1);
+        assert!(to_kif(&record).unwrap().contains("手合割：香落ち\n"));
+    }
+
+    #[test]
+    fn illegal_move_is_reported_with_line() {
+        let text =
+            "手合割：平手\n手数----指手---------消費時間--\n   1 ７六歩(77)\n   2 ７五歩(76)\n";
+        assert_eq!(
+            parse_kif(text),
+            Err(RecordError::IllegalMove {
+                line: 4,
+                ply: 2,
+                text: "７五歩(76)".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn kanji_numbers() {
+        assert_eq!(kanji_number(""), Some(1));
+        assert_eq!(kanji_number("九"), Some(9));
+        assert_eq!(kanji_number("十"), Some(10));
+        assert_eq!(kanji_number("十八"), Some(18));
+        assert_eq!(kanji_number("百"), None);
+    }
+}
@@ -0,0 +1,300 @@
+//! 棋譜ファイルの読み書き
+//!
+//! GUI や対局サーバーとの棋譜のやり取り用。各形式のパーサは開始局面の SFEN と
+//! 駒情報付きの合法手列からなる [`GameRecord`] を返し、ライタは [`GameRecord`] を
+//! 各形式の文字列にする。指し手はパース時に合法性を検査する。
+//!
+//! - [`kif`]: KIF / KI2（Shift_JIS / UTF-8）
+//! - [`csa`]: CSA（V2.2、`/` 区切りの複数棋譜を含む）
+//! - [`jkf`]: JKF（JSON Kifu Format。変化を含む。`json` feature 有効時のみ）
+//! - [`sfen_list`]: 1 行 1 局面の SFEN リスト
+//!
+//! ファイルの保存・読み込みでは [`RecordFormat`] で拡張子から形式を選べる。
+
+pub mod csa;
+#[cfg(feature = "json")]
+pub mod jkf;
+pub mod kif;
+pub mod sfen_list;
+
+use std::fmt;
+
+use crate::movegen::{MoveList, generate_legal_all};
+use crate::position::{Position, SFEN_HIRATE, SfenError};
+use crate::types::{Color, Move, PieceType};
+
+/// 棋譜
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameRecord {
+    /// ヘッダ（`先手` / `開始日時` などのキーと値、出現順）
+    pub headers: Vec<(String, String)>,
+    /// 開始局面の SFEN
+    pub start_sfen: String,
+    /// 初手より前のコメント
+    pub comments: Vec<String>,
+    /// 指し手（駒情報付きの合法手）
+    pub moves: Vec<RecordMove>,
+    /// 終局（投了・千日手など。棋譜が途中で終わっていれば `None`）
+    pub end: Option<GameEnd>,
+}
+
+impl Default for GameRecord {
+    fn default() -> Self {
+        Self {
+            headers: Vec::new(),
+            start_sfen: SFEN_HIRATE.to_string(),
+            comments: Vec::new(),
+            moves: Vec::new(),
+            end: None,
+        }
+    }
+}
+
+impl GameRecord {
+    /// ヘッダの値（同じキーが複数あれば最初のもの）
+    pub fn header(&self, key: &str) -> Option<&str> {
+        self.headers.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+    }
+
+    /// 指し手の USI 表記
+    pub fn usi_moves(&self) -> Vec<String> {
+        self.moves.iter().map(|m| m.mv.to_usi()).collect()
+    }
+
+    /// USI の `position` コマンド（平手なら `startpos`）
+    pub fn to_usi_position(&self) -> String {
+        let mut cmd = if self.start_sfen == SFEN_HIRATE {
+            "position startpos".to_string()
+        } else {
+            format!("position sfen {}", self.start_sfen)
+        };
+        if !self.moves.is_empty() {
+            cmd.push_str(" moves");
+            for m in &self.moves {
+                cmd.push(' ');
+                cmd.push_str(&m.mv.to_usi());
+            }
+        }
+        cmd
+    }
+
+    /// 開始局面
+    pub fn start_position(&self) -> Result<Position, RecordError> {
+        let mut pos = Position::new();
+        pos.set_sfen(&self.start_sfen).map_err(RecordError::Position)?;
+        Ok(pos)
+    }
+
+    /// 全ての指し手を進めた局面
+    pub fn final_position(&self) -> Result<Position, RecordError> {
+        let mut pos = self.start_position()?;
+        for m in &self.moves {
+            let gives_check = pos.gives_check(m.mv);
+            pos.do_move(m.mv, gives_check);
+        }
+        Ok(pos)
+    }
+}
+
+/// 棋譜ファイルの形式
+///
+/// JKF は JSON 文字列との変換を呼び出し側で行うため含めない（[`jkf`] 参照）。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordFormat {
+    /// KIF / KI2（読み込みは形式を自動判定、書き出しは KIF）
+    Kif,
+    /// CSA
+    Csa,
+    /// SFEN リスト
+    SfenList,
+}
+
+impl RecordFormat {
+    /// 拡張子（`.` なし、大文字小文字を区別しない）から形式を決める
+    pub fn from_extension(ext: &str) -> Option<Self> {
+        match ext.to_ascii_lowercase().as_str() {
+            "kif" | "kifu" | "ki2" | "ki2u" => Some(RecordFormat::Kif),
+            "csa" => Some(RecordFormat::Csa),
+            "sfen" => Some(RecordFormat::SfenList),
+            _ => None,
+        }
+    }
+
+    /// 棋譜ファイルの内容を読む（文字コードは [`kif::decode`] で判定）
+    pub fn parse(self, bytes: &[u8]) -> Result<GameRecord, RecordError> {
+        let text = kif::decode(bytes);
+        match self {
+            RecordFormat::Kif => kif::parse_str(&text),
+            RecordFormat::Csa => csa::parse(&text),
+            RecordFormat::SfenList => sfen_list::parse(&text),
+        }
+    }
+
+    /// 棋譜を書き出す（UTF-8）
+    pub fn write(self, record: &GameRecord) -> Result<String, RecordError> {
+        match self {
+            RecordFormat::Kif => kif::to_kif(record),
+            RecordFormat::Csa => csa::to_csa(record),
+            RecordFormat::SfenList => sfen_list::to_sfen_list(record),
+        }
+    }
+}
+
+/// 棋譜の 1 手
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordMove {
+    /// 指し手（駒情報付き）
+    pub mv: Move,
+    /// 消費時間
+    pub time: Option<MoveTime>,
+    /// この手に付いたコメント
+    pub comments: Vec<String>,
+    /// この手の代わりの変化（各変化の初手はこの手と同じ局面から指す）
+    pub forks: Vec<Variation>,
+}
+
+impl RecordMove {
+    pub fn new(mv: Move) -> Self {
+        Self {
+            mv,
+            time: None,
+            comments: Vec::new(),
+            forks: Vec::new(),
+        }
+    }
+}
+
+/// 変化（本譜から分岐した指し手列）
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Variation {
+    pub moves: Vec<RecordMove>,
+    /// 変化の終局（投了など。途中で終わっていれば `None`）
+    pub end: Option<GameEnd>,
+}
+
+/// 消費時間
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MoveTime {
+    /// この手の消費時間（ミリ秒）
+    pub elapsed_ms: u64,
+    /// 手番側の累計消費時間（ミリ秒、棋譜に無ければ `None`）
+    pub total_ms: Option<u64>,
+}
+
+/// 終局
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameEnd {
+    pub kind: GameEndKind,
+    /// 終局時の消費時間
+    pub time: Option<MoveTime>,
+    /// 終局に付いたコメント
+    pub comments: Vec<String>,
+}
+
+impl GameEnd {
+    pub fn new(kind: GameEndKind) -> Self {
+        Self {
+            kind,
+            time: None,
+            comments: Vec::new(),
+        }
+    }
+}
+
+/// 終局の種類
+///
+/// 「勝ち」「負け」は終局時点の手番側（投了・反則などを宣言した側）から見た結果。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameEndKind {
+    /// 投了（手番側の負け）
+    Resign,
+    /// 中断
+    Interrupt,
+    /// 千日手
+    Repetition,
+    /// 持将棋
+    Impasse,
+    /// 手数制限による引き分け
+    MaxMoves,
+    /// その他の引き分け
+    Draw,
+    /// 詰み（手番側の負け）
+    Mate,
+    /// 時間切れ（手番側の負け）
+    Timeout,
+    /// 反則勝ち（手番側の勝ち。直前の相手の手が反則）
+    IllegalWin,
+    /// 反則負け（手番側の負け）
+    IllegalLoss,
+    /// 入玉宣言勝ち（手番側の勝ち）
+    EnteringKingWin,
+}
+
+impl GameEndKind {
+    /// 勝った側（引き分け・中断なら `None`）。`side_to_move` は終局時点の手番
+    pub fn winner(self, side_to_move: Color) -> Option<Color> {
+        match self {
+            GameEndKind::Resign
+            | GameEndKind::Mate
+            | GameEndKind::Timeout
+            | GameEndKind::IllegalLoss => Some(!side_to_move),
+            GameEndKind::IllegalWin | GameEndKind::EnteringKingWin => Some(side_to_move),
+            GameEndKind::Interrupt
+            | GameEndKind::Repetition
+            | GameEndKind::Impasse
+            | GameEndKind::MaxMoves
+            | GameEndKind::Draw => None,
+        }
+    }
+}
+
+/// 棋譜の読み込みエラー
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RecordError {
+    /// 行の書式が不正（`line` は 1 始まり。JKF など行の無い形式では 0）
+    Syntax { line: usize, message: String },
+    /// 指し手が非合法、または局面から一意に決まらない
+    IllegalMove {
+        line: usize,
+        ply: usize,
+        text: String,
+    },
+    /// 開始局面が不正
+    Position(SfenError),
+}
+
+impl fmt::Display for RecordError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecordError::Syntax { line, message } => write!(f, "line {line}: {message}"),
+            RecordError::IllegalMove { line, ply, text } => {
+                write!(f, "line {line}: illegal move at ply {ply}: {text}")
+            }
+            RecordError::Position(e) => write!(f, "invalid start position: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for RecordError {}
+
+/// 駒情報を補った合法手（非合法なら `None`）
+fn legal_move(pos: &Position, mv: Move) -> Option<Move> {
+    let mv = pos.to_move(mv)?;
+    (mv.is_some() && pos.pseudo_legal(mv) && pos.is_legal(mv)).then_some(mv)
+}
+
+/// 不成を含む全ての合法手
+fn legal_moves(pos: &Position) -> MoveList {
+    let mut list = MoveList::new();
+    generate_legal_all(pos, &mut list);
+    list
+}
+
+/// 指した駒の駒種（成る前）
+fn moved_piece_type(pos: &Position, mv: Move) -> PieceType {
+    if mv.is_drop() {
+        mv.drop_piece_type()
+    } else {
+        pos.piece_on(mv.from()).piece_type()
+    }
+}
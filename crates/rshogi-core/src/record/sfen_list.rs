@@ -0,0 +1,125 @@
+//! SFEN リスト形式
+//!
+//! 開始局面から 1 行に 1 局面ずつ SFEN を並べた形式。局面単位で棋譜を扱うツール
+//! （局面の抽出や検討）とのやり取り用で、ヘッダ・コメント・消費時間・終局は持たない。
+//!
+//! 読み込みでは隣り合う局面を結ぶ合法手を探して指し手列に戻す。手数欄は見ない。
+//! 空行と `#` で始まる行は読み飛ばし、先頭の局面は `startpos` でもよい。
+
+use super::{GameRecord, RecordError, RecordMove, legal_moves};
+use crate::position::{Position, SFEN_HIRATE};
+
+/// SFEN リストを読む
+pub fn parse(text: &str) -> Result<GameRecord, RecordError> {
+    let mut lines = text
+        .lines()
+        .enumerate()
+        .map(|(idx, line)| (idx + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'));
+    let Some((_, first)) = lines.next() else {
+        return Err(RecordError::Syntax {
+            line: 0,
+            message: "no position".to_string(),
+        });
+    };
+
+    let mut pos = Position::new();
+    let first = if first == "startpos" {
+        SFEN_HIRATE
+    } else {
+        first
+    };
+    pos.set_sfen(first).map_err(RecordError::Position)?;
+    let mut record = GameRecord {
+        start_sfen: pos.to_sfen(),
+        ..GameRecord::default()
+    };
+
+    for (line_no, line) in lines {
+        let mut next = Position::new();
+        next.set_sfen(line).map_err(|e| RecordError::Syntax {
+            line: line_no,
+            message: e.to_string(),
+        })?;
+        let next_sfen = next.to_sfen();
+        let target = position_key(&next_sfen);
+        let found = legal_moves(&pos).iter().copied().find(|&mv| {
+            let gives_check = pos.gives_check(mv);
+            pos.do_move(mv, gives_check);
+            let matched = position_key(&pos.to_sfen()) == target;
+            pos.undo_move(mv);
+            matched
+        });
+        let Some(mv) = found else {
+            return Err(RecordError::IllegalMove {
+                line: line_no,
+                ply: record.moves.len() + 1,
+                text: line.to_string(),
+            });
+        };
+        let gives_check = pos.gives_check(mv);
+        pos.do_move(mv, gives_check);
+        record.moves.push(RecordMove::new(mv));
+    }
+    Ok(record)
+}
+
+/// SFEN リストで書き出す（開始局面と各手の後の局面）
+pub fn to_sfen_list(record: &GameRecord) -> Result<String, RecordError> {
+    let mut pos = record.start_position()?;
+    let mut out = pos.to_sfen();
+    out.push('\n');
+    for m in &record.moves {
+        let gives_check = pos.gives_check(m.mv);
+        pos.do_move(m.mv, gives_check);
+        out.push_str(&pos.to_sfen());
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+/// 手数欄を除いた SFEN（盤面・手番・持駒）
+fn position_key(sfen: &str) -> Vec<&str> {
+    sfen.split_whitespace().take(3).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::record::kif;
+
+    #[test]
+    fn sfen_list_round_trip() {
+        let record = kif::parse_str(include_str!("testdata/floodgate.kif")).unwrap();
+        let text = to_sfen_list(&record).unwrap();
+        assert_eq!(text.lines().count(), record.moves.len() + 1);
+        assert!(text.starts_with(SFEN_HIRATE));
+
+        let again = parse(&text).unwrap();
+        assert_eq!(again.start_sfen, SFEN_HIRATE);
+        assert_eq!(again.usi_moves(), record.usi_moves());
+    }
+
+    #[test]
+    fn parses_startpos_and_ignores_ply() {
+        let text = "# 検討用\nstartpos\n\nlnsgkgsnl/1r5b1/ppppppppp/9/9/2P6/PP1PPPPPP/1B5R1/LNSGKGSNL w - 9\n";
+        let record = parse(text).unwrap();
+        assert_eq!(record.usi_moves(), vec!["7g7f"]);
+    }
+
+    #[test]
+    fn rejects_unreachable_position() {
+        // 先手が 2 手続けて指した局面
+        let text =
+            "startpos\nlnsgkgsnl/1r5b1/ppppppppp/9/9/2P4P1/PP1PPPP1P/1B5R1/LNSGKGSNL w - 2\n";
+        assert!(matches!(
+            parse(text),
+            Err(RecordError::IllegalMove {
+                line: 2,
+                ply: 1,
+                ..
+            })
+        ));
+        assert!(matches!(parse("\n# only comments\n"), Err(RecordError::Syntax { .. })));
+    }
+}
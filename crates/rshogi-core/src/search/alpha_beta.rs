@@ -34,7 +34,10 @@ use super::types::{
     ContHistKey, NodeType, PvTable, RootMoves, SEARCHED_MOVES_CAPACITY, STACK_SIZE,
     SearchedMoveList, StackArray, draw_value, init_stack_array, value_from_tt, value_to_tt,
 };
-use super::{LimitsType, MovePicker, SearchTuneParams, TimeManagement};
+use super::{
+    CpuThrottle, CurrMoveReport, LimitsType, MovePicker, SearchTuneParams, TimeManagement,
+    TimePoint,
+};
 
 use super::eval_helpers::{
     compute_eval_context, correction_value, probe_transposition, update_correction_history,
@@ -382,6 +385,10 @@ pub struct SearchState {
     pub acc_cache: Option<LayerStacksAccCache>,
     /// check_abort呼び出しカウンター
     pub calls_cnt: i32,
+    /// CPU 使用率の上限（`LimitsType::cpu_limit_percent`）のための調整状態
+    pub throttle: CpuThrottle,
+    /// 次に `info currmove` を通知する時刻（探索開始からのミリ秒）
+    pub next_currmove_report_ms: TimePoint,
     /// 探索統計（search-stats feature有効時のみ）
     #[cfg(feature = "search-stats")]
     pub stats: SearchStats,
@@ -410,6 +417,8 @@ impl SearchState {
             #[cfg(feature = "layerstack-arch")]
             acc_cache: None,
             calls_cnt: 0,
+            throttle: CpuThrottle::default(),
+            next_currmove_report_ms: 0,
             #[cfg(feature = "search-stats")]
             stats: SearchStats::default(),
         }
@@ -504,6 +513,9 @@ pub struct SearchWorker {
     /// 入玉宣言勝ちルール
     pub entering_king_rule: EnteringKingRule,
 
+    /// `info currmove` の通知条件（メインスレッドのみ設定する）
+    pub currmove_report: Option<CurrMoveReport>,
+
     // =========================================================================
     // 探索状態（SearchState）
     // =========================================================================
@@ -567,6 +579,7 @@ impl SearchWorker {
             draw_value_white: DEFAULT_DRAW_VALUE_WHITE,
             draw_value_table: [Value::ZERO; 2],
             entering_king_rule: EnteringKingRule::default(),
+            currmove_report: None,
             state: SearchState::new(),
         });
         worker.reset_cont_history_ptrs();
@@ -731,6 +744,7 @@ impl SearchWorker {
         self.state.best_move_changes = 0.0;
         self.state.nmp_min_ply = 0;
         self.state.root_moves.clear();
+        self.reset_currmove_report();
         // 探索統計をリセット（1回のgo毎にリセット）
         self.reset_stats();
         // low_ply_historyのみクリア
@@ -796,6 +810,7 @@ impl SearchWorker {
         // check_abort頻度制御カウンターをリセット
         // これにより新しい探索開始時に即座に停止チェックが行われる
         self.state.calls_cnt = 0;
+        self.state.throttle.reset();
     }
 
     /// best_move_changes を半減（世代減衰）
@@ -902,6 +917,11 @@ impl SearchWorker {
             }
         }
 
+        // CPU 使用率の上限（停止時刻の直前は眠らない）
+        if limits.has_cpu_limit() && !CpuThrottle::near_search_end(time_manager) {
+            self.state.throttle.checkpoint(limits.cpu_limit_percent);
+        }
+
         false
     }
 
@@ -926,6 +946,52 @@ impl SearchWorker {
         }
     }
 
+    /// ルート局面から静止探索だけを行う（`Search::qsearch` 用）
+    ///
+    /// 呼び出し前に `prepare_search` でアキュムレータを初期化しておくこと。
+    pub(crate) fn qsearch_root(
+        &mut self,
+        pos: &mut Position,
+        limits: &LimitsType,
+        time_manager: &mut TimeManagement,
+    ) -> Value {
+        self.init_draw_value_table(pos.side_to_move());
+        self.state.stack[0].in_check = pos.in_check();
+        self.state.stack[0].cont_history_ptr = self.cont_history_sentinel;
+        self.state.stack[0].cont_hist_key = None;
+
+        let ctx = SearchContext {
+            tt: &self.tt,
+            eval_hash: &self.eval_hash,
+            history: &self.history,
+            cont_history_sentinel: self.cont_history_sentinel,
+            generate_all_legal_moves: self.generate_all_legal_moves,
+            max_moves_to_draw: self.max_moves_to_draw,
+            thread_id: self.thread_id,
+            allow_tt_write: self.allow_tt_write,
+            tune_params: &self.search_tune_params,
+            reductions: &self.reductions,
+            draw_value_table: self.draw_value_table,
+        };
+        qsearch::<{ NodeType::PV as u8 }>(
+            &mut self.state,
+            &ctx,
+            pos,
+            -Value::INFINITE,
+            Value::INFINITE,
+            0,
+            limits,
+            time_manager,
+        )
+    }
+
+    /// 評価関数による局面の静的評価値（手番側から見た値、補正履歴を含まない）
+    ///
+    /// 呼び出し前に `prepare_search` でアキュムレータを初期化しておくこと。
+    pub(crate) fn evaluate_root(&mut self, pos: &Position) -> Value {
+        nnue_evaluate(&mut self.state, pos)
+    }
+
     /// ルート探索
     pub(crate) fn search_root(
         &mut self,
@@ -1098,6 +1164,19 @@ impl SearchWorker {
             }
 
             move_count += 1;
+            self.report_currmove(mv, move_count as usize, time_manager);
+
+            #[cfg(feature = "search-tracing")]
+            let root_move_span = tracing::trace_span!(
+                "root_move",
+                mv = %mv.to_usi(),
+                move_count,
+                depth,
+                alpha = alpha.raw(),
+                beta = beta.raw(),
+                nodes = tracing::field::Empty
+            )
+            .entered();
 
             let gives_check = pos.gives_check(mv);
             let is_capture = pos.is_capture(mv);
@@ -1370,6 +1449,8 @@ impl SearchWorker {
             // この手に費やしたノード数をeffortに積算
             let nodes_delta = self.state.nodes.saturating_sub(nodes_before);
             self.state.root_moves[rm_idx].effort += nodes_delta as f64;
+            #[cfg(feature = "search-tracing")]
+            root_move_span.record("nodes", nodes_delta);
 
             if self.state.abort {
                 return Value::ZERO;
@@ -1706,6 +1787,20 @@ impl SearchWorker {
             }
 
             let mv = self.state.root_moves[rm_idx].mv();
+            self.report_currmove(mv, rm_idx + 1, time_manager);
+
+            #[cfg(feature = "search-tracing")]
+            let root_move_span = tracing::trace_span!(
+                "root_move",
+                mv = %mv.to_usi(),
+                move_count = rm_idx + 1,
+                depth,
+                alpha = alpha.raw(),
+                beta = beta.raw(),
+                nodes = tracing::field::Empty
+            )
+            .entered();
+
             let gives_check = pos.gives_check(mv);
             let is_capture = pos.is_capture(mv);
 
@@ -1963,6 +2058,8 @@ impl SearchWorker {
             // この手に費やしたノード数をeffortに積算
             let nodes_delta = self.state.nodes.saturating_sub(nodes_before);
             self.state.root_moves[rm_idx].effort += nodes_delta as f64;
+            #[cfg(feature = "search-tracing")]
+            root_move_span.record("nodes", nodes_delta);
 
             if self.state.abort {
                 return Value::ZERO;
@@ -0,0 +1,161 @@
+//! 探索結果の確からしさ（confidence）の推定
+//!
+//! 最終スコアだけでは「何度読み直しても +300」と「直前まで揺れていた +300」を
+//! 区別できない。直近のイテレーションについて評価値の振れ幅・aspiration window の
+//! fail-high / fail-low 回数・最善手の入れ替わりを記録し、0.0〜1.0 の値にまとめる。
+//! 投了判定・定跡学習・GUI 表示などで最終スコアと併せて使う。
+
+use std::collections::VecDeque;
+
+use crate::types::{Move, Value};
+
+/// 推定に使う直近のイテレーション数
+pub const CONFIDENCE_WINDOW: usize = 4;
+
+/// 評価値の振れ幅を測るときの上限（cp）。詰みスコア同士の差で振り切れないようにする
+const SCORE_CLAMP: i32 = 3000;
+
+/// 振れ幅がこの値（cp）で評価値の安定度が半分になる
+const HALF_SWING_CP: f32 = 100.0;
+
+/// fail-high / fail-low がこの回数で安定度が半分になる
+const HALF_FAILURES: f32 = 2.0;
+
+/// 探索結果の確からしさ
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct SearchConfidence {
+    /// 0.0（不安定）〜 1.0（安定）。イテレーションが [`CONFIDENCE_WINDOW`] に
+    /// 満たない浅い探索では割り引く
+    pub value: f32,
+    /// 直近イテレーションの評価値の最大差（cp、±3000 で打ち切り）
+    pub score_swing: i32,
+    /// 直近イテレーションの aspiration window の fail-high / fail-low 回数
+    pub window_failures: u32,
+    /// 直近イテレーションで最善手が入れ替わった回数
+    pub best_move_changes: u32,
+    /// 推定に使ったイテレーション数
+    pub iterations: u32,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct IterationSample {
+    score: Value,
+    best_move: Move,
+    window_failures: u32,
+}
+
+/// メインスレッドの反復深化でイテレーションごとの情報を記録する
+#[derive(Debug, Default)]
+pub(crate) struct ConfidenceTracker {
+    samples: VecDeque<IterationSample>,
+    /// 完了前のイテレーションで起きた fail-high / fail-low
+    pending_failures: u32,
+}
+
+impl ConfidenceTracker {
+    /// ルートの aspiration window で fail-high / fail-low が起きた
+    pub(crate) fn record_window_failure(&mut self) {
+        self.pending_failures += 1;
+    }
+
+    /// イテレーションが完了した（中断されたイテレーションでは呼ばない）
+    pub(crate) fn complete_iteration(&mut self, score: Value, best_move: Move) {
+        if self.samples.len() == CONFIDENCE_WINDOW {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(IterationSample {
+            score,
+            best_move,
+            window_failures: std::mem::take(&mut self.pending_failures),
+        });
+    }
+
+    pub(crate) fn estimate(&self) -> SearchConfidence {
+        let iterations = self.samples.len();
+        if iterations == 0 {
+            return SearchConfidence::default();
+        }
+
+        let clamped = self.samples.iter().map(|s| s.score.raw().clamp(-SCORE_CLAMP, SCORE_CLAMP));
+        let score_swing = clamped.clone().max().unwrap_or(0) - clamped.min().unwrap_or(0);
+        let window_failures: u32 = self.samples.iter().map(|s| s.window_failures).sum();
+        let best_move_changes = self
+            .samples
+            .iter()
+            .zip(self.samples.iter().skip(1))
+            .filter(|(prev, next)| prev.best_move != next.best_move)
+            .count() as u32;
+
+        let score_stability = 1.0 / (1.0 + score_swing as f32 / HALF_SWING_CP);
+        let window_stability = 1.0 / (1.0 + window_failures as f32 / HALF_FAILURES);
+        let move_stability = if iterations > 1 {
+            1.0 - best_move_changes as f32 / (iterations - 1) as f32
+        } else {
+            1.0
+        };
+        let depth_factor = iterations as f32 / CONFIDENCE_WINDOW as f32;
+
+        SearchConfidence {
+            value: score_stability * window_stability * move_stability * depth_factor,
+            score_swing,
+            window_failures,
+            best_move_changes,
+            iterations: iterations as u32,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mv(usi: &str) -> Move {
+        Move::from_usi(usi).unwrap()
+    }
+
+    #[test]
+    fn stable_iterations_give_full_confidence() {
+        let mut tracker = ConfidenceTracker::default();
+        for _ in 0..6 {
+            tracker.complete_iteration(Value::new(300), mv("7g7f"));
+        }
+        let confidence = tracker.estimate();
+        assert_eq!(confidence.iterations, CONFIDENCE_WINDOW as u32);
+        assert_eq!(confidence.score_swing, 0);
+        assert_eq!(confidence.value, 1.0);
+    }
+
+    #[test]
+    fn volatile_iterations_lower_confidence() {
+        let mut stable = ConfidenceTracker::default();
+        let mut volatile = ConfidenceTracker::default();
+        for (i, score) in [100, 450, 150, 300].into_iter().enumerate() {
+            stable.complete_iteration(Value::new(300), mv("7g7f"));
+            if i == 2 {
+                volatile.record_window_failure();
+                volatile.record_window_failure();
+            }
+            let best = if i % 2 == 0 { "7g7f" } else { "2g2f" };
+            volatile.complete_iteration(Value::new(score), mv(best));
+        }
+
+        let confidence = volatile.estimate();
+        assert_eq!(confidence.score_swing, 350);
+        assert_eq!(confidence.window_failures, 2);
+        assert_eq!(confidence.best_move_changes, 3);
+        assert_eq!(confidence.value, 0.0);
+        assert!(stable.estimate().value > confidence.value);
+    }
+
+    #[test]
+    fn failures_of_aborted_iteration_are_not_counted_until_completion() {
+        let mut tracker = ConfidenceTracker::default();
+        tracker.record_window_failure();
+        assert_eq!(tracker.estimate(), SearchConfidence::default());
+
+        tracker.complete_iteration(Value::new(0), mv("7g7f"));
+        let confidence = tracker.estimate();
+        assert_eq!(confidence.window_failures, 1);
+        assert!(confidence.value < 0.25);
+    }
+}
@@ -0,0 +1,89 @@
+//! 探索中のルート手の進捗（USI の `info currmove`）
+//!
+//! GUI は `currmove` / `currmovenumber` で「今どの手を読んでいるか」を表示する。
+//! [`CurrMoveReport`] を設定すると、メインスレッドのルートの指し手ループが、
+//! 前回の通知から `interval_ms` 以上経つごとに、これから探索する手を通知する。
+//! 最初の通知も探索開始から `interval_ms` 経ってからで、短い探索では何も出さない。
+//! 遅い GUI を溢れさせないよう、既定（未設定）では通知しない。
+
+use std::fmt;
+use std::sync::Arc;
+
+use super::{SearchWorker, TimeManagement, TimePoint};
+use crate::types::{Depth, Move};
+
+/// ルートで探索を始める手
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CurrMoveInfo {
+    /// 反復深さ
+    pub depth: Depth,
+    /// これから探索する手
+    pub currmove: Move,
+    /// ルートで何手目か（1-indexed）
+    pub currmovenumber: usize,
+}
+
+impl CurrMoveInfo {
+    /// USI形式のinfo文字列を生成
+    pub fn to_usi_string(&self) -> String {
+        format!(
+            "info depth {} currmove {} currmovenumber {}",
+            self.depth,
+            self.currmove.to_usi(),
+            self.currmovenumber
+        )
+    }
+}
+
+/// [`CurrMoveInfo`] の通知先（探索スレッドから呼ばれる）
+pub type CurrMoveSink = Arc<dyn Fn(&CurrMoveInfo) + Send + Sync>;
+
+/// `info currmove` の通知条件
+#[derive(Clone)]
+pub struct CurrMoveReport {
+    /// 通知の最小間隔（ミリ秒）
+    pub interval_ms: u64,
+    /// 通知先
+    pub sink: CurrMoveSink,
+}
+
+impl fmt::Debug for CurrMoveReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CurrMoveReport")
+            .field("interval_ms", &self.interval_ms)
+            .finish_non_exhaustive()
+    }
+}
+
+impl SearchWorker {
+    /// 次の通知時刻を探索開始時の値に戻す
+    pub(super) fn reset_currmove_report(&mut self) {
+        self.state.next_currmove_report_ms = self
+            .currmove_report
+            .as_ref()
+            .map_or(0, |report| report.interval_ms as TimePoint);
+    }
+
+    /// ルートの指し手ループで、通知時刻を過ぎていれば `mv` を通知する
+    #[inline]
+    pub(super) fn report_currmove(
+        &mut self,
+        mv: Move,
+        move_number: usize,
+        time_manager: &TimeManagement,
+    ) {
+        let Some(report) = &self.currmove_report else {
+            return;
+        };
+        let elapsed = time_manager.elapsed();
+        if elapsed < self.state.next_currmove_report_ms {
+            return;
+        }
+        self.state.next_currmove_report_ms = elapsed + report.interval_ms as TimePoint;
+        (report.sink)(&CurrMoveInfo {
+            depth: self.state.root_depth,
+            currmove: mv,
+            currmovenumber: move_number,
+        });
+    }
+}
@@ -5,6 +5,9 @@
 use crate::eval::EvalHash;
 use crate::time::Instant;
 use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::Path;
 // AtomicU64 is only needed for native multi-threaded builds.
 // Wasm Rayon model doesn't use SearchProgress.
 use std::sync::Arc;
@@ -14,16 +17,23 @@ use std::sync::atomic::{AtomicBool, Ordering};
 use std::thread;
 use std::time::Duration;
 
+use super::confidence::ConfidenceTracker;
+use super::snapshot::{
+    IterationOutcome, SearchSnapshot, SnapshotError, SnapshotOptions, SnapshotRecorder,
+};
 use super::time_manager::{
     DEFAULT_MAX_MOVES_TO_DRAW, calculate_falling_eval, calculate_time_reduction,
     normalize_nodes_effort,
 };
 use super::{
-    DEFAULT_DRAW_VALUE_BLACK, DEFAULT_DRAW_VALUE_WHITE, LimitsType, RootMove, SearchTuneParams,
-    SearchWorker, Skill, SkillOptions, ThreadPool, TimeManagement,
+    CurrMoveReport, DEFAULT_DRAW_VALUE_BLACK, DEFAULT_DRAW_VALUE_WHITE, EvalBreakdown, LimitsType,
+    RootMove, SearchConfidence, SearchTuneParams, SearchWorker, Skill, SkillOptions, ThreadPool,
+    TimeManagement,
 };
+use crate::book::Book;
+use crate::nnue::{AccumulatorStackVariant, evaluate_dispatch, get_network};
 use crate::position::Position;
-use crate::tt::TranspositionTable;
+use crate::tt::{TranspositionTable, TtAllocInfo, TtAllocOptions, TtFileError, TtStats};
 use crate::types::{Depth, EnteringKingRule, MAX_PLY, Move, Value};
 
 // =============================================================================
@@ -51,6 +61,10 @@ pub struct SearchInfo {
     pub pv: Vec<Move>,
     /// MultiPV番号（1-indexed）
     pub multi_pv: usize,
+    /// メインスレッドの実際の CPU 使用率（%、`cpu_limit_percent` が有効な探索のみ）
+    ///
+    /// USI の info には含めない（[`to_usi_string`](Self::to_usi_string) は出力しない）。
+    pub cpu_usage: Option<u32>,
 }
 
 impl SearchInfo {
@@ -172,10 +186,99 @@ pub struct SearchResult {
     pub nodes: u64,
     /// Principal Variation（読み筋）
     pub pv: Vec<Move>,
+    /// 最終スコアの確からしさ（メインスレッドの直近イテレーションから推定）
+    pub confidence: SearchConfidence,
     /// 探索統計レポート（search-stats feature有効時のみ内容あり）
     pub stats_report: String,
+    /// MultiPV の各候補手の読み筋（順位順、最後に出力した `info ... multipv` と同じ並び）
+    ///
+    /// `MultiPV` が 1 のときは最善手の 1 本だけを持つ。
+    pub lines: Vec<PvLine>,
+}
+
+/// MultiPV の候補手 1 本分の読み筋
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PvLine {
+    /// 順位（1-indexed、`info` の `multipv` と同じ）
+    pub multi_pv: usize,
+    /// スコア（ルート手番側から見た値）
+    pub score: Value,
+    /// スコアが下界（fail high）か
+    pub score_lower_bound: bool,
+    /// スコアが上界（fail low）か
+    pub score_upper_bound: bool,
+    /// このスコアを得た探索深さ
+    pub depth: Depth,
+    /// 選択的深さ
+    pub sel_depth: i32,
+    /// 読み筋（先頭が候補手）
+    pub pv: Vec<Move>,
+}
+
+/// ルート手の上位 `count` 本を [`PvLine`] にする
+///
+/// 中断された反復で未探索のまま残った候補手は、完了した反復のスコアを使う。
+/// 一度も探索されていない候補手（スコアが -INFINITE のまま）は含めない。
+fn collect_pv_lines(
+    root_moves: &super::RootMoves,
+    count: usize,
+    completed_depth: Depth,
+) -> Vec<PvLine> {
+    let count = count.min(root_moves.len());
+    let mut lines = Vec::with_capacity(count);
+    for idx in 0..count {
+        let rm = &root_moves[idx];
+        let updated = rm.score != -Value::INFINITE;
+        let score = if updated { rm.score } else { rm.previous_score };
+        if score == -Value::INFINITE {
+            continue;
+        }
+        lines.push(PvLine {
+            multi_pv: lines.len() + 1,
+            score,
+            score_lower_bound: updated && rm.score_lower_bound,
+            score_upper_bound: updated && rm.score_upper_bound,
+            depth: completed_depth,
+            sel_depth: rm.sel_depth,
+            pv: rm.pv.clone(),
+        });
+    }
+    lines
+}
+
+// =============================================================================
+// SearchMemoryStats - メモリ使用量
+// =============================================================================
+
+/// 探索エンジンのメモリ使用量（`Search::memory_stats()`）
+///
+/// Web 向けバインディングがメモリ逼迫時に `Search::resize_tt()` で置換表を縮小する判断や、
+/// タブのクラッシュ時の診断情報として使う。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchMemoryStats {
+    /// 置換表サイズ（MB）
+    pub tt_mb: usize,
+    /// EvalHash サイズ（MB）
+    pub eval_hash_mb: usize,
+    /// wasm の linear memory サイズ（バイト、wasm32 以外では `None`）
+    pub linear_memory_bytes: Option<u64>,
+}
+
+/// `Search::prepare_root()` の結果
+///
+/// bestmove 出力後、次に来る見込みのルート局面を先読みで温めたときの状態を表す。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RootPreparation {
+    /// ルート局面が置換表にヒットしたか
+    pub tt_hit: bool,
+    /// ルート局面の静的評価値（NNUE 未ロード時は `None`）
+    pub static_eval: Option<Value>,
 }
 
+/// wasm の linear memory のページサイズ（バイト）
+#[cfg(target_arch = "wasm32")]
+const WASM_PAGE_SIZE: u64 = 64 * 1024;
+
 // =============================================================================
 // PonderhitHandle - ponderhit 通知用のハンドル
 // =============================================================================
@@ -236,6 +339,8 @@ pub struct Search {
     eval_hash: Arc<EvalHash>,
     /// 置換表のサイズ（MB）
     tt_size_mb: usize,
+    /// 置換表の確保方法（Large Pages / NUMA interleave）
+    tt_alloc_options: TtAllocOptions,
     /// EvalHashのサイズ（MB）
     eval_hash_size_mb: usize,
     /// 停止フラグ
@@ -282,6 +387,8 @@ pub struct Search {
     increase_depth_shared: Arc<AtomicBool>,
     /// 深さを伸ばせなかった回数（aspiration時の調整に使用）
     search_again_counter: i32,
+    /// 直前の探索結果の確からしさ
+    confidence: SearchConfidence,
 
     /// 引き分けまでの最大手数（エンジンオプション）
     max_moves_to_draw: i32,
@@ -289,10 +396,18 @@ pub struct Search {
     draw_value_black: i32,
     /// YaneuraOuオプション `DrawValueWhite`
     draw_value_white: i32,
+    /// 引き分けを嫌う度合い（cp）。ルート手番側の引き分けの評価値から差し引く
+    contempt: i32,
     /// SPSA向け探索係数
     search_tune_params: SearchTuneParams,
     /// 入玉宣言勝ちルール
     entering_king_rule: EnteringKingRule,
+    /// 定跡（`go` で定跡にある局面なら探索せずに定跡手を返す）
+    book: Option<Arc<Book>>,
+    /// 探索スナップショットの書き出し条件（`None` で書き出さない）
+    snapshot_options: Option<SnapshotOptions>,
+    /// `info currmove` の通知条件（`None` で通知しない）
+    currmove_report: Option<CurrMoveReport>,
 }
 
 /// best_move_changes を集約する（並列探索対応のためのヘルパー）
@@ -571,6 +686,7 @@ struct BestThreadResult {
     best_previous_score: Option<Value>,
     best_previous_average_score: Option<Value>,
     pv: Vec<Move>,
+    lines: Vec<PvLine>,
 }
 
 fn collect_best_thread_result(
@@ -600,6 +716,7 @@ fn collect_best_thread_result(
             best_previous_score,
             best_previous_average_score,
             pv: Vec::new(),
+            lines: Vec::new(),
         };
     }
 
@@ -635,6 +752,7 @@ fn collect_best_thread_result(
         .unwrap_or(worker.state.root_moves.get(0).map(|rm| rm.score).unwrap_or(Value::ZERO));
 
     let pv = best_rm.map(|rm| rm.pv.clone()).unwrap_or_default();
+    let lines = collect_pv_lines(&worker.state.root_moves, limits.multi_pv, completed_depth);
 
     BestThreadResult {
         best_move,
@@ -645,6 +763,7 @@ fn collect_best_thread_result(
         best_previous_score,
         best_previous_average_score,
         pv,
+        lines,
     }
 }
 
@@ -720,6 +839,7 @@ impl Search {
             tt,
             eval_hash,
             tt_size_mb,
+            tt_alloc_options: TtAllocOptions::default(),
             eval_hash_size_mb,
             stop,
             ponderhit_flag,
@@ -742,17 +862,22 @@ impl Search {
             increase_depth: true,
             increase_depth_shared,
             search_again_counter: 0,
+            confidence: SearchConfidence::default(),
             max_moves_to_draw,
             draw_value_black: DEFAULT_DRAW_VALUE_BLACK,
             draw_value_white: DEFAULT_DRAW_VALUE_WHITE,
+            contempt: 0,
             search_tune_params,
             entering_king_rule: EnteringKingRule::default(),
+            book: None,
+            snapshot_options: None,
+            currmove_report: None,
         }
     }
 
     /// 置換表のサイズを変更
     pub fn resize_tt(&mut self, size_mb: usize) {
-        self.tt = Arc::new(TranspositionTable::new(size_mb));
+        self.tt = Arc::new(TranspositionTable::with_options(size_mb, self.tt_alloc_options));
         self.tt_size_mb = size_mb;
         // workerが存在する場合、TT参照を更新
         if let Some(worker) = &mut self.worker {
@@ -761,17 +886,51 @@ impl Search {
         self.thread_pool.update_tt(Arc::clone(&self.tt));
     }
 
-    /// 置換表をクリア
+    /// 置換表の現在サイズ（MB）を返す。
+    pub fn tt_size_mb(&self) -> usize {
+        self.tt_size_mb
+    }
+
+    /// 置換表をクリアし、要した時間を返す
     ///
-    /// 新しい置換表を作成して置き換える。
-    pub fn clear_tt(&mut self) {
-        // Arc経由では&mutが取れないので、同じサイズの新しいTTを作成して置き換える
-        self.tt = Arc::new(TranspositionTable::new(self.tt_size_mb));
-        // workerが存在する場合、TT参照を更新
-        if let Some(worker) = &mut self.worker {
-            worker.tt = Arc::clone(&self.tt);
-        }
-        self.thread_pool.update_tt(Arc::clone(&self.tt));
+    /// 確保し直さずにその場で並列ゼロクリアする（大きなテーブルで isready / usinewgame が
+    /// 長時間ブロックしないように）。探索停止中にのみ呼び出すこと。
+    pub fn clear_tt(&mut self) -> Duration {
+        let start = Instant::now();
+        self.tt.clear();
+        start.elapsed()
+    }
+
+    /// 新しい対局に向けて置換表の世代を進める（確保し直しもゼロクリアもしない）
+    ///
+    /// 前の対局のエントリは置換されやすくなるだけで、probe では引き続き参照できる。
+    pub fn new_game_tt(&self) {
+        self.tt.new_game();
+    }
+
+    /// 置換表の使用中のエントリをファイルに保存し、保存したエントリ数を返す
+    ///
+    /// 長い検討を中断して後で [`load_tt`](Self::load_tt) で再開する用途。
+    /// 探索停止中にのみ呼び出すこと。
+    pub fn save_tt(&self, path: &Path) -> Result<usize, TtFileError> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        let entries = self.tt.save_to(&mut writer)?;
+        writer.flush()?;
+        Ok(entries)
+    }
+
+    /// [`save_tt`](Self::save_tt) で保存した置換表を読み込み、読み込んだエントリ数を返す
+    ///
+    /// 置換表のサイズ（`USI_Hash`）は保存時と同じにしておくこと。サイズや Zobrist キーが
+    /// 合わないファイルは置換表に触れずにエラーを返す。探索停止中にのみ呼び出すこと。
+    pub fn load_tt(&mut self, path: &Path) -> Result<usize, TtFileError> {
+        self.tt.load_from(&mut BufReader::new(File::open(path)?))
+    }
+
+    /// 置換表の統計（使用中のエントリの世代・深さの内訳と、`tt-stats` feature 有効時は
+    /// probe のヒット率・置換率）。ベンチマークで置換方針を比べる用途
+    pub fn tt_stats(&self) -> TtStats {
+        self.tt.stats()
     }
 
     /// Large Pagesで確保されているかを返す
@@ -779,6 +938,52 @@ impl Search {
         self.tt.uses_large_pages()
     }
 
+    /// 置換表の確保方法を変更する（変わったときだけ確保し直し、内容は失われる）
+    pub fn set_tt_alloc_options(&mut self, options: TtAllocOptions) {
+        if options != self.tt_alloc_options {
+            self.tt_alloc_options = options;
+            self.resize_tt(self.tt_size_mb);
+        }
+    }
+
+    /// 置換表の実際の確保方法
+    pub fn tt_alloc_info(&self) -> TtAllocInfo {
+        self.tt.alloc_info()
+    }
+
+    /// 直前の探索の root 統計から補助 policy の教師分布を作る（学習ツール向け）
+    ///
+    /// `us` は探索した局面の手番。探索前、または root move が無い場合は空を返す。
+    #[cfg(feature = "policy-ordering")]
+    pub fn root_policy_targets(&self, us: crate::types::Color) -> Vec<super::PolicyTarget> {
+        self.worker
+            .as_ref()
+            .map(|worker| super::policy_targets(&worker.state.root_moves, us))
+            .unwrap_or_default()
+    }
+
+    /// 次の `go` で使う見込みのルート局面を事前に温める
+    ///
+    /// ルート局面の置換表クラスターを prefetch して probe し、NNUE でルート局面を
+    /// 評価してネットワークの重みをキャッシュに載せる。worker の探索状態には触れない
+    /// ため、予測が外れて別の局面で `go` されても探索結果は変わらない。
+    /// 探索停止中にのみ呼び出すこと。
+    pub fn prepare_root(&self, pos: &Position) -> RootPreparation {
+        let key = pos.key();
+        self.tt.prefetch(key, pos.side_to_move());
+        let tt_hit = self.tt.probe(key, pos).found;
+
+        let static_eval = get_network().map(|network| {
+            let mut stack = AccumulatorStackVariant::from_network(&network);
+            evaluate_dispatch(pos, &mut stack, &mut None)
+        });
+
+        RootPreparation {
+            tt_hit,
+            static_eval,
+        }
+    }
+
     /// EvalHashのサイズを変更
     ///
     /// # 注意
@@ -806,6 +1011,21 @@ impl Search {
         self.eval_hash_size_mb
     }
 
+    /// メモリ使用量を返す
+    pub fn memory_stats(&self) -> SearchMemoryStats {
+        #[cfg(target_arch = "wasm32")]
+        let linear_memory_bytes =
+            Some(core::arch::wasm32::memory_size::<0>() as u64 * WASM_PAGE_SIZE);
+        #[cfg(not(target_arch = "wasm32"))]
+        let linear_memory_bytes = None;
+
+        SearchMemoryStats {
+            tt_mb: self.tt_size_mb,
+            eval_hash_mb: self.eval_hash_size_mb,
+            linear_memory_bytes,
+        }
+    }
+
     /// 履歴統計をクリア（usinewgame時に呼び出し）
     ///
     /// Worker::clear()相当
@@ -886,9 +1106,7 @@ impl Search {
     /// 有効範囲は `[-30000, 30000]`。
     pub fn set_draw_value_black(&mut self, v: i32) {
         self.draw_value_black = v.clamp(-30000, 30000);
-        if let Some(worker) = &mut self.worker {
-            worker.draw_value_black = self.draw_value_black;
-        }
+        self.sync_worker_draw_values();
     }
 
     /// 現在の `DrawValueBlack` を取得する。
@@ -901,9 +1119,7 @@ impl Search {
     /// 有効範囲は `[-30000, 30000]`。
     pub fn set_draw_value_white(&mut self, v: i32) {
         self.draw_value_white = v.clamp(-30000, 30000);
-        if let Some(worker) = &mut self.worker {
-            worker.draw_value_white = self.draw_value_white;
-        }
+        self.sync_worker_draw_values();
     }
 
     /// 現在の `DrawValueWhite` を取得する。
@@ -911,6 +1127,38 @@ impl Search {
         self.draw_value_white
     }
 
+    /// 引き分けを嫌う度合い `Contempt`（cp）を設定する。
+    ///
+    /// 正の値ほど引き分け（千日手・最大手数）をルート手番側にとって悪く評価し、
+    /// 負の値なら引き分けを好む。相手番の局面では符号が反転する。`DrawValueBlack` /
+    /// `DrawValueWhite` に加算されるので、両方を指定した場合は
+    /// `DrawValue(手番) - Contempt` が引き分けの評価値になる。有効範囲は `[-30000, 30000]`。
+    pub fn set_contempt(&mut self, v: i32) {
+        self.contempt = v.clamp(-30000, 30000);
+        self.sync_worker_draw_values();
+    }
+
+    /// 現在の `Contempt` を取得する。
+    pub fn contempt(&self) -> i32 {
+        self.contempt
+    }
+
+    /// `Contempt` を反映した先手・後手の引き分けの評価値（cp）
+    fn effective_draw_values(&self) -> (i32, i32) {
+        (
+            (self.draw_value_black - self.contempt).clamp(-30000, 30000),
+            (self.draw_value_white - self.contempt).clamp(-30000, 30000),
+        )
+    }
+
+    fn sync_worker_draw_values(&mut self) {
+        let (black, white) = self.effective_draw_values();
+        if let Some(worker) = &mut self.worker {
+            worker.draw_value_black = black;
+            worker.draw_value_white = white;
+        }
+    }
+
     /// 入玉宣言勝ちルールを設定する。
     pub fn set_entering_king_rule(&mut self, rule: EnteringKingRule) {
         self.entering_king_rule = rule;
@@ -972,8 +1220,220 @@ impl Search {
         Some(result)
     }
 
+    /// 定跡を設定（`None` で定跡を使わない）
+    pub fn set_book(&mut self, book: Option<Arc<Book>>) {
+        self.book = book;
+    }
+
+    /// 設定中の定跡
+    pub fn book(&self) -> Option<&Arc<Book>> {
+        self.book.as_ref()
+    }
+
+    /// 探索スナップショットの書き出し条件を設定（`None` で書き出さない）
+    ///
+    /// `Threads` が 1 の探索でのみ書き出す（[`super::snapshot`] 参照）。
+    pub fn set_snapshot_options(&mut self, options: Option<SnapshotOptions>) {
+        self.snapshot_options = options;
+    }
+
+    /// 探索スナップショットの書き出し条件
+    pub fn snapshot_options(&self) -> Option<&SnapshotOptions> {
+        self.snapshot_options.as_ref()
+    }
+
+    /// `info currmove` の通知条件を設定（`None` で通知しない）
+    ///
+    /// メインスレッドのルートの指し手ループから通知する（[`super::currmove`] 参照）。
+    pub fn set_currmove_report(&mut self, report: Option<CurrMoveReport>) {
+        self.currmove_report = report;
+    }
+
+    /// 定跡から指し手を選ぶ（定跡にない局面・定跡を使えない探索条件なら `None`）
+    ///
+    /// ponder / infinite は停止指示まで探索を続ける必要があり、mate / perft は
+    /// 目的が異なるため定跡を使わない。searchmoves 指定時は、選んだ手がその中に
+    /// なければ通常探索に回す。
+    fn probe_book(&self, pos: &Position, limits: &LimitsType) -> Option<SearchResult> {
+        let book = self.book.as_ref()?;
+        if limits.ponder || limits.infinite || limits.mate != 0 || limits.perft != 0 {
+            return None;
+        }
+        let picked = book.pick(pos, &mut rand::rng())?;
+        if !limits.search_moves.is_empty()
+            && !limits.search_moves.iter().any(|m| m.to_u16() == picked.mv.to_u16())
+        {
+            return None;
+        }
+
+        let mut next = pos.clone_with_history();
+        let gives_check = next.gives_check(picked.mv);
+        next.do_move(picked.mv, gives_check);
+        let ponder_move = book.best(&next).map_or(Move::NONE, |m| m.mv);
+        let mut pv = vec![picked.mv];
+        if ponder_move.is_some() {
+            pv.push(ponder_move);
+        }
+        let score = Value::from_cp(picked.score as i32);
+        let depth = picked.depth as Depth;
+        Some(SearchResult {
+            best_move: picked.mv,
+            ponder_move,
+            score,
+            depth,
+            nodes: 0,
+            pv: pv.clone(),
+            confidence: SearchConfidence::default(),
+            stats_report: String::new(),
+            lines: vec![PvLine {
+                multi_pv: 1,
+                score,
+                score_lower_bound: false,
+                score_upper_bound: false,
+                depth,
+                sel_depth: depth,
+                pv,
+            }],
+        })
+    }
+
+    /// メインスレッドの worker（遅延初期化、再利用する）
+    ///
+    /// setoption で変更された可能性がある設定値は呼び出しごとに反映する。
+    fn main_worker(&mut self) -> &mut SearchWorker {
+        let tt_clone = Arc::clone(&self.tt);
+        let eval_hash_clone = Arc::clone(&self.eval_hash);
+        let max_moves = self.max_moves_to_draw;
+        let search_tune_params = self.search_tune_params;
+        let draw_values = self.effective_draw_values();
+        let worker = self.worker.get_or_insert_with(|| {
+            SearchWorker::new(tt_clone, eval_hash_clone, max_moves, 0, search_tune_params)
+        });
+        worker.max_moves_to_draw = self.max_moves_to_draw;
+        worker.search_tune_params = self.search_tune_params;
+        (worker.draw_value_black, worker.draw_value_white) = draw_values;
+        worker.entering_king_rule = self.entering_king_rule;
+        worker.currmove_report = self.currmove_report.clone();
+        worker
+    }
+
+    /// 反復深化を行わず、局面から静止探索だけを行った評価値（手番側から見た値）
+    ///
+    /// 教師局面の生成で、駒の取り合いが落ち着いた後の評価値をラベルにする用途。
+    /// 置換表と履歴統計は `go` と共有し、千日手・引き分け手数の設定も `go` と同じく反映する。
+    /// 定跡は参照せず、`stop` では中断しない。探索スタックを使うため、`go` と同様に
+    /// 十分なスタックサイズのスレッドから呼び出すこと。
+    /// 終局局面（詰み・宣言勝ち）は [`Position::terminal_value`] を返す。
+    pub fn qsearch(&mut self, pos: &mut Position) -> Value {
+        if let Some(value) = pos.terminal_value(self.entering_king_rule) {
+            return value;
+        }
+        let mut limits = LimitsType {
+            infinite: true,
+            ..Default::default()
+        };
+        limits.set_start_time();
+        let mut time_manager =
+            TimeManagement::new(Arc::new(AtomicBool::new(false)), Arc::new(AtomicBool::new(false)));
+        let worker = self.main_worker();
+        worker.prepare_search();
+        worker.allow_tt_write = true;
+        worker.qsearch_root(pos, &limits, &mut time_manager)
+    }
+
+    /// 評価関数による局面の静的評価値（手番側から見た値）
+    ///
+    /// 探索用の worker のアキュムレータを初期化してから評価する。探索中の静的評価と異なり、
+    /// 補正履歴やパス権の評価は加えない。NNUE が読み込まれておらず Material 評価も
+    /// 有効でない場合は panic する（`go` と同じ）。
+    pub fn static_eval(&mut self, pos: &Position) -> Value {
+        let worker = self.main_worker();
+        worker.prepare_search();
+        worker.evaluate_root(pos)
+    }
+
+    /// 静的評価値の内訳（NNUE / Material の出力・駒得・手番の価値・駒ごとの寄与）
+    ///
+    /// 駒ごとの寄与と手番の価値は、その要素を取り除いた局面を [`static_eval`](Self::static_eval)
+    /// で評価し直した差で求める（[`super::eval_breakdown`] 参照）。盤上の駒の数 + 数十回
+    /// 評価するので、探索中には使わないこと。
+    pub fn eval_breakdown(&mut self, pos: &Position) -> EvalBreakdown {
+        let terminal = pos.terminal_value(self.entering_king_rule);
+        EvalBreakdown {
+            terminal,
+            ..EvalBreakdown::compute(pos, |p| self.static_eval(p))
+        }
+    }
+
+    /// 探索スナップショットの反復を再実行する（デバッグ用）
+    ///
+    /// スナップショットの置換表・履歴統計・探索スタック・ルート手を書き戻し、
+    /// 記録された深さの反復を 1 回だけ時間無制限で探索する。置換表のサイズは
+    /// スナップショットに合わせて変更する。
+    /// 同じスナップショットからの再実行は毎回同じ結果になる。
+    /// 評価関数は呼び出し前に設定しておくこと。`go` と同様に十分なスタックサイズの
+    /// スレッドから呼び出すこと。
+    pub fn replay_snapshot(
+        &mut self,
+        snapshot: &SearchSnapshot,
+    ) -> Result<IterationOutcome, SnapshotError> {
+        let mut pos = Position::new();
+        pos.set_sfen_ext(&snapshot.sfen)
+            .map_err(|e| SnapshotError::Format(format!("invalid sfen: {e}")))?;
+        if snapshot.tt_size_mb() != self.tt_size_mb {
+            self.resize_tt(snapshot.tt_size_mb());
+        }
+        self.max_moves_to_draw = snapshot.max_moves_to_draw;
+
+        // 打ち切り手段の有無で延長の上限が変わるため、元の探索に合わせる（どちらも時間では止めない）
+        let mut limits = if snapshot.interrupt_budget {
+            LimitsType {
+                infinite: true,
+                ..Default::default()
+            }
+        } else {
+            LimitsType {
+                depth: snapshot.depth,
+                ..Default::default()
+            }
+        };
+        limits.set_start_time();
+        let mut time_manager =
+            TimeManagement::new(Arc::new(AtomicBool::new(false)), Arc::new(AtomicBool::new(false)));
+        let worker = self.main_worker();
+        // スナップショットの値は Contempt を反映済み
+        worker.draw_value_black = snapshot.draw_value_black;
+        worker.draw_value_white = snapshot.draw_value_white;
+        worker.prepare_search();
+        worker.allow_tt_write = true;
+        worker.generate_all_legal_moves = snapshot.generate_all_legal_moves;
+        snapshot.restore(worker, &mut pos)?;
+        if worker.state.root_moves.is_empty() {
+            return Err(SnapshotError::Format("no root moves".to_string()));
+        }
+        worker.state.root_depth = snapshot.depth;
+
+        search_pv_line(
+            worker,
+            &mut pos,
+            &limits,
+            &mut time_manager,
+            0,
+            snapshot.depth,
+            snapshot.search_again_counter,
+            &mut None,
+        );
+        Ok(IterationOutcome {
+            score: worker.state.root_moves[0].score,
+            pv: worker.state.root_moves[0].pv.clone(),
+            nodes: worker.state.nodes - snapshot.nodes,
+        })
+    }
+
     /// 探索を実行
     ///
+    /// 定跡（[`set_book`](Self::set_book)）にある局面では探索せずに定跡手を返す。
+    ///
     /// # Arguments
     /// * `pos` - 探索対象の局面
     /// * `limits` - 探索制限
@@ -990,12 +1450,24 @@ impl Search {
     where
         F: FnMut(&SearchInfo),
     {
+        if let Some(result) = self.probe_book(pos, &limits) {
+            return result;
+        }
         let ply = pos.game_ply();
+        #[cfg(feature = "search-tracing")]
+        let go_span = tracing::debug_span!(
+            "go",
+            ply,
+            threads = self.num_threads,
+            depth = tracing::field::Empty,
+            nodes = tracing::field::Empty
+        )
+        .entered();
         self.prepare_time_metrics(ply);
         // 注意: stop/ponderhitフラグのリセットは go() の呼び出し元
         // (USI層の cmd_go) でスレッド生成前に行うこと。
         // ここでリセットすると、USI層で既にセットされたフラグが失われる競合が発生する。
-        self.start_time = Some(Instant::now());
+        self.start_time = Some(limits.clock.now());
         // 置換表の世代を進める
         self.tt.new_search();
         // ヘルパースレッドの結果をクリア
@@ -1007,26 +1479,12 @@ impl Search {
             TimeManagement::new(Arc::clone(&self.stop), Arc::clone(&self.ponderhit_flag));
         time_manager.set_options(&self.time_options);
         time_manager.set_previous_time_reduction(self.previous_time_reduction);
+        time_manager.set_game_phase(pos.phase());
         // ply（現在の手数）は局面から取得、max_moves_to_drawはデフォルトを使う
         time_manager.init(&limits, pos.side_to_move(), ply, self.max_moves_to_draw);
 
-        // workerは遅延初期化、再利用する
-        let tt_clone = Arc::clone(&self.tt);
-        let eval_hash_clone = Arc::clone(&self.eval_hash);
-        let max_moves = self.max_moves_to_draw;
-        let search_tune_params = self.search_tune_params;
-        let draw_value_black = self.draw_value_black;
-        let draw_value_white = self.draw_value_white;
-        let worker = self.worker.get_or_insert_with(|| {
-            SearchWorker::new(tt_clone, eval_hash_clone, max_moves, 0, search_tune_params)
-        });
-
-        // setoptionで変更された可能性があるため、最新値を反映
-        worker.max_moves_to_draw = self.max_moves_to_draw;
-        worker.search_tune_params = self.search_tune_params;
-        worker.draw_value_black = self.draw_value_black;
-        worker.draw_value_white = self.draw_value_white;
-        worker.entering_king_rule = self.entering_king_rule;
+        let (draw_value_black, draw_value_white) = self.effective_draw_values();
+        let worker = self.main_worker();
 
         // 探索状態のリセット（履歴はクリアしない）
         worker.prepare_search();
@@ -1159,6 +1617,7 @@ impl Search {
                         best_previous_score: Some(r.best_score),
                         best_previous_average_score: Some(r.best_score),
                         pv: Vec::new(), // Cannot get PV from helper in Wasm
+                        lines: Vec::new(),
                     }
                 })
             };
@@ -1185,7 +1644,14 @@ impl Search {
             best_previous_score,
             best_previous_average_score,
             pv,
+            lines,
         } = best_result;
+        // 合法手の無いルートは探索しないので、評価値を終局局面の値にそろえる
+        let score = if best_move == Move::NONE {
+            pos.terminal_value(self.entering_king_rule).unwrap_or(score)
+        } else {
+            score
+        };
         let total_nodes = {
             let main_nodes = self.worker.as_ref().map(|w| w.state.nodes).unwrap_or(0);
 
@@ -1218,6 +1684,12 @@ impl Search {
         // 探索統計レポートを取得（search-stats feature有効時のみ内容あり）
         let stats_report = self.worker.as_ref().map(|w| w.get_stats_report()).unwrap_or_default();
 
+        #[cfg(feature = "search-tracing")]
+        {
+            go_span.record("depth", completed_depth);
+            go_span.record("nodes", total_nodes);
+        }
+
         SearchResult {
             best_move,
             ponder_move,
@@ -1225,7 +1697,9 @@ impl Search {
             depth: completed_depth,
             nodes: total_nodes,
             pv,
+            confidence: self.confidence,
             stats_report,
+            lines,
         }
     }
 
@@ -1265,6 +1739,12 @@ impl Search {
             last_best_move_depth: self.last_best_move_depth,
             tot_best_move_changes: self.tot_best_move_changes,
             increase_depth_shared: &self.increase_depth_shared,
+            confidence: ConfidenceTracker::default(),
+            snapshot: self
+                .snapshot_options
+                .as_ref()
+                .filter(|_| self.num_threads == 1)
+                .map(SnapshotRecorder::new),
         };
 
         let mut noop_progress = |_nodes: u64, _bmc: f64| {};
@@ -1289,6 +1769,7 @@ impl Search {
         self.last_best_move = main_state.last_best_move;
         self.last_best_move_depth = main_state.last_best_move_depth;
         self.tot_best_move_changes = main_state.tot_best_move_changes;
+        self.confidence = main_state.confidence.estimate();
 
         // workerを戻す
         self.worker = Some(worker);
@@ -1316,6 +1797,8 @@ struct MainThreadState<'a> {
     last_best_move_depth: Depth,
     tot_best_move_changes: f64,
     increase_depth_shared: &'a AtomicBool,
+    confidence: ConfidenceTracker,
+    snapshot: Option<SnapshotRecorder<'a>>,
 }
 
 impl MainThreadState<'_> {
@@ -1340,6 +1823,25 @@ impl MainThreadState<'_> {
     }
 }
 
+/// ponder 中 / go infinite 中に探索を終えたとき、stop（ponder は ponderhit 後の時間切れ）まで待つ
+fn wait_for_stop_or_ponderhit(
+    worker: &SearchWorker,
+    limits: &LimitsType,
+    time_manager: &mut TimeManagement,
+    ms: &MainThreadState,
+) {
+    while !worker.state.abort
+        && !time_manager.stop_requested()
+        && (time_manager.is_pondering() || limits.infinite)
+    {
+        if ms.ponderhit_flag.swap(false, Ordering::Relaxed) {
+            time_manager.on_ponderhit();
+        }
+        // YaneuraOu 同様、探索終了後の待機では短時間 sleep して busy wait を避ける。
+        thread::sleep(Duration::from_millis(1));
+    }
+}
+
 /// YaneuraOu の iterative_deepening() に対応する統合反復深化ループ。
 ///
 /// メインスレッドでは `main_state = Some(...)` で呼び出し、
@@ -1375,7 +1877,8 @@ where
             worker.state.root_moves.push(super::RootMove::new(decl_move));
         }
         if let Some(idx) = worker.state.root_moves.find(decl_move) {
-            worker.state.root_moves[idx].score = Value::MATE;
+            worker.state.root_moves[idx].score =
+                pos.terminal_value(worker.entering_king_rule).unwrap_or(Value::MATE);
             worker.state.root_moves.move_to_front(idx);
         }
         worker.state.best_move = decl_move;
@@ -1387,21 +1890,17 @@ where
 
         // ponder/infinite 待機: bestmove を早出ししない（USI仕様準拠）
         if let Some(ref ms) = main_state {
-            while !worker.state.abort
-                && !time_manager.stop_requested()
-                && (time_manager.is_pondering() || limits.infinite)
-            {
-                if ms.ponderhit_flag.swap(false, Ordering::Relaxed) {
-                    time_manager.on_ponderhit();
-                }
-                thread::sleep(Duration::from_millis(1));
-            }
+            wait_for_stop_or_ponderhit(worker, limits, time_manager, ms);
         }
         return 0;
     }
 
     if worker.state.root_moves.is_empty() {
         worker.state.best_move = Move::NONE;
+        // 詰んでいる局面でも go infinite / ponder は stop まで bestmove を返さない
+        if let Some(ref ms) = main_state {
+            wait_for_stop_or_ponderhit(worker, limits, time_manager, ms);
+        }
         return 0;
     }
 
@@ -1424,6 +1923,10 @@ where
     // ヘルパー用のローカル search_again_counter
     let mut local_search_again_counter: i32 = 0;
 
+    // MultiPV フォーカスモード: 直前の反復の最善手・評価値と、次の反復で広げ直すか
+    let mut focus_prev_best: Option<(Move, Value)> = None;
+    let mut focus_rewiden = false;
+
     // 反復深化ループ開始前に best_move を初期化
     // nodes 制限等で depth 1 完了前に abort された場合でも有効な手を返すため
     if !worker.state.root_moves.is_empty() {
@@ -1491,6 +1994,17 @@ where
 
         // メインのみ: if (!mainThread) continue; に対応する部分はループ末尾で処理
 
+        #[cfg(feature = "search-tracing")]
+        let iteration_span = tracing::debug_span!(
+            "iteration",
+            thread = worker.thread_id,
+            depth,
+            nodes = tracing::field::Empty
+        )
+        .entered();
+        #[cfg(feature = "search-tracing")]
+        let iteration_nodes_start = worker.state.nodes;
+
         let search_depth = depth;
         worker.state.root_depth = search_depth;
         worker.state.sel_depth = 0;
@@ -1501,99 +2015,58 @@ where
             local_search_again_counter
         };
 
-        // MultiPVループ
+        // MultiPVループ（フォーカスモードでは深い反復で上位だけに絞る）
+        // Skill Level は全候補の評価値から選ぶため絞らない
+        let pv_lines = if skill_enabled {
+            effective_multi_pv
+        } else {
+            limits.multi_pv_focus.lines_for_depth(effective_multi_pv, depth, focus_rewiden)
+        };
+        if let Some(recorder) = main_state.as_mut().and_then(|ms| ms.snapshot.as_mut()) {
+            recorder.begin_iteration(
+                worker,
+                pos,
+                limits,
+                search_depth,
+                search_again_counter,
+                pv_lines,
+            );
+        }
         let mut processed_pv = 0;
-        for pv_idx in 0..effective_multi_pv {
+        for pv_idx in 0..pv_lines {
             if worker.state.abort {
                 break;
             }
 
-            // Aspiration Window（average/mean_squaredベース）
-            let (mut alpha, mut beta, mut delta) = compute_aspiration_window(
-                &worker.state.root_moves[pv_idx],
-                worker.thread_id,
-                &worker.search_tune_params,
+            search_pv_line(
+                worker,
+                pos,
+                limits,
+                time_manager,
+                pv_idx,
+                search_depth,
+                search_again_counter,
+                &mut main_state,
             );
-            let mut failed_high_cnt = 0;
-
-            // Aspiration Windowループ
-            loop {
-                let adjusted_depth =
-                    (search_depth - failed_high_cnt - (3 * (search_again_counter + 1) / 4)).max(1);
-
-                let score = if pv_idx == 0 {
-                    worker.search_root(pos, adjusted_depth, alpha, beta, limits, time_manager)
-                } else {
-                    worker.search_root_for_pv(
-                        pos,
-                        search_depth,
-                        alpha,
-                        beta,
-                        pv_idx,
-                        limits,
-                        time_manager,
-                    )
-                };
-
-                // aspiration loop 内ソート
-                worker.state.root_moves.stable_sort_range(pv_idx, worker.state.root_moves.len());
-
-                // nodes 制限等で探索が中断された場合の停止判定
-                // abort フラグに加え、nodes 制限超過も直接チェックする
-                // （check_abort は頻度制御で呼び出されるため、abort フラグが
-                //   立っていないまま search_root が返ることがある）
-                if worker.state.abort
-                    || (limits.nodes > 0 && worker.state.nodes >= limits.nodes)
-                    || time_manager.stop_requested()
-                {
-                    worker.state.abort = true;
-                    break;
-                }
-
-                // Window調整
-                if score <= alpha {
-                    beta = alpha;
-                    alpha = Value::new(
-                        score.raw().saturating_sub(delta.raw()).max(-Value::INFINITE.raw()),
-                    );
-                    failed_high_cnt = 0;
-                    // メインのみ
-                    if is_main {
-                        time_manager.reset_stop_on_ponderhit();
-                    }
-                } else if score >= beta {
-                    alpha = Value::new((beta.raw() - delta.raw()).max(alpha.raw()));
-                    beta = Value::new(
-                        score.raw().saturating_add(delta.raw()).min(Value::INFINITE.raw()),
-                    );
-                    failed_high_cnt += 1;
-                } else {
-                    break;
-                }
-
-                // delta 更新
-                delta = Value::new(
-                    delta.raw().saturating_add(delta.raw() / 3).min(Value::INFINITE.raw()),
-                );
-            }
-
-            // 安定ソート [pv_idx..]
-            worker.state.root_moves.stable_sort_range(pv_idx, worker.state.root_moves.len());
-            // 📝 YaneuraOu行1539: 探索済みのPVライン全体も安定ソートして順位を保つ
-            worker.state.root_moves.stable_sort_range(0, pv_idx + 1);
             processed_pv = pv_idx + 1;
         }
 
+        #[cfg(feature = "search-tracing")]
+        {
+            iteration_span.record("nodes", worker.state.nodes - iteration_nodes_start);
+            drop(iteration_span);
+        }
+
         // MultiPVループ完了後の最終ソート（YaneuraOu行1499）
-        if !worker.state.abort && effective_multi_pv > 1 {
-            worker.state.root_moves.stable_sort_range(0, effective_multi_pv);
+        if !worker.state.abort && pv_lines > 1 {
+            worker.state.root_moves.stable_sort_range(0, pv_lines);
         }
 
         // メインのみ: info出力（GUI詰まり防止のYO仕様）
         if let Some(ref ms) = main_state
             && processed_pv > 0
         {
-            let elapsed = ms.start_time.elapsed();
+            let elapsed = limits.clock.elapsed_since(ms.start_time);
             let time_ms = elapsed.as_millis() as u64;
 
             // Native: Use helper_threads() to get node counts
@@ -1626,6 +2099,7 @@ where
                     hashfull: ms.tt.hashfull(3) as u32,
                     pv: worker.state.root_moves[pv_idx].pv.clone(),
                     multi_pv: pv_idx + 1, // 1-indexed
+                    cpu_usage: worker.state.throttle.usage_percent(),
                 };
                 on_info(&info);
             }
@@ -1635,6 +2109,16 @@ where
         if !worker.state.abort {
             worker.state.completed_depth = search_depth;
             worker.state.best_move = worker.state.root_moves[0].mv();
+            if let Some(recorder) = main_state.as_mut().and_then(|ms| ms.snapshot.as_mut()) {
+                recorder.end_iteration(worker);
+            }
+
+            if limits.multi_pv_focus.is_enabled() {
+                let current = (worker.state.best_move, worker.state.root_moves[0].score);
+                focus_rewiden = focus_prev_best
+                    .is_some_and(|prev| limits.multi_pv_focus.should_rewiden(prev, current));
+                focus_prev_best = Some(current);
+            }
 
             // previous_scoreを次のiterationのためにシード
             // （YaneuraOu行1304-1305: rm.previousScore = rm.score）
@@ -1658,6 +2142,7 @@ where
                 } else {
                     worker.state.root_moves[0].score
                 };
+                ms.confidence.complete_iteration(best_value, worker.state.best_move);
                 let completed_depth = worker.state.completed_depth;
                 let effort = if worker.state.root_moves.is_empty() {
                     0.0
@@ -1782,16 +2267,7 @@ where
     // ponder中 / go infinite中はGUIからstop/ponderhitが来るまでbestmoveを出力してはならない（YaneuraOu準拠）
     // 反復深化ループが自然に終了した場合（MAX_PLY到達や詰み確定）でもここで待機する
     if let Some(ref ms) = main_state {
-        while !worker.state.abort
-            && !time_manager.stop_requested()
-            && (time_manager.is_pondering() || limits.infinite)
-        {
-            if ms.ponderhit_flag.swap(false, Ordering::Relaxed) {
-                time_manager.on_ponderhit();
-            }
-            // YaneuraOu 同様、探索終了後の待機では短時間 sleep して busy wait を避ける。
-            thread::sleep(Duration::from_millis(1));
-        }
+        wait_for_stop_or_ponderhit(worker, limits, time_manager, ms);
     }
 
     // 中断した探索で信頼できないPVになった場合のフォールバック
@@ -1813,6 +2289,96 @@ where
     effective_multi_pv
 }
 
+/// 1 本の PV ラインを aspiration window で探索し、ルート手を並べ直す（反復の本体）
+#[allow(clippy::too_many_arguments)]
+fn search_pv_line(
+    worker: &mut SearchWorker,
+    pos: &mut Position,
+    limits: &LimitsType,
+    time_manager: &mut TimeManagement,
+    pv_idx: usize,
+    search_depth: Depth,
+    search_again_counter: i32,
+    main_state: &mut Option<&mut MainThreadState>,
+) {
+    // Aspiration Window（average/mean_squaredベース）
+    let (mut alpha, mut beta, mut delta) = compute_aspiration_window(
+        &worker.state.root_moves[pv_idx],
+        worker.thread_id,
+        &worker.search_tune_params,
+    );
+    let mut failed_high_cnt = 0;
+
+    // Aspiration Windowループ
+    loop {
+        let adjusted_depth =
+            (search_depth - failed_high_cnt - (3 * (search_again_counter + 1) / 4)).max(1);
+
+        #[cfg(feature = "search-tracing")]
+        let _aspiration_span = tracing::debug_span!(
+            "aspiration",
+            pv_idx,
+            depth = adjusted_depth,
+            alpha = alpha.raw(),
+            beta = beta.raw()
+        )
+        .entered();
+
+        let score = if pv_idx == 0 {
+            worker.search_root(pos, adjusted_depth, alpha, beta, limits, time_manager)
+        } else {
+            worker.search_root_for_pv(pos, search_depth, alpha, beta, pv_idx, limits, time_manager)
+        };
+
+        // aspiration loop 内ソート
+        worker.state.root_moves.stable_sort_range(pv_idx, worker.state.root_moves.len());
+
+        // nodes 制限等で探索が中断された場合の停止判定
+        // abort フラグに加え、nodes 制限超過も直接チェックする
+        // （check_abort は頻度制御で呼び出されるため、abort フラグが
+        //   立っていないまま search_root が返ることがある）
+        if worker.state.abort
+            || (limits.nodes > 0 && worker.state.nodes >= limits.nodes)
+            || time_manager.stop_requested()
+        {
+            worker.state.abort = true;
+            break;
+        }
+
+        if pv_idx == 0
+            && (score <= alpha || score >= beta)
+            && let Some(ms) = main_state.as_mut()
+        {
+            ms.confidence.record_window_failure();
+        }
+
+        // Window調整
+        if score <= alpha {
+            beta = alpha;
+            alpha = Value::new(score.raw().saturating_sub(delta.raw()).max(-Value::INFINITE.raw()));
+            failed_high_cnt = 0;
+            // メインのみ
+            if main_state.is_some() {
+                time_manager.reset_stop_on_ponderhit();
+            }
+        } else if score >= beta {
+            alpha = Value::new((beta.raw() - delta.raw()).max(alpha.raw()));
+            beta = Value::new(score.raw().saturating_add(delta.raw()).min(Value::INFINITE.raw()));
+            failed_high_cnt += 1;
+        } else {
+            break;
+        }
+
+        // delta 更新
+        delta = Value::new(delta.raw().saturating_add(delta.raw() / 3).min(Value::INFINITE.raw()));
+    }
+
+    // 安定ソート [pv_idx..]
+    worker.state.root_moves.stable_sort_range(pv_idx, worker.state.root_moves.len());
+    // 📝 YaneuraOu行1539: 探索済みのPVライン全体も安定ソートして順位を保つ
+    worker.state.root_moves.stable_sort_range(0, pv_idx + 1);
+}
+
 // search_helper_impl is a thin wrapper that calls iterative_deepening with main_state=None.
 // Only compiled for Native and Wasm with wasm-threads (single-threaded Wasm doesn't use helper threads).
 #[cfg(any(not(target_arch = "wasm32"), feature = "wasm-threads"))]
@@ -2240,6 +2806,335 @@ mod tests {
             .unwrap();
     }
 
+    #[test]
+    fn test_qsearch_resolves_hanging_piece() {
+        std::thread::Builder::new()
+            .stack_size(STACK_SIZE)
+            .spawn(|| {
+                // NNUE なしで評価できるよう Material 評価を有効にする
+                crate::eval::set_material_level(crate::eval::MaterialLevel::Lv9);
+                let mut search = Search::new(16);
+                // 先手は 9e の飛車でただの 5e の飛車を取れる
+                let mut pos = Position::new();
+                pos.set_sfen("4k4/9/9/9/R3r4/9/9/9/4K4 b - 1").unwrap();
+                let sfen = pos.to_sfen();
+
+                let static_eval = search.static_eval(&pos);
+                let qsearch = search.qsearch(&mut pos);
+                assert!(
+                    qsearch.raw() > static_eval.raw() + 500,
+                    "qsearch {} should include the rook capture (static eval {})",
+                    qsearch.raw(),
+                    static_eval.raw()
+                );
+                assert_eq!(pos.to_sfen(), sfen, "qsearch must restore the position");
+
+                // 平手初期局面は駒の取り合いがないので静的評価から大きく離れない
+                let mut hirate = Position::new();
+                hirate.set_hirate();
+                let static_eval = search.static_eval(&hirate);
+                let qsearch = search.qsearch(&mut hirate);
+                assert!((qsearch.raw() - static_eval.raw()).abs() < 200);
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+    }
+
+    #[test]
+    fn test_terminal_position_value_is_consistent() {
+        std::thread::Builder::new()
+            .stack_size(STACK_SIZE)
+            .spawn(|| {
+                crate::eval::set_material_level(crate::eval::MaterialLevel::Lv9);
+                let mut search = Search::new(16);
+                // 頭金で詰み（後手番）
+                let mut pos = Position::new();
+                pos.set_sfen("4k4/4G4/4P4/9/9/9/9/9/4K4 w - 1").unwrap();
+                let expected = Value::mated_in(0);
+
+                let limits = LimitsType {
+                    depth: 3,
+                    ..Default::default()
+                };
+                let result = search.go(&mut pos, limits, None::<fn(&SearchInfo)>);
+                assert_eq!(result.best_move, Move::NONE);
+                assert_eq!(result.score, expected);
+                assert_eq!(search.qsearch(&mut pos), expected);
+                assert_eq!(search.eval_breakdown(&pos).terminal, Some(expected));
+
+                let mut hirate = Position::new();
+                hirate.set_hirate();
+                assert_eq!(search.eval_breakdown(&hirate).terminal, None);
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+    }
+
+    #[test]
+    fn test_eval_breakdown() {
+        use crate::types::{Color, Piece, PieceType};
+
+        std::thread::Builder::new()
+            .stack_size(STACK_SIZE)
+            .spawn(|| {
+                crate::eval::set_material_level(crate::eval::MaterialLevel::Lv9);
+                let mut search = Search::new(16);
+
+                // 平手初期局面: 玉以外の盤上の駒 38 枚、持ち駒なし、駒得なし
+                let mut hirate = Position::new();
+                hirate.set_hirate();
+                let breakdown = search.eval_breakdown(&hirate);
+                assert_eq!(breakdown.eval, search.static_eval(&hirate));
+                assert!(!breakdown.nnue);
+                assert_eq!(breakdown.material, Value::ZERO);
+                assert!(breakdown.tempo.is_some());
+                assert_eq!(breakdown.pieces.len(), 38);
+                assert!(breakdown.pieces.iter().all(|p| p.square.is_some()));
+                assert!(
+                    breakdown
+                        .pieces
+                        .windows(2)
+                        .all(|w| w[0].value.raw().abs() >= w[1].value.raw().abs())
+                );
+
+                // 後手番で先手が飛車を持っている局面: 持ち駒の寄与が入り、駒得は手番側から見て負
+                let mut pos = Position::new();
+                pos.set_sfen("4k4/9/9/9/9/9/9/9/4K4 w R 1").unwrap();
+                let breakdown = search.eval_breakdown(&pos);
+                assert!(breakdown.material < Value::ZERO);
+                let rook = breakdown
+                    .pieces
+                    .iter()
+                    .find(|p| p.square.is_none())
+                    .expect("hand rook contribution");
+                assert_eq!(rook.piece, Piece::new(Color::Black, PieceType::Rook));
+                assert!(rook.value < Value::ZERO, "black's rook is bad for white to move");
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+    }
+
+    #[test]
+    fn test_search_snapshot_replays_iteration() {
+        std::thread::Builder::new()
+            .stack_size(STACK_SIZE)
+            .spawn(|| {
+                crate::eval::set_material_level(crate::eval::MaterialLevel::Lv9);
+                let dir = std::env::temp_dir()
+                    .join(format!("rshogi-snapshot-test-{}", std::process::id()));
+                std::fs::create_dir_all(&dir).unwrap();
+
+                // score_swing を負にして最初の対象反復で必ず書き出す
+                let mut search = Search::new(16);
+                search.set_snapshot_options(Some(SnapshotOptions {
+                    dir: dir.clone(),
+                    score_swing: -1,
+                }));
+                let mut pos = Position::new();
+                pos.set_hirate();
+                let limits = LimitsType {
+                    depth: 8,
+                    ..Default::default()
+                };
+                search.go(&mut pos, limits, None::<fn(&SearchInfo)>);
+
+                let files: Vec<_> =
+                    std::fs::read_dir(&dir).unwrap().map(|e| e.unwrap().path()).collect();
+                assert_eq!(files.len(), 1, "one snapshot per go: {files:?}");
+                let snapshot = SearchSnapshot::load(&files[0]).unwrap();
+                std::fs::remove_dir_all(&dir).unwrap();
+                assert_eq!(snapshot.depth, crate::search::SNAPSHOT_MIN_DEPTH);
+                let original = snapshot.original.clone().expect("original outcome");
+
+                // 別の Search（置換表サイズも異なる）から再実行しても元の反復を再現する
+                let mut replay = Search::new(4);
+                for _ in 0..2 {
+                    let outcome = replay.replay_snapshot(&snapshot).unwrap();
+                    assert_eq!(outcome, original);
+                }
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+    }
+
+    #[test]
+    fn test_saved_tt_resumes_in_another_search() {
+        std::thread::Builder::new()
+            .stack_size(STACK_SIZE)
+            .spawn(|| {
+                crate::eval::set_material_level(crate::eval::MaterialLevel::Lv9);
+                let path = std::env::temp_dir()
+                    .join(format!("rshogi-tt-save-test-{}.bin", std::process::id()));
+                let mut search = Search::new(4);
+                let mut pos = Position::new();
+                pos.set_hirate();
+                let limits = LimitsType {
+                    depth: 8,
+                    ..Default::default()
+                };
+                search.go(&mut pos, limits, None::<fn(&SearchInfo)>);
+                let saved = search.save_tt(&path).unwrap();
+                assert!(saved > 0);
+
+                let mut resumed = Search::new(4);
+                let loaded = resumed.load_tt(&path);
+                let mismatched = Search::new(8).load_tt(&path);
+                std::fs::remove_file(&path).unwrap();
+                assert_eq!(loaded.unwrap(), saved);
+                assert_eq!(resumed.tt.raw_bytes(), search.tt.raw_bytes());
+                assert_eq!(resumed.tt.generation(), search.tt.generation());
+                assert!(matches!(mismatched, Err(TtFileError::SizeMismatch { .. })));
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+    }
+
+    #[test]
+    fn test_contempt_shifts_draw_values_of_workers() {
+        std::thread::Builder::new()
+            .stack_size(STACK_SIZE)
+            .spawn(|| {
+                let mut search = Search::new(16);
+                search.set_draw_value_black(0);
+                search.set_draw_value_white(-10);
+                search.set_contempt(50);
+                assert_eq!(search.contempt(), 50);
+                assert_eq!(search.effective_draw_values(), (-50, -60));
+                let worker = search.main_worker();
+                assert_eq!((worker.draw_value_black, worker.draw_value_white), (-50, -60));
+
+                // 設定値そのものは DrawValue のまま
+                search.set_contempt(-40000);
+                assert_eq!(search.contempt(), -30000);
+                assert_eq!(search.draw_value_black(), 0);
+                assert_eq!(search.effective_draw_values(), (30000, 29990));
+                let worker = search.worker.as_ref().unwrap();
+                assert_eq!((worker.draw_value_black, worker.draw_value_white), (30000, 29990));
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+    }
+
+    #[test]
+    fn test_infinite_search_on_mated_position_waits_for_stop() {
+        std::thread::Builder::new()
+            .stack_size(STACK_SIZE)
+            .spawn(|| {
+                crate::eval::set_material_level(crate::eval::MaterialLevel::Lv9);
+                let mut search = Search::new(16);
+                let stop = search.stop_flag();
+                let finished = Arc::new(AtomicBool::new(false));
+                let handle = {
+                    let finished = Arc::clone(&finished);
+                    std::thread::Builder::new()
+                        .stack_size(STACK_SIZE)
+                        .spawn(move || {
+                            // 後手玉は 5b の金で詰んでいて合法手がない
+                            let mut pos = Position::new();
+                            pos.set_sfen("4k4/4G4/4P4/9/9/9/9/9/4K4 w - 1").unwrap();
+                            let limits = LimitsType {
+                                infinite: true,
+                                ..Default::default()
+                            };
+                            let result = search.go(&mut pos, limits, None::<fn(&SearchInfo)>);
+                            finished.store(true, Ordering::SeqCst);
+                            result
+                        })
+                        .unwrap()
+                };
+                std::thread::sleep(Duration::from_millis(200));
+                assert!(!finished.load(Ordering::SeqCst), "go infinite returned before stop");
+                stop.store(true, Ordering::SeqCst);
+                let result = handle.join().unwrap();
+                assert_eq!(result.best_move, Move::NONE);
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+    }
+
+    #[test]
+    fn test_search_result_lines_follow_multipv_ranking() {
+        std::thread::Builder::new()
+            .stack_size(STACK_SIZE)
+            .spawn(|| {
+                crate::eval::set_material_level(crate::eval::MaterialLevel::Lv9);
+                let mut search = Search::new(16);
+                let mut pos = Position::new();
+                pos.set_hirate();
+                let limits = LimitsType {
+                    depth: 6,
+                    multi_pv: 3,
+                    ..Default::default()
+                };
+                let mut infos: Vec<SearchInfo> = Vec::new();
+                let result =
+                    search.go(&mut pos, limits, Some(|info: &SearchInfo| infos.push(info.clone())));
+
+                assert_eq!(result.lines.len(), 3);
+                for (idx, line) in result.lines.iter().enumerate() {
+                    assert_eq!(line.multi_pv, idx + 1);
+                    assert_eq!(line.depth, result.depth);
+                }
+                assert!(result.lines.windows(2).all(|w| w[0].score >= w[1].score));
+                let mut first_moves: Vec<Move> = result.lines.iter().map(|l| l.pv[0]).collect();
+                first_moves.dedup();
+                assert_eq!(first_moves.len(), 3, "each line starts with a distinct root move");
+                assert_eq!(result.lines[0].pv[0], result.best_move);
+                assert_eq!(result.lines[0].pv, result.pv);
+
+                // 各反復で multipv 1..=3 を順に出力し、最後の反復は lines と一致する
+                for depth in 1..=result.depth {
+                    let ranks: Vec<usize> = infos
+                        .iter()
+                        .filter(|info| info.depth == depth)
+                        .map(|info| info.multi_pv)
+                        .collect();
+                    assert_eq!(ranks, vec![1, 2, 3], "depth {depth}");
+                }
+                let last: Vec<&SearchInfo> =
+                    infos.iter().filter(|info| info.depth == result.depth).collect();
+                for (info, line) in last.iter().zip(&result.lines) {
+                    assert_eq!(info.pv, line.pv);
+                    assert_eq!(info.score, line.score);
+                }
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+    }
+
+    #[test]
+    fn test_search_result_reports_confidence() {
+        std::thread::Builder::new()
+            .stack_size(STACK_SIZE)
+            .spawn(|| {
+                let mut search = Search::new(16);
+                let mut pos = Position::new();
+                pos.set_hirate();
+
+                let limits = LimitsType {
+                    depth: 6,
+                    ..Default::default()
+                };
+
+                let result = search.go(&mut pos, limits, None::<fn(&SearchInfo)>);
+
+                let confidence = result.confidence;
+                assert_eq!(confidence.iterations, crate::search::CONFIDENCE_WINDOW as u32);
+                assert!((0.0..=1.0).contains(&confidence.value), "{confidence:?}");
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+    }
+
     #[test]
     fn test_search_with_callback() {
         // スタックサイズを増やした別スレッドで実行
@@ -2272,6 +3167,100 @@ mod tests {
             .unwrap();
     }
 
+    #[test]
+    fn test_cpu_limit_reports_usage_near_limit() {
+        std::thread::Builder::new()
+            .stack_size(STACK_SIZE)
+            .spawn(|| {
+                crate::eval::set_material_level(crate::eval::MaterialLevel::Lv9);
+                let mut search = Search::new(16);
+                let mut pos = Position::new();
+                pos.set_hirate();
+
+                let mut usages = Vec::new();
+                search.go(
+                    &mut pos,
+                    LimitsType {
+                        depth: 6,
+                        ..Default::default()
+                    },
+                    Some(|info: &SearchInfo| usages.push(info.cpu_usage)),
+                );
+                assert!(usages.iter().all(Option::is_none), "{usages:?}");
+
+                let mut usages = Vec::new();
+                let result = search.go(
+                    &mut pos,
+                    LimitsType {
+                        movetime: 300,
+                        cpu_limit_percent: 25,
+                        ..Default::default()
+                    },
+                    Some(|info: &SearchInfo| usages.push(info.cpu_usage)),
+                );
+                assert_ne!(result.best_move, Move::NONE);
+                let usage = usages.iter().rev().find_map(|u| *u).expect("usage reported");
+                assert!(usage <= 50, "usage={usage} {usages:?}");
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+    }
+
+    #[test]
+    fn test_currmove_report_from_root_move_loop() {
+        std::thread::Builder::new()
+            .stack_size(STACK_SIZE)
+            .spawn(|| {
+                crate::eval::set_material_level(crate::eval::MaterialLevel::Lv9);
+                let mut search = Search::new(16);
+                let mut pos = Position::new();
+                pos.set_hirate();
+                let limits = LimitsType {
+                    depth: 4,
+                    ..Default::default()
+                };
+
+                let reports = Arc::new(std::sync::Mutex::new(Vec::new()));
+                let report = |interval_ms| {
+                    let reports = Arc::clone(&reports);
+                    Some(CurrMoveReport {
+                        interval_ms,
+                        sink: Arc::new(move |info: &super::super::CurrMoveInfo| {
+                            reports.lock().unwrap().push(info.clone());
+                        }),
+                    })
+                };
+
+                // 間隔 0 ならルートの手ごとに通知する
+                search.set_currmove_report(report(0));
+                search.go(&mut pos, limits.clone(), None::<fn(&SearchInfo)>);
+                let got = std::mem::take(&mut *reports.lock().unwrap());
+                let mut legal = crate::movegen::MoveList::new();
+                crate::movegen::generate_legal(&pos, &mut legal);
+                assert!(got.iter().any(|info| info.depth == 4 && info.currmovenumber == 1));
+                for info in &got {
+                    assert!((1..=legal.len()).contains(&info.currmovenumber), "{info:?}");
+                    assert!(legal.contains(info.currmove), "{info:?}");
+                }
+                assert!(
+                    got[0]
+                        .to_usi_string()
+                        .starts_with(&format!("info depth {} currmove ", got[0].depth))
+                );
+
+                // 間隔に達しない短い探索・未設定では通知しない
+                search.set_currmove_report(report(60_000));
+                search.go(&mut pos, limits.clone(), None::<fn(&SearchInfo)>);
+                search.set_currmove_report(None);
+                search.go(&mut pos, limits, None::<fn(&SearchInfo)>);
+                assert!(reports.lock().unwrap().is_empty());
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+    }
+
     #[test]
     fn test_search_info_to_usi() {
         let info = SearchInfo {
@@ -2284,6 +3273,7 @@ mod tests {
             hashfull: 100,
             pv: vec![],
             multi_pv: 1,
+            cpu_usage: None,
         };
 
         let usi = info.to_usi_string();
@@ -2307,6 +3297,7 @@ mod tests {
             hashfull: 0,
             pv: vec![],
             multi_pv: 1,
+            cpu_usage: None,
         };
 
         let usi = info.to_usi_string();
@@ -2325,6 +3316,7 @@ mod tests {
             hashfull: 0,
             pv: vec![],
             multi_pv: 1,
+            cpu_usage: None,
         };
 
         let usi = info.to_usi_string();
@@ -2367,4 +3359,40 @@ mod tests {
         search.reset_flags();
         assert!(!search.ponderhit_flag_for_test());
     }
+
+    #[test]
+    fn prepare_root_reports_tt_hit_without_creating_worker() {
+        use crate::types::Bound;
+
+        let search = Search::new_with_eval_hash(1, 1);
+        let mut pos = Position::new();
+        pos.set_hirate();
+        assert!(!search.prepare_root(&pos).tt_hit);
+
+        let key = pos.key();
+        search.tt.probe(key, &pos).write(
+            key,
+            Value::new(30),
+            true,
+            Bound::Exact,
+            8,
+            Move::NONE,
+            Value::ZERO,
+            search.tt.generation(),
+        );
+        assert!(search.prepare_root(&pos).tt_hit);
+        // 予測が外れても次の探索に影響しないよう、worker は作らない
+        assert!(search.worker.is_none());
+    }
+
+    #[test]
+    fn memory_stats_reflects_resize_tt() {
+        let mut search = Search::new_with_eval_hash(2, 1);
+        let stats = search.memory_stats();
+        assert_eq!((stats.tt_mb, stats.eval_hash_mb), (2, 1));
+        assert_eq!(stats.linear_memory_bytes, None);
+
+        search.resize_tt(1);
+        assert_eq!(search.memory_stats().tt_mb, 1);
+    }
 }
@@ -0,0 +1,122 @@
+//! 静的評価値の内訳（局面の評価の説明用）
+//!
+//! [`Search::eval_breakdown`](super::Search::eval_breakdown) が返す。NNUE は項目ごとの値を
+//! 持たないため、駒ごとの寄与と手番の価値は「その要素を取り除いた局面をもう一度評価した差」
+//! （ablation）で求める。駒を取り除いた局面は実戦では現れない形にもなるので、値は
+//! 「評価関数がその駒をどれだけ重く見ているか」の目安として扱うこと。
+
+use crate::eval::is_material_enabled;
+use crate::eval::material::compute_material_value;
+use crate::position::Position;
+use crate::types::{Color, Hand, Piece, PieceType, Square, Value};
+
+/// 1 つの駒の評価値への寄与
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PieceContribution {
+    /// 盤上の駒ならそのマス、持ち駒なら `None`
+    pub square: Option<Square>,
+    /// 駒（持ち駒は生駒）
+    pub piece: Piece,
+    /// その駒を 1 枚取り除くと評価値がどれだけ下がるか（手番側から見た値）
+    pub value: Value,
+}
+
+/// 静的評価値の内訳（値はすべて手番側から見た値）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EvalBreakdown {
+    /// 評価関数の出力（[`Search::static_eval`](super::Search::static_eval) と同じ）
+    pub eval: Value,
+    /// NNUE で評価したか（`false` なら Material 評価）
+    pub nnue: bool,
+    /// 駒得（盤上の駒と持ち駒の価値の合計の差）
+    pub material: Value,
+    /// 手番の価値（同じ盤面で手番だけを入れ替えた局面の評価値との差の半分）
+    ///
+    /// 手番側の玉に王手が掛かっていると入れ替えた局面が作れないため `None`。
+    pub tempo: Option<Value>,
+    /// 玉以外の盤上の駒と、持ち駒の種類ごとの寄与（寄与の絶対値の大きい順）
+    pub pieces: Vec<PieceContribution>,
+    /// 終局局面なら [`Position::terminal_value`]（`eval` は評価関数の出力のまま）
+    pub terminal: Option<Value>,
+}
+
+impl EvalBreakdown {
+    /// `evaluate`（手番側から見た静的評価値）を何度も呼んで内訳を求める
+    pub(super) fn compute(pos: &Position, mut evaluate: impl FnMut(&Position) -> Value) -> Self {
+        let us = pos.side_to_move();
+        let eval = evaluate(pos);
+        let mut board = [Piece::NONE; Square::NUM];
+        for sq in pos.occupied().iter() {
+            board[sq.index()] = pos.piece_on(sq);
+        }
+        let hands = [pos.hand(Color::Black), pos.hand(Color::White)];
+
+        let material = compute_material_value(pos);
+        let material = if us == Color::Black {
+            material
+        } else {
+            -material
+        };
+
+        let tempo = (!pos.in_check())
+            .then(|| evaluate_parts(&board, &hands, !us, &mut evaluate))
+            .flatten()
+            .map(|flipped| (eval + flipped) / 2);
+
+        let mut pieces = Vec::new();
+        for sq in pos.occupied().iter() {
+            let piece = board[sq.index()];
+            if piece.piece_type() == PieceType::King {
+                continue;
+            }
+            board[sq.index()] = Piece::NONE;
+            if let Some(without) = evaluate_parts(&board, &hands, us, &mut evaluate) {
+                pieces.push(PieceContribution {
+                    square: Some(sq),
+                    piece,
+                    value: eval - without,
+                });
+            }
+            board[sq.index()] = piece;
+        }
+        for color in [Color::Black, Color::White] {
+            for pt in PieceType::HAND_PIECES {
+                let hand = hands[color.index()];
+                if !hand.has(pt) {
+                    continue;
+                }
+                let mut reduced = hands;
+                reduced[color.index()] = hand.sub(pt);
+                if let Some(without) = evaluate_parts(&board, &reduced, us, &mut evaluate) {
+                    pieces.push(PieceContribution {
+                        square: None,
+                        piece: Piece::new(color, pt),
+                        value: eval - without,
+                    });
+                }
+            }
+        }
+        pieces.sort_by_key(|p| std::cmp::Reverse(p.value.raw().abs()));
+
+        Self {
+            eval,
+            nnue: !is_material_enabled(),
+            material,
+            tempo,
+            pieces,
+            terminal: None,
+        }
+    }
+}
+
+/// 盤面・持ち駒・手番から局面を作って評価する（局面が作れなければ `None`）
+fn evaluate_parts(
+    board: &[Piece; Square::NUM],
+    hands: &[Hand; Color::NUM],
+    side_to_move: Color,
+    evaluate: &mut impl FnMut(&Position) -> Value,
+) -> Option<Value> {
+    let mut pos = Position::new();
+    pos.set_from_parts(board, hands, side_to_move).ok()?;
+    Some(evaluate(&pos))
+}
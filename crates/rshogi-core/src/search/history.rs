@@ -914,6 +914,34 @@ impl HistoryTables {
         self.tt_move_history.clear();
     }
 
+    /// テーブル全体のバイト列（探索スナップショット用）
+    pub(crate) fn as_bytes(&self) -> &[u8] {
+        // SAFETY: 各テーブルは i16 の配列のみで構成され、パディングを持たない
+        //         （下の const assert で確認）。
+        unsafe {
+            std::slice::from_raw_parts(
+                self as *const Self as *const u8,
+                std::mem::size_of::<Self>(),
+            )
+        }
+    }
+
+    /// [`as_bytes`](Self::as_bytes) で取り出した内容を書き戻す（長さが違えば `false`）
+    pub(crate) fn copy_from_bytes(&mut self, bytes: &[u8]) -> bool {
+        if bytes.len() != std::mem::size_of::<Self>() {
+            return false;
+        }
+        // SAFETY: 長さは一致しており、i16 の配列は任意のバイト列が有効な値になる。
+        unsafe {
+            std::ptr::copy_nonoverlapping(
+                bytes.as_ptr(),
+                self as *mut Self as *mut u8,
+                bytes.len(),
+            );
+        }
+        true
+    }
+
     /// SPSA パラメータに基づいた初期値ですべての履歴テーブルをクリア
     pub fn clear_with_params(&mut self, tp: &SearchTuneParams) {
         self.main_history.clear_with_init(tp.main_history_init as i16);
@@ -930,6 +958,18 @@ impl HistoryTables {
     }
 }
 
+// バイト列として複製できるよう、HistoryTables がパディングを持たないことを保証する
+const _: () = assert!(
+    std::mem::size_of::<HistoryTables>()
+        == std::mem::size_of::<ButterflyHistory>()
+            + std::mem::size_of::<LowPlyHistory>()
+            + std::mem::size_of::<CapturePieceToHistory>()
+            + std::mem::size_of::<[[ContinuationHistory; 2]; 2]>()
+            + std::mem::size_of::<PawnHistory>()
+            + std::mem::size_of::<CorrectionHistory>()
+            + std::mem::size_of::<TTMoveHistory>()
+);
+
 // =============================================================================
 // HistoryCell（内部可変性ラッパー）
 // =============================================================================
@@ -2,8 +2,10 @@
 //!
 //! USI `go` コマンドのパラメータを表現する。
 
-use crate::time::Instant;
-use crate::types::Color;
+use std::sync::Arc;
+
+use crate::time::{Clock, Instant, SharedClock, system_clock};
+use crate::types::{Color, Move, Value};
 
 // =============================================================================
 // TimePoint
@@ -12,6 +14,89 @@ use crate::types::Color;
 /// 時間（ミリ秒）
 pub type TimePoint = i64;
 
+// =============================================================================
+// MultiPvFocus
+// =============================================================================
+
+/// MultiPV のフォーカスモード（解析用）
+///
+/// 浅い深さでは `multi_pv` 本すべてを探索し、`depth` 以上では上位 `lines` 本に絞る。
+/// 直前の反復から最善手が変わったか、最善手の評価値が `rewiden_margin` を超えて
+/// 動いた場合は、次の反復だけ再び全本数に広げる。
+/// 絞っている間、`lines` 本より下の候補手の評価値は最後に広げた反復のまま更新されない。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MultiPvFocus {
+    /// 絞り込みを始める深さ（0 なら無効）
+    pub depth: i32,
+    /// 絞り込み後の本数（1 以上）
+    pub lines: usize,
+    /// 再拡張する評価値の変動幅
+    pub rewiden_margin: i32,
+}
+
+impl Default for MultiPvFocus {
+    fn default() -> Self {
+        Self {
+            depth: 0,
+            lines: 1,
+            rewiden_margin: 50,
+        }
+    }
+}
+
+impl MultiPvFocus {
+    /// フォーカスモードが有効か
+    #[inline]
+    pub fn is_enabled(&self) -> bool {
+        self.depth > 0
+    }
+
+    /// `depth` の反復で探索する PV の本数
+    ///
+    /// `full` は通常の MultiPV 本数、`rewiden` は直前の反復で最善手が不安定だったか。
+    pub fn lines_for_depth(&self, full: usize, depth: i32, rewiden: bool) -> usize {
+        if !self.is_enabled() || depth < self.depth || rewiden {
+            full
+        } else {
+            self.lines.clamp(1, full.max(1))
+        }
+    }
+
+    /// 前後の反復の最善手・評価値から、次の反復で再拡張すべきか判定する
+    pub fn should_rewiden(&self, prev: (Move, Value), current: (Move, Value)) -> bool {
+        prev.0 != current.0 || (current.1.raw() - prev.1.raw()).abs() > self.rewiden_margin
+    }
+}
+
+// =============================================================================
+// TimeControl
+// =============================================================================
+
+/// `go` の時間指定の解釈
+///
+/// 複数の指定が混在するときの優先順位は
+/// `movetime` > 時間管理なし（depth / nodes / mate / perft / infinite）> `rtime` >
+/// 持ち時間（btime / wtime と byoyomi・inc の組み合わせ）。
+/// 持ち時間の種類は手番側の byoyomi と inc の有無で決まる。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeControl {
+    /// 固定思考時間（`movetime`）
+    MoveTime,
+    /// 時間管理をしない（depth / nodes / mate / perft / infinite）
+    Unmanaged,
+    /// ランダム化した固定思考時間（`rtime`）。持ち時間の指定があれば今回使える時間で頭打ち
+    RandomTime,
+    /// 切れ負け（byoyomi も inc もない）
+    SuddenDeath,
+    /// 秒読み（byoyomi のみ）
+    Byoyomi,
+    /// フィッシャー（inc のみ）
+    Fischer,
+    /// フィッシャー + 秒読み（持ち時間がある間は inc 込みで配分し、
+    /// 持ち時間が秒読みの 1.2 倍を切ったら inc を当てにせず持ち時間 + 秒読みを使い切る）
+    FischerByoyomi,
+}
+
 // =============================================================================
 // LimitsType
 // =============================================================================
@@ -60,12 +145,27 @@ pub struct LimitsType {
     /// YaneuraOu準拠: 複数の候補手を探索して表示する
     pub multi_pv: usize,
 
-    /// 探索対象の手のリスト
+    /// 探索対象の手のリスト（USI `go searchmoves`）
     /// 空なら全合法手を探索
+    ///
+    /// ルート手をこの中の合法手に限るので、MultiPV の本数や置換表の指し手もこの範囲に
+    /// 収まる。駒情報のない手（`Move::from_usi`）も指定できる。入玉宣言勝ちができる局面
+    /// では YaneuraOu と同様に宣言勝ちを優先する。
     pub search_moves: Vec<crate::types::Move>,
 
+    /// MultiPV のフォーカスモード（既定は無効）
+    pub multi_pv_focus: MultiPvFocus,
+
+    /// 探索スレッドごとの CPU 使用率の上限（%、0 または 100 以上なら無制限）
+    ///
+    /// 中断チェックのたびに短いスリープを挟んで使用率を抑える（[`CpuThrottle`](super::CpuThrottle)）。
+    pub cpu_limit_percent: u8,
+
     /// 探索開始時刻
     pub(crate) start_time: Option<Instant>,
+
+    /// 時間管理が参照する時計（既定は実時間）
+    pub(crate) clock: SharedClock,
 }
 
 impl Default for LimitsType {
@@ -84,7 +184,10 @@ impl Default for LimitsType {
             ponder: false,
             multi_pv: 1, // デフォルトは1（通常探索）
             search_moves: Vec::new(),
+            multi_pv_focus: MultiPvFocus::default(),
+            cpu_limit_percent: 0,
             start_time: None,
+            clock: system_clock(),
         }
     }
 }
@@ -116,12 +219,27 @@ impl LimitsType {
 
     /// 探索開始時刻を設定
     pub fn set_start_time(&mut self) {
-        self.start_time = Some(Instant::now());
+        self.start_time = Some(self.clock.now());
     }
 
     /// 探索開始からの経過時間（ミリ秒）
     pub fn elapsed(&self) -> TimePoint {
-        self.start_time.map(|t| t.elapsed().as_millis() as TimePoint).unwrap_or(0)
+        self.start_time
+            .map(|t| self.clock.elapsed_since(t).as_millis() as TimePoint)
+            .unwrap_or(0)
+    }
+
+    /// 時間管理に使う時計を差し替える（テスト・シミュレーション用）
+    ///
+    /// 開始時刻は新しい時計で取り直すため、`set_start_time` より前に呼ぶこと。
+    pub fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock;
+        self.start_time = None;
+    }
+
+    /// 時間管理に使う時計
+    pub fn clock(&self) -> &SharedClock {
+        &self.clock
     }
 
     /// 指定した色の残り時間を取得
@@ -142,6 +260,24 @@ impl LimitsType {
         self.inc[color.index()]
     }
 
+    /// 手番側から見た時間指定の解釈（優先順位は [`TimeControl`] 参照）
+    pub fn time_control(&self, us: Color) -> TimeControl {
+        if self.has_movetime() {
+            TimeControl::MoveTime
+        } else if !self.use_time_management() {
+            TimeControl::Unmanaged
+        } else if self.rtime > 0 {
+            TimeControl::RandomTime
+        } else {
+            match (self.byoyomi_time(us) > 0, self.increment(us) > 0) {
+                (false, false) => TimeControl::SuddenDeath,
+                (true, false) => TimeControl::Byoyomi,
+                (false, true) => TimeControl::Fischer,
+                (true, true) => TimeControl::FischerByoyomi,
+            }
+        }
+    }
+
     /// 深さ制限があるか
     #[inline]
     pub fn has_depth_limit(&self) -> bool {
@@ -154,6 +290,12 @@ impl LimitsType {
         self.nodes > 0
     }
 
+    /// CPU 使用率の上限があるか
+    #[inline]
+    pub fn has_cpu_limit(&self) -> bool {
+        (1..100).contains(&self.cpu_limit_percent)
+    }
+
     /// 思考時間が固定されているか
     #[inline]
     pub fn has_movetime(&self) -> bool {
@@ -333,4 +475,58 @@ mod tests {
         limits.movetime = 1000;
         assert!(limits.has_movetime());
     }
+
+    #[test]
+    fn test_time_control_precedence() {
+        let black = Color::Black;
+        let mut limits = LimitsType::new();
+        limits.time = [60_000, 60_000];
+        assert_eq!(limits.time_control(black), TimeControl::SuddenDeath);
+
+        limits.byoyomi = [10_000, 10_000];
+        assert_eq!(limits.time_control(black), TimeControl::Byoyomi);
+
+        limits.inc = [5_000, 0];
+        assert_eq!(limits.time_control(black), TimeControl::FischerByoyomi);
+        // 手番側の指定で決まる
+        assert_eq!(limits.time_control(Color::White), TimeControl::Byoyomi);
+
+        limits.byoyomi = [0, 0];
+        assert_eq!(limits.time_control(black), TimeControl::Fischer);
+
+        // rtime は持ち時間より優先する
+        limits.rtime = 1_000;
+        assert_eq!(limits.time_control(black), TimeControl::RandomTime);
+
+        // depth 等の指定があれば rtime も持ち時間も使わない
+        limits.depth = 10;
+        assert_eq!(limits.time_control(black), TimeControl::Unmanaged);
+
+        // movetime は depth 併用でも最優先
+        limits.movetime = 500;
+        assert_eq!(limits.time_control(black), TimeControl::MoveTime);
+    }
+
+    #[test]
+    fn test_multi_pv_focus() {
+        let disabled = MultiPvFocus::default();
+        assert_eq!(disabled.lines_for_depth(8, 30, false), 8);
+
+        let focus = MultiPvFocus {
+            depth: 12,
+            lines: 2,
+            rewiden_margin: 50,
+        };
+        assert_eq!(focus.lines_for_depth(8, 11, false), 8);
+        assert_eq!(focus.lines_for_depth(8, 12, false), 2);
+        assert_eq!(focus.lines_for_depth(8, 20, true), 8);
+        // 合法手が絞り込み本数より少ない場合
+        assert_eq!(focus.lines_for_depth(1, 20, false), 1);
+
+        let mv = Move::from_usi("7g7f").unwrap();
+        let other = Move::from_usi("2g2f").unwrap();
+        assert!(!focus.should_rewiden((mv, Value::new(100)), (mv, Value::new(150))));
+        assert!(focus.should_rewiden((mv, Value::new(100)), (mv, Value::new(151))));
+        assert!(focus.should_rewiden((mv, Value::new(100)), (other, Value::new(100))));
+    }
 }
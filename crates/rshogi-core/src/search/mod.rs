@@ -11,16 +11,23 @@
 mod stats;
 
 mod alpha_beta;
+mod confidence;
+mod currmove;
 mod engine;
+mod eval_breakdown;
 mod eval_helpers;
 mod history;
 mod limits;
 mod movepicker;
+#[cfg(feature = "policy-ordering")]
+mod policy;
 mod pruning;
 mod qsearch;
 mod search_helpers;
 mod skill;
+mod snapshot;
 mod thread;
+mod throttle;
 mod time_manager;
 mod time_options;
 mod tt_history;
@@ -32,14 +39,23 @@ mod types;
 mod tests;
 
 pub use alpha_beta::*;
+pub use confidence::{CONFIDENCE_WINDOW, SearchConfidence};
+pub use currmove::{CurrMoveInfo, CurrMoveReport, CurrMoveSink};
 pub use engine::*;
+pub use eval_breakdown::{EvalBreakdown, PieceContribution};
 pub use history::*;
 pub use limits::*;
 pub use movepicker::*;
+#[cfg(feature = "policy-ordering")]
+pub use policy::*;
 pub use skill::*;
+pub use snapshot::{
+    IterationOutcome, SNAPSHOT_MIN_DEPTH, SearchSnapshot, SnapshotError, SnapshotOptions,
+};
 #[cfg(feature = "search-stats")]
 pub use stats::SearchStats;
 pub use thread::*;
+pub use throttle::CpuThrottle;
 pub use time_manager::*;
 pub use time_options::*;
 pub use tt_history::*;
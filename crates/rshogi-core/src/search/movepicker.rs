@@ -629,6 +629,13 @@ impl MovePicker {
             debug_assert!(self.ply >= 0, "ply must be non-negative: {}", self.ply);
             let low_ply_idx = self.ply as usize;
             let low_ply_div = 1 + self.ply;
+            // 補助 policy は root と直下のノードのみ（POLICY_ORDERING_MAX_PLY < LOW_PLY_HISTORY_SIZE）
+            #[cfg(feature = "policy-ordering")]
+            let policy_net = if self.ply < super::POLICY_ORDERING_MAX_PLY {
+                super::get_policy_net()
+            } else {
+                None
+            };
 
             for ext in moves {
                 let m = ext.mv;
@@ -652,6 +659,12 @@ impl MovePicker {
                 // コンパイラが除算ゼロチェックを除去できないため .max(1) で明示。
                 value +=
                     8 * history.low_ply_history.get(low_ply_idx, m) as i32 / low_ply_div.max(1);
+
+                #[cfg(feature = "policy-ordering")]
+                if let Some(net) = policy_net.as_deref() {
+                    value += net.move_bonus(us, m);
+                }
+
                 ext.value = value;
             }
         } else {
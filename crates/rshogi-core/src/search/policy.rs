@@ -0,0 +1,242 @@
+//! 補助 policy による指し手オーダリング（実験的機能、`policy-ordering` feature）
+//!
+//! 評価関数とは別にロードする小さな線形 policy ヘッドで、root と浅いノードの
+//! 静かな手のスコアにバイアスを加える。入力特徴は「手番視点の移動先マス × 移動後の駒種
+//! × 駒打ちか」のみで、重みは history と同じスケールの `i16` として保持する。
+//!
+//! 学習ツール向けには、探索後の root 統計（root move ごとの探索ノード数）から
+//! policy の教師分布を作る [`policy_targets`] を提供する。
+//!
+//! ## ファイル形式
+//!
+//! | offset | size | 内容 |
+//! |--------|------|------|
+//! | 0      | 8    | magic `b"RSPOLICY"` |
+//! | 8      | 4    | version (u32 LE, 現在は 1) |
+//! | 12     | 4    | 重み数 (u32 LE, [`POLICY_NUM_WEIGHTS`] と一致すること) |
+//! | 16     | 2×N  | 重み (i16 LE) |
+
+use std::io;
+use std::path::Path;
+use std::sync::{Arc, LazyLock, RwLock};
+
+use crate::types::{Color, Move, PieceType, Square};
+
+use super::RootMoves;
+
+/// ファイル先頭の magic
+const POLICY_MAGIC: &[u8; 8] = b"RSPOLICY";
+/// 対応するファイル形式のバージョン
+const POLICY_VERSION: u32 = 1;
+
+/// 重み数（駒打ちか 2 通り × 駒種 15 通り（0 は未使用）× 81 マス）
+pub const POLICY_NUM_WEIGHTS: usize = 2 * (PieceType::NUM + 1) * Square::NUM;
+
+/// policy バイアスを加える最大 ply（root と直下の子ノードのみ）
+pub const POLICY_ORDERING_MAX_PLY: i32 = 2;
+
+/// 補助 policy ネットワーク
+pub struct PolicyNet {
+    weights: Box<[i16]>,
+}
+
+impl PolicyNet {
+    /// 重み配列から生成する
+    pub fn from_weights(weights: Box<[i16]>) -> io::Result<Self> {
+        if weights.len() != POLICY_NUM_WEIGHTS {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "policy weight count mismatch: got {}, expected {POLICY_NUM_WEIGHTS}",
+                    weights.len()
+                ),
+            ));
+        }
+        Ok(Self { weights })
+    }
+
+    /// バイト列から読み込む
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<Self> {
+        let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidData, msg);
+
+        if bytes.len() < 16 || &bytes[..8] != POLICY_MAGIC {
+            return Err(invalid("not a policy file (bad magic)".to_string()));
+        }
+        let version = u32::from_le_bytes(bytes[8..12].try_into().expect("slice length is 4"));
+        if version != POLICY_VERSION {
+            return Err(invalid(format!("unsupported policy file version {version}")));
+        }
+        let count = u32::from_le_bytes(bytes[12..16].try_into().expect("slice length is 4"));
+        let body = &bytes[16..];
+        if count as usize != POLICY_NUM_WEIGHTS || body.len() != POLICY_NUM_WEIGHTS * 2 {
+            return Err(invalid(format!(
+                "policy size mismatch: header {count}, body {} bytes, expected {POLICY_NUM_WEIGHTS} weights",
+                body.len()
+            )));
+        }
+
+        let weights: Box<[i16]> =
+            body.chunks_exact(2).map(|c| i16::from_le_bytes([c[0], c[1]])).collect();
+        Self::from_weights(weights)
+    }
+
+    /// ファイルから読み込む
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::from_bytes(&std::fs::read(path)?)
+    }
+
+    /// ファイル形式のバイト列に変換する
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(16 + self.weights.len() * 2);
+        out.extend_from_slice(POLICY_MAGIC);
+        out.extend_from_slice(&POLICY_VERSION.to_le_bytes());
+        out.extend_from_slice(&(self.weights.len() as u32).to_le_bytes());
+        for w in self.weights.iter() {
+            out.extend_from_slice(&w.to_le_bytes());
+        }
+        out
+    }
+
+    /// 指し手に対するオーダリング用バイアス（history と同スケール）
+    #[inline]
+    pub fn move_bonus(&self, us: Color, mv: Move) -> i32 {
+        match policy_index(us, mv) {
+            Some(idx) => self.weights[idx] as i32,
+            None => 0,
+        }
+    }
+}
+
+/// 指し手を policy の特徴インデックスに変換する
+///
+/// 手番視点に正規化する（後手番では移動先マスを 180 度回転）。
+/// 駒情報を持たない手・PASS・宣言勝ちは `None`。
+pub fn policy_index(us: Color, mv: Move) -> Option<usize> {
+    if mv.is_none() || mv.is_pass() || mv.is_win() {
+        return None;
+    }
+    let pc = mv.moved_piece_after();
+    if pc.is_none() {
+        return None;
+    }
+    let to = if us == Color::Black {
+        mv.to()
+    } else {
+        mv.to().inverse()
+    };
+    let drop = usize::from(mv.is_drop());
+    Some((drop * (PieceType::NUM + 1) + pc.piece_type().index()) * Square::NUM + to.index())
+}
+
+/// policy の教師データ 1 件（root move 1 手分）
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PolicyTarget {
+    /// root move
+    pub mv: Move,
+    /// [`policy_index`] による特徴インデックス
+    pub index: usize,
+    /// 教師確率（全 root move の合計が 1）
+    pub prob: f32,
+}
+
+/// 探索後の root 統計から policy の教師分布を作る
+///
+/// root move ごとの探索ノード数（`RootMove::effort`）を正規化して確率とする。
+/// 統計が空（探索ノード 0）の場合は空を返す。
+pub fn policy_targets(root_moves: &RootMoves, us: Color) -> Vec<PolicyTarget> {
+    let total: f64 = root_moves.iter().map(|rm| rm.effort).sum();
+    if total <= 0.0 {
+        return Vec::new();
+    }
+    root_moves
+        .iter()
+        .filter_map(|rm| {
+            let mv = rm.mv();
+            policy_index(us, mv).map(|index| PolicyTarget {
+                mv,
+                index,
+                prob: (rm.effort / total) as f32,
+            })
+        })
+        .collect()
+}
+
+// =============================================================================
+// グローバル policy（NNUE と同様に探索スレッド間で共有）
+// =============================================================================
+
+static POLICY_NET: LazyLock<RwLock<Option<Arc<PolicyNet>>>> = LazyLock::new(|| RwLock::new(None));
+
+/// ファイルから policy を読み込み、グローバルに設定する
+pub fn init_policy_net<P: AsRef<Path>>(path: P) -> io::Result<()> {
+    let net = Arc::new(PolicyNet::load(path)?);
+    *POLICY_NET.write().expect("policy lock poisoned") = Some(net);
+    Ok(())
+}
+
+/// グローバル policy をクリアする（オーダリングへのバイアスも無効になる）
+pub fn clear_policy_net() {
+    *POLICY_NET.write().expect("policy lock poisoned") = None;
+}
+
+/// グローバル policy を取得する
+pub fn get_policy_net() -> Option<Arc<PolicyNet>> {
+    POLICY_NET.read().expect("policy lock poisoned").clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::position::Position;
+    use crate::search::RootMove;
+
+    #[test]
+    fn policy_index_is_side_relative() {
+        let mut pos = Position::new();
+        pos.set_hirate();
+        let black_mv = pos.to_move(Move::from_usi("7g7f").unwrap()).unwrap();
+
+        pos.set_sfen("lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL w - 1")
+            .unwrap();
+        let white_mv = pos.to_move(Move::from_usi("3c3d").unwrap()).unwrap();
+
+        assert_eq!(policy_index(Color::Black, black_mv), policy_index(Color::White, white_mv));
+        assert!(policy_index(Color::Black, Move::PASS).is_none());
+    }
+
+    #[test]
+    fn policy_file_roundtrip() {
+        let mut weights = vec![0i16; POLICY_NUM_WEIGHTS].into_boxed_slice();
+        weights[5] = 123;
+        weights[POLICY_NUM_WEIGHTS - 1] = -7;
+        let net = PolicyNet::from_weights(weights).unwrap();
+
+        let loaded = PolicyNet::from_bytes(&net.to_bytes()).unwrap();
+        assert_eq!(loaded.weights[5], 123);
+        assert_eq!(loaded.weights[POLICY_NUM_WEIGHTS - 1], -7);
+
+        let mut broken = net.to_bytes();
+        broken.pop();
+        assert!(PolicyNet::from_bytes(&broken).is_err());
+    }
+
+    #[test]
+    fn policy_targets_normalize_effort() {
+        let mut pos = Position::new();
+        pos.set_hirate();
+        let a = pos.to_move(Move::from_usi("7g7f").unwrap()).unwrap();
+        let b = pos.to_move(Move::from_usi("2g2f").unwrap()).unwrap();
+
+        let mut rm_a = RootMove::new(a);
+        rm_a.effort = 300.0;
+        let mut rm_b = RootMove::new(b);
+        rm_b.effort = 100.0;
+        let root_moves = RootMoves::from_vec(vec![rm_a, rm_b]);
+
+        let targets = policy_targets(&root_moves, Color::Black);
+        assert_eq!(targets.len(), 2);
+        assert_eq!(targets[0].mv, a);
+        assert!((targets[0].prob - 0.75).abs() < 1e-6);
+        assert!((targets[1].prob - 0.25).abs() < 1e-6);
+    }
+}
@@ -16,7 +16,7 @@ use crate::types::{Move, Piece, Square, Value};
 
 use super::alpha_beta::{SearchContext, SearchState};
 use super::types::{ContHistKey, STACK_SIZE};
-use super::{LimitsType, TimeManagement};
+use super::{CpuThrottle, LimitsType, TimeManagement};
 
 // =============================================================================
 // 中断チェック
@@ -100,6 +100,11 @@ pub(super) fn check_abort(
         }
     }
 
+    // CPU 使用率の上限（停止時刻の直前は眠らない）
+    if limits.has_cpu_limit() && !CpuThrottle::near_search_end(time_manager) {
+        st.throttle.checkpoint(limits.cpu_limit_percent);
+    }
+
     false
 }
 
@@ -0,0 +1,571 @@
+//! 探索スナップショット（反復開始時点の探索状態の保存と再実行）
+//!
+//! 枝刈りまわりの不具合は、対局ログからは同じ探索を再現できず調べられないことが多い。
+//! [`SnapshotOptions`] を設定すると、メインスレッドは各反復の開始時点の状態
+//! （ルート局面・ルート手・置換表・履歴統計・探索スタック）を控え、その反復の最善手の評価値が
+//! 前の反復から `score_swing` を超えて動いたら、開始時点の状態をファイルに書き出す。
+//! 書き出したファイルは [`SearchSnapshot::load`] で読み、
+//! [`Search::replay_snapshot`](super::Search::replay_snapshot) で同じ反復を再実行できる。
+//!
+//! - 控えるのは `Threads` 1・MultiPV 1 の探索で、深さ [`SNAPSHOT_MIN_DEPTH`] 以上の反復のみ
+//!   （helper スレッドによる置換表の書き換えは再現できないため）
+//! - 置換表と履歴統計は丸ごと複製するため、反復ごとに置換表サイズ + 約 100MB を複製する。
+//!   小さい `USI_Hash` で再現を試す用途を想定し、1 回の `go` で書き出すのは 1 ファイルまで
+//! - ルート局面は千日手判定用の履歴ごと拡張 SFEN（[`Position::to_sfen_ext`]）で保存する
+//! - 評価関数と探索パラメータは、再実行時に元の探索と同じものを設定しておくこと
+
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::ptr::NonNull;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::{ContHistKey, LimitsType, PieceToHistory, RootMove, SearchWorker, StackArray};
+use crate::position::Position;
+use crate::types::{Depth, Move, Piece, Square, Value};
+
+/// スナップショットを控える最小の反復深さ（浅い反復は値が揺れやすく、複製に見合わない）
+pub const SNAPSHOT_MIN_DEPTH: Depth = 6;
+
+/// ファイル先頭のマジック
+const MAGIC: &[u8; 8] = b"RSSNAP01";
+
+/// スナップショットの書き出し条件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnapshotOptions {
+    /// 書き出し先ディレクトリ
+    pub dir: PathBuf,
+    /// 反復間の最善手の評価値の変動がこれを超えたら書き出す（cp）
+    pub score_swing: i32,
+}
+
+/// 1 反復の探索結果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IterationOutcome {
+    /// 最善手の評価値
+    pub score: Value,
+    /// 読み筋
+    pub pv: Vec<Move>,
+    /// 反復中の探索ノード数
+    pub nodes: u64,
+}
+
+/// スナップショットの読み書き・再実行のエラー
+#[derive(Debug)]
+pub enum SnapshotError {
+    Io(io::Error),
+    /// ファイルの形式や内容が不正
+    Format(String),
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::Io(e) => write!(f, "snapshot I/O error: {e}"),
+            SnapshotError::Format(msg) => write!(f, "invalid snapshot: {msg}"),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+impl From<io::Error> for SnapshotError {
+    fn from(e: io::Error) -> Self {
+        SnapshotError::Io(e)
+    }
+}
+
+/// 反復開始時点の探索状態
+///
+/// 指し手は駒情報を持たない 16bit 形式で保存する。再実行時に局面から駒情報を補う。
+#[derive(Clone)]
+pub struct SearchSnapshot {
+    /// ルート局面の拡張 SFEN（千日手判定用の履歴付き）
+    pub sfen: String,
+    /// 再実行する反復の深さ
+    pub depth: Depth,
+    /// 深さを伸ばせなかった回数（aspiration の探索深さの調整に使う）
+    pub search_again_counter: i32,
+    /// 反復開始時の探索ノード数（千日手の評価値の揺らぎなどが参照する）
+    pub nodes: u64,
+    /// 引き分けまでの最大手数
+    pub max_moves_to_draw: i32,
+    /// `DrawValueBlack`
+    pub draw_value_black: i32,
+    /// `DrawValueWhite`
+    pub draw_value_white: i32,
+    /// 不成を含む全合法手を生成するか
+    pub generate_all_legal_moves: bool,
+    /// 元の探索に打ち切り手段（時間・ノード数・infinite）があったか（延長の上限が変わる）
+    pub interrupt_budget: bool,
+    /// 反復開始時のルート手（順序・評価値・読み筋）
+    pub root_moves: Vec<RootMove>,
+    /// 元の探索でのこの反復の結果
+    pub original: Option<IterationOutcome>,
+    /// 置換表の世代
+    tt_generation: u8,
+    /// 置換表の内容
+    tt: Vec<u8>,
+    /// 履歴統計の内容
+    history: Vec<u8>,
+    /// 探索スタックの内容（前の反復の値が残っており、探索の分岐に影響する）
+    stack: Vec<u8>,
+}
+
+impl SearchSnapshot {
+    /// worker の現在の状態を控える（探索の合間に呼ぶこと）
+    pub(crate) fn capture(
+        worker: &SearchWorker,
+        pos: &Position,
+        limits: &LimitsType,
+        depth: Depth,
+        search_again_counter: i32,
+    ) -> Self {
+        // SAFETY: 反復の合間に呼ばれ、履歴への可変参照は保持されていない
+        let tables = unsafe { worker.history.as_ref_unchecked() };
+        let history = tables.as_bytes().to_vec();
+        let stack = encode_stack(&worker.state.stack, tables.as_bytes().as_ptr_range());
+        Self {
+            sfen: pos.to_sfen_ext(),
+            depth,
+            search_again_counter,
+            nodes: worker.state.nodes,
+            max_moves_to_draw: worker.max_moves_to_draw,
+            draw_value_black: worker.draw_value_black,
+            draw_value_white: worker.draw_value_white,
+            generate_all_legal_moves: worker.generate_all_legal_moves,
+            interrupt_budget: limits.has_interrupt_budget(),
+            root_moves: worker.state.root_moves.iter().cloned().collect(),
+            original: None,
+            tt_generation: worker.tt.generation(),
+            tt: worker.tt.raw_bytes().to_vec(),
+            history,
+            stack,
+        }
+    }
+
+    /// 置換表のサイズ（MB）
+    pub fn tt_size_mb(&self) -> usize {
+        self.tt.len() >> 20
+    }
+
+    /// 置換表・履歴統計・ルート手を worker に書き戻す
+    ///
+    /// `pos` はルート局面。ルート手と読み筋には局面から駒情報を補う。
+    pub(crate) fn restore(
+        &self,
+        worker: &mut SearchWorker,
+        pos: &mut Position,
+    ) -> Result<(), SnapshotError> {
+        if !worker.tt.load_raw_bytes(&self.tt, self.tt_generation) {
+            return Err(SnapshotError::Format(format!(
+                "transposition table size mismatch (snapshot {} MB)",
+                self.tt_size_mb()
+            )));
+        }
+        // SAFETY: 探索開始前で、履歴への他の参照は保持されていない
+        let tables = unsafe { worker.history.as_mut_unchecked() };
+        if !tables.copy_from_bytes(&self.history) {
+            return Err(SnapshotError::Format(
+                "history table size mismatch (built with a different version?)".to_string(),
+            ));
+        }
+        let base = tables.as_bytes().as_ptr();
+        decode_stack(&self.stack, &mut worker.state.stack, base, worker.cont_history_sentinel)?;
+        worker.state.nodes = self.nodes;
+        worker.state.root_moves.clear();
+        for rm in &self.root_moves {
+            let mut restored = rm.clone();
+            restored.pv = restore_line(pos, &rm.pv)?;
+            worker.state.root_moves.push(restored);
+        }
+        Ok(())
+    }
+
+    /// ファイルに書き出す
+    pub fn save(&self, path: &Path) -> Result<(), SnapshotError> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_to(&mut writer)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// ファイルから読む
+    pub fn load(path: &Path) -> Result<Self, SnapshotError> {
+        Self::read_from(&mut BufReader::new(File::open(path)?))
+    }
+
+    /// バイナリ形式で書き出す
+    pub fn write_to<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        w.write_all(MAGIC)?;
+        write_bytes(w, self.sfen.as_bytes())?;
+        for v in [
+            self.depth,
+            self.search_again_counter,
+            self.max_moves_to_draw,
+            self.draw_value_black,
+            self.draw_value_white,
+        ] {
+            w.write_all(&v.to_le_bytes())?;
+        }
+        w.write_all(&self.nodes.to_le_bytes())?;
+        w.write_all(&[
+            self.generate_all_legal_moves as u8,
+            self.interrupt_budget as u8,
+            self.tt_generation,
+        ])?;
+
+        w.write_all(&(self.root_moves.len() as u32).to_le_bytes())?;
+        for rm in &self.root_moves {
+            for v in [rm.score, rm.previous_score, rm.average_score] {
+                w.write_all(&v.raw().to_le_bytes())?;
+            }
+            w.write_all(&[rm.mean_squared_score.is_some() as u8])?;
+            w.write_all(&rm.mean_squared_score.unwrap_or(0).to_le_bytes())?;
+            w.write_all(&[rm.score_lower_bound as u8, rm.score_upper_bound as u8])?;
+            w.write_all(&rm.sel_depth.to_le_bytes())?;
+            w.write_all(&rm.effort.to_le_bytes())?;
+            write_line(w, &rm.pv)?;
+        }
+
+        match &self.original {
+            Some(outcome) => {
+                w.write_all(&[1])?;
+                w.write_all(&outcome.score.raw().to_le_bytes())?;
+                w.write_all(&outcome.nodes.to_le_bytes())?;
+                write_line(w, &outcome.pv)?;
+            }
+            None => w.write_all(&[0])?,
+        }
+
+        write_bytes(w, &self.history)?;
+        write_bytes(w, &self.stack)?;
+        write_bytes(w, &self.tt)
+    }
+
+    /// バイナリ形式から読む
+    pub fn read_from<R: Read>(r: &mut R) -> Result<Self, SnapshotError> {
+        let mut magic = [0u8; 8];
+        r.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(SnapshotError::Format("not a search snapshot".to_string()));
+        }
+        let sfen = String::from_utf8(read_bytes(r)?)
+            .map_err(|_| SnapshotError::Format("sfen is not UTF-8".to_string()))?;
+        let mut pos = Position::new();
+        pos.set_sfen(&sfen)
+            .map_err(|e| SnapshotError::Format(format!("invalid sfen: {e}")))?;
+        let depth = read_i32(r)?;
+        let search_again_counter = read_i32(r)?;
+        let max_moves_to_draw = read_i32(r)?;
+        let draw_value_black = read_i32(r)?;
+        let draw_value_white = read_i32(r)?;
+        let nodes = u64::from_le_bytes(read_array(r)?);
+        let [generate_all_legal_moves, interrupt_budget, tt_generation] = read_array(r)?;
+
+        let count = u32::from_le_bytes(read_array(r)?) as usize;
+        let mut root_moves = Vec::with_capacity(count.min(1024));
+        for _ in 0..count {
+            let score = Value::new(read_i32(r)?);
+            let previous_score = Value::new(read_i32(r)?);
+            let average_score = Value::new(read_i32(r)?);
+            let [has_msq] = read_array(r)?;
+            let msq = i64::from_le_bytes(read_array(r)?);
+            let [lower, upper] = read_array(r)?;
+            let sel_depth = read_i32(r)?;
+            let effort = f64::from_le_bytes(read_array(r)?);
+            let pv = read_line(r)?;
+            if pv.is_empty() {
+                return Err(SnapshotError::Format("root move without move".to_string()));
+            }
+            root_moves.push(RootMove {
+                score,
+                previous_score,
+                average_score,
+                mean_squared_score: (has_msq != 0).then_some(msq),
+                score_lower_bound: lower != 0,
+                score_upper_bound: upper != 0,
+                sel_depth,
+                effort,
+                pv,
+            });
+        }
+
+        let [has_original] = read_array(r)?;
+        let original = if has_original != 0 {
+            let score = Value::new(read_i32(r)?);
+            let nodes = u64::from_le_bytes(read_array(r)?);
+            let pv = restore_line(&mut pos, &read_line(r)?)?;
+            Some(IterationOutcome { score, pv, nodes })
+        } else {
+            None
+        };
+
+        let history = read_bytes(r)?;
+        let stack = read_bytes(r)?;
+        let tt = read_bytes(r)?;
+        Ok(Self {
+            sfen,
+            depth,
+            search_again_counter,
+            nodes,
+            max_moves_to_draw,
+            draw_value_black,
+            draw_value_white,
+            generate_all_legal_moves: generate_all_legal_moves != 0,
+            interrupt_budget: interrupt_budget != 0,
+            root_moves,
+            original,
+            tt_generation,
+            tt,
+            history,
+            stack,
+        })
+    }
+}
+
+/// 探索中にスナップショットを控え、条件を満たした反復で書き出す（メインスレッド用）
+pub(crate) struct SnapshotRecorder<'a> {
+    options: &'a SnapshotOptions,
+    /// 現在の反復の開始状態
+    pending: Option<SearchSnapshot>,
+    /// この `go` で書き出し済みか
+    written: bool,
+}
+
+impl<'a> SnapshotRecorder<'a> {
+    pub(crate) fn new(options: &'a SnapshotOptions) -> Self {
+        Self {
+            options,
+            pending: None,
+            written: false,
+        }
+    }
+
+    /// 反復の開始状態を控える
+    pub(crate) fn begin_iteration(
+        &mut self,
+        worker: &SearchWorker,
+        pos: &Position,
+        limits: &LimitsType,
+        depth: Depth,
+        search_again_counter: i32,
+        multi_pv: usize,
+    ) {
+        self.pending = None;
+        if self.written || multi_pv != 1 || depth < SNAPSHOT_MIN_DEPTH {
+            return;
+        }
+        let snapshot = SearchSnapshot::capture(worker, pos, limits, depth, search_again_counter);
+        self.pending = Some(snapshot);
+    }
+
+    /// 反復の完了時に評価値の変動を調べ、`score_swing` を超えていれば書き出す
+    pub(crate) fn end_iteration(&mut self, worker: &SearchWorker) {
+        let Some(mut snapshot) = self.pending.take() else {
+            return;
+        };
+        let (Some(before), Some(after)) =
+            (snapshot.root_moves.first(), worker.state.root_moves.get(0))
+        else {
+            return;
+        };
+        let (prev, score) = (before.score, after.score);
+        if prev.is_mate_score()
+            || score.is_mate_score()
+            || (score.raw() - prev.raw()).abs() <= self.options.score_swing
+        {
+            return;
+        }
+        snapshot.original = Some(IterationOutcome {
+            score,
+            pv: after.pv.clone(),
+            nodes: worker.state.nodes - snapshot.nodes,
+        });
+        self.written = true;
+
+        let millis = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_millis());
+        let path = self.options.dir.join(format!("snapshot-{millis}-d{}.bin", snapshot.depth));
+        match snapshot.save(&path) {
+            Ok(()) => eprintln!(
+                "info string search snapshot written: {} (depth {}, score {} -> {})",
+                path.display(),
+                snapshot.depth,
+                prev.raw(),
+                score.raw()
+            ),
+            Err(e) => eprintln!("info string failed to write search snapshot: {e}"),
+        }
+    }
+}
+
+/// 16bit 形式の指し手列に、ルート局面から順に駒情報を補う
+fn restore_line(pos: &mut Position, line: &[Move]) -> Result<Vec<Move>, SnapshotError> {
+    let mut restored = Vec::with_capacity(line.len());
+    for &raw in line {
+        let mv = pos
+            .to_move(raw)
+            .filter(|&mv| pos.pseudo_legal(mv) && pos.is_legal(mv))
+            .ok_or_else(|| {
+                SnapshotError::Format(format!("illegal move in snapshot: {}", raw.to_usi()))
+            })?;
+        let gives_check = pos.gives_check(mv);
+        pos.do_move(mv, gives_check);
+        restored.push(mv);
+    }
+    for &mv in restored.iter().rev() {
+        pos.undo_move(mv);
+    }
+    Ok(restored)
+}
+
+/// 探索スタック 1 要素のバイト数
+const STACK_ENTRY_BYTES: usize = 53;
+
+/// 探索スタックをバイト列にする
+///
+/// continuation history への参照は履歴統計の先頭からのオフセットで保存する
+/// （テーブル外を指す初期値は `u64::MAX`）。
+fn encode_stack(stack: &StackArray, history: std::ops::Range<*const u8>) -> Vec<u8> {
+    let mut out = Vec::with_capacity(stack.len() * STACK_ENTRY_BYTES);
+    for ss in stack {
+        let ptr = ss.cont_history_ptr.as_ptr() as *const u8;
+        let offset = if history.contains(&ptr) {
+            ptr as u64 - history.start as u64
+        } else {
+            u64::MAX
+        };
+        let key = ss.cont_hist_key.map_or([0; 5], |k| {
+            [
+                1,
+                k.in_check as u8,
+                k.capture as u8,
+                k.piece.raw(),
+                k.to.raw(),
+            ]
+        });
+        out.push(ss.follow_pv as u8);
+        out.extend_from_slice(&(ss.cont_history_idx as u32).to_le_bytes());
+        out.extend_from_slice(&offset.to_le_bytes());
+        out.extend_from_slice(&key);
+        for v in [
+            ss.ply,
+            ss.current_move.raw32() as i32,
+            ss.excluded_move.raw32() as i32,
+            ss.static_eval.raw(),
+            ss.stat_score,
+            ss.move_count,
+            ss.cutoff_cnt,
+            ss.reduction,
+        ] {
+            out.extend_from_slice(&v.to_le_bytes());
+        }
+        out.extend_from_slice(&[ss.in_check as u8, ss.tt_pv as u8, ss.tt_hit as u8]);
+    }
+    out
+}
+
+/// [`encode_stack`] の逆変換
+fn decode_stack(
+    bytes: &[u8],
+    stack: &mut StackArray,
+    history: *const u8,
+    sentinel: NonNull<PieceToHistory>,
+) -> Result<(), SnapshotError> {
+    if bytes.len() != stack.len() * STACK_ENTRY_BYTES {
+        return Err(SnapshotError::Format("search stack size mismatch".to_string()));
+    }
+    let invalid = || SnapshotError::Format("invalid search stack entry".to_string());
+    for (ss, b) in stack.iter_mut().zip(bytes.chunks_exact(STACK_ENTRY_BYTES)) {
+        let int = |at: usize| i32::from_le_bytes(b[at..at + 4].try_into().unwrap());
+        let offset = u64::from_le_bytes(b[5..13].try_into().unwrap());
+        let key = &b[13..18];
+        ss.follow_pv = b[0] != 0;
+        ss.cont_history_idx = u32::from_le_bytes(b[1..5].try_into().unwrap()) as usize;
+        ss.cont_history_ptr = if offset == u64::MAX {
+            sentinel
+        } else {
+            // SAFETY: オフセットは同じレイアウトの履歴統計の内側を指していたもの
+            //         （長さの一致は copy_from_bytes で確認済み）
+            NonNull::new(unsafe { history.add(offset as usize) } as *mut PieceToHistory)
+                .ok_or_else(invalid)?
+        };
+        ss.cont_hist_key = if key[0] == 0 {
+            None
+        } else {
+            Some(ContHistKey::new(
+                key[1] != 0,
+                key[2] != 0,
+                decode_piece(key[3]).ok_or_else(invalid)?,
+                Square::from_u8(key[4]).ok_or_else(invalid)?,
+            ))
+        };
+        ss.ply = int(18);
+        ss.current_move = decode_move(int(22) as u32).ok_or_else(invalid)?;
+        ss.excluded_move = decode_move(int(26) as u32).ok_or_else(invalid)?;
+        ss.static_eval = Value::new(int(30));
+        ss.stat_score = int(34);
+        ss.move_count = int(38);
+        ss.cutoff_cnt = int(42);
+        ss.reduction = int(46);
+        ss.in_check = b[50] != 0;
+        ss.tt_pv = b[51] != 0;
+        ss.tt_hit = b[52] != 0;
+    }
+    Ok(())
+}
+
+fn decode_piece(raw: u8) -> Option<Piece> {
+    // SAFETY: 正規エンコードの値であることを確認してから変換する
+    (raw < Piece::NUM as u8 && raw != 15 && raw != 16).then(|| unsafe { Piece::from_raw(raw) })
+}
+
+/// 駒情報付きの 32bit の指し手を戻す
+fn decode_move(raw: u32) -> Option<Move> {
+    let mv = Move::from_u16(raw as u16);
+    match raw >> 16 {
+        0 => Some(mv),
+        piece => Some(mv.with_piece(decode_piece(u8::try_from(piece).ok()?)?)),
+    }
+}
+
+fn write_bytes<W: Write>(w: &mut W, bytes: &[u8]) -> io::Result<()> {
+    w.write_all(&(bytes.len() as u64).to_le_bytes())?;
+    w.write_all(bytes)
+}
+
+fn write_line<W: Write>(w: &mut W, line: &[Move]) -> io::Result<()> {
+    w.write_all(&(line.len() as u16).to_le_bytes())?;
+    for mv in line {
+        w.write_all(&mv.raw().to_le_bytes())?;
+    }
+    Ok(())
+}
+
+fn read_array<const N: usize, R: Read>(r: &mut R) -> io::Result<[u8; N]> {
+    let mut buf = [0u8; N];
+    r.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+fn read_i32<R: Read>(r: &mut R) -> io::Result<i32> {
+    Ok(i32::from_le_bytes(read_array(r)?))
+}
+
+fn read_bytes<R: Read>(r: &mut R) -> Result<Vec<u8>, SnapshotError> {
+    let len = u64::from_le_bytes(read_array(r)?) as usize;
+    let mut buf = Vec::new();
+    r.take(len as u64).read_to_end(&mut buf)?;
+    if buf.len() != len {
+        return Err(SnapshotError::Format("truncated file".to_string()));
+    }
+    Ok(buf)
+}
+
+fn read_line<R: Read>(r: &mut R) -> io::Result<Vec<Move>> {
+    let len = u16::from_le_bytes(read_array(r)?) as usize;
+    (0..len)
+        .map(|_| Ok(Move::from_u16(u16::from_le_bytes(read_array(r)?))))
+        .collect()
+}
@@ -0,0 +1,97 @@
+//! 定跡フック統合テスト
+
+use std::sync::Arc;
+
+use crate::book::{Book, BookMove};
+use crate::position::Position;
+use crate::search::LimitsType;
+use crate::search::engine::{Search, SearchInfo};
+use crate::types::{Move, Value};
+
+/// SearchWorkerは大きなスタックを使うため 64MB 確保
+const STACK_SIZE: usize = 64 * 1024 * 1024;
+
+fn book_move(usi: &str, score: i16) -> BookMove {
+    BookMove {
+        mv: Move::from_usi(usi).unwrap(),
+        weight: 1,
+        score,
+        depth: 24,
+    }
+}
+
+/// 平手に 7g7f、7g7f の後に 3c3d だけを持つ定跡
+fn book() -> Arc<Book> {
+    let mut pos = Position::new();
+    pos.set_hirate();
+    let mut book = Book::new();
+    book.insert(&pos, book_move("7g7f", 35));
+    let mv = pos.to_move(Move::from_usi("7g7f").unwrap()).unwrap();
+    pos.do_move(mv, pos.gives_check(mv));
+    book.insert(&pos, book_move("3c3d", -30));
+    Arc::new(book)
+}
+
+#[test]
+fn go_returns_book_move_without_searching() {
+    std::thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(|| {
+            let mut search = Search::new(16);
+            search.set_book(Some(book()));
+            let mut pos = Position::new();
+            pos.set_hirate();
+
+            let mut infos = 0;
+            let limits = LimitsType {
+                depth: 1,
+                ..Default::default()
+            };
+            let result = search.go(&mut pos, limits, Some(|_: &SearchInfo| infos += 1));
+
+            assert_eq!(result.best_move.to_usi(), "7g7f");
+            assert_eq!(result.ponder_move.to_usi(), "3c3d");
+            assert_eq!(result.score, Value::from_cp(35));
+            assert_eq!(result.depth, 24);
+            assert_eq!(result.nodes, 0);
+            assert_eq!(infos, 0);
+        })
+        .unwrap()
+        .join()
+        .unwrap();
+}
+
+#[test]
+fn go_falls_back_to_search_when_book_is_not_usable() {
+    std::thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(|| {
+            let mut search = Search::new(16);
+            search.set_book(Some(book()));
+            let mut pos = Position::new();
+            pos.set_hirate();
+
+            // searchmoves に定跡手が含まれない
+            let limits = LimitsType {
+                depth: 1,
+                search_moves: vec![pos.to_move(Move::from_usi("2g2f").unwrap()).unwrap()],
+                ..Default::default()
+            };
+            let result = search.go(&mut pos, limits, None::<fn(&SearchInfo)>);
+            assert_eq!(result.best_move.to_usi(), "2g2f");
+            assert!(result.nodes > 0);
+
+            // 定跡にない局面
+            pos.set_sfen("lnsgkgsnl/1r5b1/ppppppppp/9/9/7P1/PPPPPPP1P/1B5R1/LNSGKGSNL w - 2")
+                .unwrap();
+            let limits = LimitsType {
+                depth: 1,
+                ..Default::default()
+            };
+            let result = search.go(&mut pos, limits, None::<fn(&SearchInfo)>);
+            assert!(result.nodes > 0);
+        })
+        .unwrap()
+        .join()
+        .unwrap();
+}
@@ -1,7 +1,9 @@
 //! 探索モジュールのテスト
 
 mod alpha_beta;
+mod book;
 mod history_update;
 mod multi_pv;
+mod search_moves;
 mod skill;
 mod time_management;
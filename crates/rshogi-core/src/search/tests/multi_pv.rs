@@ -398,6 +398,65 @@ fn test_multi_pv_clamped_to_legal_moves_integration() {
     });
 }
 
+/// フォーカスモードでは絞り込み深さ以降、最善手が安定していれば上位だけを探索する
+#[test]
+fn test_multi_pv_focus_narrows_at_depth() {
+    use crate::position::Position;
+    use crate::search::engine::{Search, SearchInfo};
+    use crate::search::{LimitsType, MultiPvFocus};
+    use std::collections::{BTreeMap, BTreeSet};
+
+    run_with_large_stack(|| {
+        let mut search = Search::new(16);
+        let mut pos = Position::new();
+        pos.set_hirate();
+
+        let focus = MultiPvFocus {
+            depth: 3,
+            lines: 1,
+            // 評価値の変動では広げ直さない（最善手の変化だけで判定する）
+            rewiden_margin: Value::INFINITE.raw(),
+        };
+        let limits = LimitsType {
+            depth: 7,
+            multi_pv: 4,
+            multi_pv_focus: focus,
+            ..Default::default()
+        };
+
+        let mut infos = Vec::new();
+        search.go(
+            &mut pos,
+            limits,
+            Some(|info: &SearchInfo| {
+                infos.push(info.clone());
+            }),
+        );
+
+        // 深さごとの出力本数と最善手
+        let mut lines: BTreeMap<i32, BTreeSet<usize>> = BTreeMap::new();
+        let mut best: BTreeMap<i32, Move> = BTreeMap::new();
+        for info in &infos {
+            lines.entry(info.depth).or_default().insert(info.multi_pv);
+            if info.multi_pv == 1 && !info.pv.is_empty() {
+                best.insert(info.depth, info.pv[0]);
+            }
+        }
+
+        for (&depth, pvs) in &lines {
+            let expected = if depth < focus.depth {
+                4
+            } else if best.get(&(depth - 1)) != best.get(&(depth - 2)) {
+                // 直前の反復で最善手が変わったので広げ直す
+                4
+            } else {
+                1
+            };
+            assert_eq!(pvs.len(), expected, "depth {depth}: {pvs:?}");
+        }
+    });
+}
+
 /// MultiPV出力がスコア降順で並ぶことを確認
 #[test]
 fn test_multi_pv_scores_sorted_desc() {
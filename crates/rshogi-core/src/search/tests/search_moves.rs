@@ -0,0 +1,116 @@
+//! searchmoves（ルート手の制限）のテスト
+
+use crate::eval::{MaterialLevel, set_material_level};
+use crate::position::Position;
+use crate::search::LimitsType;
+use crate::search::engine::{Search, SearchInfo};
+use crate::types::Move;
+
+/// SearchWorkerは大きなスタックを使うため 64MB 確保
+const STACK_SIZE: usize = 64 * 1024 * 1024;
+
+fn run_with_stack(f: impl FnOnce() + Send + 'static) {
+    std::thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(f)
+        .unwrap()
+        .join()
+        .unwrap();
+}
+
+fn usi_moves(moves: &[&str]) -> Vec<Move> {
+    moves.iter().map(|usi| Move::from_usi(usi).unwrap()).collect()
+}
+
+#[test]
+fn restricted_search_never_returns_move_outside_set() {
+    run_with_stack(|| {
+        set_material_level(MaterialLevel::Lv9);
+        let mut search = Search::new(16);
+        let mut pos = Position::new();
+        pos.set_hirate();
+
+        // 制限なしの探索で置換表に最善手を残しておき、制限付き探索で TT の指し手が
+        // ルートに持ち込まれないことを確かめる
+        let unrestricted = search.go(
+            &mut pos,
+            LimitsType {
+                depth: 6,
+                ..Default::default()
+            },
+            None::<fn(&SearchInfo)>,
+        );
+        let allowed: Vec<&str> = ["1g1f", "9g9f", "4i5h"]
+            .into_iter()
+            .filter(|usi| *usi != unrestricted.best_move.to_usi())
+            .take(2)
+            .collect();
+
+        // 駒情報のない USI 表記の指し手でも制限できる
+        let limits = LimitsType {
+            depth: 6,
+            multi_pv: 3,
+            search_moves: usi_moves(&allowed),
+            ..Default::default()
+        };
+        let mut info_moves: Vec<String> = Vec::new();
+        let result = search.go(
+            &mut pos,
+            limits,
+            Some(|info: &SearchInfo| {
+                if let Some(mv) = info.pv.first() {
+                    info_moves.push(mv.to_usi());
+                }
+            }),
+        );
+
+        assert!(
+            allowed.contains(&result.best_move.to_usi().as_str()),
+            "{}",
+            result.best_move.to_usi()
+        );
+        // MultiPV は制限した手の数で打ち切る
+        assert_eq!(result.lines.len(), allowed.len());
+        for line in &result.lines {
+            assert!(allowed.contains(&line.pv[0].to_usi().as_str()));
+        }
+        assert!(!info_moves.is_empty());
+        assert!(info_moves.iter().all(|mv| allowed.contains(&mv.as_str())), "{info_moves:?}");
+    });
+}
+
+#[test]
+fn restricted_search_with_single_move_returns_it() {
+    run_with_stack(|| {
+        set_material_level(MaterialLevel::Lv9);
+        let mut search = Search::new(16);
+        let mut pos = Position::new();
+        pos.set_hirate();
+        let limits = LimitsType {
+            depth: 4,
+            search_moves: usi_moves(&["5i4h"]),
+            ..Default::default()
+        };
+        let result = search.go(&mut pos, limits, None::<fn(&SearchInfo)>);
+        assert_eq!(result.best_move.to_usi(), "5i4h");
+        assert!(result.pv.first().is_some_and(|mv| mv.to_usi() == "5i4h"));
+    });
+}
+
+#[test]
+fn searchmoves_without_legal_moves_returns_no_move() {
+    run_with_stack(|| {
+        set_material_level(MaterialLevel::Lv9);
+        let mut search = Search::new(16);
+        let mut pos = Position::new();
+        pos.set_hirate();
+        // 平手では指せない手だけを指定した場合はルート手がない
+        let limits = LimitsType {
+            depth: 4,
+            search_moves: usi_moves(&["5e5d"]),
+            ..Default::default()
+        };
+        let result = search.go(&mut pos, limits, None::<fn(&SearchInfo)>);
+        assert_eq!(result.best_move, Move::NONE);
+    });
+}
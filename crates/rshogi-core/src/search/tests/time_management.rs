@@ -301,6 +301,7 @@ fn test_round_up_basic() {
         slow_mover: 100,
         usi_ponder: false,
         stochastic_ponder: false,
+        phase_time_weight: 0,
     });
     // remain_timeを設定するため一度init
     let mut limits = LimitsType::new();
@@ -330,6 +331,7 @@ fn test_round_up_below_minimum() {
         slow_mover: 100,
         usi_ponder: false,
         stochastic_ponder: false,
+        phase_time_weight: 0,
     });
     let mut limits = LimitsType::new();
     limits.time[Color::Black.index()] = 100000;
@@ -356,6 +358,7 @@ fn test_round_up_add_extra_second() {
         slow_mover: 100,
         usi_ponder: false,
         stochastic_ponder: false,
+        phase_time_weight: 0,
     });
     let mut limits = LimitsType::new();
     limits.time[Color::Black.index()] = 100000;
@@ -383,6 +386,7 @@ fn test_round_up_exceeds_remain_time() {
         slow_mover: 100,
         usi_ponder: false,
         stochastic_ponder: false,
+        phase_time_weight: 0,
     });
     let mut limits = LimitsType::new();
     limits.time[Color::Black.index()] = 5000; // 少ない
@@ -102,7 +102,7 @@ mod imp {
 
             for thread in &self.threads {
                 thread.start_searching(SearchTask {
-                    pos: pos.clone(),
+                    pos: pos.clone_with_history(),
                     limits: limits.clone(),
                     max_depth,
                     time_options,
@@ -773,7 +773,7 @@ mod imp {
                 let helper_results = Arc::clone(&self.helper_results);
                 // thread_id is 1-indexed, so subtract 1 to get the progress index
                 let progress = Arc::clone(&self.helper_progress[thread_id - 1]);
-                let pos_clone = pos.clone();
+                let pos_clone = pos.clone_with_history();
                 let limits_clone = limits.clone();
 
                 rayon::spawn_fifo(move || {
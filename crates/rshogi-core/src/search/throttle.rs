@@ -0,0 +1,129 @@
+//! CPU 使用率の上限（nice モード）
+//!
+//! 検討をバックグラウンドで走らせる場合に、探索スレッドが CPU を使い切らないよう
+//! 中断チェック（`check_abort` が 512 ノードごとに行う実際のチェック）のたびに
+//! 短いスリープを挟む。上限 `p`% なら、前回のチェックから働いた時間の `(100 - p) / p` 倍を
+//! 「眠るべき時間」として積み立て、[`MIN_NAP`] 以上たまったら [`MAX_NAP`] を上限に眠る。
+//!
+//! 時刻は時間管理の時計（[`crate::time::Clock`]）ではなく実時間で測る。スリープの後に
+//! 時間切れを確認するのは次のチェックなので、停止時刻（`search_end`）まで [`MAX_NAP`] を
+//! 切ったら眠らない。
+
+use std::time::Duration;
+
+use super::TimeManagement;
+use crate::time::Instant;
+
+/// これより短い分はまとめて眠る（OS のスリープ精度より細かく眠っても効かない）
+const MIN_NAP: Duration = Duration::from_millis(1);
+
+/// 1 回に眠る時間の上限（`stop` や時間切れへの応答を遅らせすぎない）
+const MAX_NAP: Duration = Duration::from_millis(10);
+
+/// 探索スレッド 1 本分の CPU 使用率の調整状態
+#[derive(Debug, Clone, Default)]
+pub struct CpuThrottle {
+    /// 前回のチェックの時刻（探索開始後の最初のチェックまでは `None`）
+    last: Option<Instant>,
+    /// これから眠るべき時間
+    debt: Duration,
+    /// 探索に使った時間
+    busy: Duration,
+    /// 眠った時間
+    slept: Duration,
+}
+
+impl CpuThrottle {
+    /// 探索開始時に計測をやり直す
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    /// 停止時刻が決まっていて、眠ると停止が遅れうるか
+    #[inline]
+    pub(super) fn near_search_end(time_manager: &TimeManagement) -> bool {
+        let end = time_manager.search_end();
+        end > 0 && time_manager.elapsed() + MAX_NAP.as_millis() as i64 >= end
+    }
+
+    /// 中断チェックのたびに呼び、必要なら眠る
+    ///
+    /// `limit_percent` が 0 または 100 以上なら何もしない。
+    pub fn checkpoint(&mut self, limit_percent: u8) {
+        if !(1..100).contains(&limit_percent) {
+            return;
+        }
+        let now = Instant::now();
+        let Some(last) = self.last.replace(now) else {
+            return;
+        };
+        let worked = now.saturating_duration_since(last);
+        self.busy += worked;
+        let p = u32::from(limit_percent);
+        self.debt += worked * (100 - p) / p;
+        if self.debt < MIN_NAP {
+            return;
+        }
+        let nap = self.debt.min(MAX_NAP);
+        sleep(nap);
+        let woke = Instant::now();
+        let slept = woke.saturating_duration_since(now);
+        self.slept += slept;
+        self.debt = self.debt.saturating_sub(slept);
+        self.last = Some(woke);
+    }
+
+    /// 探索開始からの実際の CPU 使用率（%）。まだ計測していなければ `None`
+    pub fn usage_percent(&self) -> Option<u32> {
+        let total = self.busy + self.slept;
+        if total.is_zero() {
+            return None;
+        }
+        Some((self.busy.as_secs_f64() * 100.0 / total.as_secs_f64()).round() as u32)
+    }
+}
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+fn sleep(nap: Duration) {
+    std::thread::sleep(nap);
+}
+
+/// wasm32-unknown-unknown のメインスレッドは眠れないため、使用率の調整はしない
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+fn sleep(_nap: Duration) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spin(d: Duration) {
+        let start = Instant::now();
+        while start.elapsed() < d {
+            std::hint::spin_loop();
+        }
+    }
+
+    #[test]
+    fn unlimited_does_not_measure() {
+        let mut throttle = CpuThrottle::default();
+        for _ in 0..3 {
+            throttle.checkpoint(100);
+            throttle.checkpoint(0);
+        }
+        assert_eq!(throttle.usage_percent(), None);
+    }
+
+    #[test]
+    fn limit_sleeps_in_proportion_to_work() {
+        let mut throttle = CpuThrottle::default();
+        throttle.checkpoint(25);
+        for _ in 0..40 {
+            spin(Duration::from_millis(1));
+            throttle.checkpoint(25);
+        }
+        // 働いた時間の 3 倍眠る（スリープは指定より長くなり得るので上側は緩めに見る）
+        let usage = throttle.usage_percent().unwrap();
+        assert!((5..=30).contains(&usage), "usage={usage}");
+        assert!(throttle.slept >= throttle.busy * 2, "{throttle:?}");
+    }
+}
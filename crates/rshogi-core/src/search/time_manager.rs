@@ -3,8 +3,9 @@
 //! 使用可能な最大時間、対局の手数、その他のパラメータに応じて、
 //! 思考に費やす最適な時間を計算する。
 
-use super::{LimitsType, TimeOptions, TimePoint};
-use crate::time::Instant;
+use super::{LimitsType, TimeControl, TimeOptions, TimePoint};
+use crate::position::GamePhase;
+use crate::time::{Instant, SharedClock, system_clock};
 use crate::types::Color;
 use log::debug;
 use rand::Rng;
@@ -36,6 +37,16 @@ pub const DEFAULT_MAX_MOVES_TO_DRAW: i32 = 100000;
 /// 合法手1つの場合の時間上限（ミリ秒）- YaneuraOu準拠
 const SINGLE_MOVE_TIME_LIMIT: TimePoint = 500;
 
+/// フィッシャー（秒読みなし）で持ち時間に残しておく予備（ミリ秒）
+///
+/// 持ち時間がこれを切ったら、その分には手を付けず加算（inc）の範囲で指す。
+const FISCHER_PANIC_RESERVE: TimePoint = 3000;
+
+/// フィッシャー（秒読みなし）で maximum を「inc + 貯金 / この値」までに抑える
+///
+/// 最善手が不安定なときの延長（total_time の伸び）で貯金を一度に使い果たさないようにする。
+const FISCHER_MAX_BANK_DIVISOR: TimePoint = 5;
+
 /// 最善手不安定性係数の定数 - YaneuraOu準拠
 /// bestMoveInstability = BASE + FACTOR * totBestMoveChanges / threads.size()
 /// 注: クランプなし（YaneuraOu準拠）
@@ -115,6 +126,9 @@ pub fn normalize_nodes_effort(effort: f64, nodes_total: u64) -> f64 {
 ///
 /// 探索の思考時間を計算し、停止判定を行う。
 pub struct TimeManagement {
+    /// 時計（`init` で `LimitsType` から受け取る）
+    clock: SharedClock,
+
     /// 探索開始時刻
     start_time: Instant,
 
@@ -148,6 +162,12 @@ pub struct TimeManagement {
     /// SlowMover（百分率）
     slow_mover: i32,
 
+    /// 中盤で optimum を増やす割合（百分率、0 なら無効）
+    phase_time_weight: i32,
+
+    /// 今回の局面の進行度（`set_game_phase` で設定）
+    game_phase: Option<GamePhase>,
+
     /// 今回の最大残り時間（NetworkDelay2 減算後）
     remain_time: TimePoint,
 
@@ -183,8 +203,10 @@ pub struct TimeManagement {
 impl TimeManagement {
     /// 新しいTimeManagementを作成
     pub fn new(stop: Arc<AtomicBool>, ponderhit: Arc<AtomicBool>) -> Self {
-        let now = Instant::now();
+        let clock = system_clock();
+        let now = clock.now();
         Self {
+            clock,
             start_time: now,
             optimum_time: 0,
             maximum_time: 0,
@@ -196,6 +218,8 @@ impl TimeManagement {
             network_delay: DEFAULT_NETWORK_DELAY,
             network_delay2: DEFAULT_NETWORK_DELAY2,
             slow_mover: DEFAULT_SLOW_MOVER,
+            phase_time_weight: 0,
+            game_phase: None,
             remain_time: TimePoint::MAX / 2,
             stop,
             ponderhit,
@@ -215,10 +239,16 @@ impl TimeManagement {
         self.network_delay2 = opts.network_delay2.max(0);
         self.minimum_thinking_time = opts.minimum_thinking_time.max(MIN_MINIMUM_THINKING_TIME);
         self.slow_mover = opts.slow_mover.clamp(1, 1000);
+        self.phase_time_weight = opts.phase_time_weight.clamp(0, 100);
         self.usi_ponder = opts.usi_ponder;
         self.stochastic_ponder = opts.stochastic_ponder;
     }
 
+    /// 今回の局面の進行度をセット（`init` より前に呼ぶ）
+    pub fn set_game_phase(&mut self, phase: GamePhase) {
+        self.game_phase = Some(phase);
+    }
+
     /// 前回の time_reduction をセット（YO準拠の持ち回り用）
     pub fn set_previous_time_reduction(&mut self, value: f64) {
         self.previous_time_reduction = value;
@@ -274,7 +304,8 @@ impl TimeManagement {
     /// * `ply` - 現在の手数
     /// * `max_moves_to_draw` - 引き分けまでの最大手数
     pub fn init(&mut self, limits: &LimitsType, us: Color, ply: i32, max_moves_to_draw: i32) {
-        self.start_time = limits.start_time.unwrap_or_else(Instant::now);
+        self.clock = Arc::clone(&limits.clock);
+        self.start_time = limits.start_time.unwrap_or_else(|| self.clock.now());
         self.ponderhit_time = self.start_time;
         self.search_end = 0;
         self.is_final_push = false;
@@ -284,8 +315,11 @@ impl TimeManagement {
         self.stop_on_ponderhit = false;
         self.last_stop_threshold = None;
 
+        // 時間指定の解釈（複数の指定が混在するときの優先順位は TimeControl 参照）
+        let time_control = limits.time_control(us);
+
         // movetime指定の場合
-        if limits.has_movetime() {
+        if time_control == TimeControl::MoveTime {
             let movetime = limits.movetime;
             self.remain_time = movetime;
             self.optimum_time = movetime;
@@ -297,7 +331,7 @@ impl TimeManagement {
         }
 
         // 時間制御を使わない場合（depth, nodes, infinite等）
-        if !limits.use_time_management() {
+        if time_control == TimeControl::Unmanaged {
             self.optimum_time = TimePoint::MAX / 2;
             self.maximum_time = TimePoint::MAX / 2;
             self.remain_time = TimePoint::MAX / 2;
@@ -310,12 +344,12 @@ impl TimeManagement {
         let byoyomi = limits.byoyomi_time(us);
 
         // 秒読みモードかどうかを先に判定（持ち時間が秒読みの1.2倍未満）
-        // increment > 0 の場合はフィッシャールールなので秒読みモードにしない
-        let is_byoyomi_mode =
-            byoyomi > 0 && increment == 0 && time_left < (byoyomi as f64 * 1.2) as TimePoint;
+        // フィッシャー + 秒読みでも、持ち時間が尽きかけたら秒読みを使い切るモードに入る。
+        // increment のみ（秒読みなし）のフィッシャールールは秒読みモードにしない
+        let is_byoyomi_mode = byoyomi > 0 && time_left < (byoyomi as f64 * 1.2) as TimePoint;
 
         // NetworkDelay2 を考慮した今回の残り時間
-        // 秒読みモードでは network_delay（短い方）を引く
+        // 秒読みモードでは network_delay（短い方）を引き、加算（inc）は当てにしない
         // 持ち時間モード/フィッシャールールでは network_delay2 を引く
         self.remain_time = if is_byoyomi_mode {
             // 秒読みモード: byoyomi + time_left から network_delay を引く
@@ -325,8 +359,16 @@ impl TimeManagement {
             (time_left + increment + byoyomi - self.network_delay2).max(100)
         };
 
+        // 秒読みのないフィッシャーは、持ち時間のうち FISCHER_PANIC_RESERVE を今回使わない
+        let fischer_reserve = if time_control == TimeControl::Fischer {
+            time_left.clamp(0, FISCHER_PANIC_RESERVE)
+        } else {
+            0
+        };
+        self.remain_time = (self.remain_time - fischer_reserve).max(100);
+
         // rtime 指定時はランダム化した固定時間を使用
-        if limits.rtime > 0 {
+        if time_control == TimeControl::RandomTime {
             let mut r = limits.rtime;
             if ply > 0 {
                 let max_rand = (r as f64 * 0.5).min(r as f64 * 10.0 / ply as f64);
@@ -336,6 +378,10 @@ impl TimeManagement {
                     r = r.saturating_add(extra);
                 }
             }
+            // 持ち時間の指定があれば、時間切れにならないよう今回使える時間で頭打ちにする
+            if time_left > 0 || byoyomi > 0 || increment > 0 {
+                r = r.min(self.remain_time);
+            }
 
             self.remain_time = r;
             self.minimum_time = r;
@@ -353,7 +399,7 @@ impl TimeManagement {
         };
 
         // 切れ負けルールか？
-        let time_forfeit = increment == 0 && byoyomi == 0;
+        let time_forfeit = time_control == TimeControl::SuddenDeath;
 
         // move_horizon の近似 (MoveHorizon = 160 をベースに補正)
         let move_horizon = calculate_move_horizon(time_forfeit, ply);
@@ -408,9 +454,23 @@ impl TimeManagement {
         self.optimum_time = t1.min(self.optimum_time);
         self.maximum_time = t2.min(self.maximum_time);
 
+        // フィッシャー: 延長の上限は inc + 貯金（予備を除いた持ち時間）の一部まで
+        if time_control == TimeControl::Fischer {
+            let bank = (time_left - fischer_reserve).max(0);
+            self.maximum_time = self.maximum_time.min(increment + bank / FISCHER_MAX_BANK_DIVISOR);
+        }
+
         // SlowMover は YaneuraOu 同様 optimum のみスケールする（秒読みの最終局面は除外）
         self.optimum_time = self.optimum_time * self.slow_mover as i64 / 100;
 
+        // PhaseTimeWeight: 中盤ほど optimum を増やす（0 なら YaneuraOu と同じ配分）
+        if self.phase_time_weight > 0
+            && let Some(phase) = self.game_phase
+        {
+            let bonus = self.phase_time_weight as f64 / 100.0 * phase.middlegame_weight();
+            self.optimum_time += (self.optimum_time as f64 * bonus) as TimePoint;
+        }
+
         // Ponder時調整（YaneuraOu準拠）
         // Ponderが有効でStochastic_Ponderが無効の場合、optimumTimeを25%増やす
         if self.usi_ponder && !self.stochastic_ponder {
@@ -640,13 +700,13 @@ impl TimeManagement {
     /// 探索開始からの経過時間（ミリ秒）
     #[inline]
     pub fn elapsed(&self) -> TimePoint {
-        self.start_time.elapsed().as_millis() as TimePoint
+        self.clock.elapsed_since(self.start_time).as_millis() as TimePoint
     }
 
     /// ponderhitからの経過時間（ミリ秒）
     #[inline]
     pub fn elapsed_from_ponderhit(&self) -> TimePoint {
-        self.ponderhit_time.elapsed().as_millis() as TimePoint
+        self.clock.elapsed_since(self.ponderhit_time).as_millis() as TimePoint
     }
 
     /// ponderhitが通知されているか
@@ -746,7 +806,7 @@ impl TimeManagement {
 
     /// ponderhit時の処理（時刻を記録）
     pub fn set_ponderhit(&mut self) {
-        self.ponderhit_time = Instant::now();
+        self.ponderhit_time = self.clock.now();
     }
 
     /// ponderhit_time までのオフセット（start_time 基準の経過ミリ秒）を取得
@@ -873,6 +933,157 @@ mod tests {
         assert_eq!(tm.search_end(), 2500, "rtime は固定時間として search_end も設定されるべき");
     }
 
+    /// ネットワーク遅延なしの時間管理（混在した時間指定の配分を数値で確かめる用）
+    fn time_manager_without_delay() -> TimeManagement {
+        let mut tm = create_time_manager();
+        tm.set_options(&TimeOptions {
+            network_delay: 0,
+            network_delay2: 0,
+            minimum_thinking_time: 2000,
+            slow_mover: 100,
+            usi_ponder: false,
+            stochastic_ponder: false,
+            phase_time_weight: 0,
+        });
+        tm
+    }
+
+    fn clock_limits(time: TimePoint, byoyomi: TimePoint, inc: TimePoint) -> LimitsType {
+        let mut limits = LimitsType::new();
+        limits.time = [time, time];
+        limits.byoyomi = [byoyomi, byoyomi];
+        limits.inc = [inc, inc];
+        limits.set_start_time();
+        limits
+    }
+
+    #[test]
+    fn test_mixed_clock_movetime_wins() {
+        let mut tm = time_manager_without_delay();
+        let mut limits = clock_limits(60_000, 10_000, 5_000);
+        limits.movetime = 700;
+        limits.rtime = 3_000;
+        tm.init(&limits, Color::Black, 40, DEFAULT_MAX_MOVES_TO_DRAW);
+        assert_eq!((tm.minimum(), tm.optimum(), tm.maximum()), (700, 700, 700));
+        assert_eq!(tm.search_end(), 700);
+    }
+
+    #[test]
+    fn test_mixed_clock_depth_disables_clock() {
+        let mut tm = time_manager_without_delay();
+        let mut limits = clock_limits(60_000, 10_000, 0);
+        limits.depth = 8;
+        tm.init(&limits, Color::Black, 40, DEFAULT_MAX_MOVES_TO_DRAW);
+        assert_eq!(tm.optimum(), TimePoint::MAX / 2);
+        assert_eq!(tm.search_end(), 0);
+    }
+
+    #[test]
+    fn test_mixed_clock_rtime_capped_by_clock() {
+        // 持ち時間なし・秒読み 1 秒で rtime 5 秒なら、秒読みの範囲で止める
+        let mut tm = time_manager_without_delay();
+        let mut limits = clock_limits(0, 1_000, 0);
+        limits.rtime = 5_000;
+        tm.init(&limits, Color::Black, 0, DEFAULT_MAX_MOVES_TO_DRAW);
+        assert_eq!(tm.maximum(), 1_000);
+        assert_eq!(tm.search_end(), 1_000);
+
+        // 十分な持ち時間があれば rtime どおり
+        let mut tm = time_manager_without_delay();
+        let mut limits = clock_limits(600_000, 10_000, 0);
+        limits.rtime = 5_000;
+        tm.init(&limits, Color::Black, 0, DEFAULT_MAX_MOVES_TO_DRAW);
+        assert_eq!(tm.maximum(), 5_000);
+    }
+
+    #[test]
+    fn test_fischer_only_never_final_push() {
+        // 持ち時間が尽きていても、秒読みなしのフィッシャーは使い切りモードにしない
+        let mut tm = time_manager_without_delay();
+        tm.init(&clock_limits(0, 0, 5_000), Color::Black, 40, DEFAULT_MAX_MOVES_TO_DRAW);
+        assert!(!tm.is_final_push());
+        assert_eq!(tm.remain_time(), 5_000);
+        assert!(tm.maximum() <= 5_000);
+    }
+
+    #[test]
+    fn test_fischer_byoyomi_allocates_with_increment_while_time_remains() {
+        let mut tm = time_manager_without_delay();
+        tm.init(
+            &clock_limits(600_000, 10_000, 5_000),
+            Color::Black,
+            40,
+            DEFAULT_MAX_MOVES_TO_DRAW,
+        );
+        assert!(!tm.is_final_push());
+        assert_eq!(tm.remain_time(), 615_000);
+
+        // 同じ持ち時間・秒読みで inc なしより配分が多い
+        let mut byoyomi_only = time_manager_without_delay();
+        byoyomi_only.init(
+            &clock_limits(600_000, 10_000, 0),
+            Color::Black,
+            40,
+            DEFAULT_MAX_MOVES_TO_DRAW,
+        );
+        assert!(tm.optimum() > byoyomi_only.optimum());
+    }
+
+    #[test]
+    fn test_fischer_byoyomi_falls_back_to_byoyomi_without_increment() {
+        // 持ち時間 3 秒 < 秒読み 10 秒 × 1.2 なら、inc を当てにせず持ち時間 + 秒読みを使い切る
+        let mut tm = time_manager_without_delay();
+        tm.init(&clock_limits(3_000, 10_000, 5_000), Color::Black, 40, DEFAULT_MAX_MOVES_TO_DRAW);
+        assert!(tm.is_final_push());
+        assert_eq!(tm.remain_time(), 13_000);
+        assert_eq!((tm.minimum(), tm.optimum(), tm.maximum()), (13_000, 13_000, 13_000));
+    }
+
+    #[test]
+    fn test_fischer_allocation_across_typical_controls() {
+        // (持ち時間, inc)：floodgate-300-10F・600 秒 + 10 秒加算・60 秒 + 1 秒加算
+        let controls = [(300_000, 10_000), (600_000, 10_000), (60_000, 1_000)];
+        for (time, inc) in controls {
+            for ply in [0, 40, 100, 200] {
+                let mut tm = create_time_manager();
+                tm.init(&clock_limits(time, 0, inc), Color::Black, ply, DEFAULT_MAX_MOVES_TO_DRAW);
+                let label = format!("{time}+{inc} ply={ply}");
+
+                // 貯金が十分なら毎手 inc 以上を使い、延長の余地も残す
+                assert!(tm.optimum() >= inc, "{label}: optimum={}", tm.optimum());
+                assert!(tm.maximum() > tm.optimum(), "{label}: maximum={}", tm.maximum());
+                // 延長しても inc + 貯金の 1/5（round_up の秒単位の切り上げを除く）まで、
+                // 予備には手を付けない
+                let bank = time - FISCHER_PANIC_RESERVE;
+                assert!(
+                    tm.maximum() <= inc + bank / FISCHER_MAX_BANK_DIVISOR + 1000,
+                    "{label}: maximum={}",
+                    tm.maximum()
+                );
+                assert!(time + inc - tm.maximum() >= FISCHER_PANIC_RESERVE, "{label}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_fischer_keeps_panic_reserve() {
+        // 持ち時間 2 秒 + 10 秒加算：持ち時間には手を付けず inc（NetworkDelay2 を引く）の範囲で指す
+        let mut tm = create_time_manager();
+        tm.init(&clock_limits(2_000, 0, 10_000), Color::Black, 80, DEFAULT_MAX_MOVES_TO_DRAW);
+        assert!(!tm.is_final_push());
+        assert_eq!(tm.remain_time(), 10_000 - DEFAULT_NETWORK_DELAY2);
+        assert!(tm.maximum() <= tm.remain_time());
+        assert!(tm.optimum() <= tm.maximum());
+    }
+
+    #[test]
+    fn test_byoyomi_controls_take_no_fischer_reserve() {
+        // floodgate-600-10（秒読み 10 秒）は秒読みが予備になるため持ち時間を差し引かない
+        let mut tm = create_time_manager();
+        tm.init(&clock_limits(600_000, 10_000, 0), Color::Black, 40, DEFAULT_MAX_MOVES_TO_DRAW);
+        assert_eq!(tm.remain_time(), 610_000 - DEFAULT_NETWORK_DELAY2);
+    }
+
     #[test]
     fn test_optimum_scales_with_ponder_option() {
         let mut base = create_time_manager();
@@ -1006,19 +1217,45 @@ mod tests {
 
     #[test]
     fn test_time_manager_elapsed() {
+        let clock = Arc::new(crate::time::ManualClock::new());
         let mut tm = create_time_manager();
         let mut limits = LimitsType::new();
         limits.time[Color::Black.index()] = 60000;
+        limits.set_clock(clock.clone());
         limits.set_start_time();
 
         tm.init(&limits, Color::Black, 0, 256);
+        assert_eq!(tm.elapsed(), 0);
+
+        clock.advance(Duration::from_millis(10));
+        assert_eq!(tm.elapsed(), 10);
+        assert_eq!(limits.elapsed(), 10);
+    }
+
+    #[test]
+    fn test_time_manager_with_manual_clock_stops_at_maximum() {
+        let clock = Arc::new(crate::time::ManualClock::new());
+        let mut tm = create_time_manager();
+        let mut limits = LimitsType::new();
+        limits.time[Color::Black.index()] = 60000;
+        limits.ponder = true;
+        limits.set_clock(clock.clone());
+        limits.set_start_time();
 
-        // 少し待つ
-        std::thread::sleep(std::time::Duration::from_millis(10));
+        tm.init(&limits, Color::Black, 0, 256);
+        let maximum = tm.maximum();
+        assert!(maximum > 0);
 
-        let elapsed = tm.elapsed();
-        assert!(elapsed >= 10);
-        assert!(elapsed < 1000);
+        // ponder 中の経過時間は ponderhit 後の持ち時間に数えない
+        clock.advance(Duration::from_millis(maximum as u64 * 2));
+        assert!(!tm.should_stop(5));
+        tm.on_ponderhit();
+        assert_eq!(tm.elapsed_from_ponderhit(), 0);
+
+        clock.advance(Duration::from_millis(maximum as u64 - 1));
+        assert!(!tm.should_stop_immediately());
+        clock.advance(Duration::from_millis(1));
+        assert!(tm.should_stop_immediately());
     }
 
     #[test]
@@ -1228,6 +1465,7 @@ mod tests {
             slow_mover: 100,
             usi_ponder: false,
             stochastic_ponder: false,
+            phase_time_weight: 0,
         });
 
         let mut limits = LimitsType::new();
@@ -1252,6 +1490,7 @@ mod tests {
             slow_mover: 100,
             usi_ponder: false,
             stochastic_ponder: false,
+            phase_time_weight: 0,
         });
 
         let mut tm_delay = create_time_manager();
@@ -1262,6 +1501,7 @@ mod tests {
             slow_mover: 100,
             usi_ponder: false,
             stochastic_ponder: false,
+            phase_time_weight: 0,
         });
 
         let mut limits = LimitsType::new();
@@ -1297,6 +1537,7 @@ mod tests {
             slow_mover: 200, // 2倍
             usi_ponder: false,
             stochastic_ponder: false,
+            phase_time_weight: 0,
         });
         tm_slow.init(&limits, Color::Black, 0, 256);
 
@@ -1307,4 +1548,36 @@ mod tests {
             tm_slow.optimum()
         );
     }
+
+    #[test]
+    fn test_phase_time_weight_extends_middlegame_optimum() {
+        let mut limits = LimitsType::new();
+        limits.time[Color::Black.index()] = 600_000;
+        limits.set_start_time();
+        let opts = TimeOptions {
+            phase_time_weight: 50,
+            ..TimeOptions::default()
+        };
+
+        let mut tm_base = create_time_manager();
+        tm_base.init(&limits, Color::Black, 40, 256);
+
+        // 重み 0 なら進行度を設定しても配分は変わらない
+        let mut tm_disabled = create_time_manager();
+        tm_disabled.set_game_phase(GamePhase::new(112));
+        tm_disabled.init(&limits, Color::Black, 40, 256);
+        assert_eq!(tm_disabled.optimum(), tm_base.optimum());
+
+        let mut tm_opening = create_time_manager();
+        tm_opening.set_options(&opts);
+        tm_opening.set_game_phase(GamePhase::new(0));
+        tm_opening.init(&limits, Color::Black, 40, 256);
+        assert_eq!(tm_opening.optimum(), tm_base.optimum());
+
+        let mut tm_middle = create_time_manager();
+        tm_middle.set_options(&opts);
+        tm_middle.set_game_phase(GamePhase::new(112));
+        tm_middle.init(&limits, Color::Black, 40, 256);
+        assert_eq!(tm_middle.optimum(), tm_base.optimum() + tm_base.optimum() / 2);
+    }
 }
@@ -10,6 +10,8 @@ pub struct TimeOptions {
     pub slow_mover: i32,
     pub usi_ponder: bool,
     pub stochastic_ponder: bool,
+    /// 中盤（`GamePhase::middlegame_weight`）で optimum を増やす割合（百分率、0 で無効）
+    pub phase_time_weight: i32,
 }
 
 // 深い探索(GPU/ネットワーク待ちが長い環境)用プリセット。
@@ -34,6 +36,7 @@ impl Default for TimeOptions {
             slow_mover: 100,
             usi_ponder: false,
             stochastic_ponder: false,
+            phase_time_weight: 0,
         }
     }
 }
@@ -48,6 +51,7 @@ impl TimeOptions {
             slow_mover: 100,
             usi_ponder: false,
             stochastic_ponder: false,
+            phase_time_weight: 0,
         }
     }
 }
@@ -607,7 +607,8 @@ impl RootMoves {
 
         for &mv in legal_moves.as_slice() {
             // search_movesが指定されていれば、その中にある手のみ
-            if search_moves.is_empty() || search_moves.contains(&mv) {
+            // （駒情報のない `Move::from_usi` の手でも一致するよう 16bit 部分で比べる）
+            if search_moves.is_empty() || search_moves.iter().any(|m| m.to_u16() == mv.to_u16()) {
                 moves.push(RootMove::new(mv));
             }
         }
@@ -0,0 +1,361 @@
+//! 標準テスト局面集
+//!
+//! ベンチマーク・戦術テスト・評価関数の確認・CI が同じ局面を参照できるよう、
+//! タグと期待結果付きの局面集をクレートに埋め込む。ツールごとに YaneuraOu の
+//! ベンチマーク 4 局面などを写し持つ代わりに、ここから取り出して使う。
+//!
+//! ```
+//! use rshogi_core::testpos::{self, Tag};
+//!
+//! for tp in testpos::by_tag(Tag::Bench) {
+//!     let pos = tp.position();
+//!     assert_eq!(pos.to_sfen(), tp.sfen);
+//! }
+//! ```
+//!
+//! `to_epd()` は `tools::tactics` のスイート形式（`<sfen>; bm ...; id "..."`）を返す。
+
+use std::fmt;
+
+use crate::position::Position;
+
+/// 局面の分類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Tag {
+    /// ベンチマーク用（YaneuraOu 準拠の 4 局面）
+    Bench,
+    /// 詰みのある局面
+    Mate,
+    /// 指せる手が限られ、手を渡せない局面
+    Zugzwang,
+    /// 入玉・宣言勝ちの判定
+    Nyugyoku,
+    /// 持ち駒を打つ手筋（打ち歩詰めの禁止を含む）
+    DropTactics,
+}
+
+impl Tag {
+    pub const ALL: [Tag; 5] = [
+        Tag::Bench,
+        Tag::Mate,
+        Tag::Zugzwang,
+        Tag::Nyugyoku,
+        Tag::DropTactics,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Tag::Bench => "bench",
+            Tag::Mate => "mate",
+            Tag::Zugzwang => "zugzwang",
+            Tag::Nyugyoku => "nyugyoku",
+            Tag::DropTactics => "drop",
+        }
+    }
+
+    /// `as_str()` の逆変換
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|tag| tag.as_str() == name)
+    }
+}
+
+impl fmt::Display for Tag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// 局面の期待結果（指し手は USI 形式）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Expected {
+    /// 期待結果なし（ベンチマーク用）
+    None,
+    /// 手番側が `plies` 手（奇数）で詰ます。初手は `first_moves` のいずれか
+    ///
+    /// 途中の手は王手でなくてもよい（詰将棋ではなく、最短で勝つ手の集合）。
+    Mate {
+        plies: u32,
+        first_moves: &'static [&'static str],
+    },
+    /// いずれかを指すのが正解
+    BestMove(&'static [&'static str]),
+    /// いずれも指してはいけない
+    AvoidMove(&'static [&'static str]),
+    /// 手番側が入玉宣言できるか（`EnteringKingRule::Point27` で判定）
+    Declaration(bool),
+}
+
+/// タグと期待結果付きのテスト局面
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TestPosition {
+    /// 局面名（局面集の中で一意）
+    pub id: &'static str,
+    /// 局面（SFEN）
+    pub sfen: &'static str,
+    pub tags: &'static [Tag],
+    pub expected: Expected,
+    /// 局面の説明
+    pub note: &'static str,
+}
+
+impl TestPosition {
+    pub fn has_tag(&self, tag: Tag) -> bool {
+        self.tags.contains(&tag)
+    }
+
+    /// 局面を組み立てる（局面集の SFEN はテストで検証済み）
+    pub fn position(&self) -> Position {
+        let mut pos = Position::new();
+        pos.set_sfen(self.sfen)
+            .unwrap_or_else(|e| panic!("invalid SFEN in test position {}: {e}", self.id));
+        pos
+    }
+
+    /// 戦術テストスイート形式の 1 行
+    ///
+    /// 期待手（`bm`）・回避手（`am`）が無い局面では SFEN と `id` だけになる。
+    pub fn to_epd(self) -> String {
+        let mut line = self.sfen.to_string();
+        match self.expected {
+            Expected::Mate { first_moves, .. } | Expected::BestMove(first_moves) => {
+                line.push_str("; bm ");
+                line.push_str(&first_moves.join(" "));
+            }
+            Expected::AvoidMove(moves) => {
+                line.push_str("; am ");
+                line.push_str(&moves.join(" "));
+            }
+            Expected::Declaration(true) => line.push_str("; bm win"),
+            Expected::Declaration(false) | Expected::None => {}
+        }
+        line.push_str(&format!("; id \"{}\"", self.id));
+        line
+    }
+}
+
+/// 埋め込みの局面集
+pub const TEST_POSITIONS: &[TestPosition] = &[
+    // --- ベンチマーク（YaneuraOu 準拠） ---
+    TestPosition {
+        id: "hirate-like",
+        sfen: "lnsgkgsnl/1r7/p1ppp1bpp/1p3pp2/7P1/2P6/PP1PPPP1P/1B3S1R1/LNSGKG1NL b - 9",
+        tags: &[Tag::Bench],
+        expected: Expected::None,
+        note: "初期局面に近い局面",
+    },
+    TestPosition {
+        id: "complex-middle",
+        sfen: "l4S2l/4g1gs1/5p1p1/pr2N1pkp/4Gn3/PP3PPPP/2GPP4/1K7/L3r+s2L w BS2N5Pb 1",
+        tags: &[Tag::Bench],
+        expected: Expected::None,
+        note: "読めば読むほど後手悪いような局面",
+    },
+    TestPosition {
+        id: "tactical",
+        sfen: "6n1l/2+S1k4/2lp4p/1np1B2b1/3PP4/1N1S3rP/1P2+pPP+p1/1p1G5/3KG2r1 b GSN2L4Pgs2p 1",
+        tags: &[Tag::Bench],
+        expected: Expected::None,
+        note: "57同銀は詰み、みたいな。読めば読むほど先手が悪いことがわかってくる局面",
+    },
+    TestPosition {
+        id: "movegen-heavy",
+        sfen: "l6nl/5+P1gk/2np1S3/p1p4Pp/3P2Sp1/1PPb2P1P/P5GS1/R8/LN4bKL w RGgsn5p 1",
+        tags: &[Tag::Bench],
+        expected: Expected::None,
+        note: "指し手生成祭りの局面 (cf. http://d.hatena.ne.jp/ak11/20110508/p1)",
+    },
+    // --- 詰み ---
+    TestPosition {
+        id: "mate1-head-gold",
+        sfen: "4k4/9/4P4/9/9/9/9/9/4K4 b G 1",
+        tags: &[Tag::Mate, Tag::DropTactics],
+        expected: Expected::Mate {
+            plies: 1,
+            first_moves: &["G*5b"],
+        },
+        note: "歩に支えられた頭金",
+    },
+    TestPosition {
+        id: "mate3-gold-drop",
+        sfen: "9/6Sk1/9/9/9/9/9/9/4K4 b RG 1",
+        tags: &[Tag::Mate, Tag::DropTactics],
+        expected: Expected::Mate {
+            plies: 3,
+            first_moves: &["G*2c"],
+        },
+        note: "金打ちから始まる 3 手詰め",
+    },
+    TestPosition {
+        id: "mate3-dragon",
+        sfen: "7k1/4pl3/6+R2/9/9/9/9/9/4K4 b 2S 1",
+        tags: &[Tag::Mate],
+        expected: Expected::Mate {
+            plies: 3,
+            first_moves: &["3c2c", "3c4b", "S*2b"],
+        },
+        note: "龍と銀 2 枚の 3 手詰め（3c4b は王手でない必至）",
+    },
+    // --- 打つ手筋 ---
+    TestPosition {
+        id: "uchifuzume",
+        sfen: "8k/6S2/7G1/9/9/9/9/9/4K4 b P 1",
+        tags: &[Tag::DropTactics],
+        expected: Expected::AvoidMove(&["P*1b"]),
+        note: "1二歩打は詰むが打ち歩詰めで指せない（1 手詰めなし）",
+    },
+    TestPosition {
+        id: "knight-fork",
+        sfen: "4k1r2/9/9/9/9/9/9/9/4K4 b N 1",
+        tags: &[Tag::DropTactics],
+        expected: Expected::BestMove(&["N*4c"]),
+        note: "王手飛車の桂打ち",
+    },
+    // --- 手を渡せない局面 ---
+    TestPosition {
+        id: "only-move",
+        sfen: "k6r1/9/9/9/4Pb3/9/9/9/8K b - 1",
+        tags: &[Tag::Zugzwang],
+        expected: Expected::BestMove(&["5e5d"]),
+        note: "玉が動けず、合法手は歩を突く 1 手だけ",
+    },
+    // --- 入玉 ---
+    TestPosition {
+        id: "declaration-win",
+        sfen: "KGG6/SS7/PPPPPP3/9/9/9/2pppppp1/1ss1gg1nl/4k2nl b 2R2B3p 1",
+        tags: &[Tag::Nyugyoku],
+        expected: Expected::Declaration(true),
+        note: "27 点法で先手が宣言勝ちできる",
+    },
+    TestPosition {
+        id: "declaration-short",
+        sfen: "KGG6/SS7/PPPPPP3/9/9/9/2pppppp1/1ss1gg1nl/4k2nl b R2Br3p 1",
+        tags: &[Tag::Nyugyoku],
+        expected: Expected::Declaration(false),
+        note: "入玉しているが点数が足りず宣言できない",
+    },
+];
+
+/// 局面集全体
+pub fn all() -> &'static [TestPosition] {
+    TEST_POSITIONS
+}
+
+/// `tag` の付いた局面
+pub fn by_tag(tag: Tag) -> impl Iterator<Item = &'static TestPosition> {
+    TEST_POSITIONS.iter().filter(move |tp| tp.has_tag(tag))
+}
+
+/// `id` の局面
+pub fn find(id: &str) -> Option<&'static TestPosition> {
+    TEST_POSITIONS.iter().find(|tp| tp.id == id)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::movegen::{MoveList, generate_legal};
+    use crate::types::{EnteringKingRule, Move};
+
+    fn legal_moves(pos: &Position) -> Vec<Move> {
+        let mut list = MoveList::new();
+        generate_legal(pos, &mut list);
+        list.iter().copied().collect()
+    }
+
+    /// `plies` 手以内に詰ませる初手を列挙する（総当たり）
+    ///
+    /// 最終手だけは王手に限る。途中は王手でない手（必至をかける手）も含める。
+    fn mating_first_moves(pos: &mut Position, plies: u32) -> Vec<String> {
+        legal_moves(pos)
+            .into_iter()
+            .filter(|&m| (plies > 1 || pos.gives_check(m)) && mates_after(pos, m, plies))
+            .map(|m| m.to_usi())
+            .collect()
+    }
+
+    fn mates_after(pos: &mut Position, m: Move, plies: u32) -> bool {
+        let gives_check = pos.gives_check(m);
+        pos.do_move(m, gives_check);
+        let replies = legal_moves(pos);
+        let mated = if replies.is_empty() {
+            gives_check
+        } else {
+            plies > 1
+                && replies.into_iter().all(|r| {
+                    let gives_check = pos.gives_check(r);
+                    pos.do_move(r, gives_check);
+                    let mated = !mating_first_moves(pos, plies - 2).is_empty();
+                    pos.undo_move(r);
+                    mated
+                })
+        };
+        pos.undo_move(m);
+        mated
+    }
+
+    #[test]
+    fn ids_are_unique_and_sfens_round_trip() {
+        let mut ids = HashSet::new();
+        for tp in all() {
+            assert!(ids.insert(tp.id), "duplicate id {}", tp.id);
+            assert!(!tp.tags.is_empty(), "{} has no tags", tp.id);
+            assert_eq!(tp.position().to_sfen(), tp.sfen, "{}", tp.id);
+        }
+        assert_eq!(by_tag(Tag::Bench).count(), 4);
+        for tag in Tag::ALL {
+            assert_eq!(Tag::from_name(tag.as_str()), Some(tag));
+            assert!(by_tag(tag).next().is_some(), "no positions tagged {tag}");
+        }
+    }
+
+    #[test]
+    fn expected_outcomes_hold() {
+        for tp in all() {
+            let mut pos = tp.position();
+            let legal: Vec<String> = legal_moves(&pos).iter().map(|m| m.to_usi()).collect();
+            match tp.expected {
+                Expected::None => {}
+                Expected::Mate { plies, first_moves } => {
+                    let shorter = plies.checked_sub(2).map_or(Vec::new(), |p| {
+                        (1..=p).step_by(2).flat_map(|p| mating_first_moves(&mut pos, p)).collect()
+                    });
+                    assert!(shorter.is_empty(), "{}: shorter mate {shorter:?}", tp.id);
+                    assert_eq!(mating_first_moves(&mut pos, plies), first_moves, "{}", tp.id);
+                }
+                Expected::BestMove(moves) => {
+                    for m in moves {
+                        assert!(legal.iter().any(|l| l == m), "{}: {m} is not legal", tp.id);
+                    }
+                }
+                Expected::AvoidMove(_) => {}
+                Expected::Declaration(win) => {
+                    let declared = pos.declaration_win(EnteringKingRule::Point27) == Move::WIN;
+                    assert_eq!(declared, win, "{}", tp.id);
+                }
+            }
+        }
+
+        let only_move = find("only-move").unwrap();
+        assert_eq!(legal_moves(&only_move.position()).len(), 1);
+
+        // 打ち歩詰めは合法手に含まれず、他に 1 手詰めもない
+        let mut pos = find("uchifuzume").unwrap().position();
+        assert!(legal_moves(&pos).iter().all(|m| m.to_usi() != "P*1b"));
+        assert!(mating_first_moves(&mut pos, 1).is_empty());
+    }
+
+    #[test]
+    fn to_epd_lists_expected_moves() {
+        assert_eq!(
+            find("mate3-dragon").unwrap().to_epd(),
+            "7k1/4pl3/6+R2/9/9/9/9/9/4K4 b 2S 1; bm 3c2c 3c4b S*2b; id \"mate3-dragon\""
+        );
+        assert_eq!(
+            find("uchifuzume").unwrap().to_epd(),
+            "8k/6S2/7G1/9/9/9/9/9/4K4 b P 1; am P*1b; id \"uchifuzume\""
+        );
+        assert!(find("hirate-like").unwrap().to_epd().ends_with("b - 9; id \"hirate-like\""));
+    }
+}
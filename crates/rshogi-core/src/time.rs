@@ -1,5 +1,111 @@
+//! 時刻の抽象化
+//!
+//! 探索の時間管理は [`Clock`] 経由で現在時刻を得る。通常は [`SystemClock`] を使い、
+//! テストでは [`ManualClock`] を [`LimitsType::set_clock`](crate::search::LimitsType::set_clock)
+//! で差し込むことで、実時間を待たずに時間切れ・秒読みの挙動を再現できる。
+//!
+//! `Instant` は wasm32-unknown-unknown では `web_time`（`performance.now()` ベース）、
+//! それ以外では `std::time::Instant`。
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
 #[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
 pub use web_time::Instant;
 
 #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
 pub use std::time::Instant;
+
+/// 現在時刻の供給元
+pub trait Clock: Send + Sync {
+    /// 現在時刻
+    fn now(&self) -> Instant;
+
+    /// `since` からの経過時間（`since` が未来なら 0）
+    fn elapsed_since(&self, since: Instant) -> Duration {
+        self.now().saturating_duration_since(since)
+    }
+}
+
+/// スレッド間で共有する時計
+pub type SharedClock = Arc<dyn Clock>;
+
+/// 実時間の時計
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    #[inline]
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// 既定の時計（[`SystemClock`]）
+pub fn system_clock() -> SharedClock {
+    Arc::new(SystemClock)
+}
+
+/// 手動で進める時計（テスト・シミュレーション用）
+///
+/// 作成時刻で止まっており、[`advance`](Self::advance) を呼んだ分だけ進む。
+/// 探索スレッドから読まれている最中に別スレッドから進めてもよい。
+#[derive(Debug)]
+pub struct ManualClock {
+    origin: Instant,
+    offset_us: AtomicU64,
+}
+
+impl ManualClock {
+    /// 現在の実時刻を起点とする時計を作る
+    pub fn new() -> Self {
+        Self {
+            origin: Instant::now(),
+            offset_us: AtomicU64::new(0),
+        }
+    }
+
+    /// 時計を `d` だけ進める
+    pub fn advance(&self, d: Duration) {
+        let us = u64::try_from(d.as_micros()).unwrap_or(u64::MAX);
+        self.offset_us.fetch_add(us, Ordering::Relaxed);
+    }
+
+    /// 作成時からの経過時間
+    pub fn offset(&self) -> Duration {
+        Duration::from_micros(self.offset_us.load(Ordering::Relaxed))
+    }
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        self.origin + self.offset()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn manual_clock_moves_only_when_advanced() {
+        let clock = ManualClock::new();
+        let start = clock.now();
+        assert_eq!(clock.elapsed_since(start), Duration::ZERO);
+
+        clock.advance(Duration::from_millis(1500));
+        assert_eq!(clock.elapsed_since(start), Duration::from_millis(1500));
+        assert_eq!(clock.offset(), Duration::from_millis(1500));
+
+        // 未来の時刻からの経過は 0
+        let future = start + Duration::from_secs(10);
+        assert_eq!(clock.elapsed_since(future), Duration::ZERO);
+    }
+}
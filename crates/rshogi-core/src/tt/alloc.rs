@@ -1,4 +1,21 @@
+//! 置換表のメモリ確保（Large Pages / HugeTLB / NUMA interleave）
+//!
+//! `LargePages` オプションで確保方法を選び、確保できなければ通常のページに落とす。
+//! 実際に使われた方法は [`TtAllocInfo`] で返し、USI 側が `info string` で報告する。
+//!
+//! | 指定 | Linux | Windows | その他 |
+//! |---|---|---|---|
+//! | `off` | 通常のページ | 通常のページ | 通常のページ |
+//! | `auto` | THP のヒント（madvise） | Large Pages を試す | 通常のページ |
+//! | `2MB` / `1GB` | HugeTLB（`mmap(MAP_HUGETLB)`）を試し、だめなら `auto` | Large Pages を試す | 通常のページ |
+//!
+//! HugeTLB は事前に `vm.nr_hugepages`（1GB は `hugepagesz=1G` の起動時確保）で予約した
+//! ページから取るため、予約が足りなければ `auto` に落ちる。
+//! NUMA interleave（Linux のみ）はページに触れる前に `mbind(MPOL_INTERLEAVE)` で
+//! オンラインの全ノードに分散させる。ノードが 1 つなら何もしない。
+
 use std::alloc::Layout;
+use std::fmt;
 use std::ptr::NonNull;
 
 #[cfg(not(windows))]
@@ -23,36 +40,149 @@ use windows_sys::Win32::System::Memory::{
 #[cfg(windows)]
 use windows_sys::Win32::System::Threading::{GetCurrentProcess, OpenProcessToken};
 
+/// 置換表のページの確保方法（USI の `LargePages` オプション）
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LargePages {
+    /// 通常のページ
+    Off,
+    /// Linux は THP のヒント、Windows は Large Pages を試す（既定）
+    #[default]
+    Auto,
+    /// 2MB の HugeTLB ページを試す
+    Huge2Mb,
+    /// 1GB の HugeTLB ページを試す
+    Huge1Gb,
+}
+
+impl LargePages {
+    /// USI オプションの値
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LargePages::Off => "off",
+            LargePages::Auto => "auto",
+            LargePages::Huge2Mb => "2MB",
+            LargePages::Huge1Gb => "1GB",
+        }
+    }
+
+    /// USI オプションの値から変換（大文字小文字は区別しない）
+    pub fn from_usi(s: &str) -> Option<Self> {
+        [
+            LargePages::Off,
+            LargePages::Auto,
+            LargePages::Huge2Mb,
+            LargePages::Huge1Gb,
+        ]
+        .into_iter()
+        .find(|mode| mode.as_str().eq_ignore_ascii_case(s))
+    }
+}
+
+/// 置換表の確保の指定
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TtAllocOptions {
+    pub large_pages: LargePages,
+    /// NUMA ノードにページを分散させる（Linux のみ）
+    pub numa_interleave: bool,
+}
+
+/// 実際に使われたページ
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
-pub(super) enum AllocKind {
-    LargePages,
-    /// Windows で Large Pages 確保失敗時のフォールバック、
-    /// または macOS 等の Large Pages 未対応環境で使用
-    #[allow(dead_code)]
+pub enum PageKind {
+    /// 通常のページ
     Regular,
+    /// Transparent Huge Pages のヒント付き（実際に huge page になるかはカーネル次第）
+    TransparentHuge,
+    /// Windows の Large Pages
+    LargePages,
+    /// 2MB の HugeTLB ページ
+    HugeTlb2Mb,
+    /// 1GB の HugeTLB ページ
+    HugeTlb1Gb,
+}
+
+impl PageKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            PageKind::Regular => "regular",
+            PageKind::TransparentHuge => "transparent huge pages",
+            PageKind::LargePages => "large pages",
+            PageKind::HugeTlb2Mb => "2MB hugetlb",
+            PageKind::HugeTlb1Gb => "1GB hugetlb",
+        }
+    }
+}
+
+impl fmt::Display for PageKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// 置換表の確保結果
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TtAllocInfo {
+    /// 指定された確保方法
+    pub requested: TtAllocOptions,
+    /// 実際に使われたページ
+    pub pages: PageKind,
+    /// interleave したノード数（interleave していなければ `None`）
+    pub numa_nodes: Option<usize>,
+}
+
+impl TtAllocInfo {
+    /// 指定どおりに確保できたか（`auto` は何に落ちても指定どおりとみなす）
+    pub fn fell_back(&self) -> bool {
+        let pages_ok = match self.requested.large_pages {
+            LargePages::Off | LargePages::Auto => true,
+            LargePages::Huge2Mb => {
+                matches!(self.pages, PageKind::HugeTlb2Mb | PageKind::LargePages)
+            }
+            LargePages::Huge1Gb => {
+                matches!(self.pages, PageKind::HugeTlb1Gb | PageKind::LargePages)
+            }
+        };
+        !pages_ok || (self.requested.numa_interleave && self.numa_nodes.is_none())
+    }
+}
+
+/// 解放方法
+#[cfg(not(windows))]
+enum Backing {
+    /// グローバルアロケータ
+    Heap(Layout),
+    /// mmap（HugeTLB）。長さはページサイズに切り上げたもの
+    #[cfg(target_os = "linux")]
+    Mmap(usize),
 }
 
 pub(super) struct Allocation {
     ptr: NonNull<u8>,
-    kind: AllocKind,
+    info: TtAllocInfo,
     #[cfg(not(windows))]
-    layout: Layout,
+    backing: Backing,
 }
 
 impl Allocation {
-    pub(super) fn allocate(size: usize, alignment: usize) -> Self {
+    pub(super) fn allocate(size: usize, alignment: usize, options: TtAllocOptions) -> Self {
         #[cfg(windows)]
         {
             debug_assert!(alignment.is_power_of_two(), "alignment must be power of two");
-            if let Some(alloc) = try_alloc_large_pages(size) {
+            if options.large_pages != LargePages::Off
+                && let Some(alloc) = try_alloc_large_pages(size, options)
+            {
                 return alloc;
             }
-            alloc_windows(size, alignment)
+            alloc_windows(size, alignment, options)
         }
 
         #[cfg(not(windows))]
         {
-            alloc_unix(size, alignment)
+            #[cfg(target_os = "linux")]
+            if let Some(alloc) = try_alloc_hugetlb(size, options) {
+                return alloc;
+            }
+            alloc_unix(size, alignment, options)
         }
     }
 
@@ -60,8 +190,8 @@ impl Allocation {
         self.ptr
     }
 
-    pub(super) fn kind(&self) -> AllocKind {
-        self.kind
+    pub(super) fn info(&self) -> TtAllocInfo {
+        self.info
     }
 }
 
@@ -77,7 +207,7 @@ fn align_up(value: usize, align: usize) -> usize {
 }
 
 #[cfg(windows)]
-fn try_alloc_large_pages(size: usize) -> Option<Allocation> {
+fn try_alloc_large_pages(size: usize, options: TtAllocOptions) -> Option<Allocation> {
     unsafe {
         let large_page_size = GetLargePageMinimum() as usize;
         if large_page_size == 0 {
@@ -145,13 +275,17 @@ fn try_alloc_large_pages(size: usize) -> Option<Allocation> {
         let ptr = NonNull::new(ptr as *mut u8)?;
         Some(Allocation {
             ptr,
-            kind: AllocKind::LargePages,
+            info: TtAllocInfo {
+                requested: options,
+                pages: PageKind::LargePages,
+                numa_nodes: None,
+            },
         })
     }
 }
 
 #[cfg(windows)]
-fn alloc_windows(size: usize, alignment: usize) -> Allocation {
+fn alloc_windows(size: usize, alignment: usize, options: TtAllocOptions) -> Allocation {
     unsafe {
         let ptr =
             VirtualAlloc(std::ptr::null_mut(), size, MEM_RESERVE | MEM_COMMIT, PAGE_READWRITE);
@@ -161,17 +295,111 @@ fn alloc_windows(size: usize, alignment: usize) -> Allocation {
         });
         Allocation {
             ptr,
-            kind: AllocKind::Regular,
+            info: TtAllocInfo {
+                requested: options,
+                pages: PageKind::Regular,
+                numa_nodes: None,
+            },
+        }
+    }
+}
+
+/// HugeTLB ページを mmap で確保する（予約が足りなければ `None`）
+#[cfg(target_os = "linux")]
+fn try_alloc_hugetlb(size: usize, options: TtAllocOptions) -> Option<Allocation> {
+    let (page_size, flag, pages) = match options.large_pages {
+        LargePages::Huge2Mb => (2 << 20, libc::MAP_HUGE_2MB, PageKind::HugeTlb2Mb),
+        LargePages::Huge1Gb => (1 << 30, libc::MAP_HUGE_1GB, PageKind::HugeTlb1Gb),
+        LargePages::Off | LargePages::Auto => return None,
+    };
+    let len = size.div_ceil(page_size) * page_size;
+    // SAFETY: 固定アドレスを指定しない匿名マッピング
+    let ptr = unsafe {
+        libc::mmap(
+            std::ptr::null_mut(),
+            len,
+            libc::PROT_READ | libc::PROT_WRITE,
+            libc::MAP_PRIVATE | libc::MAP_ANONYMOUS | libc::MAP_HUGETLB | flag,
+            -1,
+            0,
+        )
+    };
+    if ptr == libc::MAP_FAILED {
+        return None;
+    }
+    let ptr = NonNull::new(ptr as *mut u8)?;
+    let numa_nodes = options.numa_interleave.then(|| interleave_numa(ptr, len)).flatten();
+    Some(Allocation {
+        ptr,
+        info: TtAllocInfo {
+            requested: options,
+            pages,
+            numa_nodes,
+        },
+        backing: Backing::Mmap(len),
+    })
+}
+
+/// まだ触れていない領域をオンラインの全 NUMA ノードに interleave する
+///
+/// ノードが 1 つしかない、または mbind に失敗した場合は `None`。
+#[cfg(target_os = "linux")]
+fn interleave_numa(ptr: NonNull<u8>, len: usize) -> Option<usize> {
+    let online = std::fs::read_to_string("/sys/devices/system/node/online").ok()?;
+    let nodes = parse_node_list(online.trim())?;
+    if nodes.len() < 2 {
+        return None;
+    }
+    let max_node = *nodes.iter().max()?;
+    let mut mask = vec![0u64; max_node / 64 + 1];
+    for &node in &nodes {
+        mask[node / 64] |= 1 << (node % 64);
+    }
+    // SAFETY: ptr..ptr+len は確保済みでページ境界に揃っている。mask は maxnode ビット分ある
+    let result = unsafe {
+        libc::syscall(
+            libc::SYS_mbind,
+            ptr.as_ptr(),
+            len,
+            libc::MPOL_INTERLEAVE,
+            mask.as_ptr(),
+            // カーネルは maxnode - 1 ビットを読む
+            mask.len() * 64 + 1,
+            0,
+        )
+    };
+    (result == 0).then_some(nodes.len())
+}
+
+/// `/sys/devices/system/node/online` 形式（`0-3,5`）のノード番号の一覧
+#[cfg(any(target_os = "linux", test))]
+fn parse_node_list(s: &str) -> Option<Vec<usize>> {
+    let mut nodes = Vec::new();
+    for part in s.split(',').filter(|part| !part.is_empty()) {
+        match part.split_once('-') {
+            Some((first, last)) => {
+                let (first, last) = (first.parse::<usize>().ok()?, last.parse::<usize>().ok()?);
+                if first > last || last >= 4096 {
+                    return None;
+                }
+                nodes.extend(first..=last);
+            }
+            None => nodes.push(part.parse().ok().filter(|&node| node < 4096)?),
         }
     }
+    Some(nodes)
 }
 
 #[cfg(not(windows))]
-fn alloc_unix(size: usize, alignment: usize) -> Allocation {
-    #[cfg(any(target_os = "linux", target_os = "android"))]
-    let (page_align, kind) = (2 * 1024 * 1024, AllocKind::LargePages);
-    #[cfg(not(any(target_os = "linux", target_os = "android")))]
-    let (page_align, kind) = (4096, AllocKind::Regular);
+fn alloc_unix(size: usize, alignment: usize, options: TtAllocOptions) -> Allocation {
+    let thp = cfg!(any(target_os = "linux", target_os = "android"))
+        && options.large_pages != LargePages::Off;
+    // THP のヒントを付けるときは 2MB 境界に揃える
+    let (page_align, pages) = if thp {
+        (2 * 1024 * 1024, PageKind::TransparentHuge)
+    } else {
+        (4096, PageKind::Regular)
+    };
 
     let alignment = max(alignment, page_align);
     let layout = Layout::from_size_align(size, alignment)
@@ -183,8 +411,9 @@ fn alloc_unix(size: usize, alignment: usize) -> Allocation {
     }
 
     #[cfg(any(target_os = "linux", target_os = "android"))]
-    unsafe {
-        let result = libc::madvise(ptr as *mut _, layout.size(), libc::MADV_HUGEPAGE);
+    if thp {
+        // SAFETY: ptr..ptr+layout.size() は確保済み
+        let result = unsafe { libc::madvise(ptr as *mut _, layout.size(), libc::MADV_HUGEPAGE) };
         // madvise失敗は動作に影響しないが、パフォーマンスに影響する可能性があるため
         // デバッグビルドでは警告を出力
         #[cfg(debug_assertions)]
@@ -195,10 +424,20 @@ fn alloc_unix(size: usize, alignment: usize) -> Allocation {
         let _ = result;
     }
 
+    let ptr = NonNull::new(ptr).expect("TT allocation returned null");
+    // 大きな確保はグローバルアロケータも mmap するため、まだページに触れていない
+    #[cfg(target_os = "linux")]
+    let numa_nodes = options.numa_interleave.then(|| interleave_numa(ptr, layout.size())).flatten();
+    #[cfg(not(target_os = "linux"))]
+    let numa_nodes = None;
     Allocation {
-        ptr: NonNull::new(ptr).expect("TT allocation returned null"),
-        kind,
-        layout,
+        ptr,
+        info: TtAllocInfo {
+            requested: options,
+            pages,
+            numa_nodes,
+        },
+        backing: Backing::Heap(layout),
     }
 }
 
@@ -215,8 +454,12 @@ impl Drop for Allocation {
                 }
             }
             #[cfg(not(windows))]
-            {
-                dealloc(self.ptr.as_ptr(), self.layout);
+            match self.backing {
+                Backing::Heap(layout) => dealloc(self.ptr.as_ptr(), layout),
+                #[cfg(target_os = "linux")]
+                Backing::Mmap(len) => {
+                    libc::munmap(self.ptr.as_ptr() as *mut _, len);
+                }
             }
         }
     }
@@ -225,3 +468,50 @@ impl Drop for Allocation {
 // SAFETY: Allocation owns raw memory for the TT and is protected by higher-level synchronization.
 unsafe impl Send for Allocation {}
 unsafe impl Sync for Allocation {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn large_pages_option_round_trips() {
+        for mode in [
+            LargePages::Off,
+            LargePages::Auto,
+            LargePages::Huge2Mb,
+            LargePages::Huge1Gb,
+        ] {
+            assert_eq!(LargePages::from_usi(mode.as_str()), Some(mode));
+        }
+        assert_eq!(LargePages::from_usi("2mb"), Some(LargePages::Huge2Mb));
+        assert_eq!(LargePages::from_usi("4MB"), None);
+    }
+
+    #[test]
+    fn parse_node_list_expands_ranges() {
+        assert_eq!(parse_node_list("0"), Some(vec![0]));
+        assert_eq!(parse_node_list("0-3,5"), Some(vec![0, 1, 2, 3, 5]));
+        assert_eq!(parse_node_list("3-1"), None);
+        assert_eq!(parse_node_list("x"), None);
+    }
+
+    #[test]
+    fn allocation_falls_back_to_usable_memory() {
+        // HugeTLB の予約がない環境でも確保でき、書き込める
+        for large_pages in [LargePages::Off, LargePages::Auto, LargePages::Huge2Mb] {
+            let options = TtAllocOptions {
+                large_pages,
+                numa_interleave: true,
+            };
+            let size = 4 << 20;
+            let alloc = Allocation::allocate(size, 32, options);
+            // SAFETY: size バイト確保済み
+            unsafe { std::ptr::write_bytes(alloc.ptr().as_ptr(), 0xab, size) };
+            let info = alloc.info();
+            assert_eq!(info.requested, options);
+            if large_pages == LargePages::Off {
+                assert_eq!(info.pages, PageKind::Regular);
+            }
+        }
+    }
+}
@@ -31,8 +31,11 @@ pub struct TTEntry {
     eval16: i16,
 }
 
+/// ファイル保存時のエントリのバイト数
+pub(super) const ENTRY_BYTES: usize = 10;
+
 // エントリサイズが10バイトであることを保証
-const _: () = assert!(std::mem::size_of::<TTEntry>() == 10);
+const _: () = assert!(std::mem::size_of::<TTEntry>() == ENTRY_BYTES);
 
 impl TTEntry {
     /// 新しい空のエントリを作成
@@ -132,6 +135,30 @@ impl TTEntry {
         }
     }
 
+    /// ファイル保存用のバイト列（リトルエンディアン）
+    pub(super) fn to_le_bytes(self) -> [u8; ENTRY_BYTES] {
+        let mut bytes = [0u8; ENTRY_BYTES];
+        bytes[0..2].copy_from_slice(&self.key16.to_le_bytes());
+        bytes[2] = self.depth8;
+        bytes[3] = self.gen_bound8;
+        bytes[4..6].copy_from_slice(&self.move16.to_le_bytes());
+        bytes[6..8].copy_from_slice(&self.value16.to_le_bytes());
+        bytes[8..10].copy_from_slice(&self.eval16.to_le_bytes());
+        bytes
+    }
+
+    /// [`to_le_bytes`](Self::to_le_bytes) の逆変換
+    pub(super) fn from_le_bytes(bytes: [u8; ENTRY_BYTES]) -> Self {
+        Self {
+            key16: u16::from_le_bytes([bytes[0], bytes[1]]),
+            depth8: bytes[2],
+            gen_bound8: bytes[3],
+            move16: u16::from_le_bytes([bytes[4], bytes[5]]),
+            value16: i16::from_le_bytes([bytes[6], bytes[7]]),
+            eval16: i16::from_le_bytes([bytes[8], bytes[9]]),
+        }
+    }
+
     /// 相対的な世代（0 = 最新）
     #[inline]
     pub fn relative_age(&self, generation8: u8) -> u8 {
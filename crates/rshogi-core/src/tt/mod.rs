@@ -7,18 +7,47 @@
 //! - `TranspositionTable`: テーブル本体
 //! - 世代管理
 //! - prefetch
+//! - Large Pages / HugeTLB / NUMA interleave での確保（`alloc`）
+//! - ファイルへの保存・読み込み（`persist`）
+//! - 置換方針の調整用の統計（`stats`）
 //!
 //! # YaneuraOu（CLUSTER_SIZE=3）準拠
 //!
 //! クラスターインデックスは64bitキーの上位ビットで決定し、
 //! クラスター内マッチングに下位16bitを使用する。
 //! 10バイトエントリ × 3 + 2パディング = 32バイト/クラスター。
+//!
+//! # 並列アクセス
+//!
+//! 探索スレッドはロックも atomic 命令も使わずにエントリを読み書きする（YaneuraOu /
+//! Stockfish と同じ）。別スレッドの書き込みと重なって読んだエントリは、キーと中身が
+//! 別の局面のものになり得るが、次の二段で実害を防ぐ。
+//!
+//! - クラスター内では 16bit キーの一致を確かめる
+//! - 指し手は `Position::to_move` で合法性を確かめ、不正なら一致しなかったものとして扱う
+//!
+//! 評価値・深さが食い違っても探索の精度が一時的に落ちるだけで、不正な指し手や
+//! 範囲外アクセスにはならない。XOR キーや 128bit atomic でエントリの一貫性を保証する
+//! 方式は、エントリが 16 バイトになりクラスターに 2 つしか入らないため採らない。
+//!
+//! # 置換方針と世代
+//!
+//! 世代は `new_search` ごとに [`GENERATION_DELTA`] ずつ進め、エントリには書き込み時の世代を
+//! 5bit で持つ。probe で一致するエントリがなければ、クラスター内で
+//! 「深さ − 相対的な世代」が最小のものを書き込み先にする（古く浅いものから追い出す）。
+//! 同じ局面への書き込みは、Exact・古い世代・十分深い（PV ノードは 2 手分優遇）場合だけ
+//! 上書きする。置換がどう起きているかは [`TranspositionTable::stats`] で確かめられる。
 
 mod alloc;
 mod entry;
+mod persist;
+mod stats;
 mod table;
 
+pub use alloc::{LargePages, PageKind, TtAllocInfo, TtAllocOptions};
 pub use entry::{TTData, TTEntry};
+pub use persist::TtFileError;
+pub use stats::{STATS_AGE_BUCKETS, STATS_SAMPLE_CLUSTERS, TtCounters, TtStats};
 pub use table::{ProbeResult, TranspositionTable};
 
 /// クラスターサイズ（エントリ数）
@@ -30,3 +59,5 @@ pub const GENERATION_BITS: u32 = 3;
 pub const GENERATION_DELTA: u8 = 1 << GENERATION_BITS; // 8
 pub const GENERATION_CYCLE: u16 = 255 + GENERATION_DELTA as u16;
 pub const GENERATION_MASK: u16 = 0xF8; // (0xFF << GENERATION_BITS) as u8
+/// usinewgame で進める世代数（周期の半分。前の対局のエントリを最も古く見せる）
+pub const NEW_GAME_GENERATIONS: u8 = 16;
@@ -0,0 +1,313 @@
+//! 置換表のファイルへの保存・読み込み
+//!
+//! 長い検討を中断して後で再開できるよう、置換表の使用中のエントリだけをファイルに書き出す。
+//!
+//! # 形式（整数はすべてリトルエンディアン）
+//!
+//! | 内容 | バイト数 |
+//! |---|---|
+//! | マジック `RSHOGITT` | 8 |
+//! | 形式のバージョン | 4 |
+//! | Zobrist テーブルの指紋 | 8 |
+//! | クラスター数 | 8 |
+//! | 世代 | 1 |
+//! | 使用中のクラスター数 | 8 |
+//!
+//! ヘッダーに続けて、使用中のクラスターごとに「前のクラスターからの間隔（LEB128）・
+//! 使用中のエントリのビットマスク（1 バイト）・使用中のエントリ（各 10 バイト）」を並べる。
+//!
+//! エントリはキーの下位 16bit しか持たず、格納先のクラスターはクラスター数で決まるため、
+//! 読み込めるのは保存時と同じサイズの置換表だけ。Zobrist キーの体系が違うエンジンで
+//! 保存したファイルも読み込まない。
+
+use std::fmt;
+use std::io::{self, Read, Write};
+
+use super::entry::TTEntry;
+use super::table::Cluster;
+use super::{CLUSTER_SIZE, TranspositionTable};
+use crate::position::zobrist_fingerprint;
+
+/// ファイル先頭のマジック
+const MAGIC: &[u8; 8] = b"RSHOGITT";
+
+/// 形式のバージョン
+const FORMAT_VERSION: u32 = 1;
+
+/// 置換表ファイルの読み書きのエラー
+#[derive(Debug)]
+pub enum TtFileError {
+    Io(io::Error),
+    /// ファイルの形式や内容が不正
+    Format(String),
+    /// 保存時と置換表のサイズが違う
+    SizeMismatch {
+        /// ファイルのサイズ（MB）
+        file_mb: usize,
+        /// 現在の置換表のサイズ（MB）
+        table_mb: usize,
+    },
+}
+
+impl fmt::Display for TtFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TtFileError::Io(e) => write!(f, "TT file I/O error: {e}"),
+            TtFileError::Format(msg) => write!(f, "invalid TT file: {msg}"),
+            TtFileError::SizeMismatch { file_mb, table_mb } => write!(
+                f,
+                "TT file was saved with USI_Hash {file_mb} MB but the table is {table_mb} MB"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for TtFileError {}
+
+impl From<io::Error> for TtFileError {
+    fn from(e: io::Error) -> Self {
+        TtFileError::Io(e)
+    }
+}
+
+impl TranspositionTable {
+    /// 使用中のエントリを書き出し、書き出したエントリ数を返す
+    ///
+    /// 探索中の save と排他を取らないため、探索停止中に呼ぶこと。
+    pub fn save_to<W: Write>(&self, w: &mut W) -> io::Result<usize> {
+        let clusters = self.clusters();
+        let occupied = clusters.iter().filter(|c| occupied_mask(c) != 0).count();
+        w.write_all(MAGIC)?;
+        w.write_all(&FORMAT_VERSION.to_le_bytes())?;
+        w.write_all(&zobrist_fingerprint().to_le_bytes())?;
+        w.write_all(&(clusters.len() as u64).to_le_bytes())?;
+        w.write_all(&[self.generation()])?;
+        w.write_all(&(occupied as u64).to_le_bytes())?;
+
+        let mut entries = 0;
+        let mut next_index = 0;
+        for (index, cluster) in clusters.iter().enumerate() {
+            let mask = occupied_mask(cluster);
+            if mask == 0 {
+                continue;
+            }
+            write_varint(w, (index - next_index) as u64)?;
+            w.write_all(&[mask])?;
+            for entry in cluster.entries.iter().filter(|e| e.is_occupied()) {
+                w.write_all(&entry.to_le_bytes())?;
+                entries += 1;
+            }
+            next_index = index + 1;
+        }
+        Ok(entries)
+    }
+
+    /// [`save_to`](Self::save_to) で書き出した内容で置き換え、読み込んだエントリ数を返す
+    ///
+    /// ヘッダーが合わなければ置換表に触れずにエラーを返す。途中で読み込みに失敗した場合は
+    /// 置換表をクリアする。[`clear`](Self::clear) と同様に探索停止中に呼ぶこと。
+    pub fn load_from<R: Read>(&self, r: &mut R) -> Result<usize, TtFileError> {
+        let mut magic = [0u8; 8];
+        r.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(TtFileError::Format("bad magic".to_string()));
+        }
+        let version = u32::from_le_bytes(read_array(r)?);
+        if version != FORMAT_VERSION {
+            return Err(TtFileError::Format(format!(
+                "unsupported version {version} (expected {FORMAT_VERSION})"
+            )));
+        }
+        if u64::from_le_bytes(read_array(r)?) != zobrist_fingerprint() {
+            return Err(TtFileError::Format(
+                "Zobrist keys differ (saved by an incompatible engine build)".to_string(),
+            ));
+        }
+        let cluster_count = u64::from_le_bytes(read_array(r)?);
+        let table_len = self.clusters().len();
+        if cluster_count != table_len as u64 {
+            let to_mb = |count: u64| (count as usize * std::mem::size_of::<Cluster>()) >> 20;
+            return Err(TtFileError::SizeMismatch {
+                file_mb: to_mb(cluster_count),
+                table_mb: to_mb(table_len as u64),
+            });
+        }
+        let [generation] = read_array(r)?;
+        let occupied = u64::from_le_bytes(read_array(r)?);
+
+        self.clear();
+        let result = self.load_clusters(r, occupied, table_len);
+        match result {
+            Ok(entries) => {
+                self.set_generation(generation);
+                Ok(entries)
+            }
+            Err(e) => {
+                self.clear();
+                Err(e)
+            }
+        }
+    }
+
+    fn load_clusters<R: Read>(
+        &self,
+        r: &mut R,
+        occupied: u64,
+        table_len: usize,
+    ) -> Result<usize, TtFileError> {
+        let ptr = self.clusters_ptr();
+        let mut entries = 0;
+        let mut next_index = 0usize;
+        for _ in 0..occupied {
+            let index = usize::try_from(read_varint(r)?)
+                .ok()
+                .and_then(|gap| next_index.checked_add(gap))
+                .filter(|&index| index < table_len)
+                .ok_or_else(|| TtFileError::Format("cluster index out of range".to_string()))?;
+            let [mask] = read_array(r)?;
+            if mask == 0 || mask >= 1 << CLUSTER_SIZE {
+                return Err(TtFileError::Format(format!("bad entry mask {mask:#x}")));
+            }
+            for slot in (0..CLUSTER_SIZE).filter(|slot| mask & (1 << slot) != 0) {
+                let entry = TTEntry::from_le_bytes(read_array(r)?);
+                // SAFETY: index < table_len なので領域内。探索停止中の呼び出しを前提とし、
+                //         並行する読み書きは無い。
+                unsafe {
+                    (*ptr.add(index)).entries[slot] = entry;
+                }
+                entries += 1;
+            }
+            next_index = index + 1;
+        }
+        Ok(entries)
+    }
+}
+
+/// 使用中のエントリのビットマスク
+fn occupied_mask(cluster: &Cluster) -> u8 {
+    cluster
+        .entries
+        .iter()
+        .enumerate()
+        .filter(|(_, entry)| entry.is_occupied())
+        .fold(0, |mask, (slot, _)| mask | (1 << slot))
+}
+
+fn read_array<R: Read, const N: usize>(r: &mut R) -> io::Result<[u8; N]> {
+    let mut buf = [0u8; N];
+    r.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+fn write_varint<W: Write>(w: &mut W, mut value: u64) -> io::Result<()> {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            return w.write_all(&[byte]);
+        }
+        w.write_all(&[byte | 0x80])?;
+    }
+}
+
+fn read_varint<R: Read>(r: &mut R) -> Result<u64, TtFileError> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let [byte] = read_array(r)?;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(TtFileError::Format("varint too long".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::position::{Position, SFEN_HIRATE};
+    use crate::types::{Bound, Move, Value};
+
+    fn write_entries(tt: &TranspositionTable, pos: &Position, count: u64) {
+        for i in 0..count {
+            let key = pos.key() ^ i.wrapping_mul(0x9E37_79B9_7F4A_7C15);
+            tt.probe(key, pos).write(
+                key,
+                Value::new(i as i32),
+                i % 2 == 0,
+                Bound::Exact,
+                10,
+                Move::NONE,
+                Value::new(-(i as i32)),
+                tt.generation(),
+            );
+        }
+    }
+
+    #[test]
+    fn save_and_load_round_trips_entries_and_generation() {
+        let mut pos = Position::new();
+        pos.set_sfen(SFEN_HIRATE).unwrap();
+        let tt = TranspositionTable::new(1);
+        tt.new_search();
+        write_entries(&tt, &pos, 500);
+
+        let mut bytes = Vec::new();
+        let saved = tt.save_to(&mut bytes).unwrap();
+        assert!(saved > 0);
+        // 使用中のエントリだけを書き出す
+        assert!(bytes.len() < tt.raw_bytes().len() / 10);
+
+        let loaded_tt = TranspositionTable::new(1);
+        write_entries(&loaded_tt, &pos, 7);
+        assert_eq!(loaded_tt.load_from(&mut bytes.as_slice()).unwrap(), saved);
+        assert_eq!(loaded_tt.generation(), tt.generation());
+        assert_eq!(loaded_tt.raw_bytes(), tt.raw_bytes());
+    }
+
+    #[test]
+    fn load_rejects_other_sizes_and_corrupt_files_without_touching_table() {
+        let mut pos = Position::new();
+        pos.set_sfen(SFEN_HIRATE).unwrap();
+        let tt = TranspositionTable::new(1);
+        write_entries(&tt, &pos, 10);
+        let mut bytes = Vec::new();
+        tt.save_to(&mut bytes).unwrap();
+
+        let larger = TranspositionTable::new(2);
+        write_entries(&larger, &pos, 3);
+        let before = larger.raw_bytes().to_vec();
+        let err = larger.load_from(&mut bytes.as_slice()).unwrap_err();
+        assert!(
+            matches!(
+                err,
+                TtFileError::SizeMismatch {
+                    file_mb: 1,
+                    table_mb: 2
+                }
+            ),
+            "{err}"
+        );
+        assert_eq!(larger.raw_bytes(), before.as_slice());
+
+        // Zobrist の指紋が違う
+        let mut other_keys = bytes.clone();
+        other_keys[12] ^= 1;
+        assert!(matches!(tt.load_from(&mut other_keys.as_slice()), Err(TtFileError::Format(_))));
+
+        // 途中で切れたファイルは読み込まず、置換表を空にする
+        let truncated = &bytes[..bytes.len() - 5];
+        assert!(tt.load_from(&mut &truncated[..]).is_err());
+        assert_eq!(tt.hashfull(0), 0);
+        assert!(tt.clusters().iter().all(|c| occupied_mask(c) == 0));
+    }
+
+    #[test]
+    fn varint_round_trips() {
+        for value in [0, 1, 127, 128, 300, u32::MAX as u64, u64::MAX] {
+            let mut bytes = Vec::new();
+            write_varint(&mut bytes, value).unwrap();
+            assert_eq!(read_varint(&mut bytes.as_slice()).unwrap(), value);
+        }
+    }
+}
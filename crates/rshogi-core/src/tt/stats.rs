@@ -0,0 +1,286 @@
+//! 置換表の統計（置換方針の調整・ベンチマーク用）
+//!
+//! - [`TtStats`]: テーブルの先頭 [`STATS_SAMPLE_CLUSTERS`] クラスターを走査した、使用中の
+//!   エントリの世代・深さ・bound の内訳。常に取得できる
+//! - [`TtCounters`]: probe のヒット数と、書き込みが空きエントリ・同じ局面の更新・
+//!   別の局面の追い出しのどれだったかの回数。`tt-stats` feature 有効時のみ数える
+//!
+//! ```bash
+//! cargo run --release -p tools --features tt-stats --bin benchmark -- --internal
+//! ```
+
+#[cfg(feature = "tt-stats")]
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use super::GENERATION_BITS;
+use super::entry::TTEntry;
+use super::table::Cluster;
+use crate::types::Bound;
+
+/// [`TtStats`] で走査するクラスター数の上限（32 バイト × 65536 = 2MB）
+pub const STATS_SAMPLE_CLUSTERS: usize = 1 << 16;
+
+/// 世代別の内訳の区分数（最後の区分はそれ以上古いもの）
+pub const STATS_AGE_BUCKETS: usize = 8;
+
+/// 置換表の統計
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TtStats {
+    /// 走査したエントリ数
+    pub sampled_entries: u64,
+    /// 使用中のエントリ数
+    pub occupied: u64,
+    /// 使用中のエントリの世代別の内訳（`[a]` は `a` 回前の探索で書かれたもの）
+    pub by_age: [u64; STATS_AGE_BUCKETS],
+    /// 使用中のうち BOUND_EXACT のもの
+    pub exact: u64,
+    /// 使用中のうち PV ノードで書かれたもの
+    pub pv: u64,
+    /// 使用中のエントリの深さ（`depth8`）の合計
+    pub depth8_sum: u64,
+    /// probe と書き込みの回数（`tt-stats` feature 無効時は `None`）
+    pub counters: Option<TtCounters>,
+}
+
+impl TtStats {
+    /// クラスターを走査して集計する（`counters` は呼び出し側で設定する）
+    pub(super) fn scan(clusters: &[Cluster], generation8: u8) -> Self {
+        let mut stats = Self::default();
+        for cluster in clusters.iter().take(STATS_SAMPLE_CLUSTERS) {
+            for entry in &cluster.entries {
+                stats.add(entry, generation8);
+            }
+        }
+        stats
+    }
+
+    fn add(&mut self, entry: &TTEntry, generation8: u8) {
+        self.sampled_entries += 1;
+        if !entry.is_occupied() {
+            return;
+        }
+        self.occupied += 1;
+        let age = (entry.relative_age(generation8) >> GENERATION_BITS) as usize;
+        self.by_age[age.min(STATS_AGE_BUCKETS - 1)] += 1;
+        let data = entry.read();
+        if data.bound == Bound::Exact {
+            self.exact += 1;
+        }
+        if data.is_pv {
+            self.pv += 1;
+        }
+        self.depth8_sum += u64::from(entry.depth8());
+    }
+
+    /// 使用率（千分率、世代を問わない）
+    pub fn occupancy_permille(&self) -> u32 {
+        permille(self.occupied, self.sampled_entries)
+    }
+
+    /// 今の探索で書かれたエントリの割合（千分率、`hashfull` と同じ基準）
+    pub fn current_permille(&self) -> u32 {
+        permille(self.by_age[0], self.sampled_entries)
+    }
+
+    /// 使用中のエントリの平均の深さ（`depth8` 単位）
+    pub fn average_depth8(&self) -> f64 {
+        if self.occupied == 0 {
+            0.0
+        } else {
+            self.depth8_sum as f64 / self.occupied as f64
+        }
+    }
+}
+
+fn permille(part: u64, whole: u64) -> u32 {
+    (part * 1000).checked_div(whole).unwrap_or(0) as u32
+}
+
+/// probe と書き込みの回数
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TtCounters {
+    /// probe の回数
+    pub probes: u64,
+    /// probe で同じ局面のエントリが見つかった回数
+    pub hits: u64,
+    /// 空きエントリへの書き込み
+    pub stored_empty: u64,
+    /// 同じ局面のエントリの更新
+    pub updated: u64,
+    /// 同じ局面のエントリが深いため書き込まなかった回数（指し手の更新のみ）
+    pub kept: u64,
+    /// 別の局面のエントリを追い出した書き込み
+    pub replaced: u64,
+}
+
+impl TtCounters {
+    /// 書き込みの回数
+    pub fn writes(&self) -> u64 {
+        self.stored_empty + self.updated + self.kept + self.replaced
+    }
+
+    /// probe のヒット率
+    pub fn hit_rate(&self) -> f64 {
+        ratio(self.hits, self.probes)
+    }
+
+    /// 書き込みのうち別の局面を追い出した割合
+    pub fn replacement_rate(&self) -> f64 {
+        ratio(self.replaced, self.writes())
+    }
+}
+
+fn ratio(part: u64, whole: u64) -> f64 {
+    if whole == 0 {
+        0.0
+    } else {
+        part as f64 / whole as f64
+    }
+}
+
+/// [`TtCounters`] のカウンタ本体
+///
+/// 探索スレッド間で共有するため Relaxed の atomic で数える。キャッシュラインの競合で
+/// NPS が落ちるので、計測用のビルドに限る。
+#[cfg(feature = "tt-stats")]
+#[derive(Debug, Default)]
+pub(super) struct AtomicTtCounters {
+    probes: AtomicU64,
+    hits: AtomicU64,
+    stored_empty: AtomicU64,
+    updated: AtomicU64,
+    kept: AtomicU64,
+    replaced: AtomicU64,
+}
+
+#[cfg(feature = "tt-stats")]
+impl AtomicTtCounters {
+    #[inline]
+    pub(super) fn record_probe(&self, hit: bool) {
+        self.probes.fetch_add(1, Ordering::Relaxed);
+        if hit {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// 書き込み前後のエントリから、書き込みの種類を数える
+    #[inline]
+    pub(super) fn record_write(&self, before: &TTEntry, after: &TTEntry) {
+        let counter = if !before.is_occupied() {
+            &self.stored_empty
+        } else if before.key16() != after.key16() {
+            &self.replaced
+        } else if without_move(before) != without_move(after) {
+            &self.updated
+        } else {
+            &self.kept
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(super) fn snapshot(&self) -> TtCounters {
+        TtCounters {
+            probes: self.probes.load(Ordering::Relaxed),
+            hits: self.hits.load(Ordering::Relaxed),
+            stored_empty: self.stored_empty.load(Ordering::Relaxed),
+            updated: self.updated.load(Ordering::Relaxed),
+            kept: self.kept.load(Ordering::Relaxed),
+            replaced: self.replaced.load(Ordering::Relaxed),
+        }
+    }
+
+    pub(super) fn reset(&self) {
+        for counter in [
+            &self.probes,
+            &self.hits,
+            &self.stored_empty,
+            &self.updated,
+            &self.kept,
+            &self.replaced,
+        ] {
+            counter.store(0, Ordering::Relaxed);
+        }
+    }
+}
+
+/// 指し手以外の内容（深さが足りない書き込みでも指し手だけは更新されるため）
+#[cfg(feature = "tt-stats")]
+#[inline]
+fn without_move(entry: &TTEntry) -> [u8; super::entry::ENTRY_BYTES] {
+    let mut bytes = entry.to_le_bytes();
+    bytes[4..6].fill(0);
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::position::{Position, SFEN_HIRATE};
+    use crate::tt::TranspositionTable;
+    use crate::types::{Move, Value};
+
+    fn write(tt: &TranspositionTable, pos: &Position, key: u64, depth: i32, bound: Bound) {
+        tt.probe(key, pos).write(
+            key,
+            Value::new(10),
+            false,
+            bound,
+            depth,
+            Move::NONE,
+            Value::new(0),
+            tt.generation(),
+        );
+    }
+
+    #[test]
+    fn stats_count_entries_by_age_and_bound() {
+        let mut pos = Position::new();
+        pos.set_sfen(SFEN_HIRATE).unwrap();
+        let tt = TranspositionTable::new(1);
+        assert_eq!(tt.stats().occupied, 0);
+
+        let key = pos.key();
+        write(&tt, &pos, key, 10, Bound::Exact);
+        tt.new_search();
+        tt.new_search();
+        write(&tt, &pos, key ^ 0x1234_5678_0000_0000, 6, Bound::Lower);
+
+        let stats = tt.stats();
+        assert_eq!(
+            stats.sampled_entries,
+            (tt.clusters().len() * super::super::CLUSTER_SIZE) as u64
+        );
+        assert_eq!(stats.occupied, 2);
+        assert_eq!(stats.by_age[0], 1);
+        assert_eq!(stats.by_age[2], 1);
+        assert_eq!(stats.exact, 1);
+        assert!(stats.current_permille() <= stats.occupancy_permille());
+        assert!(stats.average_depth8() > 0.0);
+        assert_eq!(stats.counters.is_some(), cfg!(feature = "tt-stats"));
+    }
+
+    #[cfg(feature = "tt-stats")]
+    #[test]
+    fn counters_classify_writes() {
+        let mut pos = Position::new();
+        pos.set_sfen(SFEN_HIRATE).unwrap();
+        let tt = TranspositionTable::new(1);
+        let key = pos.key();
+        write(&tt, &pos, key, 10, Bound::Lower);
+        // 同じ局面への浅い書き込みは残す
+        write(&tt, &pos, key, 2, Bound::Lower);
+        write(&tt, &pos, key, 12, Bound::Lower);
+
+        let counters = tt.stats().counters.unwrap();
+        assert_eq!(counters.probes, 3);
+        assert_eq!(counters.hits, 2);
+        assert_eq!(counters.stored_empty, 1);
+        assert_eq!(counters.kept, 1);
+        assert_eq!(counters.updated, 1);
+        assert_eq!(counters.replaced, 0);
+        assert_eq!(counters.writes(), 3);
+
+        tt.clear();
+        assert_eq!(tt.stats().counters.unwrap(), TtCounters::default());
+    }
+}
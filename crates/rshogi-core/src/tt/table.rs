@@ -4,9 +4,12 @@
 //! - TranspositionTable: テーブル本体
 //! - probe/write操作
 
-use super::alloc::{AllocKind, Allocation};
+use super::alloc::{Allocation, PageKind, TtAllocInfo, TtAllocOptions};
 use super::entry::{TTData, TTEntry};
-use super::{CLUSTER_SIZE, GENERATION_DELTA};
+#[cfg(feature = "tt-stats")]
+use super::stats::AtomicTtCounters;
+use super::stats::TtStats;
+use super::{CLUSTER_SIZE, GENERATION_DELTA, NEW_GAME_GENERATIONS};
 use crate::position::Position;
 use crate::prefetch::TtPrefetch;
 use crate::types::{Bound, Color, Move, Value};
@@ -18,7 +21,7 @@ use std::sync::atomic::{AtomicU8, Ordering};
 /// YaneuraOu（CLUSTER_SIZE=3）準拠: 10bytes × 3 + 2padding = 32bytes
 #[repr(C, align(32))]
 pub struct Cluster {
-    entries: [TTEntry; CLUSTER_SIZE],
+    pub(super) entries: [TTEntry; CLUSTER_SIZE],
     _padding: [u8; 2], // 10 * 3 + 2 = 32 bytes
 }
 
@@ -56,18 +59,19 @@ struct ClusterTable {
 }
 
 impl ClusterTable {
-    fn new(len: usize) -> Self {
+    fn new(len: usize, options: TtAllocOptions) -> Self {
         let bytes = len * std::mem::size_of::<Cluster>();
-        let alloc = Allocation::allocate(bytes, std::mem::align_of::<Cluster>());
+        let alloc = Allocation::allocate(bytes, std::mem::align_of::<Cluster>(), options);
         let ptr = alloc.ptr().as_ptr() as *mut Cluster;
+        // SAFETY: alloc は len 個の Cluster を格納できるサイズ・アライメントで確保済み
         unsafe {
-            std::ptr::write_bytes(ptr, 0, len);
+            zero_clusters(ptr, len);
         }
         Self { alloc, len }
     }
 
-    fn uses_large_pages(&self) -> bool {
-        self.alloc.kind() == AllocKind::LargePages
+    fn alloc_info(&self) -> TtAllocInfo {
+        self.alloc.info()
     }
 }
 
@@ -87,6 +91,39 @@ impl DerefMut for ClusterTable {
     }
 }
 
+/// 並列ゼロクリアに切り替えるクラスター数の下限（スレッドあたり）
+const PARALLEL_CLEAR_MIN_CLUSTERS_PER_THREAD: usize = 1024;
+
+/// `ptr` から `len` 個の Cluster をゼロクリアする
+///
+/// 大きなテーブルはスレッド数で分割したチャンクを並列にクリアする。
+/// スレッドを使えない環境（wasm 等）や小さいテーブルでは逐次クリアする。
+///
+/// # Safety
+/// `ptr` は `len` 個の Cluster を格納できる有効な領域を指し、クリア中に他から
+/// 読み書きされないこと。
+unsafe fn zero_clusters(ptr: *mut Cluster, len: usize) {
+    let threads = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+
+    if threads <= 1 || len < threads * PARALLEL_CLEAR_MIN_CLUSTERS_PER_THREAD {
+        // SAFETY: 呼び出し元の契約による
+        unsafe { std::ptr::write_bytes(ptr, 0, len) };
+        return;
+    }
+
+    let chunk = len.div_ceil(threads);
+    std::thread::scope(|scope| {
+        for start in (0..len).step_by(chunk) {
+            let count = chunk.min(len - start);
+            // SAFETY: start < len なので領域内。チャンク同士は重ならない
+            let ptr_addr = unsafe { ptr.add(start) } as usize;
+            scope.spawn(move || unsafe {
+                std::ptr::write_bytes(ptr_addr as *mut Cluster, 0, count);
+            });
+        }
+    });
+}
+
 /// 置換表
 pub struct TranspositionTable {
     /// クラスターの配列
@@ -95,20 +132,36 @@ pub struct TranspositionTable {
     cluster_count: usize,
     /// 世代カウンター（下位3bitは使用しない）
     generation8: AtomicU8,
+    /// 確保の指定（resize でも引き継ぐ）
+    alloc_options: TtAllocOptions,
+    /// probe と書き込みの回数（`tt-stats` feature 有効時のみ）
+    #[cfg(feature = "tt-stats")]
+    counters: AtomicTtCounters,
 }
 
 impl TranspositionTable {
     /// 新しい置換表を作成（サイズはMB単位）
     pub fn new(mb_size: usize) -> Self {
+        Self::with_options(mb_size, TtAllocOptions::default())
+    }
+
+    /// 確保方法（Large Pages / NUMA interleave）を指定して作成
+    ///
+    /// 指定どおりに確保できなければ通常のページに落とす。実際の確保方法は
+    /// [`alloc_info`](Self::alloc_info) で確かめられる。
+    pub fn with_options(mb_size: usize, alloc_options: TtAllocOptions) -> Self {
         let cluster_count = (mb_size * 1024 * 1024 / std::mem::size_of::<Cluster>()) & !1;
         let cluster_count = cluster_count.max(2); // 最小2クラスター
 
-        let table = ClusterTable::new(cluster_count);
+        let table = ClusterTable::new(cluster_count, alloc_options);
 
         Self {
             table,
             cluster_count,
             generation8: AtomicU8::new(0),
+            alloc_options,
+            #[cfg(feature = "tt-stats")]
+            counters: AtomicTtCounters::default(),
         }
     }
 
@@ -118,45 +171,27 @@ impl TranspositionTable {
         let new_count = new_count.max(2);
 
         if new_count != self.cluster_count {
-            self.table = ClusterTable::new(new_count);
+            self.table = ClusterTable::new(new_count, self.alloc_options);
             self.cluster_count = new_count;
+            #[cfg(feature = "tt-stats")]
+            self.counters.reset();
         }
     }
 
     /// クリア
-    pub fn clear(&mut self) {
+    ///
+    /// 数 GB 以上のテーブルでも GUI のタイムアウトに掛からないよう、チャンクに分けて
+    /// 複数スレッドでゼロクリアする（やねうら王の Tools::memclear 相当）。
+    /// 探索中の probe/save と同様に排他は取らないため、探索停止中に呼ぶこと。
+    pub fn clear(&self) {
         self.generation8.store(0, Ordering::Relaxed);
-        let len = self.table.len();
-        let threads = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
-
-        // サイズが小さい場合やスレッド数が1の場合は逐次クリア
-        if threads <= 1 || len < threads * 1024 {
-            for cluster in self.table.iter_mut() {
-                *cluster = Cluster::new();
-            }
-            return;
+        #[cfg(feature = "tt-stats")]
+        self.counters.reset();
+        // SAFETY: table は cluster_count 個の Cluster を保持している。
+        //         探索停止中の呼び出しを前提とし、並行する読み書きは無い。
+        unsafe {
+            zero_clusters(self.table.alloc.ptr().as_ptr() as *mut Cluster, self.table.len);
         }
-
-        let chunk = len.div_ceil(threads);
-        let ptr = self.table.as_mut_ptr();
-
-        // スレッドを分割してゼロクリア（やねうら王の Tools::memclear 相当）
-        std::thread::scope(|scope| {
-            for i in 0..threads {
-                let start = i * chunk;
-                if start >= len {
-                    break;
-                }
-                let end = (start + chunk).min(len);
-                let count = end - start;
-                let ptr_addr = unsafe { ptr.add(start) } as usize;
-
-                scope.spawn(move || unsafe {
-                    let ptr = ptr_addr as *mut Cluster;
-                    std::ptr::write_bytes(ptr, 0, count);
-                });
-            }
-        });
     }
 
     /// 新しい探索を開始（世代を進める）
@@ -164,6 +199,15 @@ impl TranspositionTable {
         self.generation8.fetch_add(GENERATION_DELTA, Ordering::Relaxed);
     }
 
+    /// 新しい対局を開始（世代を半周進める）
+    ///
+    /// ゼロクリアせず、前の対局のエントリを最も置換されやすい年齢にする。
+    /// エントリ自体は probe で引き続き参照できる（同じ序盤の局面は再利用される）。
+    pub fn new_game(&self) {
+        self.generation8
+            .fetch_add(GENERATION_DELTA * NEW_GAME_GENERATIONS, Ordering::Relaxed);
+    }
+
     /// 現在の世代を取得
     #[inline]
     pub fn generation(&self) -> u8 {
@@ -189,10 +233,14 @@ impl TranspositionTable {
                     }
                 }
 
+                #[cfg(feature = "tt-stats")]
+                self.counters.record_probe(entry.is_occupied());
                 return ProbeResult {
                     found: entry.is_occupied(),
                     data,
                     writer: entry as *const _ as *mut _,
+                    #[cfg(feature = "tt-stats")]
+                    counters: &self.counters,
                 };
             }
         }
@@ -212,10 +260,14 @@ impl TranspositionTable {
             }
         }
 
+        #[cfg(feature = "tt-stats")]
+        self.counters.record_probe(false);
         ProbeResult {
             found: false,
             data: TTData::EMPTY,
             writer: replace,
+            #[cfg(feature = "tt-stats")]
+            counters: &self.counters,
         }
     }
 
@@ -237,9 +289,81 @@ impl TranspositionTable {
         count / CLUSTER_SIZE as i32
     }
 
-    /// Large Pagesを使って確保されたかを返す
+    /// 置換方針の調整・ベンチマーク用の統計
+    ///
+    /// 先頭のクラスターだけを走査する（[`TtStats`] 参照）。探索中に呼んでもよいが、
+    /// 書き込み途中のエントリを数えることがある。
+    pub fn stats(&self) -> TtStats {
+        #[allow(unused_mut)]
+        let mut stats = TtStats::scan(&self.table, self.generation());
+        #[cfg(feature = "tt-stats")]
+        {
+            stats.counters = Some(self.counters.snapshot());
+        }
+        stats
+    }
+
+    /// Large Pagesを使って確保されたかを返す（THP のヒントを含む）
     pub fn uses_large_pages(&self) -> bool {
-        self.table.uses_large_pages()
+        self.table.alloc_info().pages != PageKind::Regular
+    }
+
+    /// 実際の確保方法
+    pub fn alloc_info(&self) -> TtAllocInfo {
+        self.table.alloc_info()
+    }
+
+    /// クラスター配列全体のバイト列（探索スナップショット用）
+    ///
+    /// 探索中の probe/save と排他を取らないため、探索停止中（または単一スレッド探索の
+    /// 反復の合間）に呼ぶこと。
+    pub(crate) fn raw_bytes(&self) -> &[u8] {
+        // SAFETY: table は cluster_count 個の Cluster（整数のみ、パディングもゼロ初期化済み）を
+        //         連続して保持している。
+        unsafe {
+            std::slice::from_raw_parts(
+                self.table.alloc.ptr().as_ptr() as *const u8,
+                self.table.len * std::mem::size_of::<Cluster>(),
+            )
+        }
+    }
+
+    /// [`raw_bytes`](Self::raw_bytes) で取り出した内容と世代を書き戻す
+    ///
+    /// バイト数がテーブルの大きさと一致しなければ何もせず `false` を返す。
+    /// [`clear`](Self::clear) と同様に探索停止中に呼ぶこと。
+    pub(crate) fn load_raw_bytes(&self, bytes: &[u8], generation: u8) -> bool {
+        if bytes.len() != self.table.len * std::mem::size_of::<Cluster>() {
+            return false;
+        }
+        // SAFETY: 長さは一致しており、Cluster は任意のバイト列が有効な整数のみで構成される。
+        //         探索停止中の呼び出しを前提とし、並行する読み書きは無い。
+        unsafe {
+            std::ptr::copy_nonoverlapping(
+                bytes.as_ptr(),
+                self.table.alloc.ptr().as_ptr(),
+                bytes.len(),
+            );
+        }
+        self.generation8.store(generation, Ordering::Relaxed);
+        true
+    }
+
+    /// クラスター配列（ファイル保存用）
+    pub(super) fn clusters(&self) -> &[Cluster] {
+        &self.table
+    }
+
+    /// クラスター配列の先頭（ファイル読み込み用）
+    ///
+    /// 書き込みは [`clear`](Self::clear) と同様に探索停止中に限ること。
+    pub(super) fn clusters_ptr(&self) -> *mut Cluster {
+        self.table.alloc.ptr().as_ptr() as *mut Cluster
+    }
+
+    /// 世代を設定する（ファイル読み込み用）
+    pub(super) fn set_generation(&self, generation: u8) {
+        self.generation8.store(generation, Ordering::Relaxed);
     }
 
     /// クラスターインデックスを計算
@@ -293,6 +417,9 @@ pub struct ProbeResult {
     pub data: TTData,
     /// 書き込み用エントリ
     writer: *mut TTEntry,
+    /// 書き込みを数えるカウンタ（probe したテーブルのもの）
+    #[cfg(feature = "tt-stats")]
+    counters: *const AtomicTtCounters,
 }
 
 impl ProbeResult {
@@ -312,9 +439,16 @@ impl ProbeResult {
         generation8: u8,
     ) {
         // SAFETY: writerはprobe()で取得した有効なポインタ
+        #[cfg(feature = "tt-stats")]
+        let before = unsafe { *self.writer };
         unsafe {
             (*self.writer).save(key, value, is_pv, bound, depth, mv, eval, generation8);
         }
+        // SAFETY: counters は writer と同じテーブルのフィールドを指す
+        #[cfg(feature = "tt-stats")]
+        unsafe {
+            (*self.counters).record_write(&before, &*self.writer);
+        }
     }
 }
 
@@ -349,6 +483,33 @@ mod tests {
         assert_eq!(tt.generation(), GENERATION_DELTA * 2);
     }
 
+    #[test]
+    fn test_tt_new_game_ages_entries_without_clearing() {
+        let mut pos = Position::new();
+        pos.set_sfen(SFEN_HIRATE).unwrap();
+        let tt = TranspositionTable::new(1);
+        let key = pos.key();
+        let result = tt.probe(key, &pos);
+        result.write(
+            key,
+            Value::new(100),
+            false,
+            Bound::Exact,
+            10,
+            Move::NONE,
+            Value::new(50),
+            tt.generation(),
+        );
+
+        tt.new_game();
+        assert_eq!(tt.generation(), GENERATION_DELTA * NEW_GAME_GENERATIONS);
+        let result = tt.probe(key, &pos);
+        assert!(result.found);
+        // SAFETY: writer は tt 内のエントリを指し、tt はこのスコープで生存している
+        let age = unsafe { &*result.writer }.relative_age(tt.generation());
+        assert_eq!(age, GENERATION_DELTA * NEW_GAME_GENERATIONS);
+    }
+
     #[test]
     fn test_tt_probe_empty() {
         let tt = TranspositionTable::new(1);
@@ -418,7 +579,7 @@ mod tests {
         let mut pos = Position::new();
         pos.set_sfen(SFEN_HIRATE).unwrap();
 
-        let mut tt = TranspositionTable::new(1);
+        let tt = TranspositionTable::new(1);
         let key = pos.key();
 
         // 書き込み（DEPTH_ENTRY_OFFSETを考慮して有効な深さ）
@@ -442,6 +603,34 @@ mod tests {
         assert!(!probe2.found);
     }
 
+    #[test]
+    fn test_tt_clear_shared_large_table() {
+        let mut pos = Position::new();
+        pos.set_sfen(SFEN_HIRATE).unwrap();
+
+        // 並列クリアの経路に入るサイズ。探索スレッドと共有する Arc 越しにクリアする
+        let tt = std::sync::Arc::new(TranspositionTable::new(64));
+        let shared = std::sync::Arc::clone(&tt);
+        for i in 0..64u64 {
+            let key = pos.key() ^ i.wrapping_mul(0x9E37_79B9_7F4A_7C15);
+            shared.probe(key, &pos).write(
+                key,
+                Value::new(100),
+                false,
+                Bound::Lower,
+                10,
+                Move::NONE,
+                Value::ZERO,
+                shared.generation(),
+            );
+        }
+        assert!(tt.table.iter().any(|c| c.entries.iter().any(TTEntry::is_occupied)));
+
+        tt.clear();
+
+        assert!(!shared.table.iter().any(|c| c.entries.iter().any(TTEntry::is_occupied)));
+    }
+
     #[test]
     fn test_tt_resize() {
         let mut tt = TranspositionTable::new(1);
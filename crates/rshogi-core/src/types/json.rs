@@ -71,3 +71,52 @@ pub struct ReplayResultJson {
     pub board: BoardStateJson,
     pub error: Option<String>,
 }
+
+/// 合法手1つと、UI（ハイライト・効果音）向けの付帯情報
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct AnnotatedMoveJson {
+    /// USI形式の指し手（"7g7f", "P*5e", "pass" など）
+    pub usi: String,
+    /// 移動元（駒打ち・パスでは null）
+    pub from: Option<String>,
+    /// 移動先（パスでは null）
+    pub to: Option<String>,
+    /// 駒打ちか
+    pub drop: bool,
+    /// 駒を取るか
+    pub capture: bool,
+    /// 王手になるか
+    pub check: bool,
+    /// この手が成りか
+    pub promote: bool,
+    /// 同じ移動元・移動先での成りの選択: "none" | "optional" | "forced"
+    pub promotion: String,
+}
+
+/// 片方の対局者の駒（読み上げ向けの日本語表記）
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SideDescriptionJson {
+    /// 盤上の駒（"５九玉" 形式。玉・飛・角・金・銀・桂・香・歩の順、成駒は元の駒の前）
+    pub pieces: Vec<String>,
+    /// 持ち駒（"歩二" 形式。飛車から歩の順、無ければ空）
+    pub hand: Vec<String>,
+}
+
+/// 局面の文章表現（スクリーンリーダー等の読み上げ向け）
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PositionDescriptionJson {
+    /// 手番: "sente" | "gote"
+    pub turn: String,
+    /// 手数
+    pub ply: i32,
+    /// 手番側が王手されているか
+    pub in_check: bool,
+    /// 手番側に合法手が無いか（王手なら詰み）
+    pub no_legal_moves: bool,
+    /// 直前の手の KIF 表記（"▲７六歩(77)" 形式、棋譜が空なら null）
+    pub last_move: Option<String>,
+    pub sente: SideDescriptionJson,
+    pub gote: SideDescriptionJson,
+    /// 上記をまとめた読み上げ用の 1 文（"後手番、2手目。直前の手は▲７六歩(77)。"）
+    pub summary: String,
+}
@@ -26,6 +26,7 @@ mod depth;
 mod entering_king;
 mod file;
 mod hand;
+#[cfg(feature = "json")]
 pub mod json;
 mod moves;
 mod piece;
@@ -41,8 +42,9 @@ pub use depth::*;
 pub use entering_king::EnteringKingRule;
 pub use file::File;
 pub use hand::Hand;
+#[cfg(feature = "json")]
 pub use json::*;
-pub use moves::Move;
+pub use moves::{Move, MoveParseError};
 pub use piece::Piece;
 pub use piece_type::{PieceType, PieceTypeSet};
 pub use rank::Rank;
@@ -325,6 +325,9 @@ impl Move {
     /// `"win"` は受理しない。`bestmove win` は終局トークンであり、
     /// `position ... moves` の指し手列で使用されると不正な局面更新を引き起こすため、
     /// 上位レイヤー（USI bestmove 解析等）で文字列として処理する。
+    ///
+    /// 5 文字目以降は `+` 以外を無視する緩い解析。不正な入力の理由を知りたい場合や
+    /// 末尾の余計な文字も拒否したい場合は [`parse_usi`](Self::parse_usi) を使う。
     pub fn from_usi(s: &str) -> Option<Move> {
         if s == "none" {
             return Some(Move::NONE);
@@ -368,8 +371,89 @@ impl Move {
 
         None
     }
+
+    /// USI形式の文字列を厳密に解析する（不正なら理由を返す）
+    ///
+    /// [`from_usi`](Self::from_usi) と同じく `"none"`・`"pass"`・`"0000"` を受理する。
+    /// それ以外は移動 `7g7f` / `7g7f+`（4〜5 文字）と駒打ち `P*7f`（4 文字）だけを受理し、
+    /// 末尾の余計な文字や小文字の駒種は拒否する。
+    pub fn parse_usi(s: &str) -> Result<Move, MoveParseError> {
+        match s {
+            "none" => return Ok(Move::NONE),
+            "pass" | "0000" => return Ok(Move::PASS),
+            _ => {}
+        }
+        let chars: Vec<char> = s.chars().collect();
+        if !(4..=5).contains(&chars.len()) {
+            return Err(MoveParseError::Length(chars.len()));
+        }
+        let square = |at: usize| {
+            let text: String = chars[at..at + 2].iter().collect();
+            Square::from_usi(&text).ok_or(MoveParseError::BadSquare(text))
+        };
+
+        if chars[1] == '*' {
+            let pt = match chars[0] {
+                'P' => PieceType::Pawn,
+                'L' => PieceType::Lance,
+                'N' => PieceType::Knight,
+                'S' => PieceType::Silver,
+                'G' => PieceType::Gold,
+                'B' => PieceType::Bishop,
+                'R' => PieceType::Rook,
+                c => return Err(MoveParseError::BadDropPiece(c)),
+            };
+            let to = square(2)?;
+            // 駒打ちは成れない
+            if let Some(&suffix) = chars.get(4) {
+                return Err(MoveParseError::BadPromotion(suffix));
+            }
+            return Ok(Move::new_drop(pt, to));
+        }
+
+        let from = square(0)?;
+        let to = square(2)?;
+        let promote = match chars.get(4) {
+            None => false,
+            Some('+') => true,
+            Some(&suffix) => return Err(MoveParseError::BadPromotion(suffix)),
+        };
+        Ok(Move::new_move(from, to, promote))
+    }
+}
+
+/// [`Move::parse_usi`] のエラー
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MoveParseError {
+    /// 文字数が不正（`none`・`pass`・`0000` 以外は 4〜5 文字）
+    Length(usize),
+    /// マスの表記が不正（筋は `1`〜`9`、段は `a`〜`i`）
+    BadSquare(String),
+    /// 打つ駒の表記が不正（`P`・`L`・`N`・`S`・`G`・`B`・`R`）
+    BadDropPiece(char),
+    /// 5 文字目が成りの `+` でない（駒打ちには付けられない）
+    BadPromotion(char),
+}
+
+impl std::fmt::Display for MoveParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MoveParseError::Length(len) => {
+                write!(f, "Invalid length: {len} characters (expected 4 or 5)")
+            }
+            MoveParseError::BadSquare(s) => write!(f, "Invalid square: '{s}'"),
+            MoveParseError::BadDropPiece(c) => {
+                write!(f, "Invalid drop piece: '{c}' (expected one of PLNSGBR)")
+            }
+            MoveParseError::BadPromotion(c) => {
+                write!(f, "Invalid promotion suffix: '{c}' (only '+' on a board move)")
+            }
+        }
+    }
 }
 
+impl std::error::Error for MoveParseError {}
+
 impl Default for Move {
     fn default() -> Self {
         Move::NONE
@@ -655,6 +739,29 @@ mod tests {
         assert!(!Move::WIN.is_promote());
     }
 
+    #[test]
+    fn test_move_parse_usi_accepts_what_from_usi_accepts() {
+        for usi in ["7g7f", "8h2b+", "P*5e", "R*1a", "none", "pass", "0000"] {
+            assert_eq!(Move::parse_usi(usi), Ok(Move::from_usi(usi).unwrap()), "{usi}");
+        }
+    }
+
+    #[test]
+    fn test_move_parse_usi_errors() {
+        assert_eq!(Move::parse_usi("7g7"), Err(MoveParseError::Length(3)));
+        assert_eq!(Move::parse_usi("win"), Err(MoveParseError::Length(3)));
+        assert_eq!(Move::parse_usi("7g7f++"), Err(MoveParseError::Length(6)));
+        assert_eq!(Move::parse_usi("0g7f"), Err(MoveParseError::BadSquare("0g".to_string())));
+        assert_eq!(Move::parse_usi("7g7j"), Err(MoveParseError::BadSquare("7j".to_string())));
+        assert_eq!(Move::parse_usi("P*5z"), Err(MoveParseError::BadSquare("5z".to_string())));
+        assert_eq!(Move::parse_usi("K*5e"), Err(MoveParseError::BadDropPiece('K')));
+        assert_eq!(Move::parse_usi("p*5e"), Err(MoveParseError::BadDropPiece('p')));
+        assert_eq!(Move::parse_usi("P*5e+"), Err(MoveParseError::BadPromotion('+')));
+        // from_usi は 5 文字目を無視するが、parse_usi は拒否する
+        assert_eq!(Move::from_usi("7g7f="), Move::from_usi("7g7f"));
+        assert_eq!(Move::parse_usi("7g7f="), Err(MoveParseError::BadPromotion('=')));
+    }
+
     #[test]
     fn test_move_win_usi() {
         assert_eq!(Move::WIN.to_usi(), "win");
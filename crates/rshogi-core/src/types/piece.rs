@@ -115,6 +115,32 @@ impl Piece {
         self.0 as usize
     }
 
+    /// SFEN / USI の駒の表記（"P", "+b" 等）。`Piece::NONE` には使わないこと
+    pub fn to_usi(self) -> String {
+        let base = match self.piece_type() {
+            PieceType::Pawn => "P",
+            PieceType::Lance => "L",
+            PieceType::Knight => "N",
+            PieceType::Silver => "S",
+            PieceType::Bishop => "B",
+            PieceType::Rook => "R",
+            PieceType::Gold => "G",
+            PieceType::King => "K",
+            PieceType::ProPawn => "+P",
+            PieceType::ProLance => "+L",
+            PieceType::ProKnight => "+N",
+            PieceType::ProSilver => "+S",
+            PieceType::Horse => "+B",
+            PieceType::Dragon => "+R",
+        };
+
+        if self.color() == Color::White {
+            base.to_lowercase()
+        } else {
+            base.to_string()
+        }
+    }
+
     /// 内部値を取得
     #[inline]
     pub const fn raw(self) -> u8 {
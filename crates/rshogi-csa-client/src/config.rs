@@ -6,7 +6,10 @@ use std::path::{Path, PathBuf};
 use anyhow::{Result, bail};
 use serde::Deserialize;
 
+use rshogi_csa::Color;
+
 use crate::events::SearchInfoEmitPolicy;
+use crate::protocol::{GameSummary, KEEPALIVE_MIN_INTERVAL_SEC};
 
 /// CSAクライアント全体の設定
 #[derive(Clone, Debug, Deserialize)]
@@ -20,6 +23,10 @@ pub struct CsaClientConfig {
     pub retry: RetryConfig,
     pub record: RecordConfig,
     pub log: LogConfig,
+    /// 対戦相手別の準備プロファイル（`[[profile]]`）。上から順に照合し、最初に
+    /// 一致したものを使う。
+    #[serde(rename = "profile")]
+    pub profiles: Vec<OpponentProfile>,
 }
 
 #[derive(Clone, Debug, Deserialize)]
@@ -127,6 +134,21 @@ pub struct GameConfig {
     /// (TOML から読み込まず、library consumer が programmatic に上書きする想定)。
     #[serde(skip)]
     pub search_info_emit: SearchInfoEmitPolicy,
+    /// 終了シグナル (SIGINT / SIGTERM) 受信時の対局中の扱い
+    pub shutdown_policy: ShutdownPolicy,
+}
+
+/// 終了シグナル受信時に対局中の局をどう扱うか
+///
+/// どちらのポリシーでも新しい対局は受け付けず、局の終了後に `LOGOUT` して終了する。
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ShutdownPolicy {
+    /// 対局を最後まで指し切る。2 度目のシグナルで `%CHUDAN` して即時終了する
+    #[default]
+    Finish,
+    /// 次の自分の手番で投了 (`%TORYO`) し、終局を待って終了する
+    Resign,
 }
 
 impl Default for GameConfig {
@@ -136,10 +158,122 @@ impl Default for GameConfig {
             restart_engine_every_game: false,
             ponder: true,
             search_info_emit: SearchInfoEmitPolicy::default(),
+            shutdown_policy: ShutdownPolicy::default(),
         }
     }
 }
 
+/// 対戦相手別の準備プロファイル
+///
+/// `Game_Summary` 受信時に相手の名前で選ばれ、`usinewgame` の前に USI オプションの
+/// 上書きとしてエンジンへ送られる。選ばれたプロファイル名は対局ログに記録する。
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct OpponentProfile {
+    /// プロファイル名（対局ログに記録する）
+    pub name: String,
+    /// 相手の名前のパターン。`*` は任意の文字列に一致する（大文字小文字は区別する）
+    pub opponents: Vec<String>,
+    /// 定跡ファイル（USI `BookFile`）
+    pub book_file: Option<String>,
+    /// 千日手を自分から見て `-contempt` として扱う (cp)。自分の手番側の
+    /// `DrawValueBlack` / `DrawValueWhite` に設定する。
+    pub contempt: Option<i32>,
+    /// 持ち時間の使い方（USI `SlowMover`、100 が標準で大きいほど序盤から時間を使う）
+    pub slow_mover: Option<i32>,
+    /// 棋力の上限（USI `Skill Level`）。指導対局用のアカウント向け
+    pub skill_level: Option<i32>,
+    /// その他の USI オプションの上書き
+    pub options: HashMap<String, toml::Value>,
+}
+
+impl OpponentProfile {
+    /// 相手の名前がパターンのいずれかに一致するか
+    pub fn matches(&self, opponent: &str) -> bool {
+        self.opponents.iter().any(|pattern| wildcard_match(pattern, opponent))
+    }
+
+    /// このプロファイルが上書きする USI オプションを `(名前, 値)` で返す
+    fn usi_overrides(&self, my_color: Color) -> Vec<(String, String)> {
+        let mut overrides: Vec<(String, String)> = self
+            .options
+            .iter()
+            .filter_map(|(key, value)| Some((key.clone(), toml_value_to_usi(value)?)))
+            .collect();
+        if let Some(book_file) = &self.book_file {
+            overrides.push(("BookFile".to_string(), book_file.clone()));
+        }
+        if let Some(contempt) = self.contempt {
+            overrides.push((draw_value_option(my_color).to_string(), (-contempt).to_string()));
+        }
+        if let Some(slow_mover) = self.slow_mover {
+            overrides.push(("SlowMover".to_string(), slow_mover.to_string()));
+        }
+        if let Some(skill_level) = self.skill_level {
+            overrides.push(("Skill Level".to_string(), skill_level.to_string()));
+        }
+        overrides
+    }
+
+    /// 手番に関係なく、このプロファイルが触りうる USI オプション名
+    fn touched_options(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.options.keys().cloned().collect();
+        if self.book_file.is_some() {
+            names.push("BookFile".to_string());
+        }
+        if self.contempt.is_some() {
+            names.push(draw_value_option(Color::Black).to_string());
+            names.push(draw_value_option(Color::White).to_string());
+        }
+        if self.slow_mover.is_some() {
+            names.push("SlowMover".to_string());
+        }
+        if self.skill_level.is_some() {
+            names.push("Skill Level".to_string());
+        }
+        names
+    }
+}
+
+fn draw_value_option(color: Color) -> &'static str {
+    match color {
+        Color::Black => "DrawValueBlack",
+        Color::White => "DrawValueWhite",
+    }
+}
+
+/// TOML の値を `setoption` の値文字列にする。配列・テーブルは送らない。
+pub(crate) fn toml_value_to_usi(value: &toml::Value) -> Option<String> {
+    match value {
+        toml::Value::Integer(n) => Some(n.to_string()),
+        toml::Value::Boolean(b) => Some(b.to_string()),
+        toml::Value::String(s) => Some(s.clone()),
+        toml::Value::Float(f) => Some(f.to_string()),
+        _ => None,
+    }
+}
+
+/// `*` だけを特殊文字とする単純なワイルドカード照合
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        // `*` を含まないパターンは完全一致
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(idx) => rest = &rest[idx + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
 #[derive(Clone, Debug, Deserialize)]
 #[serde(default)]
 pub struct RetryConfig {
@@ -223,6 +357,38 @@ impl CsaClientConfig {
         Ok(config)
     }
 
+    /// 対局の相手に一致する最初のプロファイルを返す
+    pub fn select_profile(&self, summary: &GameSummary) -> Option<&OpponentProfile> {
+        let opponent = match summary.my_color {
+            Color::Black => &summary.gote_name,
+            Color::White => &summary.sente_name,
+        };
+        self.profiles.iter().find(|profile| profile.matches(opponent))
+    }
+
+    /// 対局開始前にエンジンへ送る USI オプションを返す。
+    ///
+    /// 選ばれたプロファイルの上書きに加え、他のプロファイルだけが触るオプションは
+    /// `[engine.options]` の値に戻す（エンジンを局ごとに再起動しない運用で前局の
+    /// プロファイルの値が残らないようにするため）。`[engine.options]` にも無い
+    /// オプションは戻す値が分からないので送らない。
+    pub fn profile_usi_options(
+        &self,
+        profile: Option<&OpponentProfile>,
+        my_color: Color,
+    ) -> Vec<(String, String)> {
+        let mut options = profile.map(|p| p.usi_overrides(my_color)).unwrap_or_default();
+        for name in self.profiles.iter().flat_map(OpponentProfile::touched_options) {
+            if options.iter().any(|(key, _)| *key == name) {
+                continue;
+            }
+            if let Some(value) = self.engine.options.get(&name).and_then(toml_value_to_usi) {
+                options.push((name, value));
+            }
+        }
+        options
+    }
+
     /// バリデーション
     pub fn validate(&self) -> Result<()> {
         if self.server.id.is_empty() {
@@ -232,10 +398,18 @@ impl CsaClientConfig {
             bail!("engine.path is required");
         }
         if self.server.keepalive.ping_interval_sec > 0
-            && self.server.keepalive.ping_interval_sec < 30
+            && self.server.keepalive.ping_interval_sec < KEEPALIVE_MIN_INTERVAL_SEC
         {
             bail!("keepalive.ping_interval_sec must be >= 30 (CSA protocol requirement)");
         }
+        for profile in &self.profiles {
+            if profile.name.is_empty() {
+                bail!("profile.name is required");
+            }
+            if profile.opponents.is_empty() {
+                bail!("profile \"{}\" has no opponents", profile.name);
+            }
+        }
         Ok(())
     }
 }
@@ -277,10 +451,122 @@ mod tests {
         assert_eq!(config.jsonl_dir(), None);
     }
 
+    #[test]
+    fn shutdown_policy_defaults_to_finish_and_parses_resign() {
+        let config: CsaClientConfig = toml::from_str("").unwrap();
+        assert_eq!(config.game.shutdown_policy, ShutdownPolicy::Finish);
+        let config: CsaClientConfig =
+            toml::from_str("[game]\nshutdown_policy = \"resign\"\n").unwrap();
+        assert_eq!(config.game.shutdown_policy, ShutdownPolicy::Resign);
+    }
+
     #[test]
     fn record_toml_without_save_jsonl_defaults_on() {
         let config: CsaClientConfig = toml::from_str("[record]\nenabled = true\n").unwrap();
         assert!(config.record.save_jsonl);
         assert_eq!(config.record.jsonl_dir(), Some(PathBuf::from("./records/jsonl")));
     }
+
+    fn summary_with(my_color: Color, sente: &str, gote: &str) -> GameSummary {
+        use crate::protocol::TimeConfig as ProtoTimeConfig;
+        let time = ProtoTimeConfig {
+            total_time_ms: 600_000,
+            byoyomi_ms: 10_000,
+            increment_ms: 0,
+        };
+        GameSummary {
+            game_id: "g1".to_string(),
+            my_color,
+            sente_name: sente.to_string(),
+            gote_name: gote.to_string(),
+            position: rshogi_csa::initial_position(),
+            initial_moves: Vec::new(),
+            black_time: time.clone(),
+            white_time: time,
+            reconnect_token: None,
+        }
+    }
+
+    const PROFILES_TOML: &str = r#"
+[engine.options]
+BookFile = "book/standard.db"
+SlowMover = 100
+
+[[profile]]
+name = "teaching"
+opponents = ["student*", "*_kid"]
+skill_level = 5
+book_file = "book/teaching.db"
+
+[[profile]]
+name = "rival"
+opponents = ["rival_engine"]
+contempt = 50
+slow_mover = 130
+options = { MultiPV = 1 }
+"#;
+
+    #[test]
+    fn wildcard_match_handles_prefix_suffix_and_middle() {
+        assert!(wildcard_match("abc", "abc"));
+        assert!(!wildcard_match("abc", "abcd"));
+        assert!(wildcard_match("*", ""));
+        assert!(wildcard_match("student*", "student_01"));
+        assert!(!wildcard_match("student*", "a_student"));
+        assert!(wildcard_match("*_kid", "taro_kid"));
+        assert!(wildcard_match("a*b*c", "a--b--c"));
+        assert!(!wildcard_match("ab*b", "ab"));
+    }
+
+    #[test]
+    fn select_profile_uses_opponent_name_for_my_color() {
+        let config: CsaClientConfig = toml::from_str(PROFILES_TOML).unwrap();
+
+        let summary = summary_with(Color::Black, "me", "student_01");
+        assert_eq!(config.select_profile(&summary).map(|p| p.name.as_str()), Some("teaching"));
+        // 自分の名前ではなく相手の名前で照合する
+        let summary = summary_with(Color::White, "me", "student_01");
+        assert!(config.select_profile(&summary).is_none());
+        let summary = summary_with(Color::White, "rival_engine", "me");
+        assert_eq!(config.select_profile(&summary).map(|p| p.name.as_str()), Some("rival"));
+    }
+
+    #[test]
+    fn profile_usi_options_apply_overrides_and_restore_base_values() {
+        let config: CsaClientConfig = toml::from_str(PROFILES_TOML).unwrap();
+        let sorted = |mut options: Vec<(String, String)>| {
+            options.sort();
+            options
+        };
+        let pair = |k: &str, v: &str| (k.to_string(), v.to_string());
+
+        let rival = config.profiles.iter().find(|p| p.name == "rival");
+        assert_eq!(
+            sorted(config.profile_usi_options(rival, Color::White)),
+            vec![
+                pair("BookFile", "book/standard.db"),
+                pair("DrawValueWhite", "-50"),
+                pair("MultiPV", "1"),
+                pair("SlowMover", "130"),
+            ]
+        );
+
+        // プロファイルなしでは基本設定のある値だけ戻す（Skill Level などは送らない）
+        assert_eq!(
+            sorted(config.profile_usi_options(None, Color::Black)),
+            vec![
+                pair("BookFile", "book/standard.db"),
+                pair("SlowMover", "100")
+            ]
+        );
+    }
+
+    #[test]
+    fn validate_rejects_profile_without_opponents() {
+        let mut config: CsaClientConfig =
+            toml::from_str("[[profile]]\nname = \"empty\"\n").unwrap();
+        config.server.id = "me".to_string();
+        config.engine.path = PathBuf::from("/usr/bin/engine");
+        assert!(config.validate().is_err());
+    }
 }
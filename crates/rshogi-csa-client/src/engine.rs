@@ -12,6 +12,7 @@ use std::time::Duration;
 
 use anyhow::{Context, Result, anyhow, bail};
 
+use crate::config::toml_value_to_usi;
 use crate::event::Event;
 use crate::protocol::parse_game_result;
 
@@ -154,6 +155,15 @@ pub trait UsiEngineDriver {
     /// USI `usinewgame` 相当を実装する。対局開始前に 1 度呼ばれる。
     fn new_game(&mut self) -> Result<()>;
 
+    /// 対戦相手別プロファイルの USI オプションを `setoption` で送る。
+    ///
+    /// [`UsiEngineDriver::new_game`] の直前に呼ばれる（定跡ファイルなど `isready` で
+    /// 読み込まれるオプションを対局前に反映するため）。既定実装は何もしない。
+    fn set_options(&mut self, options: &[(String, String)]) -> Result<()> {
+        let _ = options;
+        Ok(())
+    }
+
     /// `position` + `go` を送信し、bestmove または server interrupt まで block する。
     ///
     /// 実装の責任:
@@ -220,6 +230,13 @@ impl UsiEngineDriver for UsiEngine {
         UsiEngine::new_game(self)
     }
 
+    fn set_options(&mut self, options: &[(String, String)]) -> Result<()> {
+        for (name, value) in options {
+            self.send(&format!("setoption name {name} value {value}"))?;
+        }
+        Ok(())
+    }
+
     fn go_with_info(
         &mut self,
         position_cmd: &str,
@@ -417,12 +434,8 @@ impl UsiEngine {
 
         // USI オプション設定
         for (key, value) in options {
-            let val_str = match value {
-                toml::Value::Integer(n) => n.to_string(),
-                toml::Value::Boolean(b) => b.to_string(),
-                toml::Value::String(s) => s.clone(),
-                toml::Value::Float(f) => f.to_string(),
-                _ => continue,
+            let Some(val_str) = toml_value_to_usi(value) else {
+                continue;
             };
             self.send(&format!("setoption name {key} value {val_str}"))?;
         }
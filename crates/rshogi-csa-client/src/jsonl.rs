@@ -92,6 +92,9 @@ struct MetaLog<'a> {
     engine_cmd: EngineCommandMeta<'a>,
     start_positions: Vec<String>,
     output: String,
+    /// 選ばれた対戦相手別プロファイルの名前
+    #[serde(skip_serializing_if = "Option::is_none")]
+    profile: Option<&'a str>,
 }
 
 #[derive(Serialize)]
@@ -201,6 +204,7 @@ fn write_meta<W: Write>(
         },
         start_positions,
         output: output_path.display().to_string(),
+        profile: record.profile.as_deref(),
     };
     serde_json::to_writer(&mut *writer, &meta)?;
     writer.write_all(b"\n")?;
@@ -15,13 +15,13 @@
 
 use std::path::PathBuf;
 use std::sync::Arc;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 use std::time::{Duration, Instant};
 
 use anyhow::{Context, Result, anyhow, bail};
 use clap::{ArgAction, Parser, ValueEnum};
 
-use rshogi_csa_client::config::CsaClientConfig;
+use rshogi_csa_client::config::{CsaClientConfig, ShutdownPolicy};
 use rshogi_csa_client::engine::{SpawnOptions, UsiEngine};
 use rshogi_csa_client::events::SessionOutcome;
 use rshogi_csa_client::jsonl::write_game_jsonl;
@@ -245,6 +245,36 @@ fn sleep_retry(effective: Duration, backoff: Duration, shutdown: &AtomicBool) ->
     sleep_with_shutdown(effective, shutdown)
 }
 
+/// 終了シグナル (SIGINT / SIGTERM) を受けたときの処理。`count` は受信回数 (1 始まり)。
+///
+/// - 1 度目: `draining` を立て、新しい対局・ロビー待ち・リトライ待ちを打ち切る。
+///   `ShutdownPolicy::Resign` なら session にも `shutdown` を伝え、次の自手番で投了させる。
+/// - 2 度目以降: `shutdown` を立て、`Finish` でも対局中の局を `%CHUDAN` で即時中断する。
+///
+/// session 側の `shutdown` は「対局を打ち切る」合図なので、`Finish` の 1 度目では立てない。
+fn on_shutdown_signal(
+    count: u32,
+    policy: ShutdownPolicy,
+    draining: &AtomicBool,
+    shutdown: &AtomicBool,
+) {
+    draining.store(true, Ordering::SeqCst);
+    if count >= 2 {
+        log::warn!("終了シグナルを再受信。対局を中断して終了します...");
+        shutdown.store(true, Ordering::SeqCst);
+        return;
+    }
+    match policy {
+        ShutdownPolicy::Finish => {
+            log::info!("終了シグナル受信。対局完了後に終了します (再送で即時中断)...");
+        }
+        ShutdownPolicy::Resign => {
+            log::info!("終了シグナル受信。次の手番で投了して終了します...");
+            shutdown.store(true, Ordering::SeqCst);
+        }
+    }
+}
+
 /// CLI で `--engine-stderr-passthrough` / `--no-engine-stderr-passthrough` のいずれかが
 /// 明示指定された場合のみ `Some(bool)` を返す。未指定時は `None` を返し、TOML/環境変数の
 /// 値をそのまま温存する。`overrides_with` のため両方指定後に最後に勝った方の flag のみ
@@ -300,13 +330,20 @@ fn main() -> Result<()> {
     );
     log::info!("エンジン: {}", config.engine.path.display());
 
-    // SIGINT ハンドラ
+    // SIGINT / SIGTERM ハンドラ。`draining` は新しい対局を受け付けない合図、
+    // `shutdown` は対局中の session を打ち切る (Resign ポリシーでは投了させる) 合図。
+    let draining = Arc::new(AtomicBool::new(false));
     let shutdown = Arc::new(AtomicBool::new(false));
-    let shutdown_clone = shutdown.clone();
-    ctrlc::set_handler(move || {
-        log::info!("終了シグナル受信。対局完了後に終了します...");
-        shutdown_clone.store(true, Ordering::SeqCst);
-    })?;
+    {
+        let draining = Arc::clone(&draining);
+        let shutdown = Arc::clone(&shutdown);
+        let policy = config.game.shutdown_policy;
+        let signals = AtomicU32::new(0);
+        ctrlc::set_handler(move || {
+            let count = signals.fetch_add(1, Ordering::SeqCst) + 1;
+            on_shutdown_signal(count, policy, &draining, &shutdown);
+        })?;
+    }
 
     // エンジン起動（ループ外で保持し再利用する）
     let mut engine = spawn_engine(&config)?;
@@ -319,7 +356,7 @@ fn main() -> Result<()> {
     let mut retry_delay = Duration::from_secs(config.retry.initial_delay_sec);
 
     loop {
-        if shutdown.load(Ordering::SeqCst) {
+        if draining.load(Ordering::SeqCst) {
             log::info!("シャットダウン");
             break;
         }
@@ -330,16 +367,16 @@ fn main() -> Result<()> {
 
         // `--lobby` モードは対局直前に LobbyDO へ問い合わせて room_id を取得する。
         let lobby_room_assignment = if cli.lobby {
-            match acquire_lobby_match(&config, &cli, &shutdown) {
+            match acquire_lobby_match(&config, &cli, &draining) {
                 Ok(Some(assignment)) => Some(assignment),
                 Ok(None) => break, // shutdown
                 Err(e) => {
                     log::error!("ロビー接続エラー: {e}");
-                    if shutdown.load(Ordering::SeqCst) {
+                    if draining.load(Ordering::SeqCst) {
                         break;
                     }
                     let effective = compute_effective_retry_delay(&e.to_string(), retry_delay);
-                    if !sleep_retry(effective, retry_delay, &shutdown) {
+                    if !sleep_retry(effective, retry_delay, &draining) {
                         break;
                     }
                     retry_delay =
@@ -382,6 +419,7 @@ fn main() -> Result<()> {
                     "対局 #{games_played} 結果: {:?} | 通算: {wins}勝 {losses}敗 {draws}分",
                     result
                 );
+                log::info!("対局 #{games_played} 通信: {}", record.link_stats.summary_line());
 
                 // 成功したのでリトライ間隔をリセット
                 retry_delay = Duration::from_secs(config.retry.initial_delay_sec);
@@ -394,13 +432,13 @@ fn main() -> Result<()> {
             }
             Err(e) => {
                 log::error!("対局エラー: {e}");
-                if shutdown.load(Ordering::SeqCst) {
+                if draining.load(Ordering::SeqCst) {
                     break;
                 }
                 // エラー後はエンジンを再起動（不整合な状態の可能性）
                 engine.quit();
                 let effective = compute_effective_retry_delay(&e.to_string(), retry_delay);
-                if !sleep_retry(effective, retry_delay, &shutdown) {
+                if !sleep_retry(effective, retry_delay, &draining) {
                     break;
                 }
                 retry_delay =
@@ -412,6 +450,8 @@ fn main() -> Result<()> {
 
     engine.quit();
     log::info!("終了。合計 {games_played} 局: {wins}勝 {losses}敗 {draws}分");
+    // 棋譜・JSONL は対局ごとに保存済み。ログのバッファだけ終了前に書き出す。
+    log::logger().flush();
     Ok(())
 }
 
@@ -1109,6 +1149,8 @@ mod tests {
                 start_time: chrono::Local::now(),
                 my_color: CsaColor::Black,
                 jsonl_moves: vec![],
+                link_stats: Default::default(),
+                profile: None,
             },
             summary: Some(summary),
         }
@@ -1193,6 +1235,26 @@ mod tests {
     // `compute_effective_retry_delay` の test は protocol.rs 側に集約。
     // ───────────────────────────────────────────────
 
+    #[test]
+    fn shutdown_signal_finish_policy_drains_then_aborts_on_second_signal() {
+        let draining = AtomicBool::new(false);
+        let shutdown = AtomicBool::new(false);
+        on_shutdown_signal(1, ShutdownPolicy::Finish, &draining, &shutdown);
+        assert!(draining.load(Ordering::SeqCst));
+        assert!(!shutdown.load(Ordering::SeqCst), "1 度目は対局を打ち切らない");
+        on_shutdown_signal(2, ShutdownPolicy::Finish, &draining, &shutdown);
+        assert!(shutdown.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn shutdown_signal_resign_policy_signals_session_immediately() {
+        let draining = AtomicBool::new(false);
+        let shutdown = AtomicBool::new(false);
+        on_shutdown_signal(1, ShutdownPolicy::Resign, &draining, &shutdown);
+        assert!(draining.load(Ordering::SeqCst));
+        assert!(shutdown.load(Ordering::SeqCst));
+    }
+
     #[test]
     fn sleep_with_shutdown_completes_when_shutdown_remains_clear() {
         // shutdown が立たないまま delay が経過したら true を返す。
@@ -85,11 +85,35 @@ pub enum GameResult {
     Interrupted,
 }
 
+/// CSA プロトコルが許す keep-alive 空行の最短送信間隔（秒）
+pub const KEEPALIVE_MIN_INTERVAL_SEC: u64 = 30;
+
+/// 送信 keep-alive の統計（対局ごとのログ集計用）
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct KeepaliveStats {
+    /// 送信した keep-alive 空行の数
+    pub pings: u32,
+    /// 送信（指し手・空行を問わない）から次の送信までの最大間隔
+    pub max_send_gap: Duration,
+}
+
+/// 最後の送信から `since_last_send` 経過した時点で keep-alive を送るべきか
+///
+/// 間隔は送信のみで数える（受信では延長しない）。指し手などの通常送信も
+/// keep-alive を兼ねるため、空行は無送信が `interval_sec` 続いたときだけ送る。
+/// `interval_sec` が [`KEEPALIVE_MIN_INTERVAL_SEC`] 未満でもその値に切り上げる。
+fn keepalive_due(since_last_send: Duration, interval_sec: u64) -> bool {
+    interval_sec != 0
+        && since_last_send >= Duration::from_secs(interval_sec.max(KEEPALIVE_MIN_INTERVAL_SEC))
+}
+
 /// CSAプロトコルクライアント
 pub struct CsaConnection {
     /// 下層 transport（TCP / WebSocket）。
     transport: CsaTransport,
-    last_activity_time: Instant,
+    /// 最後に行（空行を含む）を送信した時刻
+    last_send_time: Instant,
+    keepalive_stats: KeepaliveStats,
     /// パスワードマスク用
     password: String,
     /// 直前に受信した終局理由行（#TIME_UP 等）
@@ -115,7 +139,8 @@ impl CsaConnection {
         let transport = CsaTransport::connect(target, opts)?;
         Ok(Self {
             transport,
-            last_activity_time: Instant::now(),
+            last_send_time: Instant::now(),
+            keepalive_stats: KeepaliveStats::default(),
             password: String::new(),
             pending_end_reason: None,
         })
@@ -498,16 +523,28 @@ impl CsaConnection {
 
     /// keep-alive 空行を送信（必要な場合）
     pub fn maybe_send_keepalive(&mut self, interval_sec: u64) -> Result<()> {
-        if interval_sec == 0 {
+        if !keepalive_due(self.last_send_time.elapsed(), interval_sec) {
             return Ok(());
         }
-        if self.last_activity_time.elapsed() >= Duration::from_secs(interval_sec) {
-            self.transport.write_keepalive()?;
-            self.last_activity_time = Instant::now();
-        }
+        log::trace!("[CSA] > (keep-alive)");
+        self.transport.write_keepalive()?;
+        self.keepalive_stats.pings += 1;
+        self.mark_sent();
         Ok(())
     }
 
+    /// これまでの keep-alive 統計を取り出してリセットする
+    pub fn take_keepalive_stats(&mut self) -> KeepaliveStats {
+        std::mem::take(&mut self.keepalive_stats)
+    }
+
+    fn mark_sent(&mut self) {
+        let now = Instant::now();
+        let gap = now.duration_since(self.last_send_time);
+        self.keepalive_stats.max_send_gap = self.keepalive_stats.max_send_gap.max(gap);
+        self.last_send_time = now;
+    }
+
     fn send_line(&mut self, line: &str) -> Result<()> {
         if !self.password.is_empty() && line.contains(&self.password) {
             let masked = line.replace(&self.password, "*****");
@@ -516,7 +553,7 @@ impl CsaConnection {
             log::debug!("[CSA] > {line}");
         }
         self.transport.write_line(line)?;
-        self.last_activity_time = Instant::now();
+        self.mark_sent();
         Ok(())
     }
 
@@ -527,17 +564,11 @@ impl CsaConnection {
     }
 
     fn recv_line_blocking(&mut self, timeout: Duration) -> Result<String> {
-        let line = self.transport.read_line_blocking(timeout)?;
-        self.last_activity_time = Instant::now();
-        Ok(line)
+        self.transport.read_line_blocking(timeout)
     }
 
     fn recv_line_nonblocking(&mut self) -> Result<Option<String>> {
-        let opt = self.transport.read_line_nonblocking()?;
-        if opt.is_some() {
-            self.last_activity_time = Instant::now();
-        }
-        Ok(opt)
+        self.transport.read_line_nonblocking()
     }
 }
 
@@ -643,6 +674,16 @@ pub fn compute_effective_retry_delay(err_msg: &str, retry_delay: Duration) -> Du
 mod tests {
     use super::*;
 
+    #[test]
+    fn keepalive_due_respects_interval_and_protocol_minimum() {
+        assert!(!keepalive_due(Duration::from_secs(600), 0));
+        assert!(!keepalive_due(Duration::from_secs(59), 60));
+        assert!(keepalive_due(Duration::from_secs(60), 60));
+        // 30 秒未満の設定でも 30 秒より短い間隔では送らない
+        assert!(!keepalive_due(Duration::from_secs(29), 10));
+        assert!(keepalive_due(Duration::from_secs(30), 10));
+    }
+
     #[test]
     fn extract_retry_after_sec_parses_lobby_login_format() {
         // `acquire_lobby_match` が `bail!("[Lobby] LOGIN_LOBBY 拒否: {rest}")` を
@@ -1,6 +1,7 @@
 //! 棋譜記録・保存
 
 use std::fmt::Write as _;
+use std::time::Duration;
 
 use anyhow::Result;
 use chrono::Local;
@@ -9,7 +10,7 @@ use rshogi_csa::{Color, Position, usi_move_to_csa};
 
 use crate::config::RecordConfig;
 use crate::engine::SearchInfo;
-use crate::protocol::{GameSummary, TimeConfig};
+use crate::protocol::{GameSummary, KeepaliveStats, TimeConfig};
 
 /// 対局中に蓄積する棋譜データ
 #[derive(Clone, Debug)]
@@ -30,6 +31,51 @@ pub struct GameRecord {
     /// 各要素は `moves[i]` に対応する。投了 / 勝ち宣言など `apply_csa_move` を経由しない
     /// 手は含まれず、ply ベースで一致する。
     pub jsonl_moves: Vec<JsonlMoveExtra>,
+    /// サーバーとの通信統計（対局ログの集計用）。棋譜出力には影響しない。
+    pub link_stats: LinkStats,
+    /// この対局で選ばれた対戦相手別プロファイルの名前
+    pub profile: Option<String>,
+}
+
+/// 対局 1 局分の通信統計
+///
+/// keep-alive の送信状況と、自分の指し手を送ってからサーバーのエコーを
+/// 受信するまでの時間を記録する。長時間対局での切断の切り分けに使う。
+#[derive(Clone, Debug, Default)]
+pub struct LinkStats {
+    pub keepalive: KeepaliveStats,
+    /// 自分の指し手ごとのエコー待ち時間（送信順）
+    pub echo_latencies: Vec<Duration>,
+}
+
+impl LinkStats {
+    /// 対局ログ用の 1 行要約
+    pub fn summary_line(&self) -> String {
+        let mut line = format!(
+            "keepalive pings={} max_send_gap={:.1}s",
+            self.keepalive.pings,
+            self.keepalive.max_send_gap.as_secs_f64()
+        );
+        let mut sorted = self.echo_latencies.clone();
+        sorted.sort_unstable();
+        match sorted.last() {
+            None => line.push_str(" | echo n=0"),
+            Some(max) => {
+                let total: Duration = sorted.iter().sum();
+                let mean = total / sorted.len() as u32;
+                let p90 = sorted[(sorted.len() * 9).div_ceil(10) - 1];
+                let _ = write!(
+                    line,
+                    " | echo n={} mean={}ms p90={}ms max={}ms",
+                    sorted.len(),
+                    mean.as_millis(),
+                    p90.as_millis(),
+                    max.as_millis()
+                );
+            }
+        }
+        line
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -102,9 +148,17 @@ impl GameRecord {
             start_time: Local::now(),
             my_color: summary.my_color,
             jsonl_moves: Vec::new(),
+            link_stats: LinkStats::default(),
+            profile: None,
         }
     }
 
+    /// 選ばれた対戦相手別プロファイルを記録する
+    pub fn with_profile(mut self, profile: Option<String>) -> Self {
+        self.profile = profile;
+        self
+    }
+
     /// JSONL 出力モード向けの追加情報を 1 手分蓄積する。
     /// CSA 棋譜・SFEN 出力にはこのバッファは使われない。
     pub fn add_jsonl_move(&mut self, extra: JsonlMoveExtra) {
@@ -140,6 +194,11 @@ impl GameRecord {
         }
     }
 
+    /// 自分の指し手のエコー待ち時間を記録する
+    pub fn add_echo_latency(&mut self, latency: Duration) {
+        self.link_stats.echo_latencies.push(latency);
+    }
+
     pub fn set_result(&mut self, result: &str) {
         self.result = result.to_string();
     }
@@ -152,6 +211,9 @@ impl GameRecord {
         writeln!(out, "N-{}", self.gote_name).unwrap();
         writeln!(out, "$EVENT:{}", self.game_id).unwrap();
         writeln!(out, "$START_TIME:{}", self.start_time.format("%Y/%m/%d %H:%M:%S")).unwrap();
+        if let Some(profile) = &self.profile {
+            writeln!(out, "'profile:{profile}").unwrap();
+        }
         // 先手の時間設定を $TIME_LIMIT に出力（CSA標準）
         let total_sec = (self.black_time.total_time_ms / 1000) as u32;
         let byoyomi_sec = (self.black_time.byoyomi_ms / 1000) as u32;
@@ -287,3 +349,26 @@ fn sanitize_filename(name: &str) -> String {
         })
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn link_stats_summary_line_reports_keepalive_and_echo_latency() {
+        let mut stats = LinkStats {
+            keepalive: KeepaliveStats {
+                pings: 3,
+                max_send_gap: Duration::from_millis(61_500),
+            },
+            echo_latencies: Vec::new(),
+        };
+        assert_eq!(stats.summary_line(), "keepalive pings=3 max_send_gap=61.5s | echo n=0");
+
+        stats.echo_latencies = (1..=10).rev().map(|i| Duration::from_millis(i * 10)).collect();
+        assert_eq!(
+            stats.summary_line(),
+            "keepalive pings=3 max_send_gap=61.5s | echo n=10 mean=55ms p90=90ms max=100ms"
+        );
+    }
+}
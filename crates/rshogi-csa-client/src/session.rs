@@ -25,7 +25,7 @@ use anyhow::Result;
 
 use rshogi_csa::{Color, Position, csa_move_to_usi, usi_move_to_csa};
 
-use crate::config::CsaClientConfig;
+use crate::config::{CsaClientConfig, ShutdownPolicy};
 use crate::engine::{BestMoveResult, SearchInfo, SearchOutcome, UsiEngine, UsiEngineDriver};
 use crate::event::Event;
 use crate::events::{
@@ -185,6 +185,14 @@ where
     {
         return Err(map_anyhow_to_session_error(err));
     }
+    let profile = config.select_profile(&summary);
+    if let Some(profile) = profile {
+        log::info!("[CSA] 対局プロファイル: {}", profile.name);
+    }
+    let profile_options = config.profile_usi_options(profile, summary.my_color);
+    if let Err(err) = engine.set_options(&profile_options) {
+        return Err(SessionError::Engine(format!("{err}")));
+    }
     if let Err(err) = engine.new_game() {
         return Err(SessionError::Engine(format!("{err}")));
     }
@@ -211,9 +219,10 @@ where
     match loop_result {
         LoopOutcome::GameEnded {
             result,
-            record,
+            mut record,
             game_end_event,
         } => {
+            record.link_stats.keepalive = conn.take_keepalive_stats();
             // GameEnded 発火
             if let Some(action) =
                 emit_with_nonfatal_warn(sink, SessionProgress::GameEnded(game_end_event))
@@ -338,7 +347,8 @@ where
         initial_sfen: summary.position.to_sfen(),
         usi_moves: Vec::new(),
         clock,
-        record: GameRecord::new(&summary),
+        record: GameRecord::new(&summary)
+            .with_profile(config.select_profile(&summary).map(|p| p.name.clone())),
         ponder_state: None,
         my_color: summary.my_color,
         conn,
@@ -386,12 +396,38 @@ where
                 game_already_ended: false,
             };
         }
-        if s.shutdown.load(Ordering::SeqCst) {
+        if s.shutdown.load(Ordering::SeqCst) && !resigns_on_shutdown(&s) {
             return LoopOutcome::Shutdown {
                 game_already_ended: false,
             };
         }
 
+        if s.pos.side_to_move == s.my_color && resigns_on_shutdown(&s) {
+            log::info!("[CSA] shutdown 要求により投了します");
+            if let Err(err) = cleanup_ponder(s.engine, &mut s.ponder_state) {
+                return LoopOutcome::Error(map_anyhow_to_session_error(err));
+            }
+            match resign_and_wait_game_end(&mut s) {
+                MoveAction::GameEnd(result, record_box, game_end_event) => {
+                    return LoopOutcome::GameEnded {
+                        result,
+                        record: record_box,
+                        game_end_event,
+                    };
+                }
+                MoveAction::SinkAborted(sink_err, game_already_ended) => {
+                    return LoopOutcome::SinkAborted {
+                        sink_err,
+                        game_already_ended,
+                    };
+                }
+                MoveAction::Shutdown(game_already_ended) => {
+                    return LoopOutcome::Shutdown { game_already_ended };
+                }
+                MoveAction::Continue => {}
+            }
+        }
+
         if s.pos.side_to_move == s.my_color {
             let turn_start = Instant::now();
             let sfen_before = s.pos.to_sfen();
@@ -402,8 +438,11 @@ where
 
             let outcome = {
                 let mut emitter = SearchInfoEmitter::new(&mut s.info_throttle, s.sink);
+                let conn = &mut *s.conn;
+                let ping_interval_sec = s.config.server.keepalive.ping_interval_sec;
                 let mut info_callback = |info: &SearchInfo, raw: &str| {
                     emitter.observe(info, raw);
+                    keepalive_during_search(conn, ping_interval_sec);
                 };
                 let result = s.engine.go_with_info(
                     &position_cmd,
@@ -551,7 +590,8 @@ where
                     {
                         return LoopOutcome::Error(map_anyhow_to_session_error(err));
                     }
-                    if s.shutdown.load(Ordering::SeqCst) {
+                    // Resign ポリシーでは相手の手を待ち、次の自分の手番で投了する
+                    if s.shutdown.load(Ordering::SeqCst) && !resigns_on_shutdown(&s) {
                         return LoopOutcome::Shutdown {
                             game_already_ended: false,
                         };
@@ -605,6 +645,45 @@ where
     }
 }
 
+/// `ShutdownPolicy::Resign` で shutdown が要求されているか
+fn resigns_on_shutdown<E, S>(s: &SessionState<'_, E, S>) -> bool
+where
+    E: UsiEngineDriver + ?Sized,
+    S: SessionEventSink + ?Sized,
+{
+    s.config.game.shutdown_policy == ShutdownPolicy::Resign && s.shutdown.load(Ordering::SeqCst)
+}
+
+/// `%TORYO` を送信し、サーバーからの終局通知を待つ
+fn resign_and_wait_game_end<E, S>(s: &mut SessionState<'_, E, S>) -> MoveAction
+where
+    E: UsiEngineDriver + ?Sized,
+    S: SessionEventSink + ?Sized,
+{
+    if let Err(err) = s.conn.send_resign() {
+        return MoveAction::sink_or_error(err);
+    }
+    s.record.set_result("resign");
+    let (game_result, reason_line, raw_result_line) = wait_game_end_full_from_rx(s.server_rx);
+    if let Err(err) = s.engine.gameover(gameover_str(&game_result)) {
+        return MoveAction::sink_or_error(err);
+    }
+    let game_end_event =
+        build_game_end_event(&game_result, reason_line, raw_result_line, s.my_color);
+    MoveAction::GameEnd(game_result, Box::new(s.record.clone()), game_end_event)
+}
+
+/// 自手番の探索中（info 受信ごと）に keep-alive を送る
+///
+/// 長考中はサーバーへの送信が途絶えるため、相手番と同じ間隔で空行を送る。
+/// info callback は失敗を返せないので、送信エラーは警告ログに留める
+/// （切断は reader thread 側の `ServerDisconnected` で検出される）。
+fn keepalive_during_search(conn: &mut CsaConnection, ping_interval_sec: u64) {
+    if let Err(err) = conn.maybe_send_keepalive(ping_interval_sec) {
+        log::warn!("[CSA] 探索中の keep-alive 送信に失敗: {err}");
+    }
+}
+
 fn send_bestmove_and_wait_echo<E, S>(
     s: &mut SessionState<'_, E, S>,
     result: &BestMoveResult,
@@ -618,18 +697,9 @@ where
     E: UsiEngineDriver + ?Sized,
     S: SessionEventSink + ?Sized,
 {
-    if result.bestmove == "resign" {
-        if let Err(err) = s.conn.send_resign() {
-            return MoveAction::sink_or_error(err);
-        }
-        s.record.set_result("resign");
-        let (game_result, reason_line, raw_result_line) = wait_game_end_full_from_rx(s.server_rx);
-        if let Err(err) = s.engine.gameover(gameover_str(&game_result)) {
-            return MoveAction::sink_or_error(err);
-        }
-        let game_end_event =
-            build_game_end_event(&game_result, reason_line, raw_result_line, s.my_color);
-        return MoveAction::GameEnd(game_result, Box::new(s.record.clone()), game_end_event);
+    // shutdown で打ち切られた探索の bestmove は指さずに投了する
+    if result.bestmove == "resign" || resigns_on_shutdown(s) {
+        return resign_and_wait_game_end(s);
     }
     if result.bestmove == "win" {
         if let Err(err) = s.conn.send_win() {
@@ -675,6 +745,8 @@ where
     if let Err(err) = s.conn.send_move_with_comment(&csa_move, comment.as_deref()) {
         return MoveAction::sink_or_error(err);
     }
+    // エコー待ち時間の起点（ponder 開始より前に取る）
+    let sent_at = Instant::now();
 
     // 局面適用
     if let Err(err) = s.pos.apply_csa_move(&csa_move) {
@@ -745,6 +817,7 @@ where
                     let (_, time_sec) = parse_server_move(&line);
                     s.clock.consume(s.my_color, time_sec);
                     s.record.update_last_time(time_sec);
+                    s.record.add_echo_latency(sent_at.elapsed());
                     // MoveConfirmed 発火 (自エンジンの手、time_sec 確定)
                     let confirmed_event = MoveEvent {
                         player: MovePlayer::SelfPlayer,
@@ -813,7 +886,7 @@ where
                 {
                     return MoveAction::sink_or_error(err);
                 }
-                if s.shutdown.load(Ordering::SeqCst) {
+                if s.shutdown.load(Ordering::SeqCst) && !resigns_on_shutdown(s) {
                     return MoveAction::Shutdown(false);
                 }
             }
@@ -881,8 +954,11 @@ where
             let my_think_limit_ms = s.clock.think_limit_ms(s.config.time.margin_msec, s.my_color);
             let outcome = {
                 let mut emitter = SearchInfoEmitter::new(&mut s.info_throttle, s.sink);
+                let conn = &mut *s.conn;
+                let ping_interval_sec = s.config.server.keepalive.ping_interval_sec;
                 let mut info_callback = |info: &SearchInfo, raw: &str| {
                     emitter.observe(info, raw);
+                    keepalive_during_search(conn, ping_interval_sec);
                 };
                 let result =
                     s.engine.ponderhit_with_info(s.shutdown, s.server_rx, &mut info_callback);
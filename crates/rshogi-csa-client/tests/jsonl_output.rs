@@ -36,6 +36,8 @@ fn build_record(my_color: Color) -> GameRecord {
         start_time: chrono::Local::now(),
         my_color,
         jsonl_moves: Vec::new(),
+        link_stats: Default::default(),
+        profile: None,
     }
 }
 
@@ -172,6 +174,26 @@ fn draws_when_interrupted_regardless_of_side() {
     assert!(res["winner"].is_null());
 }
 
+#[test]
+fn meta_records_selected_profile() {
+    let tmp = tempdir();
+    let config = build_config();
+
+    let record = build_record(Color::Black).with_profile(Some("teaching".to_string()));
+    let path = write_game_jsonl(&tmp, &record, &config, &GameResult::Draw).expect("write");
+    let lines = std::fs::read_to_string(&path).expect("read jsonl");
+    let meta: Value = serde_json::from_str(lines.lines().next().unwrap()).unwrap();
+    assert_eq!(meta["profile"], "teaching");
+
+    // プロファイルが選ばれなかった対局では `profile` キー自体を出さない
+    let tmp = tempdir();
+    let record = build_record(Color::Black);
+    let path = write_game_jsonl(&tmp, &record, &config, &GameResult::Draw).expect("write");
+    let lines = std::fs::read_to_string(&path).expect("read jsonl");
+    let meta: Value = serde_json::from_str(lines.lines().next().unwrap()).unwrap();
+    assert!(meta.get("profile").is_none());
+}
+
 #[test]
 fn filename_includes_datetime_and_player_names() {
     let record = build_record(Color::Black);
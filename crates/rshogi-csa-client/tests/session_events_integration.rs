@@ -23,13 +23,13 @@ use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::Duration;
 
-use rshogi_csa_client::config::CsaClientConfig;
+use rshogi_csa_client::config::{CsaClientConfig, ShutdownPolicy};
 use rshogi_csa_client::engine::{SpawnOptions, UsiEngine};
 use rshogi_csa_client::events::{
     DisconnectReason, MovePlayer, ReconnectState, SearchInfoEmitPolicy, SessionError,
     SessionEventSink, SessionProgress, SinkError,
 };
-use rshogi_csa_client::protocol::CsaConnection;
+use rshogi_csa_client::protocol::{CsaConnection, GameResult};
 use rshogi_csa_client::session::{
     run_game_session, run_game_session_with_events, run_resumed_session_with_events,
 };
@@ -733,6 +733,64 @@ fn external_shutdown_observed_through_legacy_run_game_session() {
     }
 }
 
+/// `ShutdownPolicy::Resign` では shutdown を `%CHUDAN` ではなく投了として扱い、
+/// 終局通知を受けてから `LOGOUT` する (棋譜には resign として残る)。
+#[test]
+fn resign_policy_shutdown_resigns_and_waits_for_game_end() {
+    let port = spawn_mock_tcp_server(|reader, writer| {
+        let _ = read_line(reader);
+        write_lines(writer, &["LOGIN:alice OK"]);
+        let lines = game_summary_lines("g-resign-sd");
+        let line_refs: Vec<&str> = lines.iter().map(String::as_str).collect();
+        write_lines(writer, &line_refs);
+        let _ = read_line(reader); // AGREE
+        write_lines(writer, &["START:g-resign-sd"]);
+        let resign = read_line(reader);
+        assert_eq!(resign, "%TORYO");
+        write_lines(writer, &["%TORYO,T0", "#RESIGN", "#LOSE"]);
+        let logout = read_line(reader);
+        assert!(logout.contains("LOGOUT"), "got {logout}");
+    });
+
+    let engine_path = mock_usi_engine_script();
+    let mut config = mock_config(engine_path, SearchInfoEmitPolicy::Disabled);
+    config.game.shutdown_policy = ShutdownPolicy::Resign;
+    // 対局開始前から shutdown 済み: 最初の自手番で探索せずに投了する
+    let shutdown = Arc::new(AtomicBool::new(true));
+
+    let mut conn = CsaConnection::connect("127.0.0.1", port, false).expect("connect");
+    conn.login("alice", "pw").expect("login");
+
+    let mut engine = UsiEngine::spawn(
+        &config.engine.path,
+        &config.engine.options,
+        SpawnOptions {
+            ponder: config.game.ponder,
+            startup_timeout: Duration::from_secs(5),
+            stderr_passthrough: false,
+        },
+    )
+    .expect("spawn engine");
+
+    let (sink, (events, _err_calls)) = CapturingSink::new();
+    let mut sink = sink;
+    let outcome = run_game_session_with_events(
+        &config,
+        &mut conn,
+        &mut engine,
+        Arc::clone(&shutdown),
+        &mut sink,
+    );
+
+    engine.quit();
+
+    let outcome = outcome.expect("resign policy should end the game normally");
+    assert_eq!(outcome.result, GameResult::Lose);
+    let events = events.lock().unwrap().clone();
+    assert!(events.contains(&"Disconnected:GameOver"), "events: {events:?}");
+    assert!(!events.contains(&"Disconnected:Shutdown"), "events: {events:?}");
+}
+
 // ────────────────────────────────────────────
 // NonFatal は対局継続
 // ────────────────────────────────────────────
@@ -80,6 +80,13 @@ struct Cli {
     /// global clock を全 `game_name` で使用する（後方互換）。
     #[arg(long, value_name = "PATH")]
     clock_presets_toml: Option<PathBuf>,
+    /// 対局ライフサイクル Webhook の TOML パス。`[[destination]]` 配列で通知先
+    /// `url` と対象 `events` (`game_started` / `game_finished` / `game_aborted` /
+    /// `player_disconnected`、省略時は全種別)・`format` (`json` / `slack` /
+    /// `discord`)・HMAC 署名用 `secret` を宛先ごとに宣言する。トップレベルの
+    /// `max_attempts` / `initial_backoff_ms` / `request_timeout_ms` で再送を調整する。
+    #[arg(long, value_name = "PATH")]
+    webhooks_toml: Option<PathBuf>,
     /// 同名ログイン重複時に旧セッションを evict する（既定は新接続を拒否）。
     /// `--allow-floodgate-features` opt-in が必須。`AgreeWaiting` 以降の
     /// 対局進行中セッションは evict されず新接続を拒否する。
@@ -241,6 +248,14 @@ fn main() -> anyhow::Result<()> {
             HashMap::new()
         };
 
+    // Webhook 通知設定 TOML を読み込む（指定時のみ）。未指定なら通知しない。
+    let webhooks = if let Some(path) = cli.webhooks_toml.as_ref() {
+        load_webhooks_toml(path)
+            .with_context(|| format!("failed to load webhooks TOML at {path:?}"))?
+    } else {
+        rshogi_csa_server::WebhookConfig::default()
+    };
+
     // 2. ServerConfig を構築。
     let config = ServerConfig {
         bind_addr,
@@ -284,6 +299,7 @@ fn main() -> anyhow::Result<()> {
         // 組み合わせて十分な expire 検出が得られるため、CLI 露出は YAGNI で
         // 固定 60 秒に閉じる。
         challenge_purge_interval: std::time::Duration::from_secs(60),
+        webhooks,
     };
     // Floodgate 系機能の opt-in ゲートを起動前に評価する。`players_yaml_path` が
     // `Some` の状態は `enable_persistent_player_rates` 要求として intent に乗るため、
@@ -702,6 +718,37 @@ fn load_floodgate_schedule_toml(
     Ok(root.schedules)
 }
 
+/// Webhook 通知設定 TOML を読む。
+///
+/// 期待する形式:
+/// ```toml
+/// max_attempts = 5
+///
+/// [[destination]]
+/// url = "https://hooks.slack.com/services/XXX"
+/// events = ["game_finished", "game_aborted"]
+/// format = "slack"
+/// ```
+fn load_webhooks_toml(path: &std::path::Path) -> anyhow::Result<rshogi_csa_server::WebhookConfig> {
+    let raw = std::fs::read_to_string(path)?;
+    parse_webhooks_toml_str(&raw)
+}
+
+/// `load_webhooks_toml` の入力 TOML 文字列パース部分。URL の形だけ検査し、
+/// 起動後に全通知が失敗し続ける設定ミスを起動時に弾く。
+fn parse_webhooks_toml_str(raw: &str) -> anyhow::Result<rshogi_csa_server::WebhookConfig> {
+    let config: rshogi_csa_server::WebhookConfig = toml::from_str(raw)?;
+    for dest in &config.destinations {
+        if !(dest.url.starts_with("https://") || dest.url.starts_with("http://")) {
+            anyhow::bail!("webhook url must start with http:// or https://: {}", dest.url);
+        }
+    }
+    if config.max_attempts == 0 {
+        anyhow::bail!("max_attempts must be at least 1");
+    }
+    Ok(config)
+}
+
 /// インメモリの `RateStorage`。再起動時は players.toml から再構築する前提で、
 /// 実行中の書き戻し先は持たない（永続書き戻しを付けるなら別 impl を差し込む）。
 pub struct InMemoryRateStorage {
@@ -922,4 +969,57 @@ byoyomi_sec = 0
         let map = parse_clock_presets_toml_str(raw).unwrap();
         assert_eq!(map.len(), 1);
     }
+
+    /// 宛先ごとの `events` / `format` / `secret` と再送設定を読み、省略した
+    /// 項目は既定値（全種別・JSON・署名なし）になる。
+    #[test]
+    fn parse_webhooks_toml_reads_per_destination_settings() {
+        let raw = r#"
+max_attempts = 3
+
+[[destination]]
+url = "https://hooks.slack.com/services/XXX"
+events = ["game_finished", "game_aborted"]
+format = "slack"
+
+[[destination]]
+url = "http://127.0.0.1:8080/csa"
+secret = "s3cret"
+"#;
+        let config = parse_webhooks_toml_str(raw).unwrap();
+        assert_eq!(config.max_attempts, 3);
+        assert_eq!(config.initial_backoff_ms, 1_000);
+        assert_eq!(config.destinations.len(), 2);
+        let slack = &config.destinations[0];
+        assert_eq!(slack.format, rshogi_csa_server::WebhookFormat::Slack);
+        assert!(slack.accepts(rshogi_csa_server::WebhookEventKind::GameAborted));
+        assert!(!slack.accepts(rshogi_csa_server::WebhookEventKind::GameStarted));
+        let json = &config.destinations[1];
+        assert_eq!(json.format, rshogi_csa_server::WebhookFormat::Json);
+        assert!(json.accepts(rshogi_csa_server::WebhookEventKind::PlayerDisconnected));
+        assert_eq!(json.secret.as_deref(), Some("s3cret"));
+    }
+
+    /// URL スキームの誤りと未知のイベント名は起動時に弾く。
+    #[test]
+    fn parse_webhooks_toml_rejects_bad_url_and_unknown_event() {
+        let err = parse_webhooks_toml_str(
+            r#"
+[[destination]]
+url = "hooks.example.com/csa"
+"#,
+        )
+        .unwrap_err();
+        assert!(format!("{err:#}").contains("http"), "{err:#}");
+
+        let err = parse_webhooks_toml_str(
+            r#"
+[[destination]]
+url = "https://hooks.example.com/csa"
+events = ["game_paused"]
+"#,
+        )
+        .unwrap_err();
+        assert!(format!("{err:#}").contains("game_paused"), "{err:#}");
+    }
 }
@@ -9,6 +9,7 @@
 //! - [`rate_limit::IpLoginRateLimiter`]: 同一 IP からの LOGIN 試行を制限するイン・メモリ実装。
 //! - [`auth`]: パスワードハッシュ照合と `RateStorage` 経由の認証経路。
 //! - [`server::run_server`]: accept ループと 1 接続分のタスク spawn を担うエントリ関数。
+//! - [`webhook::WebhookNotifier`]: 対局ライフサイクルの Webhook を再送・署名付きで送る通知口。
 
 // Workers 側のアダプタを本クレートに取り込んでしまうのは設計上の事故。
 // feature unification で誤って立ってしまった場合、コンパイル時点で止める。
@@ -26,6 +27,7 @@ pub mod rate_limit;
 pub mod scheduler;
 pub mod server;
 pub mod transport;
+pub mod webhook;
 
 pub use auth::{AuthError, AuthOutcome, PasswordHasher, PlainPasswordHasher, authenticate};
 pub use broadcaster::InMemoryBroadcaster;
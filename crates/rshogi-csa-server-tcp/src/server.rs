@@ -56,6 +56,7 @@ use rshogi_csa_server::record::kifu::{
 use rshogi_csa_server::types::{
     Color, CsaLine, CsaMoveToken, GameId, GameName, PlayerName, ReconnectToken, RoomId, Secret,
 };
+use rshogi_csa_server::webhook::{WebhookConfig, WebhookEvent, WebhookEventKind};
 use rshogi_csa_server::{FileKifuStorage, TransportError};
 use tokio::net::{TcpListener, TcpStream};
 use tokio::sync::{Mutex, Notify, oneshot};
@@ -66,6 +67,7 @@ use crate::auth::{AuthOutcome, PasswordHasher, authenticate};
 use crate::broadcaster::{InMemoryBroadcaster, Subscriber};
 use crate::rate_limit::IpLoginRateLimiter;
 use crate::transport::TcpTransport;
+use crate::webhook::WebhookNotifier;
 
 /// プレイヤハンドル1 件分の期待形式 (`<handle>+<game_name>+<color>`) を分解する。
 ///
@@ -309,6 +311,9 @@ pub struct ServerConfig {
     /// 内で起動する `challenge_purge_loop` task が参照する (TTL purge loop の
     /// 配線時に活用される、それまでは config として保持されるのみ)。
     pub challenge_purge_interval: Duration,
+    /// 対局ライフサイクル（開始・終局・不成立／中断・対局者切断）の Webhook
+    /// 通知先と再送設定。`destinations` が空（既定）なら通知しない。
+    pub webhooks: WebhookConfig,
 }
 
 impl ServerConfig {
@@ -337,6 +342,7 @@ impl ServerConfig {
             reconnect_grace_duration: Duration::ZERO,
             challenge_ttl: Duration::from_secs(3600),
             challenge_purge_interval: Duration::from_secs(60),
+            webhooks: WebhookConfig::default(),
         }
     }
 }
@@ -804,6 +810,8 @@ where
     /// に対し、本 map は `Arc<Notify>` と `oneshot::Sender<MatchRequest>` という
     /// serialize 不能な runtime 値を保持するため frontend 側に分離する。
     pub(crate) tcp_challenge_pending: TcpChallengePending,
+    /// `config.webhooks` から組み立てた Webhook 通知口。宛先未設定なら no-op。
+    webhooks: WebhookNotifier,
 }
 
 impl<R, K, P, H> SharedState<R, K, P, H>
@@ -2345,6 +2353,7 @@ where
         &result_code_slot,
     )
     .await;
+    notify_game_aborted(&state, &game_id, &game_name, &matched, &result_code_slot, &inner);
 
     // 後始末は inner の結果に関係なく必ず走る。`league` ロックを保持したまま
     // `session_cancellers` まで取りに行くことで、「end_game + logout で League が
@@ -2508,6 +2517,14 @@ where
             started_at: started_at_iso,
         });
     }
+    state.webhooks.notify(WebhookEvent::new(
+        WebhookEventKind::GameStarted,
+        game_id,
+        &game_name,
+        &matched.black,
+        &matched.white,
+        start_time,
+    ));
 
     // 指し手と消費時間を記録しつつ終局まで駆動する。再接続経路で使う handle/token と
     // Game_Summary builder への参照も渡す。`reconnect_grace_duration` が `Duration::ZERO`
@@ -2518,6 +2535,7 @@ where
         black_token: black_reconnect_token.as_ref(),
         white_token: white_reconnect_token.as_ref(),
         summary: &summary,
+        game_name: &game_name,
     };
     let result_moves = run_game_loop_and_record(
         state,
@@ -2570,6 +2588,17 @@ where
     // が `csa_games_finished_total{result_code}` を正しいラベルで +1 する。
     // `csa_games_total` と `csa_games_finished_total` の総和不変条件が崩れない。
     result_code_slot.set(Some(primary_result_code(&result)));
+    state.webhooks.notify(
+        WebhookEvent::new(
+            WebhookEventKind::GameFinished,
+            game_id,
+            &game_name,
+            &matched.black,
+            &matched.white,
+            end_time,
+        )
+        .with_result(&result),
+    );
 
     // 棋譜 + 00LIST 永続化。`time_section` は `drive_game_inner` 入口で
     // `clock_spec` から解決済の値を再利用する (二重 resolve を避けるため
@@ -2590,6 +2619,45 @@ where
     Ok(())
 }
 
+/// `drive_game_inner` が終局確定（`result_code_slot` の設定）に至らずに戻った
+/// 対局を `game_aborted` として Webhook 通知する。
+///
+/// `Ok` で戻るのは AGREE 不成立（REJECT / `%CHUDAN` / AGREE 待ち中の切断）の
+/// 経路だけなので理由は `agree_failed`、`Err` は送信失敗・内部エラーの文言を
+/// そのまま載せる。
+fn notify_game_aborted<R, K, P, H>(
+    state: &SharedState<R, K, P, H>,
+    game_id: &GameId,
+    game_name: &GameName,
+    matched: &MatchedPair,
+    result_code_slot: &Rc<std::cell::Cell<Option<&'static str>>>,
+    inner: &Result<(), ServerError>,
+) where
+    R: RateStorage + 'static,
+    K: KifuStorage + 'static,
+    P: PasswordStore + 'static,
+    H: FloodgateHistoryStorage + 'static,
+{
+    if result_code_slot.get().is_some() {
+        return;
+    }
+    let reason = match inner {
+        Ok(()) => "agree_failed".to_owned(),
+        Err(e) => e.to_string(),
+    };
+    state.webhooks.notify(
+        WebhookEvent::new(
+            WebhookEventKind::GameAborted,
+            game_id,
+            game_name,
+            &matched.black,
+            &matched.white,
+            chrono::Utc::now(),
+        )
+        .with_reason(reason),
+    );
+}
+
 /// 複数行文字列（`Game_Summary` 等）を `ClientTransport::send_line` に分解して送る。
 async fn send_multiline<T: ClientTransport>(
     transport: &mut T,
@@ -2764,6 +2832,8 @@ struct ReconnectContext<'a> {
     black_token: Option<&'a ReconnectToken>,
     white_token: Option<&'a ReconnectToken>,
     summary: &'a GameSummaryBuilder,
+    /// 切断時の Webhook 通知（`player_disconnected`）に載せる `game_name`。
+    game_name: &'a GameName,
 }
 
 async fn run_game_loop_and_record<R, K, P, H>(
@@ -2807,6 +2877,21 @@ where
         let r = match evt {
             Evt::Recv(from, Ok(line)) => room.handle_line(from, &line, now_ms())?,
             Evt::Recv(from, Err(TransportError::Closed | TransportError::Timeout)) => {
+                let player = match from {
+                    Color::Black => reconnect_ctx.black_handle,
+                    Color::White => reconnect_ctx.white_handle,
+                };
+                state.webhooks.notify(
+                    WebhookEvent::new(
+                        WebhookEventKind::PlayerDisconnected,
+                        game_id,
+                        reconnect_ctx.game_name,
+                        reconnect_ctx.black_handle,
+                        reconnect_ctx.white_handle,
+                        chrono::Utc::now(),
+                    )
+                    .with_player(player),
+                );
                 let grace = state.config.reconnect_grace_duration;
                 if grace.is_zero() {
                     room.force_abnormal(from)
@@ -3634,6 +3719,7 @@ where
         &result_code_slot,
     )
     .await;
+    notify_game_aborted(&state, &game_id, &game_name, &matched, &result_code_slot, &inner);
 
     // private 経路の epilogue は public と非対称。具体的には:
     // - `League::end_game` / `League::logout` は呼ばない (private 経路は
@@ -4063,6 +4149,7 @@ where
     H: FloodgateHistoryStorage + 'static,
 {
     let buoy_storage = rshogi_csa_server::FileBuoyStorage::new(config.kifu_topdir.clone());
+    let webhooks = WebhookNotifier::new(config.webhooks.clone());
     SharedState {
         config,
         league: Mutex::new(League::new()),
@@ -4085,6 +4172,7 @@ where
         reconnect_pending: Mutex::new(HashMap::new()),
         challenge_registry: Mutex::new(ChallengeRegistry::new()),
         tcp_challenge_pending: TcpChallengePending::new(),
+        webhooks,
     }
 }
 
@@ -0,0 +1,246 @@
+//! 対局ライフサイクル Webhook の HTTP 送信。
+//!
+//! ペイロード・宛先フィルタ・署名はコアの [`rshogi_csa_server::webhook`] が
+//! 決め、本モジュールは宛先ごとに送信タスクを spawn して再送を回す。
+//!
+//! 送信は対局タスクから切り離した fire-and-forget で、宛先の遅延や障害が
+//! 指し手処理や終局処理を待たせることはない。再送は 5xx / 429 / 通信エラーに
+//! 限り、[`WebhookConfig::max_attempts`] 回まで指数バックオフで行う。
+//! プロセス終了時に送信中・再送待ちの通知は破棄される（永続キューは持たない）。
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use rshogi_csa_server::webhook::{
+    EVENT_HEADER, SIGNATURE_HEADER, WebhookConfig, WebhookDestination, WebhookEvent,
+    is_retryable_status,
+};
+
+/// 宛先設定と HTTP クライアントを束ねた通知口。
+///
+/// `SharedState` に 1 つだけ持たせ、対局タスクから [`Self::notify`] を呼ぶ。
+/// 宛先が 1 件も無い構成では HTTP クライアントを作らず、`notify` は何もしない。
+pub struct WebhookNotifier {
+    config: Arc<WebhookConfig>,
+    client: Option<reqwest::Client>,
+}
+
+impl WebhookNotifier {
+    /// 設定から通知口を作る。HTTP クライアントの初期化に失敗した場合は
+    /// エラーログを出して通知を無効化する（対局サーバー自体は止めない）。
+    pub fn new(config: WebhookConfig) -> Self {
+        let client = if config.is_empty() {
+            None
+        } else {
+            match reqwest::Client::builder()
+                .timeout(Duration::from_millis(config.request_timeout_ms))
+                .build()
+            {
+                Ok(client) => Some(client),
+                Err(e) => {
+                    tracing::error!(error = %e, "failed to build webhook HTTP client; webhooks disabled");
+                    None
+                }
+            }
+        };
+        Self {
+            config: Arc::new(config),
+            client,
+        }
+    }
+
+    /// 通知が有効か（宛先があり、HTTP クライアントを作れたか）。
+    pub fn is_enabled(&self) -> bool {
+        self.client.is_some()
+    }
+
+    /// `event` を受け付ける全宛先へ送信タスクを spawn する。
+    ///
+    /// tokio ランタイム上から呼ぶこと。送信結果は待たずに即座に戻る。
+    pub fn notify(&self, event: WebhookEvent) {
+        let Some(client) = &self.client else {
+            return;
+        };
+        for dest in self.config.destinations.iter().filter(|d| d.accepts(event.event)) {
+            tokio::spawn(deliver(
+                client.clone(),
+                Arc::clone(&self.config),
+                dest.clone(),
+                event.clone(),
+            ));
+        }
+    }
+}
+
+/// 1 宛先へ 1 イベントを送る。失敗時は設定に従って再送する。
+///
+/// 最終的に届いたら `true`、諦めたら `false`。
+async fn deliver(
+    client: reqwest::Client,
+    config: Arc<WebhookConfig>,
+    dest: WebhookDestination,
+    event: WebhookEvent,
+) -> bool {
+    let body = dest.render_body(&event);
+    let signature = dest.signature(body.as_bytes());
+    let max_attempts = config.max_attempts.max(1);
+    for attempt in 1..=max_attempts {
+        let mut request = client
+            .post(&dest.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(EVENT_HEADER, event.event.as_str())
+            .body(body.clone());
+        if let Some(sig) = &signature {
+            request = request.header(SIGNATURE_HEADER, sig);
+        }
+        let retryable = match request.send().await {
+            Ok(resp) if resp.status().is_success() => {
+                tracing::debug!(
+                    url = %dest.url,
+                    event = event.event.as_str(),
+                    game_id = %event.game_id,
+                    attempt,
+                    "webhook delivered"
+                );
+                return true;
+            }
+            Ok(resp) => {
+                let status = resp.status().as_u16();
+                tracing::warn!(
+                    url = %dest.url,
+                    event = event.event.as_str(),
+                    game_id = %event.game_id,
+                    attempt,
+                    status,
+                    "webhook rejected"
+                );
+                is_retryable_status(status)
+            }
+            Err(e) => {
+                tracing::warn!(
+                    url = %dest.url,
+                    event = event.event.as_str(),
+                    game_id = %event.game_id,
+                    attempt,
+                    error = %e,
+                    "webhook request failed"
+                );
+                true
+            }
+        };
+        if !retryable || attempt == max_attempts {
+            break;
+        }
+        tokio::time::sleep(config.backoff_after(attempt)).await;
+    }
+    tracing::error!(
+        url = %dest.url,
+        event = event.event.as_str(),
+        game_id = %event.game_id,
+        "webhook delivery gave up"
+    );
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rshogi_csa_server::types::{GameId, GameName, PlayerName};
+    use rshogi_csa_server::webhook::{WebhookEventKind, WebhookFormat, hmac_sha256};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// 受信したリクエスト（ヘッダ部を小文字化したもの、ボディ）。
+    struct Received {
+        head: String,
+        body: String,
+    }
+
+    /// `statuses` の順にステータスを返す最小 HTTP サーバー。1 接続 1 リクエスト。
+    async fn spawn_server(statuses: Vec<u16>) -> (String, tokio::task::JoinHandle<Vec<Received>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let handle = tokio::spawn(async move {
+            let mut received = Vec::new();
+            for status in statuses {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut buf = Vec::new();
+                let mut chunk = [0u8; 4096];
+                let (head, body) = loop {
+                    let n = stream.read(&mut chunk).await.unwrap();
+                    buf.extend_from_slice(&chunk[..n]);
+                    let text = String::from_utf8_lossy(&buf).into_owned();
+                    if let Some(pos) = text.find("\r\n\r\n") {
+                        let head = text[..pos].to_ascii_lowercase();
+                        let len = head
+                            .lines()
+                            .find_map(|l| l.strip_prefix("content-length: "))
+                            .map_or(0, |v| v.trim().parse::<usize>().unwrap());
+                        if buf.len() >= pos + 4 + len {
+                            break (head, text[pos + 4..pos + 4 + len].to_owned());
+                        }
+                    }
+                };
+                let response = format!(
+                    "HTTP/1.1 {status} X\r\ncontent-length: 0\r\nconnection: close\r\n\r\n"
+                );
+                stream.write_all(response.as_bytes()).await.unwrap();
+                received.push(Received { head, body });
+            }
+            received
+        });
+        (url, handle)
+    }
+
+    fn event() -> WebhookEvent {
+        WebhookEvent::new(
+            WebhookEventKind::GameStarted,
+            &GameId::new("20260102030405_0001"),
+            &GameName::new("byoyomi-600-10"),
+            &PlayerName::new("alice"),
+            &PlayerName::new("bob"),
+            chrono::Utc::now(),
+        )
+    }
+
+    fn config(url: &str, max_attempts: u32) -> Arc<WebhookConfig> {
+        Arc::new(WebhookConfig {
+            destinations: vec![WebhookDestination {
+                url: url.to_owned(),
+                events: Vec::new(),
+                format: WebhookFormat::Json,
+                secret: Some("s3cret".to_owned()),
+            }],
+            max_attempts,
+            initial_backoff_ms: 10,
+            ..WebhookConfig::default()
+        })
+    }
+
+    #[tokio::test]
+    async fn deliver_retries_server_errors_and_signs_body() {
+        let (url, server) = spawn_server(vec![503, 200]).await;
+        let config = config(&url, 3);
+        let dest = config.destinations[0].clone();
+        assert!(deliver(reqwest::Client::new(), config, dest, event()).await);
+
+        let received = server.await.unwrap();
+        assert_eq!(received.len(), 2);
+        let last = &received[1];
+        assert!(last.head.contains("x-rshogi-event: game_started"));
+        let mac = hmac_sha256(b"s3cret", last.body.as_bytes());
+        let hex: String = mac.iter().map(|b| format!("{b:02x}")).collect();
+        assert!(last.head.contains(&format!("x-rshogi-signature: sha256={hex}")));
+        let value: serde_json::Value = serde_json::from_str(&last.body).unwrap();
+        assert_eq!(value["black"], "alice");
+    }
+
+    #[tokio::test]
+    async fn deliver_gives_up_on_client_errors() {
+        let (url, server) = spawn_server(vec![404]).await;
+        let config = config(&url, 5);
+        let dest = config.destinations[0].clone();
+        assert!(!deliver(reqwest::Client::new(), config, dest, event()).await);
+        assert_eq!(server.await.unwrap().len(), 1);
+    }
+}
@@ -284,8 +284,8 @@ impl GameRoom {
                 self.handle_reject(from)
             }
             ClientCommand::Move { token, .. } => self.handle_move(from, &token, now_ms),
-            ClientCommand::Toryo => self.handle_toryo(from),
-            ClientCommand::Kachi => self.handle_kachi(from),
+            ClientCommand::Toryo => self.handle_toryo(from, now_ms),
+            ClientCommand::Kachi => self.handle_kachi(from, now_ms),
             ClientCommand::Chudan => self.handle_chudan(from),
             // LOGIN/LOGOUT は接続ハンドラ側の責務。GameRoom には到達しない想定。
             ClientCommand::Login { .. } | ClientCommand::Logout => {
@@ -496,10 +496,7 @@ impl GameRoom {
         now_ms: u64,
     ) -> Result<HandleResult, ServerError> {
         // 1. 経過時間を計算し通信マージンを差し引いて時計を消費。
-        let started = self.turn_started_at_ms.unwrap_or(now_ms);
-        let raw_elapsed_ms = now_ms.saturating_sub(started);
-        let elapsed_ms = raw_elapsed_ms.saturating_sub(self.config.time_margin_ms);
-        let clock_result = self.clock.consume(from, elapsed_ms);
+        let (elapsed_ms, clock_result) = self.consume_turn_time(from, now_ms);
 
         // 2. 時間切れなら盤面を進めず終局（手は受理しない）。
         if matches!(clock_result, ClockResult::TimeUp) {
@@ -585,18 +582,37 @@ impl GameRoom {
         })
     }
 
-    fn handle_toryo(&mut self, from: Color) -> Result<HandleResult, ServerError> {
+    /// 手番側 `from` の経過時間から通信マージンを差し引いて時計を消費する。
+    ///
+    /// 経過時間はサーバーが記録した手番開始時刻から測り、クライアントの申告は使わない。
+    /// 指し手だけでなく手番側の %TORYO / %KACHI もここを通し、時間切れ後に届いた
+    /// 宣言は `#TIME_UP` として扱う（切れた後の %KACHI で勝ちを拾わせないため）。
+    fn consume_turn_time(&mut self, from: Color, now_ms: u64) -> (u64, ClockResult) {
+        let started = self.turn_started_at_ms.unwrap_or(now_ms);
+        let raw_elapsed_ms = now_ms.saturating_sub(started);
+        let elapsed_ms = raw_elapsed_ms.saturating_sub(self.config.time_margin_ms);
+        (elapsed_ms, self.clock.consume(from, elapsed_ms))
+    }
+
+    fn handle_toryo(&mut self, from: Color, now_ms: u64) -> Result<HandleResult, ServerError> {
         if !matches!(self.status, GameStatus::Playing) {
             return Err(ServerError::State(StateError::InvalidForState {
                 current: format!("{:?}", self.status),
             }));
         }
+        // 手番外の投了は時計と無関係に受理する。
+        let core_side: rshogi_core::types::Color = from.into();
+        if core_side == self.pos.side_to_move()
+            && matches!(self.consume_turn_time(from, now_ms).1, ClockResult::TimeUp)
+        {
+            return Ok(self.finish(GameResult::TimeUp { loser: from }));
+        }
         Ok(self.finish(GameResult::Toryo {
             winner: from.opposite(),
         }))
     }
 
-    fn handle_kachi(&mut self, from: Color) -> Result<HandleResult, ServerError> {
+    fn handle_kachi(&mut self, from: Color, now_ms: u64) -> Result<HandleResult, ServerError> {
         if !matches!(self.status, GameStatus::Playing) {
             return Err(ServerError::State(StateError::InvalidForState {
                 current: format!("{:?}", self.status),
@@ -608,6 +624,9 @@ impl GameRoom {
                 "out-of-turn %KACHI from {from:?}"
             ))));
         }
+        if matches!(self.consume_turn_time(from, now_ms).1, ClockResult::TimeUp) {
+            return Ok(self.finish(GameResult::TimeUp { loser: from }));
+        }
         match self.validator.evaluate_kachi(&self.pos) {
             KachiOutcome::Accepted => Ok(self.finish(GameResult::Kachi { winner: from })),
             KachiOutcome::Rejected => Ok(self.finish(GameResult::IllegalMove {
@@ -872,6 +891,35 @@ mod tests {
         }
     }
 
+    #[test]
+    fn declarations_after_flag_fall_end_as_time_up() {
+        // 時間切れ後に届いた %KACHI / %TORYO は宣言として扱わず #TIME_UP。
+        for command in ["%KACHI", "%TORYO"] {
+            let mut room = make_room();
+            agree_both(&mut room);
+            let r = room.handle_line(Color::Black, &line(command), 70_000).unwrap();
+            assert_eq!(
+                r.outcome,
+                HandleOutcome::GameEnded(GameResult::TimeUp {
+                    loser: Color::Black
+                }),
+                "{command}"
+            );
+            assert!(r.broadcasts.iter().any(|b| b.line.as_str() == "#TIME_UP"), "{command}");
+        }
+
+        // 手番外の投了は時計に関係なく受理する
+        let mut room = make_room();
+        agree_both(&mut room);
+        let r = room.handle_line(Color::White, &line("%TORYO"), 70_000).unwrap();
+        assert_eq!(
+            r.outcome,
+            HandleOutcome::GameEnded(GameResult::Toryo {
+                winner: Color::Black
+            })
+        );
+    }
+
     #[test]
     fn time_margin_is_subtracted_before_consume() {
         let config = GameRoomConfig {
@@ -5,7 +5,6 @@
 //! 切替（24 点法／27 点法／トライルール等）も同じ API から受け付けられるよう
 //! 構成している。
 
-use rshogi_core::movegen::{MoveList, generate_legal_all};
 use rshogi_core::position::Position;
 use rshogi_core::types::{
     Color as CoreColor, EnteringKingRule, File, Move, PieceType, Rank, RepetitionState, Square,
@@ -106,12 +105,10 @@ impl Validator {
             }
         }
 
-        // 同一の {from, to, drop_pt, promote} を持つ合法手があれば採用する。
-        // generate_legal_all は不成も含めて生成するため CSA 仕様（不成許容）と整合する。
-        let mut list = MoveList::new();
-        generate_legal_all(pos, &mut list);
-        if let Some(found) = list.iter().find(|mv| moves_match(**mv, candidate)) {
-            return Ok(*found);
+        // is_legal_move は generate_legal_all（不成含む）と同じ判定のため CSA 仕様（不成許容）と
+        // 整合する。全合法手を生成せずに判定できる。
+        if let Some(found) = pos.to_move(candidate).filter(|mv| pos.is_legal_move(*mv)) {
+            return Ok(found);
         }
 
         // 候補が合法手リストに無い場合、駒打ちで歩なら打ち歩詰の可能性がある。
@@ -400,21 +397,6 @@ fn build_move(pos: &Position, parsed: &ParsedToken) -> Result<Move, Violation> {
     Ok(Move::new_move(from_sq, to_sq, promote))
 }
 
-/// 候補手と合法手リスト中の 1 手が等しいかを判定する。
-fn moves_match(a: Move, b: Move) -> bool {
-    if a.is_drop() != b.is_drop() {
-        return false;
-    }
-    if a.to() != b.to() {
-        return false;
-    }
-    if a.is_drop() {
-        a.drop_piece_type() == b.drop_piece_type()
-    } else {
-        a.from() == b.from() && a.is_promote() == b.is_promote()
-    }
-}
-
 /// 自分の歩が `to.file()` に既に存在するか（二歩判定）。
 fn file_has_own_pawn(pos: &Position, color: CoreColor, to: Square) -> bool {
     let our_pawns = pos.pieces(color, PieceType::Pawn);
@@ -456,7 +438,7 @@ fn attacks_enemy_king_head(pos: &Position, to: Square) -> bool {
 
 /// 歩打ち候補手が「打ち歩詰でなければ合法だった」かを判定する。
 ///
-/// `validate_move` の最終分岐で呼ばれる。`generate_legal_all` に含まれない歩打ちが
+/// `validate_move` の最終分岐で呼ばれる。`is_legal_move` で非合法となった歩打ちが
 /// 打ち歩詰なのか、それとも別理由（最終段への打ち、占有マスへの打ち、王手放置で
 /// 自玉が残るなど）の単なる非合法手なのかを切り分ける。
 fn is_pawn_drop_only_blocked_by_uchifuzume(pos: &Position, to: Square, us: CoreColor) -> bool {
@@ -14,6 +14,7 @@ pub mod protocol;
 pub mod record;
 pub mod scheduler;
 pub mod storage;
+pub mod webhook;
 
 pub use config::{
     FloodgateFeatureIntent, parse_allow_floodgate_features, validate_floodgate_feature_gate,
@@ -61,3 +62,6 @@ pub use types::{
     AdminId, Color, CsaLine, CsaMoveToken, GameId, GameName, IpKey, PlayerName, ReconnectToken,
     RoomId, Secret, StorageKey,
 };
+pub use webhook::{
+    WebhookConfig, WebhookDestination, WebhookEvent, WebhookEventKind, WebhookFormat,
+};
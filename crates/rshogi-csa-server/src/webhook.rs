@@ -0,0 +1,405 @@
+//! 対局ライフサイクルの Webhook 通知（ペイロード・宛先設定・署名）。
+//!
+//! 対局開始・終局・不成立／中断・対局者切断を外部のダッシュボードや
+//! Slack / Discord 連携へ push 通知するための純粋ロジック。HTTP 送信と再送の
+//! スケジューリングはフロントエンド側（TCP 版は
+//! `rshogi-csa-server-tcp/src/webhook.rs`）が担い、本モジュールは
+//! 「何をどこへどの形式で送るか」と署名計算だけを提供する。
+//!
+//! # 署名
+//!
+//! 宛先に `secret` を設定すると、送信ボディ全体の HMAC-SHA256 を
+//! `X-Rshogi-Signature: sha256=<hex>` ヘッダで付ける。受信側は同じ secret で
+//! ボディの HMAC を計算し、定数時間比較で照合する。ボディには `timestamp` が
+//! 含まれるので、受信側は古すぎる通知を破棄してリプレイを防げる。
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::game::result::GameResult;
+use crate::record::kifu::{primary_result_code, winner_of};
+use crate::types::{Color, GameId, GameName, PlayerName};
+
+/// 署名ヘッダ名。値は `sha256=<小文字 hex>`。
+pub const SIGNATURE_HEADER: &str = "X-Rshogi-Signature";
+
+/// イベント種別ヘッダ名。値は [`WebhookEventKind::as_str`]。
+pub const EVENT_HEADER: &str = "X-Rshogi-Event";
+
+/// 再送間隔の上限。指数バックオフがこれを超えないよう頭打ちにする。
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// 通知するイベントの種別。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEventKind {
+    /// 両者 AGREE 後に `START` を配信し、対局が始まった。
+    GameStarted,
+    /// 終局が確定した（`#RESIGN` / `#TIME_UP` / `#SENNICHITE` 等）。
+    GameFinished,
+    /// AGREE 不成立・進行中の内部エラー等で、正規の終局に至らなかった。
+    GameAborted,
+    /// 対局中に対局者の接続が切れた。
+    PlayerDisconnected,
+}
+
+impl WebhookEventKind {
+    /// ペイロードとヘッダで使う識別子。
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::GameStarted => "game_started",
+            Self::GameFinished => "game_finished",
+            Self::GameAborted => "game_aborted",
+            Self::PlayerDisconnected => "player_disconnected",
+        }
+    }
+}
+
+/// Webhook で送る 1 イベント分のペイロード。
+///
+/// [`WebhookFormat::Json`] の宛先にはこの構造体をそのまま JSON で送る。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WebhookEvent {
+    /// イベント種別。
+    pub event: WebhookEventKind,
+    /// 対局 ID。
+    pub game_id: String,
+    /// CSA の `game_name`（私的対局では challenge 発行時の値）。
+    pub game_name: String,
+    /// 先手のハンドル。
+    pub black: String,
+    /// 後手のハンドル。
+    pub white: String,
+    /// イベント発生時刻（RFC 3339, UTC）。
+    pub timestamp: String,
+    /// 終局理由コード（`#RESIGN` 等）。`game_finished` でのみ設定する。
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result_code: Option<String>,
+    /// 勝者の手番（`"black"` / `"white"`）。引き分け・勝敗不定では `None`。
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub winner: Option<String>,
+    /// 切断した対局者のハンドル。`player_disconnected` でのみ設定する。
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub player: Option<String>,
+    /// 不成立・中断の理由。`game_aborted` でのみ設定する。
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+impl WebhookEvent {
+    /// 対局の識別情報だけを埋めたイベントを作る。
+    pub fn new(
+        event: WebhookEventKind,
+        game_id: &GameId,
+        game_name: &GameName,
+        black: &PlayerName,
+        white: &PlayerName,
+        timestamp: chrono::DateTime<chrono::Utc>,
+    ) -> Self {
+        Self {
+            event,
+            game_id: game_id.as_str().to_owned(),
+            game_name: game_name.as_str().to_owned(),
+            black: black.as_str().to_owned(),
+            white: white.as_str().to_owned(),
+            timestamp: timestamp.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            result_code: None,
+            winner: None,
+            player: None,
+            reason: None,
+        }
+    }
+
+    /// 終局結果（理由コードと勝者）を設定する。
+    pub fn with_result(mut self, result: &GameResult) -> Self {
+        self.result_code = Some(primary_result_code(result).to_owned());
+        self.winner = winner_of(result).map(|c| color_label(c).to_owned());
+        self
+    }
+
+    /// 切断した対局者を設定する。
+    pub fn with_player(mut self, player: &PlayerName) -> Self {
+        self.player = Some(player.as_str().to_owned());
+        self
+    }
+
+    /// 不成立・中断の理由を設定する。
+    pub fn with_reason(mut self, reason: impl Into<String>) -> Self {
+        self.reason = Some(reason.into());
+        self
+    }
+
+    /// チャット向けの 1 行要約。
+    pub fn summary_line(&self) -> String {
+        let mut line = format!(
+            "[{}] {} {} vs {} ({})",
+            self.event.as_str(),
+            self.game_id,
+            self.black,
+            self.white,
+            self.game_name
+        );
+        if let Some(code) = &self.result_code {
+            line.push_str(&format!(" {code}"));
+        }
+        if let Some(winner) = &self.winner {
+            line.push_str(&format!(" winner={winner}"));
+        }
+        if let Some(player) = &self.player {
+            line.push_str(&format!(" player={player}"));
+        }
+        if let Some(reason) = &self.reason {
+            line.push_str(&format!(" reason={reason}"));
+        }
+        line
+    }
+}
+
+fn color_label(color: Color) -> &'static str {
+    match color {
+        Color::Black => "black",
+        Color::White => "white",
+    }
+}
+
+/// 送信ボディの形式。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookFormat {
+    /// [`WebhookEvent`] をそのまま JSON で送る。
+    #[default]
+    Json,
+    /// Slack Incoming Webhook 形式（`{"text": ...}`）。
+    Slack,
+    /// Discord Webhook 形式（`{"content": ...}`）。
+    Discord,
+}
+
+/// 通知先 1 件の設定。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WebhookDestination {
+    /// POST 先 URL。
+    pub url: String,
+    /// 通知するイベント種別。空なら全種別を通知する。
+    #[serde(default)]
+    pub events: Vec<WebhookEventKind>,
+    /// 送信ボディの形式。
+    #[serde(default)]
+    pub format: WebhookFormat,
+    /// HMAC-SHA256 署名の鍵。`None` なら署名ヘッダを付けない。
+    #[serde(default)]
+    pub secret: Option<String>,
+}
+
+impl WebhookDestination {
+    /// `kind` のイベントをこの宛先に送るか。
+    pub fn accepts(&self, kind: WebhookEventKind) -> bool {
+        self.events.is_empty() || self.events.contains(&kind)
+    }
+
+    /// `event` をこの宛先の形式で送信ボディに変換する。
+    pub fn render_body(&self, event: &WebhookEvent) -> String {
+        let value = match self.format {
+            WebhookFormat::Json => serde_json::to_value(event).expect("event is serializable"),
+            WebhookFormat::Slack => serde_json::json!({ "text": event.summary_line() }),
+            WebhookFormat::Discord => serde_json::json!({ "content": event.summary_line() }),
+        };
+        value.to_string()
+    }
+
+    /// `body` に付ける署名ヘッダの値。`secret` 未設定なら `None`。
+    pub fn signature(&self, body: &[u8]) -> Option<String> {
+        self.secret
+            .as_ref()
+            .map(|secret| format!("sha256={}", hex(&hmac_sha256(secret.as_bytes(), body))))
+    }
+}
+
+/// Webhook 通知全体の設定。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WebhookConfig {
+    /// 通知先。空なら通知を行わない。
+    #[serde(default, rename = "destination")]
+    pub destinations: Vec<WebhookDestination>,
+    /// 1 通知あたりの最大送信回数（初回を含む）。
+    #[serde(default = "default_max_attempts")]
+    pub max_attempts: u32,
+    /// 初回失敗後の再送待ち時間 (ミリ秒)。以降は失敗ごとに 2 倍にする。
+    #[serde(default = "default_initial_backoff_ms")]
+    pub initial_backoff_ms: u64,
+    /// 1 回の HTTP 送信のタイムアウト (ミリ秒)。
+    #[serde(default = "default_request_timeout_ms")]
+    pub request_timeout_ms: u64,
+}
+
+fn default_max_attempts() -> u32 {
+    5
+}
+
+fn default_initial_backoff_ms() -> u64 {
+    1_000
+}
+
+fn default_request_timeout_ms() -> u64 {
+    10_000
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        Self {
+            destinations: Vec::new(),
+            max_attempts: default_max_attempts(),
+            initial_backoff_ms: default_initial_backoff_ms(),
+            request_timeout_ms: default_request_timeout_ms(),
+        }
+    }
+}
+
+impl WebhookConfig {
+    /// 通知先が 1 件も無いか。
+    pub fn is_empty(&self) -> bool {
+        self.destinations.is_empty()
+    }
+
+    /// `attempt` 回目（1 始まり）の送信に失敗した後、次の送信までの待ち時間。
+    pub fn backoff_after(&self, attempt: u32) -> Duration {
+        let factor = 1u64 << attempt.saturating_sub(1).min(16);
+        Duration::from_millis(self.initial_backoff_ms.saturating_mul(factor)).min(MAX_BACKOFF)
+    }
+}
+
+/// HTTP ステータスが再送に値する失敗か（5xx と 429）。
+///
+/// 他の 4xx は宛先 URL や署名の設定誤りで、再送しても結果が変わらないため
+/// 即座に諦める。
+pub fn is_retryable_status(status: u16) -> bool {
+    status == 429 || (500..600).contains(&status)
+}
+
+/// HMAC-SHA256（RFC 2104）。
+pub fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    const BLOCK_SIZE: usize = 64;
+    let mut block = [0u8; BLOCK_SIZE];
+    if key.len() > BLOCK_SIZE {
+        block[..32].copy_from_slice(&Sha256::digest(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+    let mut inner = Sha256::new();
+    inner.update(block.map(|b| b ^ 0x36));
+    inner.update(message);
+    let mut outer = Sha256::new();
+    outer.update(block.map(|b| b ^ 0x5c));
+    outer.update(inner.finalize());
+    outer.finalize().into()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_event(kind: WebhookEventKind) -> WebhookEvent {
+        let timestamp = chrono::DateTime::parse_from_rfc3339("2026-01-02T03:04:05Z")
+            .unwrap()
+            .with_timezone(&chrono::Utc);
+        WebhookEvent::new(
+            kind,
+            &GameId::new("20260102030405_0001"),
+            &GameName::new("byoyomi-600-10"),
+            &PlayerName::new("alice"),
+            &PlayerName::new("bob"),
+            timestamp,
+        )
+    }
+
+    #[test]
+    fn hmac_matches_rfc4231_vectors() {
+        // RFC 4231 Test Case 2
+        assert_eq!(
+            hex(&hmac_sha256(b"Jefe", b"what do ya want for nothing?")),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        // RFC 4231 Test Case 6（ブロック長より長い鍵）
+        assert_eq!(
+            hex(&hmac_sha256(
+                &[0xaa; 131],
+                b"Test Using Larger Than Block-Size Key - Hash Key First"
+            )),
+            "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54"
+        );
+    }
+
+    #[test]
+    fn finished_event_serializes_result_and_winner() {
+        let event = sample_event(WebhookEventKind::GameFinished).with_result(&GameResult::Toryo {
+            winner: Color::White,
+        });
+        let value: serde_json::Value = serde_json::from_str(
+            &WebhookDestination {
+                url: "http://localhost/hook".to_owned(),
+                events: Vec::new(),
+                format: WebhookFormat::Json,
+                secret: None,
+            }
+            .render_body(&event),
+        )
+        .unwrap();
+        assert_eq!(value["event"], "game_finished");
+        assert_eq!(value["result_code"], "#RESIGN");
+        assert_eq!(value["winner"], "white");
+        assert_eq!(value["timestamp"], "2026-01-02T03:04:05Z");
+        assert!(value.get("player").is_none());
+    }
+
+    #[test]
+    fn destination_filters_events_and_signs_body() {
+        let dest: WebhookDestination = discord_destination();
+        assert!(dest.accepts(WebhookEventKind::GameFinished));
+        assert!(!dest.accepts(WebhookEventKind::GameStarted));
+
+        let event =
+            sample_event(WebhookEventKind::PlayerDisconnected).with_player(&PlayerName::new("bob"));
+        let body = dest.render_body(&event);
+        let value: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert!(value["content"].as_str().unwrap().contains("player=bob"));
+        let sig = dest.signature(body.as_bytes()).unwrap();
+        assert_eq!(sig, format!("sha256={}", hex(&hmac_sha256(b"s3cret", body.as_bytes()))));
+
+        let unsigned = WebhookDestination {
+            secret: None,
+            ..dest
+        };
+        assert_eq!(unsigned.signature(body.as_bytes()), None);
+    }
+
+    fn discord_destination() -> WebhookDestination {
+        serde_json::from_value(serde_json::json!({
+            "url": "https://discord.example/api/webhooks/1",
+            "events": ["game_finished", "player_disconnected"],
+            "format": "discord",
+            "secret": "s3cret",
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn backoff_doubles_and_is_capped() {
+        let config = WebhookConfig {
+            initial_backoff_ms: 500,
+            ..WebhookConfig::default()
+        };
+        assert_eq!(config.backoff_after(1), Duration::from_millis(500));
+        assert_eq!(config.backoff_after(2), Duration::from_millis(1_000));
+        assert_eq!(config.backoff_after(3), Duration::from_millis(2_000));
+        assert_eq!(config.backoff_after(30), MAX_BACKOFF);
+        assert!(is_retryable_status(503));
+        assert!(is_retryable_status(429));
+        assert!(!is_retryable_status(404));
+    }
+}
@@ -0,0 +1,140 @@
+//! `bench` コマンド（USI 拡張）
+//!
+//! 標準ベンチマーク局面（`rshogi_core::testpos` の bench タグ。tools の `benchmark` と同じ局面）を
+//! エンジン内で順に探索し、局面ごとの探索ノード数・NPS と合計を `info string` で出力する。
+//! GUI のコンソールからビルドを検証する用途で、合計ノード数（signature）は `Threads` が 1 なら
+//! 同じ評価関数・同じ制限でビルドや環境によらず一致する。
+//!
+//! `bench [depth N | nodes N | movetime N]`（既定は `depth 12`）
+
+use std::time::Duration;
+
+use rshogi_core::search::LimitsType;
+use rshogi_core::testpos::{self, Tag};
+
+/// 既定の探索深さ
+const DEFAULT_DEPTH: i32 = 12;
+
+/// 1 局面あたりの探索制限
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BenchLimit {
+    Depth(i32),
+    Nodes(u64),
+    Movetime(i64),
+}
+
+impl BenchLimit {
+    /// `bench` に続くトークンを解析する（`tokens[0]` は `bench`）
+    pub fn parse(tokens: &[&str]) -> Result<Self, String> {
+        match tokens.get(1..) {
+            None | Some([]) => Ok(BenchLimit::Depth(DEFAULT_DEPTH)),
+            Some([kind, value]) => {
+                let invalid = || format!("invalid bench {kind} value: {value}");
+                match *kind {
+                    "depth" => value.parse().ok().filter(|d| *d > 0).map(BenchLimit::Depth),
+                    "nodes" => value.parse().ok().filter(|n| *n > 0).map(BenchLimit::Nodes),
+                    "movetime" => value.parse().ok().filter(|t| *t > 0).map(BenchLimit::Movetime),
+                    _ => return Err(format!("unknown bench limit: {kind}")),
+                }
+                .ok_or_else(invalid)
+            }
+            Some(_) => Err("usage: bench [depth N | nodes N | movetime N]".to_string()),
+        }
+    }
+
+    /// 探索開始時刻を記録した探索制限
+    pub fn to_limits(self) -> LimitsType {
+        let mut limits = LimitsType::default();
+        limits.set_start_time();
+        match self {
+            BenchLimit::Depth(depth) => limits.depth = depth,
+            BenchLimit::Nodes(nodes) => limits.nodes = nodes,
+            BenchLimit::Movetime(ms) => limits.movetime = ms,
+        }
+        limits
+    }
+}
+
+impl std::fmt::Display for BenchLimit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BenchLimit::Depth(depth) => write!(f, "depth {depth}"),
+            BenchLimit::Nodes(nodes) => write!(f, "nodes {nodes}"),
+            BenchLimit::Movetime(ms) => write!(f, "movetime {ms}"),
+        }
+    }
+}
+
+/// ベンチマーク局面（局面名と SFEN）
+pub fn positions() -> Vec<(&'static str, &'static str)> {
+    testpos::by_tag(Tag::Bench).map(|tp| (tp.id, tp.sfen)).collect()
+}
+
+/// ノード数と経過時間から NPS を求める（経過時間 0 は 1ms とみなす）
+pub fn nps(nodes: u64, elapsed: Duration) -> u64 {
+    let ms = elapsed.as_millis().max(1) as u64;
+    nodes.saturating_mul(1000) / ms
+}
+
+/// 1 局面の結果行
+pub fn position_line(
+    index: usize,
+    total: usize,
+    id: &str,
+    nodes: u64,
+    elapsed: Duration,
+) -> String {
+    format!(
+        "info string bench position {index}/{total} {id} nodes {nodes} time {} nps {}",
+        elapsed.as_millis(),
+        nps(nodes, elapsed)
+    )
+}
+
+/// 合計の結果行（最終行の signature は合計ノード数）
+pub fn summary_lines(limit: BenchLimit, nodes: u64, elapsed: Duration) -> [String; 2] {
+    [
+        format!(
+            "info string bench total {limit} nodes {nodes} time {} nps {}",
+            elapsed.as_millis(),
+            nps(nodes, elapsed)
+        ),
+        format!("info string bench signature {nodes}"),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_bench_limits() {
+        assert_eq!(BenchLimit::parse(&["bench"]), Ok(BenchLimit::Depth(DEFAULT_DEPTH)));
+        assert_eq!(BenchLimit::parse(&["bench", "depth", "8"]), Ok(BenchLimit::Depth(8)));
+        assert_eq!(
+            BenchLimit::parse(&["bench", "nodes", "100000"]),
+            Ok(BenchLimit::Nodes(100_000))
+        );
+        assert_eq!(BenchLimit::parse(&["bench", "movetime", "500"]), Ok(BenchLimit::Movetime(500)));
+        assert!(BenchLimit::parse(&["bench", "depth", "0"]).is_err());
+        assert!(BenchLimit::parse(&["bench", "mate", "3"]).is_err());
+        assert!(BenchLimit::parse(&["bench", "depth"]).is_err());
+    }
+
+    #[test]
+    fn limits_follow_bench_limit() {
+        assert_eq!(BenchLimit::Depth(5).to_limits().depth, 5);
+        assert_eq!(BenchLimit::Nodes(42).to_limits().nodes, 42);
+        assert_eq!(BenchLimit::Movetime(300).to_limits().movetime, 300);
+    }
+
+    #[test]
+    fn bench_positions_are_available() {
+        assert!(!positions().is_empty());
+        assert_eq!(nps(5000, Duration::from_millis(250)), 20_000);
+        assert_eq!(nps(10, Duration::ZERO), 10_000);
+        let [total, signature] = summary_lines(BenchLimit::Depth(3), 1234, Duration::from_secs(1));
+        assert_eq!(total, "info string bench total depth 3 nodes 1234 time 1000 nps 1234");
+        assert_eq!(signature, "info string bench signature 1234");
+    }
+}
@@ -0,0 +1,183 @@
+//! 定跡（BookFile）
+//!
+//! やねうら王形式（`#YANEURAOU-DB2016 1.00`）の定跡を読み込み、`go` の局面が
+//! 定跡にあれば探索せずに定跡手を返す。局面は SFEN の盤面・手番・持ち駒
+//! （手数を除く 3 要素）で同一視する。候補手のうち評価値が最も高い合法手を選ぶ。
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+
+use rshogi_core::position::Position;
+use rshogi_core::types::Move;
+
+/// 定跡ファイルの先頭行
+const BOOK_HEADER: &str = "#YANEURAOU-DB2016";
+
+/// 定跡の 1 手
+#[derive(Debug, Clone, Copy)]
+struct BookMove {
+    best: Move,
+    ponder: Move,
+    eval: i32,
+}
+
+/// 定跡から選んだ指し手
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BookHit {
+    pub best: Move,
+    /// 定跡に記録が無い、または非合法なら `Move::NONE`
+    pub ponder: Move,
+    pub eval: i32,
+}
+
+/// 読み込み済みの定跡
+#[derive(Debug, Default)]
+pub struct OpeningBook {
+    entries: HashMap<String, Vec<BookMove>>,
+}
+
+impl OpeningBook {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let file =
+            File::open(path).map_err(|e| format!("failed to open BookFile '{path}': {e}"))?;
+        Self::parse(BufReader::new(file)).map_err(|e| format!("BookFile '{path}': {e}"))
+    }
+
+    /// `sfen <sfen>` 行に続く `<move> <ponder> <eval> <depth> <count>` 行を読む
+    ///
+    /// 先頭行はやねうら王形式のヘッダでなければならない。`#` 始まりの行と空行は無視する。
+    pub fn parse(reader: impl BufRead) -> Result<Self, String> {
+        let mut book = Self::default();
+        let mut current: Option<String> = None;
+        for (idx, line) in reader.lines().enumerate() {
+            let line_no = idx + 1;
+            let line = line.map_err(|e| format!("line {line_no}: {e}"))?;
+            let line = line.trim();
+            if idx == 0 && !line.starts_with(BOOK_HEADER) {
+                return Err(format!("unsupported format (expected '{BOOK_HEADER}' header)"));
+            }
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(sfen) = line.strip_prefix("sfen ") {
+                let key =
+                    position_key(sfen).ok_or_else(|| format!("line {line_no}: invalid sfen"))?;
+                current = Some(key);
+                continue;
+            }
+            let Some(key) = &current else {
+                return Err(format!("line {line_no}: move line before any sfen line"));
+            };
+            let mut tokens = line.split_whitespace();
+            let best = tokens
+                .next()
+                .and_then(Move::from_usi)
+                .ok_or_else(|| format!("line {line_no}: invalid move"))?;
+            let ponder = tokens.next().and_then(Move::from_usi).unwrap_or(Move::NONE);
+            let eval = tokens.next().and_then(|t| t.parse().ok()).unwrap_or(0);
+            book.entries
+                .entry(key.clone())
+                .or_default()
+                .push(BookMove { best, ponder, eval });
+        }
+        Ok(book)
+    }
+
+    /// 定跡手のある局面数
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// `pos` の定跡手（評価値が最も高い合法手）
+    pub fn probe(&self, pos: &Position) -> Option<BookHit> {
+        let moves = self.entries.get(&position_key(&pos.to_sfen())?)?;
+        let mut best: Option<BookHit> = None;
+        for entry in moves {
+            if best.is_some_and(|b| b.eval >= entry.eval) {
+                continue;
+            }
+            let Some(mv) = legal_move(pos, entry.best) else {
+                continue;
+            };
+            let mut next = pos.clone_with_history();
+            let gives_check = next.gives_check(mv);
+            next.do_move(mv, gives_check);
+            let ponder = legal_move(&next, entry.ponder).unwrap_or(Move::NONE);
+            best = Some(BookHit {
+                best: mv,
+                ponder,
+                eval: entry.eval,
+            });
+        }
+        best
+    }
+}
+
+/// SFEN から手数を除いたキー
+fn position_key(sfen: &str) -> Option<String> {
+    let mut it = sfen.split_whitespace();
+    let board = it.next()?;
+    let side = it.next()?;
+    let hand = it.next()?;
+    Some(format!("{board} {side} {hand}"))
+}
+
+/// 駒情報を補った合法手（非合法・パスなら `None`）
+fn legal_move(pos: &Position, mv: Move) -> Option<Move> {
+    if mv == Move::NONE || mv.is_pass() {
+        return None;
+    }
+    let mv = pos.to_move(mv)?;
+    (pos.pseudo_legal(mv) && pos.is_legal(mv)).then_some(mv)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AFTER_7G7F: &str = "lnsgkgsnl/1r5b1/ppppppppp/9/9/2P6/PP1PPPPPP/1B5R1/LNSGKGSNL w - 2";
+
+    fn book() -> OpeningBook {
+        let text = format!(
+            "#YANEURAOU-DB2016 1.00\n\
+             sfen lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL b - 1\n\
+             2g2f 8c8d 20 20 1\n\
+             7g7f 3c3d 40 20 5\n\
+             5e5d none 90 20 1\n\
+             sfen {AFTER_7G7F}\n\
+             3c3d\n"
+        );
+        OpeningBook::parse(text.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn probe_picks_highest_eval_legal_move() {
+        let book = book();
+        assert_eq!(book.len(), 2);
+        let mut pos = Position::new();
+        pos.set_hirate();
+        // 5e5d は評価値が最も高いが非合法なので選ばれない
+        let hit = book.probe(&pos).unwrap();
+        assert_eq!(hit.best.to_usi(), "7g7f");
+        assert_eq!(hit.ponder.to_usi(), "3c3d");
+        assert_eq!(hit.eval, 40);
+    }
+
+    #[test]
+    fn probe_ignores_ply_and_missing_ponder() {
+        let book = book();
+        let mut pos = Position::new();
+        pos.set_sfen(&AFTER_7G7F.replace(" 2", " 30")).unwrap();
+        let hit = book.probe(&pos).unwrap();
+        assert_eq!(hit.best.to_usi(), "3c3d");
+        assert_eq!(hit.ponder, Move::NONE);
+    }
+
+    #[test]
+    fn parse_rejects_unknown_format() {
+        assert!(OpeningBook::parse("sfen 9/9/9/9/9/9/9/9/9 b - 1\n".as_bytes()).is_err());
+        let text = "#YANEURAOU-DB2016 1.00\n7g7f\n";
+        assert!(OpeningBook::parse(text.as_bytes()).is_err());
+    }
+}
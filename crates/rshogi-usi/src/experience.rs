@@ -0,0 +1,156 @@
+//! `gameover` 時の対局記録（ExperienceFile）と置換表の扱い（GameoverTT）
+//!
+//! 対局中に bestmove を返した探索ごとに、ルート局面と探索統計（評価値・深さ・ノード数）を
+//! 溜めておき、`gameover win|lose|draw` を受けたら 1 対局 1 行の JSONL として追記する。
+//! 後段の学習データ生成で、実戦に現れた局面を取り出すために使う。
+
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::Path;
+
+use rshogi_core::position::Position;
+use rshogi_core::search::SearchResult;
+use serde_json::{Value as JsonValue, json};
+
+/// 1 回の探索（bestmove を返したもの）の記録
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExperienceEntry {
+    /// 探索したルート局面
+    pub sfen: String,
+    /// 出力した bestmove（`resign` / `win` を含む）
+    pub bestmove: String,
+    /// ルート手番側から見た評価値（centipawn、詰みは `mate` に入れる）
+    pub score_cp: Option<i32>,
+    /// 詰み手数（正は勝ち、負は負け）
+    pub mate: Option<i32>,
+    pub depth: i32,
+    pub nodes: u64,
+}
+
+impl ExperienceEntry {
+    pub fn new(root: &Position, result: &SearchResult, bestmove: &str) -> Self {
+        let (score_cp, mate) = if result.score.is_win() {
+            (None, Some(result.score.mate_ply()))
+        } else if result.score.is_loss() {
+            (None, Some(-result.score.mate_ply()))
+        } else {
+            (Some(result.score.to_cp()), None)
+        };
+        Self {
+            sfen: root.to_sfen(),
+            bestmove: bestmove.to_string(),
+            score_cp,
+            mate,
+            depth: result.depth,
+            nodes: result.nodes,
+        }
+    }
+
+    fn to_json(&self) -> JsonValue {
+        json!({
+            "sfen": self.sfen,
+            "bestmove": self.bestmove,
+            "score_cp": self.score_cp,
+            "mate": self.mate,
+            "depth": self.depth,
+            "nodes": self.nodes,
+        })
+    }
+}
+
+/// `gameover` を受けたときの置換表の扱い（GameoverTT）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GameoverTt {
+    /// 置換表と履歴統計を残す（次の usinewgame で世代を進めるだけ）
+    #[default]
+    Keep,
+    /// 置換表と履歴統計を空にする
+    Clear,
+}
+
+impl GameoverTt {
+    pub fn from_usi(value: &str) -> Option<Self> {
+        match value {
+            "Keep" => Some(Self::Keep),
+            "Clear" => Some(Self::Clear),
+            _ => None,
+        }
+    }
+}
+
+/// 1 対局分の記録を JSONL の 1 行にする
+///
+/// * `result` - `gameover` の引数（`win` / `lose` / `draw`）
+/// * `final_sfen` - 最後に受け取った `position` の局面
+pub fn game_record(result: &str, final_sfen: &str, entries: &[ExperienceEntry]) -> JsonValue {
+    json!({
+        "type": "game",
+        "result": result,
+        "final_sfen": final_sfen,
+        "searches": entries.len(),
+        "total_nodes": entries.iter().map(|e| e.nodes).sum::<u64>(),
+        "positions": entries.iter().map(ExperienceEntry::to_json).collect::<Vec<_>>(),
+    })
+}
+
+/// `path` に 1 行追記する（無ければ作る）
+pub fn append_record(path: &Path, record: &JsonValue) -> io::Result<()> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{record}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rshogi_core::search::SearchConfidence;
+    use rshogi_core::types::{Move, Value};
+
+    fn result(score: Value) -> SearchResult {
+        SearchResult {
+            best_move: Move::NONE,
+            ponder_move: Move::NONE,
+            score,
+            depth: 12,
+            nodes: 3000,
+            pv: Vec::new(),
+            confidence: SearchConfidence::default(),
+            stats_report: String::new(),
+            lines: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn entry_splits_mate_from_cp() {
+        let mut pos = Position::new();
+        pos.set_hirate();
+        let cp = ExperienceEntry::new(&pos, &result(Value::new(0)), "7g7f");
+        assert_eq!((cp.score_cp, cp.mate), (Some(0), None));
+        let win = ExperienceEntry::new(&pos, &result(Value::mate_in(5)), "7g7f");
+        assert_eq!((win.score_cp, win.mate), (None, Some(5)));
+        let loss = ExperienceEntry::new(&pos, &result(Value::mated_in(4)), "resign");
+        assert_eq!((loss.score_cp, loss.mate), (None, Some(-4)));
+    }
+
+    #[test]
+    fn game_record_sums_nodes() {
+        let mut pos = Position::new();
+        pos.set_hirate();
+        let entries = vec![
+            ExperienceEntry::new(&pos, &result(Value::new(30)), "7g7f"),
+            ExperienceEntry::new(&pos, &result(Value::new(-30)), "2g2f"),
+        ];
+        let record = game_record("win", &pos.to_sfen(), &entries);
+        assert_eq!(record["result"], "win");
+        assert_eq!(record["searches"], 2);
+        assert_eq!(record["total_nodes"], 6000);
+        assert_eq!(record["positions"][1]["bestmove"], "2g2f");
+        assert!(!record.to_string().contains('\n'));
+    }
+
+    #[test]
+    fn gameover_tt_parses_combo_values() {
+        assert_eq!(GameoverTt::from_usi("Keep"), Some(GameoverTt::Keep));
+        assert_eq!(GameoverTt::from_usi("Clear"), Some(GameoverTt::Clear));
+        assert_eq!(GameoverTt::from_usi("clear"), None);
+    }
+}
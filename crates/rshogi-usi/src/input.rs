@@ -0,0 +1,235 @@
+//! stdin からの USI コマンド読み込み（行長・レート制限付き）
+//!
+//! `BufRead::lines()` は改行が来るまで無制限にバッファを伸ばすため、壊れた GUI が
+//! 巨大な `position` 行を送り続けるとメモリを際限なく確保してしまう。
+//! ここでは 1 行あたりの最大バイト数と 1 秒あたりの最大コマンド数を制限する。
+
+use std::io::{self, BufRead};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// 1 行の最大バイト数
+///
+/// `position startpos moves ...` は 1 手あたり最大 6 バイト程度なので、
+/// 256 KiB あれば 4 万手超の棋譜を 1 行で受け取れる。
+pub const MAX_LINE_BYTES: usize = 256 * 1024;
+
+/// 1 秒あたりの最大コマンド数
+///
+/// 超過した場合はコマンドを捨てずに次の 1 秒窓まで読み込みを待たせる（背圧）。
+/// `stop` / `quit` を取りこぼすと GUI との同期が崩れるため、破棄はしない。
+pub const MAX_COMMANDS_PER_SEC: u32 = 2000;
+
+/// 1 行読み込みの結果
+#[derive(Debug, PartialEq, Eq)]
+pub enum ReadLine {
+    /// 正常に読み込んだ行（末尾の改行は除去済み）
+    Line(String),
+    /// 最大長を超えたため破棄した行
+    ///
+    /// `prefix` は診断用に先頭の数十バイトだけを保持する。
+    Truncated { prefix: String, total_bytes: usize },
+}
+
+/// 行長制限付きの行リーダ
+///
+/// 上限を超えた行は改行まで読み捨て、保持するメモリを `max_line_bytes` に抑える。
+pub struct BoundedLineReader<R> {
+    inner: R,
+    max_line_bytes: usize,
+    buf: Vec<u8>,
+}
+
+/// `ReadLine::Truncated` に残す先頭バイト数
+const TRUNCATED_PREFIX_BYTES: usize = 32;
+
+impl<R: BufRead> BoundedLineReader<R> {
+    pub fn new(inner: R, max_line_bytes: usize) -> Self {
+        Self {
+            inner,
+            max_line_bytes,
+            buf: Vec::new(),
+        }
+    }
+
+    /// 次の 1 行を読む。EOF なら `Ok(None)`。
+    pub fn read_line(&mut self) -> io::Result<Option<ReadLine>> {
+        self.buf.clear();
+        let mut total_bytes = 0usize;
+        let mut overflowed = false;
+        let mut saw_any = false;
+
+        loop {
+            let available = match self.inner.fill_buf() {
+                Ok(bytes) => bytes,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            if available.is_empty() {
+                // EOF
+                if !saw_any {
+                    return Ok(None);
+                }
+                break;
+            }
+            saw_any = true;
+
+            let (chunk, found_newline) = match available.iter().position(|&b| b == b'\n') {
+                Some(pos) => (&available[..pos], true),
+                None => (available, false),
+            };
+            total_bytes += chunk.len();
+
+            if !overflowed {
+                if self.buf.len() + chunk.len() > self.max_line_bytes {
+                    // 上限超過: 診断用の先頭だけ残して以降は読み捨てる
+                    let keep = TRUNCATED_PREFIX_BYTES.saturating_sub(self.buf.len());
+                    self.buf.extend_from_slice(&chunk[..keep.min(chunk.len())]);
+                    self.buf.truncate(TRUNCATED_PREFIX_BYTES);
+                    overflowed = true;
+                } else {
+                    self.buf.extend_from_slice(chunk);
+                }
+            }
+
+            let consumed = chunk.len() + usize::from(found_newline);
+            self.inner.consume(consumed);
+            if found_newline {
+                break;
+            }
+        }
+
+        if overflowed {
+            let prefix = String::from_utf8_lossy(&self.buf).into_owned();
+            return Ok(Some(ReadLine::Truncated {
+                prefix,
+                total_bytes,
+            }));
+        }
+
+        if self.buf.last() == Some(&b'\r') {
+            self.buf.pop();
+        }
+        let line = String::from_utf8(std::mem::take(&mut self.buf))
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        Ok(Some(ReadLine::Line(line)))
+    }
+}
+
+/// 1 秒窓のコマンドレート制限
+pub struct RateLimiter {
+    max_per_window: u32,
+    window: Duration,
+    window_start: Instant,
+    count: u32,
+}
+
+impl RateLimiter {
+    pub fn new(max_per_sec: u32) -> Self {
+        Self::with_window(max_per_sec, Duration::from_secs(1))
+    }
+
+    fn with_window(max_per_window: u32, window: Duration) -> Self {
+        Self {
+            max_per_window,
+            window,
+            window_start: Instant::now(),
+            count: 0,
+        }
+    }
+
+    /// コマンド 1 件を受け付ける前に呼ぶ。
+    ///
+    /// 上限を超えていれば次の窓まで待ち、`true` を返す。待つのは窓 1 つにつき
+    /// 高々 1 回なので、呼び出し側の警告出力も 1 秒に 1 回までに収まる。
+    pub fn acquire(&mut self) -> bool {
+        let mut throttled = false;
+        let now = Instant::now();
+        if now.duration_since(self.window_start) >= self.window {
+            self.reset_window(now);
+        }
+
+        if self.count >= self.max_per_window {
+            throttled = true;
+            let elapsed = now.duration_since(self.window_start);
+            thread::sleep(self.window.saturating_sub(elapsed));
+            self.reset_window(Instant::now());
+        }
+
+        self.count += 1;
+        throttled
+    }
+
+    fn reset_window(&mut self, now: Instant) {
+        self.window_start = now;
+        self.count = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn read_all(input: &[u8], max: usize) -> Vec<ReadLine> {
+        let mut reader = BoundedLineReader::new(Cursor::new(input.to_vec()), max);
+        let mut out = Vec::new();
+        while let Some(line) = reader.read_line().unwrap() {
+            out.push(line);
+        }
+        out
+    }
+
+    #[test]
+    fn reads_lines_and_strips_crlf() {
+        let lines = read_all(b"usi\r\nisready\nquit", 64);
+        assert_eq!(
+            lines,
+            vec![
+                ReadLine::Line("usi".to_string()),
+                ReadLine::Line("isready".to_string()),
+                ReadLine::Line("quit".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn overlong_line_is_discarded_and_next_line_survives() {
+        let mut input = b"position startpos moves ".to_vec();
+        input.extend(std::iter::repeat_n(b'x', 1000));
+        input.extend_from_slice(b"\nquit\n");
+
+        let lines = read_all(&input, 100);
+        assert_eq!(lines.len(), 2);
+        match &lines[0] {
+            ReadLine::Truncated {
+                prefix,
+                total_bytes,
+            } => {
+                assert!(prefix.starts_with("position startpos"));
+                assert!(prefix.len() <= TRUNCATED_PREFIX_BYTES);
+                assert_eq!(*total_bytes, 1024);
+            }
+            other => panic!("expected truncated line, got {other:?}"),
+        }
+        assert_eq!(lines[1], ReadLine::Line("quit".to_string()));
+    }
+
+    #[test]
+    fn line_exactly_at_limit_is_accepted() {
+        let lines = read_all(b"abcd\n", 4);
+        assert_eq!(lines, vec![ReadLine::Line("abcd".to_string())]);
+    }
+
+    #[test]
+    fn rate_limiter_waits_for_next_window_when_exceeded() {
+        let mut limiter = RateLimiter::with_window(2, Duration::from_millis(20));
+        assert!(!limiter.acquire());
+        assert!(!limiter.acquire());
+        // 3 件目で窓を待ち、次の窓の 1 件目として受け付ける
+        let start = Instant::now();
+        assert!(limiter.acquire());
+        assert!(start.elapsed() >= Duration::from_millis(10));
+        assert!(!limiter.acquire());
+    }
+}
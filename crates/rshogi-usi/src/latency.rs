@@ -0,0 +1,193 @@
+//! コマンド処理レイテンシの集計
+//!
+//! 対局 1 セッションの間、GUI から見た応答時間を種類別にヒストグラムで集め、
+//! `quit` 時に表と JSON で出力する。平均や直近 1 回の差分だけでは、秒読みで
+//! 時間切れを招くような稀な遅延（分布の裾）が見えないため、パーセンタイルと
+//! 最大値を残す。
+//!
+//! - `position`: `position` コマンドの処理時間（指し手列の適用）
+//! - `go_to_search_started`: `go` 受信から探索スレッドが探索を始めるまで
+//!   （前の探索の停止待ち・EvalHash の確保・スレッド生成を含む）
+//! - `stop_to_bestmove`: `stop` 受信から `bestmove` を出力するまで
+
+use std::time::Duration;
+
+use serde_json::{Value, json};
+
+/// バケット数。バケット `i`（`i >= 1`）は `[2^(i-1), 2^i)` マイクロ秒、
+/// 最後のバケットはそれ以上のすべてを受け持つ（約 1 時間以上）。
+const NUM_BUCKETS: usize = 33;
+
+/// 表に出すパーセンタイル
+const PERCENTILES: [f64; 3] = [50.0, 90.0, 99.0];
+
+/// 2 のべき乗幅のバケットによるレイテンシのヒストグラム（マイクロ秒単位）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LatencyHistogram {
+    buckets: [u64; NUM_BUCKETS],
+    count: u64,
+    sum_us: u64,
+    max_us: u64,
+}
+
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        Self {
+            buckets: [0; NUM_BUCKETS],
+            count: 0,
+            sum_us: 0,
+            max_us: 0,
+        }
+    }
+}
+
+impl LatencyHistogram {
+    /// 1 サンプルを追加する
+    pub fn record(&mut self, elapsed: Duration) {
+        let us = u64::try_from(elapsed.as_micros()).unwrap_or(u64::MAX);
+        let bucket = (u64::BITS - us.leading_zeros()) as usize;
+        self.buckets[bucket.min(NUM_BUCKETS - 1)] += 1;
+        self.count += 1;
+        self.sum_us = self.sum_us.saturating_add(us);
+        self.max_us = self.max_us.max(us);
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// 平均（マイクロ秒）
+    pub fn mean_us(&self) -> u64 {
+        self.sum_us.checked_div(self.count).unwrap_or(0)
+    }
+
+    pub fn max_us(&self) -> u64 {
+        self.max_us
+    }
+
+    /// `p` パーセンタイル（マイクロ秒）の上界
+    ///
+    /// 該当サンプルを含むバケットの上端を返す（ただし最大値を超えない）。
+    /// バケット幅が 2 倍刻みなので、真の値との誤差は最大で 2 倍。
+    pub fn percentile_us(&self, p: f64) -> u64 {
+        if self.count == 0 {
+            return 0;
+        }
+        let rank = ((p / 100.0) * self.count as f64).ceil().max(1.0) as u64;
+        let mut seen = 0;
+        for (i, &n) in self.buckets.iter().enumerate() {
+            seen += n;
+            if seen >= rank {
+                let upper = if i == 0 { 0 } else { (1u64 << i) - 1 };
+                return upper.min(self.max_us);
+            }
+        }
+        self.max_us
+    }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "count": self.count,
+            "mean_us": self.mean_us(),
+            "p50_us": self.percentile_us(50.0),
+            "p90_us": self.percentile_us(90.0),
+            "p99_us": self.percentile_us(99.0),
+            "max_us": self.max_us,
+        })
+    }
+}
+
+/// コマンド種別ごとのレイテンシ
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CommandLatency {
+    pub position: LatencyHistogram,
+    pub go_to_search_started: LatencyHistogram,
+    pub stop_to_bestmove: LatencyHistogram,
+}
+
+impl CommandLatency {
+    fn entries(&self) -> [(&'static str, &LatencyHistogram); 3] {
+        [
+            ("position", &self.position),
+            ("go_to_search_started", &self.go_to_search_started),
+            ("stop_to_bestmove", &self.stop_to_bestmove),
+        ]
+    }
+
+    /// サンプルが 1 件も無いか
+    pub fn is_empty(&self) -> bool {
+        self.entries().iter().all(|(_, h)| h.count() == 0)
+    }
+
+    /// 表形式の要約（`info string` で 1 行ずつ出す想定）
+    pub fn summary_lines(&self) -> Vec<String> {
+        let mut lines = vec![format!(
+            "{:<22} {:>7} {:>10} {:>10} {:>10} {:>10} {:>10}",
+            "latency(us)", "count", "mean", "p50", "p90", "p99", "max"
+        )];
+        for (name, h) in self.entries() {
+            let [p50, p90, p99] = PERCENTILES.map(|p| h.percentile_us(p));
+            lines.push(format!(
+                "{:<22} {:>7} {:>10} {:>10} {:>10} {:>10} {:>10}",
+                name,
+                h.count(),
+                h.mean_us(),
+                p50,
+                p90,
+                p99,
+                h.max_us()
+            ));
+        }
+        lines
+    }
+
+    /// JSON ログ用の要約
+    pub fn to_json(&self) -> Value {
+        let mut map = serde_json::Map::new();
+        for (name, h) in self.entries() {
+            map.insert(name.to_owned(), h.to_json());
+        }
+        json!({ "latency": map })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percentiles_capture_tail() {
+        let mut h = LatencyHistogram::default();
+        for _ in 0..98 {
+            h.record(Duration::from_micros(100));
+        }
+        h.record(Duration::from_millis(50));
+        h.record(Duration::from_millis(300));
+
+        assert_eq!(h.count(), 100);
+        // 100us は [64, 128) のバケット
+        assert_eq!(h.percentile_us(50.0), 127);
+        assert_eq!(h.percentile_us(90.0), 127);
+        // 上位 1% に 50ms の遅延が見える
+        assert_eq!(h.percentile_us(99.0), 65_535);
+        assert_eq!(h.percentile_us(100.0), 300_000);
+        assert_eq!(h.max_us(), 300_000);
+        assert_eq!(h.mean_us(), (98 * 100 + 50_000 + 300_000) / 100);
+    }
+
+    #[test]
+    fn empty_and_zero_samples() {
+        let mut latency = CommandLatency::default();
+        assert!(latency.is_empty());
+        assert_eq!(latency.position.percentile_us(99.0), 0);
+
+        latency.position.record(Duration::ZERO);
+        assert!(!latency.is_empty());
+        assert_eq!(latency.position.percentile_us(50.0), 0);
+
+        let json = latency.to_json();
+        assert_eq!(json["latency"]["position"]["count"], 1);
+        assert_eq!(json["latency"]["stop_to_bestmove"]["count"], 0);
+        assert_eq!(latency.summary_lines().len(), 4);
+    }
+}
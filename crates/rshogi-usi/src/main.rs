@@ -2,32 +2,58 @@
 //!
 //! 将棋GUIとの通信を行うUSIプロトコル実装。
 
-use std::io::{self, BufRead, Write};
+mod bench;
+mod book;
+mod experience;
+mod input;
+mod latency;
+mod memory;
+mod presearch;
+mod search_gate;
+mod tsume;
+mod verdict;
+
+use std::io::{self, Write};
 use std::mem::size_of;
-use std::sync::Arc;
+use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 use std::thread;
+use std::time::Instant;
 
 use anyhow::Result;
+use bench::BenchLimit;
+use book::OpeningBook;
+use experience::{ExperienceEntry, GameoverTt, append_record, game_record};
+use input::{BoundedLineReader, MAX_COMMANDS_PER_SEC, MAX_LINE_BYTES, RateLimiter, ReadLine};
+use latency::CommandLatency;
+use memory::MemoryWatchdog;
+use presearch::{PreparedPosition, PresearchStats};
 use rshogi_core::eval::{
     DEFAULT_PASS_RIGHT_VALUE_EARLY, DEFAULT_PASS_RIGHT_VALUE_LATE, MaterialLevel, disable_material,
     is_material_enabled, set_eval_hash_enabled, set_material_level, set_pass_move_bonus,
     set_pass_right_value_phased,
 };
+use rshogi_core::mate::dfpn::DfPnSolver;
+use rshogi_core::movegen::perft_divide;
 use rshogi_core::nnue::{
     AccumulatorStackVariant, LayerStackBucketMode, SHOGI_PROGRESS_KP_ABS_NUM_WEIGHTS, clear_nnue,
     evaluate_dispatch, get_network, init_nnue, parse_layer_stack_bucket_mode,
-    parse_nnue_architecture, print_nnue_stats, reset_layer_stack_progress_kpabs_weights,
-    set_fv_scale_override, set_layer_stack_bucket_mode, set_layer_stack_progress_kpabs_weights,
-    set_nnue_architecture_override,
+    parse_nnue_architecture, print_nnue_stats, read_net_metadata,
+    reset_layer_stack_progress_kpabs_weights, set_fv_scale_override, set_layer_stack_bucket_mode,
+    set_layer_stack_progress_kpabs_weights, set_nnue_architecture_override,
 };
 use rshogi_core::position::Position;
 use rshogi_core::search::{
-    DEFAULT_DRAW_VALUE_BLACK, DEFAULT_DRAW_VALUE_WHITE, LimitsType, PonderhitHandle, Search,
-    SearchInfo, SearchResult, SearchTuneParams,
+    CurrMoveInfo, CurrMoveReport, DEFAULT_DRAW_VALUE_BLACK, DEFAULT_DRAW_VALUE_WHITE,
+    EvalBreakdown, LimitsType, PonderhitHandle, Search, SearchConfidence, SearchInfo, SearchResult,
+    SearchTuneParams, SnapshotOptions,
 };
-use rshogi_core::types::{EnteringKingRule, Move};
+use rshogi_core::tt::{LargePages, TtAllocOptions};
+use rshogi_core::types::{EnteringKingRule, Move, PieceType, Value};
+use search_gate::SearchGate;
 use serde_json::json;
+use verdict::{DEFAULT_RESIGN_VALUE, Verdict, push_score};
 
 /// エンジン名
 const ENGINE_NAME: &str = "Shogi Engine";
@@ -35,8 +61,14 @@ const ENGINE_NAME: &str = "Shogi Engine";
 const ENGINE_VERSION: &str = "0.1.0";
 /// エンジン作者
 const ENGINE_AUTHOR: &str = "sh11235";
+/// Threads オプションの上限
+const MAX_THREADS: usize = 512;
 /// 探索スレッド用のスタックサイズ（SearchWorkerが大きいため増やす）
 const SEARCH_STACK_SIZE: usize = 64 * 1024 * 1024;
+/// MultiPonder オプションの上限
+const MAX_MULTI_PONDER: usize = 8;
+/// SearchSnapshotScoreSwing の既定値（cp）
+const DEFAULT_SNAPSHOT_SCORE_SWING: i32 = 800;
 
 fn load_progress_coeff_kpabs(path: &str) -> Result<Box<[f32]>, String> {
     let bytes = std::fs::read(path)
@@ -72,18 +104,33 @@ struct UsiEngine {
     use_eval_hash: bool,
     /// MultiPV値
     multi_pv: usize,
+    /// MultiPV フォーカスモード（MultiPVFocusDepth / Lines / Margin）
+    multi_pv_focus: rshogi_core::search::MultiPvFocus,
+    /// 探索スレッドごとの CPU 使用率の上限（CpuLimitPercent、100 なら無制限）
+    cpu_limit_percent: u8,
+    /// `info currmove` を出力する最小間隔（CurrMoveInterval、ミリ秒、0 なら出力しない）
+    currmove_interval_ms: u64,
     /// Skill Level オプション
     skill_options: rshogi_core::search::SkillOptions,
+    /// DrawValueBlack / DrawValueWhite（検討モード中は探索に 0 を設定するため保持する）
+    draw_values: (i32, i32),
+    /// Contempt（cp、検討モード中は探索に 0 を設定するため保持する）
+    contempt: i32,
+    /// 検討モード（USI_AnalyseMode）。時間管理・引き分けの評価値・手加減を無効にする
+    analyse_mode: bool,
     /// 探索スレッドのハンドル
     search_thread: Option<thread::JoinHandle<(Search, SearchResult)>>,
     /// 探索停止用のフラグ（探索スレッドと共有）
     stop_flag: Option<Arc<AtomicBool>>,
     /// ponderhit通知ハンドル
     ponderhit_handle: Option<PonderhitHandle>,
-    /// bestmove出力抑制フラグ（cmd_go内部でcmd_stopする際に使用）
-    suppress_bestmove: Arc<AtomicBool>,
+    /// 探索結果を `go` 時点の局面に対してだけ出力するためのゲート
+    /// （cmd_go 内部の停止や、探索中に別局面の position が来た場合に bestmove を抑制する）
+    search_gate: Arc<SearchGate>,
     /// Stochastic_Ponder オプションのミラー
     stochastic_ponder: bool,
+    /// MultiPonder（先読みで並行して読む相手の応手の数。1 なら通常の先読み）
+    multi_ponder: usize,
     /// 直近の position コマンド文字列（Stochastic_Ponder の再始動用）
     last_position_cmd: Option<String>,
     /// 直近の go コマンド文字列（Stochastic_Ponder の再始動用）
@@ -97,9 +144,18 @@ struct UsiEngine {
     eval_file_path: Option<String>,
     /// SPSAParamsFile の明示指定パス（setoption で設定）
     spsa_params_file: Option<String>,
+    // --- 定跡 ---
+    /// BookFile で指定された定跡ファイル（None は定跡を使わない）
+    book_file: Option<String>,
+    /// isready で読み込んだ定跡
+    book: Option<OpeningBook>,
+    /// book_file を読み込み済みか（BookFile 変更時に false へ戻し、isready で読み直す）
+    book_loaded: bool,
     /// SPSA params ファイルの読み込み済みフラグ
     spsa_params_loaded: bool,
-    /// Large Pages使用メッセージの出力済みフラグ
+    /// 置換表の確保方法（LargePages / NumaInterleave）
+    tt_alloc_options: TtAllocOptions,
+    /// 置換表の確保方法の出力済みフラグ（確保し直したら戻す）
     large_pages_reported: bool,
     // --- 有限パス権（Finite Pass Rights）関連 ---
     /// パス権ルール有効化フラグ
@@ -110,6 +166,45 @@ struct UsiEngine {
     pass_right_value_early: i32,
     /// パス権評価値（終盤）
     pass_right_value_late: i32,
+    // --- 投了 ---
+    /// 投了する評価値（評価値が -ResignValue 以下で `bestmove resign`）
+    resign_value: i32,
+    /// この対局での直近の評価値（投了の根拠として出力、usinewgame でクリア）
+    score_history: Arc<Mutex<Vec<i32>>>,
+    // --- メモリ監視 ---
+    /// RSS 上限と置換表の自動縮小（MemoryLimitMB / AutoShrinkHashOnPressure で変更）
+    memory_watchdog: MemoryWatchdog,
+    // --- レイテンシ計測 ---
+    /// コマンド処理レイテンシのヒストグラム（quit 時に出力）
+    latency: Arc<Mutex<CommandLatency>>,
+    /// 探索中に受け取った stop の受信時刻（bestmove 出力時に取り出して計測）
+    stop_received_at: Arc<Mutex<Option<Instant>>>,
+    // --- 次局面の先読み準備 ---
+    /// bestmove 後に次の position を予測して準備するか（PrepareNextPosition で変更）
+    prepare_next_position: bool,
+    /// 探索スレッドが bestmove 後に組み立てた次局面の予測
+    prepared_position: Arc<Mutex<Option<PreparedPosition>>>,
+    /// 予測の的中数（quit 時に出力）
+    presearch_stats: PresearchStats,
+    // --- 探索スナップショット ---
+    /// スナップショットの書き出し先（SearchSnapshotDir、None は書き出さない）
+    snapshot_dir: Option<PathBuf>,
+    /// 書き出す評価値の変動幅（SearchSnapshotScoreSwing）
+    snapshot_score_swing: i32,
+    // --- 置換表の保存・読み込み ---
+    /// gameover / quit で置換表を保存するファイル（TTSaveFile、None は保存しない）
+    tt_save_file: Option<PathBuf>,
+    /// isready で置換表を読み込むファイル（TTLoadFile、None は読み込まない）
+    tt_load_file: Option<PathBuf>,
+    /// TTLoadFile を読み込み済みか（setoption で戻し、次の isready で 1 回だけ読む）
+    tt_loaded: bool,
+    // --- gameover 時の対局記録 ---
+    /// gameover で対局記録を追記する JSONL（ExperienceFile、None は記録しない）
+    experience_file: Option<PathBuf>,
+    /// この対局で bestmove を返した探索の記録（usinewgame でクリア）
+    experience: Arc<Mutex<Vec<ExperienceEntry>>>,
+    /// gameover で置換表を空にするか（GameoverTT）
+    gameover_tt: GameoverTt,
 }
 
 impl UsiEngine {
@@ -133,23 +228,50 @@ impl UsiEngine {
             eval_hash_size_mb,
             use_eval_hash,
             multi_pv: 1,
+            multi_pv_focus: rshogi_core::search::MultiPvFocus::default(),
+            cpu_limit_percent: 100,
+            currmove_interval_ms: 0,
             skill_options: rshogi_core::search::SkillOptions::default(),
+            draw_values: (DEFAULT_DRAW_VALUE_BLACK, DEFAULT_DRAW_VALUE_WHITE),
+            contempt: 0,
+            analyse_mode: false,
             search_thread: None,
             stop_flag: None,
             ponderhit_handle: None,
-            suppress_bestmove: Arc::new(AtomicBool::new(false)),
+            search_gate: Arc::new(SearchGate::default()),
             stochastic_ponder: false,
+            multi_ponder: 1,
             last_position_cmd: None,
             last_go_cmd: None,
             eval_file_explicit: None,
             eval_file_path: None,
             spsa_params_file: None,
             spsa_params_loaded: false,
+            book_file: None,
+            book: None,
+            book_loaded: true,
+            tt_alloc_options: TtAllocOptions::default(),
             large_pages_reported: false,
             pass_rights_enabled: false,
             initial_pass_count: 2,
             pass_right_value_early: DEFAULT_PASS_RIGHT_VALUE_EARLY,
             pass_right_value_late: DEFAULT_PASS_RIGHT_VALUE_LATE,
+            resign_value: DEFAULT_RESIGN_VALUE,
+            score_history: Arc::new(Mutex::new(Vec::new())),
+            memory_watchdog: MemoryWatchdog::default(),
+            latency: Arc::new(Mutex::new(CommandLatency::default())),
+            stop_received_at: Arc::new(Mutex::new(None)),
+            prepare_next_position: true,
+            prepared_position: Arc::new(Mutex::new(None)),
+            presearch_stats: PresearchStats::default(),
+            snapshot_dir: None,
+            snapshot_score_swing: DEFAULT_SNAPSHOT_SCORE_SWING,
+            tt_save_file: None,
+            tt_load_file: None,
+            tt_loaded: true,
+            experience_file: None,
+            experience: Arc::new(Mutex::new(Vec::new())),
+            gameover_tt: GameoverTt::default(),
         }
     }
 
@@ -174,8 +296,23 @@ impl UsiEngine {
                 self.cmd_usinewgame();
             }
             "position" => {
+                // 検討モードでは同じ開始局面からの手順の間は置換表を温めたままにし、
+                // 別の開始局面（別の棋譜）に移ったら前のエントリを古くする
+                if self.analyse_mode
+                    && self.last_position_cmd.as_deref().map(position_root)
+                        != Some(position_root(line))
+                    && let Some(search) = self.search.as_ref()
+                {
+                    search.new_game_tt();
+                }
                 self.last_position_cmd = Some(line.to_string());
-                self.cmd_position(&tokens);
+                let started = Instant::now();
+                if !self.use_prepared_position(line) {
+                    self.cmd_position(&tokens);
+                }
+                // 探索中に局面が変わったら、その探索の bestmove は出さない
+                self.search_gate.set_position(self.position.key());
+                lock(&self.latency).position.record(started.elapsed());
             }
             "go" => {
                 self.last_go_cmd = Some(line.to_string());
@@ -189,20 +326,34 @@ impl UsiEngine {
             }
             "quit" => {
                 self.cmd_stop();
+                self.save_tt_if_requested();
                 // NNUE統計を出力（nnue-stats feature有効時のみ実際に出力）
                 print_nnue_stats();
+                self.report_latency();
                 return Ok(false);
             }
             "gameover" => {
                 self.cmd_stop();
+                self.save_tt_if_requested();
+                self.cmd_gameover(tokens.get(1).copied().unwrap_or("unknown"));
             }
             // デバッグ用コマンド
             "d" | "display" => {
                 self.cmd_display();
             }
             "eval" => {
-                let diagnostics = tokens.get(1).is_some_and(|s| *s == "diag");
-                self.cmd_eval(diagnostics);
+                if tokens.get(1).is_some_and(|s| *s == "detail") {
+                    self.cmd_eval_detail();
+                } else {
+                    let diagnostics = tokens.get(1).is_some_and(|s| *s == "diag");
+                    self.cmd_eval(diagnostics);
+                }
+            }
+            "bench" => {
+                self.cmd_bench(&tokens);
+            }
+            "perft" => {
+                self.cmd_perft(&tokens);
             }
             _ => {
                 // 未知のコマンドは無視
@@ -219,14 +370,20 @@ impl UsiEngine {
         println!();
         // オプション（将来的に追加）
         println!("option name USI_Hash type spin default 256 min 1 max 4096");
-        println!("option name Threads type spin default 1 min 1 max 512");
+        println!("option name Threads type spin default 1 min 1 max {MAX_THREADS}");
         println!("option name USI_Ponder type check default false");
+        println!("option name USI_AnalyseMode type check default false");
         println!("option name Stochastic_Ponder type check default false");
+        println!("option name MultiPonder type spin default 1 min 1 max {MAX_MULTI_PONDER}");
         println!("option name MultiPV type spin default 1 min 1 max 500");
+        println!("option name MultiPVFocusDepth type spin default 0 min 0 max 245");
+        println!("option name MultiPVFocusLines type spin default 1 min 1 max 500");
+        println!("option name MultiPVFocusMargin type spin default 50 min 0 max 32000");
         println!("option name NetworkDelay type spin default 120 min 0 max 10000");
         println!("option name NetworkDelay2 type spin default 1120 min 0 max 10000");
         println!("option name MinimumThinkingTime type spin default 2000 min 1000 max 100000");
         println!("option name SlowMover type spin default 100 min 1 max 1000");
+        println!("option name PhaseTimeWeight type spin default 0 min 0 max 100");
         println!("option name MaxMovesToDraw type spin default 100000 min 0 max 100000");
         println!(
             "option name DrawValueBlack type spin default {DEFAULT_DRAW_VALUE_BLACK} min -30000 max 30000"
@@ -234,6 +391,7 @@ impl UsiEngine {
         println!(
             "option name DrawValueWhite type spin default {DEFAULT_DRAW_VALUE_WHITE} min -30000 max 30000"
         );
+        println!("option name Contempt type spin default 0 min -30000 max 30000");
         println!("option name EvalHash type spin default 256 min 0 max 4096");
         println!("option name UseEvalHash type check default true");
         println!("option name Skill Level type spin default 20 min 0 max 20");
@@ -243,9 +401,28 @@ impl UsiEngine {
             "option name MaterialLevel type combo default none var none var 1 var 2 var 3 var 4 var 7 var 8 var 9"
         );
         println!("option name EvalFile type string default eval/nn.bin");
+        println!("option name BookFile type string default <empty>");
         println!(
             "option name EnteringKingRule type combo default CSARule27 var NoEnteringKing var CSARule24 var CSARule24H var CSARule27 var CSARule27H var TryRule"
         );
+        println!(
+            "option name ResignValue type spin default {DEFAULT_RESIGN_VALUE} min 0 max {DEFAULT_RESIGN_VALUE}"
+        );
+        println!("option name LargePages type combo default auto var off var auto var 2MB var 1GB");
+        println!("option name NumaInterleave type check default false");
+        println!("option name CpuLimitPercent type spin default 100 min 1 max 100");
+        println!("option name CurrMoveInterval type spin default 0 min 0 max 60000");
+        println!("option name MemoryLimitMB type spin default 0 min 0 max 1048576");
+        println!("option name AutoShrinkHashOnPressure type check default false");
+        println!("option name PrepareNextPosition type check default true");
+        println!("option name SearchSnapshotDir type string default <empty>");
+        println!("option name TTSaveFile type string default <empty>");
+        println!("option name TTLoadFile type string default <empty>");
+        println!("option name ExperienceFile type string default <empty>");
+        println!("option name GameoverTT type combo default Keep var Keep var Clear");
+        println!(
+            "option name SearchSnapshotScoreSwing type spin default {DEFAULT_SNAPSHOT_SCORE_SWING} min 0 max 32000"
+        );
         // FV_SCALE: 0=自動判定、1以上=指定値でオーバーライド
         // 水匠5等は24、YaneuraOuデフォルトは16
         println!("option name FV_SCALE type spin default 0 min 0 max 100");
@@ -268,6 +445,8 @@ impl UsiEngine {
             "option name PassRightValueLate type spin default {DEFAULT_PASS_RIGHT_VALUE_LATE} min 0 max 500"
         );
         println!("option name SPSAParamsFile type string default <auto>");
+        #[cfg(feature = "policy-ordering")]
+        println!("option name PolicyFile type string default <empty>");
         for spec in SearchTuneParams::option_specs() {
             println!(
                 "option name {} type spin default {} min {} max {}",
@@ -278,11 +457,13 @@ impl UsiEngine {
     }
 
     /// isreadyコマンド: 準備完了を通知
-    /// YaneuraOu準拠: isready 受信時にTTをクリアする
+    /// YaneuraOu準拠: isready 受信時にTTをクリアする（検討モードでは置換表を残す）
     fn cmd_isready(&mut self) {
-        if let Some(search) = self.search.as_mut() {
-            search.clear_tt();
+        if !self.analyse_mode {
+            self.clear_tt_and_report();
         }
+        // クリアの後に読み込む（検討の再開用）
+        self.maybe_load_tt();
         // EvalFile の状態を確認し、必要なら NNUE をロード
         match self.eval_file_explicit {
             Some(false) => {
@@ -307,6 +488,7 @@ impl UsiEngine {
                                 "message": format!("NNUE auto-loaded: {DEFAULT_EVAL_FILE}"),
                             });
                             eprintln!("info string {payload}");
+                            report_net_metadata(DEFAULT_EVAL_FILE);
                         }
                         Err(e) => {
                             panic!("Failed to load default NNUE file {DEFAULT_EVAL_FILE}: {e}");
@@ -346,10 +528,119 @@ impl UsiEngine {
             }
         }
         self.maybe_load_spsa_params();
+        self.maybe_load_book();
         self.maybe_report_large_pages();
+        self.search_gate.mark_ready();
         println!("readyok");
     }
 
+    /// BookFile の読み込み（isready 時）。
+    /// 読み込みに失敗した場合は info string で通知し、定跡なしで続行する。
+    fn maybe_load_book(&mut self) {
+        if self.book_loaded {
+            return;
+        }
+        self.book_loaded = true;
+        self.book = None;
+        let Some(path) = self.book_file.as_deref() else {
+            return;
+        };
+        match OpeningBook::load(path) {
+            Ok(book) => {
+                eprintln!("info string BookFile loaded: {path} ({} positions)", book.len());
+                self.book = Some(book);
+            }
+            Err(e) => {
+                eprintln!("info string Error loading BookFile: {e}");
+            }
+        }
+    }
+
+    /// TTLoadFile の読み込み（isready 時）。
+    /// 読み込みに失敗した場合は info string で通知し、空の置換表のまま続行する。
+    fn maybe_load_tt(&mut self) {
+        if self.tt_loaded {
+            return;
+        }
+        self.tt_loaded = true;
+        let (Some(path), Some(search)) = (self.tt_load_file.as_deref(), self.search.as_mut())
+        else {
+            return;
+        };
+        let started = Instant::now();
+        match search.load_tt(path) {
+            Ok(entries) => {
+                let payload = json!({
+                    "type": "info",
+                    "message": "TT loaded",
+                    "path": path.display().to_string(),
+                    "entries": entries,
+                    "elapsed_ms": started.elapsed().as_millis() as u64,
+                });
+                eprintln!("info string {payload}");
+            }
+            Err(e) => {
+                eprintln!("info string Error loading TTLoadFile '{}': {e}", path.display());
+            }
+        }
+    }
+
+    /// TTSaveFile が設定されていれば置換表を保存する（gameover / quit 時、探索停止後に呼ぶ）
+    fn save_tt_if_requested(&self) {
+        let (Some(path), Some(search)) = (self.tt_save_file.as_deref(), self.search.as_ref())
+        else {
+            return;
+        };
+        let started = Instant::now();
+        match search.save_tt(path) {
+            Ok(entries) => {
+                let payload = json!({
+                    "type": "info",
+                    "message": "TT saved",
+                    "path": path.display().to_string(),
+                    "entries": entries,
+                    "elapsed_ms": started.elapsed().as_millis() as u64,
+                });
+                eprintln!("info string {payload}");
+            }
+            Err(e) => {
+                eprintln!("info string Error saving TTSaveFile '{}': {e}", path.display());
+            }
+        }
+    }
+
+    /// gameover の後処理（探索停止・TTSaveFile の保存の後に呼ぶ）
+    ///
+    /// ExperienceFile が設定されていれば、この対局で bestmove を返した探索の記録を
+    /// 1 行追記する。GameoverTT が Clear なら置換表と履歴統計を空にする。
+    fn cmd_gameover(&mut self, result: &str) {
+        let entries = std::mem::take(&mut *lock(&self.experience));
+        if let Some(path) = self.experience_file.as_deref() {
+            let record = game_record(result, &self.position.to_sfen(), &entries);
+            match append_record(path, &record) {
+                Ok(()) => {
+                    let payload = json!({
+                        "type": "info",
+                        "message": "experience saved",
+                        "path": path.display().to_string(),
+                        "result": result,
+                        "searches": entries.len(),
+                    });
+                    eprintln!("info string {payload}");
+                }
+                Err(e) => {
+                    eprintln!("info string Error writing ExperienceFile '{}': {e}", path.display());
+                }
+            }
+        }
+        if self.gameover_tt == GameoverTt::Clear {
+            self.clear_tt_and_report();
+            if let Some(search) = self.search.as_mut() {
+                search.clear_histories();
+            }
+        }
+    }
+
     /// SPSA params ファイルの自動/明示読み込み。
     /// 優先順位: 1. SPSAParamsFile で明示指定 2. バイナリ同ディレクトリの spsa.params 3. なし
     fn maybe_load_spsa_params(&mut self) {
@@ -435,6 +726,9 @@ impl UsiEngine {
         }
     }
 
+    /// 置換表の確保方法を報告する（確保し直した後に 1 回だけ）
+    ///
+    /// 通常のページで確保し、Large Pages も NUMA interleave も指定していなければ何も出さない。
     fn maybe_report_large_pages(&mut self) {
         if self.large_pages_reported {
             return;
@@ -443,18 +737,42 @@ impl UsiEngine {
         let Some(search) = self.search.as_ref() else {
             return;
         };
-        if !search.tt_uses_large_pages() {
+        let info = search.tt_alloc_info();
+        self.large_pages_reported = true;
+        if !search.tt_uses_large_pages()
+            && info.requested.large_pages == LargePages::Auto
+            && !info.requested.numa_interleave
+        {
             return;
         }
 
         // Windows: VirtualAlloc with MEM_LARGE_PAGES
-        // Linux: madvise(MADV_HUGEPAGE) によるhugepageヒント
+        // Linux: mmap(MAP_HUGETLB) または madvise(MADV_HUGEPAGE) によるhugepageヒント
+        let message = if info.fell_back() {
+            "TT allocation fell back."
+        } else if search.tt_uses_large_pages() {
+            "Large Pages are used."
+        } else {
+            "Large Pages are not used."
+        };
         let payload = json!({
             "type": "info",
-            "message": "Large Pages are used.",
+            "message": message,
+            "requested": info.requested.large_pages.as_str(),
+            "pages": info.pages.to_string(),
+            "numa_interleave": info.requested.numa_interleave,
+            "numa_nodes": info.numa_nodes,
         });
         println!("info string {}", payload);
-        self.large_pages_reported = true;
+    }
+
+    /// LargePages / NumaInterleave を置換表に反映する（変わったときだけ確保し直す）
+    fn apply_tt_alloc_options(&mut self) {
+        if let Some(search) = self.search.as_mut() {
+            search.set_tt_alloc_options(self.tt_alloc_options);
+        }
+        self.large_pages_reported = false;
+        self.maybe_report_large_pages();
     }
 
     /// setoptionコマンド: オプション設定
@@ -528,20 +846,29 @@ impl UsiEngine {
             }
             "USI_Hash" => {
                 if let Ok(size) = value.parse::<usize>() {
-                    if let Some(search) = self.search.as_mut() {
+                    // 同じサイズの再指定（対局ごとに setoption を送る GUI）では確保し直さない
+                    if let Some(search) = self.search.as_mut()
+                        && search.tt_size_mb() != size
+                    {
                         search.resize_tt(size);
                         self.tt_size_mb = size;
+                        self.large_pages_reported = false;
                     }
                     self.maybe_report_large_pages();
                 }
             }
-            "Threads" => {
-                if let Ok(num) = value.parse::<usize>()
-                    && let Some(search) = self.search.as_mut()
-                {
-                    search.set_num_threads(num);
+            "Threads" => match value.parse::<usize>() {
+                Ok(num @ 1..=MAX_THREADS) => {
+                    if let Some(search) = self.search.as_mut() {
+                        search.set_num_threads(num);
+                    }
                 }
-            }
+                _ => {
+                    eprintln!(
+                        "info string Warning: invalid Threads '{value}', expected 1..{MAX_THREADS}"
+                    );
+                }
+            },
             "NetworkDelay" => {
                 if let Ok(v) = value.parse::<i64>()
                     && let Some(search) = self.search.as_mut()
@@ -578,6 +905,15 @@ impl UsiEngine {
                     search.set_time_options(opts);
                 }
             }
+            "PhaseTimeWeight" => {
+                if let Ok(v) = value.parse::<i32>()
+                    && let Some(search) = self.search.as_mut()
+                {
+                    let mut opts = search.time_options();
+                    opts.phase_time_weight = v;
+                    search.set_time_options(opts);
+                }
+            }
             "USI_Ponder" => {
                 if let Ok(v) = value.parse::<bool>()
                     && let Some(search) = self.search.as_mut()
@@ -587,14 +923,22 @@ impl UsiEngine {
                     search.set_time_options(opts);
                 }
             }
+            "USI_AnalyseMode" => {
+                if let Ok(v) = value.parse::<bool>() {
+                    self.analyse_mode = v;
+                    self.apply_draw_values();
+                }
+            }
             "Stochastic_Ponder" => {
                 if let Ok(v) = value.parse::<bool>() {
                     self.stochastic_ponder = v;
-                    if let Some(search) = self.search.as_mut() {
-                        let mut opts = search.time_options();
-                        opts.stochastic_ponder = v;
-                        search.set_time_options(opts);
-                    }
+                    self.apply_parent_ponder();
+                }
+            }
+            "MultiPonder" => {
+                if let Ok(v) = value.parse::<usize>() {
+                    self.multi_ponder = v.clamp(1, MAX_MULTI_PONDER);
+                    self.apply_parent_ponder();
                 }
             }
             "Skill Level" => {
@@ -648,17 +992,21 @@ impl UsiEngine {
                 }
             }
             "DrawValueBlack" => {
-                if let Ok(v) = value.parse::<i32>()
-                    && let Some(search) = self.search.as_mut()
-                {
-                    search.set_draw_value_black(v);
+                if let Ok(v) = value.parse::<i32>() {
+                    self.draw_values.0 = v;
+                    self.apply_draw_values();
                 }
             }
             "DrawValueWhite" => {
-                if let Ok(v) = value.parse::<i32>()
-                    && let Some(search) = self.search.as_mut()
-                {
-                    search.set_draw_value_white(v);
+                if let Ok(v) = value.parse::<i32>() {
+                    self.draw_values.1 = v;
+                    self.apply_draw_values();
+                }
+            }
+            "Contempt" => {
+                if let Ok(v) = value.parse::<i32>() {
+                    self.contempt = v;
+                    self.apply_draw_values();
                 }
             }
             "MultiPV" => {
@@ -666,6 +1014,21 @@ impl UsiEngine {
                     self.multi_pv = v;
                 }
             }
+            "MultiPVFocusDepth" => {
+                if let Ok(v) = value.parse::<i32>() {
+                    self.multi_pv_focus.depth = v.max(0);
+                }
+            }
+            "MultiPVFocusLines" => {
+                if let Ok(v) = value.parse::<usize>() {
+                    self.multi_pv_focus.lines = v.max(1);
+                }
+            }
+            "MultiPVFocusMargin" => {
+                if let Ok(v) = value.parse::<i32>() {
+                    self.multi_pv_focus.rewiden_margin = v.max(0);
+                }
+            }
             "MaterialLevel" => {
                 if value == "none" {
                     disable_material();
@@ -695,6 +1058,11 @@ impl UsiEngine {
                     clear_nnue();
                     self.eval_file_explicit = None;
                     self.eval_file_path = None;
+                } else if self.eval_file_explicit == Some(true)
+                    && self.eval_file_path.as_deref() == Some(value.as_str())
+                {
+                    // 同じファイルの再指定（対局ごとに setoption を送る GUI）では読み直さない
+                    eprintln!("info string NNUE already loaded: {value}");
                 } else {
                     // パス指定: ロード試行し、結果を記録
                     self.eval_file_path = Some(value.to_string());
@@ -706,6 +1074,7 @@ impl UsiEngine {
                                 "message": format!("NNUE loaded: {value}"),
                             });
                             eprintln!("info string {payload}");
+                            report_net_metadata(&value);
                             // LayerStack ネットなら net header の num_buckets を出力
                             // (file/option desync 検知用、ADR `2026-05-26` §2.8)。
                             if let Some(net) = get_network().as_deref()
@@ -725,6 +1094,15 @@ impl UsiEngine {
                     }
                 }
             }
+            "BookFile" => {
+                if value.is_empty() || value == "<empty>" {
+                    self.book_file = None;
+                } else {
+                    self.book_file = Some(value.to_string());
+                }
+                // 次の isready で読み直す
+                self.book_loaded = false;
+            }
             "FV_SCALE" => {
                 if let Ok(v) = value.parse::<i32>() {
                     set_fv_scale_override(v);
@@ -821,6 +1199,24 @@ impl UsiEngine {
                     }
                 }
             }
+            #[cfg(feature = "policy-ordering")]
+            "PolicyFile" => {
+                use rshogi_core::search::{clear_policy_net, init_policy_net};
+                if value.is_empty() || value == "<empty>" {
+                    clear_policy_net();
+                    eprintln!("info string PolicyFile: disabled");
+                } else {
+                    match init_policy_net(&value) {
+                        Ok(()) => eprintln!("info string PolicyFile loaded: {value}"),
+                        Err(e) => {
+                            clear_policy_net();
+                            eprintln!(
+                                "info string Warning: failed to load PolicyFile '{value}': {e}"
+                            );
+                        }
+                    }
+                }
+            }
             "PassRights" => {
                 let v = value == "true" || value == "1";
                 self.pass_rights_enabled = v;
@@ -832,6 +1228,97 @@ impl UsiEngine {
                     eprintln!("info string InitialPassCount: {}", self.initial_pass_count);
                 }
             }
+            "ResignValue" => {
+                if let Ok(v) = value.parse::<i32>() {
+                    self.resign_value = v.clamp(0, DEFAULT_RESIGN_VALUE);
+                }
+            }
+            "MemoryLimitMB" => {
+                if let Ok(v) = value.parse::<u64>() {
+                    self.memory_watchdog.limit_mb = v;
+                }
+            }
+            "AutoShrinkHashOnPressure" => {
+                if let Ok(v) = value.parse::<bool>() {
+                    self.memory_watchdog.auto_shrink = v;
+                }
+            }
+            "LargePages" => match LargePages::from_usi(&value) {
+                Some(mode) => {
+                    self.tt_alloc_options.large_pages = mode;
+                    self.apply_tt_alloc_options();
+                }
+                None => eprintln!("info string Error: unknown LargePages value '{value}'"),
+            },
+            "NumaInterleave" => {
+                if let Ok(v) = value.parse::<bool>() {
+                    self.tt_alloc_options.numa_interleave = v;
+                    self.apply_tt_alloc_options();
+                }
+            }
+            "CpuLimitPercent" => {
+                if let Ok(v) = value.parse::<u8>() {
+                    self.cpu_limit_percent = v.clamp(1, 100);
+                }
+            }
+            "CurrMoveInterval" => {
+                if let Ok(v) = value.parse::<u64>() {
+                    self.currmove_interval_ms = v.min(60_000);
+                }
+            }
+            "PrepareNextPosition" => {
+                if let Ok(v) = value.parse::<bool>() {
+                    self.prepare_next_position = v;
+                    if !v {
+                        lock(&self.prepared_position).take();
+                    }
+                }
+            }
+            "SearchSnapshotDir" => {
+                self.snapshot_dir = if value.is_empty() || value == "<empty>" {
+                    None
+                } else if let Err(e) = std::fs::create_dir_all(&value) {
+                    eprintln!("info string Error creating SearchSnapshotDir '{value}': {e}");
+                    None
+                } else {
+                    Some(PathBuf::from(value))
+                };
+                self.apply_snapshot_options();
+            }
+            "TTSaveFile" => {
+                self.tt_save_file = if value.is_empty() || value == "<empty>" {
+                    None
+                } else {
+                    Some(PathBuf::from(value))
+                };
+            }
+            "TTLoadFile" => {
+                self.tt_load_file = if value.is_empty() || value == "<empty>" {
+                    None
+                } else {
+                    Some(PathBuf::from(value))
+                };
+                // 次の isready で読み込む
+                self.tt_loaded = false;
+            }
+            "ExperienceFile" => {
+                self.experience_file = if value.is_empty() || value == "<empty>" {
+                    None
+                } else {
+                    Some(PathBuf::from(value))
+                };
+            }
+            "GameoverTT" => {
+                if let Some(policy) = GameoverTt::from_usi(&value) {
+                    self.gameover_tt = policy;
+                }
+            }
+            "SearchSnapshotScoreSwing" => {
+                if let Ok(v) = value.parse::<i32>() {
+                    self.snapshot_score_swing = v.clamp(0, 32000);
+                    self.apply_snapshot_options();
+                }
+            }
             "PassMoveBonus" => {
                 if let Ok(v) = value.parse::<i32>() {
                     let clamped = v.clamp(-1000, 1000);
@@ -865,15 +1352,88 @@ impl UsiEngine {
         }
     }
 
+    /// CurrMoveInterval に応じた `info currmove` の出力設定（0 なら出力しない）
+    fn currmove_report(&self) -> Option<CurrMoveReport> {
+        (self.currmove_interval_ms > 0).then(|| CurrMoveReport {
+            interval_ms: self.currmove_interval_ms,
+            sink: Arc::new(|info: &CurrMoveInfo| {
+                println!("{}", info.to_usi_string());
+                std::io::stdout().flush().ok();
+            }),
+        })
+    }
+
+    /// 置換表をクリアし、要した時間を stderr に出す
+    /// 探索スナップショットの設定を Search に反映する
+    fn apply_snapshot_options(&mut self) {
+        let options = self.snapshot_dir.clone().map(|dir| SnapshotOptions {
+            dir,
+            score_swing: self.snapshot_score_swing,
+        });
+        if let Some(search) = self.search.as_mut() {
+            search.set_snapshot_options(options);
+        }
+    }
+
+    fn clear_tt_and_report(&mut self) {
+        if let Some(search) = self.search.as_mut() {
+            let elapsed = search.clear_tt();
+            let payload = json!({
+                "type": "info",
+                "message": "TT cleared",
+                "hash_mb": search.tt_size_mb(),
+                "elapsed_ms": elapsed.as_millis() as u64,
+            });
+            eprintln!("info string {payload}");
+        }
+    }
+
+    /// 引き分けの評価値と Contempt を探索に設定する（検討モードでは評価を歪めないよう 0 にする）
+    fn apply_draw_values(&mut self) {
+        let ((black, white), contempt) = if self.analyse_mode {
+            ((0, 0), 0)
+        } else {
+            (self.draw_values, self.contempt)
+        };
+        if let Some(search) = self.search.as_mut() {
+            search.set_draw_value_black(black);
+            search.set_draw_value_white(white);
+            search.set_contempt(contempt);
+        }
+    }
+
     /// usinewgameコマンド: 新しい対局の開始
+    ///
+    /// 置換表・NNUE・探索スレッドは確保し直さずに使い回す。置換表は直前の isready で
+    /// ゼロクリア済みのため、ここでは世代を進めて前の対局のエントリを古くするだけにする。
+    /// 検討モードでは置換表と履歴統計をそのまま残す（開始局面が変わったときに古くする）。
     fn cmd_usinewgame(&mut self) {
         self.cmd_stop();
 
         if let Some(search) = self.search.as_mut() {
-            search.clear_tt();
-            search.clear_histories(); // YaneuraOu準拠：履歴統計もクリア
+            if !self.analyse_mode {
+                search.new_game_tt();
+                search.clear_histories(); // YaneuraOu準拠：履歴統計もクリア
+            }
+            let payload = json!({
+                "type": "info",
+                "message": "usinewgame: resources retained",
+                "hash_mb": search.tt_size_mb(),
+                "eval_hash_mb": search.eval_hash_size_mb(),
+                "threads": search.num_threads(),
+                "nnue": self.eval_file_path.as_deref().unwrap_or(if get_network().is_some() {
+                    "auto"
+                } else {
+                    "none"
+                }),
+            });
+            eprintln!("info string {payload}");
         }
         self.position = Position::new();
+        self.search_gate.invalidate();
+        self.score_history.lock().unwrap_or_else(|e| e.into_inner()).clear();
+        lock(&self.experience).clear();
+        lock(&self.prepared_position).take();
     }
 
     /// positionコマンド: 局面設定
@@ -888,6 +1448,27 @@ impl UsiEngine {
         );
     }
 
+    /// 探索スレッドが予測した局面が `line` と一致すれば、解析せずにそれを使う
+    ///
+    /// 予測は一致・不一致にかかわらず 1 回で捨てる。一致しなければ `false` を返し、
+    /// 呼び出し側が通常どおり `line` を解析する。
+    fn use_prepared_position(&mut self, line: &str) -> bool {
+        let Some(prepared) = lock(&self.prepared_position).take() else {
+            return false;
+        };
+        match prepared.take_if_matches(line) {
+            Some(position) => {
+                self.position = position;
+                self.presearch_stats.hits += 1;
+                true
+            }
+            None => {
+                self.presearch_stats.misses += 1;
+                false
+            }
+        }
+    }
+
     fn apply_position_tokens(
         position: &mut Position,
         tokens: &[&str],
@@ -955,23 +1536,62 @@ impl UsiEngine {
         if idx < tokens.len() && tokens[idx] == "moves" {
             idx += 1;
             while idx < tokens.len() {
-                if let Some(mv) = Move::from_usi(tokens[idx]) {
-                    // PASS の場合は gives_check は false
-                    let gives_check = if mv.is_pass() {
-                        false
-                    } else {
-                        position.gives_check(mv)
-                    };
-                    position.do_move(mv, gives_check);
-                } else {
-                    eprintln!("info string Error parsing move: {token}", token = tokens[idx]);
-                    break;
+                match Move::parse_usi(tokens[idx]) {
+                    Ok(mv) => {
+                        // PASS の場合は gives_check は false
+                        let gives_check = if mv.is_pass() {
+                            false
+                        } else {
+                            position.gives_check(mv)
+                        };
+                        position.do_move(mv, gives_check);
+                    }
+                    Err(e) => {
+                        eprintln!(
+                            "info string Error parsing move '{token}': {e}",
+                            token = tokens[idx]
+                        );
+                        break;
+                    }
                 }
                 idx += 1;
             }
         }
     }
 
+    /// 先読みを GUI の予想した相手の手を 1 手戻した局面から行うか（Stochastic_Ponder / MultiPonder）
+    ///
+    /// この場合は ponderhit で探索をやり直すので、先読みの時間を見込んだ持ち時間の配分はしない。
+    fn ponders_parent_position(&self) -> bool {
+        self.stochastic_ponder || self.multi_ponder > 1
+    }
+
+    /// [`ponders_parent_position`](Self::ponders_parent_position) を時間管理に反映する
+    fn apply_parent_ponder(&mut self) {
+        let parent = self.ponders_parent_position();
+        if let Some(search) = self.search.as_mut() {
+            let mut opts = search.time_options();
+            opts.stochastic_ponder = parent;
+            search.set_time_options(opts);
+        }
+    }
+
+    /// 先読み（go ponder）を 1 手戻した局面から行う場合の探索局面
+    ///
+    /// MultiPonder では相手の応手の上位 `MultiPonder` 手を MultiPV で並行して読む。
+    /// 置換表は共有なので、相手がそのどれかを指せば ponderhit 後の探索も外れた後の
+    /// 探索も、その手の先の読みを置換表から引き継げる。
+    fn ponder_root(&self, limits: &mut LimitsType) -> Option<Position> {
+        if !limits.ponder || !self.ponders_parent_position() {
+            return None;
+        }
+        let pos = self.stochastic_ponder_position()?;
+        if self.multi_ponder > 1 {
+            limits.multi_pv = limits.multi_pv.max(self.multi_ponder);
+        }
+        Some(pos)
+    }
+
     fn stochastic_ponder_position(&self) -> Option<Position> {
         let line = self.last_position_cmd.as_deref()?;
         let mut owned: Vec<&str> = line.split_whitespace().collect();
@@ -999,20 +1619,57 @@ impl UsiEngine {
 
     /// goコマンド: 探索開始
     fn cmd_go(&mut self, tokens: &[&str]) {
+        let go_received = Instant::now();
         // 既存の探索を停止（bestmove出力を抑制する）
         // GUIがstopを送らずにposition+goを送ってきた場合、前のponder探索の
         // bestmoveがstdoutに出力されるとGUIが混乱する（YaneuraOu準拠）
         self.stop_search_silently();
 
+        // 前回の bestmove 後の予測は、この go の局面には使わない
+        lock(&self.prepared_position).take();
+
         // 制限を解析
-        let limits = self.parse_go_options(tokens);
+        let mut limits = self.parse_go_options(tokens);
 
-        // Stochastic_Ponder では 1 手戻した局面から先読みする（YaneuraOu 準拠）
-        let mut pos = if self.stochastic_ponder && limits.ponder {
-            self.stochastic_ponder_position().unwrap_or_else(|| self.position.clone())
-        } else {
-            self.position.clone()
-        };
+        // go mate は通常探索ではなく詰将棋解答（checkmate を返す）
+        if limits.mate != 0 {
+            self.start_mate_search(limits.mate);
+            return;
+        }
+
+        // 定跡にある局面は探索せずに返す（ponder / infinite は停止指示を待つ必要があるため探索する）
+        if !limits.ponder
+            && !limits.infinite
+            && let Some(hit) = self.book.as_ref().and_then(|book| book.probe(&self.position))
+        {
+            // 探索はしないが、bestmove は探索と同じくゲートを通して出す
+            let ticket = self.search_gate.begin(self.position.key());
+            self.search_gate.emit_if_live(ticket, || {
+                println!("info string book {} eval {}", hit.best.to_usi(), hit.eval);
+                if hit.ponder == Move::NONE {
+                    println!("bestmove {}", hit.best.to_usi());
+                } else {
+                    println!("bestmove {} ponder {}", hit.best.to_usi(), hit.ponder.to_usi());
+                }
+                std::io::stdout().flush().ok();
+            });
+            self.search_gate.end(ticket);
+            return;
+        }
+
+        // bestmove 後に次局面を予測する基準（Stochastic_Ponder / MultiPonder の先読みは
+        // 1 手戻した局面なので対象外）
+        let prediction_base =
+            if self.prepare_next_position && !(self.ponders_parent_position() && limits.ponder) {
+                self.last_position_cmd.clone()
+            } else {
+                None
+            };
+
+        // Stochastic_Ponder / MultiPonder では 1 手戻した局面から先読みする（YaneuraOu 準拠）
+        let mut pos = self
+            .ponder_root(&mut limits)
+            .unwrap_or_else(|| self.position.clone_with_history());
 
         let mut search = self
             .search
@@ -1021,23 +1678,52 @@ impl UsiEngine {
         if search.eval_hash_size_mb() != self.eval_hash_size_mb {
             search.resize_eval_hash(self.eval_hash_size_mb);
         }
-        search.set_skill_options(self.skill_options);
+        // 検討モードでは手加減しない
+        search.set_skill_options(if self.analyse_mode {
+            rshogi_core::search::SkillOptions::default()
+        } else {
+            self.skill_options
+        });
+        search.set_currmove_report(self.currmove_report());
         // stop/ponderhitフラグをリセット（スレッド生成前に行い、go()内での競合を防ぐ）
         search.reset_flags();
         let stop_flag = search.stop_flag();
         self.stop_flag = Some(stop_flag.clone());
         self.ponderhit_handle = Some(search.ponderhit_handle());
 
-        let suppress_flag = Arc::clone(&self.suppress_bestmove);
+        let gate = Arc::clone(&self.search_gate);
+        let ticket = gate.begin(self.position.key());
+        let entering_king_rule = search.entering_king_rule();
+        let resign_value = self.resign_value;
+        let score_history = Arc::clone(&self.score_history);
+        let experience = self.experience_file.is_some().then(|| Arc::clone(&self.experience));
+        let memory_watchdog = self.memory_watchdog;
+        let latency = Arc::clone(&self.latency);
+        let stop_received_at = Arc::clone(&self.stop_received_at);
+        let prepared_position = Arc::clone(&self.prepared_position);
+        let cpu_limit_percent = self.cpu_limit_percent;
         let builder = thread::Builder::new().stack_size(SEARCH_STACK_SIZE);
         self.search_thread = Some(
             builder
                 .spawn(move || {
+                    lock(&latency).go_to_search_started.record(go_received.elapsed());
                     let result = search.go(
                         &mut pos,
                         limits,
                         Some(|info: &SearchInfo| {
                             println!("{}", info.to_usi_string());
+                            // CpuLimitPercent 指定時は実際の使用率を反復ごとに併せて出力する
+                            if info.multi_pv == 1
+                                && let Some(usage) = info.cpu_usage
+                            {
+                                let payload = json!({
+                                    "type": "info",
+                                    "message": "CPU usage",
+                                    "usage_percent": usage,
+                                    "limit_percent": cpu_limit_percent,
+                                });
+                                println!("info string {payload}");
+                            }
                             std::io::stdout().flush().ok();
                         }),
                     );
@@ -1050,29 +1736,225 @@ impl UsiEngine {
                         std::io::stdout().flush().ok();
                     }
 
-                    // bestmove出力（suppress_bestmoveが立っていない場合のみ）
-                    // cmd_goから内部的にstopされた場合は抑制される
-                    if !suppress_flag.load(Ordering::SeqCst) {
-                        let best_usi = if result.best_move != Move::NONE {
-                            result.best_move.to_usi()
-                        } else {
-                            "resign".to_string()
+                    // bestmove出力（go 時点の局面がまだ有効な場合のみ）
+                    // cmd_goから内部的にstopされた場合や、探索中に別局面の position が
+                    // 来た場合は抑制される
+                    // 宣言勝ち・投了の場合は根拠を info string で併せて出力する
+                    let mut verdict = None;
+                    let emitted = gate.emit_if_live(ticket, || {
+                        let decided = {
+                            let mut history =
+                                score_history.lock().unwrap_or_else(|e| e.into_inner());
+                            push_score(&mut history, &result);
+                            Verdict::decide(
+                                &pos,
+                                entering_king_rule,
+                                &result,
+                                &history,
+                                resign_value,
+                            )
                         };
-
-                        if result.ponder_move != Move::NONE {
-                            println!("bestmove {best_usi} ponder {}", result.ponder_move.to_usi());
-                        } else {
-                            println!("bestmove {best_usi}");
+                        if let Some(meta) = decided.meta_line() {
+                            println!("{meta}");
                         }
+                        let bestmove_line = decided.bestmove_line();
+                        println!("{bestmove_line}");
+                        std::io::stdout().flush().ok();
+                        if let Some(experience) = &experience {
+                            let bestmove = bestmove_line.split_whitespace().nth(1).unwrap_or("");
+                            lock(experience).push(ExperienceEntry::new(&pos, &result, bestmove));
+                        }
+                        verdict = Some(decided);
+                    });
+                    if emitted && let Some(received) = lock(&stop_received_at).take() {
+                        lock(&latency).stop_to_bestmove.record(received.elapsed());
+                    }
+
+                    // 相手の手番中にメモリを確認し、必要なら置換表を縮小する
+                    if let Some(action) = memory_watchdog.enforce(&mut search) {
+                        println!("{}", action.info_line());
                         std::io::stdout().flush().ok();
                     }
 
+                    // GUI が次に送ってくる見込みの position（bestmove + ponder）を準備する
+                    if let (Some(base), Some(Verdict::Move { best, ponder })) =
+                        (prediction_base, verdict)
+                        && ponder != Move::NONE
+                        && let Some(prepared) = PreparedPosition::predict(&base, &pos, best, ponder)
+                    {
+                        search.prepare_root(prepared.position());
+                        *lock(&prepared_position) = Some(prepared);
+                    }
+
+                    gate.end(ticket);
                     (search, result)
                 })
                 .expect("failed to spawn search thread"),
         );
     }
 
+    /// `go mate` の詰将棋解答を探索スレッドで開始する
+    ///
+    /// `mate_ms` は解答時間（ミリ秒、`i32::MAX` は infinite）。`stop` で中断した
+    /// 場合は `checkmate timeout` を返す。
+    fn start_mate_search(&mut self, mate_ms: i32) {
+        let mut pos = self.position.clone_with_history();
+        let search = self
+            .search
+            .take()
+            .unwrap_or_else(|| Search::new_with_eval_hash(self.tt_size_mb, self.eval_hash_size_mb));
+        search.reset_flags();
+        let stop_flag = search.stop_flag();
+        self.stop_flag = Some(stop_flag.clone());
+
+        let gate = Arc::clone(&self.search_gate);
+        let ticket = gate.begin(self.position.key());
+        let builder = thread::Builder::new().stack_size(SEARCH_STACK_SIZE);
+        self.search_thread = Some(
+            builder
+                .spawn(move || {
+                    let mut solver =
+                        DfPnSolver::new(tsume::mate_limits(mate_ms)).with_stop_flag(&stop_flag);
+                    let outcome = solver.solve(&mut pos);
+                    gate.emit_if_live(ticket, || {
+                        println!("info nodes {}", solver.nodes());
+                        println!("{}", tsume::checkmate_line(&outcome));
+                        std::io::stdout().flush().ok();
+                    });
+                    let result = SearchResult {
+                        best_move: Move::NONE,
+                        ponder_move: Move::NONE,
+                        score: Value::ZERO,
+                        depth: 0,
+                        nodes: solver.nodes(),
+                        pv: Vec::new(),
+                        confidence: SearchConfidence::default(),
+                        stats_report: String::new(),
+                        lines: Vec::new(),
+                    };
+                    gate.end(ticket);
+                    (search, result)
+                })
+                .expect("failed to spawn mate search thread"),
+        );
+    }
+
+    /// perftコマンド: 現在の局面から指定深さまでの合法手の組み合わせ数を数える
+    ///
+    /// ルートの合法手ごとの数（divide）と合計を出力する。指し手生成の検証用で、
+    /// 数え終わるまで次のコマンドを処理しない。
+    fn cmd_perft(&mut self, tokens: &[&str]) {
+        let depth = match tokens.get(1..) {
+            Some([depth]) => depth.parse::<u32>().ok().filter(|d| *d > 0),
+            _ => None,
+        };
+        let Some(depth) = depth else {
+            println!("info string usage: perft <depth>");
+            return;
+        };
+        if self.position.pieces_pt(PieceType::King).count() != 2 {
+            println!("info string perft requires a position with both kings (send position first)");
+            return;
+        }
+        self.stop_search_silently();
+
+        let mut pos = self.position.clone_with_history();
+        let started = Instant::now();
+        let divide = perft_divide(&mut pos, depth);
+        let elapsed = started.elapsed();
+        let total: u64 = divide.iter().map(|(_, nodes)| nodes).sum();
+        for (mv, nodes) in &divide {
+            println!("info string perft {} {nodes}", mv.to_usi());
+        }
+        println!(
+            "info string perft total depth {depth} moves {} nodes {total} time {} nps {}",
+            divide.len(),
+            elapsed.as_millis(),
+            bench::nps(total, elapsed)
+        );
+        std::io::stdout().flush().ok();
+    }
+
+    /// benchコマンド: 標準ベンチマーク局面を順に探索し、ノード数・NPS を出力する
+    ///
+    /// 全局面の探索が終わるまで次のコマンドを処理しない（途中の stop は受け付けない）。
+    /// 評価関数は事前の isready で読み込んでおくこと。
+    fn cmd_bench(&mut self, tokens: &[&str]) {
+        let limit = match BenchLimit::parse(tokens) {
+            Ok(limit) => limit,
+            Err(e) => {
+                println!("info string {e}");
+                return;
+            }
+        };
+        if get_network().is_none() && !is_material_enabled() {
+            println!("info string bench requires an evaluation function (run isready first)");
+            return;
+        }
+        self.stop_search_silently();
+
+        let mut search = self
+            .search
+            .take()
+            .unwrap_or_else(|| Search::new_with_eval_hash(self.tt_size_mb, self.eval_hash_size_mb));
+        if search.eval_hash_size_mb() != self.eval_hash_size_mb {
+            search.resize_eval_hash(self.eval_hash_size_mb);
+        }
+        search.set_skill_options(self.skill_options);
+        // signature がコマンドの実行順によらないよう、置換表と履歴統計を空にしてから測る
+        search.clear_tt();
+        search.clear_histories();
+
+        let builder = thread::Builder::new().stack_size(SEARCH_STACK_SIZE);
+        let handle = builder
+            .spawn(move || {
+                let positions = bench::positions();
+                let started = Instant::now();
+                let mut total_nodes = 0;
+                for (i, (id, sfen)) in positions.iter().enumerate() {
+                    let mut pos = Position::new();
+                    if let Err(e) = pos.set_sfen(sfen) {
+                        println!("info string bench position {id}: invalid sfen: {e}");
+                        continue;
+                    }
+                    search.reset_flags();
+                    let position_started = Instant::now();
+                    let result = search.go(&mut pos, limit.to_limits(), None::<fn(&SearchInfo)>);
+                    total_nodes += result.nodes;
+                    println!(
+                        "{}",
+                        bench::position_line(
+                            i + 1,
+                            positions.len(),
+                            id,
+                            result.nodes,
+                            position_started.elapsed()
+                        )
+                    );
+                    std::io::stdout().flush().ok();
+                }
+                for line in bench::summary_lines(limit, total_nodes, started.elapsed()) {
+                    println!("{line}");
+                }
+                std::io::stdout().flush().ok();
+                search
+            })
+            .expect("failed to spawn bench thread");
+        match handle.join() {
+            Ok(search) => {
+                self.tt_size_mb = search.tt_size_mb();
+                self.search = Some(search);
+            }
+            Err(_) => {
+                eprintln!("info string bench thread panicked, resetting Search");
+                let mut search =
+                    Search::new_with_eval_hash(self.tt_size_mb, self.eval_hash_size_mb);
+                search.set_skill_options(self.skill_options);
+                self.search = Some(search);
+            }
+        }
+    }
+
     /// goオプションを解析
     fn parse_go_options(&self, tokens: &[&str]) -> LimitsType {
         let mut limits = LimitsType::default();
@@ -1108,7 +1990,7 @@ impl UsiEngine {
                 }
                 "mate" => {
                     idx += 1;
-                    // `go mate` without a value is treated as infinite (YaneuraOu互換)
+                    // 値は解答時間（ミリ秒）。`go mate` without a value is treated as infinite (YaneuraOu互換)
                     limits.mate = if idx < tokens.len() {
                         match tokens[idx] {
                             "infinite" => i32::MAX,
@@ -1179,11 +2061,16 @@ impl UsiEngine {
                             idx -= 1; // 巻き戻して次のループで処理
                             break;
                         }
-                        if let Some(mv) = Move::from_usi(tokens[idx]) {
-                            if let Some(normalized) = self.position.to_move(mv) {
-                                limits.search_moves.push(normalized);
-                            } else {
-                                eprintln!("warning: invalid searchmoves: {}", tokens[idx]);
+                        match Move::parse_usi(tokens[idx]) {
+                            Ok(mv) => {
+                                if let Some(normalized) = self.position.to_move(mv) {
+                                    limits.search_moves.push(normalized);
+                                } else {
+                                    eprintln!("warning: invalid searchmoves: {}", tokens[idx]);
+                                }
+                            }
+                            Err(e) => {
+                                eprintln!("warning: invalid searchmoves '{}': {e}", tokens[idx]);
                             }
                         }
                         idx += 1;
@@ -1196,16 +2083,55 @@ impl UsiEngine {
 
         // MultiPVを設定
         limits.multi_pv = self.multi_pv;
+        limits.multi_pv_focus = self.multi_pv_focus;
+        limits.cpu_limit_percent = self.cpu_limit_percent;
+
+        // 検討モードでは持ち時間を使わず、stop まで考え続ける
+        if self.analyse_mode && limits.use_time_management() && !limits.ponder {
+            limits.time = [0; 2];
+            limits.inc = [0; 2];
+            limits.byoyomi = [0; 2];
+            limits.rtime = 0;
+            limits.infinite = true;
+        }
 
         limits
     }
 
     /// stopコマンド: 探索停止（GUIからの明示的stop — bestmoveは探索スレッドが出力）
     fn cmd_stop(&mut self) {
+        self.search_gate.request_stop();
         if let Some(stop_flag) = &self.stop_flag {
+            *lock(&self.stop_received_at) = Some(Instant::now());
             stop_flag.store(true, Ordering::SeqCst);
         }
         self.wait_for_search();
+        // 探索が先に bestmove を出し終えていた場合は計測対象外
+        lock(&self.stop_received_at).take();
+    }
+
+    /// セッション中のコマンド処理レイテンシを表と JSON で出力する（quit 時）
+    fn report_latency(&self) {
+        // 局面の変更などで捨てた探索結果があれば件数を出す
+        let (emitted, suppressed) = self.search_gate.counts();
+        if suppressed > 0 {
+            eprintln!("info string search results: emitted={emitted} suppressed={suppressed}");
+        }
+        let violations = self.search_gate.violations();
+        if violations > 0 {
+            eprintln!("info string engine state invariant violations: {violations}");
+        }
+        let latency = lock(&self.latency);
+        if latency.is_empty() {
+            return;
+        }
+        for line in latency.summary_lines() {
+            eprintln!("info string {line}");
+        }
+        if !self.presearch_stats.is_empty() {
+            eprintln!("info string {}", self.presearch_stats.summary_line());
+        }
+        eprintln!("info string {}", latency.to_json());
     }
 
     /// 探索を停止するがbestmoveを出力しない（cmd_go内部で使用）
@@ -1213,17 +2139,17 @@ impl UsiEngine {
     /// GUIがstopを送らずにposition+goを送ってきた場合、前のponder探索の
     /// bestmoveを出力するとGUIが混乱する（YaneuraOu準拠）
     fn stop_search_silently(&mut self) {
-        self.suppress_bestmove.store(true, Ordering::SeqCst);
+        self.search_gate.invalidate();
+        self.search_gate.request_stop();
         if let Some(stop_flag) = &self.stop_flag {
             stop_flag.store(true, Ordering::SeqCst);
         }
         self.wait_for_search();
-        self.suppress_bestmove.store(false, Ordering::SeqCst);
     }
 
     /// ponderhitコマンド: 先読みヒットを通知
     fn cmd_ponderhit(&mut self) {
-        if self.stochastic_ponder {
+        if self.ponders_parent_position() {
             self.restart_after_ponderhit();
             return;
         }
@@ -1233,7 +2159,7 @@ impl UsiEngine {
         }
     }
 
-    /// Stochastic_Ponder の ponderhit 後に通常探索へ切り替える
+    /// Stochastic_Ponder / MultiPonder の ponderhit 後に通常探索へ切り替える
     fn restart_after_ponderhit(&mut self) {
         self.stop_search_silently();
 
@@ -1260,6 +2186,8 @@ impl UsiEngine {
         if let Some(handle) = self.search_thread.take() {
             match handle.join() {
                 Ok((search, _result)) => {
+                    // メモリ監視で置換表が縮小されていれば USI_Hash の記録も合わせる
+                    self.tt_size_mb = search.tt_size_mb();
                     self.search = Some(search);
                 }
                 Err(_) => {
@@ -1270,6 +2198,7 @@ impl UsiEngine {
                     self.search = Some(search);
                 }
             }
+            self.search_gate.joined();
         }
         self.stop_flag = None;
         self.ponderhit_handle = None;
@@ -1280,12 +2209,23 @@ impl UsiEngine {
         println!("SFEN: {}", self.position.to_sfen());
         println!("Side to move: {:?}", self.position.side_to_move());
         println!("Game ply: {}", self.position.game_ply());
+        println!("Engine state: {}", self.search_gate.engine_state());
     }
 
     /// evalコマンド: 現在の局面の静的評価値を表示（デバッグ用）
     ///
     /// `eval diag` で diagnostics 付き評価（PSQT 含む中間値をログ出力）
     fn cmd_eval(&self, diagnostics: bool) {
+        // 終局局面（詰み・宣言勝ち）は評価関数を使わず、探索と同じ値を出す
+        let rule = self
+            .search
+            .as_ref()
+            .map_or_else(EnteringKingRule::default, |search| search.entering_king_rule());
+        if let Some(value) = self.position.terminal_value(rule) {
+            println!("info string Static eval: {} (terminal)", value.raw());
+            println!("info string SFEN: {}", self.position.to_sfen());
+            return;
+        }
         let Some(network) = get_network() else {
             println!("info string Error: No NNUE network loaded");
             return;
@@ -1324,6 +2264,126 @@ impl UsiEngine {
         }
         println!("info string SFEN: {}", self.position.to_sfen());
     }
+
+    /// eval detail コマンド: 現在の局面の静的評価値の内訳を表示（デバッグ用）
+    ///
+    /// 駒ごとの寄与は駒を取り除いた局面を評価し直して求めるので、探索と同じスタックの
+    /// スレッドで評価する。
+    fn cmd_eval_detail(&mut self) {
+        if get_network().is_none() && !is_material_enabled() {
+            println!("info string Error: No evaluation function loaded (run isready first)");
+            return;
+        }
+        self.stop_search_silently();
+
+        let mut search = self
+            .search
+            .take()
+            .unwrap_or_else(|| Search::new_with_eval_hash(self.tt_size_mb, self.eval_hash_size_mb));
+        let pos = self.position.clone();
+        let handle = thread::Builder::new()
+            .stack_size(SEARCH_STACK_SIZE)
+            .spawn(move || {
+                let breakdown = search.eval_breakdown(&pos);
+                (search, breakdown)
+            })
+            .expect("failed to spawn eval thread");
+        match handle.join() {
+            Ok((search, breakdown)) => {
+                self.search = Some(search);
+                for line in eval_detail_lines(&breakdown) {
+                    println!("{line}");
+                }
+                println!("info string SFEN: {}", self.position.to_sfen());
+            }
+            Err(_) => {
+                eprintln!("info string eval thread panicked, resetting Search");
+                let mut search =
+                    Search::new_with_eval_hash(self.tt_size_mb, self.eval_hash_size_mb);
+                search.set_skill_options(self.skill_options);
+                self.search = Some(search);
+            }
+        }
+    }
+}
+
+/// 探索 span の subscriber を設定する（search-tracing feature 有効時のみ）
+///
+/// span の close 時に経過時間（`time.busy` / `time.idle`）を stderr に出す。
+/// 出力対象は環境変数 `RSHOGI_TRACE`（EnvFilter 形式）で指定し、既定は
+/// `rshogi_core::search=debug`（go / iteration / aspiration）。root move 単位まで見る場合は
+/// `RSHOGI_TRACE=rshogi_core::search=trace` とする。
+#[cfg(feature = "search-tracing")]
+fn init_search_tracing() {
+    use tracing_subscriber::EnvFilter;
+    use tracing_subscriber::fmt::format::FmtSpan;
+
+    let filter = EnvFilter::try_from_env("RSHOGI_TRACE")
+        .unwrap_or_else(|_| EnvFilter::new("rshogi_core::search=debug"));
+    let subscriber = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_span_events(FmtSpan::CLOSE)
+        .with_ansi(false)
+        .with_writer(io::stderr)
+        .finish();
+    // ログは env_logger が担当するため、log -> tracing の橋渡しは設定しない
+    if let Err(e) = tracing::subscriber::set_global_default(subscriber) {
+        eprintln!("info string Warning: failed to install tracing subscriber: {e}");
+    }
+}
+
+/// `eval detail` の出力行（値はすべて手番側から見た値）
+fn eval_detail_lines(breakdown: &EvalBreakdown) -> Vec<String> {
+    let kind = if breakdown.nnue { "nnue" } else { "material" };
+    let mut lines = match breakdown.terminal {
+        // 終局局面の値は探索と同じ。評価関数の出力は内訳の参考として残す
+        Some(value) => vec![
+            format!("info string Static eval (terminal): {}", value.raw()),
+            format!("info string Evaluation function ({kind}): {}", breakdown.eval.raw()),
+        ],
+        None => vec![format!(
+            "info string Static eval ({kind}): {}",
+            breakdown.eval.raw()
+        )],
+    };
+    lines.push(format!("info string Material: {}", breakdown.material.raw()));
+    match breakdown.tempo {
+        Some(tempo) => lines.push(format!("info string Tempo: {}", tempo.raw())),
+        None => lines.push("info string Tempo: - (in check)".to_string()),
+    }
+    for contribution in &breakdown.pieces {
+        let place = contribution.square.map_or_else(|| "hand".to_string(), |sq| sq.to_usi());
+        lines.push(format!(
+            "info string Piece {place} {} {:+}",
+            contribution.piece.to_usi(),
+            contribution.value.raw()
+        ));
+    }
+    lines
+}
+
+/// position コマンドの開始局面の部分（`moves` より前）
+fn position_root(line: &str) -> &str {
+    line.split(" moves").next().unwrap_or(line).trim_end()
+}
+
+/// poison を無視してロックする（探索スレッドの panic で計測が止まらないように）
+/// 評価関数ファイルに学習メタデータが埋め込まれていれば `info string eval ...` で出力する
+///
+/// GUI のログだけで本番ネットの来歴（学習ラン・データ・コミット）を辿れるよう stdout に出す。
+fn report_net_metadata(path: &str) {
+    let metadata = std::fs::File::open(path)
+        .and_then(|file| read_net_metadata(&mut std::io::BufReader::new(file)));
+    if let Ok(metadata) = metadata
+        && !metadata.is_empty()
+    {
+        println!("info string eval {}", metadata.summary());
+        std::io::stdout().flush().ok();
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
 }
 
 fn main() -> Result<()> {
@@ -1331,18 +2391,39 @@ fn main() -> Result<()> {
     env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info"))
         .target(env_logger::Target::Stderr)
         .init();
+    #[cfg(feature = "search-tracing")]
+    init_search_tracing();
 
     // ビットボードテーブルの初期化（ホットパスでの OnceLock atomic check 回避）
     rshogi_core::bitboard::init_bitboard_tables();
 
     let mut engine = UsiEngine::new();
     let stdin = io::stdin();
+    let mut reader = BoundedLineReader::new(stdin.lock(), MAX_LINE_BYTES);
+    let mut rate_limiter = RateLimiter::new(MAX_COMMANDS_PER_SEC);
+
+    while let Some(read) = reader.read_line()? {
+        let line = match read {
+            ReadLine::Line(line) => line,
+            ReadLine::Truncated {
+                prefix,
+                total_bytes,
+            } => {
+                eprintln!(
+                    "info string Warning: input line too long ({total_bytes} bytes > {MAX_LINE_BYTES}), \
+                     discarded: '{prefix}...'"
+                );
+                continue;
+            }
+        };
 
-    for line in stdin.lock().lines() {
-        let line = line?;
-        let line = line.trim();
+        if rate_limiter.acquire() {
+            eprintln!(
+                "info string Warning: more than {MAX_COMMANDS_PER_SEC} commands/sec, throttling input"
+            );
+        }
 
-        if !engine.process_command(line)? {
+        if !engine.process_command(line.trim())? {
             break;
         }
     }
@@ -1411,6 +2492,181 @@ mod tests {
             .unwrap();
     }
 
+    #[test]
+    #[serial]
+    fn large_pages_option_reallocates_tt_with_requested_pages() {
+        std::thread::Builder::new()
+            .stack_size(STACK_SIZE)
+            .spawn(|| {
+                let mut engine = UsiEngine::new();
+                engine.cmd_setoption(&["setoption", "name", "USI_Hash", "value", "16"]);
+                engine.cmd_setoption(&["setoption", "name", "LargePages", "value", "off"]);
+                let info = engine.search.as_ref().unwrap().tt_alloc_info();
+                assert_eq!(info.requested.large_pages, LargePages::Off);
+                assert_eq!(info.pages, rshogi_core::tt::PageKind::Regular);
+
+                // 確保できなくても通常のページ等に落ちて使える
+                engine.cmd_setoption(&["setoption", "name", "LargePages", "value", "1GB"]);
+                engine.cmd_setoption(&["setoption", "name", "NumaInterleave", "value", "true"]);
+                let search = engine.search.as_ref().unwrap();
+                let info = search.tt_alloc_info();
+                assert_eq!(info.requested.large_pages, LargePages::Huge1Gb);
+                assert!(info.requested.numa_interleave);
+                assert_eq!(search.tt_size_mb(), 16);
+
+                // 不明な値は無視する
+                engine.cmd_setoption(&["setoption", "name", "LargePages", "value", "4MB"]);
+                assert_eq!(engine.tt_alloc_options.large_pages, LargePages::Huge1Gb);
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+    }
+
+    #[test]
+    #[serial]
+    fn cpu_limit_percent_option_is_passed_to_limits() {
+        std::thread::Builder::new()
+            .stack_size(STACK_SIZE)
+            .spawn(|| {
+                let mut engine = UsiEngine::new();
+                assert_eq!(engine.parse_go_options(&["go", "infinite"]).cpu_limit_percent, 100);
+
+                engine.cmd_setoption(&["setoption", "name", "CpuLimitPercent", "value", "30"]);
+                let limits = engine.parse_go_options(&["go", "infinite"]);
+                assert_eq!(limits.cpu_limit_percent, 30);
+                assert!(limits.has_cpu_limit());
+
+                // 範囲外は丸め、数値でなければ無視する
+                engine.cmd_setoption(&["setoption", "name", "CpuLimitPercent", "value", "0"]);
+                assert_eq!(engine.cpu_limit_percent, 1);
+                engine.cmd_setoption(&["setoption", "name", "CpuLimitPercent", "value", "200"]);
+                assert_eq!(engine.cpu_limit_percent, 100);
+                engine.cmd_setoption(&["setoption", "name", "CpuLimitPercent", "value", "x"]);
+                assert_eq!(engine.cpu_limit_percent, 100);
+                assert!(!engine.parse_go_options(&["go", "infinite"]).has_cpu_limit());
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+    }
+
+    #[test]
+    #[serial]
+    fn currmove_interval_option_enables_report() {
+        std::thread::Builder::new()
+            .stack_size(STACK_SIZE)
+            .spawn(|| {
+                let mut engine = UsiEngine::new();
+                assert!(engine.currmove_report().is_none());
+
+                engine.cmd_setoption(&["setoption", "name", "CurrMoveInterval", "value", "500"]);
+                assert_eq!(engine.currmove_report().map(|r| r.interval_ms), Some(500));
+
+                // 上限で丸め、0 なら出力しない
+                engine.cmd_setoption(&["setoption", "name", "CurrMoveInterval", "value", "99999"]);
+                assert_eq!(engine.currmove_interval_ms, 60_000);
+                engine.cmd_setoption(&["setoption", "name", "CurrMoveInterval", "value", "0"]);
+                assert!(engine.currmove_report().is_none());
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+    }
+
+    #[test]
+    fn eval_detail_lines_format_breakdown() {
+        use rshogi_core::search::PieceContribution;
+        use rshogi_core::types::{Color, Piece, Square};
+
+        let breakdown = EvalBreakdown {
+            eval: Value::new(120),
+            nnue: false,
+            material: Value::new(90),
+            tempo: None,
+            pieces: vec![
+                PieceContribution {
+                    square: Some(Square::SQ_55),
+                    piece: Piece::new(Color::White, PieceType::Rook),
+                    value: Value::new(-800),
+                },
+                PieceContribution {
+                    square: None,
+                    piece: Piece::new(Color::Black, PieceType::Pawn),
+                    value: Value::new(95),
+                },
+            ],
+            terminal: None,
+        };
+        assert_eq!(
+            eval_detail_lines(&breakdown),
+            vec![
+                "info string Static eval (material): 120",
+                "info string Material: 90",
+                "info string Tempo: - (in check)",
+                "info string Piece 5e r -800",
+                "info string Piece hand P +95",
+            ]
+        );
+
+        let mated = EvalBreakdown {
+            terminal: Some(Value::mated_in(0)),
+            pieces: Vec::new(),
+            ..breakdown
+        };
+        assert_eq!(
+            eval_detail_lines(&mated)[..3],
+            [
+                "info string Static eval (terminal): -32000",
+                "info string Evaluation function (material): 120",
+                "info string Material: 90",
+            ]
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn analyse_mode_disables_time_management_and_contempt() {
+        std::thread::Builder::new()
+            .stack_size(STACK_SIZE)
+            .spawn(|| {
+                let mut engine = UsiEngine::new();
+                let go = vec!["go", "btime", "1000", "wtime", "1000", "byoyomi", "100"];
+                engine.cmd_setoption(&["setoption", "name", "DrawValueBlack", "value", "-50"]);
+                engine.cmd_setoption(&["setoption", "name", "Contempt", "value", "30"]);
+
+                engine.cmd_setoption(&["setoption", "name", "USI_AnalyseMode", "value", "true"]);
+                let limits = engine.parse_go_options(&go);
+                assert!(limits.infinite);
+                assert!(!limits.use_time_management());
+                assert_eq!(limits.byoyomi, [0, 0]);
+                assert_eq!(engine.search.as_ref().unwrap().draw_value_black(), 0);
+                assert_eq!(engine.search.as_ref().unwrap().contempt(), 0);
+                // 深さ指定などの明示的な制限はそのまま
+                let limits = engine.parse_go_options(&["go", "depth", "3"]);
+                assert_eq!(limits.depth, 3);
+                assert!(!limits.infinite);
+
+                engine.cmd_setoption(&["setoption", "name", "USI_AnalyseMode", "value", "false"]);
+                assert!(engine.parse_go_options(&go).use_time_management());
+                assert_eq!(engine.search.as_ref().unwrap().draw_value_black(), -50);
+                assert_eq!(engine.search.as_ref().unwrap().contempt(), 30);
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+    }
+
+    #[test]
+    fn position_root_strips_moves() {
+        assert_eq!(position_root("position startpos moves 7g7f 3c3d"), "position startpos");
+        assert_eq!(position_root("position startpos"), "position startpos");
+        assert_eq!(
+            position_root("position sfen 4k4/9/9/9/9/9/9/9/4K4 b - 1 moves 5i5h"),
+            "position sfen 4k4/9/9/9/9/9/9/9/4K4 b - 1"
+        );
+    }
+
     #[test]
     #[serial]
     fn stochastic_ponder_position_rewinds_last_move() {
@@ -1431,6 +2687,36 @@ mod tests {
             .unwrap();
     }
 
+    #[test]
+    #[serial]
+    fn multi_ponder_searches_top_replies_from_parent_position() {
+        std::thread::Builder::new()
+            .stack_size(STACK_SIZE)
+            .spawn(|| {
+                let mut engine = UsiEngine::new();
+                engine.last_position_cmd = Some("position startpos moves 7g7f 3c3d".to_string());
+                let mut limits = engine.parse_go_options(&["go", "ponder", "byoyomi", "1000"]);
+                assert!(engine.ponder_root(&mut limits).is_none());
+
+                engine.cmd_setoption(&["setoption", "name", "MultiPonder", "value", "3"]);
+                assert!(engine.search.as_ref().unwrap().time_options().stochastic_ponder);
+                let pos = engine.ponder_root(&mut limits).expect("parent position");
+                assert_eq!(pos.game_ply(), 2);
+                assert_eq!(limits.multi_pv, 3);
+
+                // ponder でない go はそのままの局面・MultiPV で探索する
+                let mut limits = engine.parse_go_options(&["go", "byoyomi", "1000"]);
+                assert!(engine.ponder_root(&mut limits).is_none());
+                assert_eq!(limits.multi_pv, 1);
+
+                engine.cmd_setoption(&["setoption", "name", "MultiPonder", "value", "1"]);
+                assert!(!engine.search.as_ref().unwrap().time_options().stochastic_ponder);
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+    }
+
     #[test]
     #[serial]
     fn setoption_draw_value_updates_search() {
@@ -1450,6 +2736,98 @@ mod tests {
             .unwrap();
     }
 
+    #[test]
+    fn setoption_search_snapshot_updates_search() {
+        std::thread::Builder::new()
+            .stack_size(STACK_SIZE)
+            .spawn(|| {
+                let dir = std::env::temp_dir()
+                    .join(format!("rshogi-usi-snapshot-{}", std::process::id()));
+                let dir_str = dir.to_str().unwrap();
+                let mut engine = UsiEngine::new();
+                engine.cmd_setoption(&[
+                    "setoption",
+                    "name",
+                    "SearchSnapshotScoreSwing",
+                    "value",
+                    "300",
+                ]);
+                assert!(engine.search.as_ref().unwrap().snapshot_options().is_none());
+
+                engine.cmd_setoption(&["setoption", "name", "SearchSnapshotDir", "value", dir_str]);
+                assert!(dir.is_dir());
+                assert_eq!(
+                    engine.search.as_ref().unwrap().snapshot_options(),
+                    Some(&SnapshotOptions {
+                        dir: dir.clone(),
+                        score_swing: 300,
+                    })
+                );
+
+                engine.cmd_setoption(&[
+                    "setoption",
+                    "name",
+                    "SearchSnapshotDir",
+                    "value",
+                    "<empty>",
+                ]);
+                assert!(engine.search.as_ref().unwrap().snapshot_options().is_none());
+                std::fs::remove_dir_all(&dir).unwrap();
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+    }
+
+    #[test]
+    #[serial]
+    fn gameover_appends_experience_record() {
+        std::thread::Builder::new()
+            .stack_size(STACK_SIZE)
+            .spawn(|| {
+                let path = std::env::temp_dir()
+                    .join(format!("rshogi-usi-experience-{}.jsonl", std::process::id()));
+                let _ = std::fs::remove_file(&path);
+                let mut engine = UsiEngine::new();
+                engine.cmd_setoption(&[
+                    "setoption",
+                    "name",
+                    "ExperienceFile",
+                    "value",
+                    path.to_str().unwrap(),
+                ]);
+                engine.cmd_setoption(&["setoption", "name", "GameoverTT", "value", "Clear"]);
+                assert_eq!(engine.gameover_tt, GameoverTt::Clear);
+
+                engine.process_command("position startpos moves 7g7f").unwrap();
+                lock(&engine.experience).push(ExperienceEntry {
+                    sfen: "startpos".to_string(),
+                    bestmove: "7g7f".to_string(),
+                    score_cp: Some(40),
+                    mate: None,
+                    depth: 10,
+                    nodes: 1234,
+                });
+                engine.process_command("gameover lose").unwrap();
+                engine.process_command("gameover draw").unwrap();
+
+                let text = std::fs::read_to_string(&path).unwrap();
+                let lines: Vec<serde_json::Value> =
+                    text.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+                assert_eq!(lines.len(), 2);
+                assert_eq!(lines[0]["result"], "lose");
+                assert_eq!(lines[0]["total_nodes"], 1234);
+                assert_eq!(lines[0]["final_sfen"], engine.position.to_sfen());
+                // 記録は gameover ごとに空になる
+                assert_eq!(lines[1]["result"], "draw");
+                assert_eq!(lines[1]["searches"], 0);
+                std::fs::remove_file(&path).unwrap();
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+    }
+
     #[test]
     #[serial]
     fn setoption_layerstack_bucket_updates_globals() {
@@ -0,0 +1,191 @@
+//! 探索の合間のメモリ監視（RSS watchdog）
+//!
+//! 小さな VPS では置換表・EvalHash・NNUE の合計が物理メモリを圧迫し、OOM killer に
+//! 落とされることがある。bestmove を返した後に RSS を確認し、`MemoryLimitMB` を
+//! 超えていれば `info string` で警告する。`AutoShrinkHashOnPressure` が有効なら
+//! 置換表を縮小し、その操作も `info string` に残す。
+//!
+//! RSS は Linux の `/proc/self/status` から取得する。取得できない環境では何もしない。
+
+use rshogi_core::search::Search;
+
+/// 自動縮小で下回らない置換表サイズ（MB）
+pub const MIN_SHRUNK_TT_MB: usize = 16;
+
+/// メモリ監視の設定
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MemoryWatchdog {
+    /// RSS の上限（MB）。0 なら監視しない
+    pub limit_mb: u64,
+    /// 上限超過時に置換表を縮小するか
+    pub auto_shrink: bool,
+}
+
+/// 上限超過時に取った対応
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemoryAction {
+    /// 警告のみ
+    Warn { rss_mb: u64, limit_mb: u64 },
+    /// 置換表を縮小した
+    ShrinkTt {
+        rss_mb: u64,
+        limit_mb: u64,
+        from_mb: usize,
+        to_mb: usize,
+    },
+}
+
+impl MemoryWatchdog {
+    /// RSS と現在の置換表サイズから対応を決める（上限以内なら `None`）
+    pub fn check(&self, rss_mb: u64, tt_mb: usize) -> Option<MemoryAction> {
+        if self.limit_mb == 0 || rss_mb <= self.limit_mb {
+            return None;
+        }
+        let warn = MemoryAction::Warn {
+            rss_mb,
+            limit_mb: self.limit_mb,
+        };
+        if !self.auto_shrink {
+            return Some(warn);
+        }
+        let to_mb = shrunk_tt_mb(tt_mb, rss_mb - self.limit_mb);
+        if to_mb == tt_mb {
+            // 既に下限まで縮小済み
+            return Some(warn);
+        }
+        Some(MemoryAction::ShrinkTt {
+            rss_mb,
+            limit_mb: self.limit_mb,
+            from_mb: tt_mb,
+            to_mb,
+        })
+    }
+
+    /// 現在の RSS を確認し、必要なら置換表を縮小する
+    ///
+    /// 探索停止中（bestmove 出力後など）にのみ呼ぶこと。
+    pub fn enforce(&self, search: &mut Search) -> Option<MemoryAction> {
+        if self.limit_mb == 0 {
+            return None;
+        }
+        let action = self.check(current_rss_mb()?, search.tt_size_mb())?;
+        if let MemoryAction::ShrinkTt { to_mb, .. } = action {
+            search.resize_tt(to_mb);
+        }
+        Some(action)
+    }
+}
+
+impl MemoryAction {
+    /// `info string memory ...` 行
+    pub fn info_line(&self) -> String {
+        match self {
+            Self::Warn { rss_mb, limit_mb } => format!(
+                "info string memory warning rss_mb={rss_mb} limit_mb={limit_mb} action=none"
+            ),
+            Self::ShrinkTt {
+                rss_mb,
+                limit_mb,
+                from_mb,
+                to_mb,
+            } => format!(
+                "info string memory warning rss_mb={rss_mb} limit_mb={limit_mb} action=shrink_hash hash_mb={from_mb}->{to_mb}"
+            ),
+        }
+    }
+}
+
+/// 超過分を解放できるまで置換表サイズを半分にしていく（`MIN_SHRUNK_TT_MB` で打ち止め）
+fn shrunk_tt_mb(tt_mb: usize, excess_mb: u64) -> usize {
+    let floor = MIN_SHRUNK_TT_MB.min(tt_mb);
+    let mut size = tt_mb;
+    while size > floor && ((tt_mb - size) as u64) < excess_mb {
+        size = (size / 2).max(floor);
+    }
+    size
+}
+
+/// プロセスの常駐メモリ（MB）。取得できない環境では `None`
+pub fn current_rss_mb() -> Option<u64> {
+    #[cfg(target_os = "linux")]
+    {
+        let status = std::fs::read_to_string("/proc/self/status").ok()?;
+        parse_vm_rss_kb(&status).map(|kb| kb / 1024)
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        None
+    }
+}
+
+/// `/proc/self/status` の `VmRSS:` 行（kB）を読む
+#[cfg_attr(not(any(target_os = "linux", test)), allow(dead_code))]
+fn parse_vm_rss_kb(status: &str) -> Option<u64> {
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmRSS:"))
+        .and_then(|rest| rest.split_whitespace().next())
+        .and_then(|kb| kb.parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_vm_rss_from_proc_status() {
+        let status = "Name:\trshogi-usi\nVmPeak:\t  900000 kB\nVmRSS:\t  524288 kB\nThreads:\t2\n";
+        assert_eq!(parse_vm_rss_kb(status), Some(524288));
+        assert_eq!(parse_vm_rss_kb("Name:\tx\n"), None);
+    }
+
+    #[test]
+    fn disabled_or_within_limit_does_nothing() {
+        let disabled = MemoryWatchdog::default();
+        assert_eq!(disabled.check(10_000, 256), None);
+
+        let watchdog = MemoryWatchdog {
+            limit_mb: 1024,
+            auto_shrink: true,
+        };
+        assert_eq!(watchdog.check(1024, 256), None);
+    }
+
+    #[test]
+    fn warns_without_auto_shrink() {
+        let watchdog = MemoryWatchdog {
+            limit_mb: 512,
+            auto_shrink: false,
+        };
+        let action = watchdog.check(600, 256).unwrap();
+        assert_eq!(
+            action.info_line(),
+            "info string memory warning rss_mb=600 limit_mb=512 action=none"
+        );
+    }
+
+    #[test]
+    fn shrinks_tt_until_excess_is_released() {
+        let watchdog = MemoryWatchdog {
+            limit_mb: 512,
+            auto_shrink: true,
+        };
+        // 超過 100MB → 256 を半分にすれば 128MB 解放できる
+        let action = watchdog.check(612, 256).unwrap();
+        assert_eq!(
+            action.info_line(),
+            "info string memory warning rss_mb=612 limit_mb=512 action=shrink_hash hash_mb=256->128"
+        );
+        // 超過 200MB → 64 では 192MB しか解放できないので 32 まで縮小
+        assert_eq!(shrunk_tt_mb(256, 200), 32);
+        // 下限で打ち止め、下限に達していれば警告のみ
+        assert_eq!(shrunk_tt_mb(256, 10_000), MIN_SHRUNK_TT_MB);
+        assert_eq!(
+            watchdog.check(10_000, MIN_SHRUNK_TT_MB),
+            Some(MemoryAction::Warn {
+                rss_mb: 10_000,
+                limit_mb: 512,
+            })
+        );
+    }
+}
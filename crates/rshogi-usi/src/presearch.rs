@@ -0,0 +1,148 @@
+//! bestmove 後の次局面の先読み準備
+//!
+//! ponder 手付きで `bestmove` を返したあと、GUI が続けて送ってくる見込みの
+//! `position` を予測して局面を組み立てておく（置換表の prefetch とルート局面の
+//! 評価も済ませる）。実際の `position` が予測と一字一句一致すれば組み立て済みの
+//! 局面をそのまま使い、一致しなければ予測を捨てて通常どおり解析する。
+
+use rshogi_core::position::Position;
+use rshogi_core::types::Move;
+
+/// 予測した次の `position` コマンドと、それを適用した局面
+pub struct PreparedPosition {
+    /// 予測した `position` コマンド（空白は 1 つに正規化済み）
+    command: String,
+    position: Position,
+}
+
+impl PreparedPosition {
+    /// 直前の `position` コマンドと bestmove / ponder から次の局面を予測する
+    ///
+    /// `root` は `last_command` を適用した局面。`best` と `ponder` をこの順に
+    /// 指せない場合（パスや非合法手を含む場合）は `None` を返す。
+    pub fn predict(last_command: &str, root: &Position, best: Move, ponder: Move) -> Option<Self> {
+        let mut position = root.clone_with_history();
+        for mv in [best, ponder] {
+            if mv.is_pass() {
+                return None;
+            }
+            let mv = position.to_move(mv)?;
+            if !position.pseudo_legal(mv) || !position.is_legal(mv) {
+                return None;
+            }
+            let gives_check = position.gives_check(mv);
+            position.do_move(mv, gives_check);
+        }
+
+        Some(Self {
+            command: predicted_command(last_command, best, ponder)?,
+            position,
+        })
+    }
+
+    pub fn position(&self) -> &Position {
+        &self.position
+    }
+
+    /// `line` が予測と一致すれば組み立て済みの局面を返す
+    pub fn take_if_matches(self, line: &str) -> Option<Position> {
+        (normalize(line) == self.command).then_some(self.position)
+    }
+}
+
+/// 直前の `position` コマンドに 2 手を足した、次に来る見込みのコマンド
+///
+/// `moves` が無ければ補う。`position` で始まらない行には `None` を返す。
+fn predicted_command(last_command: &str, best: Move, ponder: Move) -> Option<String> {
+    let mut command = normalize(last_command);
+    if !command.starts_with("position ") {
+        return None;
+    }
+    if !command.split(' ').any(|token| token == "moves") {
+        command.push_str(" moves");
+    }
+    command.push(' ');
+    command.push_str(&best.to_usi());
+    command.push(' ');
+    command.push_str(&ponder.to_usi());
+    Some(command)
+}
+
+fn normalize(line: &str) -> String {
+    line.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// 予測の的中数（quit 時に出力）
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PresearchStats {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+impl PresearchStats {
+    pub fn is_empty(&self) -> bool {
+        self.hits + self.misses == 0
+    }
+
+    pub fn summary_line(&self) -> String {
+        format!("presearch hits={} misses={}", self.hits, self.misses)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mv(usi: &str) -> Move {
+        Move::from_usi(usi).unwrap()
+    }
+
+    fn hirate() -> Position {
+        let mut pos = Position::new();
+        pos.set_hirate();
+        pos
+    }
+
+    #[test]
+    fn predicted_command_appends_moves() {
+        assert_eq!(
+            predicted_command("position startpos", mv("7g7f"), mv("3c3d")).as_deref(),
+            Some("position startpos moves 7g7f 3c3d")
+        );
+        assert_eq!(
+            predicted_command("position  startpos moves 2g2f ", mv("8c8d"), mv("2f2e")).as_deref(),
+            Some("position startpos moves 2g2f 8c8d 2f2e")
+        );
+        assert_eq!(predicted_command("go", mv("7g7f"), mv("3c3d")), None);
+    }
+
+    #[test]
+    fn prediction_matches_only_the_expected_line() {
+        let root = hirate();
+        let prepared =
+            PreparedPosition::predict("position startpos", &root, mv("7g7f"), mv("3c3d")).unwrap();
+        let mut expected = hirate();
+        for usi in ["7g7f", "3c3d"] {
+            let m = expected.to_move(mv(usi)).unwrap();
+            let gives_check = expected.gives_check(m);
+            expected.do_move(m, gives_check);
+        }
+        assert_eq!(prepared.position().to_sfen(), expected.to_sfen());
+
+        let position = prepared.take_if_matches("position startpos moves 7g7f 3c3d");
+        assert_eq!(position.map(|p| p.key()), Some(expected.key()));
+
+        let prepared =
+            PreparedPosition::predict("position startpos", &root, mv("7g7f"), mv("3c3d")).unwrap();
+        assert!(prepared.take_if_matches("position startpos moves 7g7f 8c8d").is_none());
+    }
+
+    #[test]
+    fn illegal_ponder_move_is_not_predicted() {
+        let root = hirate();
+        // 7g7f の後に先手の指し手は指せない
+        assert!(
+            PreparedPosition::predict("position startpos", &root, mv("7g7f"), mv("2g2f")).is_none()
+        );
+    }
+}
@@ -0,0 +1,376 @@
+//! 探索結果（`bestmove` / `checkmate`）を出力してよいかの判定
+//!
+//! `go` ごとに探索番号を振り、`go` 時点の局面のハッシュと組にして探索スレッドへ渡す。
+//! 探索スレッドは結果を出力する直前にその組が今も有効かを確かめ、次のどちらかが
+//! 起きていれば出力しない。
+//!
+//! - `go` の前の停止（`stop_search_silently`）や `usinewgame` で探索番号が進んだ
+//! - 探索中に GUI が別の局面の `position` を送ってきた
+//!
+//! 判定と出力は同じロックの中で行うので、`position` の処理と探索終了が競合しても、
+//! 新しい `position` を受け取った後に前の探索の `bestmove` が出ることはない。
+//!
+//! ゲートはエンジンの状態（[`EngineState`]）も持つ。遷移は次のとおりで、遷移ごとに
+//! `log::debug!` で記録する（`RUST_LOG=debug` で確認できる）。
+//!
+//! ```text
+//! Initializing --isready--> Ready --go--> Searching --stop--> Stopping
+//!                             ^              |                   |
+//!                             +----探索終了--+-------------------+
+//! ```
+//!
+//! 探索結果を出力してよいのは `Searching` / `Stopping` の間だけで、それ以外での出力要求や
+//! 表にない遷移（`isready` 前の `go` など）は不変条件違反として `log::warn!` で記録し、
+//! 件数を数える。`isready` 前の `go` は従来どおり受け付ける（遷移は行う）。
+
+use std::fmt;
+use std::sync::Mutex;
+
+/// エンジンの状態
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EngineState {
+    /// 起動直後（最初の `isready` の応答前）
+    #[default]
+    Initializing,
+    /// 探索していない
+    Ready,
+    /// 探索中（ponder / infinite を含む）
+    Searching,
+    /// 停止を指示し、探索の終了を待っている
+    Stopping,
+}
+
+impl EngineState {
+    fn as_str(self) -> &'static str {
+        match self {
+            EngineState::Initializing => "initializing",
+            EngineState::Ready => "ready",
+            EngineState::Searching => "searching",
+            EngineState::Stopping => "stopping",
+        }
+    }
+}
+
+impl fmt::Display for EngineState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// `go` 時点の探索番号と局面のハッシュ
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchTicket {
+    search_id: u64,
+    root_key: u64,
+}
+
+#[derive(Debug, Default)]
+struct GateState {
+    /// 最後に `begin` した探索の番号
+    search_id: u64,
+    /// 今の局面（最後の `position`）のハッシュ
+    root_key: u64,
+    /// 出力した探索結果の数
+    emitted: u64,
+    /// 古くなって捨てた探索結果の数
+    suppressed: u64,
+    /// エンジンの状態
+    engine_state: EngineState,
+    /// 実行中の探索の番号（探索スレッドが終わると `None`）
+    active: Option<u64>,
+    /// 不変条件違反の数
+    violations: u64,
+}
+
+impl GateState {
+    fn transition(&mut self, to: EngineState, event: &str) {
+        log::debug!("engine state: {} -> {to} ({event})", self.engine_state);
+        self.engine_state = to;
+    }
+
+    fn violation(&mut self, message: &str) {
+        log::warn!("engine state invariant violated in {}: {message}", self.engine_state);
+        self.violations += 1;
+    }
+}
+
+/// 探索結果の出力を `go` 時点の局面に限るゲート
+#[derive(Debug, Default)]
+pub struct SearchGate {
+    state: Mutex<GateState>,
+}
+
+impl SearchGate {
+    fn lock(&self) -> std::sync::MutexGuard<'_, GateState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// 今の状態
+    pub fn engine_state(&self) -> EngineState {
+        self.lock().engine_state
+    }
+
+    /// `isready` の処理を終えた（`readyok` の直前に呼ぶ）
+    ///
+    /// 探索中の `isready` は状態を変えない。
+    pub fn mark_ready(&self) {
+        let mut state = self.lock();
+        if state.engine_state == EngineState::Initializing {
+            state.transition(EngineState::Ready, "isready");
+        }
+    }
+
+    /// 新しい探索を始める。`root_key` は `go` 時点の局面のハッシュ
+    ///
+    /// 前の探索は終わっている（スレッドを join 済みの）こと。
+    pub fn begin(&self, root_key: u64) -> SearchTicket {
+        let mut state = self.lock();
+        match state.engine_state {
+            EngineState::Ready => {}
+            EngineState::Initializing => state.violation("go before isready"),
+            EngineState::Searching | EngineState::Stopping => {
+                state.violation("go while the previous search is running");
+            }
+        }
+        state.search_id += 1;
+        state.root_key = root_key;
+        state.active = Some(state.search_id);
+        state.transition(EngineState::Searching, "go");
+        SearchTicket {
+            search_id: state.search_id,
+            root_key,
+        }
+    }
+
+    /// `position` / `usinewgame` で局面が変わったことを記録する
+    pub fn set_position(&self, root_key: u64) {
+        self.lock().root_key = root_key;
+    }
+
+    /// 実行中の探索の結果を今後すべて捨てる
+    pub fn invalidate(&self) {
+        self.lock().search_id += 1;
+    }
+
+    /// 探索の停止を指示した（`stop` など。探索中でなければ何もしない）
+    pub fn request_stop(&self) {
+        let mut state = self.lock();
+        if state.engine_state == EngineState::Searching {
+            state.transition(EngineState::Stopping, "stop");
+        }
+    }
+
+    /// 探索スレッドが結果の出力を終えた（スレッドの最後に呼ぶ）
+    pub fn end(&self, ticket: SearchTicket) {
+        let mut state = self.lock();
+        if state.active == Some(ticket.search_id) {
+            state.active = None;
+            state.transition(EngineState::Ready, "search finished");
+        }
+    }
+
+    /// 探索スレッドを join した
+    ///
+    /// 通常は [`end`](Self::end) で `Ready` になっている。探索スレッドが `end` を呼ばずに
+    /// 終わった（panic した）場合はここで `Ready` に戻す。
+    pub fn joined(&self) {
+        let mut state = self.lock();
+        if state.active.take().is_some() {
+            state.violation("search thread ended without finishing");
+            state.transition(EngineState::Ready, "joined");
+        }
+    }
+
+    /// `ticket` が今も有効なら `emit` を呼んで `true` を返す（判定と出力は不可分）
+    ///
+    /// `Searching` / `Stopping` 以外での呼び出しは不変条件違反として出力しない。
+    pub fn emit_if_live(&self, ticket: SearchTicket, emit: impl FnOnce()) -> bool {
+        let mut state = self.lock();
+        if !matches!(state.engine_state, EngineState::Searching | EngineState::Stopping) {
+            state.violation("search result emitted outside a search");
+            state.suppressed += 1;
+            return false;
+        }
+        if state.search_id == ticket.search_id && state.root_key == ticket.root_key {
+            emit();
+            state.emitted += 1;
+            true
+        } else {
+            state.suppressed += 1;
+            false
+        }
+    }
+
+    /// 出力した数と捨てた数
+    pub fn counts(&self) -> (u64, u64) {
+        let state = self.lock();
+        (state.emitted, state.suppressed)
+    }
+
+    /// 不変条件違反の数
+    pub fn violations(&self) -> u64 {
+        self.lock().violations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    #[test]
+    fn emits_only_for_live_search_and_position() {
+        let gate = SearchGate::default();
+        let ticket = gate.begin(1);
+        assert!(gate.emit_if_live(ticket, || {}));
+
+        // 探索中に別の局面へ
+        let ticket = gate.begin(1);
+        gate.set_position(2);
+        assert!(!gate.emit_if_live(ticket, || {}));
+
+        // 同じ局面の position を送り直しただけなら有効
+        let ticket = gate.begin(2);
+        gate.set_position(2);
+        assert!(gate.emit_if_live(ticket, || {}));
+
+        // 同じ局面でも次の go / usinewgame の後は無効
+        let ticket = gate.begin(2);
+        gate.invalidate();
+        assert!(!gate.emit_if_live(ticket, || {}));
+        assert_eq!(gate.counts(), (2, 2));
+    }
+
+    #[test]
+    fn position_change_racing_with_emit_never_emits_after_change() {
+        // 探索スレッドの出力と position の処理を競合させ、出力が局面変更の後に
+        // 起きていないことを確かめる
+        for _ in 0..200 {
+            let gate = Arc::new(SearchGate::default());
+            let changed = Arc::new(AtomicBool::new(false));
+            let ticket = gate.begin(1);
+            let worker = {
+                let gate = Arc::clone(&gate);
+                let changed = Arc::clone(&changed);
+                std::thread::spawn(move || {
+                    let mut emitted_after_change = false;
+                    gate.emit_if_live(ticket, || {
+                        emitted_after_change = changed.load(Ordering::SeqCst);
+                    });
+                    emitted_after_change
+                })
+            };
+            {
+                let mut state = gate.lock();
+                state.root_key = 2;
+                changed.store(true, Ordering::SeqCst);
+            }
+            assert!(!worker.join().unwrap());
+            let (emitted, suppressed) = gate.counts();
+            assert_eq!(emitted + suppressed, 1);
+        }
+    }
+
+    #[test]
+    fn lifecycle_follows_commands() {
+        let gate = SearchGate::default();
+        assert_eq!(gate.engine_state(), EngineState::Initializing);
+        gate.mark_ready();
+        assert_eq!(gate.engine_state(), EngineState::Ready);
+
+        let ticket = gate.begin(1);
+        assert_eq!(gate.engine_state(), EngineState::Searching);
+        // 探索中の isready は状態を変えない
+        gate.mark_ready();
+        gate.request_stop();
+        assert_eq!(gate.engine_state(), EngineState::Stopping);
+        assert!(gate.emit_if_live(ticket, || {}));
+        gate.end(ticket);
+        gate.joined();
+        assert_eq!(gate.engine_state(), EngineState::Ready);
+
+        // 探索していないときの出力は拒否する
+        assert!(!gate.emit_if_live(ticket, || {}));
+        assert_eq!(gate.violations(), 1);
+
+        // end を呼ばずに終わった探索は join で Ready に戻す
+        gate.begin(1);
+        gate.joined();
+        assert_eq!(gate.engine_state(), EngineState::Ready);
+        assert_eq!(gate.violations(), 2);
+    }
+
+    /// 正しいコマンド列（探索スレッドの終了は join の前）をランダムに作り、
+    /// 違反が起きないこと・出力が Searching / Stopping の間に限られることを確かめる
+    #[test]
+    fn random_command_sequences_keep_invariants() {
+        let mut seed = 0x2545_f491_4f6c_dd1d_u64;
+        let mut next = move |n: u64| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            seed % n
+        };
+
+        for _ in 0..200 {
+            let gate = SearchGate::default();
+            gate.mark_ready();
+            // 実行中の探索（ticket と、結果を出力済みか）
+            let mut running: Option<(SearchTicket, bool)> = None;
+            let mut live_position = 0;
+            for _ in 0..100 {
+                match next(6) {
+                    // position
+                    0 => {
+                        live_position = next(3);
+                        gate.set_position(live_position);
+                    }
+                    // isready
+                    1 => gate.mark_ready(),
+                    // go（前の探索は silently に止めて join する）
+                    2 => {
+                        if let Some((ticket, _)) = running.take() {
+                            gate.invalidate();
+                            gate.request_stop();
+                            gate.end(ticket);
+                            gate.joined();
+                        }
+                        running = Some((gate.begin(live_position), false));
+                    }
+                    // stop
+                    3 => gate.request_stop(),
+                    // 探索スレッドが結果を出力する
+                    4 => {
+                        if let Some((ticket, emitted)) = running.as_mut()
+                            && !*emitted
+                        {
+                            let state = gate.engine_state();
+                            if gate.emit_if_live(*ticket, || {}) {
+                                assert!(matches!(
+                                    state,
+                                    EngineState::Searching | EngineState::Stopping
+                                ));
+                            }
+                            *emitted = true;
+                        }
+                    }
+                    // 探索スレッドの終了と join
+                    _ => {
+                        if let Some((ticket, _)) = running.take() {
+                            gate.end(ticket);
+                            gate.joined();
+                            assert_eq!(gate.engine_state(), EngineState::Ready);
+                        }
+                    }
+                }
+                if running.is_none() {
+                    assert_eq!(gate.engine_state(), EngineState::Ready);
+                } else {
+                    assert_ne!(gate.engine_state(), EngineState::Ready);
+                }
+            }
+            assert_eq!(gate.violations(), 0);
+        }
+    }
+}
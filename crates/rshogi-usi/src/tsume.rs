@@ -0,0 +1,51 @@
+//! `go mate` による詰将棋解答
+//!
+//! USI 仕様の `go mate <ms>` / `go mate infinite` を df-pn ソルバーで解き、
+//! `checkmate <moves>` / `checkmate nomate` / `checkmate timeout` を返す。
+
+use std::time::Duration;
+
+use rshogi_core::mate::dfpn::{DfPnLimits, MateOutcome};
+
+/// `go mate` の値（ミリ秒、`i32::MAX` は infinite）から探索制限を作る
+pub fn mate_limits(mate_ms: i32) -> DfPnLimits {
+    DfPnLimits {
+        time_limit: (mate_ms != i32::MAX)
+            .then(|| Duration::from_millis(u64::try_from(mate_ms).unwrap_or(0))),
+        ..DfPnLimits::default()
+    }
+}
+
+/// 探索結果を USI の `checkmate` 行にする
+///
+/// 時間切れ・`stop` で結論が出なかった場合は `checkmate timeout`。
+pub fn checkmate_line(outcome: &MateOutcome) -> String {
+    match outcome {
+        MateOutcome::Mate(pv) => {
+            let moves: Vec<String> = pv.iter().map(|m| m.to_usi()).collect();
+            format!("checkmate {}", moves.join(" "))
+        }
+        MateOutcome::NoMate => "checkmate nomate".to_string(),
+        MateOutcome::Unknown => "checkmate timeout".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rshogi_core::types::Move;
+
+    #[test]
+    fn mate_limits_maps_infinite_to_no_time_limit() {
+        assert_eq!(mate_limits(i32::MAX).time_limit, None);
+        assert_eq!(mate_limits(500).time_limit, Some(Duration::from_millis(500)));
+    }
+
+    #[test]
+    fn checkmate_line_follows_usi_spec() {
+        let pv = vec![Move::from_usi("G*5b").unwrap()];
+        assert_eq!(checkmate_line(&MateOutcome::Mate(pv)), "checkmate G*5b");
+        assert_eq!(checkmate_line(&MateOutcome::NoMate), "checkmate nomate");
+        assert_eq!(checkmate_line(&MateOutcome::Unknown), "checkmate timeout");
+    }
+}
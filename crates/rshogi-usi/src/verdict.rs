@@ -0,0 +1,274 @@
+//! `bestmove win` / `bestmove resign` の判定と根拠の出力
+//!
+//! 探索結果をそのまま bestmove にすると、宣言勝ちや投了の理由が GUI・ログに残らない。
+//! ここでは bestmove を決める際に、どの規則・どの根拠で `win` / `resign` になったかを
+//! `info string verdict ...` の 1 行（meta record）として併せて返す。
+//!
+//! 宣言勝ちは選択中の `EnteringKingRule` で局面を再判定し、規則上宣言できない場合は
+//! `bestmove win` を出さない。
+
+use rshogi_core::movegen::{MoveList, generate_legal};
+use rshogi_core::position::Position;
+use rshogi_core::search::SearchResult;
+use rshogi_core::types::{EnteringKingRule, Move, Value};
+
+/// 投了判定の根拠として残す評価値履歴の最大件数
+pub const SCORE_HISTORY_LEN: usize = 8;
+
+/// 投了判定を無効にする ResignValue の既定値（YaneuraOu 準拠）
+pub const DEFAULT_RESIGN_VALUE: i32 = 99999;
+
+/// 投了の理由
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResignReason {
+    /// 合法手が無い（詰み）
+    NoLegalMoves,
+    /// 評価値が `-threshold` 以下になった
+    ScoreBelowThreshold {
+        threshold: i32,
+        /// 直近の評価値（古い順、最後が今回の探索）
+        history: Vec<i32>,
+    },
+}
+
+/// bestmove として出力する内容
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verdict {
+    /// 通常の指し手
+    Move { best: Move, ponder: Move },
+    /// 入玉宣言勝ち
+    Win {
+        rule: EnteringKingRule,
+        points: u32,
+        required: u32,
+    },
+    /// 投了
+    Resign(ResignReason),
+}
+
+impl Verdict {
+    /// 探索結果から bestmove を決める
+    ///
+    /// * `history` - 今回の評価値を含む、この対局での直近の評価値（古い順）
+    /// * `resign_value` - 評価値がこの値の符号反転以下なら投了する
+    pub fn decide(
+        pos: &Position,
+        rule: EnteringKingRule,
+        result: &SearchResult,
+        history: &[i32],
+        resign_value: i32,
+    ) -> Self {
+        if result.best_move.is_win() {
+            if let Some(verdict) = Self::declaration(pos, rule) {
+                return verdict;
+            }
+            // 規則上宣言できない局面で WIN が返った場合は、宣言せず合法手で指し継ぐ
+            eprintln!(
+                "info string Warning: declaration win refused under EnteringKingRule {}",
+                rule.to_usi()
+            );
+            let mut moves = MoveList::new();
+            generate_legal(pos, &mut moves);
+            return match moves.iter().next() {
+                Some(&m) => Self::Move {
+                    best: m,
+                    ponder: Move::NONE,
+                },
+                None => Self::Resign(ResignReason::NoLegalMoves),
+            };
+        }
+
+        if result.best_move == Move::NONE {
+            return Self::Resign(ResignReason::NoLegalMoves);
+        }
+
+        if result.score.raw() <= -resign_value {
+            return Self::Resign(ResignReason::ScoreBelowThreshold {
+                threshold: resign_value,
+                history: history.to_vec(),
+            });
+        }
+
+        Self::Move {
+            best: result.best_move,
+            ponder: result.ponder_move,
+        }
+    }
+
+    /// 選択中の規則で入玉宣言できるなら `Verdict::Win` を返す
+    fn declaration(pos: &Position, rule: EnteringKingRule) -> Option<Self> {
+        if pos.declaration_win(rule) != Move::WIN {
+            return None;
+        }
+        Some(Self::Win {
+            rule,
+            points: pos.declaration_points(),
+            required: pos.declaration_required_points(rule)?,
+        })
+    }
+
+    /// `bestmove ...` 行
+    pub fn bestmove_line(&self) -> String {
+        match self {
+            Self::Move { best, ponder } if *ponder != Move::NONE => {
+                format!("bestmove {} ponder {}", best.to_usi(), ponder.to_usi())
+            }
+            Self::Move { best, .. } => format!("bestmove {}", best.to_usi()),
+            Self::Win { .. } => "bestmove win".to_string(),
+            Self::Resign(_) => "bestmove resign".to_string(),
+        }
+    }
+
+    /// 根拠を示す `info string verdict ...` 行（通常の指し手では `None`）
+    pub fn meta_line(&self) -> Option<String> {
+        match self {
+            Self::Move { .. } => None,
+            Self::Win {
+                rule,
+                points,
+                required,
+            } => Some(format!(
+                "info string verdict win reason=declaration rule={} points={points} required={required}",
+                rule.to_usi()
+            )),
+            Self::Resign(ResignReason::NoLegalMoves) => {
+                Some("info string verdict resign reason=no_legal_moves".to_string())
+            }
+            Self::Resign(ResignReason::ScoreBelowThreshold { threshold, history }) => {
+                let scores: Vec<String> = history.iter().map(i32::to_string).collect();
+                Some(format!(
+                    "info string verdict resign reason=resign_value threshold={threshold} scores={}",
+                    scores.join(",")
+                ))
+            }
+        }
+    }
+}
+
+/// 評価値履歴に今回の評価値を追加する（直近 `SCORE_HISTORY_LEN` 件を保持）
+///
+/// 宣言勝ちなどで指し手が無い探索の評価値は記録しない。
+pub fn push_score(history: &mut Vec<i32>, result: &SearchResult) {
+    if !result.best_move.is_normal() || result.score == Value::NONE {
+        return;
+    }
+    history.push(result.score.raw());
+    if history.len() > SCORE_HISTORY_LEN {
+        history.remove(0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(best_move: Move, score: i32) -> SearchResult {
+        SearchResult {
+            best_move,
+            ponder_move: Move::NONE,
+            score: Value::new(score),
+            depth: 1,
+            nodes: 0,
+            pv: Vec::new(),
+            confidence: Default::default(),
+            stats_report: String::new(),
+            lines: Vec::new(),
+        }
+    }
+
+    fn make_pos(sfen: &str) -> Position {
+        let mut pos = Position::new();
+        pos.set_sfen(sfen).unwrap();
+        pos
+    }
+
+    const HIRATE_SFEN: &str = "lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL b - 1";
+
+    /// 先手が 27 点法で宣言勝ちできる局面（30 点）
+    const DECLARATION_SFEN: &str = "KGG6/SS7/PPPPPP3/9/9/9/2pppppp1/1ss1gg1nl/4k2nl b 2R2B3p 1";
+
+    #[test]
+    fn win_reports_rule_and_points() {
+        let pos = make_pos(DECLARATION_SFEN);
+        let verdict = Verdict::decide(
+            &pos,
+            EnteringKingRule::Point27,
+            &result(Move::WIN, Value::MATE.raw()),
+            &[],
+            DEFAULT_RESIGN_VALUE,
+        );
+        assert_eq!(verdict.bestmove_line(), "bestmove win");
+        assert_eq!(
+            verdict.meta_line().unwrap(),
+            "info string verdict win reason=declaration rule=CSARule27 points=30 required=28"
+        );
+    }
+
+    #[test]
+    fn win_is_refused_when_rule_forbids_declaration() {
+        let pos = make_pos(DECLARATION_SFEN);
+        for rule in [EnteringKingRule::None, EnteringKingRule::Point24] {
+            let verdict = Verdict::decide(
+                &pos,
+                rule,
+                &result(Move::WIN, Value::MATE.raw()),
+                &[],
+                DEFAULT_RESIGN_VALUE,
+            );
+            assert!(matches!(verdict, Verdict::Move { .. }), "{rule:?}: {verdict:?}");
+            assert_ne!(verdict.bestmove_line(), "bestmove win");
+        }
+    }
+
+    #[test]
+    fn resign_when_no_legal_moves() {
+        let pos = make_pos(HIRATE_SFEN);
+        let verdict = Verdict::decide(
+            &pos,
+            EnteringKingRule::Point27,
+            &result(Move::NONE, 0),
+            &[],
+            DEFAULT_RESIGN_VALUE,
+        );
+        assert_eq!(verdict.bestmove_line(), "bestmove resign");
+        assert_eq!(
+            verdict.meta_line().unwrap(),
+            "info string verdict resign reason=no_legal_moves"
+        );
+    }
+
+    #[test]
+    fn resign_value_uses_score_history_as_evidence() {
+        let pos = make_pos(HIRATE_SFEN);
+        let m = Move::from_usi("7g7f").unwrap();
+        let verdict = Verdict::decide(
+            &pos,
+            EnteringKingRule::Point27,
+            &result(m, -2500),
+            &[-1800, -2500],
+            2000,
+        );
+        assert_eq!(verdict.bestmove_line(), "bestmove resign");
+        assert_eq!(
+            verdict.meta_line().unwrap(),
+            "info string verdict resign reason=resign_value threshold=2000 scores=-1800,-2500"
+        );
+
+        let verdict =
+            Verdict::decide(&pos, EnteringKingRule::Point27, &result(m, -1500), &[-1500], 2000);
+        assert_eq!(verdict.bestmove_line(), "bestmove 7g7f");
+        assert_eq!(verdict.meta_line(), None);
+    }
+
+    #[test]
+    fn push_score_keeps_recent_history() {
+        let m = Move::from_usi("7g7f").unwrap();
+        let mut history = Vec::new();
+        for i in 0..(SCORE_HISTORY_LEN as i32 + 2) {
+            push_score(&mut history, &result(m, i));
+        }
+        push_score(&mut history, &result(Move::WIN, Value::MATE.raw()));
+        assert_eq!(history.len(), SCORE_HISTORY_LEN);
+        assert_eq!(*history.last().unwrap(), SCORE_HISTORY_LEN as i32 + 1);
+    }
+}
@@ -114,3 +114,168 @@ fn stochastic_ponderhit_restarts_search() {
     assert!(stdout.contains("bestmove"), "stdout:\n{stdout}");
     assert!(output.status.success());
 }
+
+/// 上限を超える巨大な入力行は読み捨てられ、後続コマンドは通常どおり処理されること
+#[test]
+fn overlong_line_is_discarded() {
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("rshogi-usi"));
+    let mut child = cmd
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .expect("spawn engine");
+
+    {
+        let stdin = child.stdin.as_mut().expect("stdin");
+        let huge_moves = "7g7f 3c3d ".repeat(64 * 1024);
+        write!(
+            stdin,
+            "{USI_INIT}position startpos moves {huge_moves}\nposition startpos\ngo depth 1\nquit\n"
+        )
+        .expect("write");
+    }
+
+    let output = child.wait_with_output().expect("wait output");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stdout.contains("bestmove"), "stdout:\n{stdout}");
+    assert!(stderr.contains("input line too long"), "stderr:\n{stderr}");
+    assert!(output.status.success());
+}
+
+/// `go mate` は bestmove ではなく checkmate で詰み手順を返すこと
+#[test]
+fn go_mate_outputs_checkmate() {
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("rshogi-usi"));
+    let mut child = cmd
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .spawn()
+        .expect("spawn engine");
+
+    {
+        let stdin = child.stdin.as_mut().expect("stdin");
+        write!(stdin, "{USI_INIT}position sfen 4k4/9/4P4/9/9/9/9/9/4K4 b G 1\ngo mate 10000\n")
+            .expect("write");
+    }
+
+    let stdout = child.stdout.take().expect("stdout");
+    let line = std::io::BufRead::lines(std::io::BufReader::new(stdout))
+        .map_while(Result::ok)
+        .find(|line| line.starts_with("checkmate"));
+    writeln!(child.stdin.as_mut().expect("stdin"), "quit").expect("write");
+    child.wait().expect("wait");
+    assert_eq!(line.as_deref(), Some("checkmate G*5b"));
+}
+
+/// position / go / stop を高速に繰り返しても、bestmove は `go` 時点の局面に対してだけ
+/// ちょうど 1 回ずつ返ること（探索中に別局面の position が来た探索は bestmove を返さない）
+#[test]
+fn rapid_position_go_stop_cycles_emit_bestmove_only_for_live_position() {
+    const CYCLES: usize = 30;
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("rshogi-usi"));
+    let mut child = cmd
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .spawn()
+        .expect("spawn engine");
+
+    {
+        let stdin = child.stdin.as_mut().expect("stdin");
+        write!(stdin, "{USI_INIT}").expect("write");
+        for _ in 0..CYCLES {
+            // 通常の go → stop: bestmove 1 回
+            write!(stdin, "position startpos\ngo depth 1\nstop\n").expect("write");
+            // 探索中に別局面の position → stop: 出さない
+            write!(stdin, "position startpos\ngo infinite\nposition startpos moves 7g7f\nstop\n")
+                .expect("write");
+            // 探索中に別局面の position → go: 新しい go の分だけ 1 回
+            write!(
+                stdin,
+                "position startpos\ngo infinite\nposition startpos moves 7g7f\ngo depth 1\nstop\n"
+            )
+            .expect("write");
+        }
+        writeln!(stdin, "quit").expect("write");
+    }
+
+    let output = child.wait_with_output().expect("wait output");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let bestmoves = stdout.lines().filter(|line| line.starts_with("bestmove")).count();
+    assert_eq!(bestmoves, CYCLES * 2, "stdout:\n{stdout}");
+    assert!(output.status.success());
+}
+
+/// USI_AnalyseMode では秒読みを指定した go でも stop まで bestmove を返さないこと
+#[test]
+fn analyse_mode_keeps_searching_until_stop() {
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("rshogi-usi"));
+    let mut child = cmd
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .spawn()
+        .expect("spawn engine");
+
+    let stdout = child.stdout.take().expect("stdout");
+    let (tx, rx) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        for line in std::io::BufRead::lines(std::io::BufReader::new(stdout)).map_while(Result::ok) {
+            if line.starts_with("bestmove") && tx.send(line).is_err() {
+                break;
+            }
+        }
+    });
+
+    {
+        let stdin = child.stdin.as_mut().expect("stdin");
+        write!(
+            stdin,
+            "usi\nsetoption name MaterialLevel value 9\nsetoption name USI_AnalyseMode value true\n\
+             isready\nposition startpos\ngo btime 0 wtime 0 byoyomi 100\n"
+        )
+        .expect("write");
+    }
+    // 通常なら秒読み 100ms で bestmove を返す
+    let early = rx.recv_timeout(std::time::Duration::from_millis(1000));
+    assert!(early.is_err(), "bestmove before stop: {early:?}");
+
+    writeln!(child.stdin.as_mut().expect("stdin"), "stop").expect("write");
+    let bestmove = rx.recv_timeout(std::time::Duration::from_secs(10));
+    writeln!(child.stdin.as_mut().expect("stdin"), "quit").expect("write");
+    child.wait().expect("wait");
+    assert!(bestmove.is_ok(), "no bestmove after stop");
+}
+
+/// TTSaveFile で gameover 時に保存した置換表を、別プロセスの TTLoadFile で isready 時に読み込めること
+#[test]
+fn tt_saved_at_gameover_loads_at_isready() {
+    let path = std::env::temp_dir().join(format!("rshogi-usi-tt-{}.bin", std::process::id()));
+    let run = |commands: String| {
+        let mut child = Command::new(assert_cmd::cargo::cargo_bin!("rshogi-usi"))
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .spawn()
+            .expect("spawn engine");
+        write!(child.stdin.as_mut().expect("stdin"), "{commands}").expect("write");
+        let output = child.wait_with_output().expect("wait output");
+        assert!(output.status.success());
+        String::from_utf8_lossy(&output.stderr).into_owned()
+    };
+
+    let stderr = run(format!(
+        "usi\nsetoption name USI_Hash value 16\nsetoption name TTSaveFile value {}\n{USI_INIT}\
+         position startpos\ngo depth 6\ngameover win\nquit\n",
+        path.display()
+    ));
+    assert!(stderr.contains("TT saved"), "stderr:\n{stderr}");
+
+    let stderr = run(format!(
+        "usi\nsetoption name USI_Hash value 16\nsetoption name TTLoadFile value {}\n{USI_INIT}quit\n",
+        path.display()
+    ));
+    let _ = std::fs::remove_file(&path);
+    assert!(stderr.contains("TT loaded"), "stderr:\n{stderr}");
+    assert!(!stderr.contains("Error loading TTLoadFile"), "stderr:\n{stderr}");
+}
@@ -0,0 +1,267 @@
+//! ベンチマーク履歴と性能退行の検知
+//!
+//! `benchmark --daemon` が定期実行のたびに 1 行ずつ追記する JSONL 形式の履歴と、
+//! ベースラインとの比較結果から閾値を超えた退行を拾う判定を提供する。
+//! 履歴は 1 実行 = 1 行で、スレッド数ごとの平均 NPS と TTD（time-to-depth）を持つ。
+
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+
+use crate::report::{BenchmarkReport, ThreadComparison};
+
+/// 退行とみなす変化率の閾値（%）
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RegressionThresholds {
+    /// 平均 NPS がこの割合を超えて低下したら退行
+    pub nps_drop_percent: f64,
+    /// TTD がこの割合を超えて増加したら退行
+    pub ttd_increase_percent: f64,
+}
+
+/// 退行を検知した指標
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RegressionMetric {
+    Nps,
+    Ttd,
+}
+
+/// 閾値を超えた退行 1 件
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Regression {
+    /// スレッド数
+    pub threads: usize,
+    /// 指標
+    pub metric: RegressionMetric,
+    /// ベースラインからの変化率（%）
+    pub delta_percent: f64,
+}
+
+impl std::fmt::Display for Regression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let metric = match self.metric {
+            RegressionMetric::Nps => "NPS",
+            RegressionMetric::Ttd => "TTD",
+        };
+        write!(f, "threads={} {metric} {:+.1}%", self.threads, self.delta_percent)
+    }
+}
+
+/// 比較結果から閾値を超えた退行を抜き出す
+pub fn detect_regressions(
+    comparisons: &[ThreadComparison],
+    thresholds: &RegressionThresholds,
+) -> Vec<Regression> {
+    let mut regressions = Vec::new();
+    for cmp in comparisons {
+        if cmp.baseline_nps > 0 && -cmp.nps_delta_percent > thresholds.nps_drop_percent {
+            regressions.push(Regression {
+                threads: cmp.threads,
+                metric: RegressionMetric::Nps,
+                delta_percent: cmp.nps_delta_percent,
+            });
+        }
+        if let Some(delta) = cmp.ttd_delta_percent
+            && delta > thresholds.ttd_increase_percent
+        {
+            regressions.push(Regression {
+                threads: cmp.threads,
+                metric: RegressionMetric::Ttd,
+                delta_percent: delta,
+            });
+        }
+    }
+    regressions
+}
+
+/// 履歴 1 行中のスレッド数ごとの集計
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoryThreadEntry {
+    /// スレッド数
+    pub threads: usize,
+    /// 平均 NPS
+    pub average_nps: u64,
+    /// TTD の深さ（記録が無ければ 0）
+    pub ttd_depth: i32,
+    /// `ttd_depth` 到達時間の合計（ミリ秒）
+    pub total_time_to_depth_ms: u64,
+}
+
+/// 履歴 1 行（1 回のベンチマーク実行）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BenchHistoryEntry {
+    /// 実行完了時刻（RFC 3339）
+    pub timestamp: String,
+    /// エンジン名（USI モードはバイナリ名、内部 API モードは `internal`）
+    pub engine_name: String,
+    /// 詳細な結果 JSON のパス
+    pub report_path: String,
+    /// スレッド数ごとの集計
+    pub results: Vec<HistoryThreadEntry>,
+    /// ベースラインに対して検知した退行（ベースラインが無ければ空）
+    #[serde(default)]
+    pub regressions: Vec<Regression>,
+}
+
+impl BenchHistoryEntry {
+    /// レポートから履歴 1 行を作る
+    pub fn from_report(
+        report: &BenchmarkReport,
+        timestamp: String,
+        engine_name: &str,
+        report_path: &Path,
+        regressions: Vec<Regression>,
+    ) -> Self {
+        let results = report
+            .results
+            .iter()
+            .map(|r| {
+                let agg = r.aggregate();
+                HistoryThreadEntry {
+                    threads: r.threads,
+                    average_nps: agg.average_nps,
+                    ttd_depth: agg.ttd_depth,
+                    total_time_to_depth_ms: agg.total_time_to_depth_ms,
+                }
+            })
+            .collect();
+        Self {
+            timestamp,
+            engine_name: engine_name.to_string(),
+            report_path: report_path.display().to_string(),
+            results,
+            regressions,
+        }
+    }
+}
+
+/// 履歴ファイル（JSONL）に 1 行追記する。ファイルが無ければ作成する。
+pub fn append_history(path: &Path, entry: &BenchHistoryEntry) -> Result<()> {
+    if let Some(parent) = path.parent()
+        && !parent.as_os_str().is_empty()
+    {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open history file: {}", path.display()))?;
+    let line = serde_json::to_string(entry)?;
+    writeln!(file, "{line}")
+        .with_context(|| format!("Failed to append history: {}", path.display()))?;
+    Ok(())
+}
+
+/// 履歴ファイル（JSONL）を読み込む。空行は無視する。
+pub fn load_history(path: &Path) -> Result<Vec<BenchHistoryEntry>> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read history file: {}", path.display()))?;
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            serde_json::from_str(line)
+                .with_context(|| format!("{}:{}: invalid history entry", path.display(), i + 1))
+        })
+        .collect()
+}
+
+/// `30m` / `6h` / `1d` / `90s` 形式の間隔を解釈する（単位省略時は秒）
+pub fn parse_interval(s: &str) -> Result<Duration> {
+    let s = s.trim();
+    let (digits, unit) = match s.find(|c: char| !c.is_ascii_digit()) {
+        Some(pos) => s.split_at(pos),
+        None => (s, "s"),
+    };
+    let value: u64 = digits.parse().with_context(|| format!("invalid interval: {s:?}"))?;
+    let secs_per_unit = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => bail!("invalid interval unit in {s:?} (expected s, m, h or d)"),
+    };
+    if value == 0 {
+        bail!("interval must be positive: {s:?}");
+    }
+    Ok(Duration::from_secs(value * secs_per_unit))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn comparison(nps_delta: f64, ttd_delta: Option<f64>) -> ThreadComparison {
+        ThreadComparison {
+            threads: 4,
+            baseline_nps: 1_000_000,
+            current_nps: (1_000_000.0 * (1.0 + nps_delta / 100.0)) as u64,
+            nps_delta_percent: nps_delta,
+            ttd_depth: 12,
+            baseline_ttd_ms: ttd_delta.map(|_| 1000),
+            current_ttd_ms: ttd_delta.map(|d| (1000.0 * (1.0 + d / 100.0)) as u64),
+            ttd_delta_percent: ttd_delta,
+        }
+    }
+
+    #[test]
+    fn test_detect_regressions_applies_thresholds() {
+        let thresholds = RegressionThresholds {
+            nps_drop_percent: 3.0,
+            ttd_increase_percent: 5.0,
+        };
+        // 閾値内の変動と改善は退行ではない
+        assert!(detect_regressions(&[comparison(-2.9, Some(4.0))], &thresholds).is_empty());
+        assert!(detect_regressions(&[comparison(10.0, Some(-20.0))], &thresholds).is_empty());
+
+        let regressions = detect_regressions(&[comparison(-5.0, Some(8.0))], &thresholds);
+        assert_eq!(regressions.len(), 2);
+        assert_eq!(regressions[0].metric, RegressionMetric::Nps);
+        assert_eq!(regressions[1].metric, RegressionMetric::Ttd);
+        assert_eq!(regressions[0].to_string(), "threads=4 NPS -5.0%");
+
+        // TTD が比較できない場合は NPS のみで判定する
+        let regressions = detect_regressions(&[comparison(-5.0, None)], &thresholds);
+        assert_eq!(regressions.len(), 1);
+    }
+
+    #[test]
+    fn test_history_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested/history.jsonl");
+        let entry = BenchHistoryEntry {
+            timestamp: "2026-01-02T03:04:05+09:00".to_string(),
+            engine_name: "internal".to_string(),
+            report_path: "benchmark_results/a.json".to_string(),
+            results: vec![HistoryThreadEntry {
+                threads: 1,
+                average_nps: 1234,
+                ttd_depth: 10,
+                total_time_to_depth_ms: 500,
+            }],
+            regressions: vec![],
+        };
+        append_history(&path, &entry).unwrap();
+        append_history(&path, &entry).unwrap();
+        let loaded = load_history(&path).unwrap();
+        assert_eq!(loaded, vec![entry.clone(), entry]);
+    }
+
+    #[test]
+    fn test_parse_interval() {
+        assert_eq!(parse_interval("6h").unwrap(), Duration::from_secs(6 * 3600));
+        assert_eq!(parse_interval("30m").unwrap(), Duration::from_secs(1800));
+        assert_eq!(parse_interval("1d").unwrap(), Duration::from_secs(86400));
+        assert_eq!(parse_interval("90").unwrap(), Duration::from_secs(90));
+        assert!(parse_interval("0h").is_err());
+        assert!(parse_interval("6w").is_err());
+        assert!(parse_interval("h").is_err());
+    }
+}
@@ -17,6 +17,7 @@ use anyhow::{Context, Result, bail};
 use clap::Parser;
 use serde::{Deserialize, Serialize};
 
+use rshogi_core::position::Position;
 use tools::sprt::{GameSide, Penta, SprtMetaLog, SprtParameters, judge};
 
 // ---------------------------------------------------------------------------
@@ -129,6 +130,8 @@ struct MoveLog {
     think_limit_ms: u64,
     timed_out: bool,
     #[serde(default)]
+    sfen_before: Option<String>,
+    #[serde(default)]
     eval: Option<MoveEval>,
 }
 
@@ -255,6 +258,7 @@ struct EngineMoveStats {
     eval_nodes_count: u64,
     by_side: BTreeMap<String, MoveBucketStats>,
     by_ply_band: BTreeMap<String, MoveBucketStats>,
+    by_phase: BTreeMap<String, MoveBucketStats>,
 }
 
 #[derive(Default, Clone)]
@@ -351,6 +355,7 @@ struct JsonEngineTiming {
     average_nodes: Option<f64>,
     by_side: Vec<JsonTimingBucket>,
     by_ply_band: Vec<JsonTimingBucket>,
+    by_phase: Vec<JsonTimingBucket>,
 }
 
 #[derive(Serialize)]
@@ -396,6 +401,15 @@ fn ply_band_label(ply: u32) -> &'static str {
     }
 }
 
+/// 局面の段階（`Position::phase`）の表示順
+const PHASE_LABELS: [&str; 3] = ["opening", "middlegame", "endgame"];
+
+fn phase_label(sfen: &str) -> Option<&'static str> {
+    let mut pos = Position::new();
+    pos.set_sfen(sfen).ok()?;
+    Some(pos.phase().stage().as_str())
+}
+
 fn update_move_bucket(stats: &mut MoveBucketStats, elapsed_ms: u64) {
     stats.moves += 1;
     stats.elapsed_ms_sum += elapsed_ms;
@@ -424,6 +438,11 @@ fn merge_engine_move_stats(dst: &mut EngineMoveStats, src: &EngineMoveStats) {
         dst_bucket.moves += bucket.moves;
         dst_bucket.elapsed_ms_sum += bucket.elapsed_ms_sum;
     }
+    for (label, bucket) in &src.by_phase {
+        let dst_bucket = dst.by_phase.entry(label.clone()).or_default();
+        dst_bucket.moves += bucket.moves;
+        dst_bucket.elapsed_ms_sum += bucket.elapsed_ms_sum;
+    }
 }
 
 fn average(sum: u64, count: u64) -> f64 {
@@ -549,6 +568,12 @@ fn parse_normal_file(path: &str) -> Result<FileResult> {
                 engine_stats.by_ply_band.entry(ply_band_label(mv.ply).to_string()).or_default(),
                 mv.elapsed_ms,
             );
+            if let Some(label) = mv.sfen_before.as_deref().and_then(phase_label) {
+                update_move_bucket(
+                    engine_stats.by_phase.entry(label.to_string()).or_default(),
+                    mv.elapsed_ms,
+                );
+            }
         } else if trimmed.contains("\"type\":\"result\"") {
             let result: ResultLog = serde_json::from_str(trimmed)
                 .with_context(|| format!("resultパースエラー: {path}"))?;
@@ -1762,6 +1787,16 @@ fn print_text(
                     );
                 }
             }
+            for phase in PHASE_LABELS {
+                if let Some(bucket) = stats.by_phase.get(phase) {
+                    println!(
+                        "    phase {}: moves={} avg_elapsed={:.1}ms",
+                        phase,
+                        bucket.moves,
+                        average(bucket.elapsed_ms_sum, bucket.moves)
+                    );
+                }
+            }
         }
     }
 }
@@ -1903,6 +1938,16 @@ fn print_json(
                     })
                 })
                 .collect(),
+            by_phase: PHASE_LABELS
+                .into_iter()
+                .filter_map(|label| {
+                    stats.by_phase.get(label).map(|bucket| JsonTimingBucket {
+                        label: label.to_string(),
+                        moves: bucket.moves,
+                        average_elapsed_ms: average(bucket.elapsed_ms_sum, bucket.moves),
+                    })
+                })
+                .collect(),
         })
         .collect();
     let decisive = extra.black_wins + extra.white_wins;
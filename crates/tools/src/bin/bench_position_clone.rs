@@ -0,0 +1,95 @@
+//! 長手数の局面の複製コストを測る
+//!
+//! ランダムな合法手で `--plies` 手（既定 500 手）まで進めた対局を `--games` 局生成し、
+//! 最終局面の `clone()`（状態スタックを丸ごと複製）と `clone_with_history()`
+//! （現在の状態と千日手判定用の圧縮履歴だけを複製）の 1 回あたりの時間を比較する。
+//!
+//! ```bash
+//! cargo run --release -p tools --bin bench_position_clone -- --plies 500 --games 8
+//! ```
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use anyhow::{Result, bail};
+use clap::Parser;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use rshogi_core::movegen::{MoveList, generate_legal};
+use rshogi_core::position::Position;
+
+#[derive(Parser, Debug)]
+#[command(
+    version,
+    about = "長手数の局面の clone / clone_with_history の時間を比較"
+)]
+struct Cli {
+    /// 1 局の手数
+    #[arg(long, default_value_t = 500)]
+    plies: usize,
+
+    /// 生成する対局数
+    #[arg(long, default_value_t = 8)]
+    games: usize,
+
+    /// 1 局面あたりの複製回数
+    #[arg(long, default_value_t = 10_000)]
+    iterations: usize,
+
+    /// 乱数シード
+    #[arg(long, default_value_t = 1)]
+    seed: u64,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let mut rng = ChaCha8Rng::seed_from_u64(cli.seed);
+
+    let mut games = Vec::with_capacity(cli.games);
+    let mut attempts = 0;
+    while games.len() < cli.games {
+        attempts += 1;
+        if attempts > cli.games * 1000 {
+            bail!("failed to generate {} games of {} plies", cli.games, cli.plies);
+        }
+        if let Some(pos) = random_game(&mut rng, cli.plies) {
+            games.push(pos);
+        }
+    }
+
+    let clone_ns = measure(&games, cli.iterations, |pos| black_box(pos.clone()));
+    let snapshot_ns = measure(&games, cli.iterations, |pos| black_box(pos.clone_with_history()));
+    println!(
+        "plies={} games={} iterations={} clone={clone_ns:.1}ns clone_with_history={snapshot_ns:.1}ns",
+        cli.plies, cli.games, cli.iterations
+    );
+    Ok(())
+}
+
+/// 平手からランダムな合法手で `plies` 手進めた局面（途中で詰んだら `None`）
+fn random_game(rng: &mut ChaCha8Rng, plies: usize) -> Option<Position> {
+    let mut pos = Position::new();
+    pos.set_hirate();
+    for _ in 0..plies {
+        let mut list = MoveList::new();
+        generate_legal(&pos, &mut list);
+        if list.is_empty() {
+            return None;
+        }
+        let mv = list.at(rng.random_range(0..list.len()));
+        let gives_check = pos.gives_check(mv);
+        pos.do_move(mv, gives_check);
+    }
+    Some(pos)
+}
+
+/// 1 回あたりの平均時間（ナノ秒）
+fn measure(games: &[Position], iterations: usize, f: impl Fn(&Position) -> Position) -> f64 {
+    let start = Instant::now();
+    for pos in games {
+        for _ in 0..iterations {
+            drop(f(pos));
+        }
+    }
+    start.elapsed().as_nanos() as f64 / (games.len() * iterations) as f64
+}
@@ -1,14 +1,35 @@
 //! 将棋エンジンベンチマークツール
 //!
 //! YaneuraOu の bench コマンド相当の標準ベンチマークを提供します。
+//!
+//! `--daemon` を付けると `--interval` ごとに同じ条件で再実行し、結果を履歴
+//! （JSONL）に追記する。ベースライン（`--compare`、未指定時は直前の実行）から
+//! 平均 NPS / TTD が閾値を超えて悪化したら `--alert-cmd` を実行する。
+//! 常駐モード以外で `--compare`（`--baseline`）を付けた場合は、同じ判定で退行があれば
+//! 終了コード 1 で終わる。
+//!
+//! ```bash
+//! cargo run --release -p tools --bin benchmark -- \
+//!   --engine ./target/release/rshogi-usi --daemon --interval 6h \
+//!   --alert-cmd 'notify-send "bench regression" "$RSHOGI_BENCH_ALERT"'
+//! ```
+//!
+//! `--hash-profile` を付けると置換表サイズを変えて同じベンチマークを繰り返し、
+//! サイズごとの NPS・TTD・置換表のヒット率やメモリ帯域を表にする（[`tools::hash_profile`]）。
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::process::Command;
 
-use anyhow::Result;
+use anyhow::{Context, Result, bail};
 use chrono::Local;
 use clap::{Parser, ValueEnum};
 
-use tools::{BenchmarkConfig, EvalConfig, LimitType, runner};
+use tools::bench_history::{
+    BenchHistoryEntry, Regression, RegressionThresholds, append_history, detect_regressions,
+    parse_interval,
+};
+use tools::hash_profile::HashProfile;
+use tools::{BenchmarkConfig, BenchmarkReport, EvalConfig, LimitType, runner};
 
 /// 将棋エンジン汎用ベンチマークツール
 #[derive(Parser, Debug)]
@@ -82,6 +103,40 @@ struct Cli {
     /// 追加の USI オプション (format: "Name=Value", can be repeated)
     #[arg(long = "usi-option", num_args = 1..)]
     usi_options: Option<Vec<String>>,
+
+    /// 比較対象のベースライン結果 JSON（NPS と time-to-depth の差分、局面ごとの NPS・ノード数・深さの差分を表示）。
+    /// 常駐モード以外では閾値を超えた退行があると終了コード 1 で終わる
+    #[arg(long, visible_alias = "baseline")]
+    compare: Option<PathBuf>,
+
+    /// 常駐モード: --interval ごとにベンチマークを繰り返す
+    #[arg(long)]
+    daemon: bool,
+
+    /// 常駐モードの実行間隔（例: 30m, 6h, 1d）
+    #[arg(long, default_value = "6h")]
+    interval: String,
+
+    /// 履歴ファイル（JSONL、1 実行 1 行）。常駐モードで未指定時は <output-dir>/history.jsonl
+    #[arg(long)]
+    history: Option<PathBuf>,
+
+    /// 退行検知時に `sh -c` で実行するコマンド。
+    /// 環境変数 RSHOGI_BENCH_ALERT（要約）/ RSHOGI_BENCH_REPORT（結果 JSON のパス）を渡す
+    #[arg(long)]
+    alert_cmd: Option<String>,
+
+    /// 平均 NPS の低下率（%）がこれを超えたら退行とみなす
+    #[arg(long, default_value_t = 3.0)]
+    nps_threshold: f64,
+
+    /// TTD の増加率（%）がこれを超えたら退行とみなす
+    #[arg(long, default_value_t = 5.0)]
+    ttd_threshold: f64,
+
+    /// 置換表サイズ（MB、カンマ区切り）ごとに繰り返し、サイズ別の表を出力する（--tt-mb の代わり）
+    #[arg(long, value_delimiter = ',')]
+    hash_profile: Vec<u32>,
 }
 
 /// CLI用の制限タイプ（clap ValueEnum対応）
@@ -123,6 +178,14 @@ impl Cli {
             use_eval_hash: self.use_eval_hash,
         }
     }
+
+    /// 退行判定の閾値
+    fn thresholds(&self) -> RegressionThresholds {
+        RegressionThresholds {
+            nps_drop_percent: self.nps_threshold,
+            ttd_increase_percent: self.ttd_threshold,
+        }
+    }
 }
 
 /// 自動生成されるファイル名を作成
@@ -155,31 +218,82 @@ fn generate_output_filename(engine_name: &str, threads: &[usize]) -> String {
     format!("{timestamp}_{safe_engine_name}_{threads_str}.json")
 }
 
+/// 1 回分の実行結果
+struct RunOutcome {
+    report: BenchmarkReport,
+    engine_name: String,
+    output_path: PathBuf,
+}
+
 fn main() -> Result<()> {
     let cli = Cli::parse();
 
+    // 計測後に読み込みエラーで結果を失わないよう、ベースラインは先に読む
+    let baseline = cli.compare.as_deref().map(BenchmarkReport::load_json).transpose()?;
+
+    if !cli.hash_profile.is_empty() {
+        if cli.daemon || baseline.is_some() || cli.history.is_some() {
+            bail!("--hash-profile cannot be combined with --daemon, --compare or --history");
+        }
+        return run_hash_profile(&cli);
+    }
+
+    if cli.daemon {
+        return run_daemon(&cli, baseline);
+    }
+
+    if cli.nps_threshold < 0.0 || cli.ttd_threshold < 0.0 {
+        bail!("--nps-threshold and --ttd-threshold must be non-negative");
+    }
+    let outcome = run_once(&cli)?;
+    let regressions = match &baseline {
+        Some(baseline) => {
+            outcome.report.print_comparison(baseline);
+            detect_regressions(&outcome.report.compare(baseline), &cli.thresholds())
+        }
+        None => Vec::new(),
+    };
+    if let Some(history) = &cli.history {
+        record_history(history, &outcome, regressions.clone())?;
+    }
+    if !regressions.is_empty() {
+        // ローカルやスクリプトで退行を検知できるよう、結果を保存したうえで失敗扱いにする
+        eprintln!("{}", regression_summary(&outcome.engine_name, &regressions));
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
+
+/// 実行モード（内部API / USI）を選んでベンチマークを実行する
+fn run_benchmark(cli: &Cli, config: &BenchmarkConfig) -> Result<(BenchmarkReport, String)> {
     // 実行モード判定（if let パターンで unwrap を回避）
-    let (report, engine_name) = if cli.internal {
+    if cli.internal {
         // 明示的に内部APIモードを指定
         println!("Running internal API mode...");
-        let report = runner::internal::run_internal_benchmark(&cli.to_config())?;
-        (report, "internal".to_string())
+        let report = runner::internal::run_internal_benchmark(config)?;
+        Ok((report, "internal".to_string()))
     } else if let Some(engine_path) = &cli.engine {
         // USIモード
         println!("Running USI mode with engine: {}", engine_path.display());
-        let report = runner::usi::run_usi_benchmark(&cli.to_config(), engine_path)?;
+        let report = runner::usi::run_usi_benchmark(config, engine_path)?;
         let name = engine_path
             .file_name()
             .and_then(|n| n.to_str())
             .unwrap_or("unknown")
             .to_string();
-        (report, name)
+        Ok((report, name))
     } else {
         // デフォルト: 内部APIモード
         println!("Running internal API mode...");
-        let report = runner::internal::run_internal_benchmark(&cli.to_config())?;
-        (report, "internal".to_string())
-    };
+        let report = runner::internal::run_internal_benchmark(config)?;
+        Ok((report, "internal".to_string()))
+    }
+}
+
+/// ベンチマークを 1 回実行し、結果 JSON の保存とコンソール出力まで行う
+fn run_once(cli: &Cli) -> Result<RunOutcome> {
+    let (report, engine_name) = run_benchmark(cli, &cli.to_config())?;
 
     // 出力ディレクトリを作成（存在しない場合）
     if !cli.output_dir.exists() {
@@ -204,5 +318,126 @@ fn main() -> Result<()> {
         report.print_reuse_summary();
     }
 
+    Ok(RunOutcome {
+        report,
+        engine_name,
+        output_path,
+    })
+}
+
+/// 置換表サイズごとにベンチマークを繰り返し、サイズ別の表と JSON を出力する
+fn run_hash_profile(cli: &Cli) -> Result<()> {
+    let mut sizes = cli.hash_profile.clone();
+    sizes.sort_unstable();
+    sizes.dedup();
+
+    let mut reports = Vec::new();
+    let mut engine_name = String::new();
+    for &tt_mb in &sizes {
+        println!("\n=== Hash: {tt_mb} MB ===");
+        let mut config = cli.to_config();
+        config.tt_mb = tt_mb;
+        let (report, name) = run_benchmark(cli, &config)?;
+        report.print_summary();
+        engine_name = name;
+        reports.push((tt_mb, report));
+    }
+    let profile = HashProfile::new(&engine_name, &reports);
+    profile.print_table();
+
+    std::fs::create_dir_all(&cli.output_dir)?;
+    let filename =
+        generate_output_filename(&engine_name, &cli.threads).replace(".json", "_hashprofile.json");
+    let output_path = cli.output_dir.join(filename);
+    profile.save_json(&output_path)?;
+    println!("Results saved to: {}", output_path.display());
     Ok(())
 }
+
+/// 常駐モード: 一定間隔でベンチマークを繰り返し、退行を検知したら通知する
+///
+/// 比較対象は `--compare` の固定ベースライン、未指定時は直前に成功した実行。
+/// 1 回の実行が失敗しても常駐は続け、失敗も通知する。
+fn run_daemon(cli: &Cli, fixed_baseline: Option<BenchmarkReport>) -> Result<()> {
+    let interval = parse_interval(&cli.interval)?;
+    if cli.nps_threshold < 0.0 || cli.ttd_threshold < 0.0 {
+        bail!("--nps-threshold and --ttd-threshold must be non-negative");
+    }
+    let history = cli.history.clone().unwrap_or_else(|| cli.output_dir.join("history.jsonl"));
+    let thresholds = cli.thresholds();
+    let mut previous: Option<BenchmarkReport> = None;
+
+    println!(
+        "Benchmark daemon started (interval: {}, history: {})",
+        cli.interval,
+        history.display()
+    );
+    loop {
+        println!("\n=== Benchmark run at {} ===", Local::now().to_rfc3339());
+        match run_once(cli) {
+            Ok(outcome) => {
+                let baseline = fixed_baseline.as_ref().or(previous.as_ref());
+                let regressions = match baseline {
+                    Some(baseline) => {
+                        outcome.report.print_comparison(baseline);
+                        detect_regressions(&outcome.report.compare(baseline), &thresholds)
+                    }
+                    None => Vec::new(),
+                };
+                if let Err(e) = record_history(&history, &outcome, regressions.clone()) {
+                    eprintln!("Failed to record history: {e:#}");
+                }
+                if !regressions.is_empty() {
+                    let summary = regression_summary(&outcome.engine_name, &regressions);
+                    eprintln!("{summary}");
+                    run_alert(cli, &summary, Some(&outcome.output_path));
+                }
+                previous = Some(outcome.report);
+            }
+            Err(e) => {
+                let summary = format!("benchmark run failed: {e:#}");
+                eprintln!("{summary}");
+                run_alert(cli, &summary, None);
+            }
+        }
+        println!("Next run in {}", cli.interval);
+        std::thread::sleep(interval);
+    }
+}
+
+/// 履歴ファイルに今回の結果を 1 行追記する
+fn record_history(path: &Path, outcome: &RunOutcome, regressions: Vec<Regression>) -> Result<()> {
+    let entry = BenchHistoryEntry::from_report(
+        &outcome.report,
+        Local::now().to_rfc3339(),
+        &outcome.engine_name,
+        &outcome.output_path,
+        regressions,
+    );
+    append_history(path, &entry)?;
+    println!("History appended to: {}", path.display());
+    Ok(())
+}
+
+/// 通知用の 1 行要約
+fn regression_summary(engine_name: &str, regressions: &[Regression]) -> String {
+    let details = regressions.iter().map(ToString::to_string).collect::<Vec<_>>().join(", ");
+    format!("benchmark regression ({engine_name}): {details}")
+}
+
+/// `--alert-cmd` を実行する。コマンドの失敗は警告に留め、常駐は止めない
+fn run_alert(cli: &Cli, summary: &str, report_path: Option<&Path>) {
+    let Some(cmd) = &cli.alert_cmd else {
+        return;
+    };
+    let mut command = Command::new("sh");
+    command.arg("-c").arg(cmd).env("RSHOGI_BENCH_ALERT", summary);
+    if let Some(path) = report_path {
+        command.env("RSHOGI_BENCH_REPORT", path);
+    }
+    match command.status().context("Failed to run --alert-cmd") {
+        Ok(status) if !status.success() => eprintln!("--alert-cmd exited with {status}"),
+        Ok(_) => {}
+        Err(e) => eprintln!("{e:#}"),
+    }
+}
@@ -0,0 +1,233 @@
+//! book - 定跡ファイルの形式変換・検証・マージ
+//!
+//! やねうら王形式（`standard_book.db` など）、Apery 形式、rshogi 形式（`RSHOGIBK`）の
+//! 定跡を相互に変換する。入力形式はファイル内容と拡張子から推定し、出力形式は
+//! 拡張子（`.db` → やねうら王、`.rbook` → rshogi、それ以外 → Apery）から決める。
+//! いずれも `--from` / `--to` で明示できる。
+//!
+//! バイナリ形式（Apery / rshogi）はキーがハッシュ値なので、開始局面（既定は平手、
+//! `--root-sfen` で変更）から定跡手を辿って到達できる局面だけを読み出す。
+//! 形式の詳細とマージの規則は `tools::book_formats` を参照。
+//!
+//! # 使用例
+//!
+//! ```bash
+//! # やねうら王形式 → rshogi 形式（24 手目まで、出現回数 2 回以上の手だけ）
+//! cargo run --release -p tools --bin book -- convert standard_book.db -o book.rbook \
+//!   --max-ply 24 --min-count 2
+//!
+//! # SFEN と指し手の合法性を検査する
+//! cargo run --release -p tools --bin book -- validate user_book1.db
+//!
+//! # 複数の定跡をマージして各局面の最善手だけを残す
+//! cargo run --release -p tools --bin book -- merge a.db b.bin c.rbook -o merged.db --best-score
+//! ```
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Result, bail};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use log::{info, warn};
+
+use tools::book_formats::{BookDb, BookFormat, BookPolicy, read_book, write_book};
+use tools::manifest::{ArtifactKind, record_artifact};
+
+#[derive(Parser)]
+#[command(
+    name = "book",
+    version,
+    about = "定跡ファイルの形式変換・検証・マージ（やねうら王 / Apery / rshogi 形式）"
+)]
+struct Cli {
+    #[command(subcommand)]
+    cmd: Cmd,
+}
+
+#[derive(Subcommand)]
+enum Cmd {
+    /// 定跡を別の形式に変換する
+    Convert {
+        /// 入力ファイル
+        input: PathBuf,
+        /// 出力ファイル
+        #[arg(short, long)]
+        output: PathBuf,
+        #[command(flatten)]
+        read: ReadArgs,
+        #[command(flatten)]
+        write: WriteArgs,
+    },
+    /// SFEN と指し手の合法性を検査する（問題があれば終了コード 1）
+    Validate {
+        /// 入力ファイル
+        input: PathBuf,
+        #[command(flatten)]
+        read: ReadArgs,
+    },
+    /// 複数の定跡をマージする（同じ局面・同じ手は出現回数を合算し、深い探索の評価値を残す）
+    Merge {
+        /// 入力ファイル（形式は個別に推定する）
+        #[arg(required = true, num_args = 1..)]
+        inputs: Vec<PathBuf>,
+        /// 出力ファイル
+        #[arg(short, long)]
+        output: PathBuf,
+        #[command(flatten)]
+        read: ReadArgs,
+        #[command(flatten)]
+        write: WriteArgs,
+    },
+}
+
+#[derive(Args)]
+struct ReadArgs {
+    /// 入力形式（未指定ならファイル内容と拡張子から推定）
+    #[arg(long, value_enum)]
+    from: Option<FormatArg>,
+    /// バイナリ形式で定跡手を辿り始める局面（未指定なら平手）
+    #[arg(long)]
+    root_sfen: Option<String>,
+    /// バイナリ形式で定跡手を辿る手数の上限
+    #[arg(long, default_value_t = 256)]
+    walk_ply: i32,
+}
+
+#[derive(Args)]
+struct WriteArgs {
+    /// 出力形式（未指定なら出力ファイルの拡張子から決める）
+    #[arg(long, value_enum)]
+    to: Option<FormatArg>,
+    /// この手数を超える局面を捨てる
+    #[arg(long)]
+    max_ply: Option<i32>,
+    /// 出現回数がこれ未満の手を捨てる
+    #[arg(long, default_value_t = 0)]
+    min_count: u64,
+    /// 各局面で評価値が最も高い手だけを残す
+    #[arg(long)]
+    best_score: bool,
+    /// データパイプラインのマニフェスト（JSONL）。指定すると出力を入力のハッシュと共に追記する
+    #[arg(long)]
+    manifest: Option<PathBuf>,
+}
+
+impl WriteArgs {
+    fn policy(&self) -> BookPolicy {
+        BookPolicy {
+            max_ply: self.max_ply,
+            min_count: self.min_count,
+            best_score: self.best_score,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum FormatArg {
+    Yaneuraou,
+    Apery,
+    Native,
+}
+
+impl From<FormatArg> for BookFormat {
+    fn from(arg: FormatArg) -> Self {
+        match arg {
+            FormatArg::Yaneuraou => BookFormat::Yaneuraou,
+            FormatArg::Apery => BookFormat::Apery,
+            FormatArg::Native => BookFormat::Native,
+        }
+    }
+}
+
+fn main() -> Result<()> {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+    let cli = Cli::parse();
+    match cli.cmd {
+        Cmd::Convert {
+            input,
+            output,
+            read,
+            write,
+        } => {
+            let db = load(&input, &read)?;
+            save(db, &output, &write, &[input])
+        }
+        Cmd::Validate { input, read } => {
+            let (db, report) = read_book(
+                &input,
+                read.from.map(Into::into),
+                read.root_sfen.as_deref(),
+                read.walk_ply,
+            )?;
+            for issue in &report.issues {
+                println!("{issue}");
+            }
+            println!(
+                "positions={} moves={} issues={} unreachable={}",
+                db.len(),
+                db.num_moves(),
+                report.issues.len(),
+                report.unreachable
+            );
+            if !report.issues.is_empty() {
+                std::process::exit(1);
+            }
+            Ok(())
+        }
+        Cmd::Merge {
+            inputs,
+            output,
+            read,
+            write,
+        } => {
+            let mut merged = BookDb::default();
+            for input in &inputs {
+                merged.merge(load(input, &read)?);
+            }
+            save(merged, &output, &write, &inputs)
+        }
+    }
+}
+
+/// 定跡を読み込み、検証で見つかった問題を警告する
+fn load(path: &Path, read: &ReadArgs) -> Result<BookDb> {
+    let (db, report) =
+        read_book(path, read.from.map(Into::into), read.root_sfen.as_deref(), read.walk_ply)?;
+    for issue in &report.issues {
+        warn!("{}: {issue}", path.display());
+    }
+    if report.unreachable > 0 {
+        warn!(
+            "{}: {} entries are not reachable from the root position",
+            path.display(),
+            report.unreachable
+        );
+    }
+    info!(
+        "{}: {} positions, {} moves ({} skipped)",
+        path.display(),
+        db.len(),
+        db.num_moves(),
+        report.issues.len()
+    );
+    Ok(db)
+}
+
+/// 絞り込みを適用して書き出す（`--manifest` 指定時は `inputs` と共に記録する）
+fn save(mut db: BookDb, path: &Path, write: &WriteArgs, inputs: &[PathBuf]) -> Result<()> {
+    db.apply_policy(&write.policy());
+    if db.is_empty() {
+        bail!("no book moves left after filtering");
+    }
+    let format = write.to.map(Into::into).unwrap_or_else(|| BookFormat::from_extension(path));
+    write_book(path, format, &db)?;
+    info!(
+        "wrote {} positions, {} moves to {} ({format})",
+        db.len(),
+        db.num_moves(),
+        path.display()
+    );
+    if let Some(manifest) = &write.manifest {
+        record_artifact(manifest, ArtifactKind::Book, path, inputs)?;
+    }
+    Ok(())
+}
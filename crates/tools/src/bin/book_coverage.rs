@@ -0,0 +1,199 @@
+//! book_coverage - 定跡のカバー率を対局ログから分析し、定跡拡充の作業リストを作る
+//!
+//! やねうら王形式の定跡と、tournament / rshogi-csa-client の対局ログ（JSONL）を
+//! 突き合わせ、自エンジンが定跡を抜けた手数・相手の定跡外の手・定跡に追加すべき
+//! 局面を集計する。`--movetime` を指定すると上位の追加候補を探索して最善手と
+//! 評価値を付け、`--worklist` でやねうら王形式の定跡断片として書き出す。
+//! 集計の詳細は `tools::book_coverage` を参照。
+//!
+//! # 使用例
+//!
+//! ```bash
+//! cargo run --release -p tools --bin book_coverage -- \
+//!   --book user_book1.db --games "runs/floodgate/*.jsonl" --engine rshogi
+//!
+//! # 上位 50 局面を 2 秒ずつ評価して作業リストを出力
+//! cargo run --release -p tools --bin book_coverage -- \
+//!   --book user_book1.db --games "runs/floodgate/*.jsonl" --engine rshogi \
+//!   --movetime 2000 --top 50 --nnue-file nn.bin \
+//!   --output coverage.json --worklist book_additions.db
+//! ```
+
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result, bail};
+use clap::Parser;
+use log::info;
+
+use rshogi_core::eval::{MaterialLevel, set_material_level};
+use rshogi_core::nnue::init_nnue;
+use tools::book_coverage::{
+    Book, BookExit, CandidateSearchOptions, CoverageReport, analyze, evaluate_candidate,
+    load_games, write_worklist,
+};
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "book_coverage",
+    version,
+    about = "定跡のカバー率を対局ログから分析し、定跡に追加すべき局面を優先度順に列挙する"
+)]
+struct Cli {
+    /// やねうら王形式の定跡ファイル（`#YANEURAOU-DB2016 1.00`）
+    #[arg(long)]
+    book: PathBuf,
+
+    /// 対局ログ JSONL の glob パターン（複数指定可）
+    #[arg(long, required = true, num_args = 1..)]
+    games: Vec<String>,
+
+    /// 自エンジンのラベル（move 行の `engine` と完全一致）
+    #[arg(long)]
+    engine: String,
+
+    /// 表示・評価する追加候補と相手の変化の件数
+    #[arg(long, default_value_t = 20)]
+    top: usize,
+
+    /// 追加候補を評価する探索時間（ミリ秒）。未指定なら評価しない
+    #[arg(long)]
+    movetime: Option<u64>,
+
+    /// 探索スレッド数
+    #[arg(long, default_value_t = 1)]
+    threads: usize,
+
+    /// 置換表サイズ（MB）
+    #[arg(long, default_value_t = 256)]
+    tt_mb: usize,
+
+    /// NNUEファイルのパス（指定時はNNUE評価を使用）
+    #[arg(long)]
+    nnue_file: Option<PathBuf>,
+
+    /// NNUE を使わない場合の MaterialLevel
+    #[arg(long, default_value_t = 1)]
+    material_level: u8,
+
+    /// 集計結果 JSON の出力先
+    #[arg(long)]
+    output: Option<PathBuf>,
+
+    /// 上位の追加候補をやねうら王形式で書き出す先
+    #[arg(long)]
+    worklist: Option<PathBuf>,
+}
+
+fn main() -> Result<()> {
+    env_logger::init();
+    let cli = Cli::parse();
+
+    let book = Book::load(&cli.book)?;
+    info!("loaded {} book positions from {}", book.len(), cli.book.display());
+
+    let mut games = Vec::new();
+    for pattern in &cli.games {
+        let paths =
+            glob::glob(pattern).with_context(|| format!("invalid glob pattern: {pattern}"))?;
+        for path in paths {
+            let path = path?;
+            games.extend(load_games(&path)?);
+        }
+    }
+    if games.is_empty() {
+        bail!("no games found in {:?}", cli.games);
+    }
+
+    let mut report = analyze(&book, &games, &cli.engine);
+    if report.games == 0 {
+        bail!("engine '{}' did not play in any of {} games", cli.engine, games.len());
+    }
+    report.candidates.truncate(cli.top);
+
+    if let Some(movetime) = cli.movetime {
+        if let Some(nnue_path) = &cli.nnue_file {
+            init_nnue(nnue_path).map_err(|e| {
+                anyhow::anyhow!("Failed to initialize NNUE from '{}': {e}", nnue_path.display())
+            })?;
+        } else {
+            let level = MaterialLevel::from_value(cli.material_level)
+                .ok_or_else(|| anyhow::anyhow!("invalid MaterialLevel: {}", cli.material_level))?;
+            set_material_level(level);
+        }
+        let options = CandidateSearchOptions {
+            movetime_ms: movetime,
+            threads: cli.threads,
+            tt_mb: cli.tt_mb,
+        };
+        let total = report.candidates.len();
+        for (idx, candidate) in report.candidates.iter_mut().enumerate() {
+            evaluate_candidate(candidate, options)?;
+            info!("[{}/{}] evaluated {}", idx + 1, total, candidate.sfen);
+        }
+    }
+
+    print_summary(&report, cli.top);
+
+    if let Some(path) = &cli.output {
+        let file =
+            File::create(path).with_context(|| format!("Failed to create {}", path.display()))?;
+        serde_json::to_writer_pretty(BufWriter::new(file), &report)?;
+        println!("\nReport saved to: {}", path.display());
+    }
+    if let Some(path) = &cli.worklist {
+        write_worklist(path, &report.candidates)?;
+        println!("Worklist saved to: {}", path.display());
+    }
+
+    Ok(())
+}
+
+fn print_summary(report: &CoverageReport, top: usize) {
+    println!("=== Book Coverage ===");
+    println!("games: {} (skipped: {})", report.games, report.skipped_games);
+    for (exit, label) in [
+        (BookExit::NotInBook, "position not in book (our turn)"),
+        (BookExit::OurDeviation, "we played a non-book move"),
+        (BookExit::BookExhausted, "book exhausted (opponent's turn)"),
+        (BookExit::Never, "stayed in book"),
+    ] {
+        let count = report.exits.get(&exit).copied().unwrap_or(0);
+        println!(
+            "  {label:<34} {count:>6} ({:5.1}%)",
+            100.0 * f64::from(count) / f64::from(report.games)
+        );
+    }
+    if let (Some(mean), Some(median)) = (report.mean_exit_ply(), report.median_exit_ply()) {
+        println!("exit ply: mean {mean:.1}, median {median}");
+    }
+
+    println!("\n=== Opponent Novelties (top {top}) ===");
+    for novelty in report.novelties.iter().take(top) {
+        println!(
+            "  {:>4}x ply {:>3} {:<8} {}",
+            novelty.count, novelty.ply, novelty.move_usi, novelty.sfen
+        );
+    }
+
+    println!("\n=== Book Candidates (top {top}) ===");
+    for candidate in report.candidates.iter().take(top) {
+        let eval = match (&candidate.bestmove, candidate.score_cp) {
+            (Some(bestmove), Some(score)) => format!(" {bestmove} {score:+}"),
+            _ => String::new(),
+        };
+        println!(
+            "  {:>4}x ply {:>3}{}{} {}",
+            candidate.count,
+            candidate.ply,
+            if candidate.after_novelty {
+                " [novelty]"
+            } else {
+                ""
+            },
+            eval,
+            candidate.sfen
+        );
+    }
+}
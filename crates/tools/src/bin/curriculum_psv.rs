@@ -0,0 +1,226 @@
+//! curriculum_psv - カリキュラム学習用に教師データをステージ分けする
+//!
+//! PackedSfenValue 形式（40 バイト/レコード）の教師データを、局面の段階
+//! （`Position::phase`）または
+//! |評価値| の範囲でステージごとのファイルに振り分け、エポックごとに使うステージを
+//! `curriculum.json` に記録する。学習側は各エポックで対応するステージのファイルを読む。
+//! スケジュールの書式は `tools::curriculum` を参照。
+//!
+//! # 使用例
+//!
+//! ```bash
+//! # 序盤 → 中盤 → 終盤を 12 エポックで（4 エポックずつ）
+//! cargo run -p tools --release --bin curriculum_psv -- \
+//!   --input shuffled.bin --output-dir out/curriculum \
+//!   --curriculum "phase:open->mid->end" --epochs 12
+//!
+//! # |評価値| の小さい局面から段階的に広げる
+//! cargo run -p tools --release --bin curriculum_psv -- \
+//!   --input shuffled.bin --output-dir out/curriculum \
+//!   --curriculum "cp:0-300->0-1000->0-" --epochs 9
+//! ```
+
+use anyhow::{Context, Result, bail};
+use clap::Parser;
+use log::{info, warn};
+use serde::Serialize;
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, ErrorKind, Read, Write};
+use std::path::PathBuf;
+
+use tools::curriculum::{CurriculumSchedule, Stage, phase_of};
+use tools::packed_sfen::PackedSfenValue;
+
+const IO_BUF_SIZE: usize = 8 * 1024 * 1024;
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "curriculum_psv",
+    version,
+    about = "カリキュラム学習用に教師データをステージごとのファイルへ振り分ける"
+)]
+struct Cli {
+    /// 入力 PSV ファイル
+    #[arg(short, long)]
+    input: PathBuf,
+
+    /// 出力ディレクトリ（stage_<n>_<name>.bin と curriculum.json を書き出す）
+    #[arg(long)]
+    output_dir: PathBuf,
+
+    /// スケジュール（例: "phase:open->mid->end", "cp:0-300->0-1000->0-"）
+    #[arg(long)]
+    curriculum: String,
+
+    /// 学習のエポック数（ステージを均等に割り当てる）
+    #[arg(long)]
+    epochs: usize,
+}
+
+/// curriculum.json のステージ情報
+#[derive(Serialize)]
+struct StageManifest {
+    index: usize,
+    name: String,
+    #[serde(flatten)]
+    stage: Stage,
+    file: String,
+    records: u64,
+}
+
+/// curriculum.json のエポック情報
+#[derive(Serialize)]
+struct EpochManifest {
+    epoch: usize,
+    stage: usize,
+    file: String,
+}
+
+/// curriculum.json
+#[derive(Serialize)]
+struct CurriculumManifest {
+    input: String,
+    curriculum: String,
+    epochs: usize,
+    total_records: u64,
+    /// 局面を復元できず振り分けなかったレコード数
+    skipped_records: u64,
+    stages: Vec<StageManifest>,
+    schedule: Vec<EpochManifest>,
+}
+
+fn main() -> Result<()> {
+    env_logger::init();
+    let cli = Cli::parse();
+
+    let schedule = CurriculumSchedule::parse(&cli.curriculum)?;
+    if cli.epochs == 0 {
+        bail!("--epochs must be at least 1");
+    }
+    let uses_phase = schedule.uses_phase();
+
+    fs::create_dir_all(&cli.output_dir).with_context(|| {
+        format!("Failed to create output directory: {}", cli.output_dir.display())
+    })?;
+
+    let file_names: Vec<String> = schedule
+        .stages
+        .iter()
+        .enumerate()
+        .map(|(i, stage)| format!("stage_{i}_{}.bin", stage.name()))
+        .collect();
+    let mut writers = file_names
+        .iter()
+        .map(|name| {
+            let path = cli.output_dir.join(name);
+            File::create(&path)
+                .map(|f| BufWriter::with_capacity(IO_BUF_SIZE, f))
+                .with_context(|| format!("Failed to create {}", path.display()))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let mut counts = vec![0u64; schedule.stages.len()];
+
+    let input = File::open(&cli.input)
+        .with_context(|| format!("Failed to open input: {}", cli.input.display()))?;
+    let mut reader = BufReader::with_capacity(IO_BUF_SIZE, input);
+    let mut buf = [0u8; PackedSfenValue::SIZE];
+    let mut total_records = 0u64;
+    let mut skipped_records = 0u64;
+    loop {
+        match reader.read_exact(&mut buf) {
+            Ok(()) => {}
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e).context("Failed to read input"),
+        }
+        total_records += 1;
+        let record = PackedSfenValue::from_bytes(&buf).expect("buffer has record size");
+        let phase = if uses_phase {
+            match phase_of(&record) {
+                Ok(phase) => Some(phase),
+                Err(e) => {
+                    if skipped_records == 0 {
+                        warn!("record {total_records}: {e}");
+                    }
+                    skipped_records += 1;
+                    continue;
+                }
+            }
+        } else {
+            None
+        };
+        // ステージの範囲は重なってよい（cp:0-300->0-1000 等）ので、該当する全ステージに書く
+        for (i, stage) in schedule.stages.iter().enumerate() {
+            if stage.contains(&record, phase) {
+                writers[i].write_all(&buf)?;
+                counts[i] += 1;
+            }
+        }
+    }
+    for writer in &mut writers {
+        writer.flush()?;
+    }
+
+    let manifest = CurriculumManifest {
+        input: cli.input.display().to_string(),
+        curriculum: schedule.spec.clone(),
+        epochs: cli.epochs,
+        total_records,
+        skipped_records,
+        stages: schedule
+            .stages
+            .iter()
+            .enumerate()
+            .map(|(i, stage)| StageManifest {
+                index: i,
+                name: stage.name(),
+                stage: *stage,
+                file: file_names[i].clone(),
+                records: counts[i],
+            })
+            .collect(),
+        schedule: (0..cli.epochs)
+            .map(|epoch| {
+                let stage = schedule.stage_index(epoch, cli.epochs);
+                EpochManifest {
+                    epoch,
+                    stage,
+                    file: file_names[stage].clone(),
+                }
+            })
+            .collect(),
+    };
+
+    let manifest_path = cli.output_dir.join("curriculum.json");
+    let file = File::create(&manifest_path)
+        .with_context(|| format!("Failed to create {}", manifest_path.display()))?;
+    serde_json::to_writer_pretty(BufWriter::new(file), &manifest)?;
+
+    for stage in &manifest.stages {
+        info!(
+            "stage {} ({}): {} records -> {}",
+            stage.index, stage.name, stage.records, stage.file
+        );
+        if stage.records == 0 {
+            warn!("stage {} ({}) has no records", stage.index, stage.name);
+        }
+    }
+    if skipped_records > 0 {
+        warn!("{skipped_records} records skipped (invalid position)");
+    }
+    info!("{} records, manifest: {}", total_records, manifest_path.display());
+    // 構造化ログ（1 行 JSON）
+    println!(
+        "{}",
+        serde_json::json!({
+            "event": "curriculum",
+            "curriculum": manifest.curriculum,
+            "epochs": manifest.epochs,
+            "total_records": manifest.total_records,
+            "skipped_records": manifest.skipped_records,
+            "stage_records": counts,
+            "manifest": manifest_path.display().to_string(),
+        })
+    );
+
+    Ok(())
+}
@@ -0,0 +1,237 @@
+//! fit_scale - 評価値 → 勝率変換のシグモイド係数を教師データから推定する
+//!
+//! PackedSfenValue 形式（40 バイト/レコード）の自己対局データから、手番側の評価値
+//! （score）と対局結果（game_result）の組を集計し、
+//! `winrate = sigmoid((score - offset) / scale)` の `scale` / `offset` を最尤推定する。
+//! 推定値は他所から借りた係数（既定 600）との交差エントロピーの比較とともに表示し、
+//! `--output` で JSON、`--calibration-csv` で較正曲線描画用の CSV を書き出す。
+//!
+//! # 使用例
+//!
+//! ```bash
+//! cargo run --release -p tools --bin fit_scale -- \
+//!   --data selfplay.bin --output win_rate.json --calibration-csv calibration.csv
+//!
+//! # ディレクトリ指定、序盤の定跡局面を除外
+//! cargo run --release -p tools --bin fit_scale -- \
+//!   --input-dir data/ --pattern "*.bin" --min-ply 16
+//! ```
+
+use std::fs::File;
+use std::io::{BufReader, BufWriter, ErrorKind, Read, Write};
+use std::path::PathBuf;
+
+use anyhow::{Context, Result, bail};
+use clap::Parser;
+use log::{info, warn};
+use serde::Serialize;
+
+use tools::common::dedup::{PSV_SIZE, collect_input_paths};
+use tools::packed_sfen::PackedSfenValue;
+use tools::win_rate::{CalibrationBin, Outcomes, ScoreResultTable, WinRateModel};
+
+const IO_BUF_SIZE: usize = 8 * 1024 * 1024;
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "fit_scale",
+    version,
+    about = "評価値と対局結果から勝率変換のシグモイド係数（scale / offset）を推定する"
+)]
+struct Cli {
+    /// PSV ファイル（カンマ区切りで複数指定可）
+    #[arg(long, conflicts_with = "input_dir")]
+    data: Option<String>,
+
+    /// 入力ディレクトリ。--pattern と組み合わせて使用。--data と排他
+    #[arg(long, conflicts_with = "data")]
+    input_dir: Option<PathBuf>,
+
+    /// --input-dir 使用時の glob パターン
+    #[arg(long, default_value = "*.bin")]
+    pattern: String,
+
+    /// |評価値| がこれを超える局面を除外する（詰み評価値など）
+    #[arg(long, default_value_t = 3000)]
+    max_score: i32,
+
+    /// game_ply がこれ未満の局面を除外する
+    #[arg(long, default_value_t = 0)]
+    min_ply: u16,
+
+    /// 比較対象の scale（既存の係数）
+    #[arg(long, default_value_t = 600.0)]
+    reference_scale: f64,
+
+    /// 較正表の評価値の刻み幅
+    #[arg(long, default_value_t = 100)]
+    bin_width: i32,
+
+    /// 推定結果 JSON の出力先
+    #[arg(long)]
+    output: Option<PathBuf>,
+
+    /// 較正表 CSV の出力先（score_min,score_max,mean_score,count,wins,draws,losses,observed,predicted,reference）
+    #[arg(long)]
+    calibration_csv: Option<PathBuf>,
+}
+
+/// 推定結果 JSON
+#[derive(Serialize)]
+struct FitReport {
+    inputs: Vec<String>,
+    max_score: i32,
+    min_ply: u16,
+    positions: u64,
+    outcomes: Outcomes,
+    model: WinRateModel,
+    log_loss: f64,
+    reference: WinRateModel,
+    reference_log_loss: f64,
+    calibration: Vec<CalibrationBin>,
+}
+
+/// 集計から除外した局面の件数
+#[derive(Default)]
+struct SkipStats {
+    score: u64,
+    ply: u64,
+    game_result: u64,
+}
+
+fn main() -> Result<()> {
+    env_logger::init();
+    let cli = Cli::parse();
+
+    if cli.reference_scale <= 0.0 {
+        bail!("--reference-scale must be positive");
+    }
+    let inputs = collect_input_paths(cli.data.as_deref(), cli.input_dir.as_ref(), &cli.pattern)?;
+    if inputs.is_empty() {
+        bail!("no input files");
+    }
+
+    let mut table = ScoreResultTable::new();
+    let mut skipped = SkipStats::default();
+    for path in &inputs {
+        let file =
+            File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+        let mut reader = BufReader::with_capacity(IO_BUF_SIZE, file);
+        let mut buf = [0u8; PSV_SIZE];
+        loop {
+            match reader.read_exact(&mut buf) {
+                Ok(()) => {}
+                Err(e) if e.kind() == ErrorKind::UnexpectedEof => break,
+                Err(e) => {
+                    return Err(e).with_context(|| format!("Failed to read {}", path.display()));
+                }
+            }
+            let record = PackedSfenValue::from_bytes(&buf).expect("buffer has record size");
+            let score = i32::from(record.score);
+            if score.abs() > cli.max_score {
+                skipped.score += 1;
+            } else if record.game_ply < cli.min_ply {
+                skipped.ply += 1;
+            } else if !table.add(score, record.game_result) {
+                skipped.game_result += 1;
+            }
+        }
+        info!("loaded {}", path.display());
+    }
+    if skipped.game_result > 0 {
+        warn!("{} records have invalid game_result", skipped.game_result);
+    }
+    info!(
+        "{} positions (skipped: score={}, ply={}, game_result={})",
+        table.total(),
+        skipped.score,
+        skipped.ply,
+        skipped.game_result
+    );
+
+    let Some(model) = table.fit() else {
+        bail!("not enough positions to fit (need at least two distinct scores)");
+    };
+    let reference = WinRateModel {
+        scale: cli.reference_scale,
+        offset: 0.0,
+    };
+    let report = FitReport {
+        inputs: inputs.iter().map(|p| p.display().to_string()).collect(),
+        max_score: cli.max_score,
+        min_ply: cli.min_ply,
+        positions: table.total(),
+        outcomes: table.outcomes(),
+        model,
+        log_loss: table.log_loss(&model),
+        reference,
+        reference_log_loss: table.log_loss(&reference),
+        calibration: table.calibration(&model, cli.bin_width),
+    };
+
+    let outcomes = report.outcomes;
+    println!("=== Win-rate model fit ===");
+    println!(
+        "positions: {} (W {} / D {} / L {})",
+        report.positions, outcomes.wins, outcomes.draws, outcomes.losses
+    );
+    println!("scale:  {:.2}", model.scale);
+    println!("offset: {:.2}", model.offset);
+    println!(
+        "log loss: {:.6} (reference scale {}: {:.6})",
+        report.log_loss, cli.reference_scale, report.reference_log_loss
+    );
+    println!();
+    println!("{:>13} {:>10} {:>9} {:>9}", "score", "count", "observed", "predicted");
+    for bin in &report.calibration {
+        println!(
+            "{:>6}..{:<5} {:>10} {:>9.4} {:>9.4}",
+            bin.score_min,
+            bin.score_max,
+            bin.outcomes.total(),
+            bin.observed,
+            bin.predicted
+        );
+    }
+    println!();
+    println!("// fit_scale: {} positions", report.positions);
+    println!("pub const WIN_RATE_SCALE: f64 = {:.1};", model.scale);
+    println!("pub const WIN_RATE_OFFSET: f64 = {:.1};", model.offset);
+
+    if let Some(path) = &cli.output {
+        let file =
+            File::create(path).with_context(|| format!("Failed to create {}", path.display()))?;
+        serde_json::to_writer_pretty(BufWriter::new(file), &report)?;
+        println!("\nResults saved to: {}", path.display());
+    }
+
+    if let Some(path) = &cli.calibration_csv {
+        let file =
+            File::create(path).with_context(|| format!("Failed to create {}", path.display()))?;
+        let mut w = BufWriter::new(file);
+        writeln!(
+            w,
+            "score_min,score_max,mean_score,count,wins,draws,losses,observed,predicted,reference"
+        )?;
+        for bin in &report.calibration {
+            writeln!(
+                w,
+                "{},{},{:.2},{},{},{},{},{:.6},{:.6},{:.6}",
+                bin.score_min,
+                bin.score_max,
+                bin.mean_score,
+                bin.outcomes.total(),
+                bin.outcomes.wins,
+                bin.outcomes.draws,
+                bin.outcomes.losses,
+                bin.observed,
+                bin.predicted,
+                reference.win_rate(bin.mean_score)
+            )?;
+        }
+        w.flush()?;
+        println!("Calibration CSV saved to: {}", path.display());
+    }
+
+    Ok(())
+}
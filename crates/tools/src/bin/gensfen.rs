@@ -9,17 +9,20 @@ use anyhow::{Context, Result, anyhow, bail};
 use chrono::Local;
 use clap::Parser;
 use crossbeam_channel as chan;
-use rand::Rng;
+use rand::rngs::StdRng;
 use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rshogi_core::adjudication::{Adjudication, AdjudicationConfig, Adjudicator};
 use rshogi_core::movegen::{MoveList, generate_legal, is_legal_with_pass};
 use rshogi_core::position::Position;
 use rshogi_core::types::{Color, Move};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::sync::atomic::AtomicU64;
+use tools::manifest::{ArtifactKind, record_artifact};
 use tools::packed_sfen::{
-    PackedSfenValue, move_to_hcpe_move16, move_to_move16, move16_to_move, pack_position,
-    pack_position_hcp,
+    PackedSfenValue, move_to_hcpe_move16, move_to_move16, move16_to_move, move16_to_usi,
+    pack_position, pack_position_hcp, unpack_sfen,
 };
 use tools::selfplay::{
     EngineConfig, EngineProcess, EvalLog, GameEngines, GameOutcome, MultiPvCandidate,
@@ -70,6 +73,35 @@ struct Cli {
     #[arg(long, default_value_t = 512)]
     max_moves: u32,
 
+    /// 評価値による裁定を有効にする（両者の評価値が揃ったら勝ち / 引き分けで打ち切る）。
+    /// 以下の --adjudicate-* で条件を変える（未指定は rshogi_core::adjudication の既定値）。
+    #[arg(long)]
+    adjudicate: bool,
+
+    /// 裁定: 勝ちとみなす勝率 (0.5〜1.0)
+    #[arg(long)]
+    adjudicate_win_rate: Option<f64>,
+
+    /// 裁定: 勝ちの裁定に必要な連続手数（各自、0 で勝ちを裁定しない）
+    #[arg(long)]
+    adjudicate_win_moves: Option<u32>,
+
+    /// 裁定: 引き分けとみなす勝率の 50% からの幅
+    #[arg(long)]
+    adjudicate_draw_band: Option<f64>,
+
+    /// 裁定: 引き分けの裁定に必要な連続手数（各自、0 で引き分けを裁定しない）
+    #[arg(long)]
+    adjudicate_draw_moves: Option<u32>,
+
+    /// 裁定: 引き分けを裁定し始める手数
+    #[arg(long)]
+    adjudicate_draw_min_ply: Option<u32>,
+
+    /// 裁定: 評価値を勝率に換算するスケール (cp)
+    #[arg(long)]
+    adjudicate_eval_scale: Option<f64>,
+
     /// Initial time for Black in milliseconds
     #[arg(long, default_value_t = 0)]
     btime: u64,
@@ -233,7 +265,7 @@ struct Cli {
     )]
     skip_in_check: bool,
 
-    /// 学習データの出力形式（psv / pack / hcpe3）
+    /// 学習データの出力形式（psv / pack / hcpe3 / jsonl）
     #[arg(long, default_value = "psv")]
     training_data_format: String,
 
@@ -311,6 +343,21 @@ struct Cli {
     #[arg(long)]
     shuffle_seed: Option<u64>,
 
+    /// 乱数シード。対局ごとの乱数（ランダムムーブ・MultiPV ランダム選択・
+    /// `--random-startpos`）をこの値と通し番号から決め、`--shuffle-seed` 省略時はその既定値にもなる。
+    /// 省略時はランダム生成（meta 行に記録される）。
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// 複数プロセスで分担するときのシャード数。シャード i は通し番号
+    /// `k * shard_count + i`（k = 0..games）の対局を受け持つ（`--games` はシャードあたり）。
+    #[arg(long, default_value_t = 1)]
+    shard_count: u32,
+
+    /// このプロセスのシャード番号（0 始まり、`--shard-count` 未満）
+    #[arg(long, default_value_t = 0)]
+    shard_index: u32,
+
     /// dedup rate チェックの間隔（ゲーム数）。
     /// N ゲームごとに直近区間の重複率を計算し、閾値超過で警告を出力する。
     #[arg(long, default_value_t = 1000)]
@@ -320,6 +367,11 @@ struct Cli {
     /// 直近区間の重複率がこの値を超えると stderr に警告を出力する。
     #[arg(long, default_value_t = 0.1, value_parser = parse_rate_0_1)]
     dedup_warn_rate: f64,
+
+    /// データパイプラインのマニフェスト（JSONL）。指定すると学習データの出力を
+    /// コマンドライン・入力（`--eval-file` / `--startpos-file`）のハッシュと共に追記する
+    #[arg(long)]
+    manifest: Option<PathBuf>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -376,12 +428,26 @@ struct MetaSettings {
     /// 開始局面シャッフルの乱数シード（--startpos-no-repeat 用）
     #[serde(default, skip_serializing_if = "Option::is_none")]
     shuffle_seed: Option<u64>,
+    /// 対局ごとの乱数の元になるシード（--seed）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    seed: Option<u64>,
+    #[serde(default = "default_shard_count")]
+    shard_count: u32,
+    #[serde(default)]
+    shard_index: u32,
+    /// 評価値による裁定（--adjudicate）の有無
+    #[serde(default)]
+    adjudicate: bool,
 }
 
 fn default_skip_in_check() -> bool {
     true
 }
 
+fn default_shard_count() -> u32 {
+    1
+}
+
 #[derive(Serialize, Deserialize)]
 struct EngineCommandMeta {
     path_black: String,
@@ -496,6 +562,8 @@ enum TrainingFormat {
     Pack,
     /// 可変長対局棋譜 + 各手の MultiPV policy 分布を持つ hcpe3 形式
     Hcpe3,
+    /// 1 局面 1 行の JSONL（sfen / score / best_move / result / game_ply）
+    Jsonl,
 }
 
 /// hcpe3 形式でのみ使う追加データ（局面 replay 用の実着手と policy 分布）
@@ -524,6 +592,30 @@ struct TrainingEntry {
     hcpe3: Option<Hcpe3EntryData>,
 }
 
+/// `--training-data-format jsonl` の 1 行
+#[derive(Serialize)]
+struct TrainingSample {
+    sfen: String,
+    /// 探索スコア（手番側から見た評価値、詰みは ±10000）
+    score: i16,
+    /// 最善手（USI 形式）
+    best_move: String,
+    /// 手番側から見た勝敗（1 = 勝ち, 0 = 引き分け, -1 = 負け）
+    result: i8,
+    game_ply: u16,
+}
+
+/// 手番側から見た勝敗（1 = 勝ち, 0 = 引き分け, -1 = 負け）
+fn game_result_for(outcome: GameOutcome, side_to_move: Color) -> i8 {
+    match outcome {
+        GameOutcome::BlackWin if side_to_move == Color::Black => 1,
+        GameOutcome::WhiteWin if side_to_move == Color::White => 1,
+        GameOutcome::BlackWin | GameOutcome::WhiteWin => -1,
+        GameOutcome::Draw => 0,
+        GameOutcome::InProgress => unreachable!(),
+    }
+}
+
 /// 学習データ収集器
 /// 対局中の局面データを収集し、対局終了後に勝敗を設定して書き出す
 struct TrainingDataCollector {
@@ -714,6 +806,7 @@ impl TrainingDataCollector {
             TrainingFormat::Psv => self.finish_game_psv(outcome)?,
             TrainingFormat::Pack => self.finish_game_pack(outcome)?,
             TrainingFormat::Hcpe3 => self.finish_game_hcpe3(outcome)?,
+            TrainingFormat::Jsonl => self.finish_game_jsonl(outcome)?,
         }
 
         self.entries.clear();
@@ -723,27 +816,7 @@ impl TrainingDataCollector {
     /// PSV 形式で書き出す（PackedSfenValue 40バイト固定長）
     fn finish_game_psv(&mut self, outcome: GameOutcome) -> Result<()> {
         for (idx, entry) in self.entries.iter().enumerate() {
-            // game_result: 手番側から見た勝敗
-            // 1 = 勝ち, 0 = 引き分け, -1 = 負け
-            let game_result = match outcome {
-                GameOutcome::BlackWin => {
-                    if entry.side_to_move == Color::Black {
-                        1i8
-                    } else {
-                        -1i8
-                    }
-                }
-                GameOutcome::WhiteWin => {
-                    if entry.side_to_move == Color::White {
-                        1i8
-                    } else {
-                        -1i8
-                    }
-                }
-                GameOutcome::Draw => 0i8,
-                GameOutcome::InProgress => unreachable!(),
-            };
-
+            let game_result = game_result_for(outcome, entry.side_to_move);
             let psv = PackedSfenValue {
                 sfen: entry.sfen,
                 score: entry.score,
@@ -857,6 +930,27 @@ impl TrainingDataCollector {
         Ok(())
     }
 
+    /// JSONL 形式で書き出す（1 局面 1 行、評価値と勝敗は手番側視点）
+    fn finish_game_jsonl(&mut self, outcome: GameOutcome) -> Result<()> {
+        for (idx, entry) in self.entries.iter().enumerate() {
+            let sfen = unpack_sfen(&entry.sfen)
+                .map_err(|e| anyhow!("failed to unpack position {idx} of game: {e}"))?;
+            let sample = TrainingSample {
+                sfen,
+                score: entry.score,
+                best_move: move16_to_usi(entry.move16),
+                result: game_result_for(outcome, entry.side_to_move),
+                game_ply: entry.game_ply,
+            };
+            serde_json::to_writer(&mut self.writer, &sample)?;
+            self.writer
+                .write_all(b"\n")
+                .with_context(|| format!("failed to write position {idx} of game"))?;
+            self.total_written += 1;
+        }
+        Ok(())
+    }
+
     fn flush(&mut self) -> Result<()> {
         self.writer.flush()?;
         Ok(())
@@ -1141,10 +1235,29 @@ impl InfoLogger {
 struct GameTicket {
     game_idx: u32,
     startpos_idx: usize,
+    /// この対局の乱数シード（ランダムムーブ・MultiPV ランダム選択）
+    seed: u64,
+}
+
+/// シャードを通した対局の通し番号（シャード i の k 局目）
+fn global_game_index(game_idx: u32, shard_index: u32, shard_count: u32) -> u64 {
+    game_idx as u64 * shard_count as u64 + shard_index as u64
+}
+
+/// `--seed` と通し番号から対局ごとの乱数シードを作る（splitmix64）。
+/// 通し番号だけで決まるので、ワーカー数や対局の終わる順序に依存しない。
+fn game_seed(seed: u64, global_idx: u64) -> u64 {
+    let mut z = seed ^ global_idx.wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    z = z.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
 }
 
 fn make_game_ticket<R: Rng + ?Sized>(
     game_idx: u32,
+    global_idx: u64,
+    seed: u64,
     random_startpos: bool,
     startpos_count: usize,
     rng: &mut R,
@@ -1152,11 +1265,12 @@ fn make_game_ticket<R: Rng + ?Sized>(
     let startpos_idx = if random_startpos {
         rng.random_range(0..startpos_count)
     } else {
-        (game_idx as usize) % startpos_count
+        (global_idx % startpos_count as u64) as usize
     };
     GameTicket {
         game_idx,
         startpos_idx,
+        seed,
     }
 }
 
@@ -1235,6 +1349,8 @@ struct WorkerConfig {
     /// 直近 interval で既に警告済みかを示すフラグ（全ワーカー共有）。
     /// 同一タイミングで複数ワーカーが重複警告を出すのを抑制する。
     dedup_warn_emitted: Arc<AtomicBool>,
+    /// 評価値による裁定の条件（None の場合は裁定しない）
+    adjudication: Option<AdjudicationConfig>,
 }
 
 fn worker_main(
@@ -1329,7 +1445,7 @@ fn worker_main(
         };
 
         let dedup_hash = cfg.dedup_hash.clone();
-        let mut rng = rand::rng();
+        let mut adjudicator = cfg.adjudication.map(Adjudicator::new);
         let mut dedup_hits = 0u64;
         let mut dedup_discarded = 0u64;
         let mut multipv_diversions = 0u64;
@@ -1347,6 +1463,10 @@ fn worker_main(
 
             let game_idx = ticket.game_idx;
             engines.prepare_game(cfg.keep_tt)?;
+            let mut rng = StdRng::seed_from_u64(ticket.seed);
+            if let Some(adjudicator) = adjudicator.as_mut() {
+                adjudicator.clear();
+            }
 
             let parsed = &cfg.start_defs[ticket.startpos_idx];
             let mut pos = build_position(parsed, None, None)?;
@@ -1403,6 +1523,9 @@ fn worker_main(
                         collector.start_game();
                     }
                     random_moves_played += 1;
+                    if let Some(adjudicator) = adjudicator.as_mut() {
+                        adjudicator.record(side, None);
+                    }
                     let gives_check = if mv.is_pass() {
                         false
                     } else {
@@ -1563,6 +1686,25 @@ fn worker_main(
                                     pos.do_move(played_mv, gives_check);
                                     tc.update_after_move(side, search.elapsed_ms);
                                     move_usi = played_mv.to_usi();
+                                    if let Some(adjudicator) = adjudicator.as_mut() {
+                                        let score =
+                                            eval_log.as_ref().and_then(EvalLog::reported_score);
+                                        adjudicator.record(side, score);
+                                        if let Some(adjudication) = adjudicator.decide(plies_played)
+                                        {
+                                            outcome = match adjudication {
+                                                Adjudication::Win(Color::Black) => {
+                                                    GameOutcome::BlackWin
+                                                }
+                                                Adjudication::Win(Color::White) => {
+                                                    GameOutcome::WhiteWin
+                                                }
+                                                Adjudication::Draw => GameOutcome::Draw,
+                                            };
+                                            outcome_reason = "adjudication";
+                                            terminal = true;
+                                        }
+                                    }
                                 }
                                 None => {
                                     outcome = if side == Color::Black {
@@ -1764,6 +1906,8 @@ struct ResumeState {
     draws: u32,
     /// meta 行に保存された shuffle_seed（存在しない場合は None）
     shuffle_seed: Option<u64>,
+    /// meta 行に保存された seed（存在しない場合は None）
+    seed: Option<u64>,
 }
 
 /// 既存の JSONL 出力ファイルを解析し、完了済み対局数と勝敗を取得する。
@@ -1779,6 +1923,7 @@ fn parse_resume_state(path: &Path) -> Result<ResumeState> {
     let mut white_wins: u32 = 0;
     let mut draws: u32 = 0;
     let mut shuffle_seed: Option<u64> = None;
+    let mut seed: Option<u64> = None;
     let mut last_parse_error = false;
 
     for line in reader.lines() {
@@ -1805,6 +1950,7 @@ fn parse_resume_state(path: &Path) -> Result<ResumeState> {
                     .get("settings")
                     .and_then(|s| s.get("shuffle_seed"))
                     .and_then(|v| v.as_u64());
+                seed = value.get("settings").and_then(|s| s.get("seed")).and_then(|v| v.as_u64());
             }
             Some("result") => {
                 if let Some(gid) = value.get("game_id").and_then(|v| v.as_u64()) {
@@ -1830,6 +1976,7 @@ fn parse_resume_state(path: &Path) -> Result<ResumeState> {
         white_wins,
         draws,
         shuffle_seed,
+        seed,
     })
 }
 
@@ -1860,9 +2007,46 @@ fn concatenate_temp_files(final_path: &Path, temp_paths: &[PathBuf], append: boo
     Ok(())
 }
 
+impl Cli {
+    /// `--adjudicate` 指定時の裁定条件（未指定の項目は既定値）
+    fn adjudication_config(&self) -> Result<Option<AdjudicationConfig>> {
+        if !self.adjudicate {
+            return Ok(None);
+        }
+        let default = AdjudicationConfig::default();
+        let config = AdjudicationConfig {
+            eval_scale: self.adjudicate_eval_scale.unwrap_or(default.eval_scale),
+            win_rate: self.adjudicate_win_rate.unwrap_or(default.win_rate),
+            win_moves: self.adjudicate_win_moves.unwrap_or(default.win_moves),
+            draw_band: self.adjudicate_draw_band.unwrap_or(default.draw_band),
+            draw_moves: self.adjudicate_draw_moves.unwrap_or(default.draw_moves),
+            draw_min_ply: self.adjudicate_draw_min_ply.unwrap_or(default.draw_min_ply),
+        };
+        if !(0.5..=1.0).contains(&config.win_rate) {
+            bail!("--adjudicate-win-rate must be in 0.5..=1.0");
+        }
+        if !(0.0..0.5).contains(&config.draw_band) {
+            bail!("--adjudicate-draw-band must be in 0.0..0.5");
+        }
+        if config.eval_scale <= 0.0 {
+            bail!("--adjudicate-eval-scale must be positive");
+        }
+        Ok(Some(config))
+    }
+}
+
 fn main() -> Result<()> {
     let mut cli = Cli::parse();
 
+    if cli.shard_count == 0 || cli.shard_index >= cli.shard_count {
+        bail!(
+            "--shard-index {} must be less than --shard-count {}",
+            cli.shard_index,
+            cli.shard_count
+        );
+    }
+    let adjudication = cli.adjudication_config()?;
+
     // 時間制限のバリデーション: depth/nodes 指定がなく時間制御もない場合はデフォルト byoyomi を設定
     let has_limit = cli.depth.is_some() || cli.nodes.is_some();
     if !has_limit
@@ -1957,8 +2141,12 @@ fn main() -> Result<()> {
         "psv" => TrainingFormat::Psv,
         "pack" => TrainingFormat::Pack,
         "hcpe3" => TrainingFormat::Hcpe3,
+        "jsonl" => TrainingFormat::Jsonl,
         other => {
-            bail!("unknown training data format: '{}' (expected 'psv', 'pack', or 'hcpe3')", other)
+            bail!(
+                "unknown training data format: '{}' (expected 'psv', 'pack', 'hcpe3', or 'jsonl')",
+                other
+            )
         }
     };
 
@@ -1974,6 +2162,8 @@ fn main() -> Result<()> {
         TrainingFormat::Psv => "psv",
         TrainingFormat::Pack => "pack",
         TrainingFormat::Hcpe3 => "hcpe3",
+        // 対局ログ（<stem>.jsonl）と区別する
+        TrainingFormat::Jsonl => "samples.jsonl",
     };
     let training_data_path = Some(
         cli.output_training_data
@@ -2081,8 +2271,11 @@ fn main() -> Result<()> {
                 );
             }
             meta_seed
-        } else if let Some(seed) = cli.shuffle_seed {
+        } else if let Some(seed) = cli.shuffle_seed.or(cli.seed) {
             Some(seed)
+        } else if cli.shard_count > 1 {
+            // シャード間で開始局面の順列を共有しないと同じ局面を取り合う
+            bail!("--shard-count > 1 with --startpos-no-repeat requires --seed or --shuffle-seed");
         } else {
             Some(rand::random::<u64>())
         }
@@ -2090,6 +2283,22 @@ fn main() -> Result<()> {
         None
     };
 
+    // seed の解決: CLI 指定 > meta から復元 > ランダム生成
+    let seed_resolved = match resume_state.as_ref().and_then(|s| s.seed) {
+        Some(meta_seed) => {
+            if let Some(cli_seed) = cli.seed
+                && cli_seed != meta_seed
+            {
+                bail!("--seed {cli_seed} does not match meta seed {meta_seed}");
+            }
+            meta_seed
+        }
+        None => cli.seed.unwrap_or_else(rand::random::<u64>),
+    };
+    if cli.shard_count > 1 {
+        println!("shard: {}/{} (seed={})", cli.shard_index, cli.shard_count, seed_resolved);
+    }
+
     // Write meta line to final JSONL (resume時はスキップ: 既にメタ行が存在する)
     if !cli.resume {
         let mut writer = BufWriter::new(
@@ -2129,6 +2338,10 @@ fn main() -> Result<()> {
                 skip_initial_ply: cli.skip_initial_ply,
                 skip_in_check: cli.skip_in_check,
                 shuffle_seed: shuffle_seed_resolved,
+                seed: Some(seed_resolved),
+                shard_count: cli.shard_count,
+                shard_index: cli.shard_index,
+                adjudicate: adjudication.is_some(),
             },
             engine_cmd: EngineCommandMeta {
                 path_black: engine_paths.black.path.display().to_string(),
@@ -2178,7 +2391,6 @@ fn main() -> Result<()> {
 
     // ゲームチケットは逐次生成する。
     // `--games` が極端に大きい場合でも O(1) メモリで dispatch できるようにする。
-    let mut rng = rand::rng();
     let startpos_count = start_defs.len();
 
     // Compute temp file paths per worker
@@ -2306,6 +2518,7 @@ fn main() -> Result<()> {
                 .max(1),
             dedup_warn_rate: cli.dedup_warn_rate,
             dedup_warn_emitted: Arc::clone(&dedup_warn_emitted),
+            adjudication,
         };
 
         let rx = ticket_rx.clone();
@@ -2343,7 +2556,8 @@ fn main() -> Result<()> {
         };
         let mut s = ShuffledStartpos::new(startpos_count, seed);
         // resume 時は完了済み対局分だけ消費して同一位置まで進める
-        for _ in 0..resume_offset {
+        // （シャード分担時は 1 局ごとに全シャード分を消費する）
+        for _ in 0..resume_offset as u64 * cli.shard_count as u64 {
             s.next();
         }
         if resume_offset > 0 {
@@ -2357,20 +2571,39 @@ fn main() -> Result<()> {
         None
     };
     let mut next_game_idx = resume_offset;
-    let make_ticket = |game_idx: u32,
-                       rng: &mut rand::rngs::ThreadRng,
-                       shuffled: &mut Option<ShuffledStartpos>| {
+    // 乱数は通し番号から決めるので、同じ --seed なら resume やワーカー数に関係なく同じになる
+    let make_ticket = |game_idx: u32, shuffled: &mut Option<ShuffledStartpos>| {
+        let global_idx = global_game_index(game_idx, cli.shard_index, cli.shard_count);
+        let seed = game_seed(seed_resolved, global_idx);
         if let Some(s) = shuffled.as_mut() {
+            // 全シャードが同じ順列を 1 局ごとに shard_count 個ずつ進め、自分の番号の分を取る
+            let mut startpos_idx = s.next();
+            for i in 1..cli.shard_count {
+                let idx = s.next();
+                if i == cli.shard_index {
+                    startpos_idx = idx;
+                }
+            }
             GameTicket {
                 game_idx,
-                startpos_idx: s.next(),
+                startpos_idx,
+                seed,
             }
         } else {
-            make_game_ticket(game_idx, cli.random_startpos, startpos_count, rng)
+            // 開始局面の選択はワーカー側の乱数と別系列にする
+            let mut rng = StdRng::seed_from_u64(game_seed(!seed_resolved, global_idx));
+            make_game_ticket(
+                game_idx,
+                global_idx,
+                seed,
+                cli.random_startpos,
+                startpos_count,
+                &mut rng,
+            )
         }
     };
-    let mut next_ticket = (next_game_idx < cli.games)
-        .then(|| make_ticket(next_game_idx, &mut rng, &mut shuffled_startpos));
+    let mut next_ticket =
+        (next_game_idx < cli.games).then(|| make_ticket(next_game_idx, &mut shuffled_startpos));
     let mut completed = resume_offset;
     let mut black_wins = resume_state.as_ref().map_or(0, |s| s.black_wins);
     let mut white_wins = resume_state.as_ref().map_or(0, |s| s.white_wins);
@@ -2423,7 +2656,7 @@ fn main() -> Result<()> {
                         if res.is_ok() {
                             next_game_idx += 1;
                             next_ticket = (next_game_idx < cli.games).then(|| {
-                                make_ticket(next_game_idx, &mut rng, &mut shuffled_startpos)
+                                make_ticket(next_game_idx, &mut shuffled_startpos)
                             });
                         }
                     }
@@ -2489,6 +2722,11 @@ fn main() -> Result<()> {
             .cloned()
             .unwrap_or_else(|| default_training_data_path(&output_path, training_data_ext));
         concatenate_temp_files(&pack_path, &temp_pack_paths, append_mode)?;
+        if let Some(manifest) = &cli.manifest {
+            let inputs: Vec<PathBuf> =
+                cli.eval_file.iter().chain(&cli.startpos_file).cloned().collect();
+            record_artifact(manifest, ArtifactKind::DatasetShard, &pack_path, &inputs)?;
+        }
     }
 
     // 最終サマリー
@@ -2707,16 +2945,41 @@ mod tests {
     fn make_game_ticket_cycles_startpos_indices_when_not_random() {
         let mut rng = StdRng::seed_from_u64(1);
         let tickets: Vec<_> = (0..6)
-            .map(|game_idx| make_game_ticket(game_idx, false, 4, &mut rng).startpos_idx)
+            .map(|game_idx| {
+                make_game_ticket(game_idx, game_idx as u64, 0, false, 4, &mut rng).startpos_idx
+            })
             .collect();
         assert_eq!(tickets, vec![0, 1, 2, 3, 0, 1]);
     }
 
+    #[test]
+    fn sharded_tickets_partition_game_indices() {
+        // 3 シャードの通し番号は重ならず、全体で 0..12 を覆う
+        let mut all: Vec<u64> = (0..3)
+            .flat_map(|shard| (0..4).map(move |k| global_game_index(k, shard, 3)))
+            .collect();
+        all.sort_unstable();
+        assert_eq!(all, (0..12).collect::<Vec<_>>());
+
+        // 開始局面の巡回も通し番号で決まる
+        let mut rng = StdRng::seed_from_u64(1);
+        let ticket = make_game_ticket(1, global_game_index(1, 2, 3), 0, false, 4, &mut rng);
+        assert_eq!(ticket.startpos_idx, 5 % 4);
+    }
+
+    #[test]
+    fn game_seed_is_deterministic_and_distinct() {
+        assert_eq!(game_seed(42, 7), game_seed(42, 7));
+        let seeds: std::collections::HashSet<u64> = (0..1000).map(|i| game_seed(42, i)).collect();
+        assert_eq!(seeds.len(), 1000);
+        assert_ne!(game_seed(42, 0), game_seed(43, 0));
+    }
+
     #[test]
     fn make_game_ticket_random_startpos_stays_in_range() {
         let mut rng = StdRng::seed_from_u64(1);
         for game_idx in 0..128 {
-            let ticket = make_game_ticket(game_idx, true, 5, &mut rng);
+            let ticket = make_game_ticket(game_idx, game_idx as u64, 0, true, 5, &mut rng);
             assert!(ticket.startpos_idx < 5);
         }
     }
@@ -2958,6 +3221,48 @@ mod tests {
         assert!(validate_hcpe3_opts(TrainingFormat::Pack, true, 0, 0.0).is_ok());
     }
 
+    #[test]
+    fn finish_game_jsonl_writes_side_to_move_results() {
+        use rshogi_core::position::Position;
+        use rshogi_core::types::Move;
+
+        let path = std::env::temp_dir()
+            .join(format!("gensfen_jsonl_samples_{}.samples.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let mut pos = Position::new();
+        pos.set_hirate();
+        let mv = Move::from_usi("7g7f").unwrap();
+        {
+            let mut col =
+                TrainingDataCollector::new(&path, 0, false, TrainingFormat::Jsonl, 1000, 600.0)
+                    .unwrap();
+            col.start_game();
+            col.record_position(&pos, Some(40), None, Some(mv), mv, &[]);
+            pos.do_move(mv, false);
+            col.record_position(&pos, None, Some(-3), None, Move::NONE, &[]);
+            col.finish_game(GameOutcome::WhiteWin).unwrap();
+            col.flush().unwrap();
+        }
+
+        let text = std::fs::read_to_string(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        let lines: Vec<Value> =
+            text.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(
+            lines[0]["sfen"],
+            "lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL b - 1"
+        );
+        assert_eq!(lines[0]["score"], 40);
+        assert_eq!(lines[0]["best_move"], "7g7f");
+        assert_eq!(lines[0]["result"], -1);
+        assert_eq!(lines[1]["score"], -10000);
+        assert_eq!(lines[1]["best_move"], "none");
+        assert_eq!(lines[1]["result"], 1);
+        assert_eq!(lines[1]["game_ply"], 2);
+    }
+
     #[test]
     fn finish_game_hcpe3_byte_layout() {
         use rshogi_core::position::Position;
@@ -5,6 +5,10 @@
 //! `score` はログの `eval.score_cp` / `eval.score_mate` から暫定値を入れるが、
 //! 後段で `rescore_psv` する前提なら `--missing-score zero` で欠損スコアも
 //! 0 として残せる。
+//!
+//! 対局ごとに `move` 行を開始局面から指し直して検証し（[`tools::game_check::check_game`]）、
+//! 非合法手・局面の不連続・ルール違反の局面・千日手や終局理由と食い違う結果を含む対局は
+//! 診断を標準エラーに出して書き出さない。`--skip-game-check` で検証を省略できる。
 
 use std::collections::HashMap;
 use std::fs::File;
@@ -19,7 +23,10 @@ use rshogi_core::types::{Color, Move};
 use serde::Deserialize;
 use serde_json::Value;
 use tools::common::dedup::collect_input_paths;
+use tools::game_check::{LoggedMove, check_game, is_terminal_move};
+use tools::manifest::{ArtifactKind, record_artifact};
 use tools::packed_sfen::{PackedSfenValue, move_to_move16, pack_position};
+use tools::selfplay::GameOutcome;
 
 #[derive(Parser, Debug)]
 #[command(
@@ -50,6 +57,14 @@ struct Args {
     /// 変換する最大対局数（0 = 全件）
     #[arg(long, default_value_t = 0)]
     max_games: u64,
+
+    /// データパイプラインのマニフェスト（JSONL）。指定すると出力を入力のハッシュと共に追記する
+    #[arg(long)]
+    manifest: Option<PathBuf>,
+
+    /// 対局単位の検証（非合法手・千日手の誤判定など）を行わずにすべて書き出す
+    #[arg(long)]
+    skip_game_check: bool,
 }
 
 #[derive(Debug, Clone, Copy, ValueEnum)]
@@ -92,6 +107,8 @@ struct ResultLog {
     outcome: Outcome,
     #[serde(default)]
     error: bool,
+    #[serde(default)]
+    plies: Option<u32>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
@@ -103,6 +120,17 @@ enum Outcome {
     InProgress,
 }
 
+impl Outcome {
+    fn to_game_outcome(self) -> GameOutcome {
+        match self {
+            Outcome::BlackWin => GameOutcome::BlackWin,
+            Outcome::WhiteWin => GameOutcome::WhiteWin,
+            Outcome::Draw => GameOutcome::Draw,
+            Outcome::InProgress => GameOutcome::InProgress,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 struct PendingRecord {
     packed_sfen: [u8; 32],
@@ -122,6 +150,7 @@ struct Stats {
     skipped_terminal_move: u64,
     skipped_error_game: u64,
     skipped_in_progress_game: u64,
+    skipped_invalid_game: u64,
     parse_errors: u64,
     move_errors: u64,
     orphan_games: u64,
@@ -151,16 +180,17 @@ fn main() -> Result<()> {
     let mut total = Stats::default();
     let mut games_written = 0u64;
 
-    for path in paths {
+    for path in &paths {
         if args.max_games > 0 && games_written >= args.max_games {
             break;
         }
         eprintln!("Reading: {}", path.display());
         let stats = process_file(
-            &path,
+            path,
             &mut writer,
             args.missing_score,
             args.max_games,
+            !args.skip_game_check,
             &mut games_written,
         )
         .with_context(|| format!("変換に失敗しました: {}", path.display()))?;
@@ -168,6 +198,10 @@ fn main() -> Result<()> {
     }
 
     writer.flush()?;
+    drop(writer);
+    if let Some(manifest) = &args.manifest {
+        record_artifact(manifest, ArtifactKind::DatasetShard, &args.output, &paths)?;
+    }
 
     println!("=== JSONL → PSV Summary ===");
     println!("Files:                 {}", total.files);
@@ -178,6 +212,7 @@ fn main() -> Result<()> {
     println!("Skipped terminal move: {}", total.skipped_terminal_move);
     println!("Skipped error games:   {}", total.skipped_error_game);
     println!("Skipped in-progress:   {}", total.skipped_in_progress_game);
+    println!("Skipped invalid games: {}", total.skipped_invalid_game);
     println!("Move errors:           {}", total.move_errors);
     println!("Parse errors:          {}", total.parse_errors);
     println!("Orphan games:          {}", total.orphan_games);
@@ -200,6 +235,7 @@ impl Stats {
         self.skipped_terminal_move += rhs.skipped_terminal_move;
         self.skipped_error_game += rhs.skipped_error_game;
         self.skipped_in_progress_game += rhs.skipped_in_progress_game;
+        self.skipped_invalid_game += rhs.skipped_invalid_game;
         self.parse_errors += rhs.parse_errors;
         self.move_errors += rhs.move_errors;
         self.orphan_games += rhs.orphan_games;
@@ -211,12 +247,14 @@ fn process_file(
     writer: &mut BufWriter<File>,
     missing_score: MissingScoreMode,
     max_games: u64,
+    game_check: bool,
     games_written: &mut u64,
 ) -> Result<Stats> {
     let file = File::open(path)
         .with_context(|| format!("入力ファイルを開けません: {}", path.display()))?;
     let reader = BufReader::new(file);
     let mut pending: HashMap<u32, Vec<PendingRecord>> = HashMap::new();
+    let mut logged: HashMap<u32, Vec<LoggedMove>> = HashMap::new();
     let mut stats = Stats {
         files: 1,
         ..Stats::default()
@@ -246,29 +284,38 @@ fn process_file(
         };
 
         match entry {
-            LogEntry::Move(mv) => match convert_move(&mv, missing_score) {
-                Ok(Some(record)) => pending.entry(mv.game_id).or_default().push(record),
-                Ok(None) => {
-                    if is_terminal_move(&mv.move_usi) {
-                        stats.skipped_terminal_move += 1;
-                    } else {
-                        stats.skipped_missing_score += 1;
+            LogEntry::Move(mv) => {
+                match convert_move(&mv, missing_score) {
+                    Ok(Some(record)) => pending.entry(mv.game_id).or_default().push(record),
+                    Ok(None) => {
+                        if is_terminal_move(&mv.move_usi) {
+                            stats.skipped_terminal_move += 1;
+                        } else {
+                            stats.skipped_missing_score += 1;
+                        }
+                    }
+                    Err(e) => {
+                        stats.move_errors += 1;
+                        eprintln!(
+                            "  move変換エラー {}:{} game_id={} ply={}: {e}",
+                            path.display(),
+                            line_idx + 1,
+                            mv.game_id,
+                            mv.ply
+                        );
                     }
                 }
-                Err(e) => {
-                    stats.move_errors += 1;
-                    eprintln!(
-                        "  move変換エラー {}:{} game_id={} ply={}: {e}",
-                        path.display(),
-                        line_idx + 1,
-                        mv.game_id,
-                        mv.ply
-                    );
+                if game_check {
+                    logged.entry(mv.game_id).or_default().push(LoggedMove {
+                        sfen_before: mv.sfen_before,
+                        move_usi: mv.move_usi,
+                    });
                 }
-            },
+            }
             LogEntry::Result(result) => {
                 stats.games_seen += 1;
                 let records = pending.remove(&result.game_id).unwrap_or_default();
+                let moves = logged.remove(&result.game_id).unwrap_or_default();
                 if result.error {
                     stats.skipped_error_game += 1;
                     continue;
@@ -277,6 +324,21 @@ fn process_file(
                     stats.skipped_in_progress_game += 1;
                     continue;
                 }
+                if game_check {
+                    let diagnostics =
+                        check_game(&moves, result.outcome.to_game_outcome(), result.plies);
+                    if !diagnostics.is_empty() {
+                        stats.skipped_invalid_game += 1;
+                        for diagnostic in &diagnostics {
+                            eprintln!(
+                                "  対局検証エラー {} game_id={} {diagnostic}",
+                                path.display(),
+                                result.game_id
+                            );
+                        }
+                        continue;
+                    }
+                }
                 if records.is_empty() {
                     continue;
                 }
@@ -318,7 +380,7 @@ fn convert_move(mv: &MoveLog, missing_score: MissingScoreMode) -> Result<Option<
         );
     }
 
-    let best_move = Move::from_usi(&mv.move_usi)
+    let best_move = Move::parse_usi(&mv.move_usi)
         .with_context(|| format!("USI指し手パース失敗: {}", mv.move_usi))?;
     if best_move == Move::PASS {
         bail!("PASS手はPackedSfenValueにエンコードできません");
@@ -402,10 +464,6 @@ fn color_label(color: Color) -> char {
     if color == Color::Black { 'b' } else { 'w' }
 }
 
-fn is_terminal_move(move_usi: &str) -> bool {
-    matches!(move_usi, "resign" | "win" | "timeout" | "illegal" | "none")
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -461,7 +519,7 @@ mod tests {
         let mut writer = BufWriter::new(File::create(&output).expect("create output"));
         let mut games_written = 0;
         let stats =
-            process_file(&input, &mut writer, MissingScoreMode::Skip, 0, &mut games_written)
+            process_file(&input, &mut writer, MissingScoreMode::Skip, 0, true, &mut games_written)
                 .expect("process file");
         writer.flush().expect("flush");
 
@@ -485,4 +543,40 @@ mod tests {
         assert_eq!(second.game_ply, 2);
         assert_eq!(second.game_result, -1);
     }
+    #[test]
+    fn skips_games_that_fail_game_check() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let input = dir.path().join("game.jsonl");
+        let output = dir.path().join("out.psv");
+        // 2 手目の sfen_before が 1 手目を指した局面と一致しない（7g7f ではなく 2g2f の後）
+        std::fs::write(
+            &input,
+            concat!(
+                "{\"type\":\"move\",\"game_id\":1,\"ply\":1,\"side_to_move\":\"b\",",
+                "\"sfen_before\":\"lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL b - 1\",",
+                "\"move_usi\":\"7g7f\",\"eval\":{\"score_cp\":23}}\n",
+                "{\"type\":\"move\",\"game_id\":1,\"ply\":2,\"side_to_move\":\"w\",",
+                "\"sfen_before\":\"lnsgkgsnl/1r5b1/ppppppppp/9/9/7P1/PPPPPPP1P/1B5R1/LNSGKGSNL w - 2\",",
+                "\"move_usi\":\"3c3d\",\"eval\":{\"score_cp\":-20}}\n",
+                "{\"type\":\"result\",\"game_id\":1,\"outcome\":\"black_win\",\"plies\":2}\n",
+            ),
+        )
+        .expect("write input");
+
+        for (game_check, expected_written) in [(true, 0), (false, 1)] {
+            let mut writer = BufWriter::new(File::create(&output).expect("create output"));
+            let mut games_written = 0;
+            let stats = process_file(
+                &input,
+                &mut writer,
+                MissingScoreMode::Skip,
+                0,
+                game_check,
+                &mut games_written,
+            )
+            .expect("process file");
+            assert_eq!(stats.games_written, expected_written);
+            assert_eq!(stats.skipped_invalid_game, 1 - expected_written);
+        }
+    }
 }
@@ -0,0 +1,117 @@
+//! データパイプラインのマニフェストへの記録と来歴の表示
+//!
+//! `--manifest` に対応していないツール（外部の trainer など）の出力を記録し、
+//! 成果物から入力を辿って「このネットはどのデータで学習したか」を表示する。
+//! 形式は `tools::manifest` を参照。
+//!
+//! ```bash
+//! # 外部 trainer で学習したネットを記録する（`--` 以降が記録するコマンドライン）
+//! cargo run --release -p tools --bin manifest -- record --manifest data/manifest.jsonl \
+//!   --kind trained-net --output nn.bin --input shard_000.psv --input shard_001.psv \
+//!   -- bullet-trainer --lr 1e-4 --init-from base.bin
+//!
+//! # ネットの来歴（入力を再帰的に辿る）
+//! cargo run --release -p tools --bin manifest -- lineage --manifest data/manifest.jsonl nn.bin
+//! ```
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Result, bail};
+use clap::{Parser, Subcommand};
+
+use tools::manifest::{ArtifactKind, ArtifactRecord, Manifest, sha256_file};
+
+#[derive(Parser)]
+#[command(
+    name = "manifest",
+    version,
+    about = "データパイプラインのマニフェストへ成果物を記録し、来歴を表示する"
+)]
+struct Cli {
+    #[command(subcommand)]
+    cmd: Cmd,
+}
+
+#[derive(Subcommand)]
+enum Cmd {
+    /// 成果物を記録する
+    Record {
+        /// マニフェストファイル（JSONL、なければ作成）
+        #[arg(long)]
+        manifest: PathBuf,
+        /// 成果物の種類（dataset-shard / cache / teacher-net / trained-net / book / other）
+        #[arg(long)]
+        kind: ArtifactKind,
+        /// 成果物のファイル
+        #[arg(long)]
+        output: PathBuf,
+        /// 成果物を作るのに使った入力ファイル（複数指定可）
+        #[arg(long)]
+        input: Vec<PathBuf>,
+        /// 成果物を作ったコマンドライン（`--` の後に書く）
+        #[arg(last = true, required = true)]
+        command: Vec<String>,
+    },
+    /// 成果物の来歴を表示する（記録されている入力を再帰的に辿る）
+    Lineage {
+        /// マニフェストファイル
+        #[arg(long)]
+        manifest: PathBuf,
+        /// 調べるファイル
+        file: PathBuf,
+    },
+}
+
+fn main() -> Result<()> {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+    match Cli::parse().cmd {
+        Cmd::Record {
+            manifest,
+            kind,
+            output,
+            input,
+            command,
+        } => {
+            let record = ArtifactRecord::describe(kind, &output, &input, command)?;
+            Manifest::append(&manifest, &record)?;
+            println!("recorded {} {} sha256={}", record.kind, record.path, record.sha256);
+            Ok(())
+        }
+        Cmd::Lineage { manifest, file } => lineage(&manifest, &file),
+    }
+}
+
+fn lineage(manifest: &Path, file: &Path) -> Result<()> {
+    let manifest = Manifest::load(manifest)?;
+    let sha256 = sha256_file(file)?;
+    if manifest.find(&sha256).is_none() {
+        bail!("{} (sha256 {sha256}) is not recorded in the manifest", file.display());
+    }
+    // 深さ優先で表示する（入力は記録順）
+    let mut stack = vec![(0usize, sha256, file.display().to_string())];
+    let mut shown = std::collections::HashSet::new();
+    while let Some((depth, hash, path)) = stack.pop() {
+        let indent = "  ".repeat(depth);
+        let Some(record) = manifest.find(&hash) else {
+            println!("{indent}- (not recorded) {path} sha256={}", short(&hash));
+            continue;
+        };
+        println!("{indent}- {} {} sha256={}", record.kind, record.path, short(&record.sha256));
+        if !shown.insert(hash) {
+            println!("{indent}  (shown above)");
+            continue;
+        }
+        println!("{indent}  created: {}", record.created_at);
+        let dirty = if record.git_dirty { " (dirty)" } else { "" };
+        println!("{indent}  git: {}{dirty}", record.git_commit.as_deref().unwrap_or("unknown"));
+        println!("{indent}  command: {}", record.command.join(" "));
+        for input in record.inputs.iter().rev() {
+            stack.push((depth + 1, input.sha256.clone(), input.path.clone()));
+        }
+    }
+    Ok(())
+}
+
+fn short(sha256: &str) -> &str {
+    &sha256[..sha256.len().min(12)]
+}
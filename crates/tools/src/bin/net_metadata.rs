@@ -0,0 +1,101 @@
+//! net_metadata - 評価関数ファイルの学習メタデータを表示・埋め込みする
+//!
+//! 学習器が書き出したネットのヘッダー（arch_str）に、学習ラン ID・データのマニフェストの
+//! ハッシュ・エポック数・検証損失・git コミットを埋め込む。埋め込んだネットを
+//! rshogi-usi で読み込むと `info string eval ...` で出力されるので、GUI のログだけで
+//! 本番ネットの来歴を辿れる。形式は `rshogi_core::nnue::NetMetadata` を参照。
+//!
+//! # 使用例
+//!
+//! ```bash
+//! # 埋め込む
+//! cargo run --release -p tools --bin net_metadata -- \
+//!   --input nn.bin --output nn-stamped.bin \
+//!   --run bullet-42 --dataset sha256:0f3a9c --epochs 400 --val-loss 0.0123 --commit a1b2c3d
+//!
+//! # 表示だけ（--output なし）
+//! cargo run --release -p tools --bin net_metadata -- --input nn-stamped.bin
+//! ```
+
+use std::path::PathBuf;
+
+use anyhow::{Context, Result, bail};
+use clap::Parser;
+
+use rshogi_core::nnue::{NetMetadata, embed_net_metadata, read_arch_string, read_net_metadata};
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "net_metadata",
+    version,
+    about = "評価関数ファイルの学習メタデータ（学習ラン・データ・エポック・検証損失・コミット）を表示・埋め込みする"
+)]
+struct Cli {
+    /// 評価関数ファイル
+    #[arg(long)]
+    input: PathBuf,
+
+    /// 埋め込んだファイルの出力先（省略時は表示のみ）
+    #[arg(long)]
+    output: Option<PathBuf>,
+
+    /// 学習ランの ID
+    #[arg(long)]
+    run: Option<String>,
+
+    /// 学習データのマニフェストのハッシュ
+    #[arg(long)]
+    dataset: Option<String>,
+
+    /// 学習したエポック数
+    #[arg(long)]
+    epochs: Option<u32>,
+
+    /// 検証損失
+    #[arg(long)]
+    val_loss: Option<f64>,
+
+    /// 学習に使ったコードの git コミット
+    #[arg(long)]
+    commit: Option<String>,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let bytes = std::fs::read(&cli.input)
+        .with_context(|| format!("評価関数ファイルを読めません: {}", cli.input.display()))?;
+
+    let metadata = NetMetadata {
+        features: None,
+        run: cli.run,
+        dataset: cli.dataset,
+        epochs: cli.epochs,
+        val_loss: cli.val_loss,
+        commit: cli.commit,
+    };
+
+    let Some(output) = cli.output else {
+        if !metadata.is_empty() {
+            bail!("埋め込むには --output を指定してください");
+        }
+        println!("arch: {}", read_arch_string(&mut &bytes[..])?);
+        println!("metadata: {}", read_net_metadata(&mut &bytes[..])?.summary());
+        return Ok(());
+    };
+    if metadata.is_empty() {
+        bail!(
+            "埋め込むメタデータがありません（--run / --dataset / --epochs / --val-loss / --commit）"
+        );
+    }
+    if output == cli.input {
+        bail!("--output は --input と別のファイルにしてください");
+    }
+
+    let stamped = embed_net_metadata(&bytes, &metadata)
+        .with_context(|| format!("メタデータを埋め込めません: {}", cli.input.display()))?;
+    std::fs::write(&output, &stamped)
+        .with_context(|| format!("出力ファイルを書けません: {}", output.display()))?;
+    println!("metadata: {}", read_net_metadata(&mut &stamped[..])?.summary());
+    println!("output: {}", output.display());
+    Ok(())
+}
@@ -0,0 +1,75 @@
+//! perft（指し手生成の検証）
+//!
+//! 局面から指定深さまでの合法手の組み合わせ数（不成を含む）を数える。
+//! `--divide` でルートの合法手ごとの数を出し、`--expect` を渡すと合計が一致しなければ失敗する。
+//!
+//! ```bash
+//! cargo run --release -p tools --bin perft -- --depth 4 --expect 719731
+//! cargo run --release -p tools --bin perft -- --sfen "<sfen>" --depth 3 --divide
+//! ```
+
+use std::time::Instant;
+
+use anyhow::{Context, Result, bail};
+use clap::Parser;
+use rshogi_core::movegen::perft_divide;
+use rshogi_core::position::Position;
+
+#[derive(Parser, Debug)]
+#[command(version, about = "指定深さまでの合法手の組み合わせ数（perft）を数える")]
+struct Cli {
+    /// 対象局面の SFEN（省略時は平手初期局面）
+    #[arg(long)]
+    sfen: Option<String>,
+
+    /// 深さ
+    #[arg(long, default_value_t = 4)]
+    depth: u32,
+
+    /// ルートの合法手ごとの数を表示
+    #[arg(long)]
+    divide: bool,
+
+    /// 期待する合計（一致しなければ終了コード 1）
+    #[arg(long)]
+    expect: Option<u64>,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    if cli.depth == 0 {
+        bail!("--depth must be at least 1");
+    }
+
+    let mut pos = Position::new();
+    match &cli.sfen {
+        Some(sfen) => pos.set_sfen(sfen).with_context(|| format!("invalid sfen: {sfen}"))?,
+        None => pos.set_hirate(),
+    }
+
+    let started = Instant::now();
+    let divide = perft_divide(&mut pos, cli.depth);
+    let elapsed = started.elapsed();
+    let total: u64 = divide.iter().map(|(_, nodes)| nodes).sum();
+
+    if cli.divide {
+        for (mv, nodes) in &divide {
+            println!("{} {nodes}", mv.to_usi());
+        }
+    }
+    let nps = total.saturating_mul(1000) / (elapsed.as_millis().max(1) as u64);
+    println!(
+        "depth={} moves={} nodes={total} time={}ms nps={nps}",
+        cli.depth,
+        divide.len(),
+        elapsed.as_millis()
+    );
+
+    if let Some(expect) = cli.expect
+        && expect != total
+    {
+        eprintln!("perft mismatch: expected {expect}, got {total}");
+        std::process::exit(1);
+    }
+    Ok(())
+}
@@ -11,6 +11,7 @@ use anyhow::{Context, Result, bail};
 use clap::Parser;
 use rand::{Rng, SeedableRng, seq::SliceRandom};
 use rand_chacha::ChaCha8Rng;
+use tools::manifest::{ArtifactKind, record_artifact};
 
 const RECORD_SIZE: usize = 38;
 const KEY_SIZE: usize = 32;
@@ -61,6 +62,11 @@ struct Args {
     /// Bloom filter の偽陽性率。
     #[arg(long, default_value_t = 1e-6)]
     false_positive_rate: f64,
+
+    /// データパイプラインのマニフェスト（JSONL）。指定すると分割ファイルを入力・除外ファイルの
+    /// ハッシュと共に追記する。
+    #[arg(long)]
+    manifest: Option<PathBuf>,
 }
 
 /// 512 bit（1 cache line）単位の blocked Bloom filter。
@@ -260,6 +266,10 @@ fn collect_records(
     Ok((kept, summary))
 }
 
+fn chunk_path(out_dir: &Path, prefix: &str, chunk_index: usize) -> PathBuf {
+    out_dir.join(format!("{prefix}_{chunk_index:05}.hcpe"))
+}
+
 fn write_chunks(
     records: &[Record],
     out_dir: &Path,
@@ -270,7 +280,7 @@ fn write_chunks(
         .with_context(|| format!("出力ディレクトリを作成できません: {}", out_dir.display()))?;
 
     for (chunk_index, chunk) in records.chunks(chunk_records).enumerate() {
-        let path = out_dir.join(format!("{prefix}_{chunk_index:05}.hcpe"));
+        let path = chunk_path(out_dir, prefix, chunk_index);
         let file = File::create(&path)
             .with_context(|| format!("出力ファイルを作成できません: {}", path.display()))?;
         let mut writer = BufWriter::new(file);
@@ -334,11 +344,12 @@ fn run(config: Config) -> Result<Summary> {
 
 fn main() -> Result<()> {
     let args = Args::parse();
+    let sources: Vec<PathBuf> = args.inputs.iter().chain(&args.exclude).cloned().collect();
     let summary = run(Config {
         inputs: args.inputs,
         excludes: args.exclude,
-        out_dir: args.out_dir,
-        prefix: args.prefix,
+        out_dir: args.out_dir.clone(),
+        prefix: args.prefix.clone(),
         chunk_records: args.chunk_records,
         target: args.target,
         seed: args.seed,
@@ -355,6 +366,12 @@ fn main() -> Result<()> {
         summary.written,
         summary.chunks
     );
+    if let Some(manifest) = &args.manifest {
+        for chunk_index in 0..summary.chunks {
+            let path = chunk_path(&args.out_dir, &args.prefix, chunk_index);
+            record_artifact(manifest, ArtifactKind::Cache, &path, &sources)?;
+        }
+    }
     Ok(())
 }
 
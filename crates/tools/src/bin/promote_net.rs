@@ -0,0 +1,348 @@
+//! 学習済みネットの昇格パイプライン
+//!
+//! 候補のネットを本番ネットと比較し、通った場合だけ配備ディレクトリへ置く。
+//!
+//! 1. netcheck: 戦術テストスイートを両方のネットで解き、正解数が減っていないか確かめる
+//! 2. SPRT: `tournament --sprt` で本番ネットと対局させ、`analyze_selfplay` で判定を読む
+//! 3. 昇格: 版番号付きのファイル名で配備ディレクトリへコピーし、`current.json`・
+//!    `CHANGELOG.md`・マニフェストを更新する
+//!
+//! どれかが通らなければ配備ディレクトリには触れずにエラーで終わる。
+//! 配備ディレクトリの構成は `tools::promote` を参照。
+//!
+//! ```bash
+//! cargo build --release -p tools --bin tournament --bin analyze_selfplay --bin promote_net
+//! cargo build --release -p rshogi-usi
+//! ./target/release/promote_net --candidate nn.bin --deploy-dir "$SHOGI_DATA/nnue/deploy" \
+//!   --engine target/release/rshogi-usi --games 2000 --byoyomi 1000 --concurrency 16 \
+//!   --startpos-file data/startpos/start_sfens_ply32.txt --note "bullet run 42"
+//! ```
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{Context, Result, bail};
+use clap::Parser;
+
+use rshogi_core::nnue::init_nnue;
+use rshogi_core::testpos::Tag;
+use tools::collect_system_info;
+use tools::manifest::{ArtifactKind, ArtifactRecord, Manifest, sha256_file};
+use tools::promote::{DeployDir, GateSummary, NetcheckSummary, SprtSummary, short_sha};
+use tools::tactics::{
+    TacticsCase, TacticsReport, TacticsSearchOptions, builtin_suite, load_suite, run_case,
+};
+
+/// SPRT の対局で本番ネットに付けるラベル
+const BASE_LABEL: &str = "base";
+
+/// SPRT の対局で候補のネットに付けるラベル
+const TEST_LABEL: &str = "test";
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "promote_net",
+    version,
+    about = "候補のネットを本番ネットと比較し、通れば版番号を付けて配備ディレクトリへ置く"
+)]
+struct Cli {
+    /// 候補のネット
+    #[arg(long)]
+    candidate: PathBuf,
+
+    /// 配備ディレクトリ
+    #[arg(long)]
+    deploy_dir: PathBuf,
+
+    /// 対局に使う USI エンジン（EvalFile でネットを切り替える）
+    #[arg(long)]
+    engine: PathBuf,
+
+    /// 比較相手の本番ネット（省略時は配備ディレクトリの current.json のネット）
+    #[arg(long)]
+    production: Option<PathBuf>,
+
+    /// 配備するファイル名の接頭辞（`<name>-v0001.bin`）
+    #[arg(long, default_value = "nn")]
+    name: String,
+
+    /// CHANGELOG に書く補足
+    #[arg(long)]
+    note: Option<String>,
+
+    /// 配備したネットを記録するマニフェスト（省略時は `<deploy-dir>/manifest.jsonl`）
+    #[arg(long)]
+    manifest: Option<PathBuf>,
+
+    /// netcheck と SPRT の結果の出力先（省略時は `<deploy-dir>/gates/<候補の sha256 先頭 12 桁>`）
+    #[arg(long)]
+    work_dir: Option<PathBuf>,
+
+    /// `tournament` / `analyze_selfplay` のあるディレクトリ（省略時はこのバイナリと同じ）
+    #[arg(long)]
+    tools_dir: Option<PathBuf>,
+
+    /// netcheck のスイートファイル（省略時は `--netcheck-builtin` の埋め込み局面集）
+    #[arg(long)]
+    netcheck_suite: Option<PathBuf>,
+
+    /// netcheck に使う埋め込みの局面集のタグ
+    #[arg(long, default_value = "mate")]
+    netcheck_builtin: String,
+
+    /// netcheck の 1 局面あたりの探索時間（ミリ秒）
+    #[arg(long, default_value_t = 500)]
+    netcheck_movetime: u64,
+
+    /// netcheck で本番ネットより少なくてもよい正解数
+    #[arg(long, default_value_t = 0)]
+    netcheck_tolerance: usize,
+
+    /// SPRT の対局数の上限（各方向）
+    #[arg(long, default_value_t = 5000)]
+    games: u32,
+
+    /// 秒読み（ミリ秒）
+    #[arg(long, default_value_t = 1000)]
+    byoyomi: u64,
+
+    /// 並列対局数
+    #[arg(long, default_value_t = 1)]
+    concurrency: usize,
+
+    /// エンジンごとの探索スレッド数（netcheck にも使う）
+    #[arg(long, default_value_t = 1)]
+    threads: usize,
+
+    /// エンジンごとの置換表サイズ（MB、netcheck にも使う）
+    #[arg(long, default_value_t = 256)]
+    hash_mb: u32,
+
+    /// 開始局面ファイル（USI position 行）
+    #[arg(long)]
+    startpos_file: Option<PathBuf>,
+
+    /// H0 仮説の正規化 Elo
+    #[arg(long, default_value_t = 0.0)]
+    sprt_nelo0: f64,
+
+    /// H1 仮説の正規化 Elo
+    #[arg(long, default_value_t = 5.0)]
+    sprt_nelo1: f64,
+
+    /// 第一種過誤率 α
+    #[arg(long, default_value_t = 0.05)]
+    sprt_alpha: f64,
+
+    /// 第二種過誤率 β
+    #[arg(long, default_value_t = 0.05)]
+    sprt_beta: f64,
+}
+
+fn main() -> Result<()> {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+    let cli = Cli::parse();
+
+    let deploy = DeployDir::new(&cli.deploy_dir);
+    let production = match (&cli.production, deploy.current()?) {
+        (Some(path), _) => path.clone(),
+        (None, Some(current)) => deploy.path_of(&current),
+        (None, None) => bail!(
+            "{} has no current net yet; pass the production net with --production",
+            cli.deploy_dir.display()
+        ),
+    };
+    let candidate_sha256 = sha256_file(&cli.candidate)?;
+    let production_sha256 = sha256_file(&production)?;
+    if candidate_sha256 == production_sha256 {
+        bail!("candidate is identical to the production net {}", production.display());
+    }
+    let work_dir = cli
+        .work_dir
+        .clone()
+        .unwrap_or_else(|| cli.deploy_dir.join("gates").join(short_sha(&candidate_sha256)));
+    std::fs::create_dir_all(&work_dir)
+        .with_context(|| format!("Failed to create {}", work_dir.display()))?;
+    println!(
+        "candidate:  {} (sha256 {})",
+        cli.candidate.display(),
+        short_sha(&candidate_sha256)
+    );
+    println!(
+        "production: {} (sha256 {})",
+        production.display(),
+        short_sha(&production_sha256)
+    );
+
+    println!("\n=== netcheck ===");
+    let netcheck = run_netcheck(&cli, &production, &work_dir)?;
+    println!(
+        "{}: candidate {}/{}, production {}/{}",
+        netcheck.suite,
+        netcheck.candidate_solved,
+        netcheck.total,
+        netcheck.production_solved,
+        netcheck.total
+    );
+    if !netcheck.passed(cli.netcheck_tolerance) {
+        bail!(
+            "netcheck failed: candidate solved {} < production {} - tolerance {}",
+            netcheck.candidate_solved,
+            netcheck.production_solved,
+            cli.netcheck_tolerance
+        );
+    }
+
+    println!("\n=== SPRT ===");
+    let sprt = run_sprt(&cli, &production, &work_dir)?;
+    println!(
+        "decision={} pairs={} LLR={:+.3} [{:+.3}, {:+.3}]",
+        sprt.decision, sprt.pairs, sprt.llr, sprt.lower, sprt.upper
+    );
+    if !sprt.passed() {
+        bail!("SPRT did not accept the candidate (decision: {})", sprt.decision);
+    }
+
+    let gates = GateSummary {
+        production: production.display().to_string(),
+        production_sha256,
+        netcheck,
+        sprt,
+    };
+    let net = deploy.promote(&cli.candidate, &cli.name, &gates, cli.note.as_deref())?;
+    let manifest = cli.manifest.clone().unwrap_or_else(|| cli.deploy_dir.join("manifest.jsonl"));
+    record_deployed(&manifest, &deploy.path_of(&net), &candidate_sha256)?;
+
+    println!("\npromoted v{:04}: {}", net.version, deploy.path_of(&net).display());
+    Ok(())
+}
+
+/// 両方のネットで戦術テストスイートを解き、結果を `work_dir` に保存する
+fn run_netcheck(cli: &Cli, production: &Path, work_dir: &Path) -> Result<NetcheckSummary> {
+    let (suite, cases) = load_netcheck_suite(cli)?;
+    let options = TacticsSearchOptions {
+        movetime_ms: cli.netcheck_movetime,
+        threads: cli.threads,
+        tt_mb: cli.hash_mb as usize,
+    };
+    let mut solved = Vec::with_capacity(2);
+    for (label, net) in [
+        (BASE_LABEL, production),
+        (TEST_LABEL, cli.candidate.as_path()),
+    ] {
+        init_nnue(net).map_err(|e| {
+            anyhow::anyhow!("Failed to initialize NNUE from '{}': {e}", net.display())
+        })?;
+        let results = cases
+            .iter()
+            .map(|case| run_case(case, options, false))
+            .collect::<Result<Vec<_>>>()?;
+        let report = TacticsReport {
+            system_info: collect_system_info(),
+            suite: suite.clone(),
+            movetime_ms: cli.netcheck_movetime,
+            threads: cli.threads,
+            results,
+        };
+        report.save_json(&work_dir.join(format!("netcheck_{label}.json")))?;
+        solved.push(report.solved_count());
+    }
+    Ok(NetcheckSummary {
+        suite,
+        total: cases.len(),
+        production_solved: solved[0],
+        candidate_solved: solved[1],
+    })
+}
+
+fn load_netcheck_suite(cli: &Cli) -> Result<(String, Vec<TacticsCase>)> {
+    let (suite, cases) = match &cli.netcheck_suite {
+        Some(path) => (path.display().to_string(), load_suite(path)?),
+        None => {
+            let tag = Tag::from_name(&cli.netcheck_builtin)
+                .ok_or_else(|| anyhow::anyhow!("unknown builtin tag '{}'", cli.netcheck_builtin))?;
+            (format!("builtin:{tag}"), builtin_suite(tag))
+        }
+    };
+    if cases.is_empty() {
+        bail!("no positions in netcheck suite: {suite}");
+    }
+    Ok((suite, cases))
+}
+
+/// 本番ネットと候補のネットを `tournament --sprt` で対局させ、判定を読む
+fn run_sprt(cli: &Cli, production: &Path, work_dir: &Path) -> Result<SprtSummary> {
+    let tools_dir = match &cli.tools_dir {
+        Some(dir) => dir.clone(),
+        None => std::env::current_exe()?
+            .parent()
+            .context("Failed to locate the tools directory")?
+            .to_path_buf(),
+    };
+    let out_dir = work_dir.join("sprt");
+    let engine = cli.engine.display().to_string();
+    let mut tournament = Command::new(tools_dir.join("tournament"));
+    tournament
+        .args(["--engine", &engine, "--engine-label", BASE_LABEL])
+        .args(["--engine", &engine, "--engine-label", TEST_LABEL])
+        .arg("--engine-usi-option")
+        .arg(format!("0:EvalFile={}", production.display()))
+        .arg("--engine-usi-option")
+        .arg(format!("1:EvalFile={}", cli.candidate.display()))
+        .args(["--games", &cli.games.to_string()])
+        .args(["--byoyomi", &cli.byoyomi.to_string()])
+        .args(["--concurrency", &cli.concurrency.to_string()])
+        .args(["--threads", &cli.threads.to_string()])
+        .args(["--hash-mb", &cli.hash_mb.to_string()])
+        .args([
+            "--base-label",
+            BASE_LABEL,
+            "--sprt",
+            "--sprt-test-label",
+            TEST_LABEL,
+        ])
+        .args(["--sprt-nelo0", &cli.sprt_nelo0.to_string()])
+        .args(["--sprt-nelo1", &cli.sprt_nelo1.to_string()])
+        .args(["--sprt-alpha", &cli.sprt_alpha.to_string()])
+        .args(["--sprt-beta", &cli.sprt_beta.to_string()])
+        .arg("--out-dir")
+        .arg(&out_dir);
+    if let Some(startpos) = &cli.startpos_file {
+        tournament.arg("--startpos-file").arg(startpos);
+    }
+    let status = tournament.status().context("Failed to run tournament")?;
+    if !status.success() {
+        bail!("tournament exited with {status}");
+    }
+
+    let output = Command::new(tools_dir.join("analyze_selfplay"))
+        .args(["--json", "--sprt", "--sprt-base-label", BASE_LABEL])
+        .args(["--sprt-test-label", TEST_LABEL])
+        .arg(out_dir.join(format!("{BASE_LABEL}-vs-{TEST_LABEL}.jsonl")))
+        .output()
+        .context("Failed to run analyze_selfplay")?;
+    if !output.status.success() {
+        bail!(
+            "analyze_selfplay exited with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+    std::fs::write(work_dir.join("sprt.json"), &output.stdout)?;
+    SprtSummary::from_analyze_json(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// 配備したネットをマニフェストに記録する
+///
+/// 候補のネットの記録（学習に使ったデータ）がマニフェストにあれば、その入力を引き継いで
+/// 配備したネットからも来歴を辿れるようにする。
+fn record_deployed(manifest_path: &Path, deployed: &Path, candidate_sha256: &str) -> Result<()> {
+    let manifest = Manifest::load(manifest_path)?;
+    let mut record = ArtifactRecord::describe(ArtifactKind::TrainedNet, deployed, &[], Vec::new())?;
+    if let Some(trained) = manifest.find(candidate_sha256) {
+        record.inputs = trained.inputs.clone();
+    }
+    Manifest::append(manifest_path, &record)?;
+    println!("manifest: recorded {} in {}", record.path, manifest_path.display());
+    Ok(())
+}
@@ -40,6 +40,7 @@ use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Instant;
 
 use rshogi_core::types::Color;
+use tools::manifest::{ArtifactKind, record_artifact};
 use tools::packed_sfen::{PackedSfenValue, pack_hcp_from_parts, unpack_sfen_to_parts};
 
 /// hcpe3 レコード長（hcp[32] + moveNum:u16 + result:u8 + opponent:u8
@@ -103,6 +104,10 @@ struct Cli {
     /// 詳細出力（変換できなかったレコードを逐次ログ）
     #[arg(short, long)]
     verbose: bool,
+
+    /// データパイプラインのマニフェスト（JSONL）。指定すると出力を入力のハッシュと共に追記する
+    #[arg(long)]
+    manifest: Option<PathBuf>,
 }
 
 /// 1 レコードの変換結果。出力は `data[..len]`。
@@ -461,6 +466,14 @@ fn main() -> Result<()> {
     if total_errors > 0 {
         eprintln!("注意: {total_errors} レコードを変換できずスキップしました");
     }
+    if let Some(manifest) = &cli.manifest {
+        record_artifact(
+            manifest,
+            ArtifactKind::DatasetShard,
+            &cli.output,
+            std::slice::from_ref(&cli.input),
+        )?;
+    }
 
     Ok(())
 }
@@ -0,0 +1,284 @@
+//! JSONL 教師データのラベル付け替えツール
+//!
+//! `psv_to_jsonl` 等が出力する JSONL（各行に `sfen` と手番側視点 cp の `score` を持つ
+//! JSON オブジェクト）の `score` を、指定した NNUE での静止探索値（`--label qsearch`）または
+//! 固定 depth 探索値（`--label search`）に差し替える。`depth` は使ったラベルに合わせて書き換え、
+//! その他のフィールドはそのまま残す。
+//!
+//! `--drop-non-quiet` を付けると、王手の局面と静止探索で評価値が `--quiet-margin` cp より
+//! 動く局面（駒の取り合いの途中）を出力から除く。
+//!
+//! 評価器の構成とラベル付けは `tools::teacher_labeler` と共有し、局面ごとに `Search` を
+//! 作り直すので出力は処理順・`--threads` に依存しない。
+//!
+//! ```bash
+//! cargo run --release -p tools --bin relabel -- \
+//!   --input train.jsonl --output train_q.jsonl --nnue eval/nn.bin --drop-non-quiet
+//! cargo run --release -p tools --bin relabel -- \
+//!   --input train.jsonl --output train_d8.jsonl --nnue eval/nn.bin --label search --depth 8
+//! ```
+
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use anyhow::{Context, Result, bail};
+use clap::{Parser, ValueEnum};
+use indicatif::{ProgressBar, ProgressStyle};
+use rayon::prelude::*;
+use serde_json::{Value as JsonValue, json};
+
+use rshogi_core::position::Position;
+use tools::teacher_labeler::{
+    self, LabelerEvalConfig, QuietLabel, SEARCH_STACK_SIZE, label_position, qsearch_label,
+};
+
+/// 一度に並列処理する行数
+const CHUNK_SIZE: usize = 4096;
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "relabel",
+    version,
+    about = "JSONL 教師の score を静止探索 / 固定 depth 探索の値に付け替える（非静穏局面の除外可）"
+)]
+struct Cli {
+    /// 入力 JSONL（各行に `sfen` を含む JSON オブジェクト）
+    #[arg(long)]
+    input: PathBuf,
+
+    /// 出力 JSONL
+    #[arg(long)]
+    output: PathBuf,
+
+    /// ラベルに使う NNUE モデルファイル
+    #[arg(long)]
+    nnue: PathBuf,
+
+    /// FV_SCALE オーバーライド（0=ヘッダ自動判定、1 以上=指定値）
+    #[arg(long, default_value_t = 0)]
+    fv_scale: i32,
+
+    /// LayerStacks の bucket mode（例 `progress8kpabs`）。LS ビルドでは既定なので通常は指定不要。
+    #[arg(long)]
+    ls_bucket_mode: Option<String>,
+
+    /// progress8kpabs 用の進行度係数ファイル。LS + progress8kpabs で必須。
+    #[arg(long)]
+    ls_progress_coeff: Option<PathBuf>,
+
+    /// 付け替えるラベルの種類
+    #[arg(long, value_enum, default_value_t = LabelKind::Qsearch)]
+    label: LabelKind,
+
+    /// `--label search` の探索深さ
+    #[arg(long, default_value_t = 8)]
+    depth: i32,
+
+    /// `--label search` の探索ノード数上限（0=無制限）
+    #[arg(long, default_value_t = 0)]
+    nodes: u64,
+
+    /// 静かでない局面（王手、または静止探索で評価値が大きく動く局面）を出力から除く
+    #[arg(long)]
+    drop_non_quiet: bool,
+
+    /// 静かとみなす静止探索値と静的評価値の差の上限（cp）
+    #[arg(long, default_value_t = 100)]
+    quiet_margin: i32,
+
+    /// 局面ごとの置換表サイズ（MB）。局面ごとに作り直すため過大にしない。
+    #[arg(long, default_value_t = 4)]
+    hash_mb: usize,
+
+    /// スレッド数（0=利用可能 CPU 数）
+    #[arg(long, default_value_t = 0)]
+    threads: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum LabelKind {
+    /// 静止探索値（反復深化なし）
+    Qsearch,
+    /// 固定 depth 探索値
+    Search,
+}
+
+/// 1 行の処理結果
+enum Outcome {
+    Ok(String),
+    Dropped,
+    Error(String),
+}
+
+#[derive(Default)]
+struct RunStats {
+    written: u64,
+    dropped: u64,
+    errors: u64,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    if cli.input == cli.output {
+        bail!("--input and --output must differ");
+    }
+    if cli.label == LabelKind::Search && cli.depth <= 0 && cli.nodes == 0 {
+        bail!("--depth and --nodes are both unlimited; specify at least one to bound the search");
+    }
+
+    teacher_labeler::configure_eval(&LabelerEvalConfig {
+        nnue: &cli.nnue,
+        fv_scale: cli.fv_scale,
+        ls_bucket_mode: cli.ls_bucket_mode.as_deref(),
+        ls_progress_coeff: cli.ls_progress_coeff.as_deref(),
+    })?;
+
+    ctrlc::set_handler(|| INTERRUPTED.store(true, Ordering::SeqCst))
+        .context("Failed to set Ctrl-C handler")?;
+
+    let num_threads = if cli.threads > 0 {
+        cli.threads
+    } else {
+        std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1)
+    };
+    // 探索は再帰が深いので worker に十分なスタックを確保する
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(num_threads)
+        .stack_size(SEARCH_STACK_SIZE)
+        .build()
+        .context("Failed to build thread pool")?;
+
+    eprintln!(
+        "Relabeling {} -> {} (label={:?}, depth={}, nodes={}, drop_non_quiet={}, margin={}, threads={})",
+        cli.input.display(),
+        cli.output.display(),
+        cli.label,
+        cli.depth,
+        cli.nodes,
+        cli.drop_non_quiet,
+        cli.quiet_margin,
+        num_threads,
+    );
+
+    let input = File::open(&cli.input)
+        .with_context(|| format!("Failed to open {}", cli.input.display()))?;
+    let output = File::create(&cli.output)
+        .with_context(|| format!("Failed to create {}", cli.output.display()))?;
+    let mut writer = BufWriter::new(output);
+    let progress = ProgressBar::new_spinner();
+    progress.set_style(
+        ProgressStyle::default_spinner()
+            .template("[{elapsed_precise}] {pos} records ({per_sec}) {msg}")
+            .expect("valid template"),
+    );
+
+    let mut stats = RunStats::default();
+    let mut lines = BufReader::new(input).lines();
+    loop {
+        let mut chunk: Vec<String> =
+            lines.by_ref().take(CHUNK_SIZE).collect::<std::io::Result<_>>()?;
+        if chunk.is_empty() || INTERRUPTED.load(Ordering::SeqCst) {
+            break;
+        }
+        chunk.retain(|line| !line.trim().is_empty());
+        let outcomes: Vec<Outcome> =
+            pool.install(|| chunk.par_iter().map(|line| process_line(line, &cli)).collect());
+        for (line, outcome) in chunk.iter().zip(outcomes) {
+            match outcome {
+                Outcome::Ok(out) => {
+                    writeln!(writer, "{out}")?;
+                    stats.written += 1;
+                }
+                Outcome::Dropped => stats.dropped += 1,
+                Outcome::Error(msg) => {
+                    stats.errors += 1;
+                    eprintln!("skip record: {msg}: {line}");
+                }
+            }
+        }
+        progress.inc(chunk.len() as u64);
+    }
+    writer.flush()?;
+    progress.finish();
+
+    eprintln!(
+        "Wrote {} records ({} non-quiet dropped, {} errors)",
+        stats.written, stats.dropped, stats.errors
+    );
+    if INTERRUPTED.load(Ordering::SeqCst) {
+        bail!("interrupted: output truncated ({} records written)", stats.written);
+    }
+    Ok(())
+}
+
+/// 1 行の JSON のラベルを付け替える
+fn process_line(line: &str, cli: &Cli) -> Outcome {
+    let mut value: JsonValue = match serde_json::from_str(line) {
+        Ok(v) => v,
+        Err(e) => return Outcome::Error(format!("json parse error: {e}")),
+    };
+    let Some(obj) = value.as_object_mut() else {
+        return Outcome::Error("record is not a JSON object".to_string());
+    };
+    let Some(sfen) = obj.get("sfen").and_then(JsonValue::as_str) else {
+        return Outcome::Error("record has no string `sfen` field".to_string());
+    };
+    let mut pos = Position::new();
+    if let Err(e) = pos.set_sfen(sfen) {
+        return Outcome::Error(format!("set_sfen failed: {e:?}"));
+    }
+
+    let quiet = (cli.label == LabelKind::Qsearch || cli.drop_non_quiet)
+        .then(|| qsearch_label(&mut pos, cli.hash_mb));
+    if cli.drop_non_quiet && quiet.is_some_and(|q| !q.is_quiet(cli.quiet_margin)) {
+        return Outcome::Dropped;
+    }
+    let (score, depth) = match (cli.label, quiet) {
+        (LabelKind::Qsearch, Some(QuietLabel { qsearch, .. })) => (qsearch, 0),
+        _ => {
+            let labels = label_position(&mut pos, cli.depth, cli.nodes, cli.hash_mb, &[], None);
+            (labels[0].0, cli.depth)
+        }
+    };
+    apply_label(obj, score, depth);
+
+    match serde_json::to_string(&value) {
+        Ok(s) => Outcome::Ok(s),
+        Err(e) => Outcome::Error(format!("serialize error: {e}")),
+    }
+}
+
+/// `score`（手番側視点 cp）と `depth` を差し替える。その他のフィールドは残す。
+fn apply_label(obj: &mut serde_json::Map<String, JsonValue>, score: i32, depth: i32) {
+    obj.insert("score".to_string(), json!(score));
+    obj.insert("depth".to_string(), json!(depth));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn apply_label_replaces_score_and_keeps_other_fields() {
+        let mut value: JsonValue = serde_json::from_str(
+            r#"{"sfen":"x","score":321,"depth":12,"best_move":"7g7f","nodes":5000}"#,
+        )
+        .unwrap();
+        apply_label(value.as_object_mut().unwrap(), -45, 0);
+        assert_eq!(
+            value,
+            json!({"sfen":"x","score":-45,"depth":0,"best_move":"7g7f","nodes":5000})
+        );
+    }
+
+    #[test]
+    fn apply_label_adds_missing_fields() {
+        let mut value: JsonValue = serde_json::from_str(r#"{"sfen":"x"}"#).unwrap();
+        apply_label(value.as_object_mut().unwrap(), 10, 8);
+        assert_eq!(value, json!({"sfen":"x","score":10,"depth":8}));
+    }
+}
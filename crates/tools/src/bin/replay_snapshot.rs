@@ -0,0 +1,136 @@
+//! 探索スナップショットの再実行ツール
+//!
+//! USI エンジンの `SearchSnapshotDir` で書き出した探索スナップショットを読み、記録された
+//! 反復を `Search::replay_snapshot` で再実行して、元の探索の結果（評価値・読み筋・
+//! ノード数）と比べる。同じ評価関数・同じビルドなら結果は一致するので、枝刈りの変更や
+//! デバッグ出力を入れたビルドで同じ反復を何度でも調べられる。
+//!
+//! ```bash
+//! cargo run --release -p tools --bin replay_snapshot -- \
+//!   --snapshot snapshots/snapshot-1792149751600-d14.bin --nnue eval/nn.bin
+//! ```
+
+use std::path::PathBuf;
+
+use anyhow::{Context, Result, anyhow, bail};
+use clap::Parser;
+
+use rshogi_core::eval::{MaterialLevel, set_material_level};
+use rshogi_core::search::{IterationOutcome, Search, SearchSnapshot};
+use tools::teacher_labeler::{self, LabelerEvalConfig, SEARCH_STACK_SIZE};
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "replay_snapshot",
+    version,
+    about = "探索スナップショットの反復を再実行し、元の探索結果と比べる"
+)]
+struct Cli {
+    /// スナップショットファイル
+    #[arg(long)]
+    snapshot: PathBuf,
+
+    /// 元の探索で使った NNUE モデルファイル（未指定なら Material 評価）
+    #[arg(long)]
+    nnue: Option<PathBuf>,
+
+    /// `--nnue` 未指定時の MaterialLevel
+    #[arg(long, default_value_t = 9)]
+    material_level: u8,
+
+    /// FV_SCALE オーバーライド（0=ヘッダ自動判定、1 以上=指定値）
+    #[arg(long, default_value_t = 0)]
+    fv_scale: i32,
+
+    /// LayerStacks の bucket mode（例 `progress8kpabs`）。LS ビルドでは既定なので通常は指定不要。
+    #[arg(long)]
+    ls_bucket_mode: Option<String>,
+
+    /// progress8kpabs 用の進行度係数ファイル。LS + progress8kpabs で必須。
+    #[arg(long)]
+    ls_progress_coeff: Option<PathBuf>,
+
+    /// 再実行の回数（毎回スナップショットの状態から始める）
+    #[arg(long, default_value_t = 2)]
+    repeat: usize,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    if cli.repeat == 0 {
+        bail!("--repeat must be at least 1");
+    }
+
+    match &cli.nnue {
+        Some(nnue) => teacher_labeler::configure_eval(&LabelerEvalConfig {
+            nnue,
+            fv_scale: cli.fv_scale,
+            ls_bucket_mode: cli.ls_bucket_mode.as_deref(),
+            ls_progress_coeff: cli.ls_progress_coeff.as_deref(),
+        })?,
+        None => {
+            let level = MaterialLevel::from_value(cli.material_level)
+                .ok_or_else(|| anyhow!("invalid MaterialLevel: {}", cli.material_level))?;
+            set_material_level(level);
+        }
+    }
+
+    let snapshot = SearchSnapshot::load(&cli.snapshot)
+        .with_context(|| format!("Failed to load {}", cli.snapshot.display()))?;
+    println!("sfen: {}", snapshot.sfen);
+    println!(
+        "depth: {}  root moves: {}  hash: {} MB",
+        snapshot.depth,
+        snapshot.root_moves.len(),
+        snapshot.tt_size_mb()
+    );
+    if let Some(best) = snapshot.root_moves.first() {
+        println!("previous iteration: {}", best.score.raw());
+    }
+    let original = snapshot.original.clone();
+    if let Some(original) = &original {
+        print_outcome("original", original);
+    }
+
+    // 探索は再帰が深いので十分なスタックを確保したスレッドで実行する
+    let repeat = cli.repeat;
+    let outcomes = std::thread::Builder::new()
+        .stack_size(SEARCH_STACK_SIZE)
+        .spawn(move || -> Result<Vec<IterationOutcome>> {
+            let mut search = Search::new(snapshot.tt_size_mb());
+            let mut outcomes = Vec::with_capacity(repeat);
+            for _ in 0..repeat {
+                outcomes.push(search.replay_snapshot(&snapshot)?);
+            }
+            Ok(outcomes)
+        })
+        .context("Failed to spawn search thread")?
+        .join()
+        .map_err(|_| anyhow!("search thread panicked"))??;
+
+    for (idx, outcome) in outcomes.iter().enumerate() {
+        print_outcome(&format!("replay {}", idx + 1), outcome);
+    }
+    let stable = outcomes.windows(2).all(|w| w[0] == w[1]);
+    if !stable {
+        bail!("replays differ from each other");
+    }
+    match original {
+        Some(original) if original != outcomes[0] => {
+            bail!("replay differs from the original search (different build or evaluation?)")
+        }
+        Some(_) => println!("reproduced the original search"),
+        None => println!("replays are stable (snapshot has no original outcome)"),
+    }
+    Ok(())
+}
+
+fn print_outcome(label: &str, outcome: &IterationOutcome) {
+    let pv: Vec<String> = outcome.pv.iter().map(|mv| mv.to_usi()).collect();
+    println!(
+        "{label}: score {} nodes {} pv {}",
+        outcome.score.raw(),
+        outcome.nodes,
+        pv.join(" ")
+    );
+}
@@ -612,28 +612,38 @@ fn default_force_init_cleanup_paths(run_dir: &Path) -> Vec<PathBuf> {
         // が見え続け、tune.py apply に誤投入される事故になる。fresh 系のリスタート (force-init
         // / fresh / use-existing) で必ず消す。
         run_dir.join("final.params"),
+        final_toml_path(run_dir),
     ]
 }
 
+/// `<run-dir>/final.toml`: 正常完了時の確定値を engine 側 USI option 名で並べた TOML パッチ。
+fn final_toml_path(run_dir: &Path) -> PathBuf {
+    run_dir.join("final.toml")
+}
+
 /// fresh start (force-init を含む全 fresh 系) で削除すべき run-dir 直下のファイル。
 ///
 /// `apply_init_action` は force-init 経路でのみ `default_force_init_cleanup_paths`
 /// を呼ぶ。一方、`CopyInitFromFresh` / `UseExistingFresh` 経路では既に CSV writer の
-/// `cli.resume=false` truncate で派生 CSV は上書きされるが、`final.params` は writer
-/// を持たないため放置すると stale snapshot が残り続ける。これを防ぐため fresh start
-/// 全経路で `final.params` を能動削除する。
-fn remove_stale_final_params_for_fresh_start(run_dir: &Path) -> Result<()> {
-    let final_path = run_dir.join("final.params");
-    match std::fs::remove_file(&final_path) {
-        Ok(_) => Ok(()),
-        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
-        Err(e) => Err(anyhow::Error::new(e)).with_context(|| {
-            format!(
-                "failed to remove stale final.params before fresh start: {}",
-                final_path.display()
-            )
-        }),
+/// `cli.resume=false` truncate で派生 CSV は上書きされるが、`final.params` /
+/// `final.toml` は writer を持たないため放置すると stale snapshot が残り続ける。
+/// これを防ぐため fresh start 全経路で両者を能動削除する。
+fn remove_stale_final_outputs_for_fresh_start(run_dir: &Path) -> Result<()> {
+    for final_path in [run_dir.join("final.params"), final_toml_path(run_dir)] {
+        match std::fs::remove_file(&final_path) {
+            Ok(_) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => {
+                return Err(anyhow::Error::new(e)).with_context(|| {
+                    format!(
+                        "failed to remove stale final output before fresh start: {}",
+                        final_path.display()
+                    )
+                });
+            }
+        }
     }
+    Ok(())
 }
 
 fn default_meta_path(run_dir: &Path) -> PathBuf {
@@ -1893,6 +1903,53 @@ fn write_params(path: &Path, params: &[SpsaParam]) -> Result<()> {
     Ok(())
 }
 
+/// 確定値を TOML パッチに整形する。
+///
+/// active な param (not_used / `--active-only-regex` 不一致 / mapping 未登録を除く) を
+/// `[options]` テーブルに engine 側 USI option 名 (mapping の符号反転込み) で並べる。
+/// 値は engine 送信時と同じ丸め (`option_value_string`) で、int は整数、float は小数。
+/// `final.params` (θ を f64 のまま保持) と違い、そのまま engine 設定や tune.py の
+/// 差分レビューに渡せる配布用の形にする。
+fn render_final_toml(
+    params: &[SpsaParam],
+    active_mask: &[bool],
+    translator: &EngineNameTranslator,
+    completed_pairs: u32,
+) -> Result<String> {
+    let mut options = toml::Table::new();
+    for (p, _) in params.iter().zip(active_mask).filter(|(_, active)| **active) {
+        let (engine_name, engine_value) = translator.translate(&p.name, p.value);
+        let value = if p.is_int {
+            toml::Value::Integer(option_value_string(p, engine_value).parse()?)
+        } else {
+            toml::Value::Float(engine_value)
+        };
+        options.insert(engine_name.to_string(), value);
+    }
+    let mut root = toml::Table::new();
+    root.insert("options".to_string(), toml::Value::Table(options));
+    Ok(format!(
+        "# SPSA final values ({completed_pairs} game pairs). generated from final.params\n{}",
+        toml::to_string(&root)?
+    ))
+}
+
+/// `final.toml` を tempfile + persist で atomic に書き込む。
+fn write_final_toml(path: &Path, content: &str) -> Result<()> {
+    let parent = path.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let mut tmp = tempfile::Builder::new()
+        .prefix(".spsa_final_")
+        .suffix(".tmp")
+        .tempfile_in(parent)
+        .with_context(|| format!("failed to create temp file under {}", parent.display()))?;
+    tmp.as_file_mut()
+        .write_all(content.as_bytes())
+        .with_context(|| format!("failed to write {}", path.display()))?;
+    tmp.persist(path)
+        .with_context(|| format!("failed to atomic-rename to {}", path.display()))?;
+    Ok(())
+}
+
 /// engine に setoption 文字列として送る値の整形。
 ///
 /// is_int=true の場合、`value` は **既に stochastic round 済み整数値の f64 表現**
@@ -2827,7 +2884,7 @@ fn main() -> Result<()> {
                 // 「前回の確定値」が見え続ける (= apply 入力に誤投入される)。fresh 系は
                 // すべてここで能動削除する (force-init の cleanup paths にも入っているが、
                 // CopyInitFromFresh / UseExistingFresh では cleanup paths は呼ばれないため)。
-                remove_stale_final_params_for_fresh_start(&cli.run_dir)?;
+                remove_stale_final_outputs_for_fresh_start(&cli.run_dir)?;
                 let snapshot = InitMetaSnapshot::for_fresh_start(
                     &effective_action,
                     &state_params,
@@ -3046,6 +3103,7 @@ fn main() -> Result<()> {
         go_depth: None,
         go_nodes_black: cli.nodes,
         go_nodes_white: cli.nodes,
+        adjudication: None,
     };
     let tc = if cli.nodes.is_some() {
         // ノード数指定時は時間制御不要だが、タイムアウト検出用に十分大きな値を設定
@@ -3288,6 +3346,12 @@ fn main() -> Result<()> {
     let final_path = cli.run_dir.join("final.params");
     write_params(&final_path, &params)?;
     eprintln!("final params written: {}", final_path.display());
+    let toml_path = final_toml_path(&cli.run_dir);
+    write_final_toml(
+        &toml_path,
+        &render_final_toml(&params, &active_mask, &translator, completed_pairs)?,
+    )?;
+    eprintln!("final TOML patch written: {}", toml_path.display());
 
     Ok(())
 }
@@ -3865,26 +3929,67 @@ mod tests {
         // v4: stats_aggregate.csv は撤去 (multi-seed 機能と共に削除)。
         let dir = Path::new("/tmp/some_run");
         let paths = default_force_init_cleanup_paths(dir);
-        assert_eq!(paths.len(), 3, "exactly 3 derived files (2 CSV + final.params)");
+        assert_eq!(paths.len(), 4, "exactly 4 derived files (2 CSV + final.params/toml)");
         assert!(paths.contains(&dir.join("values.csv")));
         assert!(paths.contains(&dir.join("stats.csv")));
         assert!(paths.contains(&dir.join("final.params")));
+        assert!(paths.contains(&dir.join("final.toml")));
         // state.params と meta.json は含めない (apply_init_action が個別管理)
         assert!(!paths.contains(&dir.join("state.params")));
         assert!(!paths.contains(&dir.join("meta.json")));
     }
 
     #[test]
-    fn remove_stale_final_params_for_fresh_start_handles_missing_file() {
+    fn remove_stale_final_outputs_for_fresh_start_handles_missing_file() {
         let dir = tempfile::tempdir().unwrap();
         // 不在ファイルでも Ok を返すこと (idempotent)
-        remove_stale_final_params_for_fresh_start(dir.path()).unwrap();
-        // 存在するファイルは消えること
+        remove_stale_final_outputs_for_fresh_start(dir.path()).unwrap();
+        // 存在するファイルは消えること (片方だけ存在する場合も含む)
         let final_path = dir.path().join("final.params");
         std::fs::write(&final_path, b"stale").unwrap();
         assert!(final_path.exists());
-        remove_stale_final_params_for_fresh_start(dir.path()).unwrap();
+        remove_stale_final_outputs_for_fresh_start(dir.path()).unwrap();
         assert!(!final_path.exists());
+        let toml_path = final_toml_path(dir.path());
+        std::fs::write(&toml_path, b"stale").unwrap();
+        remove_stale_final_outputs_for_fresh_start(dir.path()).unwrap();
+        assert!(!toml_path.exists());
+    }
+
+    #[test]
+    fn render_final_toml_uses_engine_names_and_rounding() {
+        let dir = tempfile::tempdir().unwrap();
+        let mapping = dir.path().join("mapping.toml");
+        std::fs::write(
+            &mapping,
+            "[[mapping]]\nyo = \"yo_margin\"\nrshogi = \"SPSA_MARGIN\"\nsign_flip = true\n",
+        )
+        .unwrap();
+        let translator = EngineNameTranslator::from_mapping_file(&mapping).unwrap();
+        let param = |name: &str, is_int: bool, value: f64| SpsaParam {
+            name: name.to_string(),
+            type_name: if is_int { "int" } else { "double" }.to_string(),
+            is_int,
+            value,
+            min: -1000.0,
+            max: 1000.0,
+            c_end: 1.0,
+            r_end: 0.002,
+            comment: String::new(),
+            not_used: false,
+        };
+        let params = [
+            param("SPSA_MARGIN", true, 41.6),
+            param("SPSA_UNMAPPED", false, 0.25),
+        ];
+        let active_mask = [true, false];
+
+        let text = render_final_toml(&params, &active_mask, &translator, 128).unwrap();
+        assert!(text.starts_with("# SPSA final values (128 game pairs)"));
+        let table: toml::Table = toml::from_str(&text).unwrap();
+        let options = table["options"].as_table().unwrap();
+        assert_eq!(options.len(), 1);
+        assert_eq!(options["yo_margin"].as_integer(), Some(-42));
     }
 
     /// v3 silent migrate 経路で旧 stats.csv / stats_aggregate.csv が
@@ -0,0 +1,150 @@
+//! 戦術テストスイートの回帰テストツール
+//!
+//! 期待手（`bm`）・回避手（`am`）付きの局面集を固定時間で探索し、正解率と
+//! 正解到達時間を報告する。スイートの形式は `tools::tactics` を参照。
+//!
+//! ```bash
+//! cargo run --release -p tools --bin tactics -- \
+//!   --suite tactics.epd --movetime 1000 --output tactics.json
+//!
+//! # 埋め込みの標準局面集（rshogi_core::testpos）からタグで選ぶ
+//! cargo run --release -p tools --bin tactics -- --builtin mate --movetime 1000
+//!
+//! # 前回の結果と比較
+//! cargo run --release -p tools --bin tactics -- \
+//!   --suite tactics.epd --movetime 1000 --compare baseline.json
+//! ```
+
+use std::path::PathBuf;
+
+use anyhow::{Result, bail};
+use clap::Parser;
+
+use rshogi_core::eval::{MaterialLevel, set_material_level};
+use rshogi_core::nnue::init_nnue;
+use rshogi_core::testpos::Tag;
+use tools::collect_system_info;
+use tools::tactics::{TacticsReport, TacticsSearchOptions, builtin_suite, load_suite, run_case};
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "tactics",
+    version,
+    about = "戦術テストスイートの正解率と正解到達時間を測定する"
+)]
+struct Cli {
+    /// スイートファイル（`<sfen>; bm ...; am ...; id "..."`）
+    #[arg(long, required_unless_present = "builtin", conflicts_with = "builtin")]
+    suite: Option<PathBuf>,
+
+    /// 埋め込みの標準局面集から使うタグ（bench / mate / zugzwang / nyugyoku / drop）
+    #[arg(long)]
+    builtin: Option<String>,
+
+    /// 1 局面あたりの探索時間（ミリ秒）
+    #[arg(long, default_value = "1000")]
+    movetime: u64,
+
+    /// 探索スレッド数
+    #[arg(long, default_value = "1")]
+    threads: usize,
+
+    /// 置換表サイズ（MB）
+    #[arg(long, default_value = "256")]
+    tt_mb: usize,
+
+    /// NNUEファイルのパス（指定時はNNUE評価を使用）
+    #[arg(long)]
+    nnue_file: Option<PathBuf>,
+
+    /// NNUE を使わない場合の MaterialLevel
+    #[arg(long, default_value = "1")]
+    material_level: u8,
+
+    /// 結果 JSON の出力先
+    #[arg(long)]
+    output: Option<PathBuf>,
+
+    /// 比較対象のベースライン結果 JSON
+    #[arg(long)]
+    compare: Option<PathBuf>,
+
+    /// 探索の info 行を表示
+    #[arg(long, short = 'v')]
+    verbose: bool,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+
+    let baseline = cli.compare.as_deref().map(TacticsReport::load_json).transpose()?;
+
+    let (suite, cases) = match (&cli.suite, &cli.builtin) {
+        (Some(path), _) => (path.display().to_string(), load_suite(path)?),
+        (None, Some(name)) => {
+            let tag = Tag::from_name(name).ok_or_else(|| {
+                anyhow::anyhow!("unknown builtin tag '{name}' (expected one of: {})", tag_names())
+            })?;
+            (format!("builtin:{tag}"), builtin_suite(tag))
+        }
+        (None, None) => unreachable!("clap requires --suite or --builtin"),
+    };
+    if cases.is_empty() {
+        bail!("no positions in suite: {suite}");
+    }
+
+    if let Some(nnue_path) = &cli.nnue_file {
+        init_nnue(nnue_path).map_err(|e| {
+            anyhow::anyhow!("Failed to initialize NNUE from '{}': {e}", nnue_path.display())
+        })?;
+    } else {
+        let level = MaterialLevel::from_value(cli.material_level)
+            .ok_or_else(|| anyhow::anyhow!("invalid MaterialLevel: {}", cli.material_level))?;
+        set_material_level(level);
+    }
+
+    let options = TacticsSearchOptions {
+        movetime_ms: cli.movetime,
+        threads: cli.threads,
+        tt_mb: cli.tt_mb,
+    };
+
+    let mut results = Vec::with_capacity(cases.len());
+    for (idx, case) in cases.iter().enumerate() {
+        let result = run_case(case, options, cli.verbose)?;
+        println!(
+            "[{}/{}] {:<24} {} bestmove {}{}",
+            idx + 1,
+            cases.len(),
+            result.id,
+            if result.solved { "OK  " } else { "FAIL" },
+            result.bestmove,
+            result.time_to_solve_ms.map(|ms| format!(" ({ms}ms)")).unwrap_or_default()
+        );
+        results.push(result);
+    }
+
+    let report = TacticsReport {
+        system_info: collect_system_info(),
+        suite,
+        movetime_ms: cli.movetime,
+        threads: cli.threads,
+        results,
+    };
+
+    if let Some(path) = &cli.output {
+        report.save_json(path)?;
+        println!("\nResults saved to: {}", path.display());
+    }
+
+    report.print_summary();
+    if let Some(baseline) = &baseline {
+        report.print_comparison(baseline);
+    }
+
+    Ok(())
+}
+
+fn tag_names() -> String {
+    Tag::ALL.map(Tag::as_str).join(", ")
+}
@@ -63,6 +63,7 @@ use crossbeam_channel as chan;
 use rand::Rng as _;
 use serde::{Deserialize, Serialize};
 
+use rshogi_core::adjudication::AdjudicationConfig;
 use tools::selfplay::game::{GameConfig, MoveEvent, run_game};
 use tools::selfplay::time_control::TimeControl;
 use tools::selfplay::types::{EvalLog, side_label};
@@ -138,6 +139,35 @@ struct Cli {
     #[arg(long, default_value_t = 512)]
     max_moves: u32,
 
+    /// 評価値による裁定を有効にする（両者の評価値が揃ったら勝ち / 引き分けで打ち切る）。
+    /// 以下の --adjudicate-* で条件を変える（未指定は rshogi_core::adjudication の既定値）。
+    #[arg(long)]
+    adjudicate: bool,
+
+    /// 裁定: 勝ちとみなす勝率 (0.5〜1.0)
+    #[arg(long)]
+    adjudicate_win_rate: Option<f64>,
+
+    /// 裁定: 勝ちの裁定に必要な連続手数（各自、0 で勝ちを裁定しない）
+    #[arg(long)]
+    adjudicate_win_moves: Option<u32>,
+
+    /// 裁定: 引き分けとみなす勝率の 50% からの幅
+    #[arg(long)]
+    adjudicate_draw_band: Option<f64>,
+
+    /// 裁定: 引き分けの裁定に必要な連続手数（各自、0 で引き分けを裁定しない）
+    #[arg(long)]
+    adjudicate_draw_moves: Option<u32>,
+
+    /// 裁定: 引き分けを裁定し始める手数
+    #[arg(long)]
+    adjudicate_draw_min_ply: Option<u32>,
+
+    /// 裁定: 評価値を勝率に換算するスケール (cp)
+    #[arg(long)]
+    adjudicate_eval_scale: Option<f64>,
+
     /// Output directory (required)
     #[arg(long)]
     out_dir: PathBuf,
@@ -616,6 +646,7 @@ struct WorkerConfig {
     go_depth: Option<u32>,
     /// エンジン index ごとの固定ノード数。`go_nodes[i]` が `engine_paths[i]` に対応する。
     go_nodes: Vec<Option<u64>>,
+    adjudication: Option<AdjudicationConfig>,
     start_positions: Vec<ParsedPosition>,
 }
 
@@ -638,6 +669,7 @@ fn worker_main(
         binc,
         go_depth,
         go_nodes,
+        adjudication,
         start_positions,
     } = cfg;
     // ワーカー内で全エンジンを起動
@@ -694,6 +726,7 @@ fn worker_main(
             go_depth,
             go_nodes_black: go_nodes[ticket.black_idx],
             go_nodes_white: go_nodes[ticket.white_idx],
+            adjudication,
         };
         let game_id = (ticket.id as u32) + 1;
 
@@ -778,6 +811,7 @@ struct SpawnCtx<'a> {
     go_depth: Option<u32>,
     /// エンジン index ごとの固定ノード数。`go_nodes[i]` が `engine_paths[i]` に対応する。
     go_nodes: &'a [Option<u64>],
+    adjudication: Option<AdjudicationConfig>,
     start_defs: &'a [ParsedPosition],
 }
 
@@ -802,6 +836,7 @@ fn spawn_worker(
         binc: ctx.binc,
         go_depth: ctx.go_depth,
         go_nodes: ctx.go_nodes.to_vec(),
+        adjudication: ctx.adjudication,
         start_positions: ctx
             .start_defs
             .iter()
@@ -822,6 +857,34 @@ fn spawn_worker(
 // メイン
 // ---------------------------------------------------------------------------
 
+impl Cli {
+    /// `--adjudicate` 指定時の裁定条件（未指定の項目は既定値）
+    fn adjudication_config(&self) -> Result<Option<AdjudicationConfig>> {
+        if !self.adjudicate {
+            return Ok(None);
+        }
+        let default = AdjudicationConfig::default();
+        let config = AdjudicationConfig {
+            eval_scale: self.adjudicate_eval_scale.unwrap_or(default.eval_scale),
+            win_rate: self.adjudicate_win_rate.unwrap_or(default.win_rate),
+            win_moves: self.adjudicate_win_moves.unwrap_or(default.win_moves),
+            draw_band: self.adjudicate_draw_band.unwrap_or(default.draw_band),
+            draw_moves: self.adjudicate_draw_moves.unwrap_or(default.draw_moves),
+            draw_min_ply: self.adjudicate_draw_min_ply.unwrap_or(default.draw_min_ply),
+        };
+        if !(0.5..=1.0).contains(&config.win_rate) {
+            bail!("--adjudicate-win-rate must be in 0.5..=1.0");
+        }
+        if !(0.0..0.5).contains(&config.draw_band) {
+            bail!("--adjudicate-draw-band must be in 0.0..0.5");
+        }
+        if config.eval_scale <= 0.0 {
+            bail!("--adjudicate-eval-scale must be positive");
+        }
+        Ok(Some(config))
+    }
+}
+
 fn main() -> Result<()> {
     let cli = Cli::parse();
 
@@ -831,6 +894,7 @@ fn main() -> Result<()> {
     if cli.concurrency == 0 {
         bail!("--concurrency must be at least 1");
     }
+    let adjudication = cli.adjudication_config()?;
 
     // バイナリ存在確認
     for path in &cli.engines {
@@ -1217,6 +1281,7 @@ fn main() -> Result<()> {
         binc: cli.binc,
         go_depth: cli.depth,
         go_nodes: &engine_nodes,
+        adjudication,
         start_defs: &start_defs,
     };
 
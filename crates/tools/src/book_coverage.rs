@@ -0,0 +1,522 @@
+//! 定跡のカバー率分析
+//!
+//! やねうら王形式（`#YANEURAOU-DB2016 1.00`）の定跡ファイルと、自エンジンの対局ログ
+//! （tournament / rshogi-csa-client が出力する analyze_selfplay 互換 JSONL）を突き合わせ、
+//!
+//! - 自エンジンが何手目で定跡を抜けたか
+//! - 定跡にある局面で相手が定跡外の手を指した頻度（定跡に無い相手の変化）
+//! - 自エンジンの手番で定跡に無かった局面（追加候補）
+//!
+//! を集計する。追加候補は出現回数の多い順（同数なら手数の浅い順）に並べ、定跡拡充の
+//! 作業リストとして使う。
+//!
+//! 局面の同一視には SFEN の盤面・手番・持ち駒（手数を除く 3 要素）を使う。
+//! 追加候補は [`evaluate_candidate`] で固定時間探索した最善手・評価値を付け、
+//! [`write_worklist`] でやねうら王形式の定跡断片として書き出せる。
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::thread;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use rshogi_core::position::Position;
+use rshogi_core::search::{LimitsType, Search, SearchInfo};
+
+use crate::utils::SEARCH_STACK_SIZE;
+
+/// 定跡の 1 手
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BookMove {
+    pub usi: String,
+    pub eval: Option<i32>,
+    pub depth: Option<i32>,
+    pub count: Option<u64>,
+}
+
+/// やねうら王形式の定跡（局面ごとの候補手）
+#[derive(Debug, Default)]
+pub struct Book {
+    entries: HashMap<String, Vec<BookMove>>,
+}
+
+impl Book {
+    pub fn load(path: &Path) -> Result<Self> {
+        let file =
+            File::open(path).with_context(|| format!("failed to open book: {}", path.display()))?;
+        Self::parse(BufReader::new(file))
+            .with_context(|| format!("failed to read book: {}", path.display()))
+    }
+
+    /// `sfen <sfen>` 行に続く `<move> <ponder> <eval> <depth> <count>` 行を読む
+    ///
+    /// `#` 始まりの行と空行は無視する。ponder 以降は省略されていてもよい。
+    pub fn parse(reader: impl BufRead) -> Result<Self> {
+        let mut book = Self::default();
+        let mut current: Option<String> = None;
+        for (idx, line) in reader.lines().enumerate() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(sfen) = line.strip_prefix("sfen ") {
+                let key = position_key(sfen)
+                    .with_context(|| format!("line {}: invalid sfen: {sfen}", idx + 1))?;
+                book.entries.entry(key.clone()).or_default();
+                current = Some(key);
+                continue;
+            }
+            let Some(key) = &current else {
+                anyhow::bail!("line {}: move line before any sfen line", idx + 1);
+            };
+            let mut tokens = line.split_whitespace();
+            let usi = tokens.next().unwrap_or_default().to_string();
+            let _ponder = tokens.next();
+            let eval = tokens.next().and_then(|t| t.parse().ok());
+            let depth = tokens.next().and_then(|t| t.parse().ok());
+            let count = tokens.next().and_then(|t| t.parse().ok());
+            book.entries.entry(key.clone()).or_default().push(BookMove {
+                usi,
+                eval,
+                depth,
+                count,
+            });
+        }
+        Ok(book)
+    }
+
+    /// 登録局面数
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// 局面の候補手（`key` は [`position_key`] で作る）
+    pub fn moves(&self, key: &str) -> Option<&[BookMove]> {
+        self.entries.get(key).map(Vec::as_slice)
+    }
+
+    fn has_move(&self, key: &str, usi: &str) -> bool {
+        self.moves(key).is_some_and(|moves| moves.iter().any(|m| m.usi == usi))
+    }
+}
+
+/// 局面の同一視に使うキー（SFEN の盤面・手番・持ち駒。手数は除く）
+pub fn position_key(sfen: &str) -> Option<String> {
+    let mut it = sfen.split_whitespace();
+    let board = it.next()?;
+    let side = it.next()?;
+    let hand = it.next()?;
+    Some(format!("{board} {side} {hand}"))
+}
+
+/// 対局ログの 1 手
+#[derive(Debug, Clone, Deserialize)]
+pub struct GameMove {
+    pub game_id: u32,
+    pub ply: u32,
+    pub sfen_before: String,
+    pub move_usi: String,
+    /// 指したエンジンのラベル
+    pub engine: String,
+}
+
+/// 1 局分の指し手（ply 順）
+#[derive(Debug, Clone)]
+pub struct GameLog {
+    /// `<ファイル名>#<game_id>`
+    pub id: String,
+    pub moves: Vec<GameMove>,
+}
+
+/// analyze_selfplay 互換 JSONL から対局ごとの指し手を読む（move 行以外は無視）
+pub fn load_games(path: &Path) -> Result<Vec<GameLog>> {
+    let file = File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+    let name = path
+        .file_name()
+        .map_or_else(|| path.display().to_string(), |n| n.to_string_lossy().into_owned());
+    let mut games: Vec<GameLog> = Vec::new();
+    let mut index: HashMap<u32, usize> = HashMap::new();
+    for (idx, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if !line.contains("\"type\":\"move\"") && !line.contains("\"type\": \"move\"") {
+            continue;
+        }
+        let mv: GameMove = serde_json::from_str(&line)
+            .with_context(|| format!("{}:{}: invalid move line", path.display(), idx + 1))?;
+        let slot = *index.entry(mv.game_id).or_insert_with(|| {
+            games.push(GameLog {
+                id: format!("{name}#{}", mv.game_id),
+                moves: Vec::new(),
+            });
+            games.len() - 1
+        });
+        games[slot].moves.push(mv);
+    }
+    for game in &mut games {
+        game.moves.sort_by_key(|m| m.ply);
+    }
+    Ok(games)
+}
+
+/// 自エンジンが定跡を抜けた理由
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BookExit {
+    /// 自エンジンの手番で局面が定跡に無かった（追加候補）
+    NotInBook,
+    /// 定跡にある局面で自エンジンが定跡外の手を指した
+    OurDeviation,
+    /// 相手の手番で局面が定跡に無かった（定跡の末端まで進んだ）
+    BookExhausted,
+    /// 対局が定跡内で終わった
+    Never,
+}
+
+/// 相手が定跡外の手を指した局面と手
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpponentNovelty {
+    pub sfen: String,
+    pub move_usi: String,
+    pub ply: u32,
+    pub count: u32,
+}
+
+/// 定跡への追加候補（自エンジンの手番で定跡に無かった局面）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BookCandidate {
+    /// 最初に現れたときの SFEN（手数付き）
+    pub sfen: String,
+    /// 最も浅い出現手数
+    pub ply: u32,
+    /// 出現した対局数
+    pub count: u32,
+    /// 直前の相手の手が定跡外だったか（相手の変化への対応）
+    pub after_novelty: bool,
+    /// エンジンでの評価（`--movetime` 指定時のみ）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bestmove: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub score_cp: Option<i32>,
+}
+
+/// 集計結果
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CoverageReport {
+    pub games: u32,
+    /// 自エンジンが参加していなかった対局数（集計から除外）
+    pub skipped_games: u32,
+    /// 定跡を抜けた手数（自エンジンが最初に定跡外となった ply）
+    pub exit_plies: Vec<u32>,
+    pub exits: HashMap<BookExit, u32>,
+    /// 出現回数の多い順
+    pub novelties: Vec<OpponentNovelty>,
+    /// 出現回数の多い順（同数なら浅い順）
+    pub candidates: Vec<BookCandidate>,
+}
+
+impl CoverageReport {
+    /// 定跡を抜けた手数の平均
+    pub fn mean_exit_ply(&self) -> Option<f64> {
+        (!self.exit_plies.is_empty()).then(|| {
+            self.exit_plies.iter().map(|&p| f64::from(p)).sum::<f64>()
+                / self.exit_plies.len() as f64
+        })
+    }
+
+    /// 定跡を抜けた手数の中央値
+    pub fn median_exit_ply(&self) -> Option<u32> {
+        let mut plies = self.exit_plies.clone();
+        plies.sort_unstable();
+        plies.get(plies.len() / 2).copied()
+    }
+}
+
+/// 対局ログを定跡と突き合わせて集計する
+///
+/// `ours` は自エンジンのラベル（move 行の `engine` と完全一致で判定）。
+pub fn analyze(book: &Book, games: &[GameLog], ours: &str) -> CoverageReport {
+    let mut report = CoverageReport::default();
+    let mut novelties: HashMap<(String, String), OpponentNovelty> = HashMap::new();
+    let mut candidates: HashMap<String, BookCandidate> = HashMap::new();
+
+    for game in games {
+        if !game.moves.iter().any(|m| m.engine == ours) {
+            report.skipped_games += 1;
+            continue;
+        }
+        report.games += 1;
+
+        let mut exit = BookExit::Never;
+        let mut exit_ply = None;
+        let mut after_novelty = false;
+        for mv in &game.moves {
+            let Some(key) = position_key(&mv.sfen_before) else {
+                break;
+            };
+            let in_book = book.moves(&key).is_some();
+            if mv.engine == ours {
+                if in_book && book.has_move(&key, &mv.move_usi) {
+                    after_novelty = false;
+                    continue;
+                }
+                exit = if in_book {
+                    BookExit::OurDeviation
+                } else {
+                    BookExit::NotInBook
+                };
+                exit_ply = Some(mv.ply);
+                if !in_book {
+                    let candidate = candidates.entry(key).or_insert_with(|| BookCandidate {
+                        sfen: mv.sfen_before.clone(),
+                        ply: mv.ply,
+                        count: 0,
+                        after_novelty,
+                        bestmove: None,
+                        score_cp: None,
+                    });
+                    candidate.count += 1;
+                    candidate.after_novelty |= after_novelty;
+                    if mv.ply < candidate.ply {
+                        candidate.ply = mv.ply;
+                        candidate.sfen = mv.sfen_before.clone();
+                    }
+                }
+                break;
+            }
+
+            if !in_book {
+                exit = BookExit::BookExhausted;
+                exit_ply = Some(mv.ply);
+                break;
+            }
+            after_novelty = !book.has_move(&key, &mv.move_usi);
+            if after_novelty {
+                novelties
+                    .entry((key, mv.move_usi.clone()))
+                    .or_insert_with(|| OpponentNovelty {
+                        sfen: mv.sfen_before.clone(),
+                        move_usi: mv.move_usi.clone(),
+                        ply: mv.ply,
+                        count: 0,
+                    })
+                    .count += 1;
+            }
+        }
+
+        *report.exits.entry(exit).or_default() += 1;
+        report.exit_plies.extend(exit_ply);
+    }
+
+    report.novelties = novelties.into_values().collect();
+    report.novelties.sort_by(|a, b| b.count.cmp(&a.count).then(a.ply.cmp(&b.ply)));
+    report.candidates = candidates.into_values().collect();
+    report.candidates.sort_by(|a, b| b.count.cmp(&a.count).then(a.ply.cmp(&b.ply)));
+    report
+}
+
+/// 追加候補の評価に使う探索条件
+#[derive(Debug, Clone, Copy)]
+pub struct CandidateSearchOptions {
+    /// 1 局面あたりの探索時間（ミリ秒）
+    pub movetime_ms: u64,
+    /// 探索スレッド数
+    pub threads: usize,
+    /// 置換表サイズ（MB）
+    pub tt_mb: usize,
+}
+
+/// 追加候補を固定時間で探索し、最善手と評価値（手番側から見た cp）を埋める
+pub fn evaluate_candidate(
+    candidate: &mut BookCandidate,
+    options: CandidateSearchOptions,
+) -> Result<()> {
+    let mut pos = Position::new();
+    pos.set_sfen(&candidate.sfen)
+        .with_context(|| format!("invalid SFEN: {}", candidate.sfen))?;
+
+    let mut limits = LimitsType::default();
+    limits.set_start_time();
+    limits.movetime = options.movetime_ms as i64;
+
+    let (bestmove, score_cp) = thread::Builder::new()
+        .stack_size(SEARCH_STACK_SIZE)
+        .spawn(move || {
+            let mut search = Search::new(options.tt_mb);
+            search.set_num_threads(options.threads);
+            let result = search.go(&mut pos, limits, None::<fn(&SearchInfo)>);
+            (result.best_move.to_usi(), result.score.to_cp())
+        })
+        .context("Failed to spawn search thread")?
+        .join()
+        .map_err(|_| anyhow::anyhow!("search thread panicked"))?;
+
+    candidate.bestmove = Some(bestmove);
+    candidate.score_cp = Some(score_cp);
+    Ok(())
+}
+
+/// 追加候補をやねうら王形式の定跡断片として書き出す
+///
+/// 未評価の候補は指し手が無いため `sfen` 行だけを出力する（手作業で埋める前提）。
+/// 評価済みの候補は `<bestmove> none <score> 0 <出現回数>` の 1 行を付ける。
+pub fn write_worklist(path: &Path, candidates: &[BookCandidate]) -> Result<()> {
+    let file = File::create(path)
+        .with_context(|| format!("failed to create worklist: {}", path.display()))?;
+    let mut writer = BufWriter::new(file);
+    write_worklist_to(&mut writer, candidates)?;
+    writer.flush()?;
+    Ok(())
+}
+
+fn write_worklist_to(writer: &mut impl Write, candidates: &[BookCandidate]) -> Result<()> {
+    writeln!(writer, "#YANEURAOU-DB2016 1.00")?;
+    for candidate in candidates {
+        writeln!(writer, "sfen {}", candidate.sfen)?;
+        if let (Some(bestmove), Some(score)) = (&candidate.bestmove, candidate.score_cp) {
+            writeln!(writer, "{bestmove} none {score} 0 {}", candidate.count)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const START: &str = "lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL b - 1";
+    const AFTER_7G7F: &str = "lnsgkgsnl/1r5b1/ppppppppp/9/9/2P6/PP1PPPPPP/1B5R1/LNSGKGSNL w - 2";
+    const AFTER_3C3D: &str = "lnsgkgsnl/1r5b1/pppppp1pp/6p2/9/2P6/PP1PPPPPP/1B5R1/LNSGKGSNL b - 3";
+    const AFTER_8C8D: &str = "lnsgkgsnl/1r5b1/p1ppppppp/1p7/9/2P6/PP1PPPPPP/1B5R1/LNSGKGSNL b - 3";
+
+    fn book() -> Book {
+        let text = format!(
+            "#YANEURAOU-DB2016 1.00\nsfen {START}\n7g7f 3c3d 30 20 5\n2g2f none 25 20 3\nsfen {AFTER_7G7F}\n3c3d none -30 20 4\nsfen {AFTER_3C3D}\n2g2f\n"
+        );
+        Book::parse(text.as_bytes()).unwrap()
+    }
+
+    fn mv(game_id: u32, ply: u32, sfen: &str, usi: &str, engine: &str) -> GameMove {
+        GameMove {
+            game_id,
+            ply,
+            sfen_before: sfen.to_string(),
+            move_usi: usi.to_string(),
+            engine: engine.to_string(),
+        }
+    }
+
+    fn game(id: u32, moves: Vec<GameMove>) -> GameLog {
+        GameLog {
+            id: format!("test#{id}"),
+            moves,
+        }
+    }
+
+    #[test]
+    fn parse_book_ignores_ply_and_reads_move_fields() {
+        let book = book();
+        assert_eq!(book.len(), 3);
+        let key = position_key(&START.replace(" 1", " 99")).unwrap();
+        let moves = book.moves(&key).unwrap();
+        assert_eq!(moves[0].usi, "7g7f");
+        assert_eq!((moves[0].eval, moves[0].depth, moves[0].count), (Some(30), Some(20), Some(5)));
+        let key = position_key(AFTER_3C3D).unwrap();
+        assert_eq!(book.moves(&key).unwrap()[0].eval, None);
+    }
+
+    #[test]
+    fn analyze_reports_exits_novelties_and_candidates() {
+        let book = book();
+        let games = vec![
+            // 定跡どおり進み、定跡の末端の次（相手の手番）で抜ける
+            game(
+                1,
+                vec![
+                    mv(1, 1, START, "7g7f", "ours"),
+                    mv(1, 2, AFTER_7G7F, "3c3d", "opp"),
+                    mv(1, 3, AFTER_3C3D, "2g2f", "ours"),
+                    mv(1, 4, "9/9/9/9/9/9/9/9/9 w - 4", "8c8d", "opp"),
+                ],
+            ),
+            // 相手の定跡外の手の後、自エンジンの手番で定跡に無い局面
+            game(
+                2,
+                vec![
+                    mv(2, 1, START, "7g7f", "ours"),
+                    mv(2, 2, AFTER_7G7F, "8c8d", "opp"),
+                    mv(2, 3, AFTER_8C8D, "2g2f", "ours"),
+                ],
+            ),
+            game(
+                3,
+                vec![
+                    mv(3, 1, START, "7g7f", "ours"),
+                    mv(3, 2, AFTER_7G7F, "8c8d", "opp"),
+                    mv(3, 3, AFTER_8C8D, "6i7h", "ours"),
+                ],
+            ),
+            // 定跡にある局面で自エンジンが定跡外の手を指す
+            game(4, vec![mv(4, 1, START, "5g5f", "ours")]),
+            // 自エンジンが参加していない
+            game(5, vec![mv(5, 1, START, "7g7f", "other")]),
+        ];
+
+        let report = analyze(&book, &games, "ours");
+        assert_eq!(report.games, 4);
+        assert_eq!(report.skipped_games, 1);
+        assert_eq!(report.exits[&BookExit::BookExhausted], 1);
+        assert_eq!(report.exits[&BookExit::NotInBook], 2);
+        assert_eq!(report.exits[&BookExit::OurDeviation], 1);
+        assert_eq!(report.exit_plies, vec![4, 3, 3, 1]);
+        assert_eq!(report.median_exit_ply(), Some(3));
+
+        assert_eq!(report.novelties.len(), 1);
+        assert_eq!(report.novelties[0].move_usi, "8c8d");
+        assert_eq!(report.novelties[0].count, 2);
+
+        assert_eq!(report.candidates.len(), 1);
+        let candidate = &report.candidates[0];
+        assert_eq!(candidate.sfen, AFTER_8C8D);
+        assert_eq!(candidate.count, 2);
+        assert!(candidate.after_novelty);
+    }
+
+    #[test]
+    fn worklist_round_trips_through_book_parser() {
+        let candidates = vec![
+            BookCandidate {
+                sfen: AFTER_8C8D.to_string(),
+                ply: 3,
+                count: 2,
+                after_novelty: true,
+                bestmove: Some("2g2f".to_string()),
+                score_cp: Some(42),
+            },
+            BookCandidate {
+                sfen: AFTER_3C3D.to_string(),
+                ply: 3,
+                count: 1,
+                after_novelty: false,
+                bestmove: None,
+                score_cp: None,
+            },
+        ];
+        let mut buf = Vec::new();
+        write_worklist_to(&mut buf, &candidates).unwrap();
+
+        let book = Book::parse(buf.as_slice()).unwrap();
+        assert_eq!(book.len(), 2);
+        let moves = book.moves(&position_key(AFTER_8C8D).unwrap()).unwrap();
+        assert_eq!(moves[0].usi, "2g2f");
+        assert_eq!((moves[0].eval, moves[0].count), (Some(42), Some(2)));
+        assert!(book.moves(&position_key(AFTER_3C3D).unwrap()).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,880 @@
+//! 定跡ファイル形式の相互変換・検証・マージ
+//!
+//! 次の 3 形式を読み書きする。
+//!
+//! - **やねうら王形式**（`#YANEURAOU-DB2016 1.00`、`standard_book.db` など）:
+//!   `sfen <sfen>` 行に続けて `<move> <ponder> <eval> <depth> <count>` 行を並べたテキスト
+//! - **Apery 形式**: 16 バイトのエントリ（`key: u64`, `fromToPro: u16`, `count: u16`,
+//!   `score: i32`）をキー昇順に並べたバイナリ。キーは Apery 独自の Zobrist ハッシュ
+//! - **rshogi 形式**: [`rshogi_core::book`] のバイナリ定跡（`RSHOGIBK`）
+//!
+//! 変換は SFEN をキーにした中間表現 [`BookDb`] を経由する。局面の同一視には
+//! SFEN の盤面・手番・持ち駒（手数を除く 3 要素）を使う。
+//!
+//! バイナリ形式はキーがハッシュ値で局面を復元できないため、開始局面（既定は平手）から
+//! 定跡手を辿って到達できる局面だけを読み出す。到達できなかったエントリ数は
+//! [`ReadReport::unreachable`] に数える。
+
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::fmt;
+use std::io::{BufRead, Write};
+use std::path::Path;
+use std::sync::LazyLock;
+
+use anyhow::{Context, Result, bail};
+
+use rshogi_core::book::{Book as NativeBook, BookMove as NativeBookMove};
+use rshogi_core::position::Position;
+use rshogi_core::types::{Color, Move, PieceType, Square};
+
+/// やねうら王形式のヘッダ行
+pub const YANEURAOU_HEADER: &str = "#YANEURAOU-DB2016 1.00";
+
+/// rshogi 形式のマジック
+const NATIVE_MAGIC: &[u8; 8] = b"RSHOGIBK";
+
+/// Apery 形式のエントリのサイズ（バイト）
+const APERY_ENTRY_SIZE: usize = 16;
+
+/// 定跡ファイルの形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BookFormat {
+    /// やねうら王形式（テキスト）
+    Yaneuraou,
+    /// Apery 形式（バイナリ）
+    Apery,
+    /// rshogi 形式（バイナリ）
+    Native,
+}
+
+impl BookFormat {
+    /// ファイル内容と拡張子から形式を推定する
+    ///
+    /// 先頭が `RSHOGIBK` なら rshogi 形式、`#YANEURAOU-DB2016` で始まるか拡張子が
+    /// `.db` ならやねうら王形式、それ以外は Apery 形式とみなす。
+    pub fn detect(path: &Path, bytes: &[u8]) -> Self {
+        if bytes.starts_with(NATIVE_MAGIC) {
+            BookFormat::Native
+        } else if bytes.starts_with(b"#YANEURAOU-DB2016")
+            || path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("db"))
+        {
+            BookFormat::Yaneuraou
+        } else {
+            BookFormat::Apery
+        }
+    }
+
+    /// 出力先の拡張子から形式を推定する（`.db` → やねうら王、`.rbook` → rshogi、それ以外 → Apery）
+    pub fn from_extension(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()).map(str::to_ascii_lowercase) {
+            Some(ext) if ext == "db" => BookFormat::Yaneuraou,
+            Some(ext) if ext == "rbook" => BookFormat::Native,
+            _ => BookFormat::Apery,
+        }
+    }
+}
+
+impl fmt::Display for BookFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            BookFormat::Yaneuraou => "yaneuraou",
+            BookFormat::Apery => "apery",
+            BookFormat::Native => "native",
+        })
+    }
+}
+
+/// 定跡の 1 手
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BookMoveEntry {
+    /// 指し手（USI）
+    pub usi: String,
+    /// 予想応手（USI、無ければ `None`）
+    pub ponder: Option<String>,
+    /// 評価値（手番側から見た値）
+    pub score: i32,
+    /// 評価値を得た探索深さ
+    pub depth: i32,
+    /// 出現回数（選択の重み）
+    pub count: u64,
+}
+
+/// 1 局面の候補手
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BookPosition {
+    /// 手数（SFEN の 4 要素目）
+    pub ply: i32,
+    /// 候補手
+    pub moves: Vec<BookMoveEntry>,
+}
+
+impl BookPosition {
+    /// 候補手を追加する（同じ指し手があれば [`merge_move`] で統合する）
+    fn add_move(&mut self, entry: BookMoveEntry) {
+        match self.moves.iter_mut().find(|m| m.usi == entry.usi) {
+            Some(existing) => merge_move(existing, entry),
+            None => self.moves.push(entry),
+        }
+    }
+}
+
+/// 同じ局面・同じ指し手の統合規則
+///
+/// 出現回数は合算し、評価値・深さ・予想応手は探索深さの深い方（同じなら既存側）を残す。
+fn merge_move(existing: &mut BookMoveEntry, other: BookMoveEntry) {
+    existing.count = existing.count.saturating_add(other.count);
+    if other.depth > existing.depth {
+        existing.score = other.score;
+        existing.depth = other.depth;
+        if other.ponder.is_some() {
+            existing.ponder = other.ponder;
+        }
+    } else if existing.ponder.is_none() {
+        existing.ponder = other.ponder;
+    }
+}
+
+/// マージ・変換時に適用する絞り込み
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BookPolicy {
+    /// この手数を超える局面を捨てる
+    pub max_ply: Option<i32>,
+    /// 出現回数がこれ未満の手を捨てる
+    pub min_count: u64,
+    /// 各局面で評価値が最も高い手だけを残す（同点なら出現回数の多い手）
+    pub best_score: bool,
+}
+
+/// 読み込み時の検証結果
+#[derive(Debug, Clone, Default)]
+pub struct ReadReport {
+    /// 不正な SFEN・非合法手などの問題（行番号付き）
+    pub issues: Vec<String>,
+    /// 開始局面から到達できなかったエントリ数（バイナリ形式のみ）
+    pub unreachable: usize,
+}
+
+/// SFEN をキーにした定跡の中間表現
+///
+/// キーは [`position_key`] の値。`BTreeMap` なので書き出し順はキーの辞書順になる
+/// （やねうら王形式の定跡はこの順で並べる慣習）。
+#[derive(Debug, Clone, Default)]
+pub struct BookDb {
+    positions: BTreeMap<String, BookPosition>,
+}
+
+impl BookDb {
+    /// 登録局面数
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    /// 候補手の総数
+    pub fn num_moves(&self) -> usize {
+        self.positions.values().map(|p| p.moves.len()).sum()
+    }
+
+    /// 局面の候補手（`key` は [`position_key`] で作る）
+    pub fn get(&self, key: &str) -> Option<&BookPosition> {
+        self.positions.get(key)
+    }
+
+    /// 局面と候補手を走査する（キー順）
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &BookPosition)> {
+        self.positions.iter().map(|(k, v)| (k.as_str(), v))
+    }
+
+    /// 候補手を追加する
+    ///
+    /// 同じ局面は手数の小さい方を残し、同じ指し手は出現回数を合算する。
+    pub fn add(&mut self, key: String, ply: i32, entry: BookMoveEntry) {
+        let position = self.positions.entry(key).or_insert_with(|| BookPosition {
+            ply,
+            moves: Vec::new(),
+        });
+        position.ply = position.ply.min(ply);
+        position.add_move(entry);
+    }
+
+    /// 別の定跡を取り込む（重複は [`BookDb::add`] と同じ規則で統合する）
+    pub fn merge(&mut self, other: BookDb) {
+        for (key, position) in other.positions {
+            for entry in position.moves {
+                self.add(key.clone(), position.ply, entry);
+            }
+        }
+    }
+
+    /// 絞り込みを適用する（候補手が無くなった局面は削除する）
+    pub fn apply_policy(&mut self, policy: &BookPolicy) {
+        self.positions.retain(|_, position| {
+            if policy.max_ply.is_some_and(|max| position.ply > max) {
+                return false;
+            }
+            position.moves.retain(|m| m.count >= policy.min_count);
+            if policy.best_score
+                && let Some(best) =
+                    position.moves.iter().max_by_key(|m| (m.score, m.count)).cloned()
+            {
+                position.moves = vec![best];
+            }
+            !position.moves.is_empty()
+        });
+    }
+
+    /// 候補手を出現回数の多い順（同数なら評価値の高い順）に並べる
+    fn sort_moves(&mut self) {
+        for position in self.positions.values_mut() {
+            position.moves.sort_by(|a, b| b.count.cmp(&a.count).then(b.score.cmp(&a.score)));
+        }
+    }
+}
+
+/// 局面の同一視に使うキー（SFEN の盤面・手番・持ち駒。手数は除く）
+pub fn position_key(sfen: &str) -> Option<String> {
+    let mut it = sfen.split_whitespace();
+    let board = it.next()?;
+    let side = it.next()?;
+    let hand = it.next()?;
+    Some(format!("{board} {side} {hand}"))
+}
+
+/// 駒情報を補った合法手（非合法なら `None`）
+fn legal_move(pos: &Position, usi: &str) -> Option<Move> {
+    let mv = pos.to_move(Move::from_usi(usi)?)?;
+    (mv.is_some() && pos.pseudo_legal(mv) && pos.is_legal(mv)).then_some(mv)
+}
+
+// ---------------------------------------------------------------------------
+// 読み書きの入口
+// ---------------------------------------------------------------------------
+
+/// 定跡ファイルを読み込む
+///
+/// `root_sfen` はバイナリ形式で定跡手を辿り始める局面（`None` なら平手）。
+/// `max_ply` はバイナリ形式で辿る手数の上限。
+pub fn read_book(
+    path: &Path,
+    format: Option<BookFormat>,
+    root_sfen: Option<&str>,
+    max_ply: i32,
+) -> Result<(BookDb, ReadReport)> {
+    let bytes =
+        std::fs::read(path).with_context(|| format!("failed to read book: {}", path.display()))?;
+    let format = format.unwrap_or_else(|| BookFormat::detect(path, &bytes));
+    let result = match format {
+        BookFormat::Yaneuraou => read_yaneuraou(bytes.as_slice()),
+        BookFormat::Apery => parse_apery(&bytes)
+            .and_then(|entries| walk_hash_book(&AperyLookup(&entries), root_sfen, max_ply)),
+        BookFormat::Native => NativeBook::from_bytes(&bytes)
+            .map_err(anyhow::Error::from)
+            .and_then(|book| walk_hash_book(&NativeLookup(&book), root_sfen, max_ply)),
+    };
+    result.with_context(|| format!("failed to read {format} book: {}", path.display()))
+}
+
+/// 定跡ファイルを書き出す
+pub fn write_book(path: &Path, format: BookFormat, db: &BookDb) -> Result<()> {
+    let bytes = match format {
+        BookFormat::Yaneuraou => {
+            let mut out = Vec::new();
+            write_yaneuraou(&mut out, db)?;
+            out
+        }
+        BookFormat::Apery => to_apery(db)?,
+        BookFormat::Native => to_native(db)?.to_bytes(),
+    };
+    std::fs::write(path, bytes).with_context(|| format!("failed to write book: {}", path.display()))
+}
+
+// ---------------------------------------------------------------------------
+// やねうら王形式
+// ---------------------------------------------------------------------------
+
+/// やねうら王形式を読む
+///
+/// 不正な SFEN の局面と非合法手は読み飛ばし、[`ReadReport::issues`] に記録する。
+/// `#` 始まりの行と空行は無視する。ponder 以降は省略されていてもよい。
+pub fn read_yaneuraou(reader: impl BufRead) -> Result<(BookDb, ReadReport)> {
+    let mut db = BookDb::default();
+    let mut report = ReadReport::default();
+    let mut pos = Position::new();
+    // 直前の sfen 行（`Some(None)` はその sfen が不正で、続く指し手を読み飛ばす）
+    let mut current: Option<Option<(String, i32)>> = None;
+    for (idx, line) in reader.lines().enumerate() {
+        let line_no = idx + 1;
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some(sfen) = line.strip_prefix("sfen ") {
+            current = Some(match (position_key(sfen), pos.set_sfen(sfen)) {
+                (Some(key), Ok(())) => Some((key, pos.game_ply())),
+                (_, Err(e)) => {
+                    report.issues.push(format!("line {line_no}: invalid sfen: {sfen} ({e})"));
+                    None
+                }
+                (None, Ok(())) => {
+                    report.issues.push(format!("line {line_no}: invalid sfen: {sfen}"));
+                    None
+                }
+            });
+            continue;
+        }
+        let Some(current) = &current else {
+            bail!("line {line_no}: move line before any sfen line");
+        };
+        let Some((key, ply)) = current else {
+            continue;
+        };
+        let mut tokens = line.split_whitespace();
+        let usi = tokens.next().unwrap_or_default();
+        if legal_move(&pos, usi).is_none() {
+            report.issues.push(format!("line {line_no}: illegal move {usi} in sfen {key}"));
+            continue;
+        }
+        let ponder = tokens.next().filter(|t| *t != "none").map(str::to_string);
+        let mut number = || tokens.next().and_then(|t| t.parse::<i64>().ok());
+        let score = number().unwrap_or(0).clamp(i32::MIN as i64, i32::MAX as i64) as i32;
+        let depth = number().unwrap_or(0).clamp(0, i32::MAX as i64) as i32;
+        let count = number().unwrap_or(1).max(0) as u64;
+        db.add(
+            key.clone(),
+            *ply,
+            BookMoveEntry {
+                usi: usi.to_string(),
+                ponder,
+                score,
+                depth,
+                count,
+            },
+        );
+    }
+    Ok((db, report))
+}
+
+/// やねうら王形式で書き出す（局面はキー順、候補手は出現回数の多い順）
+pub fn write_yaneuraou(writer: &mut impl Write, db: &BookDb) -> Result<()> {
+    let mut db = db.clone();
+    db.sort_moves();
+    writeln!(writer, "{YANEURAOU_HEADER}")?;
+    for (key, position) in db.iter() {
+        writeln!(writer, "sfen {key} {}", position.ply)?;
+        for m in &position.moves {
+            writeln!(
+                writer,
+                "{} {} {} {} {}",
+                m.usi,
+                m.ponder.as_deref().unwrap_or("none"),
+                m.score,
+                m.depth,
+                m.count
+            )?;
+        }
+    }
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// バイナリ形式の共通処理
+// ---------------------------------------------------------------------------
+
+/// ハッシュキーで引くバイナリ定跡
+trait HashBook {
+    /// 定跡全体のエントリ数
+    fn num_entries(&self) -> usize;
+    /// 局面の候補手（非合法手は除く）と、その局面のエントリ数
+    fn probe(&self, pos: &Position) -> (Vec<(Move, BookMoveEntry)>, usize);
+}
+
+/// 開始局面から定跡手を幅優先で辿り、到達できた局面を中間表現にする
+fn walk_hash_book(
+    book: &impl HashBook,
+    root_sfen: Option<&str>,
+    max_ply: i32,
+) -> Result<(BookDb, ReadReport)> {
+    let mut root = Position::new();
+    match root_sfen {
+        Some(sfen) => root
+            .set_sfen(sfen)
+            .map_err(|e| anyhow::anyhow!("invalid root sfen: {sfen} ({e})"))?,
+        None => root.set_hirate(),
+    }
+    let mut db = BookDb::default();
+    let mut report = ReadReport::default();
+    let mut visited = HashSet::new();
+    let mut reached = 0usize;
+    let mut queue = VecDeque::from([root]);
+    while let Some(pos) = queue.pop_front() {
+        let sfen = pos.to_sfen();
+        let Some(key) = position_key(&sfen) else {
+            continue;
+        };
+        if !visited.insert(key.clone()) {
+            continue;
+        }
+        let (moves, num_entries) = book.probe(&pos);
+        reached += num_entries;
+        for (mv, entry) in moves {
+            db.add(key.clone(), pos.game_ply(), entry);
+            if pos.game_ply() < max_ply {
+                let mut next = pos.clone();
+                let gives_check = next.gives_check(mv);
+                next.do_move(mv, gives_check);
+                queue.push_back(next);
+            }
+        }
+    }
+    report.unreachable = book.num_entries().saturating_sub(reached);
+    Ok((db, report))
+}
+
+/// 中間表現の各候補手を (局面, 駒情報付きの指し手, 候補手) で走査する
+fn for_each_legal_move(
+    db: &BookDb,
+    mut f: impl FnMut(&Position, Move, &BookMoveEntry),
+) -> Result<()> {
+    let mut pos = Position::new();
+    for (key, position) in db.iter() {
+        let sfen = format!("{key} {}", position.ply.max(1));
+        pos.set_sfen(&sfen).map_err(|e| anyhow::anyhow!("invalid sfen: {sfen} ({e})"))?;
+        for m in &position.moves {
+            let Some(mv) = legal_move(&pos, &m.usi) else {
+                bail!("illegal move {} in sfen {sfen}", m.usi);
+            };
+            f(&pos, mv, m);
+        }
+    }
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// rshogi 形式
+// ---------------------------------------------------------------------------
+
+struct NativeLookup<'a>(&'a NativeBook);
+
+impl HashBook for NativeLookup<'_> {
+    fn num_entries(&self) -> usize {
+        self.0.len()
+    }
+
+    fn probe(&self, pos: &Position) -> (Vec<(Move, BookMoveEntry)>, usize) {
+        let num_entries = self.0.moves_by_key(pos.key()).count();
+        let moves = self
+            .0
+            .probe(pos)
+            .into_iter()
+            .map(|m| {
+                let entry = BookMoveEntry {
+                    usi: m.mv.to_usi(),
+                    ponder: None,
+                    score: m.score as i32,
+                    depth: m.depth as i32,
+                    count: m.weight as u64,
+                };
+                (m.mv, entry)
+            })
+            .collect();
+        (moves, num_entries)
+    }
+}
+
+/// rshogi 形式に変換する（出現回数・評価値・深さは各フィールドの範囲に丸める）
+pub fn to_native(db: &BookDb) -> Result<NativeBook> {
+    let mut entries = Vec::with_capacity(db.num_moves());
+    for_each_legal_move(db, |pos, mv, m| {
+        let book_move = NativeBookMove {
+            mv,
+            weight: m.count.min(u16::MAX as u64) as u16,
+            score: m.score.clamp(i16::MIN as i32, i16::MAX as i32) as i16,
+            depth: m.depth.clamp(0, u16::MAX as i32) as u16,
+        };
+        entries.push((pos.key(), book_move));
+    })?;
+    // キー順に挿入すると末尾への追加になり、挿入位置の移動が起きない
+    entries.sort_by_key(|(key, _)| *key);
+    let mut book = NativeBook::new();
+    for (key, book_move) in entries {
+        book.insert_key(key, book_move);
+    }
+    Ok(book)
+}
+
+// ---------------------------------------------------------------------------
+// Apery 形式
+// ---------------------------------------------------------------------------
+
+/// Apery 形式の 1 エントリ
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AperyEntry {
+    pub key: u64,
+    pub from_to_pro: u16,
+    pub count: u16,
+    pub score: i32,
+}
+
+/// Apery 形式のバイト列を読む（キー順に並べ直す）
+pub fn parse_apery(bytes: &[u8]) -> Result<Vec<AperyEntry>> {
+    if !bytes.len().is_multiple_of(APERY_ENTRY_SIZE) {
+        bail!("apery book size {} is not a multiple of {APERY_ENTRY_SIZE} bytes", bytes.len());
+    }
+    let mut entries: Vec<_> = bytes
+        .chunks_exact(APERY_ENTRY_SIZE)
+        .map(|chunk| AperyEntry {
+            key: u64::from_le_bytes(chunk[0..8].try_into().unwrap()),
+            from_to_pro: u16::from_le_bytes(chunk[8..10].try_into().unwrap()),
+            count: u16::from_le_bytes(chunk[10..12].try_into().unwrap()),
+            score: i32::from_le_bytes(chunk[12..16].try_into().unwrap()),
+        })
+        .collect();
+    entries.sort_by_key(|e| e.key);
+    Ok(entries)
+}
+
+struct AperyLookup<'a>(&'a [AperyEntry]);
+
+impl HashBook for AperyLookup<'_> {
+    fn num_entries(&self) -> usize {
+        self.0.len()
+    }
+
+    fn probe(&self, pos: &Position) -> (Vec<(Move, BookMoveEntry)>, usize) {
+        let key = apery_key(pos);
+        let start = self.0.partition_point(|e| e.key < key);
+        let end = start + self.0[start..].partition_point(|e| e.key == key);
+        let moves = self.0[start..end]
+            .iter()
+            .filter_map(|e| {
+                let mv = apery_move_to_usi(e.from_to_pro)?;
+                let mv = legal_move(pos, &mv)?;
+                let entry = BookMoveEntry {
+                    usi: mv.to_usi(),
+                    ponder: None,
+                    score: e.score,
+                    depth: 0,
+                    count: e.count as u64,
+                };
+                Some((mv, entry))
+            })
+            .collect();
+        (moves, end - start)
+    }
+}
+
+/// Apery 形式のバイト列に変換する（出現回数は `u16` に丸める）
+pub fn to_apery(db: &BookDb) -> Result<Vec<u8>> {
+    let mut entries = Vec::with_capacity(db.num_moves());
+    for_each_legal_move(db, |pos, mv, m| {
+        entries.push(AperyEntry {
+            key: apery_key(pos),
+            from_to_pro: apery_move(mv),
+            count: m.count.min(u16::MAX as u64) as u16,
+            score: m.score,
+        });
+    })?;
+    // 同じキーの中では出現回数の多い順（Apery の定跡選択は先頭から見る）
+    entries.sort_by(|a, b| a.key.cmp(&b.key).then(b.count.cmp(&a.count)));
+    let mut bytes = Vec::with_capacity(entries.len() * APERY_ENTRY_SIZE);
+    for e in entries {
+        bytes.extend_from_slice(&e.key.to_le_bytes());
+        bytes.extend_from_slice(&e.from_to_pro.to_le_bytes());
+        bytes.extend_from_slice(&e.count.to_le_bytes());
+        bytes.extend_from_slice(&e.score.to_le_bytes());
+    }
+    Ok(bytes)
+}
+
+/// Apery の定跡キー用 Zobrist テーブル
+///
+/// Apery は `std::mt19937_64` の既定シードで `ZobPiece[32][81]`, `ZobHand[7][19]`,
+/// `ZobTurn` の順に乱数を引く。駒番号・升番号・持ち駒の並びは rshogi と同じ。
+struct AperyZobrist {
+    piece: Vec<[u64; Square::NUM]>,
+    hand: [[u64; 19]; PieceType::HAND_NUM],
+    turn: u64,
+}
+
+static APERY_ZOBRIST: LazyLock<AperyZobrist> = LazyLock::new(|| {
+    let mut rng = Mt19937_64::new(Mt19937_64::DEFAULT_SEED);
+    let mut piece = vec![[0u64; Square::NUM]; 32];
+    for table in piece.iter_mut() {
+        for z in table.iter_mut() {
+            *z = rng.next_u64();
+        }
+    }
+    let mut hand = [[0u64; 19]; PieceType::HAND_NUM];
+    for table in hand.iter_mut() {
+        for z in table.iter_mut() {
+            *z = rng.next_u64();
+        }
+    }
+    let turn = rng.next_u64();
+    AperyZobrist { piece, hand, turn }
+});
+
+/// Apery の定跡キー
+///
+/// 盤上の駒と手番側の持ち駒（枚数 0 も含む）から作る。後手番なら手番のキーを足す。
+pub fn apery_key(pos: &Position) -> u64 {
+    let zobrist = &*APERY_ZOBRIST;
+    let mut key = 0;
+    for sq in Square::all() {
+        let pc = pos.piece_on(sq);
+        if pc.is_some() {
+            key ^= zobrist.piece[pc.index()][sq.index()];
+        }
+    }
+    let hand = pos.hand(pos.side_to_move());
+    for (i, pt) in PieceType::HAND_PIECES.iter().enumerate() {
+        key ^= zobrist.hand[i][hand.count(*pt) as usize];
+    }
+    if pos.side_to_move() == Color::White {
+        key ^= zobrist.turn;
+    }
+    key
+}
+
+/// Apery の `fromToPro`（to: bit 0-6, from: bit 7-13, 成り: bit 14。打ちは from = 80 + 駒種）
+pub fn apery_move(mv: Move) -> u16 {
+    let to = mv.to().raw() as u16;
+    let from = if mv.is_drop() {
+        80 + mv.drop_piece_type() as u16
+    } else {
+        mv.from().raw() as u16
+    };
+    let promote = if mv.is_promote() { 1 << 14 } else { 0 };
+    to | (from << 7) | promote
+}
+
+/// Apery の `fromToPro` を USI に変換する（範囲外なら `None`）
+pub fn apery_move_to_usi(from_to_pro: u16) -> Option<String> {
+    let to = Square::from_u8((from_to_pro & 0x7f) as u8)?;
+    let from = (from_to_pro >> 7) & 0x7f;
+    let promote = from_to_pro & (1 << 14) != 0;
+    if from >= Square::NUM as u16 {
+        const DROP_CHARS: [char; 7] = ['P', 'L', 'N', 'S', 'B', 'R', 'G'];
+        let c = DROP_CHARS.get((from - Square::NUM as u16) as usize)?;
+        return Some(format!("{c}*{}", to.to_usi()));
+    }
+    let from = Square::from_u8(from as u8)?;
+    Some(format!("{}{}{}", from.to_usi(), to.to_usi(), if promote { "+" } else { "" }))
+}
+
+/// `std::mt19937_64` 互換の乱数生成器（Apery の Zobrist テーブル再現用）
+struct Mt19937_64 {
+    state: [u64; Self::N],
+    index: usize,
+}
+
+impl Mt19937_64 {
+    const N: usize = 312;
+    const M: usize = 156;
+    const MATRIX_A: u64 = 0xB502_6F5A_A966_19E9;
+    const UPPER_MASK: u64 = 0xFFFF_FFFF_8000_0000;
+    const LOWER_MASK: u64 = 0x7FFF_FFFF;
+    /// `std::mt19937_64::default_seed`
+    const DEFAULT_SEED: u64 = 5489;
+
+    fn new(seed: u64) -> Self {
+        let mut state = [0u64; Self::N];
+        state[0] = seed;
+        for i in 1..Self::N {
+            let prev = state[i - 1];
+            state[i] = 6_364_136_223_846_793_005u64
+                .wrapping_mul(prev ^ (prev >> 62))
+                .wrapping_add(i as u64);
+        }
+        Self {
+            state,
+            index: Self::N,
+        }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        if self.index >= Self::N {
+            for i in 0..Self::N {
+                let x = (self.state[i] & Self::UPPER_MASK)
+                    | (self.state[(i + 1) % Self::N] & Self::LOWER_MASK);
+                let mut x_a = x >> 1;
+                if x & 1 != 0 {
+                    x_a ^= Self::MATRIX_A;
+                }
+                self.state[i] = self.state[(i + Self::M) % Self::N] ^ x_a;
+            }
+            self.index = 0;
+        }
+        let mut x = self.state[self.index];
+        self.index += 1;
+        x ^= (x >> 29) & 0x5555_5555_5555_5555;
+        x ^= (x << 17) & 0x71D6_7FFF_EDA6_0000;
+        x ^= (x << 37) & 0xFFF7_EEE0_0000_0000;
+        x ^= x >> 43;
+        x
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const START: &str = "lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL b - 1";
+    const AFTER_7G7F: &str = "lnsgkgsnl/1r5b1/ppppppppp/9/9/2P6/PP1PPPPPP/1B5R1/LNSGKGSNL w - 2";
+
+    fn sample_db() -> BookDb {
+        let text = format!(
+            "{YANEURAOU_HEADER}\n\
+             sfen {START}\n\
+             7g7f 3c3d 50 20 10\n\
+             2g2f 8c8d 30 18 5\n\
+             sfen {AFTER_7G7F}\n\
+             3c3d none -40 20 7\n"
+        );
+        let (db, report) = read_yaneuraou(text.as_bytes()).unwrap();
+        assert!(report.issues.is_empty(), "{:?}", report.issues);
+        db
+    }
+
+    #[test]
+    fn mt19937_64_matches_std() {
+        // C++ 規格が定める default_seed での 10000 個目の値
+        let mut rng = Mt19937_64::new(Mt19937_64::DEFAULT_SEED);
+        let mut last = 0;
+        for _ in 0..10000 {
+            last = rng.next_u64();
+        }
+        assert_eq!(last, 9_981_545_732_273_789_042);
+    }
+
+    #[test]
+    fn yaneuraou_reports_invalid_sfen_and_illegal_moves() {
+        let text = format!(
+            "{YANEURAOU_HEADER}\n\
+             sfen {START}\n\
+             7g7f none 0 0 1\n\
+             7g7e none 0 0 1\n\
+             sfen not-a-board b - 1\n\
+             7g7f none 0 0 1\n"
+        );
+        let (db, report) = read_yaneuraou(text.as_bytes()).unwrap();
+        assert_eq!(db.num_moves(), 1);
+        assert_eq!(report.issues.len(), 2, "{:?}", report.issues);
+        assert!(report.issues[0].starts_with("line 4: illegal move 7g7e"));
+        assert!(report.issues[1].starts_with("line 5: invalid sfen"));
+    }
+
+    #[test]
+    fn yaneuraou_round_trip() {
+        let db = sample_db();
+        let mut out = Vec::new();
+        write_yaneuraou(&mut out, &db).unwrap();
+        let (again, _) = read_yaneuraou(out.as_slice()).unwrap();
+        assert_eq!(again.positions, db.positions);
+    }
+
+    #[test]
+    fn merge_sums_counts_and_keeps_deeper_score() {
+        let mut db = sample_db();
+        let key = position_key(START).unwrap();
+        let mut other = BookDb::default();
+        other.add(
+            key.clone(),
+            1,
+            BookMoveEntry {
+                usi: "7g7f".to_string(),
+                ponder: None,
+                score: 80,
+                depth: 30,
+                count: 3,
+            },
+        );
+        db.merge(other);
+        let m = &db.get(&key).unwrap().moves[0];
+        assert_eq!((m.count, m.score, m.depth), (13, 80, 30));
+        assert_eq!(m.ponder.as_deref(), Some("3c3d"));
+    }
+
+    #[test]
+    fn policy_filters_positions_and_moves() {
+        let key = position_key(START).unwrap();
+
+        let mut db = sample_db();
+        db.apply_policy(&BookPolicy {
+            max_ply: Some(1),
+            ..BookPolicy::default()
+        });
+        assert_eq!(db.len(), 1);
+
+        let mut db = sample_db();
+        db.apply_policy(&BookPolicy {
+            min_count: 6,
+            ..BookPolicy::default()
+        });
+        assert_eq!(db.num_moves(), 2);
+        assert_eq!(db.get(&key).unwrap().moves[0].usi, "7g7f");
+
+        let mut db = sample_db();
+        db.apply_policy(&BookPolicy {
+            best_score: true,
+            ..BookPolicy::default()
+        });
+        assert_eq!(db.get(&key).unwrap().moves.len(), 1);
+        assert_eq!(db.get(&key).unwrap().moves[0].score, 50);
+    }
+
+    #[test]
+    fn native_round_trip_from_root() {
+        let db = sample_db();
+        let book = to_native(&db).unwrap();
+        assert_eq!(book.len(), 3);
+        let (again, report) = walk_hash_book(&NativeLookup(&book), None, 256).unwrap();
+        assert_eq!(report.unreachable, 0);
+        assert_eq!(again.num_moves(), 3);
+        let m = &again.get(&position_key(START).unwrap()).unwrap().moves;
+        assert!(m.iter().any(|m| m.usi == "7g7f" && m.count == 10 && m.score == 50));
+    }
+
+    #[test]
+    fn apery_round_trip_from_root() {
+        let db = sample_db();
+        let bytes = to_apery(&db).unwrap();
+        assert_eq!(bytes.len(), 3 * APERY_ENTRY_SIZE);
+        let entries = parse_apery(&bytes).unwrap();
+        let (again, report) = walk_hash_book(&AperyLookup(&entries), None, 256).unwrap();
+        assert_eq!(report.unreachable, 0);
+        assert_eq!(again.num_moves(), 3);
+
+        // 辿る手数を 1 に制限すると 2 手目の局面は読まれない
+        let (shallow, report) = walk_hash_book(&AperyLookup(&entries), None, 1).unwrap();
+        assert_eq!(shallow.len(), 1);
+        assert_eq!(report.unreachable, 1);
+    }
+
+    #[test]
+    fn apery_move_encoding() {
+        let mut pos = Position::new();
+        pos.set_sfen("4k4/9/9/9/9/9/9/9/4K4 b RP 1").unwrap();
+        for usi in ["5i5h", "P*5e", "R*1a"] {
+            let mv = legal_move(&pos, usi).unwrap();
+            assert_eq!(apery_move_to_usi(apery_move(mv)).as_deref(), Some(usi));
+        }
+        pos.set_sfen("4k4/9/9/9/9/9/4P4/9/4K4 b - 1").unwrap();
+        // 5g5f は成れないので bit 14 は立たない
+        let mv = legal_move(&pos, "5g5f").unwrap();
+        assert_eq!(apery_move(mv) & (1 << 14), 0);
+    }
+
+    #[test]
+    fn detect_format() {
+        let path = Path::new("book.bin");
+        assert_eq!(BookFormat::detect(path, b"RSHOGIBK\x01"), BookFormat::Native);
+        assert_eq!(BookFormat::detect(path, b"#YANEURAOU-DB2016 1.00\n"), BookFormat::Yaneuraou);
+        assert_eq!(BookFormat::detect(Path::new("x.db"), b""), BookFormat::Yaneuraou);
+        assert_eq!(BookFormat::detect(path, &[0u8; 16]), BookFormat::Apery);
+    }
+}
@@ -0,0 +1,220 @@
+//! カリキュラム学習のスケジュール
+//!
+//! 教師データを局面の段階（`Position::phase` による序盤・中盤・終盤）または評価値の
+//! 絶対値の範囲でステージに分け、エポックごとにどのステージを使うかを決める。
+//!
+//! ## スケジュールの書式
+//!
+//! ```text
+//! phase:open->mid->end        # 序盤 → 中盤 → 終盤
+//! cp:0-300->0-1000->0-        # |評価値| の範囲（上限省略で無制限）
+//! ```
+//!
+//! 区切りは `->` のほか `→` も使える。ステージはエポック数に対して均等に割り当てる
+//! （3 ステージ・9 エポックなら 3 エポックずつ）。
+
+use anyhow::{Result, anyhow, bail};
+use rshogi_core::position::{PhaseStage, Position};
+use serde::{Deserialize, Serialize};
+
+use crate::packed_sfen::{PackedSfenValue, unpack_sfen};
+
+/// 局面の段階
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Phase {
+    Open,
+    Mid,
+    End,
+}
+
+impl From<PhaseStage> for Phase {
+    fn from(stage: PhaseStage) -> Self {
+        match stage {
+            PhaseStage::Opening => Self::Open,
+            PhaseStage::Middlegame => Self::Mid,
+            PhaseStage::Endgame => Self::End,
+        }
+    }
+}
+
+/// レコードの局面の段階（`Position::phase` で判定する）
+pub fn phase_of(record: &PackedSfenValue) -> Result<Phase> {
+    let sfen = unpack_sfen(&record.sfen).map_err(|e| anyhow!("failed to unpack sfen: {e}"))?;
+    let mut pos = Position::new();
+    pos.set_sfen(&sfen).map_err(|e| anyhow!("invalid sfen '{sfen}': {e}"))?;
+    Ok(pos.phase().stage().into())
+}
+
+/// カリキュラムの 1 ステージ
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum Stage {
+    /// 局面の段階
+    Phase { phase: Phase },
+    /// |評価値| が `min..max`（`max` が `None` なら上限なし）
+    Score { min: u16, max: Option<u16> },
+}
+
+impl Stage {
+    /// レコードがこのステージに含まれるか
+    ///
+    /// `phase` はレコードの局面の段階（`phase_of`）。段階のステージでのみ参照する。
+    pub fn contains(&self, record: &PackedSfenValue, phase: Option<Phase>) -> bool {
+        match *self {
+            Self::Phase { phase: stage } => phase == Some(stage),
+            Self::Score { min, max } => {
+                let abs = record.score.unsigned_abs();
+                abs >= min && max.is_none_or(|max| abs < max)
+            }
+        }
+    }
+
+    /// 表示用の名前（`open` / `cp0-300` など）
+    pub fn name(&self) -> String {
+        match self {
+            Self::Phase { phase: Phase::Open } => "open".to_string(),
+            Self::Phase { phase: Phase::Mid } => "mid".to_string(),
+            Self::Phase { phase: Phase::End } => "end".to_string(),
+            Self::Score {
+                min,
+                max: Some(max),
+            } => format!("cp{min}-{max}"),
+            Self::Score { min, max: None } => format!("cp{min}-"),
+        }
+    }
+}
+
+/// カリキュラムのスケジュール
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CurriculumSchedule {
+    /// 指定された文字列（記録用）
+    pub spec: String,
+    /// ステージ（適用順）
+    pub stages: Vec<Stage>,
+}
+
+impl CurriculumSchedule {
+    /// `phase:open->mid->end` / `cp:0-300->0-` 形式を解析する
+    pub fn parse(spec: &str) -> Result<Self> {
+        let Some((axis, rest)) = spec.split_once(':') else {
+            bail!("curriculum must be '<phase|cp>:<stage>-><stage>...': {spec}");
+        };
+        let stages = rest
+            .replace('→', "->")
+            .split("->")
+            .map(|s| parse_stage(axis.trim(), s.trim()))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self {
+            spec: spec.to_string(),
+            stages,
+        })
+    }
+
+    /// 局面の段階で分けるスケジュールか
+    pub fn uses_phase(&self) -> bool {
+        self.stages.iter().any(|stage| matches!(stage, Stage::Phase { .. }))
+    }
+
+    /// エポック（0 始まり）で使うステージの番号
+    pub fn stage_index(&self, epoch: usize, epochs: usize) -> usize {
+        (epoch * self.stages.len() / epochs.max(1)).min(self.stages.len() - 1)
+    }
+}
+
+fn parse_stage(axis: &str, s: &str) -> Result<Stage> {
+    match axis {
+        "phase" => {
+            let phase = match s {
+                "open" => Phase::Open,
+                "mid" => Phase::Mid,
+                "end" => Phase::End,
+                _ => bail!("unknown phase '{s}' (expected open, mid or end)"),
+            };
+            Ok(Stage::Phase { phase })
+        }
+        "cp" => {
+            let Some((min, max)) = s.split_once('-') else {
+                bail!("score range must be '<min>-<max>' or '<min>-': {s}");
+            };
+            let min: u16 = min.parse().map_err(|_| anyhow!("invalid score range: {s}"))?;
+            let max = match max {
+                "" => None,
+                v => Some(v.parse().map_err(|_| anyhow!("invalid score range: {s}"))?),
+            };
+            if max.is_some_and(|max| max <= min) {
+                bail!("empty score range: {s}");
+            }
+            Ok(Stage::Score { min, max })
+        }
+        _ => bail!("unknown curriculum axis '{axis}' (expected phase or cp)"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::packed_sfen::pack_position;
+    use rshogi_core::position::SFEN_HIRATE;
+
+    fn record(sfen: &str, score: i16) -> PackedSfenValue {
+        let mut pos = Position::new();
+        pos.set_sfen(sfen).unwrap();
+        PackedSfenValue {
+            sfen: pack_position(&pos),
+            score,
+            move16: 0,
+            game_ply: 1,
+            game_result: 0,
+            padding: 0,
+        }
+    }
+
+    #[test]
+    fn parse_phase_schedule() {
+        let schedule = CurriculumSchedule::parse("phase:open→mid->end").unwrap();
+        assert_eq!(
+            schedule.stages.iter().map(Stage::name).collect::<Vec<_>>(),
+            vec!["open", "mid", "end"]
+        );
+
+        assert!(schedule.uses_phase());
+
+        let opening = record(SFEN_HIRATE, 0);
+        let middlegame =
+            record("lnsgkgsnl/1r7/pppppppp1/8p/9/2P6/PP1PPPPPP/7R1/LNSGKGSNL b Bb 1", 0);
+        assert_eq!(phase_of(&opening).unwrap(), Phase::Open);
+        assert_eq!(phase_of(&middlegame).unwrap(), Phase::Mid);
+        assert!(schedule.stages[0].contains(&opening, Some(Phase::Open)));
+        assert!(schedule.stages[1].contains(&middlegame, Some(Phase::Mid)));
+        assert!(!schedule.stages[2].contains(&middlegame, Some(Phase::Mid)));
+        assert!(!schedule.stages[2].contains(&middlegame, None));
+    }
+
+    #[test]
+    fn parse_score_schedule() {
+        let schedule = CurriculumSchedule::parse("cp:0-300->300-").unwrap();
+        assert!(!schedule.uses_phase());
+        assert!(schedule.stages[0].contains(&record(SFEN_HIRATE, -299), None));
+        assert!(!schedule.stages[0].contains(&record(SFEN_HIRATE, 300), None));
+        assert!(schedule.stages[1].contains(&record(SFEN_HIRATE, i16::MIN), None));
+        assert_eq!(schedule.stages[1].name(), "cp300-");
+    }
+
+    #[test]
+    fn parse_rejects_invalid_schedule() {
+        assert!(CurriculumSchedule::parse("open->mid").is_err());
+        assert!(CurriculumSchedule::parse("phase:open->late").is_err());
+        assert!(CurriculumSchedule::parse("cp:300-100").is_err());
+        assert!(CurriculumSchedule::parse("ply:0-10").is_err());
+    }
+
+    #[test]
+    fn stages_are_spread_over_epochs() {
+        let schedule = CurriculumSchedule::parse("phase:open->mid->end").unwrap();
+        let stages: Vec<usize> = (0..8).map(|e| schedule.stage_index(e, 8)).collect();
+        assert_eq!(stages, vec![0, 0, 0, 1, 1, 1, 2, 2]);
+        // エポック数がステージ数より少なければ後半のステージは使われない
+        assert_eq!(schedule.stage_index(1, 2), 1);
+    }
+}
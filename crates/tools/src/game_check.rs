@@ -0,0 +1,442 @@
+//! 生成データの検証（局面のルール違反・自己対局ログの整合性）
+//!
+//! - [`validate_position`]: 1 局面のルール違反（玉の不在・駒数超過・行き所のない駒・二歩・
+//!   手番でない側への王手）。`validate_psv` が PSV の各レコードに使う。
+//! - [`check_game`]: tournament JSONL の 1 局分の `move` 行を開始局面から指し直し、
+//!   指し手の連続性・合法性、各局面のルール違反、結果行との食い違いを対局単位で返す。
+//!   `jsonl_to_psv` は診断のある対局を学習データに入れない。
+//!
+//! 指し直しは最初の `sfen_before` から行うため、パス権ルールの対局（`pass` を含む）は
+//! パスの手前までしか検証しない。
+
+use std::fmt;
+
+use rshogi_core::bitboard::{FILE_BB, RANK_BB};
+use rshogi_core::position::Position;
+use rshogi_core::types::{Color, Move, PieceType, RepetitionState};
+
+use crate::selfplay::GameOutcome;
+
+/// 局面のルール違反を検出し、最初に見つかった理由を `(分類, 説明)` で返す
+pub fn validate_position(pos: &Position) -> Option<(&'static str, String)> {
+    // 1. 玉の存在チェック
+    for color in [Color::Black, Color::White] {
+        if pos.pieces_pt(PieceType::King) & pos.pieces_c(color) == Default::default() {
+            let side = if color == Color::Black {
+                "先手"
+            } else {
+                "後手"
+            };
+            return Some(("no_king", format!("{side}の玉がない")));
+        }
+    }
+
+    // 2. 駒数超過チェック
+    let piece_limits: &[(PieceType, Option<PieceType>, u32, &str)] = &[
+        (PieceType::Pawn, Some(PieceType::ProPawn), 18, "歩"),
+        (PieceType::Lance, Some(PieceType::ProLance), 4, "香"),
+        (PieceType::Knight, Some(PieceType::ProKnight), 4, "桂"),
+        (PieceType::Silver, Some(PieceType::ProSilver), 4, "銀"),
+        (PieceType::Gold, None, 4, "金"),
+        (PieceType::Bishop, Some(PieceType::Horse), 2, "角"),
+        (PieceType::Rook, Some(PieceType::Dragon), 2, "飛"),
+        (PieceType::King, None, 2, "玉"),
+    ];
+
+    for &(raw_pt, promoted_pt, max, name) in piece_limits {
+        let mut total = pos.pieces_pt(raw_pt).count();
+        if let Some(ppt) = promoted_pt {
+            total += pos.pieces_pt(ppt).count();
+        }
+        if raw_pt != PieceType::King {
+            for color in [Color::Black, Color::White] {
+                total += pos.hand(color).count(raw_pt);
+            }
+        }
+        if total > max {
+            return Some(("piece_overflow", format!("{name}が{total}枚（上限{max}枚）")));
+        }
+    }
+
+    // 3. 行き所のない駒チェック
+    for color in [Color::Black, Color::White] {
+        let color_bb = pos.pieces_c(color);
+        let side = if color == Color::Black {
+            "先手"
+        } else {
+            "後手"
+        };
+
+        let dead_rank1 = if color == Color::Black {
+            RANK_BB[0]
+        } else {
+            RANK_BB[8]
+        };
+
+        if (pos.pieces_pt(PieceType::Pawn) & color_bb & dead_rank1).count() > 0 {
+            return Some(("dead_piece", format!("{side}の歩が行き所のない段にある")));
+        }
+        if (pos.pieces_pt(PieceType::Lance) & color_bb & dead_rank1).count() > 0 {
+            return Some(("dead_piece", format!("{side}の香が行き所のない段にある")));
+        }
+
+        let dead_rank12 = if color == Color::Black {
+            RANK_BB[0] | RANK_BB[1]
+        } else {
+            RANK_BB[7] | RANK_BB[8]
+        };
+
+        if (pos.pieces_pt(PieceType::Knight) & color_bb & dead_rank12).count() > 0 {
+            return Some(("dead_piece", format!("{side}の桂が行き所のない段にある")));
+        }
+    }
+
+    // 4. 二歩チェック
+    for color in [Color::Black, Color::White] {
+        let pawns = pos.pieces_pt(PieceType::Pawn) & pos.pieces_c(color);
+        let side = if color == Color::Black {
+            "先手"
+        } else {
+            "後手"
+        };
+
+        for (file_idx, file_bb) in FILE_BB.iter().enumerate() {
+            if (pawns & *file_bb).count() >= 2 {
+                return Some(("double_pawn", format!("{side}の二歩（{}筋）", file_idx + 1)));
+            }
+        }
+    }
+
+    // 5. 相手の玉に王手がかかった状態
+    let them = !pos.side_to_move();
+    let their_king = pos.king_square(them);
+    let checkers = pos.attackers_to_c(their_king, pos.side_to_move());
+    if checkers.count() > 0 {
+        return Some(("enemy_in_check", "手番でない側の玉に王手がかかっている".to_string()));
+    }
+
+    None
+}
+
+/// 自己対局ログの 1 手（`move` 行）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoggedMove {
+    pub sfen_before: String,
+    pub move_usi: String,
+}
+
+/// 終局を表す擬似指し手（`resign` など）か
+pub fn is_terminal_move(move_usi: &str) -> bool {
+    matches!(move_usi, "resign" | "win" | "timeout" | "illegal" | "none")
+}
+
+/// 診断の種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagnosticKind {
+    /// `sfen_before` が読めない、またはルール違反の局面（[`validate_position`]）
+    InvalidPosition,
+    /// `sfen_before` が直前の局面に直前の手を指した局面と一致しない
+    Discontinuity,
+    /// 非合法手・USI として読めない手
+    IllegalMove,
+    /// 終局の擬似指し手の後に手が続く
+    MovesAfterTerminal,
+    /// 結果行の勝敗が終局の擬似指し手と食い違う
+    ResultMismatch,
+    /// 千日手（連続王手の千日手を含む）が成立しているのに結果がそれと食い違う
+    RepetitionMislabel,
+    /// 結果行の手数が `move` 行の数と一致しない
+    PlyCountMismatch,
+}
+
+impl DiagnosticKind {
+    /// 集計・ログ用の名前
+    pub fn label(self) -> &'static str {
+        match self {
+            DiagnosticKind::InvalidPosition => "invalid_position",
+            DiagnosticKind::Discontinuity => "discontinuity",
+            DiagnosticKind::IllegalMove => "illegal_move",
+            DiagnosticKind::MovesAfterTerminal => "moves_after_terminal",
+            DiagnosticKind::ResultMismatch => "result_mismatch",
+            DiagnosticKind::RepetitionMislabel => "repetition_mislabel",
+            DiagnosticKind::PlyCountMismatch => "ply_count_mismatch",
+        }
+    }
+}
+
+/// 対局単位の診断
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// 対局内の手数（1 始まり、対局全体に関するものは 0）
+    pub ply: u32,
+    pub kind: DiagnosticKind,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ply={} {}: {}", self.ply, self.kind.label(), self.message)
+    }
+}
+
+/// 1 局分のログを検証し、見つかった問題を返す（問題がなければ空）
+///
+/// - `moves`: 対局の `move` 行（手順どおり）
+/// - `outcome`: 結果行の勝敗（`InProgress` なら勝敗の突き合わせをしない）
+/// - `plies`: 結果行の手数（無ければ `None`）
+///
+/// 非合法手・局面の不連続が見つかったら、それ以降の指し直しはしない。
+pub fn check_game(
+    moves: &[LoggedMove],
+    outcome: GameOutcome,
+    plies: Option<u32>,
+) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut push = |ply: usize, kind: DiagnosticKind, message: String| {
+        diagnostics.push(Diagnostic {
+            ply: ply as u32,
+            kind,
+            message,
+        });
+    };
+
+    if let Some(plies) = plies
+        && plies as usize != moves.len()
+    {
+        push(
+            0,
+            DiagnosticKind::PlyCountMismatch,
+            format!("結果行の手数 {plies} に対して move 行が {} 行", moves.len()),
+        );
+    }
+
+    let mut pos = Position::new();
+    // 指し直した局面の SFEN（次の `sfen_before` と突き合わせる）
+    let mut expected_sfen: Option<String> = None;
+    // 最初に成立した千日手の手数と、そこで決まるはずだった勝敗
+    let mut repetition: Option<(usize, GameOutcome)> = None;
+    let mut terminal: Option<(usize, GameOutcome)> = None;
+
+    for (idx, logged) in moves.iter().enumerate() {
+        let ply = idx + 1;
+        if let Some((terminal_ply, _)) = terminal {
+            push(
+                ply,
+                DiagnosticKind::MovesAfterTerminal,
+                format!(
+                    "{terminal_ply} 手目の {} の後に手が続く",
+                    moves[terminal_ply - 1].move_usi
+                ),
+            );
+            break;
+        }
+
+        match &expected_sfen {
+            Some(expected) if *expected != logged.sfen_before => {
+                push(
+                    ply,
+                    DiagnosticKind::Discontinuity,
+                    format!("sfen_before {} (指し直した局面 {expected})", logged.sfen_before),
+                );
+                break;
+            }
+            Some(_) => {}
+            None => {
+                if let Err(e) = pos.set_sfen(&logged.sfen_before) {
+                    push(
+                        ply,
+                        DiagnosticKind::InvalidPosition,
+                        format!("SFEN パース失敗: {e} | {}", logged.sfen_before),
+                    );
+                    break;
+                }
+            }
+        }
+        if let Some((_, reason)) = validate_position(&pos) {
+            push(
+                ply,
+                DiagnosticKind::InvalidPosition,
+                format!("{reason} | {}", logged.sfen_before),
+            );
+            break;
+        }
+
+        let us = pos.side_to_move();
+        if is_terminal_move(&logged.move_usi) {
+            // 宣言勝ち（win）以外は指した側の負け
+            let winner = if logged.move_usi == "win" { us } else { !us };
+            terminal = Some((ply, win_of(winner)));
+            continue;
+        }
+        let Some(mv) = Move::from_usi(&logged.move_usi) else {
+            push(
+                ply,
+                DiagnosticKind::IllegalMove,
+                format!("指し手を読めない: {}", logged.move_usi),
+            );
+            break;
+        };
+        if mv.is_pass() {
+            // パス権は SFEN に含まれないため、ここから先は指し直せない
+            break;
+        }
+        let Some(mv) = pos.to_move(mv).filter(|mv| pos.is_legal_move(*mv)) else {
+            push(
+                ply,
+                DiagnosticKind::IllegalMove,
+                format!("非合法手 {} | {}", logged.move_usi, logged.sfen_before),
+            );
+            break;
+        };
+        let gives_check = pos.gives_check(mv);
+        pos.do_move(mv, gives_check);
+        expected_sfen = Some(pos.to_sfen());
+
+        if repetition.is_none() && pos.state().repetition < 0 {
+            let side = pos.side_to_move();
+            repetition = match pos.state().repetition_type {
+                RepetitionState::Draw => Some((ply, GameOutcome::Draw)),
+                // 連続王手の千日手は手番側から見た勝敗
+                RepetitionState::Win => Some((ply, win_of(side))),
+                RepetitionState::Lose => Some((ply, win_of(!side))),
+                _ => None,
+            };
+        }
+    }
+
+    if outcome == GameOutcome::InProgress {
+        return diagnostics;
+    }
+    if let Some((ply, expected)) = repetition {
+        if expected != outcome {
+            push(
+                ply,
+                DiagnosticKind::RepetitionMislabel,
+                format!(
+                    "{ply} 手目で千日手（{}）が成立しているが結果は {}",
+                    expected.label(),
+                    outcome.label()
+                ),
+            );
+        }
+    } else if let Some((ply, expected)) = terminal
+        && expected != outcome
+    {
+        push(
+            ply,
+            DiagnosticKind::ResultMismatch,
+            format!(
+                "{} の結果は {} のはずが {}",
+                moves[ply - 1].move_usi,
+                expected.label(),
+                outcome.label()
+            ),
+        );
+    }
+    diagnostics
+}
+
+fn win_of(color: Color) -> GameOutcome {
+    if color == Color::Black {
+        GameOutcome::BlackWin
+    } else {
+        GameOutcome::WhiteWin
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HIRATE: &str = "lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL b - 1";
+
+    /// `HIRATE` から `moves` を指した対局ログを作る（`sfen_before` は指し直して埋める）
+    fn game(moves: &[&str]) -> Vec<LoggedMove> {
+        let mut pos = Position::new();
+        pos.set_sfen(HIRATE).unwrap();
+        let mut logged = Vec::new();
+        for usi in moves {
+            logged.push(LoggedMove {
+                sfen_before: pos.to_sfen(),
+                move_usi: usi.to_string(),
+            });
+            if let Some(mv) = Move::from_usi(usi).and_then(|mv| pos.to_move(mv))
+                && pos.is_legal_move(mv)
+            {
+                let gives_check = pos.gives_check(mv);
+                pos.do_move(mv, gives_check);
+            }
+        }
+        logged
+    }
+
+    fn kinds(diagnostics: &[Diagnostic]) -> Vec<DiagnosticKind> {
+        diagnostics.iter().map(|d| d.kind).collect()
+    }
+
+    #[test]
+    fn clean_game_has_no_diagnostics() {
+        let moves = game(&["7g7f", "3c3d", "8h2b+", "resign"]);
+        assert!(check_game(&moves, GameOutcome::BlackWin, Some(4)).is_empty());
+    }
+
+    #[test]
+    fn detects_illegal_move_and_discontinuity() {
+        let moves = game(&["7g7f", "3c3d", "7f7e", "8b8h"]);
+        assert_eq!(
+            kinds(&check_game(&moves, GameOutcome::InProgress, None)),
+            [DiagnosticKind::IllegalMove]
+        );
+
+        let mut moves = game(&["7g7f", "3c3d", "2g2f"]);
+        moves[2].sfen_before = HIRATE.to_string();
+        assert_eq!(
+            kinds(&check_game(&moves, GameOutcome::InProgress, None)),
+            [DiagnosticKind::Discontinuity]
+        );
+    }
+
+    #[test]
+    fn detects_impossible_hand_counts() {
+        // 先手の持ち駒に飛車が 2 枚あり、盤上の飛車と合わせて 4 枚
+        let moves = vec![LoggedMove {
+            sfen_before: "lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL b 2R 1"
+                .to_string(),
+            move_usi: "7g7f".to_string(),
+        }];
+        let diagnostics = check_game(&moves, GameOutcome::InProgress, None);
+        assert_eq!(kinds(&diagnostics), [DiagnosticKind::InvalidPosition]);
+    }
+
+    #[test]
+    fn detects_result_and_ply_count_mismatch() {
+        let moves = game(&["7g7f", "resign"]);
+        assert_eq!(
+            kinds(&check_game(&moves, GameOutcome::WhiteWin, Some(3))),
+            [
+                DiagnosticKind::PlyCountMismatch,
+                DiagnosticKind::ResultMismatch
+            ]
+        );
+
+        let moves = game(&["7g7f", "resign", "3c3d"]);
+        assert_eq!(
+            kinds(&check_game(&moves, GameOutcome::BlackWin, None)),
+            [DiagnosticKind::MovesAfterTerminal]
+        );
+    }
+
+    #[test]
+    fn detects_repetition_mislabel() {
+        // 飛車の往復で同一局面が 4 回現れる（12 手目で千日手成立）
+        let cycle = ["2h3h", "8b7b", "3h2h", "7b8b"];
+        let mut usi: Vec<&str> = cycle.iter().cycle().take(12).copied().collect();
+        usi.push("resign");
+        let moves = game(&usi);
+
+        let diagnostics = check_game(&moves, GameOutcome::WhiteWin, None);
+        assert_eq!(kinds(&diagnostics), [DiagnosticKind::RepetitionMislabel]);
+        assert_eq!(diagnostics[0].ply, 12);
+        assert!(check_game(&moves, GameOutcome::Draw, None).is_empty());
+    }
+}
@@ -0,0 +1,265 @@
+//! 置換表サイズ別のベンチマーク（`benchmark --hash-profile`）
+//!
+//! 同じ条件のベンチマークを置換表サイズを変えて繰り返し、サイズごとの NPS・TTD・
+//! 置換表の使われ方を 1 つの表にまとめる。配備先（VPS・デスクトップ・wasm）ごとの
+//! 既定の `USI_Hash` を決めるための資料にする。
+//!
+//! ヒット率・置換率・メモリ帯域は置換表の probe / 書き込みの回数から求めるため、
+//! 内部APIモードを `--features tt-stats` でビルドしたときだけ得られる。メモリ帯域は
+//! 置換表への 1 回のアクセスを 1 キャッシュライン（[`CACHE_LINE_BYTES`]）の読み書きとみなした
+//! 概算で、評価関数など置換表以外のメモリアクセスは含まない。
+
+use std::fs::File;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::report::{BenchmarkReport, ThreadResult};
+use crate::system::SystemInfo;
+use crate::utils::format_number;
+
+/// 置換表への 1 回のアクセスで読み書きするとみなすバイト数
+pub const CACHE_LINE_BYTES: u64 = 64;
+
+/// 置換表サイズ・スレッド数ごとの集計
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HashProfileRow {
+    /// 置換表サイズ（MB）
+    pub tt_mb: u32,
+    /// スレッド数
+    pub threads: usize,
+    /// 平均 NPS
+    pub average_nps: u64,
+    /// time-to-depth の比較に使う深さ（全局面が到達した最大深さ）
+    pub ttd_depth: i32,
+    /// 全局面の `ttd_depth` 到達時間の合計（ミリ秒）
+    pub total_time_to_depth_ms: u64,
+    /// 平均置換表使用率（パーミル）
+    pub average_hashfull: f64,
+    /// probe のヒット率
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hit_rate: Option<f64>,
+    /// 書き込みのうち別の局面を追い出した割合
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replacement_rate: Option<f64>,
+    /// 1 ノードあたりの probe 回数
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub probes_per_node: Option<f64>,
+    /// 置換表のメモリ帯域の概算（MB/s）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tt_bandwidth_mb_s: Option<f64>,
+}
+
+impl HashProfileRow {
+    /// 1 つの置換表サイズ・スレッド数の結果から作る
+    ///
+    /// 置換表の回数は全局面に記録があるときだけ集計する。
+    pub fn from_thread_result(tt_mb: u32, result: &ThreadResult) -> Self {
+        let agg = result.aggregate();
+        let counters: Option<Vec<(u64, u64, f64, f64)>> = result
+            .results
+            .iter()
+            .map(|r| {
+                let tt = r.tt.as_ref()?;
+                Some((tt.probes?, tt.writes?, tt.hit_rate?, tt.replacement_rate?))
+            })
+            .collect();
+        let counters = counters.filter(|c| !c.is_empty());
+
+        let (mut hit_rate, mut replacement_rate) = (None, None);
+        let (mut probes_per_node, mut tt_bandwidth_mb_s) = (None, None);
+        if let Some(counters) = counters {
+            let probes: u64 = counters.iter().map(|c| c.0).sum();
+            let writes: u64 = counters.iter().map(|c| c.1).sum();
+            let hits: f64 = counters.iter().map(|c| c.0 as f64 * c.2).sum();
+            let replaced: f64 = counters.iter().map(|c| c.1 as f64 * c.3).sum();
+            hit_rate = Some(ratio(hits, probes as f64));
+            replacement_rate = Some(ratio(replaced, writes as f64));
+            probes_per_node = Some(ratio(probes as f64, agg.total_nodes as f64));
+            if agg.total_time_ms > 0 {
+                let bytes = (probes + writes) * CACHE_LINE_BYTES;
+                tt_bandwidth_mb_s =
+                    Some(bytes as f64 / (1024.0 * 1024.0) / (agg.total_time_ms as f64 / 1000.0));
+            }
+        }
+
+        Self {
+            tt_mb,
+            threads: result.threads,
+            average_nps: agg.average_nps,
+            ttd_depth: agg.ttd_depth,
+            total_time_to_depth_ms: agg.total_time_to_depth_ms,
+            average_hashfull: agg.average_hashfull,
+            hit_rate,
+            replacement_rate,
+            probes_per_node,
+            tt_bandwidth_mb_s,
+        }
+    }
+}
+
+fn ratio(part: f64, whole: f64) -> f64 {
+    if whole == 0.0 { 0.0 } else { part / whole }
+}
+
+/// 置換表サイズ別のベンチマーク結果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HashProfile {
+    /// システム情報
+    pub system_info: SystemInfo,
+    /// エンジン名
+    pub engine_name: String,
+    /// 置換表サイズ・スレッド数ごとの集計（サイズの小さい順）
+    pub rows: Vec<HashProfileRow>,
+}
+
+impl HashProfile {
+    /// 置換表サイズ別のレポートからまとめる
+    pub fn new(engine_name: &str, reports: &[(u32, BenchmarkReport)]) -> Self {
+        let mut rows: Vec<HashProfileRow> = reports
+            .iter()
+            .flat_map(|(tt_mb, report)| {
+                report.results.iter().map(|r| HashProfileRow::from_thread_result(*tt_mb, r))
+            })
+            .collect();
+        rows.sort_by_key(|row| (row.threads, row.tt_mb));
+        let system_info = reports
+            .first()
+            .map(|(_, report)| report.system_info.clone())
+            .unwrap_or_else(crate::system::collect_system_info);
+        Self {
+            system_info,
+            engine_name: engine_name.to_string(),
+            rows,
+        }
+    }
+
+    /// 表を出力する
+    pub fn print_table(&self) {
+        println!("\n=== Hash Size Profile ===");
+        println!("Engine: {}", self.engine_name);
+        println!("CPU: {}\n", self.system_info.cpu_model);
+        println!(
+            "{:<8} {:<10} {:<13} {:<13} {:<9} {:<8} {:<9} {:<11} {:<10}",
+            "Threads",
+            "Hash",
+            "Avg NPS",
+            "TTD",
+            "Hashfull",
+            "Hit",
+            "Replaced",
+            "Probe/node",
+            "TT MB/s"
+        );
+        println!("{}", "-".repeat(99));
+        let percent = |v: Option<f64>| v.map_or("-".to_string(), |v| format!("{:.1}%", v * 100.0));
+        for row in &self.rows {
+            let ttd = if row.ttd_depth > 0 {
+                format!("{}@{}ms", row.ttd_depth, row.total_time_to_depth_ms)
+            } else {
+                "-".to_string()
+            };
+            println!(
+                "{:<8} {:<10} {:<13} {:<13} {:<9.0} {:<8} {:<9} {:<11} {:<10}",
+                row.threads,
+                format!("{}MB", row.tt_mb),
+                format_number(row.average_nps),
+                ttd,
+                row.average_hashfull,
+                percent(row.hit_rate),
+                percent(row.replacement_rate),
+                row.probes_per_node.map_or("-".to_string(), |v| format!("{v:.2}")),
+                row.tt_bandwidth_mb_s.map_or("-".to_string(), |v| format!("{v:.0}")),
+            );
+        }
+        if self.rows.iter().all(|row| row.hit_rate.is_none()) {
+            println!(
+                "Hit / Replaced / Probe/node / TT MB/s: 内部APIモードを --features tt-stats でビルドしたときのみ"
+            );
+        }
+        println!();
+    }
+
+    /// JSON形式で保存
+    pub fn save_json(&self, path: &Path) -> Result<()> {
+        let file = File::create(path)
+            .with_context(|| format!("Failed to create JSON file: {}", path.display()))?;
+        serde_json::to_writer_pretty(file, self).with_context(|| "Failed to write JSON")?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::{BenchResult, TtReport};
+
+    fn bench_result(nodes: u64, time_ms: u64, tt: Option<TtReport>) -> BenchResult {
+        BenchResult {
+            sfen: "startpos".to_string(),
+            depth: 2,
+            nodes,
+            time_ms,
+            nps: 0,
+            hashfull: 100,
+            bestmove: "7g7f".to_string(),
+            is_warmup: None,
+            search_run_index: None,
+            time_to_depth_ms: vec![1, 5],
+            tt,
+        }
+    }
+
+    fn tt_report(probes: u64, writes: u64, hit_rate: f64) -> TtReport {
+        TtReport {
+            occupancy_permille: 0,
+            current_permille: 0,
+            average_depth8: 0.0,
+            exact_ratio: 0.0,
+            hit_rate: Some(hit_rate),
+            replacement_rate: Some(0.5),
+            probes: Some(probes),
+            writes: Some(writes),
+        }
+    }
+
+    #[test]
+    fn test_row_weights_counters_by_probes() {
+        let result = ThreadResult {
+            threads: 1,
+            results: vec![
+                bench_result(1000, 500, Some(tt_report(3000, 1000, 0.5))),
+                bench_result(1000, 500, Some(tt_report(1000, 1000, 0.1))),
+            ],
+        };
+        let row = HashProfileRow::from_thread_result(64, &result);
+        assert_eq!(row.tt_mb, 64);
+        assert_eq!(row.average_nps, 2000);
+        assert_eq!(row.ttd_depth, 2);
+        assert_eq!(row.total_time_to_depth_ms, 10);
+        assert!((row.hit_rate.unwrap() - 0.4).abs() < 1e-9);
+        assert_eq!(row.replacement_rate, Some(0.5));
+        assert_eq!(row.probes_per_node, Some(2.0));
+        // (4000 + 2000) 回 × 64 バイト / 1 秒
+        let expected = 6000.0 * 64.0 / (1024.0 * 1024.0);
+        assert!((row.tt_bandwidth_mb_s.unwrap() - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_row_without_counters_leaves_tt_columns_empty() {
+        let result = ThreadResult {
+            threads: 2,
+            results: vec![
+                bench_result(1000, 500, Some(tt_report(3000, 1000, 0.5))),
+                bench_result(1000, 500, None),
+            ],
+        };
+        let row = HashProfileRow::from_thread_result(16, &result);
+        assert_eq!(row.hit_rate, None);
+        assert_eq!(row.tt_bandwidth_mb_s, None);
+
+        let json = serde_json::to_string(&row).unwrap();
+        assert!(!json.contains("hit_rate"), "{json}");
+    }
+}
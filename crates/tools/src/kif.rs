@@ -227,8 +227,8 @@ fn export_game_to_kif<W: Write>(
             break;
         }
         let side = pos.side_to_move();
-        let mv = Move::from_usi(&entry.move_usi)
-            .ok_or_else(|| anyhow!("invalid move in log: {}", entry.move_usi))?;
+        let mv = Move::parse_usi(&entry.move_usi)
+            .map_err(|e| anyhow!("invalid move in log '{}': {e}", entry.move_usi))?;
         if !is_legal_with_pass(&pos, mv) {
             bail!("illegal move '{}' in log for game {}", entry.move_usi, game_id);
         }
@@ -41,16 +41,24 @@
 //! ```
 
 pub mod aobazero_features;
+pub mod bench_history;
 pub mod bench_nnue_eval_tool;
+pub mod book_coverage;
+pub mod book_formats;
 pub mod common;
 pub mod config;
+pub mod curriculum;
 pub mod dlshogi_features;
 pub mod eval_sfens_tool;
+pub mod game_check;
+pub mod hash_profile;
 pub mod kif;
+pub mod manifest;
 #[cfg(feature = "dlshogi-onnx")]
 pub mod onnx_value;
 pub mod packed_sfen;
 pub mod positions;
+pub mod promote;
 pub mod qsearch_pv;
 #[cfg(feature = "kifu-player")]
 pub mod replay;
@@ -60,12 +68,16 @@ pub mod selfplay;
 pub mod sprt;
 pub mod spsa_param_mapping;
 pub mod system;
+pub mod tactics;
 pub mod teacher_labeler;
 mod utils;
 pub mod verify_nnue_accumulator_tool;
+pub mod win_rate;
 
 // 公開API
 pub use config::{BenchmarkConfig, EvalConfig, LimitType};
-pub use positions::{DEFAULT_POSITIONS, load_positions};
-pub use report::{Aggregate, BenchResult, BenchmarkReport, EvalInfo, ThreadResult};
+pub use positions::{default_positions, load_positions};
+pub use report::{
+    Aggregate, BenchResult, BenchmarkReport, EvalInfo, ThreadComparison, ThreadResult,
+};
 pub use system::{SystemInfo, collect_system_info};
@@ -0,0 +1,383 @@
+//! データパイプラインのマニフェスト（成果物の来歴記録）
+//!
+//! 教師局面のシャード・前処理済みキャッシュ・教師ネット・学習済みネット・定跡といった
+//! パイプラインの成果物ごとに、作成したコマンドライン・入力ファイルのハッシュ・
+//! ツールをビルドした git コミットを 1 行 1 レコードの JSONL に追記していく。
+//! 成果物も入力もファイル内容の sha256 で同定するので、ファイルを移動・改名した後でも
+//! 記録を辿れる。
+//!
+//! `gensfen` / `jsonl_to_psv` / `psv_to_hcpe3` / `prep_hcpe` / `book` は `--manifest <path>` を
+//! 指定すると出力を追記する。外部の trainer で学習したネットなどは
+//! `manifest record` で追記し、`manifest lineage <file>` で成果物から入力を辿ると
+//! 「このネットはどのデータで学習したか」を後から確認できる。
+//!
+//! 追記は 1 レコード 1 回の `write` で行うので、複数のツールが同じマニフェストへ
+//! 同時に書き込んでも行が混ざらない。
+
+use std::collections::HashSet;
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::str::FromStr;
+
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// マニフェストのレコード形式のバージョン
+pub const MANIFEST_VERSION: u32 = 1;
+
+/// 成果物の種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ArtifactKind {
+    /// 教師局面のシャード（PSV / hcpe / pack など）
+    DatasetShard,
+    /// 学習用に前処理したキャッシュ（重複除去・シャッフル済みの分割ファイルなど）
+    Cache,
+    /// 教師局面のラベル付けに使ったネット
+    TeacherNet,
+    /// 学習したネット
+    TrainedNet,
+    /// 定跡
+    Book,
+    /// その他
+    Other,
+}
+
+impl ArtifactKind {
+    const ALL: [ArtifactKind; 6] = [
+        ArtifactKind::DatasetShard,
+        ArtifactKind::Cache,
+        ArtifactKind::TeacherNet,
+        ArtifactKind::TrainedNet,
+        ArtifactKind::Book,
+        ArtifactKind::Other,
+    ];
+
+    fn as_str(self) -> &'static str {
+        match self {
+            ArtifactKind::DatasetShard => "dataset-shard",
+            ArtifactKind::Cache => "cache",
+            ArtifactKind::TeacherNet => "teacher-net",
+            ArtifactKind::TrainedNet => "trained-net",
+            ArtifactKind::Book => "book",
+            ArtifactKind::Other => "other",
+        }
+    }
+}
+
+impl fmt::Display for ArtifactKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ArtifactKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        Self::ALL.into_iter().find(|kind| kind.as_str() == s).ok_or_else(|| {
+            let names: Vec<&str> = Self::ALL.iter().map(|kind| kind.as_str()).collect();
+            format!("unknown artifact kind '{s}' (expected one of {})", names.join(", "))
+        })
+    }
+}
+
+/// 成果物を作るのに使った入力ファイル
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArtifactInput {
+    /// 記録時のパス
+    pub path: String,
+    /// ファイル内容の sha256（16 進小文字）
+    pub sha256: String,
+}
+
+/// マニフェストの 1 レコード（成果物 1 つ）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArtifactRecord {
+    /// レコード形式のバージョン
+    pub manifest_version: u32,
+    pub kind: ArtifactKind,
+    /// 記録時のパス
+    pub path: String,
+    /// ファイル内容の sha256（16 進小文字）
+    pub sha256: String,
+    /// ファイルサイズ（バイト）
+    pub size: u64,
+    /// 記録した時刻（RFC 3339）
+    pub created_at: String,
+    /// 成果物を作ったコマンドライン
+    pub command: Vec<String>,
+    /// ツールをビルドしたソースの git コミット（取得できなければ `None`）
+    pub git_commit: Option<String>,
+    /// コミットされていない変更があったか
+    #[serde(default)]
+    pub git_dirty: bool,
+    pub inputs: Vec<ArtifactInput>,
+}
+
+impl ArtifactRecord {
+    /// `output` とその入力のハッシュを計算してレコードを作る
+    ///
+    /// `command` が空なら、このプロセスのコマンドラインを記録する。入力は全体を読んで
+    /// ハッシュするので、大きな教師データではその分の時間がかかる。
+    pub fn describe(
+        kind: ArtifactKind,
+        output: &Path,
+        inputs: &[PathBuf],
+        command: Vec<String>,
+    ) -> Result<Self> {
+        let size = std::fs::metadata(output)
+            .with_context(|| format!("Failed to stat {}", output.display()))?
+            .len();
+        let inputs = inputs
+            .iter()
+            .map(|input| {
+                Ok(ArtifactInput {
+                    path: input.display().to_string(),
+                    sha256: sha256_file(input)?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let command = if command.is_empty() {
+            std::env::args().collect()
+        } else {
+            command
+        };
+        let (git_commit, git_dirty) = match git_revision() {
+            Some((commit, dirty)) => (Some(commit), dirty),
+            None => (None, false),
+        };
+        Ok(Self {
+            manifest_version: MANIFEST_VERSION,
+            kind,
+            path: output.display().to_string(),
+            sha256: sha256_file(output)?,
+            size,
+            created_at: chrono::Local::now().to_rfc3339(),
+            command,
+            git_commit,
+            git_dirty,
+            inputs,
+        })
+    }
+}
+
+/// マニフェスト（読み込んだ全レコード）
+#[derive(Debug, Clone, Default)]
+pub struct Manifest {
+    pub records: Vec<ArtifactRecord>,
+}
+
+impl Manifest {
+    /// マニフェストを読み込む（ファイルがなければ空）
+    pub fn load(path: &Path) -> Result<Self> {
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e).with_context(|| format!("Failed to open {}", path.display())),
+        };
+        let mut records = Vec::new();
+        for (idx, line) in BufReader::new(file).lines().enumerate() {
+            let line = line.with_context(|| format!("Failed to read {}", path.display()))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let record: ArtifactRecord = serde_json::from_str(&line)
+                .with_context(|| format!("{}:{}: invalid record", path.display(), idx + 1))?;
+            if record.manifest_version > MANIFEST_VERSION {
+                bail!(
+                    "{}:{}: manifest_version {} is newer than supported {MANIFEST_VERSION}",
+                    path.display(),
+                    idx + 1,
+                    record.manifest_version
+                );
+            }
+            records.push(record);
+        }
+        Ok(Self { records })
+    }
+
+    /// レコードをマニフェストファイルの末尾に追記する
+    pub fn append(path: &Path, record: &ArtifactRecord) -> Result<()> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        let mut line = serde_json::to_vec(record)?;
+        line.push(b'\n');
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Failed to open {}", path.display()))?;
+        file.write_all(&line)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// 内容が `sha256` の成果物の最新のレコード
+    pub fn find(&self, sha256: &str) -> Option<&ArtifactRecord> {
+        self.records.iter().rev().find(|record| record.sha256 == sha256)
+    }
+
+    /// `sha256` の成果物から入力を辿った来歴（成果物自身を先頭に、幅優先で重複なし）
+    ///
+    /// マニフェストに記録がない入力は辿らない（呼び出し側で `inputs` と突き合わせる）。
+    pub fn lineage(&self, sha256: &str) -> Vec<&ArtifactRecord> {
+        let mut seen = HashSet::new();
+        let mut queue = vec![sha256.to_string()];
+        let mut lineage = Vec::new();
+        while !queue.is_empty() {
+            let mut next = Vec::new();
+            for hash in queue {
+                if !seen.insert(hash.clone()) {
+                    continue;
+                }
+                if let Some(record) = self.find(&hash) {
+                    next.extend(record.inputs.iter().map(|input| input.sha256.clone()));
+                    lineage.push(record);
+                }
+            }
+            queue = next;
+        }
+        lineage
+    }
+}
+
+/// `output` の来歴を `manifest` に追記する（`--manifest` を受け付けるツール用）
+pub fn record_artifact(
+    manifest: &Path,
+    kind: ArtifactKind,
+    output: &Path,
+    inputs: &[PathBuf],
+) -> Result<()> {
+    let record = ArtifactRecord::describe(kind, output, inputs, Vec::new())?;
+    Manifest::append(manifest, &record)?;
+    log::info!("manifest: recorded {} {} ({})", record.kind, record.path, &record.sha256[..12]);
+    Ok(())
+}
+
+/// ファイル内容の sha256（16 進小文字）
+pub fn sha256_file(path: &Path) -> Result<String> {
+    let mut reader = BufReader::new(
+        File::open(path)
+            .with_context(|| format!("Failed to open {} for hashing", path.display()))?,
+    );
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; 1 << 20];
+    loop {
+        let n = reader
+            .read(&mut buf)
+            .with_context(|| format!("Failed to read {} for hashing", path.display()))?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hasher.finalize().iter().map(|b| format!("{b:02x}")).collect())
+}
+
+/// ツールのソースツリーの git コミットと未コミットの変更の有無
+///
+/// 実行時にビルド元のチェックアウト（`CARGO_MANIFEST_DIR`）で `git` を呼ぶ。
+/// git がない・チェックアウトが消えている場合は `None`。
+pub fn git_revision() -> Option<(String, bool)> {
+    let dir = env!("CARGO_MANIFEST_DIR");
+    let head = Command::new("git").args(["rev-parse", "HEAD"]).current_dir(dir).output().ok()?;
+    if !head.status.success() {
+        return None;
+    }
+    let commit = String::from_utf8_lossy(&head.stdout).trim().to_string();
+    let dirty = Command::new("git")
+        .args(["status", "--porcelain", "--untracked-files=no"])
+        .current_dir(dir)
+        .output()
+        .is_ok_and(|out| out.status.success() && !out.stdout.is_empty());
+    Some((commit, dirty))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(path: &Path, bytes: &[u8]) {
+        std::fs::write(path, bytes).unwrap();
+    }
+
+    #[test]
+    fn kind_round_trips_through_str() {
+        for kind in ArtifactKind::ALL {
+            assert_eq!(kind.to_string().parse::<ArtifactKind>(), Ok(kind));
+        }
+        assert!("net".parse::<ArtifactKind>().is_err());
+    }
+
+    #[test]
+    fn appended_records_trace_lineage() {
+        let dir = tempfile::tempdir().unwrap();
+        let manifest = dir.path().join("manifest.jsonl");
+        let shard_a = dir.path().join("a.psv");
+        let shard_b = dir.path().join("b.psv");
+        let teacher = dir.path().join("teacher.bin");
+        let net = dir.path().join("nn.bin");
+        write(&teacher, b"teacher");
+        write(&shard_a, b"shard a");
+        write(&shard_b, b"shard b");
+        write(&net, b"net");
+
+        record_artifact(
+            &manifest,
+            ArtifactKind::DatasetShard,
+            &shard_a,
+            std::slice::from_ref(&teacher),
+        )
+        .unwrap();
+        record_artifact(
+            &manifest,
+            ArtifactKind::DatasetShard,
+            &shard_b,
+            std::slice::from_ref(&teacher),
+        )
+        .unwrap();
+        let trained = ArtifactRecord::describe(
+            ArtifactKind::TrainedNet,
+            &net,
+            &[shard_a.clone(), shard_b.clone()],
+            vec!["train".to_string(), "--lr".to_string(), "1e-4".to_string()],
+        )
+        .unwrap();
+        Manifest::append(&manifest, &trained).unwrap();
+
+        let loaded = Manifest::load(&manifest).unwrap();
+        assert_eq!(loaded.records.len(), 3);
+        assert_eq!(loaded.records[2], trained);
+        assert_eq!(trained.size, 3);
+        assert_eq!(trained.command[0], "train");
+
+        // ネット → 2 つのシャード。教師ネットは記録がないので辿らない
+        let lineage = loaded.lineage(&sha256_file(&net).unwrap());
+        let paths: Vec<&str> = lineage.iter().map(|r| r.path.as_str()).collect();
+        assert_eq!(paths.len(), 3);
+        assert_eq!(paths[0], net.display().to_string());
+        assert!(paths.contains(&shard_a.display().to_string().as_str()));
+        assert!(paths.contains(&shard_b.display().to_string().as_str()));
+        // 移動・改名してもハッシュで見つかる
+        let moved = dir.path().join("moved.bin");
+        std::fs::rename(&net, &moved).unwrap();
+        assert!(loaded.find(&sha256_file(&moved).unwrap()).is_some());
+    }
+
+    #[test]
+    fn missing_manifest_is_empty_and_bad_lines_are_reported() {
+        let dir = tempfile::tempdir().unwrap();
+        let manifest = dir.path().join("manifest.jsonl");
+        assert!(Manifest::load(&manifest).unwrap().records.is_empty());
+
+        write(&manifest, b"{\"not\": \"a record\"}\n");
+        let err = Manifest::load(&manifest).unwrap_err();
+        assert!(format!("{err:#}").contains("manifest.jsonl:1"), "{err:#}");
+    }
+}
@@ -0,0 +1,338 @@
+//! 学習済みネットの昇格（比較を通ったネットを配備ディレクトリへ置く）
+//!
+//! `promote_net` が使う。候補のネットが本番ネットとの比較（netcheck・SPRT）を通ったら、
+//! 版番号を付けて配備ディレクトリへコピーし、`current.json` と `CHANGELOG.md` を更新する。
+//!
+//! | ファイル | 内容 |
+//! |---|---|
+//! | `<name>-v0001.bin`, ... | 昇格したネット（版ごとに別ファイル、上書きしない） |
+//! | `current.json` | 現在の本番ネット（[`DeployedNet`]）。次の昇格の比較相手になる |
+//! | `CHANGELOG.md` | 昇格ごとの比較結果（末尾に追記） |
+//!
+//! ネットと `current.json` は一時ファイルに書いてから rename するので、途中で止まっても
+//! 書きかけのファイルが本番ネットになることはない。
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+
+use crate::manifest::sha256_file;
+
+/// 現在の本番ネットを記録するファイル
+pub const CURRENT_FILE: &str = "current.json";
+
+/// 昇格の記録を追記するファイル
+pub const CHANGELOG_FILE: &str = "CHANGELOG.md";
+
+/// 配備したネット
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeployedNet {
+    /// 版番号（1 から）
+    pub version: u32,
+    /// 配備ディレクトリ内のファイル名
+    pub file: String,
+    /// ファイル内容の sha256（16 進小文字）
+    pub sha256: String,
+    /// 昇格元の候補のパス
+    pub source: String,
+    /// 昇格した時刻（RFC 3339）
+    pub promoted_at: String,
+}
+
+/// netcheck（戦術テストスイートの正解数の比較）の結果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NetcheckSummary {
+    /// スイート名（ファイルパスまたは `builtin:<tag>`）
+    pub suite: String,
+    /// 局面数
+    pub total: usize,
+    /// 本番ネットの正解数
+    pub production_solved: usize,
+    /// 候補のネットの正解数
+    pub candidate_solved: usize,
+}
+
+impl NetcheckSummary {
+    /// 候補の正解数が本番ネットから `tolerance` 以上減っていないか
+    pub fn passed(&self, tolerance: usize) -> bool {
+        self.candidate_solved + tolerance >= self.production_solved
+    }
+}
+
+/// 正規化 Elo の推定値
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct NeloEstimate {
+    pub value: f64,
+    /// 95% 信頼区間の半幅
+    pub ci95: f64,
+}
+
+/// SPRT の判定（`analyze_selfplay --json --sprt` の `sprt` の必要な項目）
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct SprtSummary {
+    /// `accept_h1` / `accept_h0` / `running`
+    pub decision: String,
+    /// 集計したペア数
+    pub pairs: u64,
+    pub llr: f64,
+    /// LLR の下側の境界
+    pub lower: f64,
+    /// LLR の上側の境界
+    pub upper: f64,
+    pub nelo: Option<NeloEstimate>,
+}
+
+impl SprtSummary {
+    /// `analyze_selfplay --json --sprt` の出力から読み取る
+    pub fn from_analyze_json(json: &str) -> Result<Self> {
+        #[derive(Deserialize)]
+        struct AnalyzeOutput {
+            sprt: Option<SprtSummary>,
+        }
+        let output: AnalyzeOutput =
+            serde_json::from_str(json).context("invalid analyze_selfplay JSON output")?;
+        output.sprt.context("analyze_selfplay output has no SPRT result")
+    }
+
+    /// 候補が本番ネットより強いと判定されたか（H1 を採択）
+    pub fn passed(&self) -> bool {
+        self.decision == "accept_h1"
+    }
+}
+
+/// 昇格の根拠（CHANGELOG に書く）
+#[derive(Debug, Clone, PartialEq)]
+pub struct GateSummary {
+    /// 比較相手の本番ネットのパス
+    pub production: String,
+    /// 比較相手の本番ネットの sha256
+    pub production_sha256: String,
+    pub netcheck: NetcheckSummary,
+    pub sprt: SprtSummary,
+}
+
+/// 配備ディレクトリ
+#[derive(Debug, Clone)]
+pub struct DeployDir {
+    root: PathBuf,
+}
+
+impl DeployDir {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// 現在の本番ネット（まだ昇格したことがなければ `None`）
+    pub fn current(&self) -> Result<Option<DeployedNet>> {
+        let path = self.root.join(CURRENT_FILE);
+        let json = match fs::read_to_string(&path) {
+            Ok(json) => json,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        };
+        serde_json::from_str(&json)
+            .with_context(|| format!("{}: invalid deployed net record", path.display()))
+            .map(Some)
+    }
+
+    /// 配備したネットのパス
+    pub fn path_of(&self, net: &DeployedNet) -> PathBuf {
+        self.root.join(&net.file)
+    }
+
+    /// `candidate` を次の版として配備し、本番ネットにする
+    pub fn promote(
+        &self,
+        candidate: &Path,
+        name: &str,
+        gates: &GateSummary,
+        note: Option<&str>,
+    ) -> Result<DeployedNet> {
+        let version = self.current()?.map_or(1, |current| current.version + 1);
+        let file = format!("{name}-v{version:04}.bin");
+        let dest = self.root.join(&file);
+        if dest.exists() {
+            bail!("{} already exists", dest.display());
+        }
+        fs::create_dir_all(&self.root)
+            .with_context(|| format!("Failed to create {}", self.root.display()))?;
+
+        let tmp = self.root.join(format!("{file}.tmp"));
+        fs::copy(candidate, &tmp).with_context(|| {
+            format!("Failed to copy {} to {}", candidate.display(), tmp.display())
+        })?;
+        let sha256 = sha256_file(&tmp)?;
+        fs::rename(&tmp, &dest)
+            .with_context(|| format!("Failed to rename {} to {}", tmp.display(), dest.display()))?;
+
+        let net = DeployedNet {
+            version,
+            file,
+            sha256,
+            source: candidate.display().to_string(),
+            promoted_at: chrono::Local::now().to_rfc3339(),
+        };
+        self.write_current(&net)?;
+        self.append_changelog(&changelog_entry(&net, gates, note))?;
+        Ok(net)
+    }
+
+    fn write_current(&self, net: &DeployedNet) -> Result<()> {
+        let path = self.root.join(CURRENT_FILE);
+        let tmp = self.root.join(format!("{CURRENT_FILE}.tmp"));
+        let mut json = serde_json::to_string_pretty(net)?;
+        json.push('\n');
+        fs::write(&tmp, json).with_context(|| format!("Failed to write {}", tmp.display()))?;
+        fs::rename(&tmp, &path)
+            .with_context(|| format!("Failed to rename {} to {}", tmp.display(), path.display()))
+    }
+
+    fn append_changelog(&self, entry: &str) -> Result<()> {
+        let path = self.root.join(CHANGELOG_FILE);
+        let is_new = !path.exists();
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("Failed to open {}", path.display()))?;
+        let mut text = String::new();
+        if is_new {
+            text.push_str("# Net changelog\n");
+        }
+        text.push('\n');
+        text.push_str(entry);
+        file.write_all(text.as_bytes())
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
+
+/// CHANGELOG の 1 版分の記録
+pub fn changelog_entry(net: &DeployedNet, gates: &GateSummary, note: Option<&str>) -> String {
+    let netcheck = &gates.netcheck;
+    let sprt = &gates.sprt;
+    let nelo = sprt
+        .nelo
+        .map_or("n/a".to_string(), |nelo| format!("{:+.1} ± {:.1}", nelo.value, nelo.ci95));
+    let mut entry = format!(
+        "## v{:04} `{}` ({})\n\n\
+         - sha256: `{}`\n\
+         - source: `{}`\n\
+         - baseline: `{}` (sha256 `{}`)\n\
+         - netcheck: {} solved {}/{} (baseline {}/{})\n\
+         - SPRT: {} after {} pairs, LLR {:+.2} in [{:+.2}, {:+.2}], nelo {nelo}\n",
+        net.version,
+        net.file,
+        net.promoted_at,
+        net.sha256,
+        net.source,
+        gates.production,
+        short_sha(&gates.production_sha256),
+        netcheck.suite,
+        netcheck.candidate_solved,
+        netcheck.total,
+        netcheck.production_solved,
+        netcheck.total,
+        sprt.decision,
+        sprt.pairs,
+        sprt.llr,
+        sprt.lower,
+        sprt.upper,
+    );
+    if let Some(note) = note {
+        entry.push_str(&format!("- note: {note}\n"));
+    }
+    entry
+}
+
+/// 表示用に短くした sha256
+pub fn short_sha(sha256: &str) -> &str {
+    &sha256[..sha256.len().min(12)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gates() -> GateSummary {
+        GateSummary {
+            production: "nn-v0001.bin".to_string(),
+            production_sha256: "0123456789abcdef".to_string(),
+            netcheck: NetcheckSummary {
+                suite: "builtin:mate".to_string(),
+                total: 20,
+                production_solved: 17,
+                candidate_solved: 18,
+            },
+            sprt: SprtSummary {
+                decision: "accept_h1".to_string(),
+                pairs: 812,
+                llr: 2.95,
+                lower: -2.94,
+                upper: 2.94,
+                nelo: Some(NeloEstimate {
+                    value: 6.1,
+                    ci95: 3.0,
+                }),
+            },
+        }
+    }
+
+    #[test]
+    fn sprt_summary_reads_analyze_selfplay_json() {
+        let json = r#"{"files":1,"sprt":{"base":"base","test":"test","nelo0":0.0,"nelo1":5.0,
+            "alpha":0.05,"beta":0.05,"pairs":812,"llr":2.95,"lower":-2.94,"upper":2.94,
+            "decision":"accept_h1","nelo":{"value":6.1,"ci95":3.0},"logistic_elo":null,
+            "penta":{"ll":1,"dl":2,"dd":3,"wl":4,"wd":5,"ww":6}}}"#;
+        let sprt = SprtSummary::from_analyze_json(json).unwrap();
+        assert_eq!(sprt, gates().sprt);
+        assert!(sprt.passed());
+
+        let running = json.replace("accept_h1", "running");
+        assert!(!SprtSummary::from_analyze_json(&running).unwrap().passed());
+        assert!(SprtSummary::from_analyze_json(r#"{"files":1}"#).is_err());
+    }
+
+    #[test]
+    fn netcheck_allows_tolerance() {
+        let mut netcheck = gates().netcheck;
+        netcheck.candidate_solved = 15;
+        assert!(!netcheck.passed(1));
+        assert!(netcheck.passed(2));
+    }
+
+    #[test]
+    fn promote_versions_nets_and_records_changelog() {
+        let dir = tempfile::tempdir().unwrap();
+        let deploy = DeployDir::new(dir.path().join("deploy"));
+        assert_eq!(deploy.current().unwrap(), None);
+
+        let candidate = dir.path().join("candidate.bin");
+        fs::write(&candidate, b"net one").unwrap();
+        let first = deploy.promote(&candidate, "nn", &gates(), Some("first net")).unwrap();
+        assert_eq!(first.version, 1);
+        assert_eq!(first.file, "nn-v0001.bin");
+        assert_eq!(first.sha256, sha256_file(&candidate).unwrap());
+
+        fs::write(&candidate, b"net two").unwrap();
+        let second = deploy.promote(&candidate, "nn", &gates(), None).unwrap();
+        assert_eq!(second.file, "nn-v0002.bin");
+        assert_eq!(deploy.current().unwrap(), Some(second.clone()));
+        assert_eq!(fs::read(deploy.path_of(&first)).unwrap(), b"net one");
+        assert_eq!(fs::read(deploy.path_of(&second)).unwrap(), b"net two");
+
+        let changelog = fs::read_to_string(deploy.root().join(CHANGELOG_FILE)).unwrap();
+        assert!(changelog.starts_with("# Net changelog\n\n## v0001 `nn-v0001.bin`"));
+        assert!(changelog.contains("- note: first net\n"));
+        assert!(changelog.contains("## v0002 `nn-v0002.bin`"));
+        assert!(changelog.contains("netcheck: builtin:mate solved 18/20 (baseline 17/20)"));
+        assert!(changelog.contains("accept_h1 after 812 pairs, LLR +2.95 in [-2.94, +2.94]"));
+        assert!(!deploy.root().join(format!("{CURRENT_FILE}.tmp")).exists());
+    }
+}
@@ -6,6 +6,8 @@ use std::path::Path;
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 
+use rshogi_core::tt::TtStats;
+
 use crate::config::EvalConfig;
 use crate::system::SystemInfo;
 use crate::utils::format_number;
@@ -64,6 +66,86 @@ pub struct BenchResult {
     /// Search再利用モードでの探索実行インデックス（0=初回、1=2回目...）
     #[serde(skip_serializing_if = "Option::is_none")]
     pub search_run_index: Option<u32>,
+    /// 各深さに到達した時刻（ミリ秒、`[d - 1]` が depth d の完了時刻）
+    ///
+    /// 枝刈りの変更は NPS を変えずに探索木の大きさを変えるため、NPS だけでは
+    /// 比較できない。time-to-depth で同じ深さに届くまでの時間を比較する。
+    /// 旧形式の JSON には存在しないため省略可能。
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub time_to_depth_ms: Vec<u64>,
+    /// 探索後の置換表の統計（内部APIモードのみ）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tt: Option<TtReport>,
+}
+
+/// 探索後の置換表の統計（`Search::tt_stats` から作る）
+///
+/// ヒット率・置換率は tools を `--features tt-stats` でビルドしたときだけ記録される。
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TtReport {
+    /// 使用率（パーミル、世代を問わない）
+    pub occupancy_permille: u32,
+    /// 今の探索で書かれたエントリの割合（パーミル）
+    pub current_permille: u32,
+    /// 使用中のエントリの平均の深さ（`depth8` 単位）
+    pub average_depth8: f64,
+    /// 使用中のエントリのうち BOUND_EXACT の割合
+    pub exact_ratio: f64,
+    /// probe のヒット率
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hit_rate: Option<f64>,
+    /// 書き込みのうち別の局面を追い出した割合
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replacement_rate: Option<f64>,
+    /// probe の回数
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub probes: Option<u64>,
+    /// 書き込みの回数（深いエントリを据え置いて指し手だけ更新したものを含む）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub writes: Option<u64>,
+}
+
+impl From<&TtStats> for TtReport {
+    fn from(stats: &TtStats) -> Self {
+        let exact_ratio = if stats.occupied == 0 {
+            0.0
+        } else {
+            stats.exact as f64 / stats.occupied as f64
+        };
+        Self {
+            occupancy_permille: stats.occupancy_permille(),
+            current_permille: stats.current_permille(),
+            average_depth8: stats.average_depth8(),
+            exact_ratio,
+            hit_rate: stats.counters.map(|c| c.hit_rate()),
+            replacement_rate: stats.counters.map(|c| c.replacement_rate()),
+            probes: stats.counters.map(|c| c.probes),
+            writes: stats.counters.map(|c| c.writes()),
+        }
+    }
+}
+
+impl BenchResult {
+    /// depth に到達するまでの時間（ミリ秒）。未到達なら `None`
+    pub fn time_to_depth(&self, depth: i32) -> Option<u64> {
+        if depth <= 0 {
+            return None;
+        }
+        self.time_to_depth_ms.get(depth as usize - 1).copied()
+    }
+}
+
+/// info 行の depth と経過時間から time-to-depth を記録する
+///
+/// 各深さの最初の報告時刻だけを残す（MultiPV の 2 行目以降や同一深さの再報告は無視）。
+/// 深さが飛んだ場合は、飛ばされた深さも同じ時刻に到達したものとして埋める。
+pub fn record_time_to_depth(time_to_depth_ms: &mut Vec<u64>, depth: i32, elapsed_ms: u64) {
+    if depth <= 0 {
+        return;
+    }
+    while time_to_depth_ms.len() < depth as usize {
+        time_to_depth_ms.push(elapsed_ms);
+    }
 }
 
 /// スレッド数別の結果
@@ -92,6 +174,12 @@ pub struct Aggregate {
     pub average_depth: f64,
     /// 平均置換表使用率
     pub average_hashfull: f64,
+    /// time-to-depth の比較に使う深さ（全局面が到達した最大深さ、記録が無ければ 0）
+    #[serde(default)]
+    pub ttd_depth: i32,
+    /// 全局面の `ttd_depth` 到達時間の合計（ミリ秒）
+    #[serde(default)]
+    pub total_time_to_depth_ms: u64,
 }
 
 impl ThreadResult {
@@ -104,6 +192,8 @@ impl ThreadResult {
                 average_nps: 0,
                 average_depth: 0.0,
                 average_hashfull: 0.0,
+                ttd_depth: 0,
+                total_time_to_depth_ms: 0,
             };
         }
 
@@ -119,13 +209,29 @@ impl ThreadResult {
         let average_depth = self.results.iter().map(|r| r.depth as f64).sum::<f64>() / count;
         let average_hashfull = self.results.iter().map(|r| r.hashfull as f64).sum::<f64>() / count;
 
+        let ttd_depth =
+            self.results.iter().map(|r| r.time_to_depth_ms.len()).min().unwrap_or(0) as i32;
+        let total_time_to_depth_ms = self.total_time_to_depth(ttd_depth).unwrap_or(0);
+
         Aggregate {
             total_nodes,
             total_time_ms,
             average_nps,
             average_depth,
             average_hashfull,
+            ttd_depth,
+            total_time_to_depth_ms,
+        }
+    }
+
+    /// 全局面が depth に到達するまでの時間の合計（ミリ秒）
+    ///
+    /// 1 局面でも未到達なら `None`。2 つのレポートを同じ深さで比較する用途を想定する。
+    pub fn total_time_to_depth(&self, depth: i32) -> Option<u64> {
+        if self.results.is_empty() {
+            return None;
         }
+        self.results.iter().map(|r| r.time_to_depth(depth)).sum()
     }
 }
 
@@ -173,43 +279,47 @@ impl BenchmarkReport {
 
         if show_efficiency {
             println!(
-                "{:<10} {:<15} {:<15} {:<15} {:<10}",
-                "Threads", "Total Nodes", "Total Time", "Avg NPS", "Efficiency"
+                "{:<10} {:<15} {:<15} {:<15} {:<15} {:<10}",
+                "Threads", "Total Nodes", "Total Time", "Avg NPS", "TTD", "Efficiency"
             );
-            println!("{}", "-".repeat(70));
+            println!("{}", "-".repeat(86));
         } else {
             println!(
-                "{:<10} {:<15} {:<15} {:<15}",
-                "Threads", "Total Nodes", "Total Time", "Avg NPS"
+                "{:<10} {:<15} {:<15} {:<15} {:<15}",
+                "Threads", "Total Nodes", "Total Time", "Avg NPS", "TTD"
             );
-            println!("{}", "-".repeat(55));
+            println!("{}", "-".repeat(71));
         }
 
         for thread_result in &self.results {
             let agg = thread_result.aggregate();
             let efficiency =
                 calculate_efficiency(baseline_nps, agg.average_nps, thread_result.threads);
+            let ttd = format_ttd(&agg);
 
             if show_efficiency {
                 println!(
-                    "{:<10} {:<15} {:<15} {:<15} {:<9.1}%",
+                    "{:<10} {:<15} {:<15} {:<15} {:<15} {:<9.1}%",
                     thread_result.threads,
                     format_number(agg.total_nodes),
                     format!("{}ms", agg.total_time_ms),
                     format_number(agg.average_nps),
+                    ttd,
                     efficiency,
                 );
             } else {
                 println!(
-                    "{:<10} {:<15} {:<15} {:<15}",
+                    "{:<10} {:<15} {:<15} {:<15} {:<15}",
                     thread_result.threads,
                     format_number(agg.total_nodes),
                     format!("{}ms", agg.total_time_ms),
                     format_number(agg.average_nps),
+                    ttd,
                 );
             }
         }
 
+        println!("TTD: 全局面が到達した最大深さ d までの合計時間（d@ms）");
         println!();
     }
 
@@ -230,7 +340,33 @@ impl BenchmarkReport {
                 println!("    Time: {}ms", result.time_ms);
                 println!("    NPS: {}", format_number(result.nps));
                 println!("    Hashfull: {}", result.hashfull);
+                if let Some(tt) = &result.tt {
+                    let mut line = format!(
+                        "    TT: occupancy {}‰, current {}‰, avg depth8 {:.1}, exact {:.1}%",
+                        tt.occupancy_permille,
+                        tt.current_permille,
+                        tt.average_depth8,
+                        tt.exact_ratio * 100.0
+                    );
+                    if let (Some(hit), Some(replaced)) = (tt.hit_rate, tt.replacement_rate) {
+                        line.push_str(&format!(
+                            ", hit {:.1}%, replaced {:.1}%",
+                            hit * 100.0,
+                            replaced * 100.0
+                        ));
+                    }
+                    println!("{line}");
+                }
                 println!("    Bestmove: {}", result.bestmove);
+                if !result.time_to_depth_ms.is_empty() {
+                    let ttd: Vec<String> = result
+                        .time_to_depth_ms
+                        .iter()
+                        .enumerate()
+                        .map(|(i, ms)| format!("{}:{ms}", i + 1))
+                        .collect();
+                    println!("    Time to depth (depth:ms): {}", ttd.join(" "));
+                }
             }
             println!();
         }
@@ -406,6 +542,218 @@ impl BenchmarkReport {
     }
 }
 
+/// ベースラインとの比較結果（スレッド数ごと）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThreadComparison {
+    /// スレッド数
+    pub threads: usize,
+    /// ベースラインの平均NPS
+    pub baseline_nps: u64,
+    /// 今回の平均NPS
+    pub current_nps: u64,
+    /// NPS の変化率（%）
+    pub nps_delta_percent: f64,
+    /// TTD の比較に使う深さ（両レポートの全局面が到達した最大深さ、記録が無ければ 0）
+    pub ttd_depth: i32,
+    /// ベースラインの `ttd_depth` 到達時間の合計（ミリ秒）
+    pub baseline_ttd_ms: Option<u64>,
+    /// 今回の `ttd_depth` 到達時間の合計（ミリ秒）
+    pub current_ttd_ms: Option<u64>,
+    /// TTD の変化率（%、負の値が高速化）
+    pub ttd_delta_percent: Option<f64>,
+}
+
+/// ベースラインとの局面ごとの比較結果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PositionComparison {
+    /// スレッド数
+    pub threads: usize,
+    /// 局面の SFEN 文字列
+    pub sfen: String,
+    /// ベースラインの NPS
+    pub baseline_nps: u64,
+    /// 今回の NPS
+    pub current_nps: u64,
+    /// NPS の変化率（%、ベースラインが 0 なら 0）
+    pub nps_delta_percent: f64,
+    /// ベースラインのノード数
+    pub baseline_nodes: u64,
+    /// 今回のノード数
+    pub current_nodes: u64,
+    /// ノード数の変化率（%、ベースラインが 0 なら 0）
+    pub nodes_delta_percent: f64,
+    /// ベースラインの到達深さ
+    pub baseline_depth: i32,
+    /// 今回の到達深さ
+    pub current_depth: i32,
+}
+
+impl BenchmarkReport {
+    /// JSON形式のレポートを読み込む
+    pub fn load_json(path: &Path) -> Result<Self> {
+        let file = File::open(path)
+            .with_context(|| format!("Failed to open JSON file: {}", path.display()))?;
+        serde_json::from_reader(std::io::BufReader::new(file))
+            .with_context(|| format!("Failed to parse JSON: {}", path.display()))
+    }
+
+    /// ベースラインのレポートと比較する
+    ///
+    /// 同じスレッド数の結果同士を比較し、片方にしか無いスレッド数は除外する。
+    /// TTD は両レポートで共通に到達した深さで比較する。
+    pub fn compare(&self, baseline: &BenchmarkReport) -> Vec<ThreadComparison> {
+        self.results
+            .iter()
+            .filter_map(|current| {
+                let base = baseline.results.iter().find(|b| b.threads == current.threads)?;
+                let current_agg = current.aggregate();
+                let base_agg = base.aggregate();
+
+                let ttd_depth = current_agg.ttd_depth.min(base_agg.ttd_depth);
+                let (baseline_ttd_ms, current_ttd_ms) = if ttd_depth > 0 {
+                    (base.total_time_to_depth(ttd_depth), current.total_time_to_depth(ttd_depth))
+                } else {
+                    (None, None)
+                };
+                let ttd_delta_percent = match (baseline_ttd_ms, current_ttd_ms) {
+                    (Some(b), Some(c)) if b > 0 => Some(delta_percent(b, c)),
+                    _ => None,
+                };
+
+                Some(ThreadComparison {
+                    threads: current.threads,
+                    baseline_nps: base_agg.average_nps,
+                    current_nps: current_agg.average_nps,
+                    nps_delta_percent: if base_agg.average_nps > 0 {
+                        delta_percent(base_agg.average_nps, current_agg.average_nps)
+                    } else {
+                        0.0
+                    },
+                    ttd_depth,
+                    baseline_ttd_ms,
+                    current_ttd_ms,
+                    ttd_delta_percent,
+                })
+            })
+            .collect()
+    }
+
+    /// ベースラインのレポートと局面ごとに比較する
+    ///
+    /// 同じスレッド数の結果同士を、ウォームアップを除いて先頭から順に突き合わせる。
+    /// SFEN が食い違う組（局面ファイルが変わった場合など）は除外する。
+    pub fn compare_positions(&self, baseline: &BenchmarkReport) -> Vec<PositionComparison> {
+        let measured = |r: &&BenchResult| r.is_warmup != Some(true);
+        let mut comparisons = Vec::new();
+        for current in &self.results {
+            let Some(base) = baseline.results.iter().find(|b| b.threads == current.threads) else {
+                continue;
+            };
+            let pairs = current
+                .results
+                .iter()
+                .filter(measured)
+                .zip(base.results.iter().filter(measured))
+                .filter(|(c, b)| c.sfen == b.sfen);
+            for (c, b) in pairs {
+                comparisons.push(PositionComparison {
+                    threads: current.threads,
+                    sfen: c.sfen.clone(),
+                    baseline_nps: b.nps,
+                    current_nps: c.nps,
+                    nps_delta_percent: if b.nps > 0 {
+                        delta_percent(b.nps, c.nps)
+                    } else {
+                        0.0
+                    },
+                    baseline_nodes: b.nodes,
+                    current_nodes: c.nodes,
+                    nodes_delta_percent: if b.nodes > 0 {
+                        delta_percent(b.nodes, c.nodes)
+                    } else {
+                        0.0
+                    },
+                    baseline_depth: b.depth,
+                    current_depth: c.depth,
+                });
+            }
+        }
+        comparisons
+    }
+
+    /// ベースラインとの比較を出力
+    ///
+    /// スレッド数ごとの平均 NPS / TTD の表に続けて、局面ごとの NPS・ノード数・深さの表を出す。
+    pub fn print_comparison(&self, baseline: &BenchmarkReport) {
+        println!("\n=== Comparison with Baseline ===");
+        println!(
+            "{:<10} {:<12} {:<12} {:<10} {:<12} {:<12} {:<10}",
+            "Threads", "Base NPS", "NPS", "NPS Δ", "Base TTD", "TTD", "TTD Δ"
+        );
+        println!("{}", "-".repeat(84));
+
+        for cmp in self.compare(baseline) {
+            let (base_ttd, ttd, ttd_delta) =
+                match (cmp.baseline_ttd_ms, cmp.current_ttd_ms, cmp.ttd_delta_percent) {
+                    (Some(b), Some(c), Some(d)) => (
+                        format!("{}@{b}ms", cmp.ttd_depth),
+                        format!("{}@{c}ms", cmp.ttd_depth),
+                        format!("{d:+.1}%"),
+                    ),
+                    _ => ("-".to_string(), "-".to_string(), "-".to_string()),
+                };
+            println!(
+                "{:<10} {:<12} {:<12} {:<10} {:<12} {:<12} {:<10}",
+                cmp.threads,
+                format_number(cmp.baseline_nps),
+                format_number(cmp.current_nps),
+                format!("{:+.1}%", cmp.nps_delta_percent),
+                base_ttd,
+                ttd,
+                ttd_delta,
+            );
+        }
+
+        let positions = self.compare_positions(baseline);
+        if !positions.is_empty() {
+            println!("\nPosition-by-position comparison:");
+            println!(
+                "  {:<8} {:<20} | {:<12} | {:<8} | {:<14} | {:<8} | Depth",
+                "Threads", "Position", "NPS", "NPS Δ", "Nodes", "Nodes Δ"
+            );
+            println!("  {}", "-".repeat(92));
+            for pos in &positions {
+                println!(
+                    "  {:<8} {:<20} | {:<12} | {:<8} | {:<14} | {:<8} | {} -> {}",
+                    pos.threads,
+                    truncate_sfen(&pos.sfen),
+                    format_number(pos.current_nps),
+                    format!("{:+.1}%", pos.nps_delta_percent),
+                    format_number(pos.current_nodes),
+                    format!("{:+.1}%", pos.nodes_delta_percent),
+                    pos.baseline_depth,
+                    pos.current_depth,
+                );
+            }
+        }
+        println!();
+    }
+}
+
+/// ベースラインからの変化率（%）
+fn delta_percent(baseline: u64, current: u64) -> f64 {
+    ((current as f64 - baseline as f64) / baseline as f64) * 100.0
+}
+
+/// サマリー表示用の TTD 文字列（`d@ms`、記録が無ければ `-`）
+fn format_ttd(agg: &Aggregate) -> String {
+    if agg.ttd_depth > 0 {
+        format!("{}@{}ms", agg.ttd_depth, agg.total_time_to_depth_ms)
+    } else {
+        "-".to_string()
+    }
+}
+
 /// SFENを短く表示用にトランケート
 fn truncate_sfen(sfen: &str) -> String {
     if sfen.len() <= 20 {
@@ -430,6 +778,142 @@ mod tests {
         assert_eq!(agg.average_nps, 0);
     }
 
+    fn bench_result(time_to_depth_ms: Vec<u64>) -> BenchResult {
+        BenchResult {
+            sfen: "startpos".to_string(),
+            depth: time_to_depth_ms.len() as i32,
+            nodes: 1000,
+            time_ms: time_to_depth_ms.last().copied().unwrap_or(0),
+            nps: 0,
+            hashfull: 0,
+            bestmove: "7g7f".to_string(),
+            is_warmup: None,
+            search_run_index: None,
+            time_to_depth_ms,
+            tt: None,
+        }
+    }
+
+    #[test]
+    fn test_record_time_to_depth_keeps_first_report_and_fills_gaps() {
+        let mut ttd = Vec::new();
+        record_time_to_depth(&mut ttd, 1, 3);
+        record_time_to_depth(&mut ttd, 1, 4); // MultiPV 2 行目
+        record_time_to_depth(&mut ttd, 3, 10); // depth 2 は飛ばされた
+        record_time_to_depth(&mut ttd, 0, 11);
+        assert_eq!(ttd, vec![3, 10, 10]);
+    }
+
+    #[test]
+    fn test_aggregate_time_to_depth_uses_common_depth() {
+        let thread_result = ThreadResult {
+            threads: 1,
+            results: vec![
+                bench_result(vec![1, 5, 20, 80]),
+                bench_result(vec![2, 7, 30]),
+            ],
+        };
+        let agg = thread_result.aggregate();
+        assert_eq!(agg.ttd_depth, 3);
+        assert_eq!(agg.total_time_to_depth_ms, 50);
+        assert_eq!(thread_result.total_time_to_depth(2), Some(12));
+        assert_eq!(thread_result.total_time_to_depth(4), None);
+    }
+
+    #[test]
+    fn test_bench_result_without_time_to_depth_deserializes() {
+        let json = r#"{"sfen":"startpos","depth":10,"nodes":1,"time_ms":1,"nps":1,
+            "hashfull":0,"bestmove":"7g7f"}"#;
+        let result: BenchResult = serde_json::from_str(json).unwrap();
+        assert!(result.time_to_depth_ms.is_empty());
+        assert_eq!(result.time_to_depth(1), None);
+        assert!(result.tt.is_none());
+    }
+
+    #[test]
+    fn test_tt_report_from_stats() {
+        let stats = TtStats {
+            sampled_entries: 1000,
+            occupied: 400,
+            by_age: [300, 100, 0, 0, 0, 0, 0, 0],
+            exact: 100,
+            depth8_sum: 2000,
+            ..Default::default()
+        };
+        let report = TtReport::from(&stats);
+        assert_eq!(report.occupancy_permille, 400);
+        assert_eq!(report.current_permille, 300);
+        assert_eq!(report.average_depth8, 5.0);
+        assert_eq!(report.exact_ratio, 0.25);
+        assert_eq!(report.hit_rate, None);
+
+        // 統計なしのビルドではヒット率・置換率を JSON に出さない
+        let json = serde_json::to_string(&report).unwrap();
+        assert!(!json.contains("hit_rate"), "{json}");
+    }
+
+    #[test]
+    fn test_compare_uses_common_ttd_depth() {
+        let report = |results: Vec<BenchResult>| BenchmarkReport {
+            system_info: crate::system::collect_system_info(),
+            engine_name: None,
+            engine_path: None,
+            eval_info: None,
+            results: vec![ThreadResult {
+                threads: 1,
+                results,
+            }],
+        };
+        let baseline = report(vec![bench_result(vec![1, 10, 40, 100])]);
+        let current = report(vec![bench_result(vec![1, 8, 30])]);
+
+        let cmp = current.compare(&baseline);
+        assert_eq!(cmp.len(), 1);
+        assert_eq!(cmp[0].ttd_depth, 3);
+        assert_eq!(cmp[0].baseline_ttd_ms, Some(40));
+        assert_eq!(cmp[0].current_ttd_ms, Some(30));
+        assert_eq!(cmp[0].ttd_delta_percent, Some(-25.0));
+    }
+
+    #[test]
+    fn test_compare_positions_skips_warmup_and_mismatched_sfen() {
+        let result =
+            |sfen: &str, nodes: u64, nps: u64, depth: i32, is_warmup: Option<bool>| BenchResult {
+                sfen: sfen.to_string(),
+                depth,
+                nodes,
+                nps,
+                is_warmup,
+                ..bench_result(vec![])
+            };
+        let report = |results: Vec<BenchResult>| BenchmarkReport {
+            system_info: crate::system::collect_system_info(),
+            engine_name: None,
+            engine_path: None,
+            eval_info: None,
+            results: vec![ThreadResult {
+                threads: 1,
+                results,
+            }],
+        };
+        let baseline = report(vec![
+            result("a", 1000, 200, 10, None),
+            result("b", 2000, 400, 12, None),
+        ]);
+        let current = report(vec![
+            result("a", 9, 9, 1, Some(true)),
+            result("a", 1100, 150, 11, None),
+            result("c", 2000, 400, 12, None),
+        ]);
+
+        let cmp = current.compare_positions(&baseline);
+        assert_eq!(cmp.len(), 1);
+        assert_eq!(cmp[0].sfen, "a");
+        assert_eq!(cmp[0].nps_delta_percent, -25.0);
+        assert_eq!(cmp[0].nodes_delta_percent, 10.0);
+        assert_eq!((cmp[0].baseline_depth, cmp[0].current_depth), (10, 11));
+    }
+
     #[test]
     fn test_calculate_efficiency() {
         // 理想的なスケーリング（効率100%）
@@ -14,7 +14,9 @@ use rshogi_core::search::{LimitsType, Search, SearchInfo};
 
 use crate::config::{BenchmarkConfig, LimitType};
 use crate::positions::load_positions;
-use crate::report::{BenchResult, BenchmarkReport, EvalInfo, ThreadResult};
+use crate::report::{
+    BenchResult, BenchmarkReport, EvalInfo, ThreadResult, TtReport, record_time_to_depth,
+};
 use crate::system::collect_system_info;
 use crate::utils::SEARCH_STACK_SIZE;
 
@@ -127,10 +129,16 @@ fn run_internal_benchmark_standard(config: &BenchmarkConfig) -> Result<Benchmark
                         search.resize_eval_hash(eval_hash_mb as usize);
 
                         let mut last_info: Option<SearchInfo> = None;
+                        let mut time_to_depth_ms = Vec::new();
                         let result = search.go(
                             &mut pos,
                             limits,
                             Some(|info: &SearchInfo| {
+                                record_time_to_depth(
+                                    &mut time_to_depth_ms,
+                                    info.depth,
+                                    info.time_ms,
+                                );
                                 last_info = Some(info.clone());
                                 if verbose {
                                     println!("    {}", info.to_usi_string());
@@ -148,6 +156,8 @@ fn run_internal_benchmark_standard(config: &BenchmarkConfig) -> Result<Benchmark
                             bestmove: result.best_move.to_usi(),
                             is_warmup: None,
                             search_run_index: None,
+                            time_to_depth_ms,
+                            tt: Some(TtReport::from(&search.tt_stats())),
                         }
                     })
                     .with_context(|| "Failed to spawn search thread")?
@@ -340,6 +350,8 @@ fn run_single_search(
             bestmove: "none".to_string(),
             is_warmup: Some(is_warmup),
             search_run_index: Some(search_run_index),
+            time_to_depth_ms: Vec::new(),
+            tt: None,
         };
     }
 
@@ -352,10 +364,12 @@ fn run_single_search(
     }
 
     let mut last_info: Option<SearchInfo> = None;
+    let mut time_to_depth_ms = Vec::new();
     let result = search.go(
         &mut pos,
         limits,
         Some(|info: &SearchInfo| {
+            record_time_to_depth(&mut time_to_depth_ms, info.depth, info.time_ms);
             last_info = Some(info.clone());
             if verbose {
                 println!("    {}", info.to_usi_string());
@@ -373,6 +387,8 @@ fn run_single_search(
         bestmove: result.best_move.to_usi(),
         is_warmup: Some(is_warmup),
         search_run_index: Some(search_run_index),
+        time_to_depth_ms,
+        tt: Some(TtReport::from(&search.tt_stats())),
     }
 }
 
@@ -11,7 +11,7 @@ use anyhow::{Context, Result};
 
 use crate::config::{BenchmarkConfig, EvalConfig, LimitType};
 use crate::positions::load_positions;
-use crate::report::{BenchResult, BenchmarkReport, EvalInfo, ThreadResult};
+use crate::report::{BenchResult, BenchmarkReport, EvalInfo, ThreadResult, record_time_to_depth};
 use crate::system::collect_system_info;
 
 /// USIエンジンクライアント
@@ -161,6 +161,7 @@ impl UsiEngine {
         self.send(&format!("go {} {limit}", limit_type.to_usi_cmd()))?;
 
         let mut last_info = InfoSnapshot::default();
+        let mut time_to_depth_ms = Vec::new();
         let start = Instant::now();
 
         // 制限タイプに応じた適切なタイムアウトを設定
@@ -185,6 +186,12 @@ impl UsiEngine {
 
             if line.starts_with("info") {
                 last_info.update_from_line(&line);
+                // エンジン報告の time ではなく受信時刻で測る（USI 経由の遅延も含めた実測）
+                record_time_to_depth(
+                    &mut time_to_depth_ms,
+                    last_info.depth,
+                    start.elapsed().as_millis() as u64,
+                );
                 if verbose {
                     println!("    {line}");
                 }
@@ -205,6 +212,8 @@ impl UsiEngine {
                     bestmove,
                     is_warmup: None,
                     search_run_index: None,
+                    time_to_depth_ms,
+                    tt: None,
                 });
             }
         }
@@ -150,7 +150,7 @@ impl SearchBackend for NativeBackend {
         let mut multipv_candidates: Vec<MultiPvCandidate> = Vec::new();
         let collect_multipv = params.multi_pv > 1;
 
-        let mut search_pos = pos.clone();
+        let mut search_pos = pos.clone_with_history();
         let start = Instant::now();
 
         let result = self.engine.go(
@@ -1,4 +1,5 @@
 use anyhow::Result;
+use rshogi_core::adjudication::{Adjudication, AdjudicationConfig, Adjudicator};
 use rshogi_core::movegen::is_legal_with_pass;
 use rshogi_core::types::{Color, Move};
 
@@ -19,6 +20,8 @@ pub struct GameConfig {
     pub go_nodes_black: Option<u64>,
     /// 後手に適用するノード数。Some(n) の場合は `go nodes n` を使用。
     pub go_nodes_white: Option<u64>,
+    /// 評価値による裁定の条件（None の場合は裁定しない）。裁定した対局の reason は `adjudication`。
+    pub adjudication: Option<AdjudicationConfig>,
 }
 
 /// 1手ごとに呼ばれるイベント
@@ -71,6 +74,7 @@ pub fn run_game(
     let mut plies_played = 0u32;
 
     let pass_rights_enabled = config.pass_rights.is_some();
+    let mut adjudicator = config.adjudication.map(Adjudicator::new);
 
     for ply_idx in 0..config.max_moves {
         plies_played = ply_idx + 1;
@@ -160,6 +164,19 @@ pub fn run_game(
                         tc.update_after_move(side, search.elapsed_ms);
                         move_usi = mv_str.clone();
                         raw_move_usi = None;
+                        if let Some(adjudicator) = adjudicator.as_mut() {
+                            let score = eval_log.as_ref().and_then(EvalLog::reported_score);
+                            adjudicator.record(side, score);
+                            if let Some(adjudication) = adjudicator.decide(plies_played) {
+                                outcome = match adjudication {
+                                    Adjudication::Win(Color::Black) => GameOutcome::BlackWin,
+                                    Adjudication::Win(Color::White) => GameOutcome::WhiteWin,
+                                    Adjudication::Draw => GameOutcome::Draw,
+                                };
+                                outcome_reason = "adjudication".to_string();
+                                terminal = true;
+                            }
+                        }
                     }
                     _ => {
                         outcome = if side == Color::Black {
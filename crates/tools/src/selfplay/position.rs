@@ -158,8 +158,8 @@ pub fn build_position(
         pos.enable_pass_rights(black, white);
     }
     for mv_str in &parsed.moves {
-        let mv = Move::from_usi(mv_str)
-            .ok_or_else(|| anyhow!("invalid move in start position: {mv_str}"))?;
+        let mv = Move::parse_usi(mv_str)
+            .map_err(|e| anyhow!("invalid move in start position '{mv_str}': {e}"))?;
         if !is_legal_with_pass(&pos, mv) {
             bail!("illegal move '{mv_str}' in start position");
         }
@@ -1,3 +1,4 @@
+use rshogi_core::adjudication::ReportedScore;
 use rshogi_core::types::Color;
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
@@ -22,6 +23,15 @@ pub struct EvalLog {
     pub pv: Option<Vec<String>>,
 }
 
+impl EvalLog {
+    /// 裁定に使う評価値（指した側から見た値）。詰みのスコアを優先する。
+    pub fn reported_score(&self) -> Option<ReportedScore> {
+        self.score_mate
+            .map(ReportedScore::Mate)
+            .or(self.score_cp.map(ReportedScore::Cp))
+    }
+}
+
 /// USI info 行から抽出した MultiPV 候補
 #[derive(Debug, Clone)]
 pub struct UsiMultiPvCandidate {
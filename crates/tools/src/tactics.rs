@@ -0,0 +1,485 @@
+//! 戦術テストスイート（EPD 風）の回帰テスト
+//!
+//! 期待する最善手（`bm`）・避けるべき手（`am`）付きの局面集を固定時間で探索し、
+//! 正解率と正解到達時間の分布を集計する。Elo 計測とは独立した棋力の smoke test。
+//!
+//! ## スイートの形式
+//!
+//! 1 行 1 局面。SFEN の後に `;` 区切りで操作を並べる（`#` 始まりの行と空行は無視）。
+//!
+//! ```text
+//! <sfen>; bm 7g7f 2g2f; am 8h2b+; id "tsume-001"
+//! ```
+//!
+//! - `bm`: いずれかを指せば正解（複数可）
+//! - `am`: いずれも指さなければ正解（`bm` と併用時は両方を満たす必要がある）
+//! - `id`: 局面名（省略時は `line <行番号>`）
+
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::thread;
+
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+
+use rshogi_core::position::Position;
+use rshogi_core::search::{LimitsType, Search, SearchInfo};
+use rshogi_core::testpos::{self, Tag};
+
+use crate::system::SystemInfo;
+use crate::utils::{SEARCH_STACK_SIZE, format_number};
+
+/// スイートの 1 局面
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TacticsCase {
+    /// 局面名
+    pub id: String,
+    /// 局面（SFEN）
+    pub sfen: String,
+    /// 正解手（USI 形式、いずれかを指せば正解）
+    pub best_moves: Vec<String>,
+    /// 避けるべき手（USI 形式）
+    pub avoid_moves: Vec<String>,
+}
+
+impl TacticsCase {
+    /// 指し手が正解かどうか
+    pub fn is_correct(&self, usi: &str) -> bool {
+        (self.best_moves.is_empty() || self.best_moves.iter().any(|m| m == usi))
+            && !self.avoid_moves.iter().any(|m| m == usi)
+    }
+}
+
+/// スイートの 1 行を解析する。コメント行・空行は `None`
+pub fn parse_suite_line(line: &str, line_no: usize) -> Result<Option<TacticsCase>> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(None);
+    }
+
+    let mut parts = line.split(';');
+    let sfen = parts.next().unwrap_or_default().trim().to_string();
+    let mut case = TacticsCase {
+        id: format!("line {line_no}"),
+        sfen,
+        best_moves: Vec::new(),
+        avoid_moves: Vec::new(),
+    };
+
+    for op in parts.map(str::trim).filter(|op| !op.is_empty()) {
+        let (name, args) = op.split_once(char::is_whitespace).unwrap_or((op, ""));
+        match name {
+            "bm" => case.best_moves.extend(args.split_whitespace().map(str::to_string)),
+            "am" => case.avoid_moves.extend(args.split_whitespace().map(str::to_string)),
+            "id" => case.id = args.trim().trim_matches('"').to_string(),
+            _ => bail!("line {line_no}: unknown operation '{name}'"),
+        }
+    }
+
+    if case.best_moves.is_empty() && case.avoid_moves.is_empty() {
+        bail!("line {line_no}: either 'bm' or 'am' is required");
+    }
+    Ok(Some(case))
+}
+
+/// スイートファイルを読み込む
+pub fn load_suite(path: &Path) -> Result<Vec<TacticsCase>> {
+    let file = File::open(path)
+        .with_context(|| format!("Failed to open suite file: {}", path.display()))?;
+    let mut cases = Vec::new();
+    for (idx, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if let Some(case) = parse_suite_line(&line, idx + 1)? {
+            cases.push(case);
+        }
+    }
+    Ok(cases)
+}
+
+/// 埋め込みの標準局面集（`rshogi_core::testpos`）から `tag` の局面をスイートにする
+///
+/// 期待手・回避手の無い局面（ベンチマーク用、宣言できない入玉局面）は含めない。
+pub fn builtin_suite(tag: Tag) -> Vec<TacticsCase> {
+    testpos::by_tag(tag)
+        .filter_map(|tp| parse_suite_line(&tp.to_epd(), 0).ok().flatten())
+        .collect()
+}
+
+/// 探索中の最善手の推移から、正解に到達した時刻を追跡する
+///
+/// 正解手に変わった時刻を記録し、その後不正解に戻ればリセットする。
+/// 探索終了時点で正解なら、最後に正解へ変わった時刻が time-to-solve になる。
+#[derive(Debug, Default)]
+pub struct SolveTracker {
+    solved_since_ms: Option<u64>,
+}
+
+impl SolveTracker {
+    /// info の最善手を反映する
+    pub fn observe(&mut self, case: &TacticsCase, best_move: &str, time_ms: u64) {
+        if case.is_correct(best_move) {
+            self.solved_since_ms.get_or_insert(time_ms);
+        } else {
+            self.solved_since_ms = None;
+        }
+    }
+
+    /// 正解に到達し続けている時刻（ミリ秒）
+    pub fn solved_since_ms(&self) -> Option<u64> {
+        self.solved_since_ms
+    }
+}
+
+/// 1 局面の結果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TacticsResult {
+    /// 局面名
+    pub id: String,
+    /// 局面（SFEN）
+    pub sfen: String,
+    /// 探索の最善手
+    pub bestmove: String,
+    /// 正解したか
+    pub solved: bool,
+    /// 正解に到達した時刻（ミリ秒、未正解なら `None`）
+    pub time_to_solve_ms: Option<u64>,
+    /// 到達深さ
+    pub depth: i32,
+    /// 探索ノード数
+    pub nodes: u64,
+}
+
+/// 探索条件
+#[derive(Debug, Clone, Copy)]
+pub struct TacticsSearchOptions {
+    /// 1 局面あたりの探索時間（ミリ秒）
+    pub movetime_ms: u64,
+    /// 探索スレッド数
+    pub threads: usize,
+    /// 置換表サイズ（MB）
+    pub tt_mb: usize,
+}
+
+/// 1 局面を固定時間で探索する（局面ごとに新しい Search を作る）
+pub fn run_case(
+    case: &TacticsCase,
+    options: TacticsSearchOptions,
+    verbose: bool,
+) -> Result<TacticsResult> {
+    let mut pos = Position::new();
+    pos.set_sfen(&case.sfen)
+        .with_context(|| format!("{}: invalid SFEN: {}", case.id, case.sfen))?;
+
+    let mut limits = LimitsType::default();
+    limits.set_start_time();
+    limits.movetime = options.movetime_ms as i64;
+
+    let case = case.clone();
+    thread::Builder::new()
+        .stack_size(SEARCH_STACK_SIZE)
+        .spawn(move || {
+            let mut search = Search::new(options.tt_mb);
+            search.set_num_threads(options.threads);
+
+            let mut tracker = SolveTracker::default();
+            let mut last_info: Option<SearchInfo> = None;
+            let result = search.go(
+                &mut pos,
+                limits,
+                Some(|info: &SearchInfo| {
+                    if info.multi_pv <= 1
+                        && let Some(m) = info.pv.first()
+                    {
+                        tracker.observe(&case, &m.to_usi(), info.time_ms);
+                    }
+                    if verbose {
+                        println!("    {}", info.to_usi_string());
+                    }
+                    last_info = Some(info.clone());
+                }),
+            );
+
+            let bestmove = result.best_move.to_usi();
+            let solved = case.is_correct(&bestmove);
+            let time_to_solve_ms = if solved {
+                // info が出る前に終わった場合は探索時間全体を到達時間とみなす
+                Some(
+                    tracker
+                        .solved_since_ms()
+                        .unwrap_or_else(|| last_info.as_ref().map_or(0, |i| i.time_ms)),
+                )
+            } else {
+                None
+            };
+            TacticsResult {
+                id: case.id,
+                sfen: case.sfen,
+                bestmove,
+                solved,
+                time_to_solve_ms,
+                depth: result.depth,
+                nodes: result.nodes,
+            }
+        })
+        .context("Failed to spawn search thread")?
+        .join()
+        .map_err(|_| anyhow::anyhow!("search thread panicked"))
+}
+
+/// スイート全体の結果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TacticsReport {
+    /// システム情報
+    pub system_info: SystemInfo,
+    /// スイートファイル
+    pub suite: String,
+    /// 1 局面あたりの探索時間（ミリ秒）
+    pub movetime_ms: u64,
+    /// 探索スレッド数
+    pub threads: usize,
+    /// 局面ごとの結果
+    pub results: Vec<TacticsResult>,
+}
+
+/// time-to-solve の分布（正解した局面のみ）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SolveTimeStats {
+    pub p50_ms: u64,
+    pub p90_ms: u64,
+    pub max_ms: u64,
+}
+
+impl TacticsReport {
+    /// 正解数
+    pub fn solved_count(&self) -> usize {
+        self.results.iter().filter(|r| r.solved).count()
+    }
+
+    /// 正解率（%）
+    pub fn solve_rate(&self) -> f64 {
+        if self.results.is_empty() {
+            return 0.0;
+        }
+        self.solved_count() as f64 / self.results.len() as f64 * 100.0
+    }
+
+    /// 正解した局面の time-to-solve 分布（正解が無ければ `None`）
+    pub fn solve_time_stats(&self) -> Option<SolveTimeStats> {
+        let mut times: Vec<u64> = self.results.iter().filter_map(|r| r.time_to_solve_ms).collect();
+        if times.is_empty() {
+            return None;
+        }
+        times.sort_unstable();
+        let percentile = |p: usize| times[((times.len() - 1) * p).div_ceil(100)];
+        Some(SolveTimeStats {
+            p50_ms: percentile(50),
+            p90_ms: percentile(90),
+            max_ms: *times.last().unwrap(),
+        })
+    }
+
+    /// サマリーを出力
+    pub fn print_summary(&self) {
+        println!("\n=== Tactics Summary ===");
+        println!("Suite: {}", self.suite);
+        println!("Movetime: {}ms, Threads: {}", self.movetime_ms, self.threads);
+        println!(
+            "Solved: {}/{} ({:.1}%)",
+            self.solved_count(),
+            self.results.len(),
+            self.solve_rate()
+        );
+        if let Some(stats) = self.solve_time_stats() {
+            println!(
+                "Time to solve: p50 {}ms, p90 {}ms, max {}ms",
+                stats.p50_ms, stats.p90_ms, stats.max_ms
+            );
+        }
+
+        let failed: Vec<&TacticsResult> = self.results.iter().filter(|r| !r.solved).collect();
+        if !failed.is_empty() {
+            println!("\nFailed:");
+            for r in failed {
+                println!(
+                    "  {:<24} bestmove {:<8} depth {:<3} nodes {}",
+                    r.id,
+                    r.bestmove,
+                    r.depth,
+                    format_number(r.nodes)
+                );
+            }
+        }
+        println!();
+    }
+
+    /// ベースラインとの比較を出力（正解率の差分と、正解・不正解が入れ替わった局面）
+    pub fn print_comparison(&self, baseline: &TacticsReport) {
+        println!("\n=== Comparison with Baseline ===");
+        println!(
+            "Solved: {} -> {} ({:+.1}%)",
+            baseline.solved_count(),
+            self.solved_count(),
+            self.solve_rate() - baseline.solve_rate()
+        );
+        if let (Some(base), Some(cur)) = (baseline.solve_time_stats(), self.solve_time_stats()) {
+            println!(
+                "Time to solve p50: {}ms -> {}ms, p90: {}ms -> {}ms",
+                base.p50_ms, cur.p50_ms, base.p90_ms, cur.p90_ms
+            );
+        }
+
+        let (gained, lost) = self.solve_changes(baseline);
+        if !gained.is_empty() {
+            println!("Newly solved: {}", gained.join(", "));
+        }
+        if !lost.is_empty() {
+            println!("Newly failed: {}", lost.join(", "));
+        }
+        println!();
+    }
+
+    /// ベースラインから正解・不正解が入れ替わった局面の id（新たに正解, 新たに不正解）
+    ///
+    /// 局面は id で対応付け、片方にしか無い局面は無視する。
+    pub fn solve_changes(&self, baseline: &TacticsReport) -> (Vec<String>, Vec<String>) {
+        let mut gained = Vec::new();
+        let mut lost = Vec::new();
+        for r in &self.results {
+            let Some(base) = baseline.results.iter().find(|b| b.id == r.id) else {
+                continue;
+            };
+            match (base.solved, r.solved) {
+                (false, true) => gained.push(r.id.clone()),
+                (true, false) => lost.push(r.id.clone()),
+                _ => {}
+            }
+        }
+        (gained, lost)
+    }
+
+    /// JSON形式で保存
+    pub fn save_json(&self, path: &Path) -> Result<()> {
+        let file = File::create(path)
+            .with_context(|| format!("Failed to create JSON file: {}", path.display()))?;
+        serde_json::to_writer_pretty(file, self).with_context(|| "Failed to write JSON")?;
+        Ok(())
+    }
+
+    /// JSON形式のレポートを読み込む
+    pub fn load_json(path: &Path) -> Result<Self> {
+        let file = File::open(path)
+            .with_context(|| format!("Failed to open JSON file: {}", path.display()))?;
+        serde_json::from_reader(BufReader::new(file))
+            .with_context(|| format!("Failed to parse JSON: {}", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SFEN: &str = "lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL b - 1";
+
+    #[test]
+    fn test_parse_suite_line() {
+        let line = format!("{SFEN}; bm 7g7f 2g2f; am 5i5h; id \"opening\"");
+        let case = parse_suite_line(&line, 3).unwrap().unwrap();
+        assert_eq!(case.sfen, SFEN);
+        assert_eq!(case.best_moves, vec!["7g7f", "2g2f"]);
+        assert_eq!(case.avoid_moves, vec!["5i5h"]);
+        assert_eq!(case.id, "opening");
+
+        let case = parse_suite_line(&format!("{SFEN}; am 5i5h"), 7).unwrap().unwrap();
+        assert_eq!(case.id, "line 7");
+
+        assert!(parse_suite_line("  # comment", 1).unwrap().is_none());
+        assert!(parse_suite_line("", 1).unwrap().is_none());
+        assert!(parse_suite_line(SFEN, 1).is_err());
+        assert!(parse_suite_line(&format!("{SFEN}; xx 7g7f"), 1).is_err());
+    }
+
+    #[test]
+    fn test_is_correct_with_bm_and_am() {
+        let case = parse_suite_line(&format!("{SFEN}; bm 7g7f 2g2f; am 2g2f"), 1).unwrap().unwrap();
+        assert!(case.is_correct("7g7f"));
+        assert!(!case.is_correct("2g2f"));
+        assert!(!case.is_correct("5i5h"));
+
+        let case = parse_suite_line(&format!("{SFEN}; am 5i5h"), 1).unwrap().unwrap();
+        assert!(case.is_correct("7g7f"));
+        assert!(!case.is_correct("5i5h"));
+    }
+
+    #[test]
+    fn test_builtin_suite_skips_positions_without_expectations() {
+        let mate = builtin_suite(Tag::Mate);
+        assert_eq!(mate.len(), testpos::by_tag(Tag::Mate).count());
+        assert!(mate.iter().all(|case| !case.best_moves.is_empty()));
+
+        assert!(builtin_suite(Tag::Bench).is_empty());
+        let nyugyoku = builtin_suite(Tag::Nyugyoku);
+        assert_eq!(nyugyoku.len(), 1);
+        assert_eq!(nyugyoku[0].best_moves, vec!["win"]);
+    }
+
+    #[test]
+    fn test_solve_tracker_resets_when_best_move_changes() {
+        let case = parse_suite_line(&format!("{SFEN}; bm 7g7f"), 1).unwrap().unwrap();
+        let mut tracker = SolveTracker::default();
+        tracker.observe(&case, "7g7f", 10);
+        tracker.observe(&case, "7g7f", 20);
+        assert_eq!(tracker.solved_since_ms(), Some(10));
+        tracker.observe(&case, "2g2f", 30);
+        assert_eq!(tracker.solved_since_ms(), None);
+        tracker.observe(&case, "7g7f", 40);
+        assert_eq!(tracker.solved_since_ms(), Some(40));
+    }
+
+    fn result(id: &str, time_to_solve_ms: Option<u64>) -> TacticsResult {
+        TacticsResult {
+            id: id.to_string(),
+            sfen: SFEN.to_string(),
+            bestmove: "7g7f".to_string(),
+            solved: time_to_solve_ms.is_some(),
+            time_to_solve_ms,
+            depth: 10,
+            nodes: 1000,
+        }
+    }
+
+    fn report(results: Vec<TacticsResult>) -> TacticsReport {
+        TacticsReport {
+            system_info: crate::system::collect_system_info(),
+            suite: "suite.epd".to_string(),
+            movetime_ms: 1000,
+            threads: 1,
+            results,
+        }
+    }
+
+    #[test]
+    fn test_report_stats_and_comparison() {
+        let current = report(vec![
+            result("a", Some(100)),
+            result("b", Some(300)),
+            result("c", Some(200)),
+            result("d", None),
+        ]);
+        assert_eq!(current.solved_count(), 3);
+        assert_eq!(current.solve_rate(), 75.0);
+        assert_eq!(
+            current.solve_time_stats(),
+            Some(SolveTimeStats {
+                p50_ms: 200,
+                p90_ms: 300,
+                max_ms: 300,
+            })
+        );
+
+        let baseline = report(vec![result("a", None), result("d", Some(50))]);
+        let (gained, lost) = current.solve_changes(&baseline);
+        assert_eq!(gained, vec!["a"]);
+        assert_eq!(lost, vec!["d"]);
+    }
+}
@@ -4,6 +4,8 @@
 //! 集約する。`yardstick_label`（ラベル品質の物差し・JSONL 出力）と `rescore_hcpe`（教師生成・
 //! hcpe 出力）が**同一の評価器構成・同一の fresh-per-position 探索**をこのモジュール経由で使う
 //! ことで、両者のラベルが bit 一致することを構造的に保証する（「測った config = 回す config」）。
+//! `relabel`（JSONL 教師のラベル付け替え）も同じ評価器構成を使い、反復深化なしの静止探索
+//! ラベル（`qsearch_label`）もここに置く。
 //!
 //! 設計上の不変条件:
 //! - 局面ごとに `Search` を作り直し 1 スレッド固定で探索する。これにより 1 局面の評価は
@@ -261,6 +263,39 @@ pub fn label_position(
     }
 }
 
+/// 静止探索ラベル（いずれも手番側視点 cp）。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuietLabel {
+    /// `Search::qsearch` の評価値（駒の取り合いを解決した値）。
+    pub qsearch: i32,
+    /// `Search::static_eval` の評価値（評価関数の生の値）。
+    pub static_eval: i32,
+    /// 王手がかかっているか。
+    pub in_check: bool,
+}
+
+impl QuietLabel {
+    /// 静かな局面か（王手でなく、静止探索で評価値が `margin` cp より動かない）。
+    pub fn is_quiet(&self, margin: i32) -> bool {
+        !self.in_check && (self.qsearch - self.static_eval).abs() <= margin
+    }
+}
+
+/// fresh-per-position の静止探索（反復深化なし）で 1 局面をラベル付けする。
+///
+/// `label_position` と同じく局面ごとに空の `Search` を作るため、処理順・スレッド数に依存しない。
+pub fn qsearch_label(pos: &mut Position, hash_mb: usize) -> QuietLabel {
+    let mut search = Search::new(hash_mb);
+    search.set_num_threads(1);
+    let static_eval = search.static_eval(pos).to_cp();
+    let qsearch = search.qsearch(pos).to_cp();
+    QuietLabel {
+        qsearch,
+        static_eval,
+        in_check: pos.in_check(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -292,6 +327,24 @@ BAD_VALUE,int,xyz,0,8\n";
         assert!(parse_spsa_params_content("# only a comment\n").is_empty());
     }
 
+    #[test]
+    fn quiet_label_requires_no_check_and_small_swing() {
+        let label = QuietLabel {
+            qsearch: 150,
+            static_eval: 80,
+            in_check: false,
+        };
+        assert!(label.is_quiet(100));
+        assert!(!label.is_quiet(50));
+        assert!(
+            !QuietLabel {
+                in_check: true,
+                ..label
+            }
+            .is_quiet(100)
+        );
+    }
+
     #[test]
     fn parse_capture_depths_sorts_dedups_validates() {
         assert_eq!(parse_capture_depths("15,9,12,9").unwrap(), vec![9, 12, 15]);
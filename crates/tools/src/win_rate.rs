@@ -0,0 +1,294 @@
+//! 評価値 → 勝率変換（WDL 変換）のシグモイド係数の推定
+//!
+//! 自己対局などで得た（評価値, 対局結果）の組から、
+//!
+//! ```text
+//! winrate = sigmoid((score - offset) / scale)
+//! ```
+//!
+//! の `scale` / `offset` を最尤推定する。勝ち=1、引き分け=0.5、負け=0 を目標値とした
+//! ロジスティック回帰で、評価値ごとに集計した度数表に対して Newton 法で解く。
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+/// Newton 法の最大反復回数
+const MAX_ITERATIONS: usize = 100;
+
+/// 評価値をこの値で割ってから回帰する（ヘッセ行列の条件数を抑えるため）
+const SCORE_UNIT: f64 = 1000.0;
+
+/// 勝率モデルの係数
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct WinRateModel {
+    pub scale: f64,
+    pub offset: f64,
+}
+
+impl WinRateModel {
+    /// 評価値（手番側から見た値）に対する勝率
+    pub fn win_rate(&self, score: f64) -> f64 {
+        sigmoid((score - self.offset) / self.scale)
+    }
+}
+
+/// ある評価値での勝ち・引き分け・負けの件数
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Outcomes {
+    pub wins: u64,
+    pub draws: u64,
+    pub losses: u64,
+}
+
+impl Outcomes {
+    pub fn total(&self) -> u64 {
+        self.wins + self.draws + self.losses
+    }
+
+    /// 引き分けを 0.5 勝として数えた得点
+    pub fn points(&self) -> f64 {
+        self.wins as f64 + 0.5 * self.draws as f64
+    }
+
+    fn merge(&mut self, other: &Self) {
+        self.wins += other.wins;
+        self.draws += other.draws;
+        self.losses += other.losses;
+    }
+}
+
+/// 評価値ごとの対局結果の度数表
+#[derive(Debug, Clone, Default)]
+pub struct ScoreResultTable {
+    table: BTreeMap<i32, Outcomes>,
+}
+
+impl ScoreResultTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 1 局面を追加する（`game_result` は手番側から見た 1=勝ち, 0=引分, -1=負け）
+    ///
+    /// `game_result` が範囲外なら追加せず `false` を返す。
+    pub fn add(&mut self, score: i32, game_result: i8) -> bool {
+        if !(-1..=1).contains(&game_result) {
+            return false;
+        }
+        let entry = self.table.entry(score).or_default();
+        match game_result {
+            1 => entry.wins += 1,
+            0 => entry.draws += 1,
+            _ => entry.losses += 1,
+        }
+        true
+    }
+
+    pub fn merge(&mut self, other: &Self) {
+        for (&score, outcomes) in &other.table {
+            self.table.entry(score).or_default().merge(outcomes);
+        }
+    }
+
+    /// 局面数
+    pub fn total(&self) -> u64 {
+        self.table.values().map(Outcomes::total).sum()
+    }
+
+    /// 全体の勝ち・引き分け・負けの件数
+    pub fn outcomes(&self) -> Outcomes {
+        let mut sum = Outcomes::default();
+        for outcomes in self.table.values() {
+            sum.merge(outcomes);
+        }
+        sum
+    }
+
+    /// 1 局面あたりの交差エントロピー（小さいほど当てはまりがよい）
+    pub fn log_loss(&self, model: &WinRateModel) -> f64 {
+        let total = self.total();
+        if total == 0 {
+            return 0.0;
+        }
+        let mut loss = 0.0;
+        for (&score, outcomes) in &self.table {
+            let p = model.win_rate(score as f64).clamp(1e-12, 1.0 - 1e-12);
+            let y = outcomes.points();
+            let n = outcomes.total() as f64;
+            loss -= y * p.ln() + (n - y) * (1.0 - p).ln();
+        }
+        loss / total as f64
+    }
+
+    /// `scale` / `offset` を最尤推定する
+    ///
+    /// 局面がない、または評価値が 1 種類しかなく推定できない場合は `None`。
+    pub fn fit(&self) -> Option<WinRateModel> {
+        if self.table.len() < 2 {
+            return None;
+        }
+        // z = a * x + b（x = score / SCORE_UNIT）として解き、最後に scale / offset へ戻す
+        let (mut a, mut b) = (SCORE_UNIT / 600.0, 0.0);
+        for _ in 0..MAX_ITERATIONS {
+            let (mut ga, mut gb) = (0.0, 0.0);
+            let (mut haa, mut hab, mut hbb) = (0.0, 0.0, 0.0);
+            for (&score, outcomes) in &self.table {
+                let x = score as f64 / SCORE_UNIT;
+                let n = outcomes.total() as f64;
+                let p = sigmoid(a * x + b);
+                let r = n * p - outcomes.points();
+                let w = n * p * (1.0 - p);
+                ga += r * x;
+                gb += r;
+                haa += w * x * x;
+                hab += w * x;
+                hbb += w;
+            }
+            let det = haa * hbb - hab * hab;
+            if !det.is_finite() || det.abs() < 1e-12 {
+                return None;
+            }
+            let da = (hbb * ga - hab * gb) / det;
+            let db = (haa * gb - hab * ga) / det;
+            a -= da;
+            b -= db;
+            if da.abs() < 1e-10 && db.abs() < 1e-10 {
+                break;
+            }
+        }
+        if !(a.is_finite() && b.is_finite()) || a <= 0.0 {
+            return None;
+        }
+        let scale = SCORE_UNIT / a;
+        Some(WinRateModel {
+            scale,
+            offset: -b * scale,
+        })
+    }
+
+    /// 評価値を `bin_width` 刻みでまとめた較正表（実測勝率と予測勝率）
+    pub fn calibration(&self, model: &WinRateModel, bin_width: i32) -> Vec<CalibrationBin> {
+        let bin_width = bin_width.max(1);
+        let mut bins: BTreeMap<i32, (Outcomes, f64, f64)> = BTreeMap::new();
+        for (&score, outcomes) in &self.table {
+            let n = outcomes.total() as f64;
+            let (sum, score_sum, predicted_sum) =
+                bins.entry(score.div_euclid(bin_width)).or_default();
+            sum.merge(outcomes);
+            *score_sum += score as f64 * n;
+            *predicted_sum += model.win_rate(score as f64) * n;
+        }
+        bins.into_iter()
+            .filter(|(_, (outcomes, _, _))| outcomes.total() > 0)
+            .map(|(bin, (outcomes, score_sum, predicted_sum))| {
+                let n = outcomes.total() as f64;
+                CalibrationBin {
+                    score_min: bin * bin_width,
+                    score_max: bin * bin_width + bin_width - 1,
+                    mean_score: score_sum / n,
+                    outcomes,
+                    observed: outcomes.points() / n,
+                    predicted: predicted_sum / n,
+                }
+            })
+            .collect()
+    }
+}
+
+/// 較正表の 1 行
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CalibrationBin {
+    pub score_min: i32,
+    pub score_max: i32,
+    pub mean_score: f64,
+    pub outcomes: Outcomes,
+    /// 実測勝率（引き分けは 0.5 勝）
+    pub observed: f64,
+    /// モデルの予測勝率の平均
+    pub predicted: f64,
+}
+
+fn sigmoid(x: f64) -> f64 {
+    1.0 / (1.0 + (-x).exp())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 既知のモデルに従う度数表を作る
+    fn synthetic_table(model: &WinRateModel) -> ScoreResultTable {
+        let mut table = ScoreResultTable::new();
+        for score in (-2000..=2000).step_by(20) {
+            let n = 1000;
+            let wins = (model.win_rate(score as f64) * n as f64).round() as u64;
+            table.table.insert(
+                score,
+                Outcomes {
+                    wins,
+                    draws: 0,
+                    losses: n - wins,
+                },
+            );
+        }
+        table
+    }
+
+    #[test]
+    fn fit_recovers_known_model() {
+        let truth = WinRateModel {
+            scale: 450.0,
+            offset: 60.0,
+        };
+        let table = synthetic_table(&truth);
+        let fitted = table.fit().unwrap();
+        assert!((fitted.scale - truth.scale).abs() < 2.0, "{fitted:?}");
+        assert!((fitted.offset - truth.offset).abs() < 2.0, "{fitted:?}");
+
+        let borrowed = WinRateModel {
+            scale: 600.0,
+            offset: 0.0,
+        };
+        assert!(table.log_loss(&fitted) < table.log_loss(&borrowed));
+    }
+
+    #[test]
+    fn draws_count_as_half_point() {
+        let mut table = ScoreResultTable::new();
+        assert!(table.add(100, 1));
+        assert!(table.add(100, 0));
+        assert!(table.add(-100, -1));
+        assert!(table.add(-100, 0));
+        assert!(!table.add(0, 2));
+        assert_eq!(table.total(), 4);
+        assert_eq!(
+            table.outcomes(),
+            Outcomes {
+                wins: 1,
+                draws: 2,
+                losses: 1,
+            }
+        );
+
+        let model = WinRateModel {
+            scale: 600.0,
+            offset: 0.0,
+        };
+        let bins = table.calibration(&model, 200);
+        assert_eq!(bins.len(), 2);
+        assert_eq!((bins[0].score_min, bins[0].score_max), (-200, -1));
+        assert_eq!(bins[0].observed, 0.25);
+        assert_eq!(bins[1].observed, 0.75);
+        assert!(bins[1].predicted > 0.5);
+    }
+
+    #[test]
+    fn fit_requires_multiple_scores() {
+        let mut table = ScoreResultTable::new();
+        assert_eq!(table.fit(), None);
+        table.add(0, 1);
+        table.add(0, -1);
+        assert_eq!(table.fit(), None);
+    }
+}